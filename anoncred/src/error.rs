@@ -0,0 +1,36 @@
+use std::path::PathBuf;
+use thiserror::Error;
+use utils::envelope::EnvelopeError;
+
+#[derive(Error, Debug)]
+pub enum CliError {
+    #[error("{path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("{path}: {source}")]
+    Envelope {
+        path: PathBuf,
+        source: EnvelopeError,
+    },
+    #[error("Invalid attribute {0:?}, expected a decimal field element")]
+    InvalidAttribute(String),
+    #[error("Expected {expected} attributes, got {found}")]
+    AttributeCount { expected: usize, found: usize },
+    #[error("Missing argument --{0} for this scheme")]
+    MissingArgument(&'static str),
+    #[error("{0}")]
+    Unsupported(&'static str),
+    #[error("Issued signature does not verify against the credential")]
+    InvalidSignature,
+    #[error("Protocol error: {0}")]
+    Protocol(String),
+}
+
+impl CliError {
+    /// wraps a scheme crate error, each crate has its own error enum
+    pub fn protocol(e: impl std::fmt::Display) -> Self {
+        CliError::Protocol(e.to_string())
+    }
+}
@@ -0,0 +1,51 @@
+use crate::error::CliError;
+use ark_bls12_381::Fr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use utils::envelope::Envelope;
+
+pub fn read_artifact<T: Envelope>(path: &Path) -> Result<T, CliError> {
+    let bytes = std::fs::read(path).map_err(|source| CliError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    T::from_envelope(&bytes).map_err(|source| CliError::Envelope {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+pub fn write_artifact<T: Envelope>(path: &Path, artifact: &T) -> Result<(), CliError> {
+    std::fs::write(path, artifact.to_envelope()).map_err(|source| CliError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+pub fn create_dir(path: &Path) -> Result<(), CliError> {
+    std::fs::create_dir_all(path).map_err(|source| CliError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+pub fn required<'a>(path: &'a Option<PathBuf>, name: &'static str) -> Result<&'a Path, CliError> {
+    path.as_deref().ok_or(CliError::MissingArgument(name))
+}
+
+/// parses comma separated decimal attributes, e.g. "1,2,3"
+pub fn parse_attributes(s: &str, expected: usize) -> Result<Vec<Fr>, CliError> {
+    let attributes = s
+        .split(',')
+        .map(str::trim)
+        .filter(|a| !a.is_empty())
+        .map(|a| Fr::from_str(a).map_err(|_| CliError::InvalidAttribute(a.to_string())))
+        .collect::<Result<Vec<_>, _>>()?;
+    if attributes.len() != expected {
+        return Err(CliError::AttributeCount {
+            expected,
+            found: attributes.len(),
+        });
+    }
+    Ok(attributes)
+}
@@ -0,0 +1,240 @@
+//! `anoncred` drives issuer, holder and verifier workflows of the credential schemes in this
+//! workspace from the command line. Every artifact is read and written as an envelope file, see
+//! `utils::envelope`.
+//!
+//! ```text
+//! anoncred setup   --scheme ps --attributes 3 --out pp.bin
+//! anoncred keygen  --scheme ps --pp pp.bin --out-dir issuer
+//! anoncred obtain  --scheme ps --pp pp.bin --pk issuer/pk.bin --attributes 1,2,3 \
+//!                  --request-out request.bin --credential-out credential.bin
+//! anoncred issue   --scheme ps --pp pp.bin --pk issuer/pk.bin --sk issuer/sk.bin \
+//!                  --request request.bin --out response.bin
+//! anoncred unblind --scheme ps --pp pp.bin --pk issuer/pk.bin --credential credential.bin \
+//!                  --response response.bin --out signature.bin
+//! anoncred show    --scheme ps --pp pp.bin --pk issuer/pk.bin --credential credential.bin \
+//!                  --signature signature.bin --verifier shop --nonce n1 --out presentation.bin
+//! anoncred verify  --scheme ps --pp pp.bin --pk issuer/pk.bin \
+//!                  --presentation presentation.bin --verifier shop --nonce n1
+//! ```
+
+mod error;
+mod files;
+mod schemes;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use utils::context::PresentationContext;
+
+#[derive(Parser)]
+#[command(
+    name = "anoncred",
+    about = "Anonymous credential issuance and presentation"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Generate public parameters
+    Setup(SetupArgs),
+    /// Generate issuer keys, for tps also the parameters and one key share per signer
+    Keygen(KeygenArgs),
+    /// Commit to attributes and build an issuance request
+    Obtain(ObtainArgs),
+    /// Check an issuance request and answer it with a (blind or partial) signature
+    Issue(IssueArgs),
+    /// Turn the issuer response into a signature on the credential
+    Unblind(UnblindArgs),
+    /// Create a presentation of a signed credential
+    Show(ShowArgs),
+    /// Verify a presentation, exits with status 1 if it is invalid
+    Verify(VerifyArgs),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Scheme {
+    Ps,
+    PsUtt,
+    Bbs16,
+    BbsOg,
+    Mimc,
+    Tps,
+}
+
+#[derive(Args)]
+pub struct SetupArgs {
+    #[arg(long, value_enum)]
+    pub scheme: Scheme,
+    /// Number of attributes per credential
+    #[arg(long)]
+    pub attributes: usize,
+    #[arg(long)]
+    pub out: PathBuf,
+}
+
+#[derive(Args)]
+pub struct KeygenArgs {
+    #[arg(long, value_enum)]
+    pub scheme: Scheme,
+    /// Public parameters from `setup`, not used by tps
+    #[arg(long)]
+    pub pp: Option<PathBuf>,
+    /// Writes sk.bin and pk.bin, for tps also pp.bin, sk-<i>.bin and pk-<i>.bin per signer
+    #[arg(long)]
+    pub out_dir: PathBuf,
+    /// tps only, number of attributes per credential
+    #[arg(long)]
+    pub attributes: Option<usize>,
+    /// tps only, number of signers needed to issue
+    #[arg(long)]
+    pub threshold: Option<usize>,
+    /// tps only, number of key shares
+    #[arg(long)]
+    pub signers: Option<usize>,
+}
+
+#[derive(Args)]
+pub struct ObtainArgs {
+    #[arg(long, value_enum)]
+    pub scheme: Scheme,
+    #[arg(long)]
+    pub pp: PathBuf,
+    /// Issuer public key, not used by tps
+    #[arg(long)]
+    pub pk: Option<PathBuf>,
+    /// Comma separated decimal attribute values
+    #[arg(long)]
+    pub attributes: String,
+    #[arg(long)]
+    pub request_out: PathBuf,
+    /// Holder state needed by `unblind` and `show`, keep it private
+    #[arg(long)]
+    pub credential_out: PathBuf,
+}
+
+#[derive(Args)]
+pub struct IssueArgs {
+    #[arg(long, value_enum)]
+    pub scheme: Scheme,
+    #[arg(long)]
+    pub pp: PathBuf,
+    /// Issuer public key, for tps the signer's pk-<i>.bin
+    #[arg(long)]
+    pub pk: PathBuf,
+    /// Issuer secret key, for tps the signer's sk-<i>.bin
+    #[arg(long)]
+    pub sk: PathBuf,
+    #[arg(long)]
+    pub request: PathBuf,
+    #[arg(long)]
+    pub out: PathBuf,
+}
+
+#[derive(Args)]
+pub struct UnblindArgs {
+    #[arg(long, value_enum)]
+    pub scheme: Scheme,
+    #[arg(long)]
+    pub pp: PathBuf,
+    #[arg(long)]
+    pub pk: Option<PathBuf>,
+    #[arg(long)]
+    pub credential: PathBuf,
+    /// Issuer response, for tps repeat once per signer
+    #[arg(long, required = true)]
+    pub response: Vec<PathBuf>,
+    /// tps only, the issuance request the shares answer
+    #[arg(long)]
+    pub request: Option<PathBuf>,
+    /// tps only, verification key shares of the responding signers
+    #[arg(long)]
+    pub pk_share: Vec<PathBuf>,
+    #[arg(long)]
+    pub out: PathBuf,
+}
+
+#[derive(Args)]
+pub struct ShowArgs {
+    #[arg(long, value_enum)]
+    pub scheme: Scheme,
+    #[arg(long)]
+    pub pp: PathBuf,
+    #[arg(long)]
+    pub pk: Option<PathBuf>,
+    #[arg(long)]
+    pub credential: PathBuf,
+    #[arg(long)]
+    pub signature: PathBuf,
+    #[command(flatten)]
+    pub context: ContextArgs,
+    #[arg(long)]
+    pub out: PathBuf,
+}
+
+#[derive(Args)]
+pub struct VerifyArgs {
+    #[arg(long, value_enum)]
+    pub scheme: Scheme,
+    #[arg(long)]
+    pub pp: PathBuf,
+    #[arg(long)]
+    pub pk: PathBuf,
+    #[arg(long)]
+    pub presentation: PathBuf,
+    #[command(flatten)]
+    pub context: ContextArgs,
+}
+
+/// Verifier context the presentation is bound to, bbs-og and tps presentations are not bound
+#[derive(Args)]
+pub struct ContextArgs {
+    #[arg(long, default_value = "")]
+    pub verifier: String,
+    #[arg(long, default_value = "")]
+    pub nonce: String,
+    #[arg(long, default_value_t = 0)]
+    pub timestamp: u64,
+}
+
+impl ContextArgs {
+    pub fn to_context(&self) -> PresentationContext {
+        PresentationContext::new(
+            self.verifier.as_bytes(),
+            self.nonce.as_bytes(),
+            self.timestamp,
+        )
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = match &cli.command {
+        Command::Setup(args) => schemes::flow(args.scheme).setup(args),
+        Command::Keygen(args) => schemes::flow(args.scheme).keygen(args),
+        Command::Obtain(args) => schemes::flow(args.scheme).obtain(args),
+        Command::Issue(args) => schemes::flow(args.scheme).issue(args),
+        Command::Unblind(args) => schemes::flow(args.scheme).unblind(args),
+        Command::Show(args) => schemes::flow(args.scheme).show(args),
+        Command::Verify(args) => match schemes::flow(args.scheme).verify(args) {
+            Ok(true) => {
+                println!("valid");
+                Ok(())
+            }
+            Ok(false) => {
+                println!("invalid");
+                return ExitCode::from(1);
+            }
+            Err(e) => Err(e),
+        },
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::from(2)
+        }
+    }
+}
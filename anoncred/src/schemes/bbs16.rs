@@ -0,0 +1,103 @@
+use super::Flow;
+use crate::error::CliError;
+use crate::files::{create_dir, parse_attributes, read_artifact, required, write_artifact};
+use crate::{IssueArgs, KeygenArgs, ObtainArgs, SetupArgs, ShowArgs, UnblindArgs, VerifyArgs};
+use ark_bls12_381::{Bls12_381, Fr};
+use ark_ec::pairing::Pairing;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::UniformRand;
+use bbs_plus_16::anon_cred::{AnonCredProtocol, IssuerResponse, ShowCredential};
+use bbs_plus_16::keygen::{gen_keys, PublicKey, SecretKey};
+use bbs_plus_16::proofsystem::CommitmentWithProof;
+use bbs_plus_16::publicparams::PublicParams;
+use bbs_plus_16::signature::BBSPlus16Signature;
+use utils::impl_envelope;
+
+/// Holder side of a BBS+ issuance, the messages and the blinding factor s'
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct Holder<E: Pairing> {
+    pub messages: Vec<E::ScalarField>,
+    pub s_prime: E::ScalarField,
+}
+
+impl_envelope!(Holder, BbsPlus16, Credential, |h| h.messages.len());
+
+pub struct Bbs16;
+
+impl Flow for Bbs16 {
+    fn setup(&self, args: &SetupArgs) -> Result<(), CliError> {
+        let mut rng = rand::thread_rng();
+        let context = Fr::rand(&mut rng);
+        let pp = PublicParams::<Bls12_381>::new(&args.attributes, &context, &mut rng);
+        write_artifact(&args.out, &pp)
+    }
+
+    fn keygen(&self, args: &KeygenArgs) -> Result<(), CliError> {
+        let pp: PublicParams<Bls12_381> = read_artifact(required(&args.pp, "pp")?)?;
+        let (sk, pk) = gen_keys(&pp, &mut rand::thread_rng());
+        create_dir(&args.out_dir)?;
+        write_artifact(&args.out_dir.join("sk.bin"), &sk)?;
+        write_artifact(&args.out_dir.join("pk.bin"), &pk)
+    }
+
+    fn obtain(&self, args: &ObtainArgs) -> Result<(), CliError> {
+        let pp: PublicParams<Bls12_381> = read_artifact(&args.pp)?;
+        let pk: PublicKey<Bls12_381> = read_artifact(required(&args.pk, "pk")?)?;
+        let messages = parse_attributes(&args.attributes, pk.h1hL.len())?;
+        let (request, s_prime) =
+            AnonCredProtocol::obtain(&pp, &pk, &messages, &mut rand::thread_rng())
+                .map_err(CliError::protocol)?;
+        write_artifact(&args.request_out, &request)?;
+        write_artifact(
+            &args.credential_out,
+            &Holder::<Bls12_381> { messages, s_prime },
+        )
+    }
+
+    fn issue(&self, args: &IssueArgs) -> Result<(), CliError> {
+        let pp: PublicParams<Bls12_381> = read_artifact(&args.pp)?;
+        let pk: PublicKey<Bls12_381> = read_artifact(&args.pk)?;
+        let sk: SecretKey<Bls12_381> = read_artifact(&args.sk)?;
+        let request: CommitmentWithProof<Bls12_381> = read_artifact(&args.request)?;
+        let response = AnonCredProtocol::issue(&pp, &sk, &pk, &request, &mut rand::thread_rng())
+            .map_err(CliError::protocol)?;
+        write_artifact(&args.out, &response)
+    }
+
+    fn unblind(&self, args: &UnblindArgs) -> Result<(), CliError> {
+        let pp: PublicParams<Bls12_381> = read_artifact(&args.pp)?;
+        let pk: PublicKey<Bls12_381> = read_artifact(required(&args.pk, "pk")?)?;
+        let holder: Holder<Bls12_381> = read_artifact(&args.credential)?;
+        let response: IssuerResponse<Bls12_381> = read_artifact(&args.response[0])?;
+        let signature = AnonCredProtocol::complete_signature(&holder.s_prime, &response);
+        if !signature.verify(&pp, &pk, &holder.messages) {
+            return Err(CliError::InvalidSignature);
+        }
+        write_artifact(&args.out, &signature)
+    }
+
+    fn show(&self, args: &ShowArgs) -> Result<(), CliError> {
+        let pp: PublicParams<Bls12_381> = read_artifact(&args.pp)?;
+        let pk: PublicKey<Bls12_381> = read_artifact(required(&args.pk, "pk")?)?;
+        let holder: Holder<Bls12_381> = read_artifact(&args.credential)?;
+        let signature: BBSPlus16Signature<Bls12_381> = read_artifact(&args.signature)?;
+        let presentation = AnonCredProtocol::show(
+            &pp,
+            &pk,
+            &signature,
+            &holder.messages,
+            &args.context.to_context(),
+            &mut rand::thread_rng(),
+        )
+        .map_err(CliError::protocol)?;
+        write_artifact(&args.out, &presentation)
+    }
+
+    fn verify(&self, args: &VerifyArgs) -> Result<bool, CliError> {
+        let pp: PublicParams<Bls12_381> = read_artifact(&args.pp)?;
+        let pk: PublicKey<Bls12_381> = read_artifact(&args.pk)?;
+        let presentation: ShowCredential<Bls12_381> = read_artifact(&args.presentation)?;
+        AnonCredProtocol::verify(&pp, &pk, &presentation, &args.context.to_context())
+            .map_err(CliError::protocol)
+    }
+}
@@ -0,0 +1,103 @@
+use super::Flow;
+use crate::error::CliError;
+use crate::files::{create_dir, parse_attributes, read_artifact, required, write_artifact};
+use crate::{IssueArgs, KeygenArgs, ObtainArgs, SetupArgs, ShowArgs, UnblindArgs, VerifyArgs};
+use ark_bls12_381::Bls12_381;
+use ark_ec::pairing::Pairing;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use bbs_plus_og::anon_cred::{AnonCredProtocol, IssuerResponse, ShowCredential};
+use bbs_plus_og::keygen::{gen_keys, PublicKey, SecretKey};
+use bbs_plus_og::proofsystem::CommitmentProof;
+use bbs_plus_og::publicparams::PublicParams;
+use bbs_plus_og::signature::BBSPlusOgSignature;
+use utils::impl_envelope;
+
+/// Holder side of a BBS+ issuance, the messages and the blinding factor s'
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct Holder<E: Pairing> {
+    pub messages: Vec<E::ScalarField>,
+    pub s_prime: E::ScalarField,
+}
+
+impl_envelope!(Holder, BbsPlusOg, Credential, |h| h.messages.len());
+
+pub struct BbsOg;
+
+impl Flow for BbsOg {
+    fn setup(&self, args: &SetupArgs) -> Result<(), CliError> {
+        let pp = PublicParams::<Bls12_381>::new(&args.attributes, &mut rand::thread_rng());
+        write_artifact(&args.out, &pp)
+    }
+
+    fn keygen(&self, args: &KeygenArgs) -> Result<(), CliError> {
+        let pp: PublicParams<Bls12_381> = read_artifact(required(&args.pp, "pp")?)?;
+        let (sk, pk) = gen_keys(&pp, &mut rand::thread_rng());
+        create_dir(&args.out_dir)?;
+        write_artifact(&args.out_dir.join("sk.bin"), &sk)?;
+        write_artifact(&args.out_dir.join("pk.bin"), &pk)
+    }
+
+    fn obtain(&self, args: &ObtainArgs) -> Result<(), CliError> {
+        let pp: PublicParams<Bls12_381> = read_artifact(&args.pp)?;
+        let pk: PublicKey<Bls12_381> = read_artifact(required(&args.pk, "pk")?)?;
+        let messages = parse_attributes(&args.attributes, pp.L)?;
+        let (proof, s_prime) =
+            AnonCredProtocol::obtain(&pp, &pk, &messages, &mut rand::thread_rng())
+                .map_err(CliError::protocol)?;
+        let request = CommitmentProof::<Bls12_381>::deserialize_compressed(&proof[..])
+            .map_err(CliError::protocol)?;
+        write_artifact(&args.request_out, &request)?;
+        write_artifact(
+            &args.credential_out,
+            &Holder::<Bls12_381> { messages, s_prime },
+        )
+    }
+
+    fn issue(&self, args: &IssueArgs) -> Result<(), CliError> {
+        let pp: PublicParams<Bls12_381> = read_artifact(&args.pp)?;
+        let pk: PublicKey<Bls12_381> = read_artifact(&args.pk)?;
+        let sk: SecretKey<Bls12_381> = read_artifact(&args.sk)?;
+        let request: CommitmentProof<Bls12_381> = read_artifact(&args.request)?;
+        let mut proof = Vec::new();
+        request
+            .serialize_compressed(&mut proof)
+            .map_err(CliError::protocol)?;
+        let response = AnonCredProtocol::issue(&pp, &sk, &pk, &proof, &mut rand::thread_rng())
+            .map_err(CliError::protocol)?;
+        write_artifact(&args.out, &response)
+    }
+
+    fn unblind(&self, args: &UnblindArgs) -> Result<(), CliError> {
+        let pp: PublicParams<Bls12_381> = read_artifact(&args.pp)?;
+        let pk: PublicKey<Bls12_381> = read_artifact(required(&args.pk, "pk")?)?;
+        let holder: Holder<Bls12_381> = read_artifact(&args.credential)?;
+        let response: IssuerResponse<Bls12_381> = read_artifact(&args.response[0])?;
+        let signature = AnonCredProtocol::complete_signature(&holder.s_prime, &response);
+        if !signature.verify(&pp, &pk, &holder.messages) {
+            return Err(CliError::InvalidSignature);
+        }
+        write_artifact(&args.out, &signature)
+    }
+
+    fn show(&self, args: &ShowArgs) -> Result<(), CliError> {
+        let pp: PublicParams<Bls12_381> = read_artifact(&args.pp)?;
+        let pk: PublicKey<Bls12_381> = read_artifact(required(&args.pk, "pk")?)?;
+        let holder: Holder<Bls12_381> = read_artifact(&args.credential)?;
+        let signature: BBSPlusOgSignature<Bls12_381> = read_artifact(&args.signature)?;
+        let presentation = AnonCredProtocol::show(
+            &pp,
+            &pk,
+            &signature,
+            &holder.messages,
+            &mut rand::thread_rng(),
+        )
+        .map_err(CliError::protocol)?;
+        write_artifact(&args.out, &presentation)
+    }
+
+    fn verify(&self, args: &VerifyArgs) -> Result<bool, CliError> {
+        let pp: PublicParams<Bls12_381> = read_artifact(&args.pp)?;
+        let presentation: ShowCredential<Bls12_381> = read_artifact(&args.presentation)?;
+        AnonCredProtocol::verify(&pp, &presentation).map_err(CliError::protocol)
+    }
+}
@@ -0,0 +1,84 @@
+use super::Flow;
+use crate::error::CliError;
+use crate::files::{create_dir, parse_attributes, read_artifact, required, write_artifact};
+use crate::{IssueArgs, KeygenArgs, ObtainArgs, SetupArgs, ShowArgs, UnblindArgs, VerifyArgs};
+use ark_bls12_381::{Bls12_381, Fr};
+use ark_std::UniformRand;
+use mimc_abc::credential::{Credential, ShowCredential};
+use mimc_abc::proof::CommitmentProof;
+use mimc_abc::protocol::MimcAbc;
+use mimc_abc::public_params::PublicParams;
+use mimc_abc::signature::{generate_keys, IssuerResponse, SecretKey, VerificationKey};
+
+pub struct Mimc;
+
+impl Flow for Mimc {
+    fn setup(&self, args: &SetupArgs) -> Result<(), CliError> {
+        let pp = PublicParams::<Bls12_381>::new(&args.attributes, &mut rand::thread_rng());
+        write_artifact(&args.out, &pp)
+    }
+
+    fn keygen(&self, args: &KeygenArgs) -> Result<(), CliError> {
+        let pp: PublicParams<Bls12_381> = read_artifact(required(&args.pp, "pp")?)?;
+        let (sk, vk) = generate_keys(&pp, &mut rand::thread_rng());
+        create_dir(&args.out_dir)?;
+        write_artifact(&args.out_dir.join("sk.bin"), &sk)?;
+        write_artifact(&args.out_dir.join("pk.bin"), &vk)
+    }
+
+    fn obtain(&self, args: &ObtainArgs) -> Result<(), CliError> {
+        let pp: PublicParams<Bls12_381> = read_artifact(&args.pp)?;
+        let attributes = parse_attributes(&args.attributes, pp.n)?;
+        let protocol = MimcAbc::new(pp);
+        let mut rng = rand::thread_rng();
+        let credential =
+            Credential::new(&protocol.ck, &protocol.pp, &attributes, Fr::rand(&mut rng));
+        let request = protocol.obtain(&credential, &mut rng);
+        write_artifact(&args.request_out, &request)?;
+        write_artifact(&args.credential_out, &credential)
+    }
+
+    fn issue(&self, args: &IssueArgs) -> Result<(), CliError> {
+        let pp: PublicParams<Bls12_381> = read_artifact(&args.pp)?;
+        let sk: SecretKey<Bls12_381> = read_artifact(&args.sk)?;
+        let request: CommitmentProof<Bls12_381> = read_artifact(&args.request)?;
+        let response = MimcAbc::new(pp)
+            .issue(&request, &sk, &mut rand::thread_rng())
+            .map_err(CliError::protocol)?;
+        write_artifact(&args.out, &response)
+    }
+
+    /// the signature is on the commitment already, there is nothing to unblind, only check
+    fn unblind(&self, args: &UnblindArgs) -> Result<(), CliError> {
+        let pp: PublicParams<Bls12_381> = read_artifact(&args.pp)?;
+        let vk: VerificationKey<Bls12_381> = read_artifact(required(&args.pk, "pk")?)?;
+        let mut credential: Credential<Bls12_381> = read_artifact(&args.credential)?;
+        let response: IssuerResponse<Bls12_381> = read_artifact(&args.response[0])?;
+        credential.add_issuer_response(response.clone());
+        if !credential.verify(&pp, &vk) {
+            return Err(CliError::InvalidSignature);
+        }
+        write_artifact(&args.out, &response.signature)
+    }
+
+    fn show(&self, args: &ShowArgs) -> Result<(), CliError> {
+        let pp: PublicParams<Bls12_381> = read_artifact(&args.pp)?;
+        let mut credential: Credential<Bls12_381> = read_artifact(&args.credential)?;
+        credential.add_signature(read_artifact(&args.signature)?);
+        let presentation = MimcAbc::new(pp)
+            .show(
+                &credential,
+                &args.context.to_context(),
+                &mut rand::thread_rng(),
+            )
+            .map_err(CliError::protocol)?;
+        write_artifact(&args.out, &presentation)
+    }
+
+    fn verify(&self, args: &VerifyArgs) -> Result<bool, CliError> {
+        let pp: PublicParams<Bls12_381> = read_artifact(&args.pp)?;
+        let vk: VerificationKey<Bls12_381> = read_artifact(&args.pk)?;
+        let presentation: ShowCredential<Bls12_381> = read_artifact(&args.presentation)?;
+        Ok(MimcAbc::new(pp).verify(presentation, &vk, &args.context.to_context()))
+    }
+}
@@ -0,0 +1,202 @@
+mod bbs16;
+mod bbs_og;
+mod mimc;
+mod ps;
+mod ps_utt;
+mod tps;
+
+use crate::error::CliError;
+use crate::{
+    IssueArgs, KeygenArgs, ObtainArgs, Scheme, SetupArgs, ShowArgs, UnblindArgs, VerifyArgs,
+};
+
+/// One implementation per scheme, each step reads its inputs from and writes its outputs to
+/// envelope files
+pub trait Flow {
+    fn setup(&self, args: &SetupArgs) -> Result<(), CliError>;
+    fn keygen(&self, args: &KeygenArgs) -> Result<(), CliError>;
+    fn obtain(&self, args: &ObtainArgs) -> Result<(), CliError>;
+    fn issue(&self, args: &IssueArgs) -> Result<(), CliError>;
+    fn unblind(&self, args: &UnblindArgs) -> Result<(), CliError>;
+    fn show(&self, args: &ShowArgs) -> Result<(), CliError>;
+    fn verify(&self, args: &VerifyArgs) -> Result<bool, CliError>;
+}
+
+pub fn flow(scheme: Scheme) -> &'static dyn Flow {
+    match scheme {
+        Scheme::Ps => &ps::Ps,
+        Scheme::PsUtt => &ps_utt::PsUtt,
+        Scheme::Bbs16 => &bbs16::Bbs16,
+        Scheme::BbsOg => &bbs_og::BbsOg,
+        Scheme::Mimc => &mimc::Mimc,
+        Scheme::Tps => &tps::Tps,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ContextArgs;
+    use std::path::{Path, PathBuf};
+
+    fn context(nonce: &str) -> ContextArgs {
+        ContextArgs {
+            verifier: "shop".to_string(),
+            nonce: nonce.to_string(),
+            timestamp: 1_700_000_000,
+        }
+    }
+
+    /// setup, keygen, obtain, issue, unblind, show and verify through files in `dir`
+    fn run_flow(scheme: Scheme, dir: &Path) -> (bool, bool) {
+        let _ = std::fs::remove_dir_all(dir);
+        std::fs::create_dir_all(dir).unwrap();
+        let path = |name: &str| dir.join(name);
+        let flow = flow(scheme);
+        let tps = scheme == Scheme::Tps;
+
+        let pp = if tps {
+            path("issuer/pp.bin")
+        } else {
+            flow.setup(&SetupArgs {
+                scheme,
+                attributes: 3,
+                out: path("pp.bin"),
+            })
+            .unwrap();
+            path("pp.bin")
+        };
+        flow.keygen(&KeygenArgs {
+            scheme,
+            pp: (!tps).then(|| pp.clone()),
+            out_dir: path("issuer"),
+            attributes: Some(3),
+            threshold: Some(2),
+            signers: Some(3),
+        })
+        .unwrap();
+        flow.obtain(&ObtainArgs {
+            scheme,
+            pp: pp.clone(),
+            pk: Some(path("issuer/pk.bin")),
+            attributes: "1, 2, 3".to_string(),
+            request_out: path("request.bin"),
+            credential_out: path("credential.bin"),
+        })
+        .unwrap();
+
+        let issuers: Vec<(PathBuf, PathBuf)> = if tps {
+            (1..=2)
+                .map(|i| {
+                    (
+                        path(&format!("issuer/sk-{}.bin", i)),
+                        path(&format!("issuer/pk-{}.bin", i)),
+                    )
+                })
+                .collect()
+        } else {
+            vec![(path("issuer/sk.bin"), path("issuer/pk.bin"))]
+        };
+        let mut responses = Vec::new();
+        for (i, (sk, pk)) in issuers.iter().enumerate() {
+            let out = path(&format!("response-{}.bin", i));
+            flow.issue(&IssueArgs {
+                scheme,
+                pp: pp.clone(),
+                pk: pk.clone(),
+                sk: sk.clone(),
+                request: path("request.bin"),
+                out: out.clone(),
+            })
+            .unwrap();
+            responses.push(out);
+        }
+        flow.unblind(&UnblindArgs {
+            scheme,
+            pp: pp.clone(),
+            pk: Some(path("issuer/pk.bin")),
+            credential: path("credential.bin"),
+            response: responses,
+            request: Some(path("request.bin")),
+            pk_share: issuers.iter().map(|(_, pk)| pk.clone()).collect(),
+            out: path("signature.bin"),
+        })
+        .unwrap();
+        flow.show(&ShowArgs {
+            scheme,
+            pp: pp.clone(),
+            pk: Some(path("issuer/pk.bin")),
+            credential: path("credential.bin"),
+            signature: path("signature.bin"),
+            context: context("n1"),
+            out: path("presentation.bin"),
+        })
+        .unwrap();
+
+        let verify = |nonce: &str| {
+            flow.verify(&VerifyArgs {
+                scheme,
+                pp: pp.clone(),
+                pk: path("issuer/pk.bin"),
+                presentation: path("presentation.bin"),
+                context: context(nonce),
+            })
+            .unwrap()
+        };
+        let result = (verify("n1"), verify("n2"));
+        std::fs::remove_dir_all(dir).unwrap();
+        result
+    }
+
+    #[test]
+    fn test_cli_flows() {
+        let root = std::env::temp_dir().join(format!("anoncred-{}", std::process::id()));
+        for scheme in [Scheme::Ps, Scheme::PsUtt, Scheme::Bbs16, Scheme::Mimc] {
+            let dir = root.join(format!("{:?}", scheme));
+            assert_eq!(run_flow(scheme, &dir), (true, false), "{:?}", scheme);
+        }
+        // bbs-og and tps presentations are not bound to a verifier context
+        for scheme in [Scheme::BbsOg, Scheme::Tps] {
+            let dir = root.join(format!("{:?}", scheme));
+            assert_eq!(run_flow(scheme, &dir), (true, true), "{:?}", scheme);
+        }
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn test_cli_rejects_mismatched_artifacts() {
+        let dir = std::env::temp_dir().join(format!("anoncred-mismatch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let pp = dir.join("pp.bin");
+        flow(Scheme::Ps)
+            .setup(&SetupArgs {
+                scheme: Scheme::Ps,
+                attributes: 2,
+                out: pp.clone(),
+            })
+            .unwrap();
+        let err = flow(Scheme::Bbs16)
+            .keygen(&KeygenArgs {
+                scheme: Scheme::Bbs16,
+                pp: Some(pp.clone()),
+                out_dir: dir.join("issuer"),
+                attributes: None,
+                threshold: None,
+                signers: None,
+            })
+            .unwrap_err();
+        assert!(matches!(err, CliError::Envelope { .. }), "{}", err);
+        assert!(matches!(
+            crate::files::parse_attributes("1,x", 2),
+            Err(CliError::InvalidAttribute(_))
+        ));
+        assert!(matches!(
+            crate::files::parse_attributes("1,2,3", 2),
+            Err(CliError::AttributeCount {
+                expected: 2,
+                found: 3
+            })
+        ));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
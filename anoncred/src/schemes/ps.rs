@@ -0,0 +1,88 @@
+use super::Flow;
+use crate::error::CliError;
+use crate::files::{create_dir, parse_attributes, read_artifact, required, write_artifact};
+use crate::{IssueArgs, KeygenArgs, ObtainArgs, SetupArgs, ShowArgs, UnblindArgs, VerifyArgs};
+use ark_bls12_381::{Bls12_381, Fr};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::UniformRand;
+use ps::anon_cred::{IssuerResponse, PSAnonCredProtocol, ShowCredential, UserCred};
+use ps::keygen::{gen_keys, PublicKey, SecretKey};
+use ps::proofsystem::CommitmentProof;
+use ps::publicparams::PublicParams;
+use ps::signature::PSSignature;
+
+pub struct Ps;
+
+impl Flow for Ps {
+    fn setup(&self, args: &SetupArgs) -> Result<(), CliError> {
+        let mut rng = rand::thread_rng();
+        let context = Fr::rand(&mut rng);
+        let pp = PublicParams::<Bls12_381>::new(&args.attributes, &context, &mut rng);
+        write_artifact(&args.out, &pp)
+    }
+
+    fn keygen(&self, args: &KeygenArgs) -> Result<(), CliError> {
+        let pp: PublicParams<Bls12_381> = read_artifact(required(&args.pp, "pp")?)?;
+        let (sk, pk) = gen_keys(&pp, &mut rand::thread_rng());
+        create_dir(&args.out_dir)?;
+        write_artifact(&args.out_dir.join("sk.bin"), &sk)?;
+        write_artifact(&args.out_dir.join("pk.bin"), &pk)
+    }
+
+    fn obtain(&self, args: &ObtainArgs) -> Result<(), CliError> {
+        let pk: PublicKey<Bls12_381> = read_artifact(required(&args.pk, "pk")?)?;
+        let protocol = PSAnonCredProtocol::from_public_key(pk);
+        let attributes = parse_attributes(&args.attributes, protocol.pp.n)?;
+        let mut rng = rand::thread_rng();
+        let user_cred = UserCred::new(&attributes, Fr::rand(&mut rng));
+        let proof = protocol
+            .obtain(&user_cred, &mut rng)
+            .map_err(CliError::protocol)?;
+        let request = CommitmentProof::<Bls12_381>::deserialize_compressed(&proof[..])
+            .map_err(CliError::protocol)?;
+        write_artifact(&args.request_out, &request)?;
+        write_artifact(&args.credential_out, &user_cred)
+    }
+
+    fn issue(&self, args: &IssueArgs) -> Result<(), CliError> {
+        let pk: PublicKey<Bls12_381> = read_artifact(&args.pk)?;
+        let sk: SecretKey<Bls12_381> = read_artifact(&args.sk)?;
+        let request: CommitmentProof<Bls12_381> = read_artifact(&args.request)?;
+        let mut proof = Vec::new();
+        request
+            .serialize_compressed(&mut proof)
+            .map_err(CliError::protocol)?;
+        let response = PSAnonCredProtocol::from_keys(pk, sk)
+            .issue(&proof, &mut rand::thread_rng())
+            .map_err(CliError::protocol)?;
+        write_artifact(&args.out, &response)
+    }
+
+    fn unblind(&self, args: &UnblindArgs) -> Result<(), CliError> {
+        let user_cred: UserCred<Bls12_381> = read_artifact(&args.credential)?;
+        let response: IssuerResponse<Bls12_381> = read_artifact(&args.response[0])?;
+        let signature =
+            PSAnonCredProtocol::complete_signature(&response.blind_signature, &user_cred.t);
+        write_artifact(&args.out, &signature)
+    }
+
+    fn show(&self, args: &ShowArgs) -> Result<(), CliError> {
+        let pk: PublicKey<Bls12_381> = read_artifact(required(&args.pk, "pk")?)?;
+        let user_cred: UserCred<Bls12_381> = read_artifact(&args.credential)?;
+        let signature: PSSignature<Bls12_381> = read_artifact(&args.signature)?;
+        let context = args.context.to_context();
+        let presentation = PSAnonCredProtocol::from_public_key(pk)
+            .show(&signature, &user_cred, &context, &mut rand::thread_rng())
+            .map_err(CliError::protocol)?;
+        write_artifact(&args.out, &presentation)
+    }
+
+    fn verify(&self, args: &VerifyArgs) -> Result<bool, CliError> {
+        let pk: PublicKey<Bls12_381> = read_artifact(&args.pk)?;
+        let presentation: ShowCredential<Bls12_381> = read_artifact(&args.presentation)?;
+        let context = args.context.to_context();
+        PSAnonCredProtocol::from_public_key(pk)
+            .verify(&presentation, &context)
+            .map_err(CliError::protocol)
+    }
+}
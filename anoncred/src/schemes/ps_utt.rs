@@ -0,0 +1,114 @@
+use super::Flow;
+use crate::error::CliError;
+use crate::files::{create_dir, parse_attributes, read_artifact, required, write_artifact};
+use crate::{IssueArgs, KeygenArgs, ObtainArgs, SetupArgs, ShowArgs, UnblindArgs, VerifyArgs};
+use ark_bls12_381::{Bls12_381, Fr};
+use ark_ec::pairing::Pairing;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::UniformRand;
+use ps_utt::anon_cred_improved::{AnonCredProtocolImproved, ShowCredentialImproved, UserCred};
+use ps_utt::keygen::{gen_keys_improved, SecretKeyImproved, VerificationKeyImproved};
+use ps_utt::proofsystem::CommitmentProof;
+use ps_utt::publicparams::PublicParams;
+use ps_utt::signature::PSUTTSignatureImproved;
+use utils::impl_envelope;
+
+/// The issuer signs the G2 commitment after checking it against the proven G1 commitment, so both
+/// travel in the request
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct IssuanceRequest<E: Pairing> {
+    pub cmg2: E::G2Affine,
+    pub proof: CommitmentProof<E>,
+}
+
+impl_envelope!(IssuanceRequest, PsUttImproved, IssuanceRequest);
+
+pub struct PsUtt;
+
+impl Flow for PsUtt {
+    fn setup(&self, args: &SetupArgs) -> Result<(), CliError> {
+        let mut rng = rand::thread_rng();
+        let context = Fr::rand(&mut rng);
+        let pp = PublicParams::<Bls12_381>::new(&args.attributes, &context, &mut rng);
+        write_artifact(&args.out, &pp)
+    }
+
+    fn keygen(&self, args: &KeygenArgs) -> Result<(), CliError> {
+        let pp: PublicParams<Bls12_381> = read_artifact(required(&args.pp, "pp")?)?;
+        let (sk, vk) = gen_keys_improved(&pp, &mut rand::thread_rng());
+        create_dir(&args.out_dir)?;
+        write_artifact(&args.out_dir.join("sk.bin"), &sk)?;
+        write_artifact(&args.out_dir.join("pk.bin"), &vk)
+    }
+
+    fn obtain(&self, args: &ObtainArgs) -> Result<(), CliError> {
+        let pp: PublicParams<Bls12_381> = read_artifact(&args.pp)?;
+        let vk: VerificationKeyImproved<Bls12_381> = read_artifact(required(&args.pk, "pk")?)?;
+        let attributes = parse_attributes(&args.attributes, pp.n)?;
+        let mut rng = rand::thread_rng();
+        let user_cred = UserCred::new(&pp, &attributes, Fr::rand(&mut rng));
+        let proof = AnonCredProtocolImproved::from_verification_key(pp, vk)
+            .obtain(&user_cred, &mut rng)
+            .map_err(CliError::protocol)?;
+        let request = IssuanceRequest {
+            cmg2: user_cred.commitment.cmg2,
+            proof: CommitmentProof::<Bls12_381>::deserialize_compressed(&proof[..])
+                .map_err(CliError::protocol)?,
+        };
+        write_artifact(&args.request_out, &request)?;
+        write_artifact(&args.credential_out, &user_cred)
+    }
+
+    fn issue(&self, args: &IssueArgs) -> Result<(), CliError> {
+        let pp: PublicParams<Bls12_381> = read_artifact(&args.pp)?;
+        let vk: VerificationKeyImproved<Bls12_381> = read_artifact(&args.pk)?;
+        let sk: SecretKeyImproved<Bls12_381> = read_artifact(&args.sk)?;
+        let request: IssuanceRequest<Bls12_381> = read_artifact(&args.request)?;
+        let mut proof = Vec::new();
+        request
+            .proof
+            .serialize_compressed(&mut proof)
+            .map_err(CliError::protocol)?;
+        let signature = AnonCredProtocolImproved::from_keys(pp, sk, vk)
+            .issue(&request.cmg2, &proof, &mut rand::thread_rng())
+            .map_err(CliError::protocol)?;
+        write_artifact(&args.out, &signature)
+    }
+
+    /// PS-UTT signs the commitment directly, there is nothing to unblind, only check
+    fn unblind(&self, args: &UnblindArgs) -> Result<(), CliError> {
+        let pp: PublicParams<Bls12_381> = read_artifact(&args.pp)?;
+        let vk: VerificationKeyImproved<Bls12_381> = read_artifact(required(&args.pk, "pk")?)?;
+        let user_cred: UserCred<Bls12_381> = read_artifact(&args.credential)?;
+        let signature: PSUTTSignatureImproved<Bls12_381> = read_artifact(&args.response[0])?;
+        if !signature.verify(&pp, &vk, &user_cred.commitment.cmg1) {
+            return Err(CliError::InvalidSignature);
+        }
+        write_artifact(&args.out, &signature)
+    }
+
+    fn show(&self, args: &ShowArgs) -> Result<(), CliError> {
+        let pp: PublicParams<Bls12_381> = read_artifact(&args.pp)?;
+        let vk: VerificationKeyImproved<Bls12_381> = read_artifact(required(&args.pk, "pk")?)?;
+        let user_cred: UserCred<Bls12_381> = read_artifact(&args.credential)?;
+        let signature: PSUTTSignatureImproved<Bls12_381> = read_artifact(&args.signature)?;
+        let presentation = AnonCredProtocolImproved::from_verification_key(pp, vk)
+            .show(
+                &user_cred.commitment,
+                &signature,
+                &args.context.to_context(),
+                &mut rand::thread_rng(),
+            )
+            .map_err(CliError::protocol)?;
+        write_artifact(&args.out, &presentation)
+    }
+
+    fn verify(&self, args: &VerifyArgs) -> Result<bool, CliError> {
+        let pp: PublicParams<Bls12_381> = read_artifact(&args.pp)?;
+        let vk: VerificationKeyImproved<Bls12_381> = read_artifact(&args.pk)?;
+        let presentation: ShowCredentialImproved<Bls12_381> = read_artifact(&args.presentation)?;
+        AnonCredProtocolImproved::from_verification_key(pp, vk)
+            .verify(&presentation, &args.context.to_context())
+            .map_err(CliError::protocol)
+    }
+}
@@ -0,0 +1,133 @@
+use super::Flow;
+use crate::error::CliError;
+use crate::files::{create_dir, parse_attributes, read_artifact, required, write_artifact};
+use crate::{IssueArgs, KeygenArgs, ObtainArgs, SetupArgs, ShowArgs, UnblindArgs, VerifyArgs};
+use ark_bls12_381::Bls12_381;
+use ps_utt_ts::credential::{Credential, IssuanceRequest, ShowCredential};
+use ps_utt_ts::keygen::{SecretKeyShare, VerificationKey, VerificationKeyShare};
+use ps_utt_ts::protocol::{IssuerProtocol, UserProtocol, VerifierProtocol};
+use ps_utt_ts::signature::{PartialSignature, ThresholdSignature};
+use ps_utt_ts::signer::Signer;
+use ps_utt_ts::symmetric_commitment::SymmetricCommitmentKey;
+
+pub struct Tps;
+
+impl Flow for Tps {
+    fn setup(&self, _args: &SetupArgs) -> Result<(), CliError> {
+        Err(CliError::Unsupported(
+            "tps parameters are derived from the issuer keys, run keygen with --attributes",
+        ))
+    }
+
+    fn keygen(&self, args: &KeygenArgs) -> Result<(), CliError> {
+        let attributes = args
+            .attributes
+            .ok_or(CliError::MissingArgument("attributes"))?;
+        let threshold = args
+            .threshold
+            .ok_or(CliError::MissingArgument("threshold"))?;
+        let signers = args.signers.ok_or(CliError::MissingArgument("signers"))?;
+        let (ck, vk, ts_keys) = IssuerProtocol::setup::<Bls12_381>(
+            threshold,
+            signers,
+            attributes,
+            &mut rand::thread_rng(),
+        );
+        create_dir(&args.out_dir)?;
+        write_artifact(&args.out_dir.join("pp.bin"), &ck)?;
+        write_artifact(&args.out_dir.join("pk.bin"), &vk)?;
+        for (sk_share, vk_share) in ts_keys.sk_shares.iter().zip(&ts_keys.vk_shares) {
+            let sk_path = args.out_dir.join(format!("sk-{}.bin", sk_share.index));
+            let pk_path = args.out_dir.join(format!("pk-{}.bin", vk_share.index));
+            write_artifact(&sk_path, sk_share)?;
+            write_artifact(&pk_path, vk_share)?;
+        }
+        Ok(())
+    }
+
+    fn obtain(&self, args: &ObtainArgs) -> Result<(), CliError> {
+        let ck: SymmetricCommitmentKey<Bls12_381> = read_artifact(&args.pp)?;
+        let attributes = parse_attributes(&args.attributes, ck.ck.len())?;
+        let (credential, request) =
+            UserProtocol::request_credential(ck, Some(&attributes), &mut rand::thread_rng())
+                .map_err(CliError::protocol)?;
+        write_artifact(&args.request_out, &request)?;
+        write_artifact(&args.credential_out, &credential)
+    }
+
+    fn issue(&self, args: &IssueArgs) -> Result<(), CliError> {
+        let ck: SymmetricCommitmentKey<Bls12_381> = read_artifact(&args.pp)?;
+        let vk_share: VerificationKeyShare<Bls12_381> = read_artifact(&args.pk)?;
+        let sk_share: SecretKeyShare<Bls12_381> = read_artifact(&args.sk)?;
+        let request: IssuanceRequest<Bls12_381> = read_artifact(&args.request)?;
+        let signer = Signer::new(&ck, &sk_share, &vk_share);
+        let share = IssuerProtocol::issue_share(&signer, &request, &mut rand::thread_rng())
+            .map_err(CliError::protocol)?;
+        write_artifact(&args.out, &share)
+    }
+
+    /// checks every share against its signer's key share, then aggregates all of them
+    fn unblind(&self, args: &UnblindArgs) -> Result<(), CliError> {
+        let ck: SymmetricCommitmentKey<Bls12_381> = read_artifact(&args.pp)?;
+        let credential: Credential<Bls12_381> = read_artifact(&args.credential)?;
+        let request: IssuanceRequest<Bls12_381> =
+            read_artifact(required(&args.request, "request")?)?;
+        let shares = args
+            .response
+            .iter()
+            .map(|path| {
+                let share: PartialSignature<Bls12_381> = read_artifact(path)?;
+                Ok((share.party_index, share))
+            })
+            .collect::<Result<Vec<_>, CliError>>()?;
+        let vk_shares = args
+            .pk_share
+            .iter()
+            .map(|path| read_artifact(path))
+            .collect::<Result<Vec<VerificationKeyShare<Bls12_381>>, _>>()?;
+        let verified = UserProtocol::verify_signature_shares(
+            &ck,
+            &vk_shares,
+            &request,
+            &shares,
+            shares.len(),
+            &mut rand::thread_rng(),
+        )
+        .map_err(CliError::protocol)?;
+        let signature = UserProtocol::aggregate_shares(
+            &ck,
+            &verified,
+            credential.get_blinding_factors(),
+            verified.len(),
+            &request.h,
+        )
+        .map_err(CliError::protocol)?;
+        write_artifact(&args.out, &signature)
+    }
+
+    fn show(&self, args: &ShowArgs) -> Result<(), CliError> {
+        let mut credential: Credential<Bls12_381> = read_artifact(&args.credential)?;
+        let signature: ThresholdSignature<Bls12_381> = read_artifact(&args.signature)?;
+        credential.attach_signature(signature);
+        let presentation: ShowCredential<Bls12_381> =
+            UserProtocol::show(&credential, &mut rand::thread_rng())
+                .map_err(CliError::protocol)?
+                .into();
+        write_artifact(&args.out, &presentation)
+    }
+
+    fn verify(&self, args: &VerifyArgs) -> Result<bool, CliError> {
+        let ck: SymmetricCommitmentKey<Bls12_381> = read_artifact(&args.pp)?;
+        let vk: VerificationKey<Bls12_381> = read_artifact(&args.pk)?;
+        let presentation: ShowCredential<Bls12_381> = read_artifact(&args.presentation)?;
+        VerifierProtocol::verify(
+            &ck,
+            &vk,
+            &presentation.cm,
+            &presentation.cm_tilde,
+            &presentation.randomized_signature,
+            &presentation.proof,
+        )
+        .map_err(CliError::protocol)
+    }
+}
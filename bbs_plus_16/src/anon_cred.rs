@@ -1,29 +1,69 @@
-use crate::keygen::{PublicKey, SecretKey};
-use crate::proofsystem::{CommitmentWithProof, ProofError, ProofSystem};
+use crate::keygen::{PreparedVerificationKey, PublicKey, PublicKeyTables, SecretKey};
+use crate::proofsystem::{
+    check_message_indices, BBSPlusProofOfKnowledge, CommitmentWithProof, PreparedProof, ProofError,
+    ProofSystem,
+};
 use crate::publicparams::PublicParams;
 use crate::signature::{BBSPlus16RandomizedSignature, BBSPlus16Signature};
 use ark_ec::pairing::Pairing;
-use ark_ec::CurveGroup;
+use ark_ec::{AffineRepr, CurveGroup, VariableBaseMSM};
 use ark_ff::{Field, UniformRand};
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::ops::Neg;
 use ark_std::rand::Rng;
+use schnorr::batch::{SchnorrBatch, SchnorrClaim};
+use schnorr::key_proof::KeyProof;
+use schnorr::verifiable_encryption::AuditorPublicKey;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use utils::context::PresentationContext;
+use utils::disclosure::Disclosure;
+use utils::encoding::{Base64Bytes, Base64Point, HexScalar};
+use utils::pairing::bisect_failures;
+use utils::schema::Schema;
+use utils::update::{apply_updates, AttributeUpdate};
 
-#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+#[serde_as]
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize, Serialize, Deserialize)]
+#[serde(bound = "")]
 #[allow(non_snake_case)]
 pub struct IssuerResponse<E: Pairing> {
+    #[serde_as(as = "Base64Point")]
     pub A: E::G1Affine,
+    #[serde_as(as = "HexScalar")]
     pub e: E::ScalarField,
+    #[serde_as(as = "HexScalar")]
     pub s_double_prime: E::ScalarField, // Issuer's blinding factor
 }
 
-#[derive(Clone)]
+#[serde_as]
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct ShowCredential<E: Pairing> {
     pub randomized_signature: BBSPlus16RandomizedSignature<E>,
+    #[serde_as(as = "Base64Bytes")]
     pub proof: Vec<u8>,
+    /// Messages revealed by `finish_show`, empty for the other shows
+    pub disclosed: Disclosure<E::ScalarField>,
+}
+
+/// Randomized signature with both Schnorr commitments of the proof of knowledge
+pub struct ShowToken<E: Pairing> {
+    proof: PreparedProof<E>,
 }
 
 pub struct AnonCredProtocol;
 impl AnonCredProtocol {
+    /// Holder checks the issuer's key proof once, before the first `obtain` under `pk`
+    pub fn verify_issuer_key<E: Pairing, R: Rng>(
+        pp: &PublicParams<E>,
+        pk: &PublicKey<E>,
+        proof: &KeyProof<E>,
+        rng: &mut R,
+    ) -> Result<(), ProofError> {
+        Ok(pk.verify_key(pp, proof, rng)?)
+    }
+
     /// User creates a commitment to their messages and proves knowledge
     ///
     /// # Arguments
@@ -50,6 +90,20 @@ impl AnonCredProtocol {
         Ok((commitment_proof, s_prime))
     }
 
+    /// `obtain` with the commitment and proof computed from the key's tables
+    pub fn obtain_with_tables<E: Pairing, R: Rng>(
+        pk: &PublicKey<E>,
+        tables: &PublicKeyTables<E>,
+        messages: &[E::ScalarField],
+        rng: &mut R,
+    ) -> Result<(CommitmentWithProof<E>, E::ScalarField), ProofError> {
+        let s_prime = E::ScalarField::rand(rng);
+        let commitment_proof =
+            ProofSystem::create_commitment_proof_with_tables(pk, tables, messages, &s_prime, rng)?;
+
+        Ok((commitment_proof, s_prime))
+    }
+
     /// Issuer verifies the proof and issues a signature
     ///
     /// # Arguments
@@ -75,12 +129,83 @@ impl AnonCredProtocol {
             return Err(ProofError::VerificationFailed);
         }
 
+        Self::sign_commitment(pp, sk, pk, &commitment_proof.commitment, rng)
+    }
+
+    /// `issue` with h_0^s'' taken from the key's tables
+    pub fn issue_with_tables<E: Pairing, R: Rng>(
+        pp: &PublicParams<E>,
+        sk: &SecretKey<E>,
+        pk: &PublicKey<E>,
+        tables: &PublicKeyTables<E>,
+        commitment_proof: &CommitmentWithProof<E>,
+        rng: &mut R,
+    ) -> Result<IssuerResponse<E>, ProofError> {
+        if !ProofSystem::verify_commitment_proof(pp, pk, commitment_proof)? {
+            return Err(ProofError::VerificationFailed);
+        }
+
+        Self::sign_commitment_with(
+            pp,
+            sk,
+            &commitment_proof.commitment,
+            |s| tables.h0.msm(&[s]),
+            rng,
+        )
+    }
+
+    /// Issuer verifies many proofs in one batch and issues a response for each
+    ///
+    /// # Arguments
+    /// * `pp` - Public parameters
+    /// * `sk` - Issuer's secret key
+    /// * `pk` - Issuer's public key
+    /// * `commitment_proofs` - Commitments with proofs from the users
+    /// * `rng` - Random number generator
+    ///
+    /// # Returns
+    /// * One issuer response per request, or the index of the first invalid request
+    pub fn issue_batch<E: Pairing, R: Rng>(
+        pp: &PublicParams<E>,
+        sk: &SecretKey<E>,
+        pk: &PublicKey<E>,
+        commitment_proofs: &[CommitmentWithProof<E>],
+        rng: &mut R,
+    ) -> Result<Vec<IssuerResponse<E>>, ProofError> {
+        ProofSystem::verify_commitment_proofs_batch(pk, commitment_proofs, rng)?;
+
+        commitment_proofs
+            .iter()
+            .map(|commitment_proof| {
+                Self::sign_commitment(pp, sk, pk, &commitment_proof.commitment, rng)
+            })
+            .collect()
+    }
+
+    fn sign_commitment<E: Pairing, R: Rng>(
+        pp: &PublicParams<E>,
+        sk: &SecretKey<E>,
+        pk: &PublicKey<E>,
+        commitment: &E::G1Affine,
+        rng: &mut R,
+    ) -> Result<IssuerResponse<E>, ProofError> {
+        Self::sign_commitment_with(pp, sk, commitment, |s| pk.h0 * s, rng)
+    }
+
+    // h0_mul computes h_0^s, from the key or from its tables
+    fn sign_commitment_with<E: Pairing, R: Rng>(
+        pp: &PublicParams<E>,
+        sk: &SecretKey<E>,
+        commitment: &E::G1Affine,
+        h0_mul: impl Fn(E::ScalarField) -> E::G1,
+        rng: &mut R,
+    ) -> Result<IssuerResponse<E>, ProofError> {
         // Generate random values for the signature
         let e = E::ScalarField::rand(rng);
         let s_double_prime = E::ScalarField::rand(rng);
 
         // Compute A = (g₁ · g₂^s'' · Cm)^(1/(e+x))
-        let base = pp.g1 + pk.h0 * s_double_prime + commitment_proof.commitment;
+        let base = pp.g1 + h0_mul(s_double_prime) + *commitment;
         let exponent = (sk.x + e).inverse().ok_or(ProofError::VerificationFailed)?;
         #[allow(non_snake_case)]
         let A = (base * exponent).into_affine();
@@ -115,6 +240,167 @@ impl AnonCredProtocol {
         }
     }
 
+    /// User commits to all messages except those at `issuer_indices` and proves knowledge
+    ///
+    /// # Arguments
+    /// * `pk` - Issuer's public key
+    /// * `messages` - Full message vector, entries at `issuer_indices` are ignored
+    /// * `issuer_indices` - Message indices the issuer sets before signing
+    /// * `rng` - Random number generator
+    ///
+    /// # Returns
+    /// * Commitment with proof and blinding factor
+    pub fn obtain_partial<E: Pairing, R: Rng>(
+        pk: &PublicKey<E>,
+        messages: &[E::ScalarField],
+        issuer_indices: &[usize],
+        rng: &mut R,
+    ) -> Result<(CommitmentWithProof<E>, E::ScalarField), ProofError> {
+        let s_prime = E::ScalarField::rand(rng);
+        let commitment_proof = ProofSystem::create_partial_commitment_proof(
+            pk,
+            messages,
+            issuer_indices,
+            &s_prime,
+            rng,
+        )?;
+
+        Ok((commitment_proof, s_prime))
+    }
+
+    /// Issuer verifies the partial commitment proof and signs it together with its own messages
+    ///
+    /// # Arguments
+    /// * `pp` - Public parameters
+    /// * `sk` - Issuer's secret key
+    /// * `pk` - Issuer's public key
+    /// * `commitment_proof` - Commitment with proof from `obtain_partial`
+    /// * `issuer_attributes` - (index, value) pairs the issuer sets
+    /// * `rng` - Random number generator
+    ///
+    /// # Returns
+    /// * Issuer's response containing signature components
+    pub fn issue_partial<E: Pairing, R: Rng>(
+        pp: &PublicParams<E>,
+        sk: &SecretKey<E>,
+        pk: &PublicKey<E>,
+        commitment_proof: &CommitmentWithProof<E>,
+        issuer_attributes: &[(usize, E::ScalarField)],
+        rng: &mut R,
+    ) -> Result<IssuerResponse<E>, ProofError> {
+        let issuer_indices: Vec<usize> = issuer_attributes.iter().map(|(i, _)| *i).collect();
+        if !ProofSystem::verify_partial_commitment_proof(pk, &issuer_indices, commitment_proof)? {
+            return Err(ProofError::VerificationFailed);
+        }
+
+        let e = E::ScalarField::rand(rng);
+        let s_double_prime = E::ScalarField::rand(rng);
+
+        // A = (g₁ · h₀^s'' · Cm · ∏ h_j^a_j)^(1/(e+x)) over the issuer-set messages
+        let bases: Vec<E::G1Affine> = issuer_indices.iter().map(|i| pk.h1hL[*i]).collect();
+        let values: Vec<E::ScalarField> = issuer_attributes.iter().map(|(_, a)| *a).collect();
+        let base = pp.g1
+            + pk.h0 * s_double_prime
+            + commitment_proof.commitment
+            + E::G1::msm_unchecked(&bases, &values);
+        let exponent = (sk.x + e).inverse().ok_or(ProofError::VerificationFailed)?;
+        #[allow(non_snake_case)]
+        let A = (base * exponent).into_affine();
+
+        Ok(IssuerResponse {
+            A,
+            e,
+            s_double_prime,
+        })
+    }
+
+    /// User completes a partially blind signature, returns it with the merged message vector it signs
+    ///
+    /// # Arguments
+    /// * `s_prime` - User's blinding factor
+    /// * `issuer_response` - Response from the issuer
+    /// * `messages` - The user's message vector passed to `obtain_partial`
+    /// * `issuer_attributes` - (index, value) pairs the issuer set
+    pub fn complete_partial_signature<E: Pairing>(
+        s_prime: &E::ScalarField,
+        issuer_response: &IssuerResponse<E>,
+        messages: &[E::ScalarField],
+        issuer_attributes: &[(usize, E::ScalarField)],
+    ) -> Result<(BBSPlus16Signature<E>, Vec<E::ScalarField>), ProofError> {
+        let issuer_indices: Vec<usize> = issuer_attributes.iter().map(|(i, _)| *i).collect();
+        check_message_indices(messages.len(), &issuer_indices)?;
+        let mut merged = messages.to_vec();
+        for (i, a) in issuer_attributes {
+            merged[*i] = *a;
+        }
+        Ok((Self::complete_signature(s_prime, issuer_response), merged))
+    }
+
+    /// User requests a refreshed credential from the issuer of `pk`, showing the old credential
+    /// signed under `old_pp`/`old_pk` and committing to the messages after `updates`
+    ///
+    /// # Returns
+    /// * Re-issuance request, the new blinding factor s' and the updated messages
+    pub fn obtain_reissue<E: Pairing, R: Rng>(
+        old_pp: &PublicParams<E>,
+        old_pk: &PublicKey<E>,
+        pk: &PublicKey<E>,
+        signature: &BBSPlus16Signature<E>,
+        messages: &[E::ScalarField],
+        updates: &[(usize, AttributeUpdate<E::ScalarField>)],
+        context: &PresentationContext,
+        rng: &mut R,
+    ) -> Result<(Vec<u8>, E::ScalarField, Vec<E::ScalarField>), ProofError> {
+        let s_prime = E::ScalarField::rand(rng);
+        let randomized_signature = signature.rerandomize(old_pp, old_pk, messages, rng);
+        let request = ProofSystem::bbs_plus_16_prove_reissuance(
+            old_pk,
+            &randomized_signature,
+            pk,
+            messages,
+            updates,
+            &s_prime,
+            context,
+            rng,
+        )?;
+
+        Ok((request, s_prime, apply_updates(messages, updates)))
+    }
+
+    /// Issuer checks the old credential and the declared updates, then signs the new commitment.
+    /// The user finishes with `complete_signature` and the s' from `obtain_reissue`
+    pub fn reissue<E: Pairing, R: Rng>(
+        old_pp: &PublicParams<E>,
+        old_pk: &PublicKey<E>,
+        pp: &PublicParams<E>,
+        sk: &SecretKey<E>,
+        pk: &PublicKey<E>,
+        request: &[u8],
+        updates: &[(usize, AttributeUpdate<E::ScalarField>)],
+        context: &PresentationContext,
+        rng: &mut R,
+    ) -> Result<IssuerResponse<E>, ProofError> {
+        let commitment = ProofSystem::bbs_plus_16_verify_reissuance(
+            old_pp, old_pk, pk, updates, context, request,
+        )?
+        .ok_or(ProofError::VerificationFailed)?;
+
+        let e = E::ScalarField::rand(rng);
+        let s_double_prime = E::ScalarField::rand(rng);
+
+        // A = (g₁ · h₀^s'' · Cm')^(1/(e+x))
+        let base = pp.g1 + pk.h0 * s_double_prime + commitment;
+        let exponent = (sk.x + e).inverse().ok_or(ProofError::VerificationFailed)?;
+        #[allow(non_snake_case)]
+        let A = (base * exponent).into_affine();
+
+        Ok(IssuerResponse {
+            A,
+            e,
+            s_double_prime,
+        })
+    }
+
     /// User shows the credential by creating a randomized signature and proof
     ///
     /// # Arguments
@@ -148,18 +434,76 @@ impl AnonCredProtocol {
         pk: &PublicKey<E>,
         signature: &BBSPlus16Signature<E>,
         messages: &[E::ScalarField],
+        context: &PresentationContext,
         rng: &mut R,
     ) -> Result<ShowCredential<E>, ProofError> {
         // Rerandomize the signature
         let randomized_signature = signature.rerandomize(pp, pk, messages, rng);
 
-        // Generate the proof
-        let proof = ProofSystem::bbs_plus_16_prove(pp, &randomized_signature, pk, messages, rng)?;
+        // Generate the proof bound to the verifier's context
+        let proof =
+            ProofSystem::bbs_plus_16_prove(pp, &randomized_signature, pk, messages, context, rng)?;
 
         // Return the randomized signature and the proof
         Ok(ShowCredential {
             randomized_signature,
             proof,
+            disclosed: Disclosure::default(),
+        })
+    }
+
+    /// Offline half of `show`: randomizes the signature and computes the Schnorr commitments
+    pub fn precompute_show<E: Pairing, R: Rng>(
+        pp: &PublicParams<E>,
+        pk: &PublicKey<E>,
+        signature: &BBSPlus16Signature<E>,
+        messages: &[E::ScalarField],
+        rng: &mut R,
+    ) -> ShowToken<E> {
+        let randomized_signature = signature.rerandomize(pp, pk, messages, rng);
+        ShowToken {
+            proof: ProofSystem::prepare_proof(&randomized_signature, pk, messages, rng),
+        }
+    }
+
+    /// Online half of `show`: binds a precomputed token to the verifier's context and reveals
+    /// the messages at `disclosed`
+    pub fn finish_show<E: Pairing>(
+        token: ShowToken<E>,
+        context: &PresentationContext,
+        disclosed: &[usize],
+    ) -> Result<ShowCredential<E>, ProofError> {
+        let randomized_signature = token.proof.randomized_signature().clone();
+        let (proof, disclosed) = token.proof.finish(context, disclosed)?;
+        Ok(ShowCredential {
+            randomized_signature,
+            proof,
+            disclosed,
+        })
+    }
+
+    /// `show` with the randomization and proof commitments taken from the key's tables
+    pub fn show_with_tables<E: Pairing, R: Rng>(
+        pp: &PublicParams<E>,
+        tables: &PublicKeyTables<E>,
+        signature: &BBSPlus16Signature<E>,
+        messages: &[E::ScalarField],
+        context: &PresentationContext,
+        rng: &mut R,
+    ) -> Result<ShowCredential<E>, ProofError> {
+        let randomized_signature = signature.rerandomize_with_tables(pp, tables, messages, rng);
+        let proof = ProofSystem::bbs_plus_16_prove_with_tables(
+            &randomized_signature,
+            tables,
+            messages,
+            context,
+            rng,
+        )?;
+
+        Ok(ShowCredential {
+            randomized_signature,
+            proof,
+            disclosed: Disclosure::default(),
         })
     }
 
@@ -168,7 +512,8 @@ impl AnonCredProtocol {
     /// # Arguments
     /// * `pp` - Public parameters
     /// * `pk` - Issuer's public key
-    /// * `serialized_proof` - Serialized proof from the user
+    /// * `cred_show` - Presentation from the user
+    /// * `context` - The verifier's context the presentation must be bound to
     ///
     /// # Returns
     /// * Result indicating whether the proof is valid
@@ -176,31 +521,262 @@ impl AnonCredProtocol {
         pp: &PublicParams<E>,
         pk: &PublicKey<E>,
         cred_show: &ShowCredential<E>,
+        context: &PresentationContext,
+    ) -> Result<bool, ProofError> {
+        Self::verify_prepared(pp, &pk.prepare(pp), cred_show, context)
+    }
+
+    /// `verify` with a prepared key, for verifiers that check many presentations
+    pub fn verify_prepared<E: Pairing>(
+        pp: &PublicParams<E>,
+        pvk: &PreparedVerificationKey<E>,
+        cred_show: &ShowCredential<E>,
+        context: &PresentationContext,
     ) -> Result<bool, ProofError> {
+        // the signature checked against the key must be the one the proof is about
+        let proof: BBSPlusProofOfKnowledge<E> =
+            CanonicalDeserialize::deserialize_compressed(&cred_show.proof[..])?;
+        if !shows_proven_signature(cred_show, &proof) {
+            return Ok(false);
+        }
+
         // Verify the proof
-        if !ProofSystem::bbs_plus_16_verify_proof(pp, pk, &cred_show.proof)? {
+        if !ProofSystem::bbs_plus_16_verify_proof(
+            pp,
+            &pvk.pk,
+            &cred_show.proof,
+            &cred_show.disclosed,
+            context,
+        )? {
             return Ok(false);
         }
 
-        if !cred_show.randomized_signature.verify_pairing(&pp, &pk) {
+        if !cred_show.randomized_signature.verify_pairing_prepared(pvk) {
             return Ok(false);
         }
 
         Ok(true)
     }
+
+    /// Verifier checks many presentations, each against its own context, and returns the indices
+    /// of the ones that fail. The Schnorr proofs share one randomized MSM and the signatures one
+    /// pairing check, a failing batch is bisected.
+    pub fn verify_batch<E: Pairing, R: Rng + Send>(
+        pp: &PublicParams<E>,
+        pvk: &PreparedVerificationKey<E>,
+        presentations: &[(&ShowCredential<E>, &PresentationContext)],
+        rng: &mut R,
+    ) -> Vec<usize> {
+        let pk = &pvk.pk;
+        let mut failures = Vec::new();
+        let mut proofs: Vec<(usize, BBSPlusProofOfKnowledge<E>)> =
+            Vec::with_capacity(presentations.len());
+        for (i, (cred_show, context)) in presentations.iter().enumerate() {
+            let proof =
+                match BBSPlusProofOfKnowledge::<E>::deserialize_compressed(&cred_show.proof[..]) {
+                    Ok(proof) => proof,
+                    Err(_) => {
+                        failures.push(i);
+                        continue;
+                    }
+                };
+            let challenge = ProofSystem::context_challenge(
+                &proof.randomized_sig,
+                &proof.schnorr_commitment_1,
+                &proof.schnorr_commitment_2,
+                &cred_show.disclosed,
+                context,
+            );
+            if !shows_proven_signature(cred_show, &proof)
+                || !matches!(challenge, Ok(challenge) if challenge == proof.challenge)
+                || proof.schnorr_responses_1.0.len() != 2
+                || !ProofSystem::check_disclosure(pk, &proof, &cred_show.disclosed)
+            {
+                failures.push(i);
+                continue;
+            }
+            proofs.push((i, proof));
+        }
+
+        let positions: Vec<usize> = (0..proofs.len()).collect();
+        let invalid = bisect_failures(&positions, &mut |subset: &[usize]| {
+            let mut batch = SchnorrBatch::<E>::new();
+            let mut signatures = Vec::with_capacity(subset.len());
+            for k in subset {
+                let proof = &proofs[*k].1;
+                let sig = &proof.randomized_sig;
+                // Ābar/d = A'^-e · h0^r2
+                batch.add_g1(SchnorrClaim {
+                    bases: vec![sig.A_prime, pk.h0],
+                    statement: (sig.A_bar + sig.d.into_group().neg()).into_affine(),
+                    commitment: proof.schnorr_commitment_1.commited_blindings,
+                    challenge: proof.challenge,
+                    responses: proof.schnorr_responses_1.0.clone(),
+                });
+                // g1 = d^r3 · h0^-s' · ∏ hi^-mi
+                let mut bases_2 = vec![sig.d, pk.h0];
+                bases_2.extend(pk.h1hL.iter().cloned());
+                batch.add_g1(SchnorrClaim {
+                    bases: bases_2,
+                    statement: pp.g1,
+                    commitment: proof.schnorr_commitment_2.commited_blindings,
+                    challenge: proof.challenge,
+                    responses: proof.schnorr_responses_2.0.clone(),
+                });
+                signatures.push(sig);
+            }
+            batch.check(rng)
+                && BBSPlus16RandomizedSignature::verify_pairing_batch(pvk, &signatures, rng)
+        });
+        failures.extend(invalid.iter().map(|k| proofs[*k].0));
+        failures.sort_unstable();
+        failures
+    }
+
+    /// Shows the credential with message `index` verifiably encrypted to an auditor under `label`
+    pub fn show_with_escrow<E: Pairing, R: Rng>(
+        pp: &PublicParams<E>,
+        pk: &PublicKey<E>,
+        signature: &BBSPlus16Signature<E>,
+        messages: &[E::ScalarField],
+        auditor_pk: &AuditorPublicKey<E::G1Affine>,
+        index: usize,
+        label: &[u8],
+        rng: &mut R,
+    ) -> Result<ShowCredential<E>, ProofError> {
+        let randomized_signature = signature.rerandomize(pp, pk, messages, rng);
+        let proof = ProofSystem::bbs_plus_16_prove_with_escrow(
+            pp,
+            &randomized_signature,
+            pk,
+            messages,
+            auditor_pk,
+            index,
+            label,
+            rng,
+        )?;
+
+        Ok(ShowCredential {
+            randomized_signature,
+            proof,
+            disclosed: Disclosure::default(),
+        })
+    }
+
+    /// Verifier checks an escrowed presentation for its session label
+    pub fn verify_with_escrow<E: Pairing>(
+        pp: &PublicParams<E>,
+        pk: &PublicKey<E>,
+        auditor_pk: &AuditorPublicKey<E::G1Affine>,
+        index: usize,
+        label: &[u8],
+        cred_show: &ShowCredential<E>,
+    ) -> Result<bool, ProofError> {
+        // the escrow proof does not cover a disclosure
+        if !cred_show.disclosed.attributes.is_empty() {
+            return Ok(false);
+        }
+        if !ProofSystem::bbs_plus_16_verify_proof_with_escrow(
+            pp,
+            pk,
+            auditor_pk,
+            index,
+            label,
+            &cred_show.proof,
+        )? {
+            return Ok(false);
+        }
+
+        Ok(cred_show.randomized_signature.verify_pairing(pp, pk))
+    }
+
+    /// `show_with_escrow` for the schema attribute `name`, `pp` must be generated for `schema`
+    pub fn show_with_escrow_by_name<E: Pairing, R: Rng>(
+        pp: &PublicParams<E>,
+        pk: &PublicKey<E>,
+        schema: &Schema,
+        signature: &BBSPlus16Signature<E>,
+        messages: &[E::ScalarField],
+        auditor_pk: &AuditorPublicKey<E::G1Affine>,
+        name: &str,
+        label: &[u8],
+        rng: &mut R,
+    ) -> Result<ShowCredential<E>, ProofError> {
+        schema.check_context(&pp.context)?;
+        let index = schema.index_of(name)?;
+        Self::show_with_escrow(pp, pk, signature, messages, auditor_pk, index, label, rng)
+    }
+
+    /// `verify_with_escrow` for the schema attribute `name`
+    pub fn verify_with_escrow_by_name<E: Pairing>(
+        pp: &PublicParams<E>,
+        pk: &PublicKey<E>,
+        schema: &Schema,
+        auditor_pk: &AuditorPublicKey<E::G1Affine>,
+        name: &str,
+        label: &[u8],
+        cred_show: &ShowCredential<E>,
+    ) -> Result<bool, ProofError> {
+        schema.check_context(&pp.context)?;
+        let index = schema.index_of(name)?;
+        Self::verify_with_escrow(pp, pk, auditor_pk, index, label, cred_show)
+    }
+}
+
+/// the presented randomized signature is the one the proof's challenge is bound to
+fn shows_proven_signature<E: Pairing>(
+    cred_show: &ShowCredential<E>,
+    proof: &BBSPlusProofOfKnowledge<E>,
+) -> bool {
+    cred_show.randomized_signature.A_prime == proof.randomized_sig.A_prime
+        && cred_show.randomized_signature.A_bar == proof.randomized_sig.A_bar
+        && cred_show.randomized_signature.d == proof.randomized_sig.d
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::proofsystem::EscrowedProofOfKnowledge;
     use crate::test_helpers::TestSetup;
-    use ark_bls12_381::Bls12_381;
+    use ark_ec::pairing::Pairing;
     use ark_std::test_rng;
+    use schnorr::batch::BatchError;
+    use schnorr::schnorr::{SchnorrCommitment, SchnorrResponses};
+    use schnorr::verifiable_encryption::VerifiableEncryption;
+    use utils::schema::{AttributeType, AttributeValue};
 
-    #[test]
-    fn test_obtain_issue_show_verify() {
+    /// A proof simulated for a random commitment: random responses and challenge,
+    /// T = Σ h_i·z_i − c·cm
+    fn simulate_commitment_proof<E: Pairing>(
+        bases: &[E::G1Affine],
+        rng: &mut impl Rng,
+    ) -> CommitmentWithProof<E> {
+        let commitment = E::G1Affine::rand(rng);
+        let responses: Vec<E::ScalarField> = (0..bases.len())
+            .map(|_| E::ScalarField::rand(rng))
+            .collect();
+        let challenge = E::ScalarField::rand(rng);
+        let commited_blindings =
+            (E::G1::msm_unchecked(bases, &responses) - commitment * challenge).into_affine();
+        let proof = (
+            SchnorrCommitment::<E::G1Affine> {
+                random_blindings: Vec::new(),
+                commited_blindings,
+            },
+            SchnorrResponses::<E::G1Affine>(responses),
+            challenge,
+        );
+        let mut serialized_proof = Vec::new();
+        proof.serialize_compressed(&mut serialized_proof).unwrap();
+        CommitmentWithProof {
+            commitment,
+            proof: serialized_proof,
+        }
+    }
+
+    fn test_obtain_issue_show_verify<E: Pairing>() {
         let mut rng = test_rng();
-        let setup = TestSetup::<Bls12_381>::new(&mut rng, 4);
+        let setup = TestSetup::<E>::new(&mut rng, 4);
 
         // 1. Obtain: User creates commitment and proof
         let (commitment_proof, s_prime) =
@@ -215,15 +791,532 @@ mod tests {
         // 3. Complete: User completes the signature
         let signature = AnonCredProtocol::complete_signature(&s_prime, &issuer_response);
 
-        // 4. Show: User shows the credential
-        let show_cred =
-            AnonCredProtocol::show(&setup.pp, &setup.pk, &signature, &setup.messages, &mut rng)
-                .expect("Failed to generate proof");
+        // 4. Show: User shows the credential to a verifier
+        let context = PresentationContext::new(b"verifier", b"nonce-1", 1_700_000_000);
+        let show_cred = AnonCredProtocol::show(
+            &setup.pp,
+            &setup.pk,
+            &signature,
+            &setup.messages,
+            &context,
+            &mut rng,
+        )
+        .expect("Failed to generate proof");
 
         // 5. Verify: Verifier checks the credential
-        let verification_result = AnonCredProtocol::verify(&setup.pp, &setup.pk, &show_cred)
-            .expect("Verification failed");
+        let verification_result =
+            AnonCredProtocol::verify(&setup.pp, &setup.pk, &show_cred, &context)
+                .expect("Verification failed");
 
         assert!(verification_result, "Proof verification failed");
+
+        // 6. Replay to another verifier fails
+        let other_context = PresentationContext::new(b"other verifier", b"nonce-1", 1_700_000_000);
+        assert!(
+            !AnonCredProtocol::verify(&setup.pp, &setup.pk, &show_cred, &other_context).unwrap()
+        );
+
+        // 7. The prepared key gives the same answers
+        let pvk = setup.pk.prepare(&setup.pp);
+        assert!(AnonCredProtocol::verify_prepared(&setup.pp, &pvk, &show_cred, &context).unwrap());
+        assert!(
+            !AnonCredProtocol::verify_prepared(&setup.pp, &pvk, &show_cred, &other_context)
+                .unwrap()
+        );
     }
+
+    fn test_obtain_issue_show_with_tables<E: Pairing>() {
+        let mut rng = test_rng();
+        let setup = TestSetup::<E>::new(&mut rng, 4);
+        let tables = setup.pk.precompute();
+
+        let (commitment_proof, s_prime) =
+            AnonCredProtocol::obtain_with_tables(&setup.pk, &tables, &setup.messages, &mut rng)
+                .expect("Failed to create commitment");
+        let issuer_response = AnonCredProtocol::issue_with_tables(
+            &setup.pp,
+            &setup.sk,
+            &setup.pk,
+            &tables,
+            &commitment_proof,
+            &mut rng,
+        )
+        .expect("Failed to issue credential");
+        let signature = AnonCredProtocol::complete_signature(&s_prime, &issuer_response);
+        assert!(signature.verify(&setup.pp, &setup.pk, &setup.messages));
+
+        let context = PresentationContext::new(b"verifier", b"nonce-1", 1_700_000_000);
+        let show_cred = AnonCredProtocol::show_with_tables(
+            &setup.pp,
+            &tables,
+            &signature,
+            &setup.messages,
+            &context,
+            &mut rng,
+        )
+        .expect("Failed to generate proof");
+        assert!(AnonCredProtocol::verify(&setup.pp, &setup.pk, &show_cred, &context).unwrap());
+    }
+
+    fn test_precompute_show<E: Pairing>() {
+        let mut rng = test_rng();
+        let setup = TestSetup::<E>::new(&mut rng, 4);
+
+        // tokens are made before any verifier context exists
+        let tokens: Vec<ShowToken<E>> = (0..2)
+            .map(|_| {
+                AnonCredProtocol::precompute_show(
+                    &setup.pp,
+                    &setup.pk,
+                    &setup.signature,
+                    &setup.messages,
+                    &mut rng,
+                )
+            })
+            .collect();
+
+        let contexts = [
+            PresentationContext::new(b"verifier", b"nonce-1", 0),
+            PresentationContext::new(b"verifier", b"nonce-2", 0),
+        ];
+        let presentations: Vec<ShowCredential<E>> = tokens
+            .into_iter()
+            .zip(contexts.iter())
+            .map(|(token, context)| AnonCredProtocol::finish_show(token, context, &[]).unwrap())
+            .collect();
+
+        for (presentation, context) in presentations.iter().zip(contexts.iter()) {
+            assert!(AnonCredProtocol::verify(&setup.pp, &setup.pk, presentation, context).unwrap());
+        }
+        assert!(
+            !AnonCredProtocol::verify(&setup.pp, &setup.pk, &presentations[0], &contexts[1])
+                .unwrap()
+        );
+        // each token randomizes the signature afresh
+        assert_ne!(
+            presentations[0].randomized_signature.A_prime,
+            presentations[1].randomized_signature.A_prime
+        );
+
+        // the messages to reveal are picked when the request arrives, not when the token is made
+        let mut token = || {
+            AnonCredProtocol::precompute_show(
+                &setup.pp,
+                &setup.pk,
+                &setup.signature,
+                &setup.messages,
+                &mut rng,
+            )
+        };
+        let (first, second, third) = (token(), token(), token());
+        let one = AnonCredProtocol::finish_show(first, &contexts[0], &[3]).unwrap();
+        let two = AnonCredProtocol::finish_show(second, &contexts[0], &[0, 2]).unwrap();
+        assert_eq!(one.disclosed.attributes, vec![(3, setup.messages[3])]);
+        assert_eq!(
+            two.disclosed.attributes,
+            vec![(0, setup.messages[0]), (2, setup.messages[2])]
+        );
+        assert!(AnonCredProtocol::verify(&setup.pp, &setup.pk, &one, &contexts[0]).unwrap());
+        assert!(AnonCredProtocol::verify(&setup.pp, &setup.pk, &two, &contexts[0]).unwrap());
+
+        let mut forged = two.clone();
+        forged.disclosed.attributes[0].1 += E::ScalarField::from(1u64);
+        assert!(!AnonCredProtocol::verify(&setup.pp, &setup.pk, &forged, &contexts[0]).unwrap());
+        let pvk = setup.pk.prepare(&setup.pp);
+        assert_eq!(
+            AnonCredProtocol::verify_batch(
+                &setup.pp,
+                &pvk,
+                &[(&forged, &contexts[0]), (&one, &contexts[0])],
+                &mut rng
+            ),
+            vec![0]
+        );
+
+        assert!(matches!(
+            AnonCredProtocol::finish_show(third, &contexts[0], &[4]),
+            Err(ProofError::DisclosureError(_))
+        ));
+    }
+
+    fn test_issue_batch<E: Pairing>() {
+        let mut rng = test_rng();
+        let setup = TestSetup::<E>::new(&mut rng, 3);
+        let other = TestSetup::<E>::new(&mut rng, 3);
+
+        let requests: Vec<_> = (0..4)
+            .map(|_| {
+                let messages: Vec<E::ScalarField> =
+                    (0..3).map(|_| E::ScalarField::rand(&mut rng)).collect();
+                let (proof, s_prime) =
+                    AnonCredProtocol::obtain(&setup.pp, &setup.pk, &messages, &mut rng).unwrap();
+                (messages, proof, s_prime)
+            })
+            .collect();
+        let mut proofs: Vec<CommitmentWithProof<E>> =
+            requests.iter().map(|(_, p, _)| p.clone()).collect();
+
+        let responses =
+            AnonCredProtocol::issue_batch(&setup.pp, &setup.sk, &setup.pk, &proofs, &mut rng)
+                .unwrap();
+        for ((messages, _, s_prime), response) in requests.iter().zip(responses.iter()) {
+            let signature = AnonCredProtocol::complete_signature(s_prime, response);
+            assert!(signature.verify(&setup.pp, &setup.pk, messages));
+        }
+
+        // a request made for another issuer is named and nothing is issued
+        let (forged, _) =
+            AnonCredProtocol::obtain(&other.pp, &other.pk, &requests[0].0, &mut rng).unwrap();
+        proofs[0] = forged;
+        assert!(matches!(
+            AnonCredProtocol::issue_batch(&setup.pp, &setup.sk, &setup.pk, &proofs, &mut rng),
+            Err(ProofError::BatchError(BatchError::InvalidProof(0)))
+        ));
+
+        // a forged (T, c) pair satisfies the Schnorr equation but not the recomputed challenge
+        let simulated = simulate_commitment_proof::<E>(&setup.pk.get_all_h(), &mut rng);
+        assert!(matches!(
+            AnonCredProtocol::issue(&setup.pp, &setup.sk, &setup.pk, &simulated, &mut rng),
+            Err(ProofError::VerificationFailed)
+        ));
+        proofs[0] = requests[0].1.clone();
+        proofs[2] = simulated;
+        assert!(matches!(
+            AnonCredProtocol::issue_batch(&setup.pp, &setup.sk, &setup.pk, &proofs, &mut rng),
+            Err(ProofError::BatchError(BatchError::InvalidProof(2)))
+        ));
+    }
+
+    fn test_verify_batch<E: Pairing>() {
+        let mut rng = test_rng();
+        let setup = TestSetup::<E>::new(&mut rng, 3);
+
+        let contexts: Vec<PresentationContext> = (0..6u64)
+            .map(|i| PresentationContext::new(b"verifier", &i.to_le_bytes(), 1_700_000_000))
+            .collect();
+        let mut presentations: Vec<ShowCredential<E>> = contexts
+            .iter()
+            .map(|context| {
+                let messages: Vec<E::ScalarField> =
+                    (0..3).map(|_| E::ScalarField::rand(&mut rng)).collect();
+                let (proof, s_prime) =
+                    AnonCredProtocol::obtain(&setup.pp, &setup.pk, &messages, &mut rng).unwrap();
+                let response =
+                    AnonCredProtocol::issue(&setup.pp, &setup.sk, &setup.pk, &proof, &mut rng)
+                        .unwrap();
+                let signature = AnonCredProtocol::complete_signature(&s_prime, &response);
+                AnonCredProtocol::show(
+                    &setup.pp, &setup.pk, &signature, &messages, context, &mut rng,
+                )
+                .unwrap()
+            })
+            .collect();
+
+        let pvk = setup.pk.prepare(&setup.pp);
+        let batch: Vec<_> = presentations.iter().zip(contexts.iter()).collect();
+        assert!(AnonCredProtocol::verify_batch(&setup.pp, &pvk, &batch, &mut rng).is_empty());
+
+        // presentation 1 is replayed to another context, presentation 3 presents another
+        // signature than it proves, presentation 4 carries a forged response
+        presentations[1] = presentations[0].clone();
+        presentations[3].randomized_signature = presentations[2].randomized_signature.clone();
+        let mut proof: BBSPlusProofOfKnowledge<E> =
+            CanonicalDeserialize::deserialize_compressed(&presentations[4].proof[..]).unwrap();
+        proof.schnorr_responses_2.0[0] += E::ScalarField::from(1u64);
+        presentations[4].proof.clear();
+        proof
+            .serialize_compressed(&mut presentations[4].proof)
+            .unwrap();
+
+        let batch: Vec<_> = presentations.iter().zip(contexts.iter()).collect();
+        assert_eq!(
+            AnonCredProtocol::verify_batch(&setup.pp, &pvk, &batch, &mut rng),
+            vec![1, 3, 4]
+        );
+        for (i, (presentation, context)) in batch.iter().enumerate() {
+            assert_eq!(
+                AnonCredProtocol::verify(&setup.pp, &setup.pk, presentation, context).unwrap(),
+                ![1, 3, 4].contains(&i)
+            );
+        }
+    }
+
+    fn test_show_verify_with_escrow<E: Pairing>() {
+        let mut rng = test_rng();
+        let setup = TestSetup::<E>::new(&mut rng, 4);
+        let (auditor_sk, auditor_pk) = VerifiableEncryption::keygen(&mut rng);
+        let index = 2;
+        let label = b"verifier session";
+
+        let show_cred = AnonCredProtocol::show_with_escrow(
+            &setup.pp,
+            &setup.pk,
+            &setup.signature,
+            &setup.messages,
+            &auditor_pk,
+            index,
+            label,
+            &mut rng,
+        )
+        .expect("Failed to generate escrowed proof");
+
+        assert!(AnonCredProtocol::verify_with_escrow(
+            &setup.pp,
+            &setup.pk,
+            &auditor_pk,
+            index,
+            label,
+            &show_cred
+        )
+        .unwrap());
+        assert!(!AnonCredProtocol::verify_with_escrow(
+            &setup.pp,
+            &setup.pk,
+            &auditor_pk,
+            index,
+            b"other session",
+            &show_cred
+        )
+        .unwrap());
+        assert!(!AnonCredProtocol::verify_with_escrow(
+            &setup.pp,
+            &setup.pk,
+            &auditor_pk,
+            0,
+            label,
+            &show_cred
+        )
+        .unwrap());
+
+        let escrowed: EscrowedProofOfKnowledge<E> =
+            CanonicalDeserialize::deserialize_compressed(&show_cred.proof[..]).unwrap();
+        let plaintext =
+            VerifiableEncryption::decrypt(&auditor_sk, &auditor_pk, &escrowed.ciphertext, label)
+                .unwrap();
+        assert_eq!(
+            VerifiableEncryption::resolve(&auditor_pk, &plaintext, &setup.messages),
+            Some(index)
+        );
+    }
+
+    fn test_obtain_issue_with_issuer_attributes<E: Pairing>() {
+        let mut rng = test_rng();
+        let setup = TestSetup::<E>::new(&mut rng, 4);
+
+        // issuer sets expiry at index 0 and credential type at index 2
+        let issuer_attributes = vec![
+            (0, E::ScalarField::from(1_800_000_000u64)),
+            (2, E::ScalarField::from(3u64)),
+        ];
+        let (commitment_proof, s_prime) =
+            AnonCredProtocol::obtain_partial(&setup.pk, &setup.messages, &[0, 2], &mut rng)
+                .unwrap();
+        let issuer_response = AnonCredProtocol::issue_partial(
+            &setup.pp,
+            &setup.sk,
+            &setup.pk,
+            &commitment_proof,
+            &issuer_attributes,
+            &mut rng,
+        )
+        .unwrap();
+        let (signature, messages) = AnonCredProtocol::complete_partial_signature(
+            &s_prime,
+            &issuer_response,
+            &setup.messages,
+            &issuer_attributes,
+        )
+        .unwrap();
+        assert_eq!(messages[2], E::ScalarField::from(3u64));
+        assert_eq!(messages[1], setup.messages[1]);
+
+        let context = PresentationContext::new(b"verifier", b"nonce-1", 1_700_000_000);
+        let show_cred = AnonCredProtocol::show(
+            &setup.pp, &setup.pk, &signature, &messages, &context, &mut rng,
+        )
+        .unwrap();
+        assert!(AnonCredProtocol::verify(&setup.pp, &setup.pk, &show_cred, &context).unwrap());
+
+        // the issuer rejects a commitment that already covers its indices
+        let (full_proof, _) =
+            AnonCredProtocol::obtain(&setup.pp, &setup.pk, &setup.messages, &mut rng).unwrap();
+        assert!(AnonCredProtocol::issue_partial(
+            &setup.pp,
+            &setup.sk,
+            &setup.pk,
+            &full_proof,
+            &issuer_attributes,
+            &mut rng,
+        )
+        .is_err());
+
+        // a proof answering a challenge of the requester's choosing is rejected
+        let forged = simulate_commitment_proof::<E>(&setup.pk.get_h_excluding(&[0, 2]), &mut rng);
+        assert!(AnonCredProtocol::issue_partial(
+            &setup.pp,
+            &setup.sk,
+            &setup.pk,
+            &forged,
+            &issuer_attributes,
+            &mut rng,
+        )
+        .is_err());
+
+        // a response naming an index twice or past the end is refused, not merged
+        let one = E::ScalarField::from(1u64);
+        for bad in [vec![(2, one), (2, one)], vec![(4, one)]] {
+            assert!(AnonCredProtocol::complete_partial_signature(
+                &s_prime,
+                &issuer_response,
+                &setup.messages,
+                &bad
+            )
+            .is_err());
+        }
+    }
+
+    fn test_reissue_with_updates<E: Pairing>() {
+        let mut rng = test_rng();
+        let setup = TestSetup::<E>::new(&mut rng, 4);
+        let (new_sk, new_pk) = crate::keygen::gen_keys(&setup.pp, &mut rng);
+
+        // bump the counter at index 1 and set a new expiry at index 3 under a rotated key
+        let updates = vec![
+            (1, AttributeUpdate::Add(E::ScalarField::from(1u64))),
+            (
+                3,
+                AttributeUpdate::Set(E::ScalarField::from(1_900_000_000u64)),
+            ),
+        ];
+        let context = PresentationContext::new(b"issuer", b"refresh-1", 1_700_000_000);
+        let (request, s_prime, messages) = AnonCredProtocol::obtain_reissue(
+            &setup.pp,
+            &setup.pk,
+            &new_pk,
+            &setup.signature,
+            &setup.messages,
+            &updates,
+            &context,
+            &mut rng,
+        )
+        .unwrap();
+        assert_eq!(messages[1], setup.messages[1] + E::ScalarField::from(1u64));
+
+        let issuer_response = AnonCredProtocol::reissue(
+            &setup.pp, &setup.pk, &setup.pp, &new_sk, &new_pk, &request, &updates, &context,
+            &mut rng,
+        )
+        .unwrap();
+        let signature = AnonCredProtocol::complete_signature(&s_prime, &issuer_response);
+
+        let show_context = PresentationContext::new(b"verifier", b"nonce-1", 1_700_000_000);
+        let show_cred = AnonCredProtocol::show(
+            &setup.pp,
+            &new_pk,
+            &signature,
+            &messages,
+            &show_context,
+            &mut rng,
+        )
+        .unwrap();
+        assert!(AnonCredProtocol::verify(&setup.pp, &new_pk, &show_cred, &show_context).unwrap());
+
+        // a different declared update or a replayed request is rejected
+        let other_updates = vec![(1, AttributeUpdate::Add(E::ScalarField::from(5u64)))];
+        assert!(AnonCredProtocol::reissue(
+            &setup.pp,
+            &setup.pk,
+            &setup.pp,
+            &new_sk,
+            &new_pk,
+            &request,
+            &other_updates,
+            &context,
+            &mut rng,
+        )
+        .is_err());
+        let replay_context = PresentationContext::new(b"issuer", b"refresh-2", 1_700_000_000);
+        assert!(AnonCredProtocol::reissue(
+            &setup.pp,
+            &setup.pk,
+            &setup.pp,
+            &new_sk,
+            &new_pk,
+            &request,
+            &updates,
+            &replay_context,
+            &mut rng,
+        )
+        .is_err());
+    }
+
+    fn test_show_verify_with_escrow_by_name<E: Pairing>() {
+        let mut rng = test_rng();
+        let schema = Schema::new(
+            "example.org/account/v1",
+            &[
+                ("holder", AttributeType::String),
+                ("counter", AttributeType::UInt),
+            ],
+        )
+        .unwrap();
+        let pp = PublicParams::<E>::new(&schema.len(), &schema.context(), &mut rng);
+        let (sk, pk) = crate::keygen::gen_keys(&pp, &mut rng);
+        let (_, auditor_pk) = VerifiableEncryption::keygen(&mut rng);
+        let messages: Vec<E::ScalarField> = schema
+            .encode(&[
+                ("holder", AttributeValue::String("alice".into())),
+                ("counter", AttributeValue::UInt(0)),
+            ])
+            .unwrap();
+        let signature = BBSPlus16Signature::sign(&pp, &sk, &pk, &mut rng, &messages);
+
+        let label = b"verifier session";
+        let show_cred = AnonCredProtocol::show_with_escrow_by_name(
+            &pp,
+            &pk,
+            &schema,
+            &signature,
+            &messages,
+            &auditor_pk,
+            "holder",
+            label,
+            &mut rng,
+        )
+        .unwrap();
+        assert!(AnonCredProtocol::verify_with_escrow_by_name(
+            &pp,
+            &pk,
+            &schema,
+            &auditor_pk,
+            "holder",
+            label,
+            &show_cred
+        )
+        .unwrap());
+        assert!(matches!(
+            AnonCredProtocol::verify_with_escrow_by_name(
+                &pp,
+                &pk,
+                &schema,
+                &auditor_pk,
+                "email",
+                label,
+                &show_cred
+            ),
+            Err(ProofError::SchemaError(_))
+        ));
+    }
+
+    utils::curve_tests!(
+        test_obtain_issue_show_verify,
+        test_obtain_issue_show_with_tables,
+        test_precompute_show,
+        test_issue_batch,
+        test_verify_batch,
+        test_show_verify_with_escrow,
+        test_obtain_issue_with_issuer_attributes,
+        test_reissue_with_updates,
+        test_show_verify_with_escrow_by_name
+    );
 }
@@ -1,17 +1,42 @@
 use crate::publicparams::PublicParams;
 use ark_ec::pairing::Pairing;
-use ark_ec::CurveGroup;
+use ark_ec::{AffineRepr, CurveGroup};
 use ark_ff::UniformRand;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::fmt;
 use ark_std::ops::Mul;
 use ark_std::rand::Rng;
+use schnorr::key_proof::{KeyProof, KeyProofError, KeyStatement};
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use utils::encoding::Base64Point;
+use utils::fixed_base::FixedBaseMsm;
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
+const KEY_PROOF_DOMAIN: &[u8] = b"bbs-plus-16-key";
+
+/// Issuer secret key, wiped on drop and left out of `Debug` output
+#[derive(CanonicalSerialize, CanonicalDeserialize, Zeroize, ZeroizeOnDrop)]
 pub struct SecretKey<E: Pairing> {
     pub x: E::ScalarField,
 }
+
+impl<E: Pairing> fmt::Debug for SecretKey<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecretKey").finish_non_exhaustive()
+    }
+}
+
+#[serde_as]
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize, Serialize, Deserialize)]
+#[serde(bound = "")]
 #[allow(non_snake_case)]
 pub struct PublicKey<E: Pairing> {
+    #[serde_as(as = "Base64Point")]
     pub w: E::G2Affine,
+    #[serde_as(as = "Base64Point")]
     pub h0: E::G1Affine,
+    #[serde_as(as = "Vec<Base64Point>")]
     pub h1hL: Vec<E::G1Affine>,
 }
 
@@ -31,33 +56,133 @@ pub fn gen_keys<E: Pairing>(
     (sk, pk)
 }
 
+/// `gen_keys` together with the proof holders check before `obtain`
+pub fn gen_keys_with_proof<E: Pairing>(
+    pp: &PublicParams<E>,
+    rng: &mut impl Rng,
+) -> (SecretKey<E>, PublicKey<E>, KeyProof<E>) {
+    let (sk, pk) = gen_keys(pp, rng);
+    let proof = pk.prove_key(pp, &sk, rng);
+    (sk, pk, proof)
+}
+
 impl<E: Pairing> PublicKey<E> {
     pub fn get_all_h(&self) -> Vec<E::G1Affine> {
         let mut all_h = vec![self.h0];
         all_h.extend(self.h1hL.iter().cloned());
         all_h
     }
+
+    /// w = g2^x, the h_i carry no exponent and are only checked to be proper bases
+    pub fn key_statement(&self, pp: &PublicParams<E>) -> KeyStatement<E> {
+        KeyStatement {
+            g1: pp.g1,
+            images_g1: Vec::new(),
+            g2: pp.g2,
+            images_g2: vec![self.w],
+        }
+    }
+
+    /// Proves the issuer knows x behind w
+    pub fn prove_key(
+        &self,
+        pp: &PublicParams<E>,
+        sk: &SecretKey<E>,
+        rng: &mut impl Rng,
+    ) -> KeyProof<E> {
+        KeyProof::prove(KEY_PROOF_DOMAIN, &self.key_statement(pp), &[sk.x], rng)
+    }
+
+    /// Checks a proof from `prove_key` and that no h_i is the identity, holders run this
+    /// before requesting a credential
+    pub fn verify_key(
+        &self,
+        pp: &PublicParams<E>,
+        proof: &KeyProof<E>,
+        rng: &mut impl Rng,
+    ) -> Result<(), KeyProofError> {
+        if self.h1hL.len() != pp.L {
+            return Err(KeyProofError::LengthMismatch(0));
+        }
+        if self.get_all_h().iter().any(|h| h.is_zero()) {
+            return Err(KeyProofError::IdentityElement(0));
+        }
+        proof.verify(KEY_PROOF_DOMAIN, &self.key_statement(pp), rng)
+    }
+
+    /// h_0 followed by the message bases, skipping the `excluded` message indices
+    pub fn get_h_excluding(&self, excluded: &[usize]) -> Vec<E::G1Affine> {
+        let mut h = vec![self.h0];
+        h.extend(
+            self.h1hL
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !excluded.contains(i))
+                .map(|(_, h_i)| *h_i),
+        );
+        h
+    }
+}
+
+/// Public key with the line coefficients of w and g2 computed once, for verifiers that check
+/// many signatures or presentations under the same key
+#[derive(Clone)]
+pub struct PreparedVerificationKey<E: Pairing> {
+    pub pk: PublicKey<E>,
+    pub w: E::G2Prepared,
+    pub g2: E::G2Prepared,
+}
+
+impl<E: Pairing> PublicKey<E> {
+    pub fn prepare(&self, pp: &PublicParams<E>) -> PreparedVerificationKey<E> {
+        PreparedVerificationKey {
+            pk: self.clone(),
+            w: self.w.into(),
+            g2: pp.g2.into(),
+        }
+    }
+}
+
+/// Fixed-base tables for h_0 and the message bases, built once with `PublicKey::precompute` for
+/// issuers and provers and serializable so a server can load them at startup
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PublicKeyTables<E: Pairing> {
+    /// [h_0] alone, for the blinding terms h_0^s
+    pub h0: FixedBaseMsm<E::G1>,
+    /// [h_0, h_1, ..., h_L] as returned by `get_all_h`
+    pub h: FixedBaseMsm<E::G1>,
+}
+
+impl<E: Pairing> PublicKey<E> {
+    pub fn precompute(&self) -> PublicKeyTables<E> {
+        PublicKeyTables {
+            h0: FixedBaseMsm::new(&[self.h0]),
+            h: FixedBaseMsm::new(&self.get_all_h()),
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ec::pairing::Pairing;
     use ark_std::test_rng;
     use ark_std::Zero;
 
-    #[test]
-    fn test_keygen() {
+    fn test_keygen<E: Pairing>() {
         #[allow(non_snake_case)]
         let L = 4;
         let mut rng = test_rng();
-        let context = Fr::rand(&mut rng);
-        let pp = PublicParams::<Bls12_381>::new(&L, &context, &mut rng);
+        let context = E::ScalarField::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&L, &context, &mut rng);
 
         let (sk, pk) = gen_keys(&pp, &mut rng);
 
         // Check that the secret key and public key are generated correctly
-        assert!(sk.x != Fr::zero(), "Secret key should not be zero");
+        assert!(
+            sk.x != E::ScalarField::zero(),
+            "Secret key should not be zero"
+        );
         assert!(pk.w != pp.g2, "Public key w should not be equal to g2");
         assert_eq!(
             pk.h1hL.len(),
@@ -65,4 +190,43 @@ mod test {
             "Public key hig1 should have L + 1 elements"
         );
     }
+
+    fn test_public_key_tables<E: Pairing>() {
+        let mut rng = test_rng();
+        let context = E::ScalarField::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&3, &context, &mut rng);
+        let (_, pk) = gen_keys(&pp, &mut rng);
+
+        let tables = pk.precompute();
+        assert_eq!(tables.h0.bases(), vec![pk.h0]);
+        assert_eq!(tables.h.bases(), pk.get_all_h());
+
+        let mut bytes = Vec::new();
+        tables.serialize_uncompressed(&mut bytes).unwrap();
+        let loaded = PublicKeyTables::<E>::deserialize_uncompressed_unchecked(&bytes[..]).unwrap();
+        assert_eq!(loaded.h, tables.h);
+    }
+
+    fn test_key_proof<E: Pairing>() {
+        let mut rng = test_rng();
+        let context = E::ScalarField::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&3, &context, &mut rng);
+        let (_, pk, proof) = gen_keys_with_proof(&pp, &mut rng);
+        assert!(pk.verify_key(&pp, &proof, &mut rng).is_ok());
+
+        let (_, other_pk) = gen_keys(&pp, &mut rng);
+        assert_eq!(
+            other_pk.verify_key(&pp, &proof, &mut rng),
+            Err(KeyProofError::InvalidProof(0))
+        );
+
+        let mut degenerate = pk.clone();
+        degenerate.h1hL[2] = E::G1Affine::zero();
+        assert_eq!(
+            degenerate.verify_key(&pp, &proof, &mut rng),
+            Err(KeyProofError::IdentityElement(0))
+        );
+    }
+
+    utils::curve_tests!(test_keygen, test_public_key_tables, test_key_proof);
 }
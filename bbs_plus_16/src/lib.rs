@@ -4,3 +4,4 @@ pub mod proofsystem;
 pub mod publicparams;
 pub mod signature;
 pub mod test_helpers;
+pub mod wire;
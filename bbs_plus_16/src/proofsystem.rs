@@ -1,4 +1,4 @@
-use crate::keygen::PublicKey;
+use crate::keygen::{PublicKey, PublicKeyTables};
 use crate::publicparams::PublicParams;
 use crate::signature::BBSPlus16RandomizedSignature;
 use ark_ec::pairing::Pairing;
@@ -7,8 +7,19 @@ use ark_ff::UniformRand;
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::ops::{Add, Neg};
 use ark_std::rand::Rng;
+use schnorr::batch::{BatchError, SchnorrBatch, SchnorrClaim};
+use schnorr::key_proof::KeyProofError;
 use schnorr::schnorr::{SchnorrCommitment, SchnorrProtocol, SchnorrResponses};
+use schnorr::verifiable_encryption::{
+    AuditorPublicKey, Ciphertext, EncryptionProof, VerifiableEncryption,
+};
 use thiserror::Error;
+use utils::context::PresentationContext;
+use utils::disclosure::{Disclosure, DisclosureError};
+use utils::hash::HashUtil;
+use utils::schema::SchemaError;
+use utils::update::{apply_updates, unlinked_indices, updates_to_bytes, AttributeUpdate};
+use zeroize::Zeroizing;
 
 #[derive(Error, Debug)]
 pub enum ProofError {
@@ -18,6 +29,16 @@ pub enum ProofError {
     InvalidProof,
     #[error("Verification failed")]
     VerificationFailed,
+    #[error("Invalid message index {0}")]
+    InvalidMessageIndex(usize),
+    #[error("Schema error: {0}")]
+    SchemaError(#[from] SchemaError),
+    #[error("Batch verification failed: {0}")]
+    BatchError(#[from] BatchError),
+    #[error("Issuer key is not well formed: {0}")]
+    KeyProofError(#[from] KeyProofError),
+    #[error("Disclosure error: {0}")]
+    DisclosureError(#[from] DisclosureError),
 }
 
 #[derive(CanonicalSerialize, CanonicalDeserialize)]
@@ -29,6 +50,24 @@ pub struct BBSPlusProofOfKnowledge<E: Pairing> {
     pub schnorr_responses_2: SchnorrResponses<E::G1Affine>,
     pub challenge: E::ScalarField,
 }
+/// Proof of knowledge with an escrow ciphertext of one hidden message
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct EscrowedProofOfKnowledge<E: Pairing> {
+    pub proof: BBSPlusProofOfKnowledge<E>,
+    pub ciphertext: Ciphertext<E::G1Affine>,
+    pub encryption_proof: EncryptionProof<E::G1Affine>,
+}
+
+/// Proof of knowledge of an old signature linked to a commitment to the updated messages under the
+/// new key. Linked messages reuse the old proof's responses for -m_i, negated.
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct ReissuanceProofOfKnowledge<E: Pairing> {
+    pub proof: BBSPlusProofOfKnowledge<E>,
+    pub commitment: E::G1Affine,
+    pub commitment_blindings: E::G1Affine,
+    pub s_response: E::ScalarField,
+}
+
 /// Pedersen commitment with proof of knowledge
 #[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
 pub struct CommitmentWithProof<E: Pairing> {
@@ -36,32 +75,78 @@ pub struct CommitmentWithProof<E: Pairing> {
     pub proof: Vec<u8>,
 }
 
+/// Both Schnorr commitments of the proof of knowledge. A second challenge over the same
+/// blindings would expose the messages, hence no `Clone`
+pub struct PreparedProof<E: Pairing> {
+    randomized_sig: BBSPlus16RandomizedSignature<E>,
+    schnorr_commitment_1: SchnorrCommitment<E::G1Affine>,
+    exponents_1: Zeroizing<Vec<E::ScalarField>>,
+    schnorr_commitment_2: SchnorrCommitment<E::G1Affine>,
+    exponents_2: Zeroizing<Vec<E::ScalarField>>,
+}
+
+impl<E: Pairing> PreparedProof<E> {
+    /// The randomized signature the proof is about
+    pub fn randomized_signature(&self) -> &BBSPlus16RandomizedSignature<E> {
+        &self.randomized_sig
+    }
+
+    /// Responds to the challenge over `context` and the disclosure. The messages enter the
+    /// proof negated, the returned disclosure holds them as signed
+    pub fn finish(
+        self,
+        context: &PresentationContext,
+        disclosed: &[usize],
+    ) -> Result<(Vec<u8>, Disclosure<E::ScalarField>), ProofError> {
+        // the second proof holds [r3, -s', -m_1, ..., -m_L]
+        let messages: Vec<E::ScalarField> = self.exponents_2[2..].iter().map(|m| -*m).collect();
+        let disclosure = Disclosure::new(
+            disclosed,
+            &messages,
+            &self.schnorr_commitment_2.random_blindings[2..],
+        )?;
+        let proof = ProofSystem::finish_proof(
+            &self.randomized_sig,
+            self.schnorr_commitment_1,
+            &self.exponents_1,
+            self.schnorr_commitment_2,
+            &self.exponents_2,
+            &disclosure,
+            context,
+        )?;
+        Ok((proof, disclosure))
+    }
+}
+
 pub struct ProofSystem;
 
 impl ProofSystem {
-    // Proves Knowledge of a BBS+ Signature
+    // Proves Knowledge of a BBS+ Signature, the challenge is bound to the verifier's context
     pub fn bbs_plus_16_prove<E: Pairing, R: Rng>(
         pp: &PublicParams<E>,
         randomized_sig: &BBSPlus16RandomizedSignature<E>,
         pk: &PublicKey<E>,
         messages: &[E::ScalarField],
+        context: &PresentationContext,
         rng: &mut R,
     ) -> Result<Vec<u8>, ProofError> {
+        let (proof, _) =
+            Self::prepare_proof(randomized_sig, pk, messages, rng).finish(context, &[])?;
+        Ok(proof)
+    }
+
+    /// The Schnorr commitments of `bbs_plus_16_prove`, which do not depend on the verifier.
+    /// `PreparedProof::finish` adds the challenge and responses once the context is known
+    pub fn prepare_proof<E: Pairing, R: Rng>(
+        randomized_sig: &BBSPlus16RandomizedSignature<E>,
+        pk: &PublicKey<E>,
+        messages: &[E::ScalarField],
+        rng: &mut R,
+    ) -> PreparedProof<E> {
         // 1. Prove knowledge of -e, r2 such that Ābar/d = A'^-e · h0^r2
         let bases_1 = vec![randomized_sig.A_prime, pk.h0];
         let exponents_1 = vec![randomized_sig.e.neg(), randomized_sig.r2];
-        let public_statement_1 = (randomized_sig
-            .A_bar
-            .add(randomized_sig.d.into_group().neg()))
-        .into_affine();
-        let challenge = E::ScalarField::rand(rng);
-
         let schnorr_commitment_1 = SchnorrProtocol::commit(&bases_1, rng);
-        let schnorr_responses_1 =
-            SchnorrProtocol::prove(&schnorr_commitment_1, &exponents_1, &challenge);
-        // assert!(proof_1_test, "proof 1 test isn't valid!");
-
-        let public_statement_2 = pp.g1;
 
         // 2. Prove g1 = d^r3 * h_0^{-s'} * \prod_{i}^L hi^-mi
         // 2.1 create exponents vector [r3, -s', -m_i, ..., -m_L]
@@ -73,10 +158,67 @@ impl ProofSystem {
         // 2.2 create bases vector [d, h_0, h_i, ...., h_L]
         let mut bases_2 = vec![randomized_sig.d, pk.h0];
         bases_2.extend(pk.h1hL.iter().cloned());
-
         let schnorr_commitment_2 = SchnorrProtocol::commit(&bases_2, rng);
+
+        PreparedProof {
+            randomized_sig: randomized_sig.clone(),
+            schnorr_commitment_1,
+            exponents_1: Zeroizing::new(exponents_1),
+            schnorr_commitment_2,
+            exponents_2: Zeroizing::new(exponents_2),
+        }
+    }
+
+    /// `bbs_plus_16_prove` with the h_0, ..., h_L parts of both Schnorr commitments taken from
+    /// the key's tables
+    pub fn bbs_plus_16_prove_with_tables<E: Pairing, R: Rng>(
+        randomized_sig: &BBSPlus16RandomizedSignature<E>,
+        tables: &PublicKeyTables<E>,
+        messages: &[E::ScalarField],
+        context: &PresentationContext,
+        rng: &mut R,
+    ) -> Result<Vec<u8>, ProofError> {
+        let exponents_1 = vec![randomized_sig.e.neg(), randomized_sig.r2];
+        let schnorr_commitment_1 =
+            SchnorrProtocol::commit_with_table_prefix(&[randomized_sig.A_prime], &tables.h0, rng);
+
+        let mut exponents_2 = vec![randomized_sig.r3, -randomized_sig.s_prime];
+        exponents_2.extend(messages.iter().map(|m| -*m));
+        let schnorr_commitment_2 =
+            SchnorrProtocol::commit_with_table_prefix(&[randomized_sig.d], &tables.h, rng);
+
+        Self::finish_proof(
+            randomized_sig,
+            schnorr_commitment_1,
+            &exponents_1,
+            schnorr_commitment_2,
+            &exponents_2,
+            &Disclosure::default(),
+            context,
+        )
+    }
+
+    fn finish_proof<E: Pairing>(
+        randomized_sig: &BBSPlus16RandomizedSignature<E>,
+        mut schnorr_commitment_1: SchnorrCommitment<E::G1Affine>,
+        exponents_1: &[E::ScalarField],
+        mut schnorr_commitment_2: SchnorrCommitment<E::G1Affine>,
+        exponents_2: &[E::ScalarField],
+        disclosure: &Disclosure<E::ScalarField>,
+        context: &PresentationContext,
+    ) -> Result<Vec<u8>, ProofError> {
+        let challenge = Self::context_challenge(
+            randomized_sig,
+            &schnorr_commitment_1,
+            &schnorr_commitment_2,
+            disclosure,
+            context,
+        )?;
+
+        let schnorr_responses_1 =
+            SchnorrProtocol::prove_and_erase(&mut schnorr_commitment_1, exponents_1, &challenge);
         let schnorr_responses_2 =
-            SchnorrProtocol::prove(&schnorr_commitment_2, &exponents_2, &challenge);
+            SchnorrProtocol::prove_and_erase(&mut schnorr_commitment_2, exponents_2, &challenge);
 
         let proof = BBSPlusProofOfKnowledge {
             randomized_sig: randomized_sig.clone(),
@@ -92,16 +234,77 @@ impl ProofSystem {
         Ok(serialized_proof)
     }
 
-    // Verifies knowledge of a BBS+ Signature Proof
+    // Verifies knowledge of a BBS+ Signature Proof made for the verifier's context that reveals
+    // `disclosure`
     pub fn bbs_plus_16_verify_proof<E: Pairing>(
         pp: &PublicParams<E>,
         pk: &PublicKey<E>,
         serialized_proof: &[u8],
+        disclosure: &Disclosure<E::ScalarField>,
+        context: &PresentationContext,
     ) -> Result<bool, ProofError> {
         // Deserialize the proof
         let proof: BBSPlusProofOfKnowledge<E> =
             CanonicalDeserialize::deserialize_compressed(serialized_proof)?;
 
+        // 1. Recompute the challenge, a proof for another context is rejected
+        let challenge = Self::context_challenge(
+            &proof.randomized_sig,
+            &proof.schnorr_commitment_1,
+            &proof.schnorr_commitment_2,
+            disclosure,
+            context,
+        )?;
+        if challenge != proof.challenge || !Self::check_disclosure(pk, &proof, disclosure) {
+            return Ok(false);
+        }
+
+        Ok(Self::verify_proof_equations(pp, pk, &proof))
+    }
+
+    /// the second proof carries -m_i after r3 and -s', so a disclosed message's response must be
+    /// ρ - c·m
+    pub(crate) fn check_disclosure<E: Pairing>(
+        pk: &PublicKey<E>,
+        proof: &BBSPlusProofOfKnowledge<E>,
+        disclosure: &Disclosure<E::ScalarField>,
+    ) -> bool {
+        let responses = &proof.schnorr_responses_2.0;
+        responses.len() == pk.h1hL.len() + 2
+            && disclosure
+                .negated()
+                .verify(&responses[2..], &proof.challenge)
+    }
+
+    pub(crate) fn context_challenge<E: Pairing>(
+        randomized_sig: &BBSPlus16RandomizedSignature<E>,
+        schnorr_commitment_1: &SchnorrCommitment<E::G1Affine>,
+        schnorr_commitment_2: &SchnorrCommitment<E::G1Affine>,
+        disclosure: &Disclosure<E::ScalarField>,
+        context: &PresentationContext,
+    ) -> Result<E::ScalarField, ProofError> {
+        let mut transcript = Vec::new();
+        randomized_sig
+            .A_prime
+            .serialize_compressed(&mut transcript)?;
+        randomized_sig.A_bar.serialize_compressed(&mut transcript)?;
+        randomized_sig.d.serialize_compressed(&mut transcript)?;
+        schnorr_commitment_1
+            .commited_blindings
+            .serialize_compressed(&mut transcript)?;
+        schnorr_commitment_2
+            .commited_blindings
+            .serialize_compressed(&mut transcript)?;
+        disclosure.serialize_compressed(&mut transcript)?;
+        Ok(context.challenge(b"bbs-plus-16-show", &transcript))
+    }
+
+    /// checks both Schnorr proofs for the challenge the proof carries
+    fn verify_proof_equations<E: Pairing>(
+        pp: &PublicParams<E>,
+        pk: &PublicKey<E>,
+        proof: &BBSPlusProofOfKnowledge<E>,
+    ) -> bool {
         // 2. Verify the first Schnorr proof: Ābar/d = A'^-e · h0^r2
         // Verifier reconstructs
         let bases_1 = vec![proof.randomized_sig.A_prime, pk.h0];
@@ -117,7 +320,7 @@ impl ProofSystem {
         );
 
         if !is_proof_1_valid {
-            return Ok(false);
+            return false;
         }
 
         // 3. Verify the second Schnorr proof: g1= d^r3 * h_0^{-s'} * \prod_{i}^L hi^-mi
@@ -126,19 +329,334 @@ impl ProofSystem {
         let mut bases_2 = vec![proof.randomized_sig.d, pk.h0];
         bases_2.extend(pk.h1hL.iter().cloned());
 
-        let is_proof_2_valid = SchnorrProtocol::verify(
+        SchnorrProtocol::verify(
             &bases_2,
             &public_statement_2,
             &proof.schnorr_commitment_2,
             &proof.schnorr_responses_2,
             &proof.challenge,
+        )
+    }
+
+    /// Proves knowledge of a BBS+ signature and verifiably encrypts message `index` to an auditor.
+    /// The second proof carries -m_i, so the encryption blinding is the negated Schnorr blinding.
+    pub fn bbs_plus_16_prove_with_escrow<E: Pairing, R: Rng>(
+        pp: &PublicParams<E>,
+        randomized_sig: &BBSPlus16RandomizedSignature<E>,
+        pk: &PublicKey<E>,
+        messages: &[E::ScalarField],
+        auditor_pk: &AuditorPublicKey<E::G1Affine>,
+        index: usize,
+        label: &[u8],
+        rng: &mut R,
+    ) -> Result<Vec<u8>, ProofError> {
+        if index >= messages.len() {
+            return Err(ProofError::InvalidMessageIndex(index));
+        }
+        let bases_1 = vec![randomized_sig.A_prime, pk.h0];
+        let exponents_1 = vec![randomized_sig.e.neg(), randomized_sig.r2];
+        let mut schnorr_commitment_1 = SchnorrProtocol::commit(&bases_1, rng);
+
+        let mut exponents_2 = vec![randomized_sig.r3, -randomized_sig.s_prime];
+        exponents_2.extend(messages.iter().map(|m| -*m));
+        let mut bases_2 = vec![randomized_sig.d, pk.h0];
+        bases_2.extend(pk.h1hL.iter().cloned());
+        let mut schnorr_commitment_2 = SchnorrProtocol::commit(&bases_2, rng);
+
+        let (ciphertext, enc_r) =
+            VerifiableEncryption::encrypt(auditor_pk, &messages[index], label, rng);
+        let enc_commitment = VerifiableEncryption::commit(
+            auditor_pk,
+            &schnorr_commitment_2.random_blindings[index + 2].neg(),
+            rng,
         );
 
-        if !is_proof_2_valid {
+        let challenge = Self::escrow_challenge(
+            randomized_sig,
+            &schnorr_commitment_1,
+            &schnorr_commitment_2,
+            &ciphertext,
+            label,
+            &[enc_commitment.a1, enc_commitment.a2, enc_commitment.a3],
+        )?;
+
+        let schnorr_responses_1 =
+            SchnorrProtocol::prove_and_erase(&mut schnorr_commitment_1, &exponents_1, &challenge);
+        let schnorr_responses_2 =
+            SchnorrProtocol::prove_and_erase(&mut schnorr_commitment_2, &exponents_2, &challenge);
+        let encryption_proof =
+            VerifiableEncryption::prove(&enc_commitment, &enc_r, &messages[index], &challenge);
+
+        let proof = EscrowedProofOfKnowledge {
+            proof: BBSPlusProofOfKnowledge {
+                randomized_sig: randomized_sig.clone(),
+                schnorr_commitment_1,
+                schnorr_responses_1,
+                schnorr_commitment_2,
+                schnorr_responses_2,
+                challenge,
+            },
+            ciphertext,
+            encryption_proof,
+        };
+
+        let mut serialized_proof = Vec::new();
+        proof.serialize_compressed(&mut serialized_proof)?;
+        Ok(serialized_proof)
+    }
+
+    /// Verifies an escrowed proof of knowledge for the verifier's session label
+    pub fn bbs_plus_16_verify_proof_with_escrow<E: Pairing>(
+        pp: &PublicParams<E>,
+        pk: &PublicKey<E>,
+        auditor_pk: &AuditorPublicKey<E::G1Affine>,
+        index: usize,
+        label: &[u8],
+        serialized_proof: &[u8],
+    ) -> Result<bool, ProofError> {
+        let escrowed: EscrowedProofOfKnowledge<E> =
+            CanonicalDeserialize::deserialize_compressed(serialized_proof)?;
+        let proof = &escrowed.proof;
+        let response_index = index + 2;
+        if index >= pk.h1hL.len() || proof.schnorr_responses_2.0.len() != pk.h1hL.len() + 2 {
+            return Err(ProofError::InvalidMessageIndex(index));
+        }
+
+        let challenge = Self::escrow_challenge(
+            &proof.randomized_sig,
+            &proof.schnorr_commitment_1,
+            &proof.schnorr_commitment_2,
+            &escrowed.ciphertext,
+            label,
+            &[
+                escrowed.encryption_proof.a1,
+                escrowed.encryption_proof.a2,
+                escrowed.encryption_proof.a3,
+            ],
+        )?;
+        if challenge != proof.challenge {
+            return Ok(false);
+        }
+
+        // response for -m_i is the negated encryption response for m_i
+        if escrowed.encryption_proof.z_m != proof.schnorr_responses_2.0[response_index].neg() {
             return Ok(false);
         }
 
-        Ok(true)
+        if !VerifiableEncryption::verify(
+            auditor_pk,
+            &escrowed.ciphertext,
+            label,
+            &escrowed.encryption_proof,
+            &challenge,
+        ) {
+            return Ok(false);
+        }
+
+        Ok(Self::verify_proof_equations(pp, pk, proof))
+    }
+
+    fn escrow_challenge<E: Pairing>(
+        randomized_sig: &BBSPlus16RandomizedSignature<E>,
+        schnorr_commitment_1: &SchnorrCommitment<E::G1Affine>,
+        schnorr_commitment_2: &SchnorrCommitment<E::G1Affine>,
+        ciphertext: &Ciphertext<E::G1Affine>,
+        label: &[u8],
+        encryption_commitments: &[E::G1Affine; 3],
+    ) -> Result<E::ScalarField, ProofError> {
+        let mut transcript = Vec::new();
+        randomized_sig
+            .A_prime
+            .serialize_compressed(&mut transcript)?;
+        randomized_sig.A_bar.serialize_compressed(&mut transcript)?;
+        randomized_sig.d.serialize_compressed(&mut transcript)?;
+        schnorr_commitment_1
+            .commited_blindings
+            .serialize_compressed(&mut transcript)?;
+        schnorr_commitment_2
+            .commited_blindings
+            .serialize_compressed(&mut transcript)?;
+        let [a1, a2, a3] = encryption_commitments;
+        VerifiableEncryption::append_to_transcript(&mut transcript, ciphertext, label, a1, a2, a3);
+        Ok(HashUtil::<E::ScalarField, E::G1Affine>::hash_to_field(
+            &transcript,
+        ))
+    }
+
+    /// Proves knowledge of a signature on `messages` under the old key and that the new commitment
+    /// h_0^s' ∏ h_i^m'_i under `pk` holds the same messages except for `updates`
+    pub fn bbs_plus_16_prove_reissuance<E: Pairing, R: Rng>(
+        old_pk: &PublicKey<E>,
+        randomized_sig: &BBSPlus16RandomizedSignature<E>,
+        pk: &PublicKey<E>,
+        messages: &[E::ScalarField],
+        updates: &[(usize, AttributeUpdate<E::ScalarField>)],
+        s_prime: &E::ScalarField,
+        context: &PresentationContext,
+        rng: &mut R,
+    ) -> Result<Vec<u8>, ProofError> {
+        let n = messages.len();
+        if old_pk.h1hL.len() != n || pk.h1hL.len() != n {
+            return Err(ProofError::VerificationFailed);
+        }
+        let indices: Vec<usize> = updates.iter().map(|(i, _)| *i).collect();
+        check_message_indices(n, &indices)?;
+
+        let bases_1 = vec![randomized_sig.A_prime, old_pk.h0];
+        let exponents_1 = vec![randomized_sig.e.neg(), randomized_sig.r2];
+        let mut schnorr_commitment_1 = SchnorrProtocol::commit(&bases_1, rng);
+
+        let mut exponents_2 = vec![randomized_sig.r3, -randomized_sig.s_prime];
+        exponents_2.extend(messages.iter().map(|m| -*m));
+        let mut bases_2 = vec![randomized_sig.d, old_pk.h0];
+        bases_2.extend(old_pk.h1hL.iter().cloned());
+        let mut schnorr_commitment_2 = SchnorrProtocol::commit(&bases_2, rng);
+
+        let mut new_exponents = vec![*s_prime];
+        new_exponents.extend(apply_updates(messages, updates));
+        let commitment = E::G1::msm_unchecked(&pk.get_all_h(), &new_exponents).into_affine();
+
+        // the second proof carries -m_i, so a linked m_i gets the negated blinding
+        let unlinked = unlinked_indices(updates);
+        let s_blinding = E::ScalarField::rand(rng);
+        let mut blindings = vec![s_blinding];
+        blindings.extend(
+            schnorr_commitment_2.random_blindings[2..]
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !unlinked.contains(i))
+                .map(|(_, b)| b.neg()),
+        );
+        let commitment_blindings = SchnorrProtocol::commit_with_prepared_blindings(
+            &pk.get_h_excluding(&unlinked),
+            &blindings,
+        )
+        .commited_blindings;
+
+        let challenge = Self::reissuance_challenge(
+            randomized_sig,
+            &schnorr_commitment_1,
+            &schnorr_commitment_2,
+            &commitment,
+            &commitment_blindings,
+            updates,
+            context,
+        )?;
+
+        let schnorr_responses_1 =
+            SchnorrProtocol::prove_and_erase(&mut schnorr_commitment_1, &exponents_1, &challenge);
+        let schnorr_responses_2 =
+            SchnorrProtocol::prove_and_erase(&mut schnorr_commitment_2, &exponents_2, &challenge);
+
+        let proof = ReissuanceProofOfKnowledge {
+            proof: BBSPlusProofOfKnowledge {
+                randomized_sig: randomized_sig.clone(),
+                schnorr_commitment_1,
+                schnorr_responses_1,
+                schnorr_commitment_2,
+                schnorr_responses_2,
+                challenge,
+            },
+            commitment,
+            commitment_blindings,
+            s_response: s_blinding + challenge * s_prime,
+        };
+
+        let mut serialized_proof = Vec::new();
+        proof.serialize_compressed(&mut serialized_proof)?;
+        Ok(serialized_proof)
+    }
+
+    /// Verifies a re-issuance proof for the declared `updates` and the issuer's `context`,
+    /// returns the new commitment to sign if it is valid
+    pub fn bbs_plus_16_verify_reissuance<E: Pairing>(
+        old_pp: &PublicParams<E>,
+        old_pk: &PublicKey<E>,
+        pk: &PublicKey<E>,
+        updates: &[(usize, AttributeUpdate<E::ScalarField>)],
+        context: &PresentationContext,
+        serialized_proof: &[u8],
+    ) -> Result<Option<E::G1Affine>, ProofError> {
+        let n = pk.h1hL.len();
+        if old_pk.h1hL.len() != n {
+            return Err(ProofError::VerificationFailed);
+        }
+        let indices: Vec<usize> = updates.iter().map(|(i, _)| *i).collect();
+        check_message_indices(n, &indices)?;
+        let reissuance: ReissuanceProofOfKnowledge<E> =
+            CanonicalDeserialize::deserialize_compressed(serialized_proof)?;
+        let proof = &reissuance.proof;
+        if proof.schnorr_responses_2.0.len() != n + 2 {
+            return Ok(None);
+        }
+
+        let challenge = Self::reissuance_challenge(
+            &proof.randomized_sig,
+            &proof.schnorr_commitment_1,
+            &proof.schnorr_commitment_2,
+            &reissuance.commitment,
+            &reissuance.commitment_blindings,
+            updates,
+            context,
+        )?;
+        if challenge != proof.challenge || !Self::verify_proof_equations(old_pp, old_pk, proof) {
+            return Ok(None);
+        }
+
+        // cm'' = cm' · ∏ h_k^-v_k commits to the old linked messages and to zero at Set indices
+        let update_bases: Vec<E::G1Affine> = indices.iter().map(|i| pk.h1hL[*i]).collect();
+        let update_values: Vec<E::ScalarField> =
+            updates.iter().map(|(_, u)| u.public_value()).collect();
+        let statement = (reissuance.commitment.into_group()
+            - E::G1::msm_unchecked(&update_bases, &update_values))
+        .into_affine();
+
+        let unlinked = unlinked_indices(updates);
+        let mut responses = vec![reissuance.s_response];
+        responses.extend(
+            proof.schnorr_responses_2.0[2..]
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !unlinked.contains(i))
+                .map(|(_, z)| z.neg()),
+        );
+
+        let is_valid = SchnorrProtocol::verify_schnorr(
+            &pk.get_h_excluding(&unlinked),
+            &statement,
+            &reissuance.commitment_blindings,
+            &responses,
+            &challenge,
+        );
+
+        Ok(is_valid.then_some(reissuance.commitment))
+    }
+
+    fn reissuance_challenge<E: Pairing>(
+        randomized_sig: &BBSPlus16RandomizedSignature<E>,
+        schnorr_commitment_1: &SchnorrCommitment<E::G1Affine>,
+        schnorr_commitment_2: &SchnorrCommitment<E::G1Affine>,
+        commitment: &E::G1Affine,
+        commitment_blindings: &E::G1Affine,
+        updates: &[(usize, AttributeUpdate<E::ScalarField>)],
+        context: &PresentationContext,
+    ) -> Result<E::ScalarField, ProofError> {
+        let mut transcript = Vec::new();
+        randomized_sig
+            .A_prime
+            .serialize_compressed(&mut transcript)?;
+        randomized_sig.A_bar.serialize_compressed(&mut transcript)?;
+        randomized_sig.d.serialize_compressed(&mut transcript)?;
+        schnorr_commitment_1
+            .commited_blindings
+            .serialize_compressed(&mut transcript)?;
+        schnorr_commitment_2
+            .commited_blindings
+            .serialize_compressed(&mut transcript)?;
+        commitment.serialize_compressed(&mut transcript)?;
+        commitment_blindings.serialize_compressed(&mut transcript)?;
+        transcript.extend_from_slice(&updates_to_bytes(updates));
+        Ok(context.challenge(b"bbs-plus-16-reissue", &transcript))
     }
 
     // pub fn commitment_prove<E: Pairing, R: Rng>()
@@ -160,12 +678,42 @@ impl ProofSystem {
 
         // cm = h_0^s' h_1^m_1 ... h_L^m_L
         let commitment: E::G1 = E::G1::msm(&bases, &exponents).unwrap();
-        let challenge = E::ScalarField::rand(rng);
         let schnorr_commitment = SchnorrProtocol::commit(&bases, rng);
-        let schnorr_responses = SchnorrProtocol::prove(&schnorr_commitment, &exponents, &challenge);
+        Self::finish_commitment_proof(&bases, commitment, schnorr_commitment, &exponents)
+    }
+
+    /// `create_commitment_proof` with the commitment and Schnorr commitment taken from the
+    /// key's tables
+    pub fn create_commitment_proof_with_tables<E: Pairing, R: Rng>(
+        pk: &PublicKey<E>,
+        tables: &PublicKeyTables<E>,
+        messages: &[E::ScalarField],
+        s_prime: &E::ScalarField,
+        rng: &mut R,
+    ) -> Result<CommitmentWithProof<E>, ProofError> {
+        assert_eq!(messages.len(), pk.h1hL.len(), "Invalid number of messages");
+        let mut exponents = vec![*s_prime];
+        exponents.extend(messages.iter().cloned());
+
+        let commitment = tables.h.msm(&exponents);
+        let schnorr_commitment = SchnorrProtocol::commit_with_table(&tables.h, rng);
+        Self::finish_commitment_proof(&pk.get_all_h(), commitment, schnorr_commitment, &exponents)
+    }
+
+    fn finish_commitment_proof<E: Pairing>(
+        bases: &[E::G1Affine],
+        commitment: E::G1,
+        mut schnorr_commitment: SchnorrCommitment<E::G1Affine>,
+        exponents: &[E::ScalarField],
+    ) -> Result<CommitmentWithProof<E>, ProofError> {
+        let commitment = commitment.into_affine();
+        let challenge =
+            commitment_challenge::<E>(bases, &commitment, &schnorr_commitment.commited_blindings)?;
+        let schnorr_responses =
+            SchnorrProtocol::prove_and_erase(&mut schnorr_commitment, exponents, &challenge);
         let is_valid = SchnorrProtocol::verify(
-            &bases,
-            &commitment.into_affine(),
+            bases,
+            &commitment,
             &schnorr_commitment,
             &schnorr_responses,
             &challenge,
@@ -178,11 +726,86 @@ impl ProofSystem {
         proof.serialize_compressed(&mut serialized_proof)?;
 
         Ok(CommitmentWithProof {
-            commitment: commitment.into_affine(),
+            commitment,
             proof: serialized_proof,
         })
     }
 
+    /// Creates a Pedersen commitment to the user-held messages and a proof of knowledge,
+    /// the messages at `issuer_indices` are left for the issuer to set
+    pub fn create_partial_commitment_proof<E: Pairing, R: Rng>(
+        pk: &PublicKey<E>,
+        messages: &[E::ScalarField],
+        issuer_indices: &[usize],
+        s_prime: &E::ScalarField,
+        rng: &mut R,
+    ) -> Result<CommitmentWithProof<E>, ProofError> {
+        assert_eq!(messages.len(), pk.h1hL.len(), "Invalid number of messages");
+        check_message_indices(messages.len(), issuer_indices)?;
+        let mut exponents = vec![*s_prime];
+        exponents.extend(
+            messages
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !issuer_indices.contains(i))
+                .map(|(_, m)| *m),
+        );
+
+        let bases = pk.get_h_excluding(issuer_indices);
+
+        // cm = h_0^s' ∏ h_i^m_i over the user-held messages
+        let commitment = E::G1::msm_unchecked(&bases, &exponents).into_affine();
+        let mut schnorr_commitment = SchnorrProtocol::commit(&bases, rng);
+        let challenge =
+            commitment_challenge::<E>(&bases, &commitment, &schnorr_commitment.commited_blindings)?;
+        let schnorr_responses =
+            SchnorrProtocol::prove_and_erase(&mut schnorr_commitment, &exponents, &challenge);
+
+        let proof = (schnorr_commitment, schnorr_responses, challenge);
+        let mut serialized_proof = Vec::new();
+        proof.serialize_compressed(&mut serialized_proof)?;
+
+        Ok(CommitmentWithProof {
+            commitment,
+            proof: serialized_proof,
+        })
+    }
+
+    /// Verifies a partial commitment proof against the bases that skip `issuer_indices`
+    pub fn verify_partial_commitment_proof<E: Pairing>(
+        pk: &PublicKey<E>,
+        issuer_indices: &[usize],
+        commitment_proof: &CommitmentWithProof<E>,
+    ) -> Result<bool, ProofError> {
+        check_message_indices(pk.h1hL.len(), issuer_indices)?;
+        let (schnorr_commitment, schnorr_responses, challenge): (
+            SchnorrCommitment<E::G1Affine>,
+            SchnorrResponses<E::G1Affine>,
+            E::ScalarField,
+        ) = CanonicalDeserialize::deserialize_compressed(&commitment_proof.proof[..])?;
+
+        let bases = pk.get_h_excluding(issuer_indices);
+        if schnorr_responses.0.len() != bases.len() {
+            return Ok(false);
+        }
+        let expected = commitment_challenge::<E>(
+            &bases,
+            &commitment_proof.commitment,
+            &schnorr_commitment.commited_blindings,
+        )?;
+        if challenge != expected {
+            return Ok(false);
+        }
+
+        Ok(SchnorrProtocol::verify(
+            &bases,
+            &commitment_proof.commitment,
+            &schnorr_commitment,
+            &schnorr_responses,
+            &challenge,
+        ))
+    }
+
     /// Verifies a Pedersen commitment proof
     pub fn verify_commitment_proof<E: Pairing>(
         pp: &PublicParams<E>,
@@ -198,6 +821,19 @@ impl ProofSystem {
 
         // Setup for verification
         let bases = pk.get_all_h();
+        if schnorr_responses.0.len() != bases.len() {
+            return Ok(false);
+        }
+
+        // Recompute the challenge, a prover choosing it could answer for any commitment
+        let expected = commitment_challenge::<E>(
+            &bases,
+            &commitment_proof.commitment,
+            &schnorr_commitment.commited_blindings,
+        )?;
+        if challenge != expected {
+            return Ok(false);
+        }
 
         // Verify the proof
         let is_valid = SchnorrProtocol::verify(
@@ -210,19 +846,80 @@ impl ProofSystem {
 
         Ok(is_valid)
     }
+
+    /// Verifies many Pedersen commitment proofs together, for bulk issuance. On failure the
+    /// error names the first invalid proof.
+    pub fn verify_commitment_proofs_batch<E: Pairing, R: Rng>(
+        pk: &PublicKey<E>,
+        commitment_proofs: &[CommitmentWithProof<E>],
+        rng: &mut R,
+    ) -> Result<(), ProofError> {
+        let bases = pk.get_all_h();
+        let mut batch = SchnorrBatch::<E>::new();
+        for (i, commitment_proof) in commitment_proofs.iter().enumerate() {
+            let (schnorr_commitment, schnorr_responses, challenge): (
+                SchnorrCommitment<E::G1Affine>,
+                SchnorrResponses<E::G1Affine>,
+                E::ScalarField,
+            ) = CanonicalDeserialize::deserialize_compressed(&commitment_proof.proof[..])?;
+            let expected = commitment_challenge::<E>(
+                &bases,
+                &commitment_proof.commitment,
+                &schnorr_commitment.commited_blindings,
+            )?;
+            if challenge != expected {
+                return Err(BatchError::InvalidProof(i).into());
+            }
+
+            batch.add_g1(SchnorrClaim {
+                bases: bases.clone(),
+                statement: commitment_proof.commitment,
+                commitment: schnorr_commitment.commited_blindings,
+                challenge,
+                responses: schnorr_responses.0,
+            });
+        }
+        batch.verify(rng)?;
+
+        Ok(())
+    }
+}
+
+/// Fiat-Shamir challenge of a commitment proof, over the issuer's bases, the commitment and T
+fn commitment_challenge<E: Pairing>(
+    bases: &[E::G1Affine],
+    commitment: &E::G1Affine,
+    schnorr_commitment: &E::G1Affine,
+) -> Result<E::ScalarField, ProofError> {
+    let mut transcript = b"bbs-plus-16-commitment-proof".to_vec();
+    bases.serialize_compressed(&mut transcript)?;
+    commitment.serialize_compressed(&mut transcript)?;
+    schnorr_commitment.serialize_compressed(&mut transcript)?;
+    Ok(HashUtil::<E::ScalarField, E::G1Affine>::hash_to_field(
+        &transcript,
+    ))
+}
+
+/// issuer message indices must be in range and distinct
+pub fn check_message_indices(n: usize, indices: &[usize]) -> Result<(), ProofError> {
+    for (k, &i) in indices.iter().enumerate() {
+        if i >= n || indices[..k].contains(&i) {
+            return Err(ProofError::InvalidMessageIndex(i));
+        }
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::test_helpers::TestSetup;
-    use ark_bls12_381::Bls12_381;
+    use ark_ec::pairing::Pairing;
     use ark_std::test_rng;
 
-    #[test]
-    fn test_prove() {
+    fn test_prove<E: Pairing>() {
         let mut rng = test_rng();
-        let setup = TestSetup::<Bls12_381>::new(&mut rng, 4);
+        let setup = TestSetup::<E>::new(&mut rng, 4);
 
         let is_valid = setup
             .signature
@@ -237,31 +934,47 @@ mod tests {
             randomized_signature.verify_pairing(&setup.pp, &setup.pk),
             "Randomized signature verification failed"
         );
+        let context = PresentationContext::new(b"verifier", b"nonce", 0);
         let proof = ProofSystem::bbs_plus_16_prove(
             &setup.pp,
             &randomized_signature,
             &setup.pk,
             &setup.messages,
+            &context,
             &mut rng,
         )
         .expect("Failed to generate proof");
 
         // Verify the proof
-        let verification_result =
-            ProofSystem::bbs_plus_16_verify_proof(&setup.pp, &setup.pk, &proof)
-                .expect("Failed to verify proof");
+        let verification_result = ProofSystem::bbs_plus_16_verify_proof(
+            &setup.pp,
+            &setup.pk,
+            &proof,
+            &Disclosure::default(),
+            &context,
+        )
+        .expect("Failed to verify proof");
 
         assert!(verification_result, "Proof verification failed");
+
+        let other_context = PresentationContext::new(b"verifier", b"other nonce", 0);
+        assert!(!ProofSystem::bbs_plus_16_verify_proof(
+            &setup.pp,
+            &setup.pk,
+            &proof,
+            &Disclosure::default(),
+            &other_context
+        )
+        .unwrap());
     }
 
-    #[test]
-    fn test_commitment_proof_simple() {
+    fn test_commitment_proof_simple<E: Pairing>() {
         // Create test setup
         let mut rng = test_rng();
-        let setup = TestSetup::<Bls12_381>::new(&mut rng, 3);
+        let setup = TestSetup::<E>::new(&mut rng, 3);
 
         // Generate random s_prime
-        let s_prime = <Bls12_381 as Pairing>::ScalarField::rand(&mut rng);
+        let s_prime = <E as Pairing>::ScalarField::rand(&mut rng);
 
         // Create commitment and proof
         let commitment_proof = ProofSystem::create_commitment_proof(
@@ -281,4 +994,6 @@ mod tests {
         // Assert that verification succeeds
         assert!(is_valid, "Commitment proof verification failed");
     }
+
+    utils::curve_tests!(test_prove, test_commitment_proof_simple);
 }
@@ -1,13 +1,22 @@
 use ark_ec::pairing::Pairing;
 use ark_ff::UniformRand;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use utils::encoding::{Base64Point, HexScalar};
 
-#[derive(Clone)]
+#[serde_as]
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize, Serialize, Deserialize)]
+#[serde(bound = "")]
 #[allow(non_snake_case)]
 pub struct PublicParams<E: Pairing> {
+    #[serde_as(as = "HexScalar")]
     pub context: E::ScalarField, //e.g. Hash to Field(dmv)
     pub L: usize,
+    #[serde_as(as = "Base64Point")]
     pub g1: E::G1Affine,
+    #[serde_as(as = "Base64Point")]
     pub g2: E::G2Affine,
 }
 
@@ -27,12 +36,13 @@ impl<E: Pairing> PublicParams<E> {
 #[cfg(test)]
 mod test {
     use super::*;
-    use ark_bls12_381::{Bls12_381, Fr};
-    #[test]
-    fn test_pp_gen() {
+    use ark_ec::pairing::Pairing;
+    fn test_pp_gen<E: Pairing>() {
         let L = 4;
         let mut rng = ark_std::test_rng();
-        let context = Fr::rand(&mut rng);
-        let pp = PublicParams::<Bls12_381>::new(&L, &context, &mut rng);
+        let context = E::ScalarField::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&L, &context, &mut rng);
     }
+
+    utils::curve_tests!(test_pp_gen);
 }
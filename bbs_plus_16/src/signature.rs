@@ -1,33 +1,52 @@
-use crate::keygen::{self, PublicKey, SecretKey};
+use crate::keygen::{self, PreparedVerificationKey, PublicKey, PublicKeyTables, SecretKey};
 use crate::publicparams::PublicParams;
 use ark_ec::pairing::Pairing;
 use ark_ec::{AffineRepr, CurveGroup, VariableBaseMSM};
 use ark_ff::{Field, UniformRand};
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::rand::Rng;
+use ark_std::sync::Mutex;
 use ark_std::{
     ops::{Add, Mul, Neg},
     One, Zero,
 };
 use schnorr::schnorr::SchnorrProtocol;
-use utils::pairing::PairingCheck;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use utils::encoding::{Base64Point, HexScalar};
+use utils::pairing::{batch_randomizer, PairingCheck};
 
-#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+#[serde_as]
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct BBSPlus16Signature<E: Pairing> {
+    #[serde_as(as = "Base64Point")]
     pub A: E::G1Affine,
+    #[serde_as(as = "HexScalar")]
     pub e: E::ScalarField,
+    #[serde_as(as = "HexScalar")]
     pub s: E::ScalarField,
 }
 
-#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+#[serde_as]
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct BBSPlus16RandomizedSignature<E: Pairing> {
+    #[serde_as(as = "Base64Point")]
     pub A_prime: E::G1Affine,
+    #[serde_as(as = "Base64Point")]
     pub A_bar: E::G1Affine,
+    #[serde_as(as = "Base64Point")]
     pub d: E::G1Affine,
+    #[serde_as(as = "HexScalar")]
     pub e: E::ScalarField,
+    #[serde_as(as = "HexScalar")]
     pub s_prime: E::ScalarField,
+    #[serde_as(as = "HexScalar")]
     pub r1: E::ScalarField,
+    #[serde_as(as = "HexScalar")]
     pub r2: E::ScalarField,
+    #[serde_as(as = "HexScalar")]
     pub r3: E::ScalarField,
 }
 
@@ -49,6 +68,25 @@ impl<E: Pairing> BBSPlus16Signature<E> {
         Self { A, e, s }
     }
 
+    /// `sign` with h_0^s · himi taken from the key's tables
+    pub fn sign_with_tables(
+        pp: &PublicParams<E>,
+        sk: &SecretKey<E>,
+        tables: &PublicKeyTables<E>,
+        rng: &mut impl Rng,
+        messages: &[E::ScalarField],
+    ) -> Self {
+        let e = E::ScalarField::rand(rng);
+        let s = E::ScalarField::rand(rng);
+        let b = pp.g1 + tables.h.msm(&[&[s], messages].concat());
+        let invexp = (sk.x + e).inverse().unwrap();
+        Self {
+            A: (b * invexp).into_affine(),
+            e,
+            s,
+        }
+    }
+
     pub fn rerandomize<R: Rng>(
         &self,
         pp: &PublicParams<E>,
@@ -59,6 +97,18 @@ impl<E: Pairing> BBSPlus16Signature<E> {
         BBSPlus16RandomizedSignature::randomize(self, &pp, &pk, &messages, rng)
     }
 
+    /// `rerandomize` with b and h_0^-r2 taken from the key's tables
+    pub fn rerandomize_with_tables<R: Rng>(
+        &self,
+        pp: &PublicParams<E>,
+        tables: &PublicKeyTables<E>,
+        messages: &[E::ScalarField],
+        rng: &mut R,
+    ) -> BBSPlus16RandomizedSignature<E> {
+        let b = pp.g1 + tables.h.msm(&[&[self.s], messages].concat());
+        BBSPlus16RandomizedSignature::randomize_with_b(self, b, |r| tables.h0.msm(&[r]), rng)
+    }
+
     pub fn verify(
         &self,
         pp: &PublicParams<E>,
@@ -84,6 +134,27 @@ impl<E: Pairing> BBSPlus16Signature<E> {
         check.verify()
     }
 
+    /// `verify` with a prepared key, e(A, w + g2^e) · e(-b, g2) = 1 is checked as
+    /// e(A, w) · e(A^e - b, g2) = 1 so that both G2 elements are fixed
+    pub fn verify_prepared(
+        &self,
+        pp: &PublicParams<E>,
+        pvk: &PreparedVerificationKey<E>,
+        messages: &[E::ScalarField],
+    ) -> bool {
+        if messages.len() != pvk.pk.h1hL.len() {
+            return false;
+        }
+        let himi = E::G1::msm_unchecked(&pvk.pk.h1hL, messages);
+        let b = pp.g1 + pvk.pk.h0 * self.s + himi;
+        let lhs = self.A * self.e - b;
+        E::multi_pairing(
+            [E::G1Prepared::from(self.A), E::G1Prepared::from(lhs)],
+            [pvk.w.clone(), pvk.g2.clone()],
+        )
+        .is_zero()
+    }
+
     pub fn verify_blind(
         &self,
         pp: &PublicParams<E>,
@@ -113,17 +184,27 @@ impl<E: Pairing> BBSPlus16RandomizedSignature<E> {
         pk: &keygen::PublicKey<E>,
         messages: &[E::ScalarField],
         rng: &mut R,
+    ) -> Self {
+        let himi: E::G1 = E::G1::msm(&pk.h1hL, messages).unwrap();
+        let b = pp.g1 + pk.h0 * signature.s + himi;
+        Self::randomize_with_b(signature, b, |r| pk.h0 * r, rng)
+    }
+
+    // randomizes given b = g1 · h_0^s · himi, h0_mul computes h_0^r
+    fn randomize_with_b<R: Rng>(
+        signature: &BBSPlus16Signature<E>,
+        b: E::G1,
+        h0_mul: impl Fn(E::ScalarField) -> E::G1,
+        rng: &mut R,
     ) -> Self {
         let r1 = E::ScalarField::rand(rng);
         let r2 = E::ScalarField::rand(rng);
         let r3 = r1.inverse().unwrap();
 
         let A_prime = signature.A.mul(r1).into_affine();
-        let himi: E::G1 = E::G1::msm(&pk.h1hL, messages).unwrap();
-        let b = pp.g1 + pk.h0 * signature.s + himi;
         let A_bar = A_prime.mul(signature.e.neg()).add(b.mul(r1));
 
-        let d = (b * r1) + (pk.h0 * r2.neg());
+        let d = (b * r1) + h0_mul(r2.neg());
 
         let s_prime = signature.s - (r2 * r3);
 
@@ -140,12 +221,42 @@ impl<E: Pairing> BBSPlus16RandomizedSignature<E> {
     }
 
     pub fn verify_pairing(&self, pp: &PublicParams<E>, pk: &keygen::PublicKey<E>) -> bool {
-        let mut rng = ark_std::test_rng();
-        let mr = std::sync::Mutex::new(rng);
-        let A_bar_neg = self.A_bar.into_group().neg().into_affine();
-        let check = PairingCheck::<E>::rand(
-            &mr,
-            &[(&self.A_prime, &pk.w), (&A_bar_neg, &pp.g2)],
+        self.verify_pairing_prepared(&pk.prepare(pp))
+    }
+
+    /// e(A', w) · e(-Ā, g2) = 1 with a prepared key
+    pub fn verify_pairing_prepared(&self, pvk: &PreparedVerificationKey<E>) -> bool {
+        let a_bar_neg = self.A_bar.into_group().neg();
+        E::multi_pairing(
+            [
+                E::G1Prepared::from(self.A_prime),
+                E::G1Prepared::from(a_bar_neg),
+            ],
+            [pvk.w.clone(), pvk.g2.clone()],
+        )
+        .is_zero()
+    }
+
+    /// Verifies many randomized signatures with one pairing check:
+    /// e(Σ ρ_i A'_i, w) · e(-Σ ρ_i Ā_i, g2) = 1
+    pub fn verify_pairing_batch<R: Rng + Send>(
+        pvk: &PreparedVerificationKey<E>,
+        signatures: &[&Self],
+        rng: &mut R,
+    ) -> bool {
+        if signatures.is_empty() {
+            return true;
+        }
+        let rhos: Vec<E::ScalarField> = signatures.iter().map(|_| batch_randomizer(rng)).collect();
+        let a_primes: Vec<E::G1Affine> = signatures.iter().map(|sig| sig.A_prime).collect();
+        let a_bars: Vec<E::G1Affine> = signatures.iter().map(|sig| sig.A_bar).collect();
+        let sums = E::G1::normalize_batch(&[
+            E::G1::msm_unchecked(&a_primes, &rhos),
+            E::G1::msm_unchecked(&a_bars, &rhos).neg(),
+        ]);
+        let check = PairingCheck::<E>::rand_prepared(
+            &Mutex::new(&mut *rng),
+            &[(&sums[0], &pvk.w), (&sums[1], &pvk.g2)],
             &E::TargetField::one(),
         );
         check.verify()
@@ -157,19 +268,17 @@ mod tests {
     use crate::keygen;
 
     use super::*;
-    use ark_bls12_381::{Bls12_381, Fr};
     use ark_std::test_rng;
 
-    #[test]
-    fn test_sign_and_verify() {
+    fn test_sign_and_verify<E: Pairing>() {
         let L = 4;
         let mut rng = ark_std::test_rng();
-        let context = Fr::rand(&mut rng);
-        let pp = PublicParams::<Bls12_381>::new(&L, &context, &mut rng);
-        let (sk, pk) = keygen::gen_keys::<Bls12_381>(&pp, &mut rng);
+        let context = E::ScalarField::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&L, &context, &mut rng);
+        let (sk, pk) = keygen::gen_keys::<E>(&pp, &mut rng);
 
-        let messages: Vec<<Bls12_381 as Pairing>::ScalarField> = (0..L)
-            .map(|_| <Bls12_381 as Pairing>::ScalarField::rand(&mut rng))
+        let messages: Vec<<E as Pairing>::ScalarField> = (0..L)
+            .map(|_| <E as Pairing>::ScalarField::rand(&mut rng))
             .collect();
 
         let signature = BBSPlus16Signature::sign(&pp, &sk, &pk, &mut rng, &messages);
@@ -181,5 +290,24 @@ mod tests {
             randomized_signature.verify_pairing(&pp, &pk),
             "Randomized signature verification failed"
         );
+
+        // the prepared key gives the same answers
+        let pvk = pk.prepare(&pp);
+        assert!(signature.verify_prepared(&pp, &pvk, &messages));
+        assert!(randomized_signature.verify_pairing_prepared(&pvk));
+        let mut other_messages = messages.clone();
+        other_messages[0] += E::ScalarField::from(1u64);
+        assert!(!signature.verify_prepared(&pp, &pvk, &other_messages));
+
+        // signing and randomizing from the key's tables
+        let tables = pk.precompute();
+        let signature =
+            BBSPlus16Signature::sign_with_tables(&pp, &sk, &tables, &mut rng, &messages);
+        assert!(signature.verify(&pp, &pk, &messages));
+        let randomized_signature =
+            signature.rerandomize_with_tables(&pp, &tables, &messages, &mut rng);
+        assert!(randomized_signature.verify_pairing(&pp, &pk));
     }
+
+    utils::curve_tests!(test_sign_and_verify);
 }
@@ -0,0 +1,124 @@
+use crate::anon_cred::{IssuerResponse, ShowCredential};
+use crate::keygen::{PublicKey, SecretKey};
+use crate::proofsystem::{CommitmentWithProof, ReissuanceProofOfKnowledge};
+use crate::publicparams::PublicParams;
+use crate::signature::BBSPlus16Signature;
+use utils::impl_envelope;
+
+impl_envelope!(PublicParams, BbsPlus16, PublicParams, |pp| pp.L);
+impl_envelope!(PublicKey, BbsPlus16, PublicKey, |pk| pk.h1hL.len());
+impl_envelope!(SecretKey, BbsPlus16, SecretKey);
+impl_envelope!(CommitmentWithProof, BbsPlus16, IssuanceRequest);
+impl_envelope!(ReissuanceProofOfKnowledge, BbsPlus16, ReissuanceRequest);
+impl_envelope!(IssuerResponse, BbsPlus16, IssuanceResponse);
+impl_envelope!(BBSPlus16Signature, BbsPlus16, Signature);
+// carries both plain and escrowed show proofs
+impl_envelope!(ShowCredential, BbsPlus16, Presentation);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anon_cred::AnonCredProtocol;
+    use crate::keygen::gen_keys;
+    use crate::test_helpers::TestSetup;
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_serialize::CanonicalDeserialize;
+    use ark_std::test_rng;
+    use utils::context::PresentationContext;
+    use utils::envelope::{assert_golden, Envelope, EnvelopeError};
+    use utils::update::AttributeUpdate;
+
+    fn golden(name: &str) -> String {
+        format!("{}/golden/{}.hex", env!("CARGO_MANIFEST_DIR"), name)
+    }
+
+    #[test]
+    fn test_bbs_plus_16_wire_golden() {
+        let mut rng = test_rng();
+        let setup = TestSetup::<Bls12_381>::new(&mut rng, 3);
+        let (request, s_prime) =
+            AnonCredProtocol::obtain(&setup.pp, &setup.pk, &setup.messages, &mut rng).unwrap();
+        let response =
+            AnonCredProtocol::issue(&setup.pp, &setup.sk, &setup.pk, &request, &mut rng).unwrap();
+        let signature = AnonCredProtocol::complete_signature(&s_prime, &response);
+        let context = PresentationContext::new(b"verifier", b"nonce-1", 1_700_000_000);
+        let presentation = AnonCredProtocol::show(
+            &setup.pp,
+            &setup.pk,
+            &signature,
+            &setup.messages,
+            &context,
+            &mut rng,
+        )
+        .unwrap();
+        let (_, new_pk) = gen_keys(&setup.pp, &mut rng);
+        let updates = vec![(1, AttributeUpdate::Add(Fr::from(1u64)))];
+        let (reissue_request, _, _) = AnonCredProtocol::obtain_reissue(
+            &setup.pp,
+            &setup.pk,
+            &new_pk,
+            &signature,
+            &setup.messages,
+            &updates,
+            &context,
+            &mut rng,
+        )
+        .unwrap();
+        let reissue_request =
+            ReissuanceProofOfKnowledge::<Bls12_381>::deserialize_compressed(&reissue_request[..])
+                .unwrap();
+
+        assert_golden(&golden("public_params"), &setup.pp.to_envelope());
+        assert_golden(&golden("public_key"), &setup.pk.to_envelope());
+        assert_golden(&golden("issuance_request"), &request.to_envelope());
+        assert_golden(
+            &golden("reissuance_request"),
+            &reissue_request.to_envelope(),
+        );
+        assert_golden(&golden("issuance_response"), &response.to_envelope());
+        assert_golden(&golden("signature"), &signature.to_envelope());
+        assert_golden(&golden("presentation"), &presentation.to_envelope());
+
+        let pk = PublicKey::<Bls12_381>::from_envelope(&setup.pk.to_envelope()).unwrap();
+        let decoded =
+            ShowCredential::<Bls12_381>::from_envelope(&presentation.to_envelope()).unwrap();
+        assert!(AnonCredProtocol::verify(&setup.pp, &pk, &decoded, &context).unwrap());
+        assert!(matches!(
+            IssuerResponse::<Bls12_381>::from_envelope(&signature.to_envelope()),
+            Err(EnvelopeError::KindMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_bbs_plus_16_json() {
+        let mut rng = test_rng();
+        let setup = TestSetup::<Bls12_381>::new(&mut rng, 3);
+        let context = PresentationContext::new(b"verifier", b"nonce-1", 1_700_000_000);
+        let presentation = AnonCredProtocol::show(
+            &setup.pp,
+            &setup.pk,
+            &setup.signature,
+            &setup.messages,
+            &context,
+            &mut rng,
+        )
+        .unwrap();
+
+        let pp: PublicParams<Bls12_381> =
+            serde_json::from_str(&serde_json::to_string(&setup.pp).unwrap()).unwrap();
+        let pk: PublicKey<Bls12_381> =
+            serde_json::from_str(&serde_json::to_string(&setup.pk).unwrap()).unwrap();
+        let signature: BBSPlus16Signature<Bls12_381> =
+            serde_json::from_str(&serde_json::to_string(&setup.signature).unwrap()).unwrap();
+        assert_eq!(signature.e, setup.signature.e);
+        let json = serde_json::to_value(&presentation).unwrap();
+        assert!(json["randomized_signature"]["e"].is_string());
+        let decoded: ShowCredential<Bls12_381> = serde_json::from_value(json.clone()).unwrap();
+        assert!(AnonCredProtocol::verify(&pp, &pk, &decoded, &context).unwrap());
+
+        // scalars are canonical, the all-ones string exceeds the field modulus
+        let mut tampered = json;
+        tampered["randomized_signature"]["e"] = serde_json::Value::String("ff".repeat(32));
+        assert!(serde_json::from_value::<ShowCredential<Bls12_381>>(tampered).is_err());
+    }
+}
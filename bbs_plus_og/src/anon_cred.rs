@@ -6,18 +6,41 @@ use ark_ec::pairing::Pairing;
 use ark_ec::CurveGroup;
 use ark_ff::{Field, UniformRand};
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::marker::PhantomData;
 use ark_std::rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use utils::encoding::{Base64Bytes, Base64Point, HexScalar};
 
-#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+#[serde_as]
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct IssuerResponse<E: Pairing> {
+    #[serde_as(as = "Base64Point")]
     pub A: E::G1Affine,
+    #[serde_as(as = "HexScalar")]
     pub e: E::ScalarField,
+    #[serde_as(as = "HexScalar")]
     pub s_prime_prime: E::ScalarField, // Issuer's blinding factor
 }
 
-#[derive(Clone)]
-pub struct ShowCredential {
+#[serde_as]
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct ShowCredential<E: Pairing> {
+    #[serde_as(as = "Base64Bytes")]
     pub proof: Vec<u8>,
+    #[serde(skip)]
+    _pairing: PhantomData<E>,
+}
+
+impl<E: Pairing> ShowCredential<E> {
+    pub fn new(proof: Vec<u8>) -> Self {
+        Self {
+            proof,
+            _pairing: PhantomData,
+        }
+    }
 }
 
 pub struct AnonCredProtocol;
@@ -75,12 +98,45 @@ impl AnonCredProtocol {
         let proof: CommitmentProof<E> =
             CanonicalDeserialize::deserialize_compressed(serialized_proof)?;
 
+        Self::sign_commitment(pp, sk, &proof.commitment, rng)
+    }
+
+    /// Issuer verifies many proofs in one batch and issues a response for each
+    ///
+    /// # Arguments
+    /// * `pp` - Public parameters
+    /// * `sk` - Issuer's secret key
+    /// * `serialized_proofs` - Commitment proofs from the users
+    /// * `rng` - Random number generator
+    ///
+    /// # Returns
+    /// * One issuer response per request, or the index of the first invalid request
+    pub fn issue_batch<E: Pairing, R: Rng>(
+        pp: &PublicParams<E>,
+        sk: &SecretKey<E>,
+        serialized_proofs: &[Vec<u8>],
+        rng: &mut R,
+    ) -> Result<Vec<IssuerResponse<E>>, ProofError> {
+        let proofs = ProofSystem::verify_commitment_proofs_batch(pp, serialized_proofs, rng)?;
+
+        proofs
+            .iter()
+            .map(|proof| Self::sign_commitment(pp, sk, &proof.commitment, rng))
+            .collect()
+    }
+
+    fn sign_commitment<E: Pairing, R: Rng>(
+        pp: &PublicParams<E>,
+        sk: &SecretKey<E>,
+        commitment: &E::G1Affine,
+        rng: &mut R,
+    ) -> Result<IssuerResponse<E>, ProofError> {
         // Generate random values for the signature
         let e = E::ScalarField::rand(rng);
         let s_prime_prime = E::ScalarField::rand(rng);
 
         // Compute A = (g₁ · h₀^s_prime · Cm)^(1/(e+x))
-        let base = pp.g0 + pp.g1 * s_prime_prime + proof.commitment;
+        let base = pp.g0 + pp.g1 * s_prime_prime + *commitment;
         let exponent = (sk.gamma + e)
             .inverse()
             .ok_or(ProofError::VerificationFailed)?;
@@ -133,12 +189,12 @@ impl AnonCredProtocol {
         signature: &BBSPlusOgSignature<E>,
         messages: &[E::ScalarField],
         rng: &mut R,
-    ) -> Result<ShowCredential, ProofError> {
+    ) -> Result<ShowCredential<E>, ProofError> {
         // Generate the proof
         let proof = ProofSystem::pok_signature_prove(&pp, &pk, &signature, &messages, rng)?;
 
         // Return the randomized signature and the proof
-        Ok(ShowCredential { proof })
+        Ok(ShowCredential::new(proof))
     }
 
     /// Verifier checks the credential proof
@@ -152,7 +208,7 @@ impl AnonCredProtocol {
     /// * Result indicating whether the proof is valid
     pub fn verify<E: Pairing>(
         pp: &PublicParams<E>,
-        cred_show: &ShowCredential,
+        cred_show: &ShowCredential<E>,
     ) -> Result<bool, ProofError> {
         // Verify the proof
         if !ProofSystem::pok_signature_verify(pp, &cred_show.proof)? {
@@ -167,13 +223,13 @@ impl AnonCredProtocol {
 mod tests {
     use super::*;
     use crate::test_helpers::TestSetup;
-    use ark_bls12_381::Bls12_381;
+    use ark_ec::pairing::Pairing;
     use ark_std::test_rng;
+    use schnorr::batch::BatchError;
 
-    #[test]
-    fn test_obtain_issue_show_verify() {
+    fn test_obtain_issue_show_verify<E: Pairing>() {
         let mut rng = test_rng();
-        let setup = TestSetup::<Bls12_381>::new(&mut rng, 4);
+        let setup = TestSetup::<E>::new(&mut rng, 4);
         // 1. Obtain: User creates commitment and proof
         let (commitment_proof, s) =
             AnonCredProtocol::obtain(&setup.pp, &setup.pk, &setup.messages, &mut rng)
@@ -198,4 +254,39 @@ mod tests {
 
         assert!(verification_result, "Proof verification failed");
     }
+
+    fn test_issue_batch<E: Pairing>() {
+        let mut rng = test_rng();
+        let setup = TestSetup::<E>::new(&mut rng, 3);
+        let other = TestSetup::<E>::new(&mut rng, 3);
+
+        let requests: Vec<_> = (0..4)
+            .map(|_| {
+                let messages: Vec<E::ScalarField> =
+                    (0..3).map(|_| E::ScalarField::rand(&mut rng)).collect();
+                let (proof, s) =
+                    AnonCredProtocol::obtain(&setup.pp, &setup.pk, &messages, &mut rng).unwrap();
+                (messages, proof, s)
+            })
+            .collect();
+        let mut proofs: Vec<Vec<u8>> = requests.iter().map(|(_, p, _)| p.clone()).collect();
+
+        let responses =
+            AnonCredProtocol::issue_batch(&setup.pp, &setup.sk, &proofs, &mut rng).unwrap();
+        for ((messages, _, s), response) in requests.iter().zip(responses.iter()) {
+            let signature = AnonCredProtocol::complete_signature(s, response);
+            assert!(signature.verify(&setup.pp, &setup.pk, messages));
+        }
+
+        // a proof carrying another issuer's bases is checked against ours and named
+        let (forged, _) =
+            AnonCredProtocol::obtain(&other.pp, &other.pk, &requests[3].0, &mut rng).unwrap();
+        proofs[3] = forged;
+        assert!(matches!(
+            AnonCredProtocol::issue_batch(&setup.pp, &setup.sk, &proofs, &mut rng),
+            Err(ProofError::BatchError(BatchError::InvalidProof(3)))
+        ));
+    }
+
+    utils::curve_tests!(test_obtain_issue_show_verify, test_issue_batch);
 }
@@ -2,16 +2,23 @@ use crate::publicparams::PublicParams;
 use ark_ec::pairing::Pairing;
 use ark_ec::CurveGroup;
 use ark_ff::UniformRand;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::ops::Mul;
 use ark_std::rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use utils::encoding::Base64Point;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
 pub struct SecretKey<E: Pairing> {
     pub gamma: E::ScalarField, // Secret key γ
 }
 
-#[derive(Clone, Debug)]
+#[serde_as]
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct PublicKey<E: Pairing> {
+    #[serde_as(as = "Base64Point")]
     pub w: E::G2Affine, // w = h₀ᵧ in G₂
 }
 
@@ -34,17 +41,15 @@ pub fn gen_keys<E: Pairing>(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ark_bls12_381::{Bls12_381, Fr};
     use ark_ec::pairing::Pairing;
 
-    #[test]
-    fn test_keygen() {
+    fn test_keygen<E: Pairing>() {
         // Initialize test environment
         let mut rng = ark_std::test_rng();
         let L = 5; // Support 5 messages
 
         // Generate public parameters
-        let pp = PublicParams::<Bls12_381>::new(&L, &mut rng);
+        let pp = PublicParams::<E>::new(&L, &mut rng);
 
         // Generate a keypair
         let (sk, pk) = gen_keys(&pp, &mut rng);
@@ -54,8 +59,10 @@ mod tests {
         assert_eq!(pk.w, computed_w, "Public key should be h₀ᵧ");
 
         // Verify with pairing: e(g₀, w) = e(g₀, h₀ᵧ)
-        let pairing1 = Bls12_381::pairing(pp.g0, pk.w);
-        let pairing2 = Bls12_381::pairing(pp.g0, pp.h0.mul(sk.gamma).into_affine());
+        let pairing1 = E::pairing(pp.g0, pk.w);
+        let pairing2 = E::pairing(pp.g0, pp.h0.mul(sk.gamma).into_affine());
         assert_eq!(pairing1, pairing2, "Pairing consistency check failed");
     }
+
+    utils::curve_tests!(test_keygen);
 }
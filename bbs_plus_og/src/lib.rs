@@ -5,3 +5,4 @@ pub mod publicparams;
 pub mod signature;
 pub mod test_helpers;
 pub mod utils;
+pub mod wire;
@@ -12,11 +12,13 @@ use ark_std::{
     ops::{Add, Mul, Neg},
     One, Zero,
 };
+use schnorr::batch::{BatchError, SchnorrBatch, SchnorrClaim};
 use schnorr::schnorr::{SchnorrCommitment, SchnorrProtocol, SchnorrResponses};
 use schnorr::schnorr_pairing::{
     SchnorrCommitmentPairing, SchnorrProtocolPairing, SchnorrResponsesPairing,
 };
 use thiserror::Error;
+use utils::hash::HashUtil;
 
 #[derive(Error, Debug)]
 pub enum ProofError {
@@ -26,6 +28,8 @@ pub enum ProofError {
     InvalidProof,
     #[error("Verification failed")]
     VerificationFailed,
+    #[error("Batch verification failed: {0}")]
+    BatchError(#[from] BatchError),
 }
 
 /// Full proof of knowledge of a BBS+ signature
@@ -256,7 +260,8 @@ impl ProofSystem {
 
         // Generate Schnorr proof for the commitment
         let schnorr_commitment = SchnorrProtocol::commit(&bases, rng);
-        let challenge = E::ScalarField::rand(rng);
+        let challenge =
+            commitment_challenge::<E>(&bases, &commitment, &schnorr_commitment.commited_blindings)?;
         let responses = SchnorrProtocol::prove(&schnorr_commitment, &exponents, &challenge);
 
         // Create the proof struct
@@ -274,7 +279,8 @@ impl ProofSystem {
         Ok(serialized_proof)
     }
 
-    /// Verify a commitment proof
+    /// Verify a commitment proof against the public parameters' [g1, g2..gL], the bases
+    /// carried in the proof are ignored.
     ///
     /// # Arguments
     /// * `pp` - Public parameters
@@ -292,9 +298,22 @@ impl ProofSystem {
         let proof: CommitmentProof<E> =
             CanonicalDeserialize::deserialize_compressed(serialized_proof)?;
 
+        let mut bases = pp.g2_to_L.clone();
+        bases.insert(0, pp.g1);
+        if proof.responses.len() != bases.len() {
+            return Ok(false);
+        }
+
+        // Recompute the challenge, a prover choosing it could answer for any commitment
+        let expected =
+            commitment_challenge::<E>(&bases, &proof.commitment, &proof.schnorr_commitment)?;
+        if proof.challenge != expected {
+            return Ok(false);
+        }
+
         // Verify the Schnorr proof
         let is_valid = SchnorrProtocol::verify_schnorr(
-            &proof.bases,
+            &bases,
             &proof.commitment,
             &proof.schnorr_commitment,
             &proof.responses,
@@ -303,6 +322,65 @@ impl ProofSystem {
 
         Ok(is_valid)
     }
+
+    /// Verify many commitment proofs together, for bulk issuance. The bases are the
+    /// public parameters' [g1, g2..gL], the bases carried in the proofs are ignored.
+    ///
+    /// # Arguments
+    /// * `pp` - Public parameters
+    /// * `serialized_proofs` - Commitment proofs from the users
+    /// * `rng` - Source of the batch randomizers
+    ///
+    /// # Returns
+    /// * The deserialized proofs, or the index of the first invalid one
+    pub fn verify_commitment_proofs_batch<E: Pairing, R: Rng>(
+        pp: &PublicParams<E>,
+        serialized_proofs: &[Vec<u8>],
+        rng: &mut R,
+    ) -> Result<Vec<CommitmentProof<E>>, ProofError> {
+        let proofs = serialized_proofs
+            .iter()
+            .map(|bytes| CommitmentProof::<E>::deserialize_compressed(&bytes[..]))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut bases = pp.g2_to_L.clone();
+        bases.insert(0, pp.g1);
+
+        let mut batch = SchnorrBatch::<E>::new();
+        for (i, proof) in proofs.iter().enumerate() {
+            let expected =
+                commitment_challenge::<E>(&bases, &proof.commitment, &proof.schnorr_commitment)?;
+            if proof.challenge != expected {
+                return Err(BatchError::InvalidProof(i).into());
+            }
+
+            batch.add_g1(SchnorrClaim {
+                bases: bases.clone(),
+                statement: proof.commitment,
+                commitment: proof.schnorr_commitment,
+                challenge: proof.challenge,
+                responses: proof.responses.clone(),
+            });
+        }
+        batch.verify(rng)?;
+
+        Ok(proofs)
+    }
+}
+
+/// Fiat-Shamir challenge of a commitment proof, over the bases, the commitment and T
+fn commitment_challenge<E: Pairing>(
+    bases: &[E::G1Affine],
+    commitment: &E::G1Affine,
+    schnorr_commitment: &E::G1Affine,
+) -> Result<E::ScalarField, ProofError> {
+    let mut transcript = b"bbs-plus-og-commitment-proof".to_vec();
+    bases.serialize_compressed(&mut transcript)?;
+    commitment.serialize_compressed(&mut transcript)?;
+    schnorr_commitment.serialize_compressed(&mut transcript)?;
+    Ok(HashUtil::<E::ScalarField, E::G1Affine>::hash_to_field(
+        &transcript,
+    ))
 }
 
 #[cfg(test)]
@@ -310,23 +388,23 @@ mod tests {
     use super::*;
     use crate::keygen::gen_keys;
     use crate::signature::BBSPlusOgSignature;
-    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ec::pairing::Pairing;
     use ark_std::test_rng;
 
-    #[test]
-    fn test_proof_of_knowledge() {
+    fn test_proof_of_knowledge<E: Pairing>() {
         // Initialize test environment
         let mut rng = test_rng();
         let L = 1; // Support 4 messages
 
         // Generate public parameters
-        let pp = PublicParams::<Bls12_381>::new(&L, &mut rng);
+        let pp = PublicParams::<E>::new(&L, &mut rng);
 
         // Generate a keypair
         let (sk, pk) = gen_keys(&pp, &mut rng);
 
         // Create random messages
-        let messages: Vec<Fr> = (0..L).map(|_| Fr::rand(&mut rng)).collect();
+        let messages: Vec<E::ScalarField> =
+            (0..L).map(|_| E::ScalarField::rand(&mut rng)).collect();
 
         // Sign the messages
         let signature = BBSPlusOgSignature::sign(&pp, &sk, &messages, &mut rng);
@@ -346,21 +424,21 @@ mod tests {
         assert!(is_proof_valid, "Proof verification failed");
     }
 
-    #[test]
-    fn test_commitment_proof() {
+    fn test_commitment_proof<E: Pairing>() {
         // Initialize test environment
         let mut rng = test_rng();
         let L = 2; // Support 2 messages
 
         // Generate public parameters
-        let pp = PublicParams::<Bls12_381>::new(&L, &mut rng);
+        let pp = PublicParams::<E>::new(&L, &mut rng);
 
         // Generate a keypair
         let (_, pk) = gen_keys(&pp, &mut rng);
 
         // Create random messages and blinding factor
-        let messages: Vec<Fr> = (0..L).map(|_| Fr::rand(&mut rng)).collect();
-        let s = Fr::rand(&mut rng);
+        let messages: Vec<E::ScalarField> =
+            (0..L).map(|_| E::ScalarField::rand(&mut rng)).collect();
+        let s = E::ScalarField::rand(&mut rng);
 
         // Create commitment and proof
         let proof = ProofSystem::create_commitment_proof(&pp, &pk, &messages, &s, &mut rng)
@@ -372,4 +450,40 @@ mod tests {
 
         assert!(is_valid, "Commitment proof verification failed");
     }
+
+    fn test_commitment_proof_tampered_challenge<E: Pairing>() {
+        let mut rng = test_rng();
+        let L = 2;
+        let pp = PublicParams::<E>::new(&L, &mut rng);
+        let (_, pk) = gen_keys(&pp, &mut rng);
+        let messages: Vec<E::ScalarField> =
+            (0..L).map(|_| E::ScalarField::rand(&mut rng)).collect();
+        let s = E::ScalarField::rand(&mut rng);
+        let serialized = ProofSystem::create_commitment_proof(&pp, &pk, &messages, &s, &mut rng)
+            .expect("Failed to create commitment proof");
+        let mut proof = CommitmentProof::<E>::deserialize_compressed(&serialized[..]).unwrap();
+
+        // a prover picking its own challenge can fit the responses to it, (T, c, z) with
+        // T = ∏ base^z · C^-c verifies under Schnorr alone
+        let mut bases = pp.g2_to_L.clone();
+        bases.insert(0, pp.g1);
+        proof.challenge = E::ScalarField::rand(&mut rng);
+        proof.schnorr_commitment = (E::G1::msm_unchecked(&bases, &proof.responses)
+            - proof.commitment.mul(proof.challenge))
+        .into_affine();
+        let mut tampered = Vec::new();
+        proof.serialize_compressed(&mut tampered).unwrap();
+
+        assert!(!ProofSystem::verify_commitment_proof(&pp, &pk, &tampered).unwrap());
+        assert!(matches!(
+            ProofSystem::verify_commitment_proofs_batch(&pp, &[serialized, tampered], &mut rng),
+            Err(ProofError::BatchError(BatchError::InvalidProof(1)))
+        ));
+    }
+
+    utils::curve_tests!(
+        test_proof_of_knowledge,
+        test_commitment_proof,
+        test_commitment_proof_tampered_challenge
+    );
 }
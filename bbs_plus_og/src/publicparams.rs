@@ -1,16 +1,26 @@
 use ark_ec::pairing::Pairing;
 use ark_ec::AffineRepr;
 use ark_ff::UniformRand;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::rand::Rng;
 use ark_std::vec::Vec;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use utils::encoding::Base64Point;
 
-#[derive(Clone, Debug)]
+#[serde_as]
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct PublicParams<E: Pairing> {
-    pub L: usize,                  // Maximum number of messages
-    pub g0: E::G1Affine,           // Base generator g₀ in G₁
-    pub g1: E::G1Affine,           // g1 base for s
+    pub L: usize, // Maximum number of messages
+    #[serde_as(as = "Base64Point")]
+    pub g0: E::G1Affine, // Base generator g₀ in G₁
+    #[serde_as(as = "Base64Point")]
+    pub g1: E::G1Affine, // g1 base for s
+    #[serde_as(as = "Vec<Base64Point>")]
     pub g2_to_L: Vec<E::G1Affine>, // G_L for each message
-    pub h0: E::G2Affine,           // Base generator h₀ in G₂
+    #[serde_as(as = "Base64Point")]
+    pub h0: E::G2Affine, // Base generator h₀ in G₂
 }
 
 impl<E: Pairing> PublicParams<E> {
@@ -51,12 +61,13 @@ impl<E: Pairing> PublicParams<E> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ec::pairing::Pairing;
 
-    #[test]
-    fn test_pp_gen() {
+    fn test_pp_gen<E: Pairing>() {
         let L = 5; // Support 5 messages
         let mut rng = ark_std::test_rng();
-        let pp = PublicParams::<Bls12_381>::new(&L, &mut rng);
+        let pp = PublicParams::<E>::new(&L, &mut rng);
     }
+
+    utils::curve_tests!(test_pp_gen);
 }
@@ -4,16 +4,25 @@ use crate::utils::BBSPlusOgUtils;
 use ark_ec::pairing::{Pairing, PairingOutput};
 use ark_ec::{AffineRepr, CurveGroup, VariableBaseMSM};
 use ark_ff::{Field, UniformRand};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::ops::{Add, Mul};
 use ark_std::rand::Rng;
 use ark_std::vec::Vec;
 use ark_std::One;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
 use std::ops::Neg;
+use utils::encoding::{Base64Point, HexScalar};
 
-#[derive(Clone, Debug)]
+#[serde_as]
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct BBSPlusOgSignature<E: Pairing> {
+    #[serde_as(as = "Base64Point")]
     pub A: E::G1Affine,
+    #[serde_as(as = "HexScalar")]
     pub e: E::ScalarField,
+    #[serde_as(as = "HexScalar")]
     pub s: E::ScalarField,
 }
 
@@ -188,22 +197,22 @@ impl<E: Pairing> BBSPlusOgRandomizedSignature<E> {
 mod tests {
     use super::*;
     use crate::keygen::gen_keys;
-    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ec::pairing::Pairing;
 
-    #[test]
-    fn test_signature() {
+    fn test_signature<E: Pairing>() {
         // Initialize test environment
         let mut rng = ark_std::test_rng();
         let L = 5; // Support 5 messages
 
         // Generate public parameters
-        let pp = PublicParams::<Bls12_381>::new(&L, &mut rng);
+        let pp = PublicParams::<E>::new(&L, &mut rng);
 
         // Generate a keypair
         let (sk, pk) = gen_keys(&pp, &mut rng);
 
         // Create random messages
-        let messages: Vec<Fr> = (0..L).map(|_| Fr::rand(&mut rng)).collect();
+        let messages: Vec<E::ScalarField> =
+            (0..L).map(|_| E::ScalarField::rand(&mut rng)).collect();
 
         // Sign the messages
         let signature = BBSPlusOgSignature::sign(&pp, &sk, &messages, &mut rng);
@@ -212,4 +221,6 @@ mod tests {
         let is_valid = signature.verify(&pp, &pk, &messages);
         assert!(is_valid, "Signature verification failed");
     }
+
+    utils::curve_tests!(test_signature);
 }
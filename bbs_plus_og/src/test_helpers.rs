@@ -51,16 +51,15 @@ impl<E: Pairing> TestSetup<E> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ark_bls12_381::Bls12_381;
+    use ark_ec::pairing::Pairing;
     use ark_std::test_rng;
 
-    #[test]
-    fn test_setup_creation() {
+    fn test_setup_creation<E: Pairing>() {
         let mut rng = test_rng();
         let message_count = 4;
 
         // Create test setup
-        let setup = TestSetup::<Bls12_381>::new(&mut rng, message_count);
+        let setup = TestSetup::<E>::new(&mut rng, message_count);
 
         // Verify the signature
         let is_valid = setup
@@ -74,4 +73,6 @@ mod tests {
             "Should have the requested message count"
         );
     }
+
+    utils::curve_tests!(test_setup_creation);
 }
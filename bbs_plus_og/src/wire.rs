@@ -0,0 +1,89 @@
+use crate::anon_cred::{IssuerResponse, ShowCredential};
+use crate::keygen::{PublicKey, SecretKey};
+use crate::proofsystem::CommitmentProof;
+use crate::publicparams::PublicParams;
+use crate::signature::BBSPlusOgSignature;
+use utils::impl_envelope;
+
+impl_envelope!(PublicParams, BbsPlusOg, PublicParams, |pp| pp.L);
+impl_envelope!(PublicKey, BbsPlusOg, PublicKey);
+impl_envelope!(SecretKey, BbsPlusOg, SecretKey);
+impl_envelope!(CommitmentProof, BbsPlusOg, IssuanceRequest);
+impl_envelope!(IssuerResponse, BbsPlusOg, IssuanceResponse);
+impl_envelope!(BBSPlusOgSignature, BbsPlusOg, Signature);
+impl_envelope!(ShowCredential, BbsPlusOg, Presentation);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anon_cred::AnonCredProtocol;
+    use crate::test_helpers::TestSetup;
+    use ark_bls12_381::Bls12_381;
+    use ark_serialize::CanonicalDeserialize;
+    use ark_std::test_rng;
+    use utils::envelope::{assert_golden, Envelope, Header};
+
+    fn golden(name: &str) -> String {
+        format!("{}/golden/{}.hex", env!("CARGO_MANIFEST_DIR"), name)
+    }
+
+    #[test]
+    fn test_bbs_plus_og_wire_golden() {
+        let mut rng = test_rng();
+        let setup = TestSetup::<Bls12_381>::new(&mut rng, 3);
+        let (request, s) =
+            AnonCredProtocol::obtain(&setup.pp, &setup.pk, &setup.messages, &mut rng).unwrap();
+        let response =
+            AnonCredProtocol::issue(&setup.pp, &setup.sk, &setup.pk, &request, &mut rng).unwrap();
+        let signature = AnonCredProtocol::complete_signature(&s, &response);
+        let presentation =
+            AnonCredProtocol::show(&setup.pp, &setup.pk, &signature, &setup.messages, &mut rng)
+                .unwrap();
+        let request = CommitmentProof::<Bls12_381>::deserialize_compressed(&request[..]).unwrap();
+
+        assert_golden(&golden("public_params"), &setup.pp.to_envelope());
+        assert_golden(&golden("public_key"), &setup.pk.to_envelope());
+        assert_golden(&golden("issuance_request"), &request.to_envelope());
+        assert_golden(&golden("issuance_response"), &response.to_envelope());
+        assert_golden(&golden("signature"), &signature.to_envelope());
+        assert_golden(&golden("presentation"), &presentation.to_envelope());
+
+        let pp = PublicParams::<Bls12_381>::from_envelope(&setup.pp.to_envelope()).unwrap();
+        let (header, _) = Header::parse(&setup.pp.to_envelope()).unwrap();
+        assert_eq!(header.attribute_count, 3);
+        let decoded =
+            ShowCredential::<Bls12_381>::from_envelope(&presentation.to_envelope()).unwrap();
+        assert!(AnonCredProtocol::verify(&pp, &decoded).unwrap());
+    }
+
+    #[test]
+    fn test_bbs_plus_og_json() {
+        let mut rng = test_rng();
+        let setup = TestSetup::<Bls12_381>::new(&mut rng, 3);
+        let (request, s) =
+            AnonCredProtocol::obtain(&setup.pp, &setup.pk, &setup.messages, &mut rng).unwrap();
+        let response =
+            AnonCredProtocol::issue(&setup.pp, &setup.sk, &setup.pk, &request, &mut rng).unwrap();
+        let signature = AnonCredProtocol::complete_signature(&s, &response);
+        let presentation =
+            AnonCredProtocol::show(&setup.pp, &setup.pk, &signature, &setup.messages, &mut rng)
+                .unwrap();
+
+        let pp: PublicParams<Bls12_381> =
+            serde_json::from_str(&serde_json::to_string(&setup.pp).unwrap()).unwrap();
+        assert_eq!(pp.g2_to_L, setup.pp.g2_to_L);
+        let pk: PublicKey<Bls12_381> =
+            serde_json::from_str(&serde_json::to_string(&setup.pk).unwrap()).unwrap();
+        assert_eq!(pk.w, setup.pk.w);
+        let decoded: IssuerResponse<Bls12_381> =
+            serde_json::from_str(&serde_json::to_string(&response).unwrap()).unwrap();
+        assert_eq!(decoded.s_prime_prime, response.s_prime_prime);
+        let decoded: ShowCredential<Bls12_381> =
+            serde_json::from_str(&serde_json::to_string(&presentation).unwrap()).unwrap();
+        assert!(AnonCredProtocol::verify(&pp, &decoded).unwrap());
+
+        let mut json = serde_json::to_value(&signature).unwrap();
+        json["A"] = serde_json::to_value(&pk).unwrap()["w"].clone();
+        assert!(serde_json::from_value::<BBSPlusOgSignature<Bls12_381>>(json).is_err());
+    }
+}
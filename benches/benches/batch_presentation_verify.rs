@@ -0,0 +1,203 @@
+use ark_ec::pairing::Pairing;
+use ark_ff::UniformRand;
+use bbs_plus_16::anon_cred::AnonCredProtocol as BBSPlus16AnonCredProtocol;
+use bbs_plus_16::test_helpers::TestSetup;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use ps::anon_cred::{PSAnonCredProtocol, UserCred as PSUserCred};
+use ps_utt::anon_cred_improved::{AnonCredProtocolImproved, UserCred as PSUTTUserCred};
+use std::time::Duration;
+use utils::context::PresentationContext;
+use utils::envelope::CurveIdentifier;
+
+// Number of presentations verified together
+static BATCH_SIZES: [usize; 11] = [1, 2, 4, 8, 16, 32, 64, 128, 256, 512, 1024];
+
+const MESSAGE_COUNT: usize = 5;
+
+// One verifier context per presentation, as if each came from a different session
+fn contexts(count: usize) -> Vec<PresentationContext> {
+    (0..count as u64)
+        .map(|i| PresentationContext::new(b"verifier", &i.to_le_bytes(), 0))
+        .collect()
+}
+
+/// Batch verification against one verification per presentation, for PS
+fn benchmark_ps_batch_verify<E: Pairing + CurveIdentifier>(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!("ps_batch_verify_{}", E::CURVE_ID.name()));
+    group
+        .sample_size(10)
+        .measurement_time(Duration::from_secs(15));
+
+    let mut rng = ark_std::test_rng();
+    let protocol = PSAnonCredProtocol::<E>::new(MESSAGE_COUNT, &mut rng);
+    let user_cred = PSUserCred::<E>::new_random_messages(MESSAGE_COUNT);
+    let proof = protocol
+        .obtain(&user_cred, &mut rng)
+        .expect("Failed to create proof");
+    let response = protocol
+        .issue(&proof, &mut rng)
+        .expect("Failed to issue credential");
+    let signature = PSAnonCredProtocol::complete_signature(&response.blind_signature, &user_cred.t);
+
+    for &batch_size in &BATCH_SIZES {
+        println!("Benchmarking PS with {} presentations", batch_size);
+        let contexts = contexts(batch_size);
+        let presentations: Vec<_> = contexts
+            .iter()
+            .map(|context| {
+                protocol
+                    .show(&signature, &user_cred, context, &mut rng)
+                    .expect("Failed to show credential")
+            })
+            .collect();
+        let batch: Vec<_> = presentations.iter().zip(contexts.iter()).collect();
+
+        let individual_id =
+            BenchmarkId::from_parameter(format!("ps_verify_individual_{}", batch_size));
+        group.bench_function(individual_id, |b| {
+            b.iter(|| {
+                batch.iter().all(|(presentation, context)| {
+                    protocol
+                        .verify(presentation, context)
+                        .expect("Failed to verify credential")
+                })
+            })
+        });
+
+        let batch_id = BenchmarkId::from_parameter(format!("ps_verify_batch_{}", batch_size));
+        group.bench_function(batch_id, |b| {
+            b.iter(|| protocol.verify_batch(&batch, &mut rng))
+        });
+    }
+
+    group.finish();
+}
+
+/// Batch verification against one verification per presentation, for improved PS-UTT
+fn benchmark_ps_utt_improved_batch_verify<E: Pairing + CurveIdentifier>(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!(
+        "ps_utt_improved_batch_verify_{}",
+        E::CURVE_ID.name()
+    ));
+    group
+        .sample_size(10)
+        .measurement_time(Duration::from_secs(15));
+
+    let mut rng = ark_std::test_rng();
+    let protocol = AnonCredProtocolImproved::<E>::new(MESSAGE_COUNT, &mut rng);
+    let messages: Vec<E::ScalarField> = (0..MESSAGE_COUNT)
+        .map(|_| E::ScalarField::rand(&mut rng))
+        .collect();
+    let user_cred =
+        PSUTTUserCred::<E>::new(&protocol.pp, &messages, E::ScalarField::rand(&mut rng));
+    let proof = protocol
+        .obtain(&user_cred, &mut rng)
+        .expect("Failed to generate proof");
+    let signature = protocol
+        .issue(&user_cred.commitment.cmg2, &proof, &mut rng)
+        .expect("Failed to issue credential");
+
+    for &batch_size in &BATCH_SIZES {
+        println!(
+            "Benchmarking PS-UTT improved with {} presentations",
+            batch_size
+        );
+        let contexts = contexts(batch_size);
+        let presentations: Vec<_> = contexts
+            .iter()
+            .map(|context| {
+                protocol
+                    .show(&user_cred.commitment, &signature, context, &mut rng)
+                    .expect("Failed to show credential")
+            })
+            .collect();
+        let batch: Vec<_> = presentations.iter().zip(contexts.iter()).collect();
+
+        let individual_id = BenchmarkId::from_parameter(format!(
+            "ps_utt_improved_verify_individual_{}",
+            batch_size
+        ));
+        group.bench_function(individual_id, |b| {
+            b.iter(|| {
+                batch.iter().all(|(presentation, context)| {
+                    protocol
+                        .verify(presentation, context)
+                        .expect("Failed to verify credential")
+                })
+            })
+        });
+
+        let batch_id =
+            BenchmarkId::from_parameter(format!("ps_utt_improved_verify_batch_{}", batch_size));
+        group.bench_function(batch_id, |b| {
+            b.iter(|| protocol.verify_batch(&batch, &mut rng))
+        });
+    }
+
+    group.finish();
+}
+
+/// Batch verification against one verification per presentation, for BBS+ 2016
+fn benchmark_bbs_plus_16_batch_verify<E: Pairing + CurveIdentifier>(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!("bbs_plus_16_batch_verify_{}", E::CURVE_ID.name()));
+    group
+        .sample_size(10)
+        .measurement_time(Duration::from_secs(15));
+
+    let mut rng = ark_std::test_rng();
+    let setup = TestSetup::<E>::new(&mut rng, MESSAGE_COUNT);
+    let pvk = setup.pk.prepare(&setup.pp);
+
+    for &batch_size in &BATCH_SIZES {
+        println!("Benchmarking BBS+ 2016 with {} presentations", batch_size);
+        let contexts = contexts(batch_size);
+        let presentations: Vec<_> = contexts
+            .iter()
+            .map(|context| {
+                BBSPlus16AnonCredProtocol::show(
+                    &setup.pp,
+                    &setup.pk,
+                    &setup.signature,
+                    &setup.messages,
+                    context,
+                    &mut rng,
+                )
+                .expect("Failed to show credential")
+            })
+            .collect();
+        let batch: Vec<_> = presentations.iter().zip(contexts.iter()).collect();
+
+        let individual_id =
+            BenchmarkId::from_parameter(format!("bbs_plus_16_verify_individual_{}", batch_size));
+        group.bench_function(individual_id, |b| {
+            b.iter(|| {
+                batch.iter().all(|(presentation, context)| {
+                    BBSPlus16AnonCredProtocol::verify(&setup.pp, &setup.pk, presentation, context)
+                        .expect("Failed to verify credential")
+                })
+            })
+        });
+
+        let batch_id =
+            BenchmarkId::from_parameter(format!("bbs_plus_16_verify_batch_{}", batch_size));
+        group.bench_function(batch_id, |b| {
+            b.iter(|| BBSPlus16AnonCredProtocol::verify_batch(&setup.pp, &pvk, &batch, &mut rng))
+        });
+    }
+
+    group.finish();
+}
+
+/// runs the benchmarks once per enabled curve
+fn benchmark_batch_verify_curves(c: &mut Criterion) {
+    utils::for_each_curve!(benchmark_ps_batch_verify(c));
+    utils::for_each_curve!(benchmark_ps_utt_improved_batch_verify(c));
+    utils::for_each_curve!(benchmark_bbs_plus_16_batch_verify(c));
+}
+
+criterion_group!(
+    name = benches;
+    config = Criterion::default();
+    targets = benchmark_batch_verify_curves
+);
+criterion_main!(benches);
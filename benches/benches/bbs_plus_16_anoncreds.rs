@@ -1,14 +1,15 @@
-use ark_bls12_381::Bls12_381;
 use ark_ec::pairing::Pairing;
 use ark_ff::UniformRand;
 use bbs_plus_16::anon_cred::{AnonCredProtocol, ShowCredential};
 use bbs_plus_16::test_helpers::TestSetup;
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
 use std::time::Duration;
+use utils::context::PresentationContext;
+use utils::envelope::CurveIdentifier;
 
 /// Benchmark function for AnonCred protocol with different message sizes
-fn benchmark_anoncred_protocol(c: &mut Criterion) {
-    let mut group = c.benchmark_group("bbs_plus_16_anoncreds");
+fn benchmark_anoncred_protocol<E: Pairing + CurveIdentifier>(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!("bbs_plus_16_anoncreds_{}", E::CURVE_ID.name()));
     println!("Starting BBSPlus AnonCred protocol benchmarks");
 
     // Configure benchmark parameters
@@ -24,7 +25,7 @@ fn benchmark_anoncred_protocol(c: &mut Criterion) {
 
         // Setup phase using TestSetup
         let mut rng = ark_std::test_rng();
-        let setup = TestSetup::<Bls12_381>::new(&mut rng, msg_size);
+        let setup = TestSetup::<E>::new(&mut rng, msg_size);
 
         // Pre-generate data for the benchmarks
         let (pre_commitment, s_prime) =
@@ -36,6 +37,7 @@ fn benchmark_anoncred_protocol(c: &mut Criterion) {
                 .expect("Failed to issue credential");
 
         let signature = AnonCredProtocol::complete_signature(&s_prime, &issuer_response);
+        let context = PresentationContext::new(b"verifier", b"nonce", 0);
 
         // Also use the existing signature from TestSetup for show/verify
         let show_cred = AnonCredProtocol::show(
@@ -43,6 +45,7 @@ fn benchmark_anoncred_protocol(c: &mut Criterion) {
             &setup.pk,
             &setup.signature,
             &setup.messages,
+            &context,
             &mut rng,
         )
         .expect("Failed to show credential");
@@ -77,6 +80,7 @@ fn benchmark_anoncred_protocol(c: &mut Criterion) {
                     &setup.pk,
                     &setup.signature,
                     &setup.messages,
+                    &context,
                     &mut rng,
                 )
                 .expect("Failed to show")
@@ -88,7 +92,7 @@ fn benchmark_anoncred_protocol(c: &mut Criterion) {
             BenchmarkId::from_parameter(format!("bbs_plus_16_verify_messages_{}", msg_size));
         group.bench_function(verify_id, |b| {
             b.iter(|| {
-                AnonCredProtocol::verify(&setup.pp, &setup.pk, &show_cred)
+                AnonCredProtocol::verify(&setup.pp, &setup.pk, &show_cred, &context)
                     .expect("Failed to verify")
             })
         });
@@ -97,9 +101,14 @@ fn benchmark_anoncred_protocol(c: &mut Criterion) {
     group.finish();
 }
 
+/// runs the benchmarks once per enabled curve
+fn benchmark_anoncred_protocol_curves(c: &mut Criterion) {
+    utils::for_each_curve!(benchmark_anoncred_protocol(c));
+}
+
 criterion_group!(
     name = benches;
     config = Criterion::default();
-    targets = benchmark_anoncred_protocol
+    targets = benchmark_anoncred_protocol_curves
 );
 criterion_main!(benches);
@@ -1,14 +1,14 @@
-use ark_bls12_381::Bls12_381;
 use ark_ec::pairing::Pairing;
 use ark_ff::UniformRand;
 use bbs_plus_og::anon_cred::{AnonCredProtocol, IssuerResponse, ShowCredential};
 use bbs_plus_og::test_helpers::TestSetup;
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
 use std::time::Duration;
+use utils::envelope::CurveIdentifier;
 
 /// Benchmark function for BBS+ OG AnonCred protocol with different message sizes
-fn benchmark_bbs_plus_og_anoncred_protocol(c: &mut Criterion) {
-    let mut group = c.benchmark_group("bbs_plus_og_anoncreds");
+fn benchmark_bbs_plus_og_anoncred_protocol<E: Pairing + CurveIdentifier>(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!("bbs_plus_og_anoncreds_{}", E::CURVE_ID.name()));
     println!("Starting BBS+ OG AnonCred protocol benchmarks");
 
     // Configure benchmark parameters
@@ -24,7 +24,7 @@ fn benchmark_bbs_plus_og_anoncred_protocol(c: &mut Criterion) {
 
         // Setup phase using TestSetup
         let mut rng = ark_std::test_rng();
-        let setup = TestSetup::<Bls12_381>::new(&mut rng, msg_size);
+        let setup = TestSetup::<E>::new(&mut rng, msg_size);
 
         // Pre-generate data for the benchmarks
         let (pre_commitment, s_prime) =
@@ -94,9 +94,14 @@ fn benchmark_bbs_plus_og_anoncred_protocol(c: &mut Criterion) {
     group.finish();
 }
 
+/// runs the benchmarks once per enabled curve
+fn benchmark_bbs_plus_og_anoncred_protocol_curves(c: &mut Criterion) {
+    utils::for_each_curve!(benchmark_bbs_plus_og_anoncred_protocol(c));
+}
+
 criterion_group!(
     name = benches;
     config = Criterion::default();
-    targets = benchmark_bbs_plus_og_anoncred_protocol
+    targets = benchmark_bbs_plus_og_anoncred_protocol_curves
 );
 criterion_main!(benches);
@@ -0,0 +1,173 @@
+use ark_ec::pairing::Pairing;
+use ark_ff::UniformRand;
+use cmz14::anon_cred::AnonCredProtocol;
+use cmz14::anon_cred_ddh::DdhAnonCredProtocol;
+use cmz14::keygen::{gen_ddh_keys, gen_keys};
+use cmz14::publicparams::PublicParams;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::time::Duration;
+use utils::context::PresentationContext;
+use utils::envelope::CurveIdentifier;
+
+/// Benchmark function for CMZ14 keyed-verification credentials over G1 of the pairing curve, so
+/// the numbers compare directly with the pairing-based schemes
+fn benchmark_anoncred_protocol<E: Pairing + CurveIdentifier>(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!("cmz14_anoncreds_{}", E::CURVE_ID.name()));
+    println!("Starting CMZ14 AnonCred protocol benchmarks");
+
+    // Configure benchmark parameters
+    group
+        .sample_size(10)
+        .measurement_time(Duration::from_secs(15));
+
+    // Message sizes to benchmark
+    static MESSAGE_SIZES: [usize; 8] = [2, 5, 10, 15, 20, 30, 40, 128];
+
+    for &msg_size in &MESSAGE_SIZES {
+        println!("Benchmarking with {} messages", msg_size);
+
+        let mut rng = ark_std::test_rng();
+        let pp = PublicParams::<E::G1Affine>::new(&msg_size, &mut rng);
+        let (sk, iparams) = gen_keys(&pp, &mut rng);
+        let messages: Vec<E::ScalarField> = (0..msg_size)
+            .map(|_| E::ScalarField::rand(&mut rng))
+            .collect();
+        // every attribute hidden at issuance and at show, like the other schemes
+        let hidden: Vec<usize> = (0..msg_size).collect();
+
+        // Pre-generate data for the benchmarks
+        let (request, key) = AnonCredProtocol::obtain(&pp, &messages, &hidden, &mut rng)
+            .expect("Failed to create request");
+        let response = AnonCredProtocol::issue(&pp, &sk, &iparams, &request, &mut rng)
+            .expect("Failed to issue credential");
+        let credential = AnonCredProtocol::complete_credential(
+            &pp, &iparams, &request, &response, &key, &messages,
+        )
+        .expect("Failed to complete credential");
+        let context = PresentationContext::new(b"verifier", b"nonce", 0);
+        let show_cred = AnonCredProtocol::show(&pp, &iparams, &credential, &[], &context, &mut rng)
+            .expect("Failed to show credential");
+
+        // Benchmark Obtain
+        let obtain_id = BenchmarkId::from_parameter(format!("cmz14_obtain_messages_{}", msg_size));
+        group.bench_function(obtain_id, |b| {
+            b.iter(|| {
+                AnonCredProtocol::obtain(&pp, &messages, &hidden, &mut rng)
+                    .expect("Failed to obtain")
+            })
+        });
+
+        // Benchmark Issue
+        let issue_id = BenchmarkId::from_parameter(format!("cmz14_issue_messages_{}", msg_size));
+        group.bench_function(issue_id, |b| {
+            b.iter(|| {
+                AnonCredProtocol::issue(&pp, &sk, &iparams, &request, &mut rng)
+                    .expect("Failed to issue")
+            })
+        });
+
+        // Benchmark Show
+        let show_id = BenchmarkId::from_parameter(format!("cmz14_show_messages_{}", msg_size));
+        group.bench_function(show_id, |b| {
+            b.iter(|| {
+                AnonCredProtocol::show(&pp, &iparams, &credential, &[], &context, &mut rng)
+                    .expect("Failed to show")
+            })
+        });
+
+        // Benchmark Verify
+        let verify_id = BenchmarkId::from_parameter(format!("cmz14_verify_messages_{}", msg_size));
+        group.bench_function(verify_id, |b| {
+            b.iter(|| {
+                AnonCredProtocol::verify(&pp, &sk, &iparams, &show_cred, &context)
+                    .expect("Failed to verify")
+            })
+        });
+    }
+
+    group.finish();
+}
+
+/// Benchmark function for CMZ14 MAC_DDH credentials, obtain is shared with MAC_GGM
+fn benchmark_ddh_anoncred_protocol<E: Pairing + CurveIdentifier>(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!("cmz14_ddh_anoncreds_{}", E::CURVE_ID.name()));
+    println!("Starting CMZ14 MAC_DDH AnonCred protocol benchmarks");
+
+    // Configure benchmark parameters
+    group
+        .sample_size(10)
+        .measurement_time(Duration::from_secs(15));
+
+    // Message sizes to benchmark
+    static MESSAGE_SIZES: [usize; 8] = [2, 5, 10, 15, 20, 30, 40, 128];
+
+    for &msg_size in &MESSAGE_SIZES {
+        println!("Benchmarking with {} messages", msg_size);
+
+        let mut rng = ark_std::test_rng();
+        let pp = PublicParams::<E::G1Affine>::new(&msg_size, &mut rng);
+        let (sk, iparams) = gen_ddh_keys(&pp, &mut rng);
+        let messages: Vec<E::ScalarField> = (0..msg_size)
+            .map(|_| E::ScalarField::rand(&mut rng))
+            .collect();
+        let hidden: Vec<usize> = (0..msg_size).collect();
+
+        // Pre-generate data for the benchmarks
+        let (request, key) = AnonCredProtocol::obtain(&pp, &messages, &hidden, &mut rng)
+            .expect("Failed to create request");
+        let response = DdhAnonCredProtocol::issue(&pp, &sk, &iparams, &request, &mut rng)
+            .expect("Failed to issue credential");
+        let credential = DdhAnonCredProtocol::complete_credential(
+            &pp, &iparams, &request, &response, &key, &messages,
+        )
+        .expect("Failed to complete credential");
+        let context = PresentationContext::new(b"verifier", b"nonce", 0);
+        let show_cred =
+            DdhAnonCredProtocol::show(&pp, &iparams, &credential, &[], &context, &mut rng)
+                .expect("Failed to show credential");
+
+        // Benchmark Issue
+        let issue_id =
+            BenchmarkId::from_parameter(format!("cmz14_ddh_issue_messages_{}", msg_size));
+        group.bench_function(issue_id, |b| {
+            b.iter(|| {
+                DdhAnonCredProtocol::issue(&pp, &sk, &iparams, &request, &mut rng)
+                    .expect("Failed to issue")
+            })
+        });
+
+        // Benchmark Show
+        let show_id = BenchmarkId::from_parameter(format!("cmz14_ddh_show_messages_{}", msg_size));
+        group.bench_function(show_id, |b| {
+            b.iter(|| {
+                DdhAnonCredProtocol::show(&pp, &iparams, &credential, &[], &context, &mut rng)
+                    .expect("Failed to show")
+            })
+        });
+
+        // Benchmark Verify
+        let verify_id =
+            BenchmarkId::from_parameter(format!("cmz14_ddh_verify_messages_{}", msg_size));
+        group.bench_function(verify_id, |b| {
+            b.iter(|| {
+                DdhAnonCredProtocol::verify(&pp, &sk, &iparams, &show_cred, &context)
+                    .expect("Failed to verify")
+            })
+        });
+    }
+
+    group.finish();
+}
+
+/// runs the benchmarks once per enabled curve
+fn benchmark_anoncred_protocol_curves(c: &mut Criterion) {
+    utils::for_each_curve!(benchmark_anoncred_protocol(c));
+    utils::for_each_curve!(benchmark_ddh_anoncred_protocol(c));
+}
+
+criterion_group!(
+    name = benches;
+    config = Criterion::default();
+    targets = benchmark_anoncred_protocol_curves
+);
+criterion_main!(benches);
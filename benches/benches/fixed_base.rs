@@ -0,0 +1,115 @@
+use ark_ec::pairing::Pairing;
+use ark_ff::UniformRand;
+use bbs_plus_16::anon_cred::AnonCredProtocol as BBSPlus16AnonCredProtocol;
+use bbs_plus_16::signature::BBSPlus16Signature;
+use bbs_plus_16::test_helpers::TestSetup;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use ps_utt::commitment::Commitment;
+use ps_utt::publicparams::PublicParams;
+use std::time::Duration;
+use utils::context::PresentationContext;
+use utils::envelope::CurveIdentifier;
+
+// Message counts, one table is built per generator
+static MESSAGE_COUNTS: [usize; 3] = [2, 5, 10];
+
+/// Signing and showing with `msm_unchecked` against fixed-base tables, for BBS+ 2016
+fn benchmark_bbs_plus_16_fixed_base<E: Pairing + CurveIdentifier>(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!("bbs_plus_16_fixed_base_{}", E::CURVE_ID.name()));
+    group
+        .sample_size(10)
+        .measurement_time(Duration::from_secs(5));
+
+    let mut rng = ark_std::test_rng();
+    for &message_count in &MESSAGE_COUNTS {
+        let setup = TestSetup::<E>::new(&mut rng, message_count);
+        let tables = setup.pk.precompute();
+        let context = PresentationContext::new(b"verifier", b"nonce", 0);
+
+        group.bench_function(BenchmarkId::new("sign", message_count), |b| {
+            b.iter(|| {
+                BBSPlus16Signature::sign(&setup.pp, &setup.sk, &setup.pk, &mut rng, &setup.messages)
+            })
+        });
+        group.bench_function(BenchmarkId::new("sign_with_tables", message_count), |b| {
+            b.iter(|| {
+                BBSPlus16Signature::sign_with_tables(
+                    &setup.pp,
+                    &setup.sk,
+                    &tables,
+                    &mut rng,
+                    &setup.messages,
+                )
+            })
+        });
+        group.bench_function(BenchmarkId::new("show", message_count), |b| {
+            b.iter(|| {
+                BBSPlus16AnonCredProtocol::show(
+                    &setup.pp,
+                    &setup.pk,
+                    &setup.signature,
+                    &setup.messages,
+                    &context,
+                    &mut rng,
+                )
+                .expect("Failed to show credential")
+            })
+        });
+        group.bench_function(BenchmarkId::new("show_with_tables", message_count), |b| {
+            b.iter(|| {
+                BBSPlus16AnonCredProtocol::show_with_tables(
+                    &setup.pp,
+                    &tables,
+                    &setup.signature,
+                    &setup.messages,
+                    &context,
+                    &mut rng,
+                )
+                .expect("Failed to show credential")
+            })
+        });
+    }
+
+    group.finish();
+}
+
+/// Committing with `msm_unchecked` against fixed-base tables, for PS-UTT
+fn benchmark_ps_utt_fixed_base<E: Pairing + CurveIdentifier>(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!("ps_utt_fixed_base_{}", E::CURVE_ID.name()));
+    group
+        .sample_size(10)
+        .measurement_time(Duration::from_secs(5));
+
+    let mut rng = ark_std::test_rng();
+    for &message_count in &MESSAGE_COUNTS {
+        let context = E::ScalarField::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&message_count, &context, &mut rng);
+        let tables = pp.precompute();
+        let messages: Vec<E::ScalarField> = (0..message_count)
+            .map(|_| E::ScalarField::rand(&mut rng))
+            .collect();
+        let r = E::ScalarField::rand(&mut rng);
+
+        group.bench_function(BenchmarkId::new("commit", message_count), |b| {
+            b.iter(|| Commitment::new(&pp, &messages, &r))
+        });
+        group.bench_function(BenchmarkId::new("commit_with_tables", message_count), |b| {
+            b.iter(|| Commitment::new_with_tables(&pp, &tables, &messages, &r))
+        });
+    }
+
+    group.finish();
+}
+
+/// runs the benchmarks once per enabled curve
+fn benchmark_fixed_base_curves(c: &mut Criterion) {
+    utils::for_each_curve!(benchmark_bbs_plus_16_fixed_base(c));
+    utils::for_each_curve!(benchmark_ps_utt_fixed_base(c));
+}
+
+criterion_group!(
+    name = benches;
+    config = Criterion::default();
+    targets = benchmark_fixed_base_curves
+);
+criterion_main!(benches);
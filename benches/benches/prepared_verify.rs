@@ -0,0 +1,164 @@
+use ark_ec::pairing::Pairing;
+use ark_ff::UniformRand;
+use bbs_plus_16::anon_cred::AnonCredProtocol as BBSPlus16AnonCredProtocol;
+use bbs_plus_16::test_helpers::TestSetup;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use mimc_abc::credential::Credential as MimcCredential;
+use mimc_abc::protocol::MimcAbc;
+use ps::anon_cred::{PSAnonCredProtocol, UserCred as PSUserCred};
+use ps::proofsystem::SignatureProofs;
+use std::time::Duration;
+use utils::context::PresentationContext;
+use utils::envelope::CurveIdentifier;
+
+// Message counts, the PS key prepares one G2 element per message
+static MESSAGE_COUNTS: [usize; 3] = [2, 5, 10];
+
+fn context() -> PresentationContext {
+    PresentationContext::new(b"verifier", b"nonce", 0)
+}
+
+/// Presentation verification with the key prepared per call against prepared once, for PS
+fn benchmark_ps_prepared_verify<E: Pairing + CurveIdentifier>(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!("ps_prepared_verify_{}", E::CURVE_ID.name()));
+    group
+        .sample_size(10)
+        .measurement_time(Duration::from_secs(5));
+
+    let mut rng = ark_std::test_rng();
+    for &message_count in &MESSAGE_COUNTS {
+        let protocol = PSAnonCredProtocol::<E>::new(message_count, &mut rng);
+        let user_cred = PSUserCred::<E>::new_random_messages(message_count);
+        let proof = protocol
+            .obtain(&user_cred, &mut rng)
+            .expect("Failed to create proof");
+        let response = protocol
+            .issue(&proof, &mut rng)
+            .expect("Failed to issue credential");
+        let signature =
+            PSAnonCredProtocol::complete_signature(&response.blind_signature, &user_cred.t);
+        let context = context();
+        let presentation = protocol
+            .show(&signature, &user_cred, &context, &mut rng)
+            .expect("Failed to show credential");
+        let pvk = protocol.pk.prepare();
+
+        group.bench_function(BenchmarkId::new("verify", message_count), |b| {
+            b.iter(|| {
+                SignatureProofs::verify_knowledge(
+                    &protocol.pp,
+                    &protocol.pk,
+                    &presentation.proof,
+                    &presentation.disclosed,
+                    &context,
+                )
+            })
+        });
+        group.bench_function(BenchmarkId::new("verify_prepared", message_count), |b| {
+            b.iter(|| {
+                SignatureProofs::verify_knowledge_prepared(
+                    &protocol.pp,
+                    &pvk,
+                    &presentation.proof,
+                    &presentation.disclosed,
+                    &context,
+                )
+            })
+        });
+    }
+
+    group.finish();
+}
+
+/// Presentation verification with the key prepared per call against prepared once, for BBS+ 2016
+fn benchmark_bbs_plus_16_prepared_verify<E: Pairing + CurveIdentifier>(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!(
+        "bbs_plus_16_prepared_verify_{}",
+        E::CURVE_ID.name()
+    ));
+    group
+        .sample_size(10)
+        .measurement_time(Duration::from_secs(5));
+
+    let mut rng = ark_std::test_rng();
+    for &message_count in &MESSAGE_COUNTS {
+        let setup = TestSetup::<E>::new(&mut rng, message_count);
+        let context = context();
+        let presentation = BBSPlus16AnonCredProtocol::show(
+            &setup.pp,
+            &setup.pk,
+            &setup.signature,
+            &setup.messages,
+            &context,
+            &mut rng,
+        )
+        .expect("Failed to show credential");
+        let pvk = setup.pk.prepare(&setup.pp);
+
+        group.bench_function(BenchmarkId::new("verify", message_count), |b| {
+            b.iter(|| {
+                BBSPlus16AnonCredProtocol::verify(&setup.pp, &setup.pk, &presentation, &context)
+                    .expect("Failed to verify credential")
+            })
+        });
+        group.bench_function(BenchmarkId::new("verify_prepared", message_count), |b| {
+            b.iter(|| {
+                BBSPlus16AnonCredProtocol::verify_prepared(&setup.pp, &pvk, &presentation, &context)
+                    .expect("Failed to verify credential")
+            })
+        });
+    }
+
+    group.finish();
+}
+
+/// Presentation verification with the key prepared per call against prepared once, for MIMC-ABC
+fn benchmark_mimc_abc_prepared_verify<E: Pairing + CurveIdentifier>(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!("mimc_abc_prepared_verify_{}", E::CURVE_ID.name()));
+    group
+        .sample_size(10)
+        .measurement_time(Duration::from_secs(5));
+
+    let mut rng = ark_std::test_rng();
+    for &message_count in &MESSAGE_COUNTS {
+        let (protocol, sk, vk) = MimcAbc::<E>::setup(message_count, &mut rng);
+        let attributes: Vec<E::ScalarField> = (0..message_count)
+            .map(|_| E::ScalarField::rand(&mut rng))
+            .collect();
+        let r = E::ScalarField::rand(&mut rng);
+        let mut credential = MimcCredential::new(&protocol.ck, &protocol.pp, &attributes, r);
+        let proof = protocol.obtain(&credential, &mut rng);
+        let response = protocol
+            .issue(&proof, &sk, &mut rng)
+            .expect("Failed to issue credential");
+        credential.add_issuer_response(response);
+        let context = context();
+        let presentation = protocol
+            .show(&credential, &context, &mut rng)
+            .expect("Failed to show credential");
+        let pvk = vk.prepare(&protocol.pp);
+
+        group.bench_function(BenchmarkId::new("verify", message_count), |b| {
+            b.iter(|| presentation.verify_with_context(&protocol.pp, &vk, &context))
+        });
+        group.bench_function(BenchmarkId::new("verify_prepared", message_count), |b| {
+            b.iter(|| protocol.verify_prepared(&presentation, &pvk, &context))
+        });
+    }
+
+    group.finish();
+}
+
+/// runs the benchmarks once per enabled curve
+fn benchmark_prepared_verify_curves(c: &mut Criterion) {
+    utils::for_each_curve!(benchmark_ps_prepared_verify(c));
+    utils::for_each_curve!(benchmark_bbs_plus_16_prepared_verify(c));
+    utils::for_each_curve!(benchmark_mimc_abc_prepared_verify(c));
+}
+
+criterion_group!(
+    name = benches;
+    config = Criterion::default();
+    targets = benchmark_prepared_verify_curves
+);
+criterion_main!(benches);
@@ -1,10 +1,11 @@
-use ark_bls12_381::Bls12_381;
 use ark_ec::pairing::Pairing;
 use ark_ff::UniformRand;
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
 use ps::anon_cred::{PSAnonCredProtocol, ShowCredential, UserCred};
 use ps::signature::PSSignature;
 use std::time::Duration;
+use utils::context::PresentationContext;
+use utils::envelope::CurveIdentifier;
 
 // Test setup structure for PS AnonCred benchmarks
 struct PSAnonCredTestSetup<E: Pairing> {
@@ -13,48 +14,53 @@ struct PSAnonCredTestSetup<E: Pairing> {
     proof: Vec<u8>,
     blind_signature: PSSignature<E>,
     signature: PSSignature<E>,
-    presentation: ShowCredential,
+    presentation: ShowCredential<E>,
+    context: PresentationContext,
 }
 
 // Initialize a protocol test setup
-fn setup_ps_anoncred_protocol(msg_size: usize) -> PSAnonCredTestSetup<Bls12_381> {
+fn setup_ps_anoncred_protocol<E: Pairing>(msg_size: usize) -> PSAnonCredTestSetup<E> {
     let mut rng = ark_std::test_rng();
 
     // Create protocol instance
-    let protocol = PSAnonCredProtocol::<Bls12_381>::new(msg_size, &mut rng);
+    let protocol = PSAnonCredProtocol::<E>::new(msg_size, &mut rng);
 
     // Generate user credentials
-    let user_cred = UserCred::<Bls12_381>::new_random_messages(msg_size);
+    let user_cred = UserCred::<E>::new_random_messages(msg_size);
 
     // Generate proof
-    let proof = protocol.obtain(&user_cred).expect("Failed to create proof");
+    let proof = protocol
+        .obtain(&user_cred, &mut rng)
+        .expect("Failed to create proof");
 
     // Issue credential
-    let blind_signature = protocol
+    let response = protocol
         .issue(&proof, &mut rng)
         .expect("Failed to issue credential");
 
     // Unblind signature
-    let signature = PSAnonCredProtocol::complete_signature(&blind_signature, &user_cred.t);
+    let signature = PSAnonCredProtocol::complete_signature(&response.blind_signature, &user_cred.t);
 
-    // Create presentation
+    // Create presentation for a fixed verifier context
+    let context = PresentationContext::new(b"verifier", b"nonce", 0);
     let presentation = protocol
-        .show(&signature, &user_cred, &mut rng)
+        .show(&signature, &user_cred, &context, &mut rng)
         .expect("Failed to show credential");
 
     PSAnonCredTestSetup {
         protocol,
         user_cred,
         proof,
-        blind_signature,
+        blind_signature: response.blind_signature,
         signature,
         presentation,
+        context,
     }
 }
 
 /// Benchmark function for PS AnonCred protocol
-fn benchmark_ps_anoncred_protocol(c: &mut Criterion) {
-    let mut group = c.benchmark_group("ps_anoncreds");
+fn benchmark_ps_anoncred_protocol<E: Pairing + CurveIdentifier>(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!("ps_anoncreds_{}", E::CURVE_ID.name()));
     println!("Starting PS AnonCred protocol benchmarks");
 
     // Configure benchmark parameters
@@ -69,15 +75,16 @@ fn benchmark_ps_anoncred_protocol(c: &mut Criterion) {
         println!("Benchmarking with {} messages", msg_size);
 
         // Set up test environment
-        let setup = setup_ps_anoncred_protocol(msg_size);
+        let setup = setup_ps_anoncred_protocol::<E>(msg_size);
 
         // Benchmark Obtain
         let obtain_id = BenchmarkId::from_parameter(format!("ps_obtain_messages_{}", msg_size));
         group.bench_function(obtain_id, |b| {
+            let mut rng = ark_std::test_rng();
             b.iter(|| {
                 setup
                     .protocol
-                    .obtain(&setup.user_cred)
+                    .obtain(&setup.user_cred, &mut rng)
                     .expect("Failed to obtain credential")
             })
         });
@@ -101,7 +108,7 @@ fn benchmark_ps_anoncred_protocol(c: &mut Criterion) {
             b.iter(|| {
                 setup
                     .protocol
-                    .show(&setup.signature, &setup.user_cred, &mut rng)
+                    .show(&setup.signature, &setup.user_cred, &setup.context, &mut rng)
                     .expect("Failed to show credential")
             })
         });
@@ -112,7 +119,7 @@ fn benchmark_ps_anoncred_protocol(c: &mut Criterion) {
             b.iter(|| {
                 setup
                     .protocol
-                    .verify(&setup.presentation)
+                    .verify(&setup.presentation, &setup.context)
                     .expect("Failed to verify credential")
             })
         });
@@ -121,9 +128,14 @@ fn benchmark_ps_anoncred_protocol(c: &mut Criterion) {
     group.finish();
 }
 
+/// runs the benchmarks once per enabled curve
+fn benchmark_ps_anoncred_protocol_curves(c: &mut Criterion) {
+    utils::for_each_curve!(benchmark_ps_anoncred_protocol(c));
+}
+
 criterion_group!(
     name = benches;
     config = Criterion::default();
-    targets = benchmark_ps_anoncred_protocol
+    targets = benchmark_ps_anoncred_protocol_curves
 );
 criterion_main!(benches);
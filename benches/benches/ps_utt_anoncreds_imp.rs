@@ -1,10 +1,11 @@
-use ark_bls12_381::Bls12_381;
 use ark_ec::pairing::Pairing;
 use ark_ff::UniformRand;
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
 use ps_utt::anon_cred_improved::{AnonCredProtocolImproved, ShowCredentialImproved, UserCred};
 use ps_utt::signature::PSUTTSignatureImproved;
 use std::time::Duration;
+use utils::context::PresentationContext;
+use utils::envelope::CurveIdentifier;
 
 // Improved protocol test setup structure
 struct ImprovedTestSetup<E: Pairing> {
@@ -13,29 +14,33 @@ struct ImprovedTestSetup<E: Pairing> {
     proof: Vec<u8>,
     signature: PSUTTSignatureImproved<E>,
     presentation: ShowCredentialImproved<E>,
+    context: PresentationContext,
 }
 
 // Initialize an improved protocol test setup
-fn setup_improved_protocol(msg_size: usize) -> ImprovedTestSetup<Bls12_381> {
+fn setup_improved_protocol<E: Pairing>(msg_size: usize) -> ImprovedTestSetup<E> {
     let mut rng = ark_std::test_rng();
 
     // Create protocol instance
-    let protocol = AnonCredProtocolImproved::<Bls12_381>::new(msg_size, &mut rng);
+    let protocol = AnonCredProtocolImproved::<E>::new(msg_size, &mut rng);
 
     // Generate user credentials
-    let user_cred = UserCred::<Bls12_381>::new_random_messages(&protocol.pp);
+    let user_cred = UserCred::<E>::new_random_messages(&protocol.pp);
 
     // Generate proof
-    let proof = protocol.obtain(&user_cred).expect("Failed to create proof");
+    let proof = protocol
+        .obtain(&user_cred, &mut rng)
+        .expect("Failed to create proof");
 
     // Issue credential
     let signature = protocol
-        .issue(&user_cred.commitment.cmg2, &proof)
+        .issue(&user_cred.commitment.cmg2, &proof, &mut rng)
         .expect("Failed to issue credential");
 
-    // Create presentation
+    // Create presentation for a fixed verifier context
+    let context = PresentationContext::new(b"verifier", b"nonce", 0);
     let presentation = protocol
-        .show(&user_cred.commitment, &signature, &mut rng)
+        .show(&user_cred.commitment, &signature, &context, &mut rng)
         .expect("Failed to show credential");
 
     ImprovedTestSetup {
@@ -44,12 +49,13 @@ fn setup_improved_protocol(msg_size: usize) -> ImprovedTestSetup<Bls12_381> {
         proof,
         signature,
         presentation,
+        context,
     }
 }
 
 /// Benchmark function for improved PS-UTT protocol
-fn benchmark_psutt_imp(c: &mut Criterion) {
-    let mut group = c.benchmark_group("ps_utt_anoncreds_imp");
+fn benchmark_psutt_imp<E: Pairing + CurveIdentifier>(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!("ps_utt_anoncreds_imp_{}", E::CURVE_ID.name()));
     println!("Starting PS-UTT Improved AnonCred protocol benchmarks");
 
     // Configure benchmark parameters
@@ -64,15 +70,16 @@ fn benchmark_psutt_imp(c: &mut Criterion) {
         println!("Benchmarking with {} messages", msg_size);
 
         // Set up test environment for improved protocol
-        let imp_setup = setup_improved_protocol(msg_size);
+        let imp_setup = setup_improved_protocol::<E>(msg_size);
 
         // Benchmark Obtain
         let obtain_id = BenchmarkId::from_parameter(format!("obtain_messages_{}", msg_size));
         group.bench_function(obtain_id, |b| {
+            let mut rng = ark_std::test_rng();
             b.iter(|| {
                 imp_setup
                     .protocol
-                    .obtain(&imp_setup.user_cred)
+                    .obtain(&imp_setup.user_cred, &mut rng)
                     .expect("Failed to obtain credential")
             })
         });
@@ -80,10 +87,15 @@ fn benchmark_psutt_imp(c: &mut Criterion) {
         // Benchmark Issue
         let issue_id = BenchmarkId::from_parameter(format!("issue_messages_{}", msg_size));
         group.bench_function(issue_id, |b| {
+            let mut rng = ark_std::test_rng();
             b.iter(|| {
                 imp_setup
                     .protocol
-                    .issue(&imp_setup.user_cred.commitment.cmg2, &imp_setup.proof)
+                    .issue(
+                        &imp_setup.user_cred.commitment.cmg2,
+                        &imp_setup.proof,
+                        &mut rng,
+                    )
                     .expect("Failed to issue credential")
             })
         });
@@ -98,6 +110,7 @@ fn benchmark_psutt_imp(c: &mut Criterion) {
                     .show(
                         &imp_setup.user_cred.commitment,
                         &imp_setup.signature,
+                        &imp_setup.context,
                         &mut rng,
                     )
                     .expect("Failed to show credential")
@@ -110,7 +123,7 @@ fn benchmark_psutt_imp(c: &mut Criterion) {
             b.iter(|| {
                 imp_setup
                     .protocol
-                    .verify(&imp_setup.presentation)
+                    .verify(&imp_setup.presentation, &imp_setup.context)
                     .expect("Failed to verify credential")
             })
         });
@@ -119,9 +132,14 @@ fn benchmark_psutt_imp(c: &mut Criterion) {
     group.finish();
 }
 
+/// runs the benchmarks once per enabled curve
+fn benchmark_psutt_imp_curves(c: &mut Criterion) {
+    utils::for_each_curve!(benchmark_psutt_imp(c));
+}
+
 criterion_group!(
     name = benches;
     config = Criterion::default();
-    targets = benchmark_psutt_imp
+    targets = benchmark_psutt_imp_curves
 );
 criterion_main!(benches);
@@ -1,10 +1,11 @@
-use ark_bls12_381::Bls12_381;
 use ark_ec::pairing::Pairing;
 use ark_ff::UniformRand;
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
 use ps_utt::anon_cred::{AnonCredProtocol, ShowCredential, UserCred};
 use ps_utt::signature::PSUTTSignature;
 use std::time::Duration;
+use utils::context::PresentationContext;
+use utils::envelope::CurveIdentifier;
 
 // Standard protocol test setup structure
 struct StandardTestSetup<E: Pairing> {
@@ -13,27 +14,33 @@ struct StandardTestSetup<E: Pairing> {
     proof: Vec<u8>,
     signature: PSUTTSignature<E>,
     presentation: ShowCredential<E>,
+    context: PresentationContext,
 }
 
 // Initialize a standard protocol test setup
-fn setup_standard_protocol(msg_size: usize) -> StandardTestSetup<Bls12_381> {
+fn setup_standard_protocol<E: Pairing>(msg_size: usize) -> StandardTestSetup<E> {
     let mut rng = ark_std::test_rng();
 
     // Create protocol instance
-    let protocol = AnonCredProtocol::<Bls12_381>::new(msg_size, &mut rng);
+    let protocol = AnonCredProtocol::<E>::new(msg_size, &mut rng);
 
     // Generate user credentials
-    let user_cred = UserCred::<Bls12_381>::new_random_messages(&protocol.pp);
+    let user_cred = UserCred::<E>::new_random_messages(&protocol.pp);
 
     // Generate proof
-    let proof = protocol.obtain(&user_cred).expect("Failed to create proof");
+    let proof = protocol
+        .obtain(&user_cred, &mut rng)
+        .expect("Failed to create proof");
 
     // Issue credential
-    let signature = protocol.issue(&proof).expect("Failed to issue credential");
+    let signature = protocol
+        .issue(&proof, &mut rng)
+        .expect("Failed to issue credential");
 
-    // Create presentation
+    // Create presentation for a fixed verifier context
+    let context = PresentationContext::new(b"verifier", b"nonce", 0);
     let presentation = protocol
-        .show(&user_cred.commitment, &signature, &mut rng)
+        .show(&user_cred.commitment, &signature, &context, &mut rng)
         .expect("Failed to show credential");
 
     StandardTestSetup {
@@ -42,12 +49,13 @@ fn setup_standard_protocol(msg_size: usize) -> StandardTestSetup<Bls12_381> {
         proof,
         signature,
         presentation,
+        context,
     }
 }
 
 /// Benchmark function for standard PS-UTT protocol
-fn benchmark_psutt_std(c: &mut Criterion) {
-    let mut group = c.benchmark_group("ps_utt_anoncreds_std");
+fn benchmark_psutt_std<E: Pairing + CurveIdentifier>(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!("ps_utt_anoncreds_std_{}", E::CURVE_ID.name()));
     println!("Starting PS-UTT Standard AnonCred protocol benchmarks");
 
     // Configure benchmark parameters
@@ -62,15 +70,16 @@ fn benchmark_psutt_std(c: &mut Criterion) {
         println!("Benchmarking with {} messages", msg_size);
 
         // Set up test environment for standard protocol
-        let std_setup = setup_standard_protocol(msg_size);
+        let std_setup = setup_standard_protocol::<E>(msg_size);
 
         // Benchmark Obtain
         let obtain_id = BenchmarkId::from_parameter(format!("obtain_messages_{}", msg_size));
         group.bench_function(obtain_id, |b| {
+            let mut rng = ark_std::test_rng();
             b.iter(|| {
                 std_setup
                     .protocol
-                    .obtain(&std_setup.user_cred)
+                    .obtain(&std_setup.user_cred, &mut rng)
                     .expect("Failed to obtain credential")
             })
         });
@@ -78,10 +87,11 @@ fn benchmark_psutt_std(c: &mut Criterion) {
         // Benchmark Issue
         let issue_id = BenchmarkId::from_parameter(format!("issue_messages_{}", msg_size));
         group.bench_function(issue_id, |b| {
+            let mut rng = ark_std::test_rng();
             b.iter(|| {
                 std_setup
                     .protocol
-                    .issue(&std_setup.proof)
+                    .issue(&std_setup.proof, &mut rng)
                     .expect("Failed to issue credential")
             })
         });
@@ -96,6 +106,7 @@ fn benchmark_psutt_std(c: &mut Criterion) {
                     .show(
                         &std_setup.user_cred.commitment,
                         &std_setup.signature,
+                        &std_setup.context,
                         &mut rng,
                     )
                     .expect("Failed to show credential")
@@ -108,7 +119,7 @@ fn benchmark_psutt_std(c: &mut Criterion) {
             b.iter(|| {
                 std_setup
                     .protocol
-                    .verify(&std_setup.presentation)
+                    .verify(&std_setup.presentation, &std_setup.context)
                     .expect("Failed to verify credential")
             })
         });
@@ -117,9 +128,14 @@ fn benchmark_psutt_std(c: &mut Criterion) {
     group.finish();
 }
 
+/// runs the benchmarks once per enabled curve
+fn benchmark_psutt_std_curves(c: &mut Criterion) {
+    utils::for_each_curve!(benchmark_psutt_std(c));
+}
+
 criterion_group!(
     name = benches;
     config = Criterion::default();
-    targets = benchmark_psutt_std
+    targets = benchmark_psutt_std_curves
 );
 criterion_main!(benches);
@@ -1,4 +1,4 @@
-use ark_bls12_381::{Bls12_381, Fr};
+use ark_ec::pairing::Pairing;
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::UniformRand;
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
@@ -7,9 +7,13 @@ use ps_utt::commitment::Commitment;
 use ps_utt::proofsystem::CommitmentProofs;
 use ps_utt::test_helpers::{PSUttImprovedTestSetup, PSUttTestSetup};
 use std::time::Duration;
+use utils::envelope::CurveIdentifier;
 
-fn benchmark_psutt_split(c: &mut Criterion) {
-    let mut group = c.benchmark_group("psutt_improved_equality_split");
+fn benchmark_psutt_split<E: Pairing + CurveIdentifier>(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!(
+        "psutt_improved_equality_split_{}",
+        E::CURVE_ID.name()
+    ));
     println!("Starting PSUTT Improved split benchmarks");
 
     group
@@ -24,12 +28,16 @@ fn benchmark_psutt_split(c: &mut Criterion) {
 
     for cred_count in CREDENTIAL_COUNTS {
         // Setup phase (done once per credential count)
-        let setup = BenchmarkSetupImproved::<Bls12_381>::new(cred_count, MSG_COUNT);
+        let setup = BenchmarkSetupImproved::<E>::new(cred_count, MSG_COUNT);
 
         // Pre-generate random values to use in both proving and verification
         let mut rng = ark_std::test_rng();
-        let r_deltas: Vec<Fr> = (0..cred_count).map(|_| Fr::rand(&mut rng)).collect();
-        let u_deltas: Vec<Fr> = (0..cred_count).map(|_| Fr::rand(&mut rng)).collect();
+        let r_deltas: Vec<E::ScalarField> = (0..cred_count)
+            .map(|_| E::ScalarField::rand(&mut rng))
+            .collect();
+        let u_deltas: Vec<E::ScalarField> = (0..cred_count)
+            .map(|_| E::ScalarField::rand(&mut rng))
+            .collect();
 
         // Generate the proof data once outside the benchmark
         let (randomized_sigs, randomized_commitments, proof) = {
@@ -51,7 +59,8 @@ fn benchmark_psutt_split(c: &mut Criterion) {
                 randomized_commitments.push(randomized_commitment);
             }
 
-            let proof = CommitmentProofs::prove_equality(&randomized_commitments).unwrap();
+            let proof =
+                CommitmentProofs::prove_equality(&randomized_commitments, &mut rng).unwrap();
             (
                 randomized_sigs.clone(),
                 randomized_commitments.clone(),
@@ -62,6 +71,7 @@ fn benchmark_psutt_split(c: &mut Criterion) {
         // Benchmark Proving
         let prove_id = BenchmarkId::from_parameter(format!("prove_credentials_{}", cred_count));
         group.bench_function(prove_id, |b| {
+            let mut rng = ark_std::test_rng();
             b.iter_with_large_drop(|| {
                 let mut randomized_sigs = Vec::with_capacity(cred_count);
                 let mut randomized_commitments = Vec::with_capacity(cred_count);
@@ -83,16 +93,17 @@ fn benchmark_psutt_split(c: &mut Criterion) {
                 }
 
                 // Generate equality proof
-                CommitmentProofs::prove_equality(&randomized_commitments).unwrap()
+                CommitmentProofs::prove_equality(&randomized_commitments, &mut rng).unwrap()
             })
         });
 
         // Benchmark Verification using the pre-generated outputs
+        let pps: Vec<_> = setup.psutt_setups.iter().map(|s| &s.pp).collect();
         let verify_id = BenchmarkId::from_parameter(format!("verify_credentials_{}", cred_count));
         group.bench_function(verify_id, |b| {
             b.iter(|| {
                 // Verify equality proof
-                assert!(CommitmentProofs::verify_equality::<Bls12_381>(&proof).unwrap());
+                assert!(CommitmentProofs::verify_equality::<E>(&pps, &proof).unwrap());
 
                 // Verify signatures
                 for i in 0..cred_count {
@@ -110,9 +121,14 @@ fn benchmark_psutt_split(c: &mut Criterion) {
     group.finish();
 }
 
+/// runs the benchmarks once per enabled curve
+fn benchmark_psutt_split_curves(c: &mut Criterion) {
+    utils::for_each_curve!(benchmark_psutt_split(c));
+}
+
 criterion_group!(
     name = benches;
     config = Criterion::default();
-    targets = benchmark_psutt_split,
+    targets = benchmark_psutt_split_curves,
 );
 criterion_main!(benches);
@@ -0,0 +1,777 @@
+use crate::error::Error;
+use crate::keygen::{IssuerParams, SecretKey};
+use crate::mac::MacGgm;
+use crate::proof::{Equation, LinearProof};
+use crate::publicparams::PublicParams;
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::Zero;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{rand::Rng, UniformRand};
+use utils::context::PresentationContext;
+
+const REQUEST_DOMAIN: &[u8] = b"cmz14-issuance-request";
+const RESPONSE_DOMAIN: &[u8] = b"cmz14-issuance-response";
+const SHOW_DOMAIN: &[u8] = b"cmz14-show";
+
+/// ElGamal key pair (d, gamma = g^d) the user receives its MAC under during blind issuance
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct UserKey<G: AffineRepr> {
+    pub d: G::ScalarField,
+    pub gamma: G,
+}
+
+/// ElGamal encryption (g^r, g^m gamma^r) of the attribute at `index`
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct HiddenAttribute<G: AffineRepr> {
+    pub index: usize,
+    pub c1: G,
+    pub c2: G,
+}
+
+/// Attributes the issuer sees in the clear, encryptions of the others, and a proof the user knows
+/// the plaintexts and the decryption key
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct IssuanceRequest<G: AffineRepr> {
+    pub gamma: G,
+    pub revealed: Vec<(usize, G::ScalarField)>,
+    pub hidden: Vec<HiddenAttribute<G>>,
+    pub proof: LinearProof<G>,
+}
+
+/// u and an encryption (e1, e2) of u', with a proof they were computed under the committed key
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct IssuanceResponse<G: AffineRepr> {
+    pub u: G,
+    pub e1: G,
+    pub e2: G,
+    pub proof: LinearProof<G>,
+}
+
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Credential<G: AffineRepr> {
+    pub attributes: Vec<G::ScalarField>,
+    pub mac: MacGgm<G>,
+}
+
+/// Randomized u, commitments C_i = u^{m_i} h^{z_i} to the undisclosed attributes and
+/// C_u' = u' g^r, with a proof of knowledge of the openings
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct ShowCredential<G: AffineRepr> {
+    pub u: G,
+    pub c_u_prime: G,
+    pub disclosed: Vec<(usize, G::ScalarField)>,
+    pub commitments: Vec<(usize, G)>,
+    pub proof: LinearProof<G>,
+}
+
+pub struct AnonCredProtocol;
+
+impl AnonCredProtocol {
+    /// User encrypts the attributes at `hidden` under a fresh ElGamal key and proves knowledge of
+    /// them, the others are sent in the clear
+    ///
+    /// # Arguments
+    /// * `pp` - Public parameters
+    /// * `attributes` - Full attribute vector
+    /// * `hidden` - Attribute indices the issuer must not learn
+    /// * `rng` - Random number generator
+    ///
+    /// # Returns
+    /// * Issuance request and the key the MAC will be encrypted to
+    pub fn obtain<G: AffineRepr, R: Rng>(
+        pp: &PublicParams<G>,
+        attributes: &[G::ScalarField],
+        hidden: &[usize],
+        rng: &mut R,
+    ) -> Result<(IssuanceRequest<G>, UserKey<G>), Error> {
+        if attributes.len() != pp.n {
+            return Err(Error::AttributeCount {
+                expected: pp.n,
+                found: attributes.len(),
+            });
+        }
+        let hidden = sorted_indices(hidden, pp.n)?;
+
+        let d = G::ScalarField::rand(rng);
+        let gamma = pp.g.mul(d).into_affine();
+        let mut witnesses = vec![d];
+        let mut encrypted = Vec::with_capacity(hidden.len());
+        for &index in &hidden {
+            let r = G::ScalarField::rand(rng);
+            let c1 = pp.g.mul(r).into_affine();
+            let c2 = (pp.g.mul(attributes[index]) + gamma.mul(r)).into_affine();
+            encrypted.push(HiddenAttribute { index, c1, c2 });
+            witnesses.push(attributes[index]);
+            witnesses.push(r);
+        }
+        let revealed = (0..pp.n)
+            .filter(|i| !hidden.contains(i))
+            .map(|i| (i, attributes[i]))
+            .collect();
+
+        let equations = request_equations(pp, &gamma, &encrypted);
+        let proof = LinearProof::prove(
+            REQUEST_DOMAIN,
+            &PresentationContext::default(),
+            &equations,
+            &witnesses,
+            rng,
+        )?;
+
+        Ok((
+            IssuanceRequest {
+                gamma,
+                revealed,
+                hidden: encrypted,
+                proof,
+            },
+            UserKey { d, gamma },
+        ))
+    }
+
+    /// Issuer verifies the request and MACs the attributes without decrypting the hidden ones,
+    /// u' comes back encrypted to the user's key
+    ///
+    /// # Arguments
+    /// * `pp` - Public parameters
+    /// * `sk` - Issuer's MAC key
+    /// * `iparams` - Issuer parameters committing to `sk`
+    /// * `request` - Request from `obtain`
+    /// * `rng` - Random number generator
+    ///
+    /// # Returns
+    /// * Response with u, the encrypted u' and a proof of correct computation
+    pub fn issue<G: AffineRepr, R: Rng>(
+        pp: &PublicParams<G>,
+        sk: &SecretKey<G>,
+        iparams: &IssuerParams<G>,
+        request: &IssuanceRequest<G>,
+        rng: &mut R,
+    ) -> Result<IssuanceResponse<G>, Error> {
+        check_request(pp, request)?;
+
+        let b = nonzero_scalar::<G, R>(rng);
+        let u = pp.g.mul(b).into_affine();
+        let (e1, e2, key_witnesses) = encrypt_mac(pp, sk, request, &u, &b, rng);
+
+        let mut witnesses = vec![b];
+        witnesses.extend(key_witnesses);
+        let equations = response_equations(pp, iparams, request, &u, &e1, &e2);
+        let proof = LinearProof::prove(
+            RESPONSE_DOMAIN,
+            &PresentationContext::default(),
+            &equations,
+            &witnesses,
+            rng,
+        )?;
+
+        Ok(IssuanceResponse { u, e1, e2, proof })
+    }
+
+    /// User checks the issuer's proof against `iparams` and decrypts u'
+    ///
+    /// # Arguments
+    /// * `pp` - Public parameters
+    /// * `iparams` - Issuer parameters
+    /// * `request` - The request sent to the issuer
+    /// * `response` - Issuer's response
+    /// * `key` - Key returned by `obtain`
+    /// * `attributes` - Full attribute vector passed to `obtain`
+    ///
+    /// # Returns
+    /// * Credential holding the attributes and their MAC
+    pub fn complete_credential<G: AffineRepr>(
+        pp: &PublicParams<G>,
+        iparams: &IssuerParams<G>,
+        request: &IssuanceRequest<G>,
+        response: &IssuanceResponse<G>,
+        key: &UserKey<G>,
+        attributes: &[G::ScalarField],
+    ) -> Result<Credential<G>, Error> {
+        if attributes.len() != pp.n {
+            return Err(Error::AttributeCount {
+                expected: pp.n,
+                found: attributes.len(),
+            });
+        }
+        let equations = response_equations(
+            pp,
+            iparams,
+            request,
+            &response.u,
+            &response.e1,
+            &response.e2,
+        );
+        if response.u.is_zero()
+            || !response.proof.verify(
+                RESPONSE_DOMAIN,
+                &PresentationContext::default(),
+                &equations,
+            )?
+        {
+            return Err(Error::InvalidResponseProof);
+        }
+
+        let u_prime = (response.e2.into_group() - response.e1.mul(key.d)).into_affine();
+        Ok(Credential {
+            attributes: attributes.to_vec(),
+            mac: MacGgm {
+                u: response.u,
+                u_prime,
+            },
+        })
+    }
+
+    /// User randomizes the MAC, commits to the attributes not in `disclosed` and proves it holds
+    /// a MAC on them, bound to the verifier's context
+    ///
+    /// # Arguments
+    /// * `pp` - Public parameters
+    /// * `iparams` - Issuer parameters
+    /// * `credential` - Credential from `complete_credential`
+    /// * `disclosed` - Attribute indices revealed to the verifier
+    /// * `context` - Verifier supplied presentation context
+    /// * `rng` - Random number generator
+    ///
+    /// # Returns
+    /// * Presentation for the issuer to verify
+    pub fn show<G: AffineRepr, R: Rng>(
+        pp: &PublicParams<G>,
+        iparams: &IssuerParams<G>,
+        credential: &Credential<G>,
+        disclosed: &[usize],
+        context: &PresentationContext,
+        rng: &mut R,
+    ) -> Result<ShowCredential<G>, Error> {
+        let disclosed = sorted_indices(disclosed, pp.n)?;
+        let mac = credential.mac.randomize(&nonzero_scalar::<G, R>(rng));
+        let (commitments, openings, mut witnesses) =
+            commit_attributes(pp, &mac.u, &credential.attributes, &disclosed, rng);
+        let (c_u_prime, v, r) = blind_mac(pp, iparams, &mac.u_prime, &openings, rng);
+        witnesses.push(r);
+
+        let equations = show_equations(pp, &mac.u, &commitments, &[(iparams, v)]);
+        let proof = LinearProof::prove(SHOW_DOMAIN, context, &equations, &witnesses, rng)?;
+
+        Ok(ShowCredential {
+            u: mac.u,
+            c_u_prime,
+            disclosed: disclosed
+                .iter()
+                .map(|i| (*i, credential.attributes[*i]))
+                .collect(),
+            commitments,
+            proof,
+        })
+    }
+
+    /// Issuer verifies a presentation with its MAC key, no pairing is computed
+    ///
+    /// # Arguments
+    /// * `pp` - Public parameters
+    /// * `sk` - Issuer's MAC key
+    /// * `iparams` - Issuer parameters
+    /// * `presentation` - Presentation from `show`
+    /// * `context` - Context the presentation must be bound to
+    ///
+    /// # Returns
+    /// * true if the presentation is valid for this context
+    pub fn verify<G: AffineRepr>(
+        pp: &PublicParams<G>,
+        sk: &SecretKey<G>,
+        iparams: &IssuerParams<G>,
+        presentation: &ShowCredential<G>,
+        context: &PresentationContext,
+    ) -> Result<bool, Error> {
+        if presentation.u.is_zero()
+            || !covers_all_attributes(pp, &presentation.disclosed, &presentation.commitments)
+        {
+            return Ok(false);
+        }
+
+        let v = recompute_v(
+            sk,
+            &presentation.u,
+            &presentation.disclosed,
+            &presentation.commitments,
+            &presentation.c_u_prime,
+        );
+        let equations = show_equations(
+            pp,
+            &presentation.u,
+            &presentation.commitments,
+            &[(iparams, v)],
+        );
+        presentation.proof.verify(SHOW_DOMAIN, context, &equations)
+    }
+}
+
+/// uniformly random non-zero scalar, for MAC and randomization exponents
+pub(crate) fn nonzero_scalar<G: AffineRepr, R: Rng>(rng: &mut R) -> G::ScalarField {
+    loop {
+        let a = G::ScalarField::rand(rng);
+        if !a.is_zero() {
+            break a;
+        }
+    }
+}
+
+/// sorted, deduplicated indices below `n`
+pub(crate) fn sorted_indices(indices: &[usize], n: usize) -> Result<Vec<usize>, Error> {
+    let mut sorted = indices.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+    match sorted.iter().find(|i| **i >= n) {
+        Some(i) => Err(Error::InvalidAttributeIndex(*i)),
+        None => Ok(sorted),
+    }
+}
+
+/// request covers every attribute once and its proof verifies
+pub(crate) fn check_request<G: AffineRepr>(
+    pp: &PublicParams<G>,
+    request: &IssuanceRequest<G>,
+) -> Result<(), Error> {
+    check_request_indices(pp, request)?;
+    let equations = request_equations(pp, &request.gamma, &request.hidden);
+    if !request
+        .proof
+        .verify(REQUEST_DOMAIN, &PresentationContext::default(), &equations)?
+    {
+        return Err(Error::InvalidRequestProof);
+    }
+    Ok(())
+}
+
+/// every attribute index appears exactly once, revealed or hidden
+fn check_request_indices<G: AffineRepr>(
+    pp: &PublicParams<G>,
+    request: &IssuanceRequest<G>,
+) -> Result<(), Error> {
+    let found = request.revealed.len() + request.hidden.len();
+    if found != pp.n {
+        return Err(Error::AttributeCount {
+            expected: pp.n,
+            found,
+        });
+    }
+    let mut seen = vec![false; pp.n];
+    let indices = request
+        .revealed
+        .iter()
+        .map(|(i, _)| *i)
+        .chain(request.hidden.iter().map(|h| h.index));
+    for i in indices {
+        if i >= pp.n || seen[i] {
+            return Err(Error::InvalidAttributeIndex(i));
+        }
+        seen[i] = true;
+    }
+    Ok(())
+}
+
+/// witnesses d, then m_j, r_j per hidden attribute
+///   gamma = g^d, c1_j = g^{r_j}, c2_j = g^{m_j} gamma^{r_j}
+fn request_equations<G: AffineRepr>(
+    pp: &PublicParams<G>,
+    gamma: &G,
+    hidden: &[HiddenAttribute<G>],
+) -> Vec<Equation<G>> {
+    let mut equations = vec![Equation::new(*gamma, vec![(pp.g, 0)])];
+    for (k, h) in hidden.iter().enumerate() {
+        let (m, r) = (1 + 2 * k, 2 + 2 * k);
+        equations.push(Equation::new(h.c1, vec![(pp.g, r)]));
+        equations.push(Equation::new(h.c2, vec![(pp.g, m), (*gamma, r)]));
+    }
+    equations
+}
+
+/// Encryption (e1, e2) of u^{k0 + sum k_i m_i} to the user's key under one MAC key k. The
+/// revealed terms are added in the exponent, the hidden ones under encryption as c_j^{t_j}.
+/// Returns the witnesses k0, k0_tilde, k_1..k_n, r, t_1..t_l of `mac_equations`
+pub(crate) fn encrypt_mac<G: AffineRepr, R: Rng>(
+    pp: &PublicParams<G>,
+    sk: &SecretKey<G>,
+    request: &IssuanceRequest<G>,
+    u: &G,
+    b: &G::ScalarField,
+    rng: &mut R,
+) -> (G, G, Vec<G::ScalarField>) {
+    let r = G::ScalarField::rand(rng);
+    let revealed_exponent = request
+        .revealed
+        .iter()
+        .fold(sk.x0, |acc, (i, m)| acc + sk.x[*i] * m);
+    let mut e1 = pp.g.mul(r);
+    let mut e2 = u.mul(revealed_exponent) + request.gamma.mul(r);
+    let mut t = Vec::with_capacity(request.hidden.len());
+    for hidden in &request.hidden {
+        let tj = *b * sk.x[hidden.index];
+        e1 += hidden.c1.mul(tj);
+        e2 += hidden.c2.mul(tj);
+        t.push(tj);
+    }
+
+    let mut witnesses = vec![sk.x0, sk.x0_tilde];
+    witnesses.extend_from_slice(&sk.x);
+    witnesses.push(r);
+    witnesses.extend(t);
+    (e1.into_affine(), e2.into_affine(), witnesses)
+}
+
+/// witnesses b, x0, x0_tilde, x_1..x_n, r, then t_j = b x_j per hidden attribute
+///   u = g^b, then `mac_equations` for x
+fn response_equations<G: AffineRepr>(
+    pp: &PublicParams<G>,
+    iparams: &IssuerParams<G>,
+    request: &IssuanceRequest<G>,
+    u: &G,
+    e1: &G,
+    e2: &G,
+) -> Vec<Equation<G>> {
+    let mut equations = vec![Equation::new(*u, vec![(pp.g, 0)])];
+    equations.extend(mac_equations(pp, iparams, request, u, e1, e2, 0, 1));
+    equations
+}
+
+/// witnesses k0, k0_tilde, k_1..k_n, r, then t_j = b k_j per hidden attribute, from `offset` on,
+/// and b at `b`
+///   C_k0 = g^k0 h^k0_tilde, K_i = h^k_i, 1 = K_j^b h^{-t_j},
+///   e1 = g^r prod c1_j^{t_j}, e2 = u^k0 prod_revealed (u^{m_i})^{k_i} gamma^r prod c2_j^{t_j}
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn mac_equations<G: AffineRepr>(
+    pp: &PublicParams<G>,
+    iparams: &IssuerParams<G>,
+    request: &IssuanceRequest<G>,
+    u: &G,
+    e1: &G,
+    e2: &G,
+    b: usize,
+    offset: usize,
+) -> Vec<Equation<G>> {
+    let (k0, k0_tilde) = (offset, offset + 1);
+    let k = |i: usize| offset + 2 + i;
+    let r = offset + 2 + pp.n;
+    let t = |j: usize| offset + 3 + pp.n + j;
+    let minus_h = (-pp.h.into_group()).into_affine();
+
+    let mut equations = vec![Equation::new(
+        iparams.cx0,
+        vec![(pp.g, k0), (pp.h, k0_tilde)],
+    )];
+    for (i, ki) in iparams.x.iter().enumerate() {
+        equations.push(Equation::new(*ki, vec![(pp.h, k(i))]));
+    }
+
+    let mut e1_terms = vec![(pp.g, r)];
+    let mut e2_terms = vec![(*u, k0)];
+    for (i, m) in &request.revealed {
+        e2_terms.push((u.mul(*m).into_affine(), k(*i)));
+    }
+    e2_terms.push((request.gamma, r));
+    for (j, h) in request.hidden.iter().enumerate() {
+        equations.push(Equation::new(
+            G::zero(),
+            vec![(iparams.x[h.index], b), (minus_h, t(j))],
+        ));
+        e1_terms.push((h.c1, t(j)));
+        e2_terms.push((h.c2, t(j)));
+    }
+    equations.push(Equation::new(*e1, e1_terms));
+    equations.push(Equation::new(*e2, e2_terms));
+    equations
+}
+
+/// C_i = u^{m_i} h^{z_i} for every attribute not in `disclosed`. Returns the commitments, the
+/// openings z_i and the witnesses m_i, z_i of `show_equations`
+#[allow(clippy::type_complexity)]
+pub(crate) fn commit_attributes<G: AffineRepr, R: Rng>(
+    pp: &PublicParams<G>,
+    u: &G,
+    attributes: &[G::ScalarField],
+    disclosed: &[usize],
+    rng: &mut R,
+) -> (
+    Vec<(usize, G)>,
+    Vec<(usize, G::ScalarField)>,
+    Vec<G::ScalarField>,
+) {
+    let mut commitments = Vec::new();
+    let mut openings = Vec::new();
+    let mut witnesses = Vec::new();
+    for i in (0..pp.n).filter(|i| !disclosed.contains(i)) {
+        let z = G::ScalarField::rand(rng);
+        commitments.push((i, (u.mul(attributes[i]) + pp.h.mul(z)).into_affine()));
+        openings.push((i, z));
+        witnesses.push(attributes[i]);
+        witnesses.push(z);
+    }
+    (commitments, openings, witnesses)
+}
+
+/// C_u' = u' g^r and V = prod K_i^{z_i} g^{-r} for one MAC key, V is what the issuer
+/// recomputes with `recompute_v`. Returns C_u', V and r
+pub(crate) fn blind_mac<G: AffineRepr, R: Rng>(
+    pp: &PublicParams<G>,
+    iparams: &IssuerParams<G>,
+    u_prime: &G,
+    openings: &[(usize, G::ScalarField)],
+    rng: &mut R,
+) -> (G, G, G::ScalarField) {
+    let r = G::ScalarField::rand(rng);
+    let c_u_prime = (*u_prime + pp.g.mul(r)).into_affine();
+    let v = openings
+        .iter()
+        .fold(pp.g.mul(-r), |acc, (i, z)| acc + iparams.x[*i].mul(*z));
+    (c_u_prime, v.into_affine(), r)
+}
+
+/// V = u^{k0 + sum disclosed k_i m_i} prod C_i^{k_i} / C_u' under one MAC key
+pub(crate) fn recompute_v<G: AffineRepr>(
+    sk: &SecretKey<G>,
+    u: &G,
+    disclosed: &[(usize, G::ScalarField)],
+    commitments: &[(usize, G)],
+    c_u_prime: &G,
+) -> G {
+    let exponent = disclosed
+        .iter()
+        .fold(sk.x0, |acc, (i, m)| acc + sk.x[*i] * m);
+    let mut v = u.mul(exponent) - c_u_prime.into_group();
+    for (i, cm) in commitments {
+        v += cm.mul(sk.x[*i]);
+    }
+    v.into_affine()
+}
+
+/// every attribute is either disclosed or committed, exactly once
+pub(crate) fn covers_all_attributes<G: AffineRepr>(
+    pp: &PublicParams<G>,
+    disclosed: &[(usize, G::ScalarField)],
+    commitments: &[(usize, G)],
+) -> bool {
+    let mut indices: Vec<usize> = disclosed
+        .iter()
+        .map(|(i, _)| *i)
+        .chain(commitments.iter().map(|(i, _)| *i))
+        .collect();
+    indices.sort_unstable();
+    indices == (0..pp.n).collect::<Vec<_>>()
+}
+
+/// witnesses m_i, z_i per commitment, then r per MAC key
+///   C_i = u^{m_i} h^{z_i}, and V = prod K_i^{z_i} g^{-r} for each (K, V) in `keys`
+pub(crate) fn show_equations<G: AffineRepr>(
+    pp: &PublicParams<G>,
+    u: &G,
+    commitments: &[(usize, G)],
+    keys: &[(&IssuerParams<G>, G)],
+) -> Vec<Equation<G>> {
+    let minus_g = (-pp.g.into_group()).into_affine();
+    let mut equations = Vec::with_capacity(commitments.len() + keys.len());
+    for (k, (_, cm)) in commitments.iter().enumerate() {
+        let (m, z) = (2 * k, 2 * k + 1);
+        equations.push(Equation::new(*cm, vec![(*u, m), (pp.h, z)]));
+    }
+    for (j, (iparams, v)) in keys.iter().enumerate() {
+        let r = 2 * commitments.len() + j;
+        let mut v_terms: Vec<(G, usize)> = commitments
+            .iter()
+            .enumerate()
+            .map(|(k, (i, _))| (iparams.x[*i], 2 * k + 1))
+            .collect();
+        v_terms.push((minus_g, r));
+        equations.push(Equation::new(*v, v_terms));
+    }
+    equations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keygen::gen_keys;
+    use ark_std::test_rng;
+
+    struct Setup<G: AffineRepr> {
+        pp: PublicParams<G>,
+        sk: SecretKey<G>,
+        iparams: IssuerParams<G>,
+        attributes: Vec<G::ScalarField>,
+    }
+
+    fn setup<G: AffineRepr>(n: usize, rng: &mut impl Rng) -> Setup<G> {
+        let pp = PublicParams::<G>::new(&n, rng);
+        let (sk, iparams) = gen_keys(&pp, rng);
+        let attributes = (0..n).map(|_| G::ScalarField::rand(rng)).collect();
+        Setup {
+            pp,
+            sk,
+            iparams,
+            attributes,
+        }
+    }
+
+    fn issue<G: AffineRepr>(s: &Setup<G>, hidden: &[usize], rng: &mut impl Rng) -> Credential<G> {
+        let (request, key) = AnonCredProtocol::obtain(&s.pp, &s.attributes, hidden, rng).unwrap();
+        let response = AnonCredProtocol::issue(&s.pp, &s.sk, &s.iparams, &request, rng).unwrap();
+        AnonCredProtocol::complete_credential(
+            &s.pp,
+            &s.iparams,
+            &request,
+            &response,
+            &key,
+            &s.attributes,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_blind_issue_show_verify() {
+        fn check<G: AffineRepr>() {
+            let mut rng = test_rng();
+            let s = setup::<G>(5, &mut rng);
+            let credential = issue(&s, &[0, 2, 3], &mut rng);
+            assert!(credential.mac.verify(&s.sk, &s.attributes));
+
+            let context = PresentationContext::new(b"verifier", b"nonce", 1_700_000_000);
+            let presentation =
+                AnonCredProtocol::show(&s.pp, &s.iparams, &credential, &[1, 4], &context, &mut rng)
+                    .unwrap();
+            assert_eq!(
+                presentation.disclosed,
+                vec![(1, s.attributes[1]), (4, s.attributes[4])]
+            );
+            assert_eq!(presentation.commitments.len(), 3);
+            assert!(
+                AnonCredProtocol::verify(&s.pp, &s.sk, &s.iparams, &presentation, &context)
+                    .unwrap()
+            );
+
+            // bound to the verifier's context
+            let replay = PresentationContext::new(b"verifier", b"nonce-2", 1_700_000_000);
+            assert!(
+                !AnonCredProtocol::verify(&s.pp, &s.sk, &s.iparams, &presentation, &replay)
+                    .unwrap()
+            );
+
+            // a disclosed value the MAC was not computed on
+            let mut forged = presentation.clone();
+            forged.disclosed[0].1 += G::ScalarField::from(1u64);
+            assert!(
+                !AnonCredProtocol::verify(&s.pp, &s.sk, &s.iparams, &forged, &context).unwrap()
+            );
+
+            // another issuer's key rejects it
+            let other = setup::<G>(5, &mut rng);
+            assert!(!AnonCredProtocol::verify(
+                &s.pp,
+                &other.sk,
+                &s.iparams,
+                &presentation,
+                &context
+            )
+            .unwrap());
+
+            // an attribute both disclosed and committed
+            let mut duplicated = presentation.clone();
+            duplicated.disclosed.push((0, s.attributes[0]));
+            assert!(
+                !AnonCredProtocol::verify(&s.pp, &s.sk, &s.iparams, &duplicated, &context).unwrap()
+            );
+        }
+
+        check::<ark_bls12_381::G1Affine>();
+        check::<ark_secp256k1::Affine>();
+    }
+
+    #[test]
+    fn test_issue_and_show_edge_cases() {
+        fn check<G: AffineRepr>() {
+            let mut rng = test_rng();
+            let s = setup::<G>(3, &mut rng);
+            let context = PresentationContext::new(b"verifier", b"nonce", 0);
+
+            // nothing hidden at issuance, nothing disclosed at show, and the reverse
+            for (hidden, disclosed) in [(vec![], vec![]), (vec![0, 1, 2], vec![0, 1, 2])] {
+                let credential = issue(&s, &hidden, &mut rng);
+                assert!(credential.mac.verify(&s.sk, &s.attributes));
+                let presentation = AnonCredProtocol::show(
+                    &s.pp,
+                    &s.iparams,
+                    &credential,
+                    &disclosed,
+                    &context,
+                    &mut rng,
+                )
+                .unwrap();
+                assert!(AnonCredProtocol::verify(
+                    &s.pp,
+                    &s.sk,
+                    &s.iparams,
+                    &presentation,
+                    &context
+                )
+                .unwrap());
+            }
+
+            assert!(matches!(
+                AnonCredProtocol::obtain(&s.pp, &s.attributes, &[3], &mut rng),
+                Err(Error::InvalidAttributeIndex(3))
+            ));
+            assert!(matches!(
+                AnonCredProtocol::obtain(&s.pp, &s.attributes[..2], &[], &mut rng),
+                Err(Error::AttributeCount {
+                    expected: 3,
+                    found: 2
+                })
+            ));
+        }
+
+        check::<ark_bls12_381::G1Affine>();
+        check::<ark_secp256k1::Affine>();
+    }
+
+    #[test]
+    fn test_issuance_rejects_tampering() {
+        fn check<G: AffineRepr>() {
+            let mut rng = test_rng();
+            let s = setup::<G>(3, &mut rng);
+            let (request, key) =
+                AnonCredProtocol::obtain(&s.pp, &s.attributes, &[1], &mut rng).unwrap();
+
+            // the issuer sees a revealed value the proof does not cover, the hidden one is bound
+            let mut tampered = request.clone();
+            tampered.hidden[0].c2 = (tampered.hidden[0].c2.into_group() + s.pp.g).into_affine();
+            assert!(matches!(
+                AnonCredProtocol::issue(&s.pp, &s.sk, &s.iparams, &tampered, &mut rng),
+                Err(Error::InvalidRequestProof)
+            ));
+            let mut duplicated = request.clone();
+            duplicated.revealed[0].0 = 1;
+            assert!(matches!(
+                AnonCredProtocol::issue(&s.pp, &s.sk, &s.iparams, &duplicated, &mut rng),
+                Err(Error::InvalidAttributeIndex(1))
+            ));
+
+            // a response computed with a key other than the one in iparams is rejected
+            let other = setup::<G>(3, &mut rng);
+            let response =
+                AnonCredProtocol::issue(&s.pp, &other.sk, &other.iparams, &request, &mut rng)
+                    .unwrap();
+            assert!(matches!(
+                AnonCredProtocol::complete_credential(
+                    &s.pp,
+                    &s.iparams,
+                    &request,
+                    &response,
+                    &key,
+                    &s.attributes
+                ),
+                Err(Error::InvalidResponseProof)
+            ));
+        }
+
+        check::<ark_bls12_381::G1Affine>();
+        check::<ark_secp256k1::Affine>();
+    }
+}
@@ -0,0 +1,456 @@
+//! MAC_DDH credentials. Blind issuance reuses the MAC_GGM request from `anon_cred`, the issuer
+//! answers with u^z in the clear and u_x, u_y encrypted to the user's key, and proves all three
+//! were computed under the key committed in `DdhIssuerParams`.
+
+use crate::anon_cred::{
+    blind_mac, check_request, commit_attributes, covers_all_attributes, encrypt_mac, mac_equations,
+    nonzero_scalar, recompute_v, show_equations, sorted_indices, IssuanceRequest, UserKey,
+};
+use crate::error::Error;
+use crate::keygen::{DdhIssuerParams, DdhSecretKey};
+use crate::mac::MacDdh;
+use crate::proof::{Equation, LinearProof};
+use crate::publicparams::PublicParams;
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::rand::Rng;
+use utils::context::PresentationContext;
+
+const RESPONSE_DOMAIN: &[u8] = b"cmz14-ddh-issuance-response";
+const SHOW_DOMAIN: &[u8] = b"cmz14-ddh-show";
+
+/// u, u_z and encryptions of u_x and u_y, with a proof they were computed under the committed key
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct DdhIssuanceResponse<G: AffineRepr> {
+    pub u: G,
+    pub u_z: G,
+    pub e_x: (G, G),
+    pub e_y: (G, G),
+    pub proof: LinearProof<G>,
+}
+
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct DdhCredential<G: AffineRepr> {
+    pub attributes: Vec<G::ScalarField>,
+    pub mac: MacDdh<G>,
+}
+
+/// Randomized u and u_z, commitments C_i = u^{m_i} h^{z_i} to the undisclosed attributes and
+/// C_ux = u_x g^{r_x}, C_uy = u_y g^{r_y}, with a proof of knowledge of the openings
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct DdhShowCredential<G: AffineRepr> {
+    pub u: G,
+    pub u_z: G,
+    pub c_u_x: G,
+    pub c_u_y: G,
+    pub disclosed: Vec<(usize, G::ScalarField)>,
+    pub commitments: Vec<(usize, G)>,
+    pub proof: LinearProof<G>,
+}
+
+/// MAC_DDH counterpart of `AnonCredProtocol`, requests come from `AnonCredProtocol::obtain`
+pub struct DdhAnonCredProtocol;
+
+impl DdhAnonCredProtocol {
+    /// Issuer verifies the request and MACs the attributes under both halves of its key without
+    /// decrypting the hidden ones
+    ///
+    /// # Arguments
+    /// * `pp` - Public parameters
+    /// * `sk` - Issuer's MAC_DDH key
+    /// * `iparams` - Issuer parameters committing to `sk`
+    /// * `request` - Request from `AnonCredProtocol::obtain`
+    /// * `rng` - Random number generator
+    ///
+    /// # Returns
+    /// * Response with u, u_z, the encrypted u_x and u_y and a proof of correct computation
+    pub fn issue<G: AffineRepr, R: Rng>(
+        pp: &PublicParams<G>,
+        sk: &DdhSecretKey<G>,
+        iparams: &DdhIssuerParams<G>,
+        request: &IssuanceRequest<G>,
+        rng: &mut R,
+    ) -> Result<DdhIssuanceResponse<G>, Error> {
+        check_request(pp, request)?;
+
+        let b = nonzero_scalar::<G, R>(rng);
+        let u = pp.g.mul(b).into_affine();
+        let u_z = u.mul(sk.z).into_affine();
+        let (e1_x, e2_x, x_witnesses) = encrypt_mac(pp, &sk.x, request, &u, &b, rng);
+        let (e1_y, e2_y, y_witnesses) = encrypt_mac(pp, &sk.y, request, &u, &b, rng);
+
+        let mut witnesses = vec![b];
+        witnesses.extend(x_witnesses);
+        witnesses.extend(y_witnesses);
+        witnesses.push(sk.z);
+        let e_x = (e1_x, e2_x);
+        let e_y = (e1_y, e2_y);
+        let equations = response_equations(pp, iparams, request, &u, &u_z, &e_x, &e_y);
+        let proof = LinearProof::prove(
+            RESPONSE_DOMAIN,
+            &PresentationContext::default(),
+            &equations,
+            &witnesses,
+            rng,
+        )?;
+
+        Ok(DdhIssuanceResponse {
+            u,
+            u_z,
+            e_x,
+            e_y,
+            proof,
+        })
+    }
+
+    /// User checks the issuer's proof against `iparams` and decrypts u_x and u_y
+    ///
+    /// # Arguments
+    /// * `pp` - Public parameters
+    /// * `iparams` - Issuer parameters
+    /// * `request` - The request sent to the issuer
+    /// * `response` - Issuer's response
+    /// * `key` - Key returned by `AnonCredProtocol::obtain`
+    /// * `attributes` - Full attribute vector passed to `AnonCredProtocol::obtain`
+    ///
+    /// # Returns
+    /// * Credential holding the attributes and their MAC
+    pub fn complete_credential<G: AffineRepr>(
+        pp: &PublicParams<G>,
+        iparams: &DdhIssuerParams<G>,
+        request: &IssuanceRequest<G>,
+        response: &DdhIssuanceResponse<G>,
+        key: &UserKey<G>,
+        attributes: &[G::ScalarField],
+    ) -> Result<DdhCredential<G>, Error> {
+        if attributes.len() != pp.n {
+            return Err(Error::AttributeCount {
+                expected: pp.n,
+                found: attributes.len(),
+            });
+        }
+        let equations = response_equations(
+            pp,
+            iparams,
+            request,
+            &response.u,
+            &response.u_z,
+            &response.e_x,
+            &response.e_y,
+        );
+        if response.u.is_zero()
+            || !response.proof.verify(
+                RESPONSE_DOMAIN,
+                &PresentationContext::default(),
+                &equations,
+            )?
+        {
+            return Err(Error::InvalidResponseProof);
+        }
+
+        let decrypt = |(e1, e2): &(G, G)| (e2.into_group() - e1.mul(key.d)).into_affine();
+        Ok(DdhCredential {
+            attributes: attributes.to_vec(),
+            mac: MacDdh {
+                u: response.u,
+                u_x: decrypt(&response.e_x),
+                u_y: decrypt(&response.e_y),
+                u_z: response.u_z,
+            },
+        })
+    }
+
+    /// User randomizes the MAC, commits to the attributes not in `disclosed` once for both u_x
+    /// and u_y and proves it holds a MAC on them, bound to the verifier's context
+    ///
+    /// # Arguments
+    /// * `pp` - Public parameters
+    /// * `iparams` - Issuer parameters
+    /// * `credential` - Credential from `complete_credential`
+    /// * `disclosed` - Attribute indices revealed to the verifier
+    /// * `context` - Verifier supplied presentation context
+    /// * `rng` - Random number generator
+    ///
+    /// # Returns
+    /// * Presentation for the issuer to verify
+    pub fn show<G: AffineRepr, R: Rng>(
+        pp: &PublicParams<G>,
+        iparams: &DdhIssuerParams<G>,
+        credential: &DdhCredential<G>,
+        disclosed: &[usize],
+        context: &PresentationContext,
+        rng: &mut R,
+    ) -> Result<DdhShowCredential<G>, Error> {
+        let disclosed = sorted_indices(disclosed, pp.n)?;
+        let mac = credential.mac.randomize(&nonzero_scalar::<G, R>(rng));
+        let (commitments, openings, mut witnesses) =
+            commit_attributes(pp, &mac.u, &credential.attributes, &disclosed, rng);
+        let (c_u_x, v_x, r_x) = blind_mac(pp, &iparams.x, &mac.u_x, &openings, rng);
+        let (c_u_y, v_y, r_y) = blind_mac(pp, &iparams.y, &mac.u_y, &openings, rng);
+        witnesses.push(r_x);
+        witnesses.push(r_y);
+
+        let equations = show_equations(
+            pp,
+            &mac.u,
+            &commitments,
+            &[(&iparams.x, v_x), (&iparams.y, v_y)],
+        );
+        let proof = LinearProof::prove(SHOW_DOMAIN, context, &equations, &witnesses, rng)?;
+
+        Ok(DdhShowCredential {
+            u: mac.u,
+            u_z: mac.u_z,
+            c_u_x,
+            c_u_y,
+            disclosed: disclosed
+                .iter()
+                .map(|i| (*i, credential.attributes[*i]))
+                .collect(),
+            commitments,
+            proof,
+        })
+    }
+
+    /// Issuer verifies a presentation with its MAC key, checking u_z directly and u_x, u_y
+    /// through the proof
+    ///
+    /// # Arguments
+    /// * `pp` - Public parameters
+    /// * `sk` - Issuer's MAC_DDH key
+    /// * `iparams` - Issuer parameters
+    /// * `presentation` - Presentation from `show`
+    /// * `context` - Context the presentation must be bound to
+    ///
+    /// # Returns
+    /// * true if the presentation is valid for this context
+    pub fn verify<G: AffineRepr>(
+        pp: &PublicParams<G>,
+        sk: &DdhSecretKey<G>,
+        iparams: &DdhIssuerParams<G>,
+        presentation: &DdhShowCredential<G>,
+        context: &PresentationContext,
+    ) -> Result<bool, Error> {
+        if presentation.u.is_zero()
+            || presentation.u.mul(sk.z).into_affine() != presentation.u_z
+            || !covers_all_attributes(pp, &presentation.disclosed, &presentation.commitments)
+        {
+            return Ok(false);
+        }
+
+        let v_x = recompute_v(
+            &sk.x,
+            &presentation.u,
+            &presentation.disclosed,
+            &presentation.commitments,
+            &presentation.c_u_x,
+        );
+        let v_y = recompute_v(
+            &sk.y,
+            &presentation.u,
+            &presentation.disclosed,
+            &presentation.commitments,
+            &presentation.c_u_y,
+        );
+        let equations = show_equations(
+            pp,
+            &presentation.u,
+            &presentation.commitments,
+            &[(&iparams.x, v_x), (&iparams.y, v_y)],
+        );
+        presentation.proof.verify(SHOW_DOMAIN, context, &equations)
+    }
+}
+
+/// witnesses b, the x block and the y block of `mac_equations`, then z
+///   u = g^b, `mac_equations` for x and for y, Z = h^z, u_z = u^z
+fn response_equations<G: AffineRepr>(
+    pp: &PublicParams<G>,
+    iparams: &DdhIssuerParams<G>,
+    request: &IssuanceRequest<G>,
+    u: &G,
+    u_z: &G,
+    e_x: &(G, G),
+    e_y: &(G, G),
+) -> Vec<Equation<G>> {
+    // k0, k0_tilde, k_1..k_n, r, t_1..t_l
+    let block = pp.n + 3 + request.hidden.len();
+    let z = 1 + 2 * block;
+
+    let mut equations = vec![Equation::new(*u, vec![(pp.g, 0)])];
+    equations.extend(mac_equations(
+        pp, &iparams.x, request, u, &e_x.0, &e_x.1, 0, 1,
+    ));
+    equations.extend(mac_equations(
+        pp,
+        &iparams.y,
+        request,
+        u,
+        &e_y.0,
+        &e_y.1,
+        0,
+        1 + block,
+    ));
+    equations.push(Equation::new(iparams.z, vec![(pp.h, z)]));
+    equations.push(Equation::new(*u_z, vec![(*u, z)]));
+    equations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anon_cred::AnonCredProtocol;
+    use crate::keygen::gen_ddh_keys;
+    use ark_std::{test_rng, UniformRand};
+
+    struct Setup<G: AffineRepr> {
+        pp: PublicParams<G>,
+        sk: DdhSecretKey<G>,
+        iparams: DdhIssuerParams<G>,
+        attributes: Vec<G::ScalarField>,
+    }
+
+    fn setup<G: AffineRepr>(n: usize, rng: &mut impl Rng) -> Setup<G> {
+        let pp = PublicParams::<G>::new(&n, rng);
+        let (sk, iparams) = gen_ddh_keys(&pp, rng);
+        let attributes = (0..n).map(|_| G::ScalarField::rand(rng)).collect();
+        Setup {
+            pp,
+            sk,
+            iparams,
+            attributes,
+        }
+    }
+
+    fn issue<G: AffineRepr>(
+        s: &Setup<G>,
+        hidden: &[usize],
+        rng: &mut impl Rng,
+    ) -> DdhCredential<G> {
+        let (request, key) = AnonCredProtocol::obtain(&s.pp, &s.attributes, hidden, rng).unwrap();
+        let response = DdhAnonCredProtocol::issue(&s.pp, &s.sk, &s.iparams, &request, rng).unwrap();
+        DdhAnonCredProtocol::complete_credential(
+            &s.pp,
+            &s.iparams,
+            &request,
+            &response,
+            &key,
+            &s.attributes,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_ddh_blind_issue_show_verify() {
+        fn check<G: AffineRepr>() {
+            let mut rng = test_rng();
+            let s = setup::<G>(4, &mut rng);
+            let context = PresentationContext::new(b"verifier", b"nonce", 1_700_000_000);
+
+            for (hidden, disclosed) in [(vec![0, 2], vec![1, 3]), (vec![], vec![0, 1, 2, 3])] {
+                let credential = issue(&s, &hidden, &mut rng);
+                assert!(credential.mac.verify(&s.sk, &s.attributes));
+
+                let presentation = DdhAnonCredProtocol::show(
+                    &s.pp,
+                    &s.iparams,
+                    &credential,
+                    &disclosed,
+                    &context,
+                    &mut rng,
+                )
+                .unwrap();
+                assert!(DdhAnonCredProtocol::verify(
+                    &s.pp,
+                    &s.sk,
+                    &s.iparams,
+                    &presentation,
+                    &context
+                )
+                .unwrap());
+            }
+
+            let credential = issue(&s, &[1, 2, 3], &mut rng);
+            let presentation =
+                DdhAnonCredProtocol::show(&s.pp, &s.iparams, &credential, &[0], &context, &mut rng)
+                    .unwrap();
+
+            // bound to the verifier's context
+            let replay = PresentationContext::new(b"verifier", b"nonce-2", 1_700_000_000);
+            assert!(
+                !DdhAnonCredProtocol::verify(&s.pp, &s.sk, &s.iparams, &presentation, &replay)
+                    .unwrap()
+            );
+
+            // u_z that is not u^z
+            let mut forged = presentation.clone();
+            forged.u_z = (forged.u_z.into_group() + s.pp.g).into_affine();
+            assert!(
+                !DdhAnonCredProtocol::verify(&s.pp, &s.sk, &s.iparams, &forged, &context).unwrap()
+            );
+
+            // a disclosed value the MAC was not computed on
+            let mut forged = presentation.clone();
+            forged.disclosed[0].1 += G::ScalarField::from(1u64);
+            assert!(
+                !DdhAnonCredProtocol::verify(&s.pp, &s.sk, &s.iparams, &forged, &context).unwrap()
+            );
+
+            // another issuer's key rejects it
+            let other = setup::<G>(4, &mut rng);
+            assert!(!DdhAnonCredProtocol::verify(
+                &s.pp,
+                &other.sk,
+                &s.iparams,
+                &presentation,
+                &context
+            )
+            .unwrap());
+        }
+
+        check::<ark_bls12_381::G1Affine>();
+        check::<ark_secp256k1::Affine>();
+    }
+
+    #[test]
+    fn test_ddh_issuance_rejects_tampering() {
+        fn check<G: AffineRepr>() {
+            let mut rng = test_rng();
+            let s = setup::<G>(3, &mut rng);
+            let (request, key) =
+                AnonCredProtocol::obtain(&s.pp, &s.attributes, &[0, 2], &mut rng).unwrap();
+
+            let mut tampered = request.clone();
+            tampered.hidden[0].c1 = (tampered.hidden[0].c1.into_group() + s.pp.g).into_affine();
+            assert!(matches!(
+                DdhAnonCredProtocol::issue(&s.pp, &s.sk, &s.iparams, &tampered, &mut rng),
+                Err(Error::InvalidRequestProof)
+            ));
+
+            // u_z swapped for another value, and a response under another key
+            let mut response =
+                DdhAnonCredProtocol::issue(&s.pp, &s.sk, &s.iparams, &request, &mut rng).unwrap();
+            response.u_z = (response.u_z.into_group() + s.pp.h).into_affine();
+            let other = setup::<G>(3, &mut rng);
+            let foreign =
+                DdhAnonCredProtocol::issue(&s.pp, &other.sk, &other.iparams, &request, &mut rng)
+                    .unwrap();
+            for response in [response, foreign] {
+                assert!(matches!(
+                    DdhAnonCredProtocol::complete_credential(
+                        &s.pp,
+                        &s.iparams,
+                        &request,
+                        &response,
+                        &key,
+                        &s.attributes
+                    ),
+                    Err(Error::InvalidResponseProof)
+                ));
+            }
+        }
+
+        check::<ark_bls12_381::G1Affine>();
+        check::<ark_secp256k1::Affine>();
+    }
+}
@@ -0,0 +1,15 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Expected {expected} attributes, got {found}")]
+    AttributeCount { expected: usize, found: usize },
+    #[error("Invalid attribute index {0}")]
+    InvalidAttributeIndex(usize),
+    #[error("Issuance request proof is invalid")]
+    InvalidRequestProof,
+    #[error("Issuance response proof is invalid")]
+    InvalidResponseProof,
+    #[error("Serialization error: {0}")]
+    SerializationError(#[from] ark_serialize::SerializationError),
+}
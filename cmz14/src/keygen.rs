@@ -0,0 +1,118 @@
+use crate::publicparams::PublicParams;
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{rand::Rng, UniformRand};
+
+/// MAC_GGM key (x0, x1..xn) and the opening x0_tilde of the commitment to x0
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct SecretKey<G: AffineRepr> {
+    pub x0: G::ScalarField,
+    pub x0_tilde: G::ScalarField,
+    pub x: Vec<G::ScalarField>,
+}
+
+/// Public commitments to the MAC key, C_x0 = g^x0 h^x0_tilde and X_i = h^x_i. Users check the
+/// issuer's MACs against them and use them to prove possession when showing.
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct IssuerParams<G: AffineRepr> {
+    pub cx0: G,
+    pub x: Vec<G>,
+}
+
+pub fn gen_keys<G: AffineRepr, R: Rng>(
+    pp: &PublicParams<G>,
+    rng: &mut R,
+) -> (SecretKey<G>, IssuerParams<G>) {
+    let x0 = G::ScalarField::rand(rng);
+    let x0_tilde = G::ScalarField::rand(rng);
+    let x: Vec<G::ScalarField> = (0..pp.n).map(|_| G::ScalarField::rand(rng)).collect();
+
+    let cx0 = (pp.g.mul(x0) + pp.h.mul(x0_tilde)).into_affine();
+    let big_x = x.iter().map(|xi| pp.h.mul(*xi).into_affine()).collect();
+
+    (
+        SecretKey { x0, x0_tilde, x },
+        IssuerParams { cx0, x: big_x },
+    )
+}
+
+/// MAC_DDH key: two MAC_GGM keys x and y evaluated at the same u, and z for the tag's u^z
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct DdhSecretKey<G: AffineRepr> {
+    pub x: SecretKey<G>,
+    pub y: SecretKey<G>,
+    pub z: G::ScalarField,
+}
+
+/// Commitments to a MAC_DDH key, the issuer parameters of x and y and Z = h^z
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct DdhIssuerParams<G: AffineRepr> {
+    pub x: IssuerParams<G>,
+    pub y: IssuerParams<G>,
+    pub z: G,
+}
+
+pub fn gen_ddh_keys<G: AffineRepr, R: Rng>(
+    pp: &PublicParams<G>,
+    rng: &mut R,
+) -> (DdhSecretKey<G>, DdhIssuerParams<G>) {
+    let (x_sk, x_params) = gen_keys(pp, rng);
+    let (y_sk, y_params) = gen_keys(pp, rng);
+    let z = G::ScalarField::rand(rng);
+
+    (
+        DdhSecretKey {
+            x: x_sk,
+            y: y_sk,
+            z,
+        },
+        DdhIssuerParams {
+            x: x_params,
+            y: y_params,
+            z: pp.h.mul(z).into_affine(),
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_std::test_rng;
+
+    #[test]
+    fn test_keygen() {
+        fn check<G: AffineRepr>() {
+            let mut rng = test_rng();
+            let pp = PublicParams::<G>::new(&3, &mut rng);
+            let (sk, iparams) = gen_keys(&pp, &mut rng);
+            assert_eq!(sk.x.len(), 3);
+            assert_eq!(iparams.x.len(), 3);
+            assert_eq!(
+                iparams.cx0,
+                (pp.g.mul(sk.x0) + pp.h.mul(sk.x0_tilde)).into_affine()
+            );
+            for (xi, big_xi) in sk.x.iter().zip(&iparams.x) {
+                assert_eq!(*big_xi, pp.h.mul(*xi).into_affine());
+            }
+        }
+
+        check::<ark_bls12_381::G1Affine>();
+        check::<ark_secp256k1::Affine>();
+    }
+
+    #[test]
+    fn test_ddh_keygen() {
+        fn check<G: AffineRepr>() {
+            let mut rng = test_rng();
+            let pp = PublicParams::<G>::new(&3, &mut rng);
+            let (sk, iparams) = gen_ddh_keys(&pp, &mut rng);
+            assert_eq!(iparams.x.x.len(), 3);
+            assert_eq!(iparams.y.x.len(), 3);
+            assert_ne!(sk.x.x0, sk.y.x0);
+            assert_eq!(iparams.z, pp.h.mul(sk.z).into_affine());
+        }
+
+        check::<ark_bls12_381::G1Affine>();
+        check::<ark_secp256k1::Affine>();
+    }
+}
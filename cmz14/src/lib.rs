@@ -0,0 +1,16 @@
+//! Keyed-verification anonymous credentials from algebraic MACs, following CMZ14
+//! (<https://eprint.iacr.org/2013/516>). The issuer is also the verifier, so credentials are
+//! MAC_GGM or MAC_DDH tags over a prime-order group instead of signatures and no step needs a
+//! pairing. Everything is generic over `AffineRepr`, any prime-order group works.
+//!
+//! `anon_cred` implements MAC_GGM credentials, secure in the generic group model.
+//! `anon_cred_ddh` implements MAC_DDH credentials, which rely on DDH instead at the cost of a
+//! second key and larger tags, and reuses the MAC_GGM issuance request.
+
+pub mod anon_cred;
+pub mod anon_cred_ddh;
+pub mod error;
+pub mod keygen;
+pub mod mac;
+pub mod proof;
+pub mod publicparams;
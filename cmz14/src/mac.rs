@@ -0,0 +1,189 @@
+use crate::error::Error;
+use crate::keygen::{DdhSecretKey, SecretKey};
+use crate::publicparams::PublicParams;
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::Zero;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{rand::Rng, UniformRand};
+
+/// MAC_GGM tag (u, u') on messages m with u' = u^{x0 + sum x_i m_i}
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct MacGgm<G: AffineRepr> {
+    pub u: G,
+    pub u_prime: G,
+}
+
+impl<G: AffineRepr> MacGgm<G> {
+    /// x0 + sum x_i m_i
+    pub fn exponent(sk: &SecretKey<G>, messages: &[G::ScalarField]) -> G::ScalarField {
+        sk.x.iter()
+            .zip(messages)
+            .fold(sk.x0, |acc, (xi, mi)| acc + *xi * mi)
+    }
+
+    pub fn mac<R: Rng>(
+        pp: &PublicParams<G>,
+        sk: &SecretKey<G>,
+        messages: &[G::ScalarField],
+        rng: &mut R,
+    ) -> Result<Self, Error> {
+        if messages.len() != sk.x.len() {
+            return Err(Error::AttributeCount {
+                expected: sk.x.len(),
+                found: messages.len(),
+            });
+        }
+        let b = loop {
+            let b = G::ScalarField::rand(rng);
+            if !b.is_zero() {
+                break b;
+            }
+        };
+        let u = pp.g.mul(b).into_affine();
+        let u_prime = u.mul(Self::exponent(sk, messages)).into_affine();
+        Ok(MacGgm { u, u_prime })
+    }
+
+    pub fn verify(&self, sk: &SecretKey<G>, messages: &[G::ScalarField]) -> bool {
+        messages.len() == sk.x.len()
+            && !self.u.is_zero()
+            && self.u.mul(Self::exponent(sk, messages)).into_affine() == self.u_prime
+    }
+
+    /// (u^a, u'^a) is a fresh, unlinkable tag on the same messages
+    pub fn randomize(&self, a: &G::ScalarField) -> Self {
+        MacGgm {
+            u: self.u.mul(*a).into_affine(),
+            u_prime: self.u_prime.mul(*a).into_affine(),
+        }
+    }
+}
+
+/// MAC_DDH tag (u, u_x, u_y, u_z) on messages m with u = g^b, u_x = u^{x0 + sum x_i m_i},
+/// u_y = u^{y0 + sum y_i m_i} and u_z = u^z
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct MacDdh<G: AffineRepr> {
+    pub u: G,
+    pub u_x: G,
+    pub u_y: G,
+    pub u_z: G,
+}
+
+impl<G: AffineRepr> MacDdh<G> {
+    pub fn mac<R: Rng>(
+        pp: &PublicParams<G>,
+        sk: &DdhSecretKey<G>,
+        messages: &[G::ScalarField],
+        rng: &mut R,
+    ) -> Result<Self, Error> {
+        let x_tag = MacGgm::mac(pp, &sk.x, messages, rng)?;
+        let u = x_tag.u;
+        Ok(MacDdh {
+            u,
+            u_x: x_tag.u_prime,
+            u_y: u.mul(MacGgm::exponent(&sk.y, messages)).into_affine(),
+            u_z: u.mul(sk.z).into_affine(),
+        })
+    }
+
+    pub fn verify(&self, sk: &DdhSecretKey<G>, messages: &[G::ScalarField]) -> bool {
+        let x_tag = MacGgm {
+            u: self.u,
+            u_prime: self.u_x,
+        };
+        let y_tag = MacGgm {
+            u: self.u,
+            u_prime: self.u_y,
+        };
+        x_tag.verify(&sk.x, messages)
+            && y_tag.verify(&sk.y, messages)
+            && self.u.mul(sk.z).into_affine() == self.u_z
+    }
+
+    /// every component raised to a, a fresh tag on the same messages
+    pub fn randomize(&self, a: &G::ScalarField) -> Self {
+        MacDdh {
+            u: self.u.mul(*a).into_affine(),
+            u_x: self.u_x.mul(*a).into_affine(),
+            u_y: self.u_y.mul(*a).into_affine(),
+            u_z: self.u_z.mul(*a).into_affine(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keygen::{gen_ddh_keys, gen_keys};
+    use ark_std::test_rng;
+
+    #[test]
+    fn test_mac_ggm() {
+        fn check<G: AffineRepr>() {
+            let mut rng = test_rng();
+            let pp = PublicParams::<G>::new(&4, &mut rng);
+            let (sk, _) = gen_keys(&pp, &mut rng);
+            let messages: Vec<G::ScalarField> =
+                (0..4).map(|_| G::ScalarField::rand(&mut rng)).collect();
+
+            let mac = MacGgm::mac(&pp, &sk, &messages, &mut rng).unwrap();
+            assert!(mac.verify(&sk, &messages));
+            assert!(mac
+                .randomize(&G::ScalarField::rand(&mut rng))
+                .verify(&sk, &messages));
+
+            let mut modified = messages.clone();
+            modified[1] += G::ScalarField::from(1u64);
+            assert!(!mac.verify(&sk, &modified));
+            let (other_sk, _) = gen_keys(&pp, &mut rng);
+            assert!(!mac.verify(&other_sk, &messages));
+            assert!(!MacGgm {
+                u: G::zero(),
+                u_prime: G::zero()
+            }
+            .verify(&sk, &messages));
+            assert!(matches!(
+                MacGgm::mac(&pp, &sk, &messages[..3], &mut rng),
+                Err(Error::AttributeCount {
+                    expected: 4,
+                    found: 3
+                })
+            ));
+        }
+
+        check::<ark_bls12_381::G1Affine>();
+        check::<ark_secp256k1::Affine>();
+    }
+
+    #[test]
+    fn test_mac_ddh() {
+        fn check<G: AffineRepr>() {
+            let mut rng = test_rng();
+            let pp = PublicParams::<G>::new(&4, &mut rng);
+            let (sk, _) = gen_ddh_keys(&pp, &mut rng);
+            let messages: Vec<G::ScalarField> =
+                (0..4).map(|_| G::ScalarField::rand(&mut rng)).collect();
+
+            let mac = MacDdh::mac(&pp, &sk, &messages, &mut rng).unwrap();
+            assert!(mac.verify(&sk, &messages));
+            assert!(mac
+                .randomize(&G::ScalarField::rand(&mut rng))
+                .verify(&sk, &messages));
+
+            let mut modified = messages.clone();
+            modified[2] += G::ScalarField::from(1u64);
+            assert!(!mac.verify(&sk, &modified));
+
+            // each component is checked, a tag that is only right under x is rejected
+            let mut wrong_y = mac.clone();
+            wrong_y.u_y = mac.u_x;
+            assert!(!wrong_y.verify(&sk, &messages));
+            let mut wrong_z = mac.clone();
+            wrong_z.u_z = mac.u;
+            assert!(!wrong_z.verify(&sk, &messages));
+        }
+
+        check::<ark_bls12_381::G1Affine>();
+        check::<ark_secp256k1::Affine>();
+    }
+}
@@ -0,0 +1,162 @@
+use crate::error::Error;
+use ark_ec::AffineRepr;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{rand::Rng, UniformRand};
+use schnorr::schnorr::{SchnorrCommitment, SchnorrProtocol};
+use utils::context::PresentationContext;
+
+/// One equation `statement = sum bases[k] * w[indices[k]]` over a witness vector `w`
+#[derive(Clone, Debug)]
+pub struct Equation<G: AffineRepr> {
+    pub statement: G,
+    pub bases: Vec<G>,
+    pub indices: Vec<usize>,
+}
+
+impl<G: AffineRepr> Equation<G> {
+    pub fn new(statement: G, terms: Vec<(G, usize)>) -> Self {
+        let (bases, indices) = terms.into_iter().unzip();
+        Self {
+            statement,
+            bases,
+            indices,
+        }
+    }
+}
+
+/// Non-interactive proof that one witness vector satisfies a set of equations. Every equation is
+/// a Schnorr proof, all of them share the blinding of a witness and the challenge, which proves
+/// the witnesses at equal indices equal.
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct LinearProof<G: AffineRepr> {
+    pub commitments: Vec<G>,
+    pub responses: Vec<G::ScalarField>,
+}
+
+impl<G: AffineRepr> LinearProof<G> {
+    pub fn prove<R: Rng>(
+        domain: &[u8],
+        context: &PresentationContext,
+        equations: &[Equation<G>],
+        witnesses: &[G::ScalarField],
+        rng: &mut R,
+    ) -> Result<Self, Error> {
+        let blindings: Vec<G::ScalarField> = (0..witnesses.len())
+            .map(|_| G::ScalarField::rand(rng))
+            .collect();
+        let commitments: Vec<G> = equations
+            .iter()
+            .map(|eq| {
+                let eq_blindings: Vec<G::ScalarField> =
+                    eq.indices.iter().map(|i| blindings[*i]).collect();
+                SchnorrProtocol::commit_with_prepared_blindings(&eq.bases, &eq_blindings)
+                    .commited_blindings
+            })
+            .collect();
+        let challenge = Self::challenge(domain, context, equations, &commitments)?;
+
+        // the blindings are shared, so one response per witness serves every equation
+        let shared = SchnorrCommitment {
+            random_blindings: blindings,
+            commited_blindings: G::zero(),
+        };
+        let responses = SchnorrProtocol::prove(&shared, witnesses, &challenge).0;
+
+        Ok(LinearProof {
+            commitments,
+            responses,
+        })
+    }
+
+    pub fn verify(
+        &self,
+        domain: &[u8],
+        context: &PresentationContext,
+        equations: &[Equation<G>],
+    ) -> Result<bool, Error> {
+        let witness_count = equations
+            .iter()
+            .flat_map(|eq| eq.indices.iter())
+            .max()
+            .map_or(0, |i| i + 1);
+        if self.commitments.len() != equations.len() || self.responses.len() != witness_count {
+            return Ok(false);
+        }
+        let challenge = Self::challenge(domain, context, equations, &self.commitments)?;
+
+        Ok(equations
+            .iter()
+            .zip(&self.commitments)
+            .all(|(eq, commitment)| {
+                let responses: Vec<G::ScalarField> =
+                    eq.indices.iter().map(|i| self.responses[*i]).collect();
+                SchnorrProtocol::verify_schnorr(
+                    &eq.bases,
+                    &eq.statement,
+                    commitment,
+                    &responses,
+                    &challenge,
+                )
+            }))
+    }
+
+    fn challenge(
+        domain: &[u8],
+        context: &PresentationContext,
+        equations: &[Equation<G>],
+        commitments: &[G],
+    ) -> Result<G::ScalarField, Error> {
+        let mut transcript = Vec::new();
+        for eq in equations {
+            eq.statement.serialize_compressed(&mut transcript)?;
+            eq.bases.serialize_compressed(&mut transcript)?;
+            eq.indices.serialize_compressed(&mut transcript)?;
+        }
+        commitments.serialize_compressed(&mut transcript)?;
+        Ok(context.challenge(domain, &transcript))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ec::CurveGroup;
+    use ark_std::test_rng;
+
+    #[test]
+    fn test_linear_proof_shared_witness() {
+        fn check<G: AffineRepr>() {
+            let mut rng = test_rng();
+            let g = G::Group::rand(&mut rng).into_affine();
+            let h = G::Group::rand(&mut rng).into_affine();
+            let w: Vec<G::ScalarField> = (0..2).map(|_| G::ScalarField::rand(&mut rng)).collect();
+            // y1 = g^w0, y2 = h^w0 g^w1
+            let y1 = g.mul(w[0]).into_affine();
+            let y2 = (h.mul(w[0]) + g.mul(w[1])).into_affine();
+            let equations = vec![
+                Equation::new(y1, vec![(g, 0)]),
+                Equation::new(y2, vec![(h, 0), (g, 1)]),
+            ];
+            let context = PresentationContext::new(b"verifier", b"nonce", 0);
+
+            let proof = LinearProof::prove(b"test", &context, &equations, &w, &mut rng).unwrap();
+            assert!(proof.verify(b"test", &context, &equations).unwrap());
+            assert!(!proof.verify(b"other", &context, &equations).unwrap());
+            let other_context = PresentationContext::new(b"verifier", b"nonce-2", 0);
+            assert!(!proof.verify(b"test", &other_context, &equations).unwrap());
+
+            // y2 with a different first witness breaks the equality across equations
+            let w_prime = G::ScalarField::rand(&mut rng);
+            let y2_prime = (h.mul(w_prime) + g.mul(w[1])).into_affine();
+            let unequal = vec![
+                Equation::new(y1, vec![(g, 0)]),
+                Equation::new(y2_prime, vec![(h, 0), (g, 1)]),
+            ];
+            let bad = LinearProof::prove(b"test", &context, &unequal, &w, &mut rng).unwrap();
+            assert!(!bad.verify(b"test", &context, &unequal).unwrap());
+        }
+
+        check::<ark_bls12_381::G1Affine>();
+        check::<ark_secp256k1::Affine>();
+    }
+}
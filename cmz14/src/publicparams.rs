@@ -0,0 +1,42 @@
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{rand::Rng, UniformRand};
+
+/// Group generators shared by issuer and users. Nobody may know the discrete log of `h` base `g`,
+/// so in a deployment both come from a trusted setup rather than from the issuer.
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PublicParams<G: AffineRepr> {
+    /// number of attributes in a credential
+    pub n: usize,
+    pub g: G,
+    pub h: G,
+}
+
+impl<G: AffineRepr> PublicParams<G> {
+    pub fn new<R: Rng>(n: &usize, rng: &mut R) -> Self {
+        Self {
+            n: *n,
+            g: G::generator(),
+            h: G::Group::rand(rng).into_affine(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_std::test_rng;
+
+    #[test]
+    fn test_pp_gen() {
+        fn check<G: AffineRepr>() {
+            let pp = PublicParams::<G>::new(&4, &mut test_rng());
+            assert_eq!(pp.n, 4);
+            assert!(!pp.h.is_zero());
+            assert_ne!(pp.g, pp.h);
+        }
+
+        check::<ark_bls12_381::G1Affine>();
+        check::<ark_secp256k1::Affine>();
+    }
+}
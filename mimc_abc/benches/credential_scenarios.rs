@@ -1,4 +1,4 @@
-use ark_bls12_381::{Bls12_381, Fr};
+use ark_ec::pairing::Pairing;
 use ark_ff::UniformRand;
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
 use mimc_abc::{
@@ -8,9 +8,10 @@ use mimc_abc::{
     public_params::PublicParams,
     signature::VerificationKey,
 };
+use utils::envelope::CurveIdentifier;
 
-fn benchmark_verification_methods(c: &mut Criterion) {
-    let mut group = c.benchmark_group("mimc_abc");
+fn benchmark_verification_methods<E: Pairing + CurveIdentifier>(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!("mimc_abc_{}", E::CURVE_ID.name()));
 
     for credential_count in [4, 16, 32].iter() {
         for attribute_count in [4, 16, 32].iter() {
@@ -25,27 +26,27 @@ fn benchmark_verification_methods(c: &mut Criterion) {
                     // Setup code OUTSIDE benchmark
                     let mut rng = ark_std::test_rng();
                     let (protocol, issuer_sk, issuer_vk) =
-                        MimcAbc::<Bls12_381>::setup(attr_count, &mut rng);
+                        MimcAbc::<E>::setup(attr_count, &mut rng);
 
                     // Create credentials without privacy features
-                    let user_id = Fr::rand(&mut rng);
+                    let user_id = E::ScalarField::rand(&mut rng);
                     let mut credentials = Vec::new();
 
                     for _ in 0..cred_count {
                         // Create basic credential
                         let mut attributes = vec![user_id]; // First attribute is user ID
                         for _ in 1..attr_count {
-                            attributes.push(Fr::rand(&mut rng));
+                            attributes.push(E::ScalarField::rand(&mut rng));
                         }
 
-                        let r = Fr::rand(&mut rng);
+                        let r = E::ScalarField::rand(&mut rng);
                         let mut credential =
                             Credential::new(&protocol.ck, &protocol.pp, &attributes, r);
 
                         // Issue credential
                         let proof = credential.prove_commitment(&protocol.pp, &mut rng);
-                        let signature = protocol.issue(&proof, &issuer_sk, &mut rng).unwrap();
-                        credential.add_signature(signature);
+                        let response = protocol.issue(&proof, &issuer_sk, &mut rng).unwrap();
+                        credential.add_issuer_response(response);
 
                         credentials.push(credential);
                     }
@@ -68,26 +69,26 @@ fn benchmark_verification_methods(c: &mut Criterion) {
                     // Setup code OUTSIDE benchmark
                     let mut rng = ark_std::test_rng();
                     let (protocol, issuer_sk, issuer_vk) =
-                        MimcAbc::<Bls12_381>::setup(attr_count, &mut rng);
+                        MimcAbc::<E>::setup(attr_count, &mut rng);
 
                     // Create credentials without privacy features
-                    let user_id = Fr::rand(&mut rng);
+                    let user_id = E::ScalarField::rand(&mut rng);
                     let mut credentials = Vec::new();
 
                     for _ in 0..cred_count {
                         // Create credential as before
                         let mut attributes = vec![user_id];
                         for _ in 1..attr_count {
-                            attributes.push(Fr::rand(&mut rng));
+                            attributes.push(E::ScalarField::rand(&mut rng));
                         }
 
-                        let r = Fr::rand(&mut rng);
+                        let r = E::ScalarField::rand(&mut rng);
                         let mut credential =
                             Credential::new(&protocol.ck, &protocol.pp, &attributes, r);
 
                         let proof = credential.prove_commitment(&protocol.pp, &mut rng);
-                        let signature = protocol.issue(&proof, &issuer_sk, &mut rng).unwrap();
-                        credential.add_signature(signature);
+                        let response = protocol.issue(&proof, &issuer_sk, &mut rng).unwrap();
+                        credential.add_issuer_response(response);
 
                         credentials.push(credential);
                     }
@@ -111,27 +112,27 @@ fn benchmark_verification_methods(c: &mut Criterion) {
                     // Setup code for single issuer - OUTSIDE benchmark
                     let mut rng = ark_std::test_rng();
                     let (protocol, issuer_sk, issuer_vk) =
-                        MimcAbc::<Bls12_381>::setup(attr_count, &mut rng);
+                        MimcAbc::<E>::setup(attr_count, &mut rng);
 
                     // Create credentials with privacy features
-                    let user_id = Fr::rand(&mut rng);
+                    let user_id = E::ScalarField::rand(&mut rng);
                     let mut credentials = Vec::new();
 
                     for _ in 0..cred_count {
                         // Create credential
                         let mut attributes = vec![user_id];
                         for _ in 1..attr_count {
-                            attributes.push(Fr::rand(&mut rng));
+                            attributes.push(E::ScalarField::rand(&mut rng));
                         }
 
-                        let r = Fr::rand(&mut rng);
+                        let r = E::ScalarField::rand(&mut rng);
                         let mut credential =
                             Credential::new(&protocol.ck, &protocol.pp, &attributes, r);
 
                         // Issue credential
                         let proof = credential.prove_commitment(&protocol.pp, &mut rng);
-                        let signature = protocol.issue(&proof, &issuer_sk, &mut rng).unwrap();
-                        credential.add_signature(signature);
+                        let response = protocol.issue(&proof, &issuer_sk, &mut rng).unwrap();
+                        credential.add_issuer_response(response);
 
                         credentials.push(credential);
                     }
@@ -156,27 +157,27 @@ fn benchmark_verification_methods(c: &mut Criterion) {
                     // Setup code for single issuer - OUTSIDE benchmark
                     let mut rng = ark_std::test_rng();
                     let (protocol, issuer_sk, issuer_vk) =
-                        MimcAbc::<Bls12_381>::setup(attr_count, &mut rng);
+                        MimcAbc::<E>::setup(attr_count, &mut rng);
 
                     // Create credentials with privacy features
-                    let user_id = Fr::rand(&mut rng);
+                    let user_id = E::ScalarField::rand(&mut rng);
                     let mut credentials = Vec::new();
 
                     for _ in 0..cred_count {
                         // Create credential
                         let mut attributes = vec![user_id];
                         for _ in 1..attr_count {
-                            attributes.push(Fr::rand(&mut rng));
+                            attributes.push(E::ScalarField::rand(&mut rng));
                         }
 
-                        let r = Fr::rand(&mut rng);
+                        let r = E::ScalarField::rand(&mut rng);
                         let mut credential =
                             Credential::new(&protocol.ck, &protocol.pp, &attributes, r);
 
                         // Issue credential
                         let proof = credential.prove_commitment(&protocol.pp, &mut rng);
-                        let signature = protocol.issue(&proof, &issuer_sk, &mut rng).unwrap();
-                        credential.add_signature(signature);
+                        let response = protocol.issue(&proof, &issuer_sk, &mut rng).unwrap();
+                        credential.add_issuer_response(response);
 
                         credentials.push(credential);
                     }
@@ -215,14 +216,14 @@ fn benchmark_verification_methods(c: &mut Criterion) {
 
                     for _ in 0..issuer_count {
                         let (protocol, issuer_sk, issuer_vk) =
-                            MimcAbc::<Bls12_381>::setup(attr_count, &mut rng);
+                            MimcAbc::<E>::setup(attr_count, &mut rng);
                         protocols.push(protocol);
                         issuer_sks.push(issuer_sk);
                         issuer_vks.push(issuer_vk);
                     }
 
                     // Create credentials with same user ID across issuers
-                    let user_id = Fr::rand(&mut rng);
+                    let user_id = E::ScalarField::rand(&mut rng);
                     let mut all_credentials = Vec::new();
                     let mut credential_to_issuer = Vec::new();
 
@@ -233,10 +234,10 @@ fn benchmark_verification_methods(c: &mut Criterion) {
                         // Create credential with user_id
                         let mut attributes = vec![user_id];
                         for _ in 1..attr_count {
-                            attributes.push(Fr::rand(&mut rng));
+                            attributes.push(E::ScalarField::rand(&mut rng));
                         }
 
-                        let r = Fr::rand(&mut rng);
+                        let r = E::ScalarField::rand(&mut rng);
                         let mut credential = Credential::new(
                             &protocols[issuer_idx].ck,
                             &protocols[issuer_idx].pp,
@@ -247,10 +248,10 @@ fn benchmark_verification_methods(c: &mut Criterion) {
                         // Issue credential
                         let proof =
                             credential.prove_commitment(&protocols[issuer_idx].pp, &mut rng);
-                        let signature = protocols[issuer_idx]
+                        let response = protocols[issuer_idx]
                             .issue(&proof, &issuer_sks[issuer_idx], &mut rng)
                             .unwrap();
-                        credential.add_signature(signature);
+                        credential.add_issuer_response(response);
 
                         // Simple verification check
                         assert!(
@@ -263,10 +264,10 @@ fn benchmark_verification_methods(c: &mut Criterion) {
                     }
 
                     // Create credential references
-                    let cred_refs: Vec<&Credential<Bls12_381>> = all_credentials.iter().collect();
+                    let cred_refs: Vec<&Credential<E>> = all_credentials.iter().collect();
 
                     // Create public parameter references in the same order as credentials
-                    let pp_refs: Vec<&PublicParams<Bls12_381>> = credential_to_issuer
+                    let pp_refs: Vec<&PublicParams<E>> = credential_to_issuer
                         .iter()
                         .map(|&idx| &protocols[idx].pp)
                         .collect();
@@ -301,14 +302,14 @@ fn benchmark_verification_methods(c: &mut Criterion) {
 
                     for _ in 0..issuer_count {
                         let (protocol, issuer_sk, issuer_vk) =
-                            MimcAbc::<Bls12_381>::setup(attr_count, &mut rng);
+                            MimcAbc::<E>::setup(attr_count, &mut rng);
                         protocols.push(protocol);
                         issuer_sks.push(issuer_sk);
                         issuer_vks.push(issuer_vk);
                     }
 
                     // Create credentials with same user ID across issuers
-                    let user_id = Fr::rand(&mut rng);
+                    let user_id = E::ScalarField::rand(&mut rng);
                     let mut all_credentials = Vec::new();
                     let mut credential_to_issuer = Vec::new();
 
@@ -319,10 +320,10 @@ fn benchmark_verification_methods(c: &mut Criterion) {
                         // Create credential with user_id
                         let mut attributes = vec![user_id];
                         for _ in 1..attr_count {
-                            attributes.push(Fr::rand(&mut rng));
+                            attributes.push(E::ScalarField::rand(&mut rng));
                         }
 
-                        let r = Fr::rand(&mut rng);
+                        let r = E::ScalarField::rand(&mut rng);
                         let mut credential = Credential::new(
                             &protocols[issuer_idx].ck,
                             &protocols[issuer_idx].pp,
@@ -333,10 +334,10 @@ fn benchmark_verification_methods(c: &mut Criterion) {
                         // Issue credential
                         let proof =
                             credential.prove_commitment(&protocols[issuer_idx].pp, &mut rng);
-                        let signature = protocols[issuer_idx]
+                        let response = protocols[issuer_idx]
                             .issue(&proof, &issuer_sks[issuer_idx], &mut rng)
                             .unwrap();
-                        credential.add_signature(signature);
+                        credential.add_issuer_response(response);
 
                         // Simple verification check
                         assert!(
@@ -349,10 +350,10 @@ fn benchmark_verification_methods(c: &mut Criterion) {
                     }
 
                     // Create credential references
-                    let cred_refs: Vec<&Credential<Bls12_381>> = all_credentials.iter().collect();
+                    let cred_refs: Vec<&Credential<E>> = all_credentials.iter().collect();
 
                     // Create public parameter references in the same order as credentials
-                    let pp_refs: Vec<&PublicParams<Bls12_381>> = credential_to_issuer
+                    let pp_refs: Vec<&PublicParams<E>> = credential_to_issuer
                         .iter()
                         .map(|&idx| &protocols[idx].pp)
                         .collect();
@@ -365,7 +366,7 @@ fn benchmark_verification_methods(c: &mut Criterion) {
                         .unwrap();
 
                     // Get verification key references in the same order
-                    let vk_refs: Vec<&VerificationKey<Bls12_381>> = credential_to_issuer
+                    let vk_refs: Vec<&VerificationKey<E>> = credential_to_issuer
                         .iter()
                         .map(|&idx| &issuer_vks[idx])
                         .collect();
@@ -385,5 +386,10 @@ fn benchmark_verification_methods(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, benchmark_verification_methods);
+/// runs the benchmarks once per enabled curve
+fn benchmark_verification_methods_curves(c: &mut Criterion) {
+    utils::for_each_curve!(benchmark_verification_methods(c));
+}
+
+criterion_group!(benches, benchmark_verification_methods_curves);
 criterion_main!(benches);
@@ -2,13 +2,21 @@ use crate::public_params::PublicParams;
 use ark_ec::pairing::Pairing;
 use ark_ec::{CurveGroup, VariableBaseMSM};
 use ark_ff::UniformRand;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::ops::{Add, Mul, Neg};
 use ark_std::rand::Rng;
 use schnorr::schnorr::SchnorrProtocol;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use utils::encoding::Base64Point;
 
-#[derive(Debug, Clone)]
+#[serde_as]
+#[derive(Debug, Clone, CanonicalSerialize, CanonicalDeserialize, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Commitment<E: Pairing> {
+    #[serde_as(as = "Base64Point")]
     pub cm: E::G1Affine,
+    #[serde_as(as = "Base64Point")]
     pub cm_tilde: E::G2Affine,
 }
 
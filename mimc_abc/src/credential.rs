@@ -1,15 +1,26 @@
 use crate::commitment::{Commitment, CommitmentKey};
-use crate::proof::CommitmentProof;
+use crate::error::Error;
+use crate::proof::{CommitmentProof, EscrowedCommitmentProof, PreparedCommitmentProof};
 use crate::public_params::PublicParams;
-use crate::signature::{Signature, VerificationKey};
+use crate::signature::{IssuerResponse, PreparedVerificationKey, Signature, VerificationKey};
 use ark_ec::pairing::Pairing;
 use ark_ec::{CurveGroup, VariableBaseMSM};
 use ark_ff::UniformRand;
-use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, SerializationError, Valid, Validate,
+};
+use ark_std::io::{Read, Write};
 use ark_std::ops::{Add, Mul, Neg};
 use ark_std::rand::Rng;
 use schnorr::schnorr::{SchnorrCommitment, SchnorrProtocol, SchnorrResponses};
+use schnorr::verifiable_encryption::AuditorPublicKey;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
 use thiserror::Error;
+use utils::context::PresentationContext;
+use utils::disclosure::Disclosure;
+use utils::encoding::HexScalar;
+use utils::key_ring::KeyId;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum CredentialState {
@@ -19,12 +30,61 @@ pub enum CredentialState {
     Randomized,  // Has been shown/randomized
 }
 
+impl CredentialState {
+    fn tag(&self) -> u8 {
+        match self {
+            CredentialState::Initialized => 0,
+            CredentialState::Committed => 1,
+            CredentialState::Signed => 2,
+            CredentialState::Randomized => 3,
+        }
+    }
+}
+
+impl CanonicalSerialize for CredentialState {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.tag().serialize_with_mode(writer, compress)
+    }
+
+    fn serialized_size(&self, _compress: Compress) -> usize {
+        1
+    }
+}
+
+impl Valid for CredentialState {
+    fn check(&self) -> Result<(), SerializationError> {
+        Ok(())
+    }
+}
+
+impl CanonicalDeserialize for CredentialState {
+    fn deserialize_with_mode<R: Read>(
+        reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        match u8::deserialize_with_mode(reader, compress, validate)? {
+            0 => Ok(CredentialState::Initialized),
+            1 => Ok(CredentialState::Committed),
+            2 => Ok(CredentialState::Signed),
+            3 => Ok(CredentialState::Randomized),
+            _ => Err(SerializationError::InvalidData),
+        }
+    }
+}
+
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
 pub struct Credential<E: Pairing> {
     pub commitment: Commitment<E>,
     messages: Vec<E::ScalarField>,
     r: E::ScalarField,
     pub signature: Option<Signature<E>>,
     state: CredentialState,
+    key_id: KeyId, // issuer key the signature is under, named in presentations
 }
 
 impl<E: Pairing> Credential<E> {
@@ -42,6 +102,7 @@ impl<E: Pairing> Credential<E> {
             r,
             signature: None,
             state: CredentialState::Committed,
+            key_id: KeyId::default(),
         }
     }
 
@@ -56,6 +117,22 @@ impl<E: Pairing> Credential<E> {
         self.state = CredentialState::Signed;
     }
 
+    // Add signature after issuance and remember the issuer key, so presentations name it
+    pub fn add_signature_under(&mut self, signature: Signature<E>, key_id: KeyId) {
+        self.add_signature(signature);
+        self.key_id = key_id;
+    }
+
+    // Add the signature from an issuer response under the key it names
+    pub fn add_issuer_response(&mut self, response: IssuerResponse<E>) {
+        self.add_signature_under(response.signature, response.key_id);
+    }
+
+    // Issuer key recorded by `add_signature_under`, all zero if unknown
+    pub fn key_id(&self) -> KeyId {
+        self.key_id
+    }
+
     // get messages from credential
     pub fn get_messages(&self) -> &Vec<E::ScalarField> {
         &self.messages
@@ -88,11 +165,97 @@ impl<E: Pairing> Credential<E> {
 
         // Return presentation object
         ShowCredential {
+            key_id: self.key_id,
             randomized_signature,
             randomized_commitment,
             proof,
             r_new: new_r,
+            disclosed: Disclosure::default(),
+        }
+    }
+
+    // Randomize credential for showing to a verifier, the proof is bound to its context
+    pub fn show_with_context(
+        &self,
+        pp: &PublicParams<E>,
+        delta_r: &E::ScalarField,
+        delta_u: &E::ScalarField,
+        context: &PresentationContext,
+        rng: &mut impl Rng,
+    ) -> Result<ShowCredential<E>, Error> {
+        self.precompute_show(pp, delta_r, delta_u, rng)?
+            .finish(context, &[])
+    }
+
+    // Offline half of `show_with_context`: randomizes the credential and commits to the blindings
+    pub fn precompute_show(
+        &self,
+        pp: &PublicParams<E>,
+        delta_r: &E::ScalarField,
+        delta_u: &E::ScalarField,
+        rng: &mut impl Rng,
+    ) -> Result<ShowToken<E>, Error> {
+        if self.state != CredentialState::Signed {
+            return Err(Error::InvalidCredentialState {
+                expected: CredentialState::Signed,
+                actual: self.state.clone(),
+            });
+        }
+        let signature = self.signature.as_ref().ok_or(Error::MissingSignature)?;
+
+        let new_r = self.r + delta_r;
+        let randomized_signature = signature.randomize(delta_r, delta_u);
+        let randomized_commitment = self.commitment.randomize(pp, delta_r);
+        let proof =
+            CommitmentProof::prepare(pp, &randomized_commitment, &self.messages, &new_r, rng);
+
+        Ok(ShowToken {
+            key_id: self.key_id,
+            randomized_signature,
+            randomized_commitment,
+            proof,
+            r_new: new_r,
+        })
+    }
+
+    // Randomize credential for showing, with attribute `index` escrowed to an auditor under `label`
+    pub fn show_with_escrow(
+        &self,
+        pp: &PublicParams<E>,
+        delta_r: &E::ScalarField,
+        delta_u: &E::ScalarField,
+        auditor_pk: &AuditorPublicKey<E::G1Affine>,
+        index: usize,
+        label: &[u8],
+        rng: &mut impl Rng,
+    ) -> Result<EscrowedShowCredential<E>, Error> {
+        if self.state != CredentialState::Signed {
+            return Err(Error::InvalidCredentialState {
+                expected: CredentialState::Signed,
+                actual: self.state.clone(),
+            });
         }
+        let signature = self.signature.as_ref().ok_or(Error::MissingSignature)?;
+
+        let new_r = self.r + delta_r;
+        let randomized_signature = signature.randomize(delta_r, delta_u);
+        let randomized_commitment = self.commitment.randomize(pp, delta_r);
+        let proof = EscrowedCommitmentProof::prove(
+            pp,
+            &randomized_commitment,
+            &self.messages,
+            &new_r,
+            auditor_pk,
+            index,
+            label,
+            rng,
+        )?;
+
+        Ok(EscrowedShowCredential {
+            randomized_signature,
+            randomized_commitment,
+            proof,
+        })
     }
 
     // Get user ID (useful for many applications)
@@ -111,17 +274,56 @@ impl<E: Pairing> Credential<E> {
     }
 }
 
+// Randomized signature and commitment under r_new, the opening proof still lacks its challenge
+pub struct ShowToken<E: Pairing> {
+    key_id: KeyId,
+    randomized_signature: Signature<E>,
+    randomized_commitment: Commitment<E>,
+    proof: PreparedCommitmentProof<E>,
+    r_new: E::ScalarField,
+}
+
+impl<E: Pairing> ShowToken<E> {
+    // Online half of `show_with_context`: binds the token to the verifier's context and reveals
+    // the messages at `disclosed`
+    pub fn finish(
+        self,
+        context: &PresentationContext,
+        disclosed: &[usize],
+    ) -> Result<ShowCredential<E>, Error> {
+        let (proof, disclosed) = self.proof.finish(context, disclosed)?;
+        Ok(ShowCredential {
+            key_id: self.key_id,
+            randomized_signature: self.randomized_signature,
+            randomized_commitment: self.randomized_commitment,
+            proof,
+            r_new: self.r_new,
+            disclosed,
+        })
+    }
+}
+
 // Presentation object for shown credentials
-#[derive(Clone)]
+#[serde_as]
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct ShowCredential<E: Pairing> {
+    pub key_id: KeyId, // issuer key, verifiers holding several pick it by this id
     pub randomized_signature: Signature<E>,
     pub randomized_commitment: Commitment<E>,
     pub proof: CommitmentProof<E>,
+    #[serde_as(as = "HexScalar")]
     pub r_new: E::ScalarField,
+    pub disclosed: Disclosure<E::ScalarField>, // messages revealed by `finish`, empty otherwise
 }
 
 impl<E: Pairing> ShowCredential<E> {
     pub fn verify(&self, pp: &PublicParams<E>, vk: &VerificationKey<E>) -> bool {
+        // a disclosure is only checked against a context bound proof
+        if !self.disclosed.attributes.is_empty() {
+            return false;
+        }
+
         // First verify the proof
         if !self.proof.verify() {
             println!("Show Cred Proof failed");
@@ -136,3 +338,69 @@ impl<E: Pairing> ShowCredential<E> {
         true
     }
 }
+
+impl<E: Pairing> ShowCredential<E> {
+    // Verify a presentation made by show_with_context for this verifier's context
+    pub fn verify_with_context(
+        &self,
+        pp: &PublicParams<E>,
+        vk: &VerificationKey<E>,
+        context: &PresentationContext,
+    ) -> bool {
+        self.verify_with_context_prepared(pp, &vk.prepare(pp), context)
+    }
+
+    // verify_with_context with a prepared key, for verifiers that check many presentations
+    pub fn verify_with_context_prepared(
+        &self,
+        pp: &PublicParams<E>,
+        pvk: &PreparedVerificationKey<E>,
+        context: &PresentationContext,
+    ) -> bool {
+        if self.proof.commitment.cm != self.randomized_commitment.cm {
+            return false;
+        }
+        if !self.proof.verify_with_disclosure(&self.disclosed, context) {
+            return false;
+        }
+        pvk.verify(&self.randomized_signature, &self.randomized_commitment, pp)
+    }
+}
+
+// Presentation object with an escrowed attribute
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct EscrowedShowCredential<E: Pairing> {
+    pub randomized_signature: Signature<E>,
+    pub randomized_commitment: Commitment<E>,
+    pub proof: EscrowedCommitmentProof<E>,
+}
+
+impl<E: Pairing> EscrowedShowCredential<E> {
+    pub fn verify(
+        &self,
+        pp: &PublicParams<E>,
+        vk: &VerificationKey<E>,
+        auditor_pk: &AuditorPublicKey<E::G1Affine>,
+        index: usize,
+        label: &[u8],
+    ) -> bool {
+        self.verify_prepared(pp, &vk.prepare(pp), auditor_pk, index, label)
+    }
+
+    pub fn verify_prepared(
+        &self,
+        pp: &PublicParams<E>,
+        pvk: &PreparedVerificationKey<E>,
+        auditor_pk: &AuditorPublicKey<E::G1Affine>,
+        index: usize,
+        label: &[u8],
+    ) -> bool {
+        if self.proof.proof.commitment.cm != self.randomized_commitment.cm {
+            return false;
+        }
+        if !self.proof.verify(auditor_pk, index, label) {
+            return false;
+        }
+        pvk.verify(&self.randomized_signature, &self.randomized_commitment, pp)
+    }
+}
@@ -1,5 +1,7 @@
 use crate::credential::CredentialState;
 use thiserror::Error;
+use utils::disclosure::DisclosureError;
+use utils::key_ring::KeyRingError;
 
 #[derive(Error, Debug)]
 pub enum Error {
@@ -14,6 +16,10 @@ pub enum Error {
     InvalidProof,
     #[error("Proof verification failed")]
     ProofVerificationFailed,
+    #[error("Invalid attribute index {0}")]
+    InvalidAttributeIndex(usize),
+    #[error("Disclosure error: {0}")]
+    DisclosureError(#[from] DisclosureError),
 
     // Signature errors
     #[error("Invalid signature")]
@@ -30,6 +36,12 @@ pub enum Error {
     #[error("Missing signature on credential")]
     MissingSignature,
 
+    // Policy errors
+    #[error("Issuer key is not in the verifier's policy")]
+    IssuerNotInPolicy,
+    #[error("Issuer key not accepted: {0}")]
+    KeyRingError(#[from] KeyRingError),
+
     // Protocol errors
     #[error("Protocol aborted")]
     ProtocolAborted,
@@ -89,7 +89,11 @@ impl<E: Pairing> IdentityBindingProof<E> {
             exponents.push(randomness[i]);
 
             // Generate responses
-            let responses = SchnorrProtocol::prove(&schnorr_commitments[i], &exponents, &challenge);
+            let responses = SchnorrProtocol::prove_and_erase(
+                &mut schnorr_commitments[i],
+                &exponents,
+                &challenge,
+            );
             all_responses.push(responses.0);
         }
 
@@ -172,18 +176,17 @@ impl IdentityBinding {
 mod tests {
     use super::*;
     use crate::commitment::{Commitment, CommitmentKey};
-    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ec::pairing::Pairing;
     use ark_std::test_rng;
 
-    #[test]
-    fn test_identity_binding_proof() {
+    fn test_identity_binding_proof<E: Pairing>() {
         let mut rng = test_rng();
 
         // Create multiple public parameters
         let n = 5; // Number of attributes in each commitment
-        let pp1 = PublicParams::<Bls12_381>::new(&n, &mut rng);
-        let pp2 = PublicParams::<Bls12_381>::new(&n, &mut rng);
-        let pp3 = PublicParams::<Bls12_381>::new(&n, &mut rng);
+        let pp1 = PublicParams::<E>::new(&n, &mut rng);
+        let pp2 = PublicParams::<E>::new(&n, &mut rng);
+        let pp3 = PublicParams::<E>::new(&n, &mut rng);
 
         // Create commitment keys
         let ck1 = CommitmentKey {
@@ -200,7 +203,7 @@ mod tests {
         };
 
         // Generate a shared identity
-        let user_id = Fr::rand(&mut rng);
+        let user_id = E::ScalarField::rand(&mut rng);
 
         // Create messages with same identity at position 0
         let mut messages1 = vec![user_id];
@@ -209,15 +212,15 @@ mod tests {
 
         // Add random attributes
         for _ in 1..n {
-            messages1.push(Fr::rand(&mut rng));
-            messages2.push(Fr::rand(&mut rng));
-            messages3.push(Fr::rand(&mut rng));
+            messages1.push(E::ScalarField::rand(&mut rng));
+            messages2.push(E::ScalarField::rand(&mut rng));
+            messages3.push(E::ScalarField::rand(&mut rng));
         }
 
         // Generate randomness
-        let r1 = Fr::rand(&mut rng);
-        let r2 = Fr::rand(&mut rng);
-        let r3 = Fr::rand(&mut rng);
+        let r1 = E::ScalarField::rand(&mut rng);
+        let r2 = E::ScalarField::rand(&mut rng);
+        let r3 = E::ScalarField::rand(&mut rng);
 
         // Create commitments
         let commitment1 = ck1.commit(&pp1, &messages1, &r1);
@@ -248,12 +251,12 @@ mod tests {
         );
 
         // Test negative case: different user ID
-        let different_id = Fr::rand(&mut rng);
+        let different_id = E::ScalarField::rand(&mut rng);
         let mut messages4 = vec![different_id]; // Different ID!
         for _ in 1..n {
-            messages4.push(Fr::rand(&mut rng));
+            messages4.push(E::ScalarField::rand(&mut rng));
         }
-        let r4 = Fr::rand(&mut rng);
+        let r4 = E::ScalarField::rand(&mut rng);
         let commitment4 = ck1.commit(&pp1, &messages4, &r4);
 
         // This should fail during proof creation
@@ -270,4 +273,6 @@ mod tests {
             "Proof with different user IDs should fail"
         );
     }
+
+    utils::curve_tests!(test_identity_binding_proof);
 }
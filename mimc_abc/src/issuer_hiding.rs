@@ -0,0 +1,363 @@
+use crate::commitment::Commitment;
+use crate::credential::Credential;
+use crate::error::Error;
+use crate::proof::CommitmentProof;
+use crate::public_params::PublicParams;
+use crate::signature::{PreparedVerificationKey, Signature, VerificationKey};
+use ark_ec::pairing::Pairing;
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{Field, UniformRand, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::ops::{Mul, Neg};
+use ark_std::rand::Rng;
+use utils::context::PresentationContext;
+use utils::key_ring::KeyRing;
+
+// Issuer-hiding presentations following Bobolz et al. 2021. The verifier signs every accepted
+// issuer key as the pair (g_tilde, vk_tilde) with an SPS-EQ signature on G2 vectors. A user moves
+// its issuer's pair to the class representative (g_tilde^gamma, vk_tilde^gamma), adapts the policy
+// signature, and shows the credential against the randomized pair, so the verifier learns that the
+// issuer is in its policy but not which one. All issuers must share the same PublicParams.
+
+/// Verifier's policy signing key
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PolicySecretKey<E: Pairing> {
+    x1: E::ScalarField,
+    x2: E::ScalarField,
+}
+
+/// Verifier's policy verification key (g^x1, g^x2)
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PolicyPublicKey<E: Pairing> {
+    pub x1: E::G1Affine,
+    pub x2: E::G1Affine,
+}
+
+/// SPS-EQ signature on (g_tilde, vk_tilde), valid for every scalar multiple of the pair
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PolicySignature<E: Pairing> {
+    pub z: E::G2Affine,
+    pub y: E::G2Affine,
+    pub y_hat: E::G1Affine,
+}
+
+/// Issuer keys a verifier accepts, each signed under its policy key
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct IssuerPolicy<E: Pairing> {
+    pub keys: Vec<VerificationKey<E>>,
+    pub signatures: Vec<PolicySignature<E>>,
+}
+
+pub fn generate_policy_keys<E: Pairing>(
+    pp: &PublicParams<E>,
+    rng: &mut impl Rng,
+) -> (PolicySecretKey<E>, PolicyPublicKey<E>) {
+    let x1 = E::ScalarField::rand(rng);
+    let x2 = E::ScalarField::rand(rng);
+    let pk = PolicyPublicKey {
+        x1: pp.g.mul(x1).into_affine(),
+        x2: pp.g.mul(x2).into_affine(),
+    };
+    (PolicySecretKey { x1, x2 }, pk)
+}
+
+impl<E: Pairing> PolicySignature<E> {
+    fn sign(
+        pp: &PublicParams<E>,
+        sk: &PolicySecretKey<E>,
+        g_tilde: &E::G2Affine,
+        vk_tilde: &E::G2Affine,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let y = loop {
+            let y = E::ScalarField::rand(rng);
+            if !y.is_zero() {
+                break y;
+            }
+        };
+        let y_inv = y.inverse().unwrap();
+        Self {
+            z: (g_tilde.mul(sk.x1) + vk_tilde.mul(sk.x2))
+                .mul(y)
+                .into_affine(),
+            y: pp.g_tilde.mul(y_inv).into_affine(),
+            y_hat: pp.g.mul(y_inv).into_affine(),
+        }
+    }
+
+    /// signature on (g_tilde^mu, vk_tilde^mu), re-randomized so it does not link to this one
+    fn change_representative(&self, mu: &E::ScalarField, rng: &mut impl Rng) -> Self {
+        let psi = loop {
+            let psi = E::ScalarField::rand(rng);
+            if !psi.is_zero() {
+                break psi;
+            }
+        };
+        let psi_inv = psi.inverse().unwrap();
+        Self {
+            z: self.z.mul(psi * mu).into_affine(),
+            y: self.y.mul(psi_inv).into_affine(),
+            y_hat: self.y_hat.mul(psi_inv).into_affine(),
+        }
+    }
+
+    /// e(X1, g_tilde) e(X2, vk_tilde) = e(Y_hat, Z) and e(g, Y) = e(Y_hat, g_tilde)
+    fn verify(
+        &self,
+        pp: &PublicParams<E>,
+        pk: &PolicyPublicKey<E>,
+        g_tilde: &E::G2Affine,
+        vk_tilde: &E::G2Affine,
+    ) -> bool {
+        if self.y_hat.is_zero() || g_tilde.is_zero() {
+            return false;
+        }
+        let neg_y_hat = self.y_hat.into_group().neg().into_affine();
+        E::multi_pairing([pk.x1, pk.x2, neg_y_hat], [*g_tilde, *vk_tilde, self.z]).is_zero()
+            && E::multi_pairing([pp.g, neg_y_hat], [self.y, pp.g_tilde]).is_zero()
+    }
+}
+
+impl<E: Pairing> IssuerPolicy<E> {
+    /// Verifier signs the issuer keys it accepts
+    pub fn new(
+        pp: &PublicParams<E>,
+        sk: &PolicySecretKey<E>,
+        keys: Vec<VerificationKey<E>>,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let signatures = keys
+            .iter()
+            .map(|vk| PolicySignature::sign(pp, sk, &pp.g_tilde, &vk.vk_tilde, rng))
+            .collect();
+        Self { keys, signatures }
+    }
+
+    /// Policy over the keys of `ring` valid at `timestamp`. A rotating verifier re-signs its
+    /// policy when a key enters or leaves the ring, presentations then hide which key was used.
+    pub fn from_key_ring(
+        pp: &PublicParams<E>,
+        sk: &PolicySecretKey<E>,
+        ring: &KeyRing<PreparedVerificationKey<E>>,
+        timestamp: u64,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let keys = ring
+            .valid_at(timestamp)
+            .map(|(_, pvk)| pvk.vk.clone())
+            .collect();
+        Self::new(pp, sk, keys, rng)
+    }
+
+    /// User checks the policy really comes from the verifier before showing against it
+    pub fn verify(&self, pp: &PublicParams<E>, pk: &PolicyPublicKey<E>) -> bool {
+        self.keys.len() == self.signatures.len()
+            && self
+                .keys
+                .iter()
+                .zip(&self.signatures)
+                .all(|(vk, sig)| sig.verify(pp, pk, &pp.g_tilde, &vk.vk_tilde))
+    }
+}
+
+/// Presentation of a credential from some issuer in the verifier's policy. g_tilde, vk_tilde and
+/// the G2 commitment are all scaled by the same hidden gamma.
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct IssuerHidingShow<E: Pairing> {
+    pub g_tilde: E::G2Affine,
+    pub vk_tilde: E::G2Affine,
+    pub policy_signature: PolicySignature<E>,
+    pub randomized_signature: Signature<E>,
+    pub randomized_commitment: Commitment<E>,
+    pub proof: CommitmentProof<E>,
+}
+
+impl<E: Pairing> IssuerHidingShow<E> {
+    /// Show `credential`, issued under `vk`, to the verifier that signed `policy`
+    pub fn prove(
+        pp: &PublicParams<E>,
+        credential: &Credential<E>,
+        vk: &VerificationKey<E>,
+        policy: &IssuerPolicy<E>,
+        context: &PresentationContext,
+        rng: &mut impl Rng,
+    ) -> Result<Self, Error> {
+        let position = policy
+            .keys
+            .iter()
+            .position(|key| key.vk_tilde == vk.vk_tilde)
+            .ok_or(Error::IssuerNotInPolicy)?;
+        let policy_signature = policy
+            .signatures
+            .get(position)
+            .ok_or(Error::IssuerNotInPolicy)?;
+
+        let delta_r = E::ScalarField::rand(rng);
+        let delta_u = E::ScalarField::rand(rng);
+        let show = credential.show_with_context(pp, &delta_r, &delta_u, context, rng)?;
+
+        let gamma = loop {
+            let gamma = E::ScalarField::rand(rng);
+            if !gamma.is_zero() {
+                break gamma;
+            }
+        };
+        // the opening proof only covers cm in G1, which stays as is
+        let randomized_commitment = Commitment {
+            cm: show.randomized_commitment.cm,
+            cm_tilde: show.randomized_commitment.cm_tilde.mul(gamma).into_affine(),
+        };
+        let mut proof = show.proof;
+        proof.commitment = randomized_commitment.clone();
+
+        Ok(Self {
+            g_tilde: pp.g_tilde.mul(gamma).into_affine(),
+            vk_tilde: vk.vk_tilde.mul(gamma).into_affine(),
+            policy_signature: policy_signature.change_representative(&gamma, rng),
+            randomized_signature: show.randomized_signature,
+            randomized_commitment,
+            proof,
+        })
+    }
+
+    /// Verifier checks the presentation against its policy key and context
+    pub fn verify(
+        &self,
+        pp: &PublicParams<E>,
+        policy_pk: &PolicyPublicKey<E>,
+        context: &PresentationContext,
+    ) -> bool {
+        // (g_tilde, vk_tilde) is a multiple of a signed pair
+        if !self
+            .policy_signature
+            .verify(pp, policy_pk, &self.g_tilde, &self.vk_tilde)
+        {
+            return false;
+        }
+
+        // opening proof of cm, bound to the context
+        if self.proof.commitment.cm != self.randomized_commitment.cm
+            || !self.proof.verify_with_context(context)
+        {
+            return false;
+        }
+
+        // e(cm, g_tilde) = e(g, cm_tilde) and e(sigma2, g_tilde) = e(sigma1, vk_tilde + cm_tilde)
+        let sigma = &self.randomized_signature;
+        if sigma.sigma1.is_zero() {
+            return false;
+        }
+        let cm = &self.randomized_commitment;
+        let neg_g = pp.g.into_group().neg().into_affine();
+        let neg_sigma1 = sigma.sigma1.into_group().neg().into_affine();
+        let vk_plus_cm = (self.vk_tilde + cm.cm_tilde).into_affine();
+        E::multi_pairing([cm.cm, neg_g], [self.g_tilde, cm.cm_tilde]).is_zero()
+            && E::multi_pairing([sigma.sigma2, neg_sigma1], [self.g_tilde, vk_plus_cm]).is_zero()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::multi_issuer::{MultiIssuerSystem, User};
+    use ark_ec::pairing::Pairing;
+    use utils::key_ring::ValidityWindow;
+
+    fn test_issuer_hiding_show<E: Pairing>() {
+        let mut rng = ark_std::test_rng();
+        let mut system = MultiIssuerSystem::<E>::new();
+        system.setup_issuers_with_shared_params(3, 4, &mut rng);
+        let pp = system.get_issuer(0).unwrap().protocol.pp.clone();
+
+        let mut user = User::<E>::new(&mut rng);
+        let attributes: Vec<E::ScalarField> =
+            (0..3).map(|_| E::ScalarField::rand(&mut rng)).collect();
+        user.obtain_credential(1, 0, &system, attributes, &mut rng)
+            .unwrap();
+        let credential = &user.credentials[&(1, 0)];
+        let vk = &system.get_issuer(1).unwrap().vk;
+
+        let (policy_sk, policy_pk) = generate_policy_keys(&pp, &mut rng);
+        let keys = (0..3)
+            .map(|i| system.get_issuer(i).unwrap().vk.clone())
+            .collect();
+        let policy = IssuerPolicy::new(&pp, &policy_sk, keys, &mut rng);
+        assert!(policy.verify(&pp, &policy_pk));
+
+        let context = PresentationContext::new(b"verifier", b"nonce", 0);
+        let show =
+            IssuerHidingShow::prove(&pp, credential, vk, &policy, &context, &mut rng).unwrap();
+        assert!(show.verify(&pp, &policy_pk, &context));
+        // the issuer key is randomized, it matches none of the policy keys
+        assert!(policy.keys.iter().all(|k| k.vk_tilde != show.vk_tilde));
+
+        let other_context = PresentationContext::new(b"verifier", b"nonce-2", 0);
+        assert!(!show.verify(&pp, &policy_pk, &other_context));
+
+        // another verifier's policy key does not accept it
+        let (_, other_pk) = generate_policy_keys(&pp, &mut rng);
+        assert!(!policy.verify(&pp, &other_pk));
+        assert!(!show.verify(&pp, &other_pk, &context));
+
+        // a randomized key of another issuer in the policy does not verify the signature
+        let mut swapped = show.clone();
+        let gamma = E::ScalarField::rand(&mut rng);
+        swapped.g_tilde = pp.g_tilde.mul(gamma).into_affine();
+        swapped.vk_tilde = policy.keys[0].vk_tilde.mul(gamma).into_affine();
+        swapped.policy_signature = policy.signatures[0].change_representative(&gamma, &mut rng);
+        assert!(!swapped.verify(&pp, &policy_pk, &context));
+
+        // a policy without the credential's issuer
+        let partial = IssuerPolicy::new(
+            &pp,
+            &policy_sk,
+            vec![system.get_issuer(0).unwrap().vk.clone()],
+            &mut rng,
+        );
+        assert!(matches!(
+            IssuerHidingShow::prove(&pp, credential, vk, &partial, &context, &mut rng),
+            Err(Error::IssuerNotInPolicy)
+        ));
+    }
+
+    fn test_issuer_hiding_key_ring<E: Pairing>() {
+        let mut rng = ark_std::test_rng();
+        let mut system = MultiIssuerSystem::<E>::new();
+        system.setup_issuers_with_shared_params(2, 4, &mut rng);
+        let pp = system.get_issuer(0).unwrap().protocol.pp.clone();
+
+        let mut user = User::<E>::new(&mut rng);
+        let attributes: Vec<E::ScalarField> =
+            (0..3).map(|_| E::ScalarField::rand(&mut rng)).collect();
+        user.obtain_credential(1, 0, &system, attributes, &mut rng)
+            .unwrap();
+        let credential = &user.credentials[&(1, 0)];
+        let vk = &system.get_issuer(1).unwrap().vk;
+
+        // issuer 1 is being rotated out and is accepted until 100
+        let mut ring = KeyRing::new();
+        for i in 0..2 {
+            let vk = &system.get_issuer(i).unwrap().vk;
+            ring.insert(vk.key_id(), vk.prepare(&pp), ValidityWindow::starting(0));
+        }
+        ring.retire(&vk.key_id(), 100).unwrap();
+
+        let (policy_sk, policy_pk) = generate_policy_keys(&pp, &mut rng);
+        let policy = IssuerPolicy::from_key_ring(&pp, &policy_sk, &ring, 50, &mut rng);
+        assert_eq!(policy.keys.len(), 2);
+        let context = PresentationContext::new(b"verifier", b"nonce", 50);
+        let show =
+            IssuerHidingShow::prove(&pp, credential, vk, &policy, &context, &mut rng).unwrap();
+        assert!(show.verify(&pp, &policy_pk, &context));
+
+        let policy = IssuerPolicy::from_key_ring(&pp, &policy_sk, &ring, 150, &mut rng);
+        assert_eq!(policy.keys.len(), 1);
+        let context = PresentationContext::new(b"verifier", b"nonce", 150);
+        assert!(matches!(
+            IssuerHidingShow::prove(&pp, credential, vk, &policy, &context, &mut rng),
+            Err(Error::IssuerNotInPolicy)
+        ));
+    }
+
+    utils::curve_tests!(test_issuer_hiding_show, test_issuer_hiding_key_ring);
+}
@@ -2,6 +2,7 @@ pub mod commitment;
 pub mod credential;
 pub mod error;
 pub mod identity_binding;
+pub mod issuer_hiding;
 pub mod linked_credentials;
 pub mod multi_credential;
 pub mod multi_identity_credentials;
@@ -11,3 +12,4 @@ pub mod protocol;
 pub mod public_params;
 pub mod signature;
 pub mod verkey;
+pub mod wire;
@@ -36,7 +36,6 @@ impl<E: Pairing> LinkedCredentialPresentation<E> {
             let delta_r = E::ScalarField::rand(rng);
             let delta_u = E::ScalarField::rand(rng);
 
-            
             // Show the credential (creating randomized presentation)
             let presentation = credential.show(public_params[i], &delta_r, &delta_u, rng);
 
@@ -90,20 +89,19 @@ mod tests {
     use super::*;
     use crate::protocol::MimcAbc;
     use crate::signature::{SecretKey, Signature};
-    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ec::pairing::Pairing;
     use ark_std::test_rng;
 
-    #[test]
-    fn test_linked_credential_presentation() {
+    fn test_linked_credential_presentation<E: Pairing>() {
         let mut rng = test_rng();
 
         // Setup protocols and keys for multiple issuers
         let n = 5; // attributes per credential
-        let (protocol1, sk1, vk1) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
-        let (protocol2, sk2, vk2) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+        let (protocol1, sk1, vk1) = MimcAbc::<E>::setup(n, &mut rng);
+        let (protocol2, sk2, vk2) = MimcAbc::<E>::setup(n, &mut rng);
 
         // Create a common user identity
-        let user_id = Fr::rand(&mut rng);
+        let user_id = E::ScalarField::rand(&mut rng);
 
         // Create credentials with the same identity
         let mut messages1 = vec![user_id];
@@ -111,13 +109,13 @@ mod tests {
 
         // Add random attributes
         for _ in 1..n {
-            messages1.push(Fr::rand(&mut rng));
-            messages2.push(Fr::rand(&mut rng));
+            messages1.push(E::ScalarField::rand(&mut rng));
+            messages2.push(E::ScalarField::rand(&mut rng));
         }
 
         // Create commitments and credentials
-        let r1 = Fr::rand(&mut rng);
-        let r2 = Fr::rand(&mut rng);
+        let r1 = E::ScalarField::rand(&mut rng);
+        let r2 = E::ScalarField::rand(&mut rng);
 
         let mut credential1 = Credential::new(&protocol1.ck, &protocol1.pp, &messages1, r1);
 
@@ -127,15 +125,15 @@ mod tests {
         let proof1 = credential1.prove_commitment(&protocol1.pp, &mut rng);
         let proof2 = credential2.prove_commitment(&protocol2.pp, &mut rng);
 
-        let signature1 = protocol1
+        let response1 = protocol1
             .issue(&proof1, &sk1, &mut rng)
             .expect("Issuance failed");
-        let signature2 = protocol2
+        let response2 = protocol2
             .issue(&proof2, &sk2, &mut rng)
             .expect("Issuance failed");
 
-        credential1.add_signature(signature1);
-        credential2.add_signature(signature2);
+        credential1.add_issuer_response(response1);
+        credential2.add_issuer_response(response2);
 
         // Create linked presentation
         let linked_presentation = LinkedCredentialPresentation::create(
@@ -153,20 +151,20 @@ mod tests {
         assert!(is_valid, "Linked credential presentation should verify");
 
         // Test negative case: different user identities (this should be caught in create())
-        let different_id = Fr::rand(&mut rng);
+        let different_id = E::ScalarField::rand(&mut rng);
         let mut messages3 = vec![different_id]; // Different ID
         for _ in 1..n {
-            messages3.push(Fr::rand(&mut rng));
+            messages3.push(E::ScalarField::rand(&mut rng));
         }
 
-        let r3 = Fr::rand(&mut rng);
+        let r3 = E::ScalarField::rand(&mut rng);
         let mut credential3 = Credential::new(&protocol1.ck, &protocol1.pp, &messages3, r3);
 
         let proof3 = credential3.prove_commitment(&protocol1.pp, &mut rng);
-        let signature3 = protocol1
+        let response3 = protocol1
             .issue(&proof3, &sk1, &mut rng)
             .expect("Issuance failed");
-        credential3.add_signature(signature3);
+        credential3.add_issuer_response(response3);
 
         // This should fail during creation
         let invalid_presentation = LinkedCredentialPresentation::create(
@@ -180,4 +178,6 @@ mod tests {
             "Creating linked presentation with different IDs should fail"
         );
     }
+
+    utils::curve_tests!(test_linked_credential_presentation);
 }
@@ -261,37 +261,36 @@ impl<E: Pairing> PlaintextAggregation<E> {
 mod tests {
     use super::*;
     use crate::protocol::MimcAbc;
-    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ec::pairing::Pairing;
     use ark_std::test_rng;
     use ark_std::UniformRand;
 
-    #[test]
-    fn test_multiple_credentials_batch_verification() {
+    fn test_multiple_credentials_batch_verification<E: Pairing>() {
         // Setup
         let mut rng = test_rng();
         let n = 10; // Attributes per credential
         let credential_count = 5; // Number of credentials
-        let (protocol, issuer_sk, issuer_vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+        let (protocol, issuer_sk, issuer_vk) = MimcAbc::<E>::setup(n, &mut rng);
 
         // Create multiple credentials for the same user
-        let user_id = Fr::rand(&mut rng);
+        let user_id = E::ScalarField::rand(&mut rng);
         let mut credentials = Vec::new();
 
         for _ in 0..credential_count {
             // Create random attributes (with user_id as first attribute)
             let mut attributes = vec![user_id];
             for _ in 1..n {
-                attributes.push(Fr::rand(&mut rng));
+                attributes.push(E::ScalarField::rand(&mut rng));
             }
 
             // Create credential
-            let r = Fr::rand(&mut rng);
+            let r = E::ScalarField::rand(&mut rng);
             let mut credential = Credential::new(&protocol.ck, &protocol.pp, &attributes, r);
 
             // Issue credential
             let proof = credential.prove_commitment(&protocol.pp, &mut rng);
-            let signature = protocol.issue(&proof, &issuer_sk, &mut rng).unwrap();
-            credential.add_signature(signature);
+            let response = protocol.issue(&proof, &issuer_sk, &mut rng).unwrap();
+            credential.add_issuer_response(response);
 
             // Verify individual credential
             assert!(
@@ -326,4 +325,6 @@ mod tests {
             credential_count, standard_time, batch_time
         );
     }
+
+    utils::curve_tests!(test_multiple_credentials_batch_verification);
 }
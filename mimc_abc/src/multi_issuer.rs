@@ -1,11 +1,14 @@
 use crate::credential::{Credential, ShowCredential};
 use crate::error::Error;
+use crate::issuer_hiding::{IssuerHidingShow, IssuerPolicy};
 use crate::protocol::MimcAbc;
-use crate::signature::{SecretKey, VerificationKey};
+use crate::public_params::PublicParams;
+use crate::signature::{generate_keys, SecretKey, VerificationKey};
 use ark_ec::pairing::Pairing;
 use ark_ff::UniformRand;
 use ark_std::rand::Rng;
 use std::collections::HashMap;
+use utils::context::PresentationContext;
 
 /// Structure to represent an issuer in the system
 pub struct Issuer<E: Pairing> {
@@ -26,6 +29,18 @@ impl<E: Pairing> Issuer<E> {
             vk,
         }
     }
+
+    /// Create an issuer with its own keys over parameters shared with other issuers
+    pub fn with_params(id: usize, pp: PublicParams<E>, rng: &mut impl Rng) -> Self {
+        let protocol = MimcAbc::new(pp);
+        let (sk, vk) = generate_keys(&protocol.pp, rng);
+        Self {
+            id,
+            protocol,
+            sk,
+            vk,
+        }
+    }
 }
 
 /// Multi-issuer system manager
@@ -65,6 +80,21 @@ impl<E: Pairing> MultiIssuerSystem<E> {
         }
     }
 
+    /// Generate issuers that share one set of public parameters, as issuer-hiding presentations
+    /// require
+    pub fn setup_issuers_with_shared_params(
+        &mut self,
+        issuer_count: usize,
+        num_attributes: usize,
+        rng: &mut impl Rng,
+    ) {
+        let pp = PublicParams::<E>::new(&num_attributes, rng);
+        for i in 0..issuer_count {
+            let issuer = Issuer::with_params(i, pp.clone(), rng);
+            self.add_issuer(issuer);
+        }
+    }
+
     /// Get an issuer by ID
     pub fn get_issuer(&self, issuer_id: usize) -> Option<&Issuer<E>> {
         self.issuers.get(&issuer_id)
@@ -122,10 +152,10 @@ impl<E: Pairing> User<E> {
         let proof = credential.prove_commitment(&issuer.protocol.pp, rng);
 
         // Get signature from issuer
-        let signature = issuer.protocol.issue(&proof, &issuer.sk, rng)?;
+        let response = issuer.protocol.issue(&proof, &issuer.sk, rng)?;
 
         // Add signature to credential
-        credential.add_signature(signature);
+        credential.add_issuer_response(response);
 
         // Store the credential
         self.credentials
@@ -139,6 +169,7 @@ impl<E: Pairing> User<E> {
         &self,
         credential_keys: &[(usize, usize)], // List of (issuer_id, credential_id) to show
         issuer_system: &MultiIssuerSystem<E>,
+        context: &PresentationContext,
         rng: &mut impl Rng,
     ) -> Result<Vec<ShowCredential<E>>, Error> {
         let mut presentations = Vec::new();
@@ -158,7 +189,48 @@ impl<E: Pairing> User<E> {
                 .get_issuer(*issuer_id)
                 .ok_or_else(|| Error::Other(format!("Issuer {} not found", issuer_id)))?;
 
-            let presentation = issuer.protocol.show(credential, rng);
+            let presentation = issuer.protocol.show(credential, context, rng)?;
+            presentations.push(presentation);
+        }
+
+        Ok(presentations)
+    }
+
+    /// Show credentials without revealing their issuers, each only proves its issuer is one of
+    /// the keys in the verifier's policy
+    pub fn show_credentials_issuer_hiding(
+        &self,
+        credential_keys: &[(usize, usize)], // List of (issuer_id, credential_id) to show
+        issuer_system: &MultiIssuerSystem<E>,
+        policy: &IssuerPolicy<E>,
+        context: &PresentationContext,
+        rng: &mut impl Rng,
+    ) -> Result<Vec<IssuerHidingShow<E>>, Error> {
+        let mut presentations = Vec::new();
+
+        for (issuer_id, credential_id) in credential_keys {
+            let credential = self
+                .credentials
+                .get(&(*issuer_id, *credential_id))
+                .ok_or_else(|| {
+                    Error::Other(format!(
+                        "Credential ({}, {}) not found",
+                        issuer_id, credential_id
+                    ))
+                })?;
+
+            let issuer = issuer_system
+                .get_issuer(*issuer_id)
+                .ok_or_else(|| Error::Other(format!("Issuer {} not found", issuer_id)))?;
+
+            let presentation = IssuerHidingShow::prove(
+                &issuer.protocol.pp,
+                credential,
+                &issuer.vk,
+                policy,
+                context,
+                rng,
+            )?;
             presentations.push(presentation);
         }
 
@@ -169,28 +241,29 @@ impl<E: Pairing> User<E> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ec::pairing::Pairing;
     use ark_ff::UniformRand;
 
-    #[test]
-    fn test_multi_issuer_system() {
+    fn test_multi_issuer_system<E: Pairing>() {
         // Initialize random number generator
         let mut rng = ark_std::test_rng();
 
         // Create multi-issuer system
-        let mut system = MultiIssuerSystem::<Bls12_381>::new();
+        let mut system = MultiIssuerSystem::<E>::new();
 
         // Setup issuers with different attribute counts
         let attributes_per_issuer = [5, 10, 32];
         system.setup_issuers(3, &attributes_per_issuer, &mut rng);
 
         // Create a user
-        let mut user = User::<Bls12_381>::new(&mut rng);
+        let mut user = User::<E>::new(&mut rng);
 
         // Obtain credentials from different issuers
         for issuer_id in 0..3 {
             let attr_count = attributes_per_issuer[issuer_id];
-            let attributes: Vec<Fr> = (0..(attr_count - 1)).map(|_| Fr::rand(&mut rng)).collect();
+            let attributes: Vec<E::ScalarField> = (0..(attr_count - 1))
+                .map(|_| E::ScalarField::rand(&mut rng))
+                .collect();
 
             user.obtain_credential(issuer_id, 0, &system, attributes, &mut rng)
                 .expect("Credential issuance should succeed");
@@ -198,8 +271,9 @@ mod tests {
 
         // Show credentials from different issuers
         let credential_keys = vec![(0, 0), (1, 0), (2, 0)];
+        let context = PresentationContext::new(b"verifier", b"nonce", 0);
         let presentations = user
-            .show_credentials(&credential_keys, &system, &mut rng)
+            .show_credentials(&credential_keys, &system, &context, &mut rng)
             .expect("Credential presentation should succeed");
 
         // Verify each presentation
@@ -208,9 +282,51 @@ mod tests {
             let issuer = system.get_issuer(issuer_id).unwrap();
 
             assert!(
-                issuer.protocol.verify(presentation.clone(), &issuer.vk),
+                issuer
+                    .protocol
+                    .verify(presentation.clone(), &issuer.vk, &context),
                 "Credential verification should succeed"
             );
         }
     }
+
+    fn test_multi_issuer_issuer_hiding<E: Pairing>() {
+        use crate::issuer_hiding::generate_policy_keys;
+
+        let mut rng = ark_std::test_rng();
+        let mut system = MultiIssuerSystem::<E>::new();
+        system.setup_issuers_with_shared_params(3, 5, &mut rng);
+        let pp = &system.get_issuer(0).unwrap().protocol.pp;
+
+        let mut user = User::<E>::new(&mut rng);
+        for issuer_id in 0..3 {
+            let attributes: Vec<E::ScalarField> =
+                (0..4).map(|_| E::ScalarField::rand(&mut rng)).collect();
+            user.obtain_credential(issuer_id, 0, &system, attributes, &mut rng)
+                .expect("Credential issuance should succeed");
+        }
+
+        // the verifier accepts issuers 0 and 2
+        let (policy_sk, policy_pk) = generate_policy_keys(pp, &mut rng);
+        let keys = vec![
+            system.get_issuer(0).unwrap().vk.clone(),
+            system.get_issuer(2).unwrap().vk.clone(),
+        ];
+        let policy = IssuerPolicy::new(pp, &policy_sk, keys, &mut rng);
+        let context = PresentationContext::new(b"verifier", b"nonce", 0);
+
+        let presentations = user
+            .show_credentials_issuer_hiding(&[(0, 0), (2, 0)], &system, &policy, &context, &mut rng)
+            .expect("Issuer-hiding presentation should succeed");
+        for presentation in &presentations {
+            assert!(presentation.verify(pp, &policy_pk, &context));
+        }
+
+        assert!(matches!(
+            user.show_credentials_issuer_hiding(&[(1, 0)], &system, &policy, &context, &mut rng),
+            Err(Error::IssuerNotInPolicy)
+        ));
+    }
+
+    utils::curve_tests!(test_multi_issuer_system, test_multi_issuer_issuer_hiding);
 }
@@ -7,15 +7,31 @@ use ark_ec::AffineRepr;
 use ark_ff::UniformRand;
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::rand::Rng;
-use schnorr::schnorr::SchnorrProtocol;
+use schnorr::schnorr::{SchnorrCommitment, SchnorrProtocol};
+use schnorr::verifiable_encryption::{
+    AuditorPublicKey, Ciphertext, EncryptionProof, VerifiableEncryption,
+};
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
 use thiserror::Error;
+use utils::context::PresentationContext;
+use utils::disclosure::Disclosure;
+use utils::encoding::{Base64Point, HexScalar};
+use utils::hash::HashUtil;
+use zeroize::Zeroizing;
 
-#[derive(Debug, Clone)]
+#[serde_as]
+#[derive(Debug, Clone, CanonicalSerialize, CanonicalDeserialize, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct CommitmentProof<E: Pairing> {
     pub commitment: Commitment<E>,
+    #[serde_as(as = "Base64Point")]
     pub schnorr_commitment: E::G1Affine,
+    #[serde_as(as = "Vec<Base64Point>")]
     pub bases: Vec<E::G1Affine>,
+    #[serde_as(as = "HexScalar")]
     pub challenge: E::ScalarField,
+    #[serde_as(as = "Vec<HexScalar>")]
     pub responses: Vec<E::ScalarField>,
 }
 
@@ -68,3 +84,238 @@ impl<E: Pairing> CommitmentProof<E> {
         is_valid
     }
 }
+
+impl<E: Pairing> CommitmentProof<E> {
+    /// Opening proof whose challenge is derived from the verifier's presentation context
+    pub fn prove_with_context(
+        pp: &PublicParams<E>,
+        commitment: &Commitment<E>,
+        messages: &[E::ScalarField],
+        r: &E::ScalarField,
+        context: &PresentationContext,
+        rng: &mut impl Rng,
+    ) -> Result<Self, Error> {
+        let (proof, _) = Self::prepare(pp, commitment, messages, r, rng).finish(context, &[])?;
+        Ok(proof)
+    }
+
+    /// The Schnorr commitment of `prove_with_context`, which does not depend on the verifier.
+    /// `PreparedCommitmentProof::finish` adds the challenge and responses
+    pub fn prepare(
+        pp: &PublicParams<E>,
+        commitment: &Commitment<E>,
+        messages: &[E::ScalarField],
+        r: &E::ScalarField,
+        rng: &mut impl Rng,
+    ) -> PreparedCommitmentProof<E> {
+        let bases = pp.get_g1_bases();
+        let mut exponents = messages.to_vec();
+        exponents.push(*r);
+        let schnorr_commitment = SchnorrProtocol::commit(&bases, rng);
+
+        PreparedCommitmentProof {
+            commitment: commitment.clone(),
+            bases,
+            schnorr_commitment,
+            exponents: Zeroizing::new(exponents),
+        }
+    }
+
+    /// Verify a proof made by `prove_with_context` for this verifier's context
+    pub fn verify_with_context(&self, context: &PresentationContext) -> bool {
+        self.verify_with_disclosure(&Disclosure::default(), context)
+    }
+
+    /// Verify a proof for this verifier's context that reveals `disclosure`. The last response
+    /// is for r, which is never disclosed
+    pub fn verify_with_disclosure(
+        &self,
+        disclosure: &Disclosure<E::ScalarField>,
+        context: &PresentationContext,
+    ) -> bool {
+        match Self::context_challenge(
+            &self.commitment.cm,
+            &self.schnorr_commitment,
+            disclosure,
+            context,
+        ) {
+            Ok(challenge) if challenge == self.challenge => {
+                self.responses
+                    .split_last()
+                    .is_some_and(|(_, messages)| disclosure.verify(messages, &self.challenge))
+                    && self.verify()
+            }
+            _ => false,
+        }
+    }
+
+    fn context_challenge(
+        cm: &E::G1Affine,
+        schnorr_commitment: &E::G1Affine,
+        disclosure: &Disclosure<E::ScalarField>,
+        context: &PresentationContext,
+    ) -> Result<E::ScalarField, Error> {
+        let mut transcript = Vec::new();
+        cm.serialize_compressed(&mut transcript)?;
+        schnorr_commitment.serialize_compressed(&mut transcript)?;
+        disclosure.serialize_compressed(&mut transcript)?;
+        Ok(context.challenge(b"mimc-abc-show", &transcript))
+    }
+}
+
+/// Schnorr commitment for an opening of `commitment` over `bases`, zeroized when dropped
+pub struct PreparedCommitmentProof<E: Pairing> {
+    commitment: Commitment<E>,
+    bases: Vec<E::G1Affine>,
+    schnorr_commitment: SchnorrCommitment<E::G1Affine>,
+    exponents: Zeroizing<Vec<E::ScalarField>>,
+}
+
+impl<E: Pairing> PreparedCommitmentProof<E> {
+    /// Answers for `context`, opening the messages at `disclosed` to the verifier
+    pub fn finish(
+        self,
+        context: &PresentationContext,
+        disclosed: &[usize],
+    ) -> Result<(CommitmentProof<E>, Disclosure<E::ScalarField>), Error> {
+        let n = self.exponents.len() - 1;
+        let disclosure = Disclosure::new(
+            disclosed,
+            &self.exponents[..n],
+            &self.schnorr_commitment.random_blindings[..n],
+        )?;
+        let challenge = CommitmentProof::<E>::context_challenge(
+            &self.commitment.cm,
+            &self.schnorr_commitment.commited_blindings,
+            &disclosure,
+            context,
+        )?;
+        let responses =
+            SchnorrProtocol::prove(&self.schnorr_commitment, &self.exponents, &challenge);
+
+        let proof = CommitmentProof {
+            commitment: self.commitment,
+            schnorr_commitment: self.schnorr_commitment.commited_blindings,
+            bases: self.bases,
+            challenge,
+            responses: responses.0,
+        };
+        Ok((proof, disclosure))
+    }
+}
+
+/// Commitment opening proof with message `index` verifiably encrypted to an auditor
+#[derive(Debug, Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct EscrowedCommitmentProof<E: Pairing> {
+    pub proof: CommitmentProof<E>,
+    pub ciphertext: Ciphertext<E::G1Affine>,
+    pub encryption_proof: EncryptionProof<E::G1Affine>,
+}
+
+impl<E: Pairing> EscrowedCommitmentProof<E> {
+    pub fn prove(
+        pp: &PublicParams<E>,
+        commitment: &Commitment<E>,
+        messages: &[E::ScalarField],
+        r: &E::ScalarField,
+        auditor_pk: &AuditorPublicKey<E::G1Affine>,
+        index: usize,
+        label: &[u8],
+        rng: &mut impl Rng,
+    ) -> Result<Self, Error> {
+        if index >= messages.len() {
+            return Err(Error::InvalidAttributeIndex(index));
+        }
+        let bases = pp.get_g1_bases();
+        let mut exponents = messages.to_vec();
+        exponents.push(*r);
+
+        // the attribute blinding is shared between the opening proof and the encryption proof
+        let schnorr_commitment = SchnorrProtocol::commit(&bases, rng);
+        let (ciphertext, enc_r) =
+            VerifiableEncryption::encrypt(auditor_pk, &messages[index], label, rng);
+        let enc_commitment = VerifiableEncryption::commit(
+            auditor_pk,
+            &schnorr_commitment.random_blindings[index],
+            rng,
+        );
+
+        let challenge = Self::challenge(
+            &commitment.cm,
+            &schnorr_commitment.commited_blindings,
+            &ciphertext,
+            label,
+            &[enc_commitment.a1, enc_commitment.a2, enc_commitment.a3],
+        )?;
+
+        let responses = SchnorrProtocol::prove(&schnorr_commitment, &exponents, &challenge);
+        let encryption_proof =
+            VerifiableEncryption::prove(&enc_commitment, &enc_r, &messages[index], &challenge);
+
+        Ok(Self {
+            proof: CommitmentProof {
+                commitment: commitment.clone(),
+                schnorr_commitment: schnorr_commitment.commited_blindings,
+                bases,
+                challenge,
+                responses: responses.0,
+            },
+            ciphertext,
+            encryption_proof,
+        })
+    }
+
+    pub fn verify(
+        &self,
+        auditor_pk: &AuditorPublicKey<E::G1Affine>,
+        index: usize,
+        label: &[u8],
+    ) -> bool {
+        // the last response belongs to r, which is never escrowed
+        if index + 1 >= self.proof.responses.len() {
+            return false;
+        }
+        let challenge = match Self::challenge(
+            &self.proof.commitment.cm,
+            &self.proof.schnorr_commitment,
+            &self.ciphertext,
+            label,
+            &[
+                self.encryption_proof.a1,
+                self.encryption_proof.a2,
+                self.encryption_proof.a3,
+            ],
+        ) {
+            Ok(challenge) => challenge,
+            Err(_) => return false,
+        };
+
+        challenge == self.proof.challenge
+            && self.encryption_proof.z_m == self.proof.responses[index]
+            && VerifiableEncryption::verify(
+                auditor_pk,
+                &self.ciphertext,
+                label,
+                &self.encryption_proof,
+                &challenge,
+            )
+            && self.proof.verify()
+    }
+
+    fn challenge(
+        cm: &E::G1Affine,
+        schnorr_commitment: &E::G1Affine,
+        ciphertext: &Ciphertext<E::G1Affine>,
+        label: &[u8],
+        encryption_commitments: &[E::G1Affine; 3],
+    ) -> Result<E::ScalarField, Error> {
+        let mut transcript = Vec::new();
+        cm.serialize_compressed(&mut transcript)?;
+        schnorr_commitment.serialize_compressed(&mut transcript)?;
+        let [a1, a2, a3] = encryption_commitments;
+        VerifiableEncryption::append_to_transcript(&mut transcript, ciphertext, label, a1, a2, a3);
+        Ok(HashUtil::<E::ScalarField, E::G1Affine>::hash_to_field(
+            &transcript,
+        ))
+    }
+}
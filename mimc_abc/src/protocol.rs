@@ -1,10 +1,12 @@
 use crate::commitment::{Commitment, CommitmentKey};
-use crate::credential::ShowCredential;
 use crate::credential::{self, Credential};
+use crate::credential::{EscrowedShowCredential, ShowCredential, ShowToken};
 use crate::error::Error;
 use crate::proof::CommitmentProof;
 use crate::public_params::PublicParams;
-use crate::signature::{generate_keys, SecretKey, Signature, VerificationKey};
+use crate::signature::{
+    generate_keys, IssuerResponse, PreparedVerificationKey, SecretKey, VerificationKey,
+};
 use crate::verkey::{VerKey, VerKeyProof};
 use ark_ec::pairing::Pairing;
 use ark_ec::{CurveGroup, VariableBaseMSM};
@@ -12,6 +14,9 @@ use ark_ff::UniformRand;
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::ops::{Add, Mul, Neg};
 use ark_std::rand::Rng;
+use schnorr::verifiable_encryption::AuditorPublicKey;
+use utils::context::PresentationContext;
+use utils::key_ring::KeyRing;
 // We can speedup multi credential verification by batching the signature pairings into a pairing checker.
 // Then implement the schnorr efficiency improvement from the threshold variant I made
 
@@ -43,28 +48,111 @@ impl<E: Pairing> MimcAbc<E> {
         credential.prove_commitment(&self.pp, rng)
     }
 
-    // Issuer issues a signature
+    // Issuer issues a signature, tagged with the id of the key it is under
     pub fn issue(
         &self,
         proof: &CommitmentProof<E>,
         sk: &SecretKey<E>,
         rng: &mut impl Rng,
-    ) -> Result<Signature<E>, Error> {
+    ) -> Result<IssuerResponse<E>, Error> {
         if !proof.verify() {
             return Err(Error::InvalidProof);
         }
-        Ok(sk.sign(&proof.commitment, &self.pp, rng))
+        Ok(IssuerResponse {
+            key_id: sk.verification_key(&self.pp).key_id(),
+            signature: sk.sign(&proof.commitment, &self.pp, rng),
+        })
     }
 
-    pub fn show(&self, credential: &Credential<E>, rng: &mut impl Rng) -> ShowCredential<E> {
+    // User shows a credential to the verifier identified by context
+    pub fn show(
+        &self,
+        credential: &Credential<E>,
+        context: &PresentationContext,
+        rng: &mut impl Rng,
+    ) -> Result<ShowCredential<E>, Error> {
         let delta_r = E::ScalarField::rand(rng);
         let delta_u = E::ScalarField::rand(rng);
-        credential.show(&self.pp, &delta_r, &delta_u, rng)
+        credential.show_with_context(&self.pp, &delta_r, &delta_u, context, rng)
     }
 
-    // Verifier checks a credential
-    pub fn verify(&self, show_cred: ShowCredential<E>, vk: &VerificationKey<E>) -> bool {
-        show_cred.verify(&self.pp, vk)
+    // Offline half of `show`, done before the verifier's request arrives
+    pub fn precompute_show(
+        &self,
+        credential: &Credential<E>,
+        rng: &mut impl Rng,
+    ) -> Result<ShowToken<E>, Error> {
+        let delta_r = E::ScalarField::rand(rng);
+        let delta_u = E::ScalarField::rand(rng);
+        credential.precompute_show(&self.pp, &delta_r, &delta_u, rng)
+    }
+
+    // Online half of `show`, binds a precomputed token to the verifier's context and reveals the
+    // messages at `disclosed`
+    pub fn finish_show(
+        &self,
+        token: ShowToken<E>,
+        context: &PresentationContext,
+        disclosed: &[usize],
+    ) -> Result<ShowCredential<E>, Error> {
+        token.finish(context, disclosed)
+    }
+
+    // Verifier checks a credential against its own context
+    pub fn verify(
+        &self,
+        show_cred: ShowCredential<E>,
+        vk: &VerificationKey<E>,
+        context: &PresentationContext,
+    ) -> bool {
+        show_cred.verify_with_context(&self.pp, vk, context)
+    }
+
+    // Verifier checks a credential with a key prepared once via VerificationKey::prepare
+    pub fn verify_prepared(
+        &self,
+        show_cred: &ShowCredential<E>,
+        pvk: &PreparedVerificationKey<E>,
+        context: &PresentationContext,
+    ) -> bool {
+        show_cred.verify_with_context_prepared(&self.pp, pvk, context)
+    }
+
+    // Verifier holding several issuer keys, e.g. during a rotation, checks a presentation under
+    // the key it names. The key must be valid at the context's timestamp
+    pub fn verify_with_key_ring(
+        &self,
+        show_cred: &ShowCredential<E>,
+        ring: &KeyRing<PreparedVerificationKey<E>>,
+        context: &PresentationContext,
+    ) -> Result<bool, Error> {
+        let pvk = ring.get(&show_cred.key_id, context.timestamp)?;
+        Ok(self.verify_prepared(show_cred, pvk, context))
+    }
+
+    pub fn show_with_escrow(
+        &self,
+        credential: &Credential<E>,
+        auditor_pk: &AuditorPublicKey<E::G1Affine>,
+        index: usize,
+        label: &[u8],
+        rng: &mut impl Rng,
+    ) -> Result<EscrowedShowCredential<E>, Error> {
+        let delta_r = E::ScalarField::rand(rng);
+        let delta_u = E::ScalarField::rand(rng);
+        credential.show_with_escrow(&self.pp, &delta_r, &delta_u, auditor_pk, index, label, rng)
+    }
+
+    // Verifier checks an escrowed presentation for its session label
+    pub fn verify_with_escrow(
+        &self,
+        show_cred: &EscrowedShowCredential<E>,
+        vk: &VerificationKey<E>,
+        auditor_pk: &AuditorPublicKey<E::G1Affine>,
+        index: usize,
+        label: &[u8],
+    ) -> bool {
+        show_cred.verify(&self.pp, vk, auditor_pk, index, label)
     }
 
     pub fn verify_key_correctness(&self, proof: &VerKeyProof<E>, vk: &VerificationKey<E>) -> bool {
@@ -81,25 +169,27 @@ impl<E: Pairing> MimcAbc<E> {
 mod tests {
     use super::*;
     use crate::credential::Credential;
-    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ec::pairing::Pairing;
     use ark_ff::UniformRand;
+    use schnorr::verifiable_encryption::VerifiableEncryption;
+    use utils::key_ring::{KeyRingError, ValidityWindow};
 
-    #[test]
-    fn test_mimc_abc_credential_lifecycle() {
+    fn test_mimc_abc_credential_lifecycle<E: Pairing>() {
         // Setup protocol with parameters and keys
         let mut rng = ark_std::test_rng();
         let n = 4; // Number of attributes
-        let (protocol, issuer_sk, issuer_vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+        let (protocol, issuer_sk, issuer_vk) = MimcAbc::<E>::setup(n, &mut rng);
 
         // Create user attributes with ID as first attribute
-        let user_id = Fr::rand(&mut rng);
-        let attributes: Vec<Fr> = (0..n - 1).map(|_| Fr::rand(&mut rng)).collect();
+        let user_id = E::ScalarField::rand(&mut rng);
+        let attributes: Vec<E::ScalarField> =
+            (0..n - 1).map(|_| E::ScalarField::rand(&mut rng)).collect();
         let mut attributes_with_id = vec![user_id];
         attributes_with_id.extend(attributes);
         println!("Attributes: {:?}", attributes_with_id.len());
 
         // Create credential
-        let r = Fr::rand(&mut rng);
+        let r = E::ScalarField::rand(&mut rng);
         let mut credential = Credential::new(&protocol.ck, &protocol.pp, &attributes_with_id, r);
 
         // User creates proof for credential
@@ -113,12 +203,12 @@ mod tests {
         assert!(is_key_valid, "Valid issuer key verification should succeed");
 
         // Issuer issues signature
-        let signature = protocol
+        let response = protocol
             .issue(&proof, &issuer_sk, &mut rng)
             .expect("Issuance failed");
 
         // Add signature to credential
-        credential.add_signature(signature);
+        credential.add_issuer_response(response);
 
         // Verify the original credential
         assert!(
@@ -126,24 +216,104 @@ mod tests {
             "Original credential verification failed"
         );
 
-        // User shows credential
-        let presentation = protocol.show(&credential, &mut rng);
+        // User shows credential to a verifier
+        let context = PresentationContext::new(b"verifier", b"nonce-1", 1_700_000_000);
+        let presentation = protocol
+            .show(&credential, &context, &mut rng)
+            .expect("Show failed");
 
         // Verifier checks presentation
         assert!(
-            protocol.verify(presentation, &issuer_vk),
+            protocol.verify(presentation.clone(), &issuer_vk, &context),
             "Credential presentation verification failed"
         );
+
+        // Prepared key gives the same answer and rejects a tampered signature
+        let pvk = issuer_vk.prepare(&protocol.pp);
+        assert!(protocol.verify_prepared(&presentation, &pvk, &context));
+        let mut tampered = presentation.clone();
+        tampered.randomized_signature.sigma2 = tampered.randomized_signature.sigma1;
+        assert!(!protocol.verify_prepared(&tampered, &pvk, &context));
+
+        // Replayed presentation under a new nonce is rejected
+        let replay_context = PresentationContext::new(b"verifier", b"nonce-2", 1_700_000_000);
+        assert!(!protocol.verify(presentation, &issuer_vk, &replay_context));
     }
 
-    #[test]
-    fn test_issuer_key_verification() {
+    fn test_mimc_abc_precompute_show<E: Pairing>() {
+        let mut rng = ark_std::test_rng();
+        let n = 3;
+        let (protocol, issuer_sk, issuer_vk) = MimcAbc::<E>::setup(n, &mut rng);
+        let attributes: Vec<E::ScalarField> =
+            (0..n).map(|_| E::ScalarField::rand(&mut rng)).collect();
+        let r = E::ScalarField::rand(&mut rng);
+        let mut credential = Credential::new(&protocol.ck, &protocol.pp, &attributes, r);
+
+        // an unsigned credential cannot be prepared for showing
+        assert!(protocol.precompute_show(&credential, &mut rng).is_err());
+
+        let proof = protocol.obtain(&credential, &mut rng);
+        let response = protocol.issue(&proof, &issuer_sk, &mut rng).unwrap();
+        credential.add_issuer_response(response);
+
+        // tokens are made before any verifier context exists, the disclosure is chosen on finish
+        let tokens: Vec<ShowToken<E>> = (0..3)
+            .map(|_| protocol.precompute_show(&credential, &mut rng).unwrap())
+            .collect();
+
+        let contexts = [
+            PresentationContext::new(b"verifier", b"nonce-1", 0),
+            PresentationContext::new(b"verifier", b"nonce-2", 0),
+        ];
+        let disclosed: [&[usize]; 2] = [&[0], &[2, 1]];
+        let mut tokens = tokens.into_iter();
+        let presentations: Vec<ShowCredential<E>> = contexts
+            .iter()
+            .zip(disclosed)
+            .map(|(context, disclosed)| {
+                protocol
+                    .finish_show(tokens.next().unwrap(), context, disclosed)
+                    .unwrap()
+            })
+            .collect();
+
+        assert!(protocol.verify(presentations[0].clone(), &issuer_vk, &contexts[0]));
+        assert!(protocol.verify(presentations[1].clone(), &issuer_vk, &contexts[1]));
+        assert!(!protocol.verify(presentations[0].clone(), &issuer_vk, &contexts[1]));
+        assert_ne!(
+            presentations[0].randomized_commitment.cm,
+            presentations[1].randomized_commitment.cm
+        );
+        assert_eq!(
+            presentations[0].disclosed.attributes,
+            vec![(0, attributes[0])]
+        );
+        assert_eq!(
+            presentations[1].disclosed.attributes,
+            vec![(2, attributes[2]), (1, attributes[1])]
+        );
+
+        // a disclosed value other than the committed one, or a stripped disclosure, is rejected
+        let mut forged = presentations[1].clone();
+        forged.disclosed.attributes[1].1 += E::ScalarField::from(1u64);
+        assert!(!protocol.verify(forged, &issuer_vk, &contexts[1]));
+        let mut stripped = presentations[1].clone();
+        stripped.disclosed = Default::default();
+        assert!(!protocol.verify(stripped, &issuer_vk, &contexts[1]));
+
+        assert!(matches!(
+            protocol.finish_show(tokens.next().unwrap(), &contexts[0], &[n]),
+            Err(Error::DisclosureError(_))
+        ));
+    }
+
+    fn test_issuer_key_verification<E: Pairing>() {
         // Initialize random number generator
         let mut rng = ark_std::test_rng();
 
         // Setup protocol with parameters and keys
         let n = 4; // Number of message attributes
-        let (protocol, issuer_sk, issuer_vk) = MimcAbc::<Bls12_381>::setup(n, &mut rng);
+        let (protocol, issuer_sk, issuer_vk) = MimcAbc::<E>::setup(n, &mut rng);
 
         // Generate proof of key correctness
         let key_proof = protocol.prove_key_correctness(&issuer_sk, &mut rng);
@@ -153,7 +323,7 @@ mod tests {
         assert!(is_key_valid, "Valid issuer key verification should succeed");
 
         // Test with wrong secret key
-        // let wrong_x = Fr::rand(&mut rng);
+        // let wrong_x = E::ScalarField::rand(&mut rng);
         // let wrong_sk = protocol.pp.g.mul(wrong_x).into_affine();
 
         // let is_invalid_key_valid = protocol.verify_key_correctness(&key_proof, &issuer_vk);
@@ -162,4 +332,113 @@ mod tests {
         //     "Invalid issuer key verification should fail"
         // );
     }
+
+    fn test_mimc_abc_escrowed_show<E: Pairing>() {
+        let mut rng = ark_std::test_rng();
+        let n = 4;
+        let (protocol, issuer_sk, issuer_vk) = MimcAbc::<E>::setup(n, &mut rng);
+        let (auditor_sk, auditor_pk) = VerifiableEncryption::keygen(&mut rng);
+
+        let attributes: Vec<E::ScalarField> =
+            (0..n).map(|_| E::ScalarField::rand(&mut rng)).collect();
+        let r = E::ScalarField::rand(&mut rng);
+        let mut credential = Credential::new(&protocol.ck, &protocol.pp, &attributes, r);
+        let proof = protocol.obtain(&credential, &mut rng);
+        let response = protocol
+            .issue(&proof, &issuer_sk, &mut rng)
+            .expect("Issuance failed");
+        credential.add_issuer_response(response);
+
+        // escrow the user id in attribute 0
+        let label = b"verifier session";
+        let presentation = protocol
+            .show_with_escrow(&credential, &auditor_pk, 0, label, &mut rng)
+            .expect("Escrowed show failed");
+        assert!(protocol.verify_with_escrow(&presentation, &issuer_vk, &auditor_pk, 0, label));
+        assert!(!protocol.verify_with_escrow(
+            &presentation,
+            &issuer_vk,
+            &auditor_pk,
+            0,
+            b"other session"
+        ));
+        assert!(!protocol.verify_with_escrow(&presentation, &issuer_vk, &auditor_pk, 1, label));
+
+        let plaintext = VerifiableEncryption::decrypt(
+            &auditor_sk,
+            &auditor_pk,
+            &presentation.proof.ciphertext,
+            label,
+        )
+        .unwrap();
+        assert_eq!(
+            VerifiableEncryption::resolve(&auditor_pk, &plaintext, credential.get_messages()),
+            Some(0)
+        );
+    }
+
+    fn test_mimc_abc_key_rotation<E: Pairing>() {
+        let mut rng = ark_std::test_rng();
+        let n = 3;
+        let (protocol, old_sk, old_vk) = MimcAbc::<E>::setup(n, &mut rng);
+        let (new_sk, new_vk) = generate_keys(&protocol.pp, &mut rng);
+
+        let mut issue = |sk: &SecretKey<E>, vk: &VerificationKey<E>| {
+            let attributes: Vec<E::ScalarField> =
+                (0..n).map(|_| E::ScalarField::rand(&mut rng)).collect();
+            let r = E::ScalarField::rand(&mut rng);
+            let mut credential = Credential::new(&protocol.ck, &protocol.pp, &attributes, r);
+            let proof = protocol.obtain(&credential, &mut rng);
+            let response = protocol.issue(&proof, sk, &mut rng).unwrap();
+            assert_eq!(response.key_id, vk.key_id());
+            credential.add_issuer_response(response);
+            credential
+        };
+        let old_credential = issue(&old_sk, &old_vk);
+        let new_credential = issue(&new_sk, &new_vk);
+        assert_eq!(old_credential.key_id(), old_vk.key_id());
+        assert_eq!(new_credential.key_id(), new_vk.key_id());
+
+        let mut ring = KeyRing::new();
+        ring.insert(
+            old_vk.key_id(),
+            old_vk.prepare(&protocol.pp),
+            ValidityWindow::starting(0),
+        );
+        ring.insert(
+            new_vk.key_id(),
+            new_vk.prepare(&protocol.pp),
+            ValidityWindow::starting(200),
+        );
+        ring.retire(&old_vk.key_id(), 300).unwrap();
+
+        let during = PresentationContext::new(b"verifier", b"nonce", 250);
+        for credential in [&old_credential, &new_credential] {
+            let show = protocol.show(credential, &during, &mut rng).unwrap();
+            assert_eq!(show.key_id, credential.key_id());
+            assert!(protocol
+                .verify_with_key_ring(&show, &ring, &during)
+                .unwrap());
+        }
+
+        let after = PresentationContext::new(b"verifier", b"nonce", 301);
+        let show = protocol.show(&old_credential, &after, &mut rng).unwrap();
+        assert!(matches!(
+            protocol.verify_with_key_ring(&show, &ring, &after),
+            Err(Error::KeyRingError(KeyRingError::OutsideValidity { .. }))
+        ));
+        let mut relabelled = protocol.show(&old_credential, &during, &mut rng).unwrap();
+        relabelled.key_id = new_vk.key_id();
+        assert!(!protocol
+            .verify_with_key_ring(&relabelled, &ring, &during)
+            .unwrap());
+    }
+
+    utils::curve_tests!(
+        test_mimc_abc_credential_lifecycle,
+        test_mimc_abc_precompute_show,
+        test_issuer_key_verification,
+        test_mimc_abc_escrowed_show,
+        test_mimc_abc_key_rotation
+    );
 }
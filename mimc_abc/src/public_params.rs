@@ -1,17 +1,31 @@
 use ark_ec::pairing::Pairing;
 use ark_ec::CurveGroup;
 use ark_ff::UniformRand;
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, SerializationError, Valid, Validate,
+};
+use ark_std::io::{Read, Write};
 use ark_std::ops::Mul;
 use ark_std::rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
 use std::iter;
+use utils::encoding::Base64Point;
 
-#[derive(Clone, Debug)]
+#[serde_as]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct PublicParams<E: Pairing> {
     pub n: usize,
+    #[serde_as(as = "Base64Point")]
     pub g: E::G1Affine,
+    #[serde_as(as = "Base64Point")]
     pub g_tilde: E::G2Affine,
+    #[serde_as(as = "Vec<Base64Point>")]
     pub ck: Vec<E::G1Affine>,
+    #[serde_as(as = "Vec<Base64Point>")]
     pub ck_tilde: Vec<E::G2Affine>,
+    #[serde(skip)]
     y_values: Vec<E::ScalarField>, // Store the y values
 }
 
@@ -66,18 +80,73 @@ impl<E: Pairing> PublicParams<E> {
     }
 }
 
+// y_values are the trapdoor of ck and stay with the party that ran setup,
+// decoded parameters cannot prove key correctness
+impl<E: Pairing> CanonicalSerialize for PublicParams<E> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.n.serialize_with_mode(&mut writer, compress)?;
+        self.g.serialize_with_mode(&mut writer, compress)?;
+        self.g_tilde.serialize_with_mode(&mut writer, compress)?;
+        self.ck.serialize_with_mode(&mut writer, compress)?;
+        self.ck_tilde.serialize_with_mode(&mut writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        self.n.serialized_size(compress)
+            + self.g.serialized_size(compress)
+            + self.g_tilde.serialized_size(compress)
+            + self.ck.serialized_size(compress)
+            + self.ck_tilde.serialized_size(compress)
+    }
+}
+
+impl<E: Pairing> Valid for PublicParams<E> {
+    fn check(&self) -> Result<(), SerializationError> {
+        if self.ck.len() != self.n || self.ck_tilde.len() != self.n {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(())
+    }
+}
+
+impl<E: Pairing> CanonicalDeserialize for PublicParams<E> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let pp = Self {
+            n: usize::deserialize_with_mode(&mut reader, compress, validate)?,
+            g: E::G1Affine::deserialize_with_mode(&mut reader, compress, validate)?,
+            g_tilde: E::G2Affine::deserialize_with_mode(&mut reader, compress, validate)?,
+            ck: Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+            ck_tilde: Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+            y_values: Vec::new(),
+        };
+        if let Validate::Yes = validate {
+            pp.check()?;
+        }
+        Ok(pp)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
-    use ark_bls12_381::Bls12_381;
-    #[test]
-    fn test_pp_gen() {
+    use ark_ec::pairing::Pairing;
+    fn test_pp_gen<E: Pairing>() {
         let n = 4;
         let mut rng = ark_std::test_rng();
-        let pp = PublicParams::<Bls12_381>::new(&n, &mut rng);
+        let pp = PublicParams::<E>::new(&n, &mut rng);
 
         assert_eq!(pp.ck.len(), n, "ck length should match n");
         assert_eq!(pp.ck_tilde.len(), n, "ck_tilde length should match n");
         assert_eq!(pp.get_g1_bases().len(), n + 1, "g1 bases should include g");
     }
+
+    utils::curve_tests!(test_pp_gen);
 }
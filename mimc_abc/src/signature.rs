@@ -5,17 +5,33 @@ use ark_ec::AffineRepr;
 use ark_ec::CurveGroup;
 use ark_ec::VariableBaseMSM;
 use ark_ff::UniformRand;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::fmt;
 use ark_std::ops::{Add, Mul, Neg};
 use ark_std::rand::Rng;
-use ark_std::One;
+use ark_std::Zero;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use utils::encoding::Base64Point;
+use utils::hash::HashUtil;
+use utils::key_ring::KeyId;
 use utils::pairing::{create_check, PairingCheck};
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 // Secret and verification keys
+/// Issuer secret key, wiped on drop and left out of `Debug` output
+#[derive(CanonicalSerialize, CanonicalDeserialize, Zeroize, ZeroizeOnDrop)]
 pub struct SecretKey<E: Pairing> {
     pub sk: E::G1Affine,
     x: E::ScalarField,
 }
 
+impl<E: Pairing> fmt::Debug for SecretKey<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecretKey").finish_non_exhaustive()
+    }
+}
+
 impl<E: Pairing> SecretKey<E> {
     pub fn sign(
         &self,
@@ -32,15 +48,30 @@ impl<E: Pairing> SecretKey<E> {
         self.x
     }
 
+    pub fn verification_key(&self, pp: &PublicParams<E>) -> VerificationKey<E> {
+        VerificationKey {
+            vk_tilde: pp.g_tilde.mul(self.x).into_affine(),
+        }
+    }
+
     pub fn new(sk: E::G1Affine, x: E::ScalarField) -> Self {
         Self { sk, x }
     }
 }
+#[serde_as]
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct VerificationKey<E: Pairing> {
+    #[serde_as(as = "Base64Point")]
     pub vk_tilde: E::G2Affine,
 }
 
 impl<E: Pairing> VerificationKey<E> {
+    /// Name of this key in presentations and verifier key rings
+    pub fn key_id(&self) -> KeyId {
+        KeyId::of(self)
+    }
+
     pub fn verify(
         &self,
         signature: &Signature<E>,
@@ -95,23 +126,94 @@ impl<E: Pairing> VerificationKey<E> {
     }
 }
 
+/// Verification key with vk_tilde and g_tilde prepared once, for verifiers that check many
+/// presentations under the same key
+#[derive(Clone)]
+pub struct PreparedVerificationKey<E: Pairing> {
+    pub vk: VerificationKey<E>,
+    pub vk_tilde: E::G2Prepared,
+    pub g_tilde: E::G2Prepared,
+}
+
+impl<E: Pairing> VerificationKey<E> {
+    pub fn prepare(&self, pp: &PublicParams<E>) -> PreparedVerificationKey<E> {
+        PreparedVerificationKey {
+            vk: self.clone(),
+            vk_tilde: self.vk_tilde.into(),
+            g_tilde: pp.g_tilde.into(),
+        }
+    }
+}
+
+impl<E: Pairing> PreparedVerificationKey<E> {
+    /// Checks e(σ2, g̃) = e(σ1, ṽk · cm̃) and e(cm, g̃) = e(g, cm̃) as one pairing product
+    /// e(σ2 · cm^ρ, g̃) · e(σ1^-1, ṽk) · e(σ1^-1 · g^-ρ, cm̃) = 1, with ρ hashed from the
+    /// presented elements so that only cm̃ is prepared per call
+    pub fn verify(
+        &self,
+        signature: &Signature<E>,
+        commitment: &Commitment<E>,
+        pp: &PublicParams<E>,
+    ) -> bool {
+        let mut transcript = Vec::new();
+        for point in [signature.sigma1, signature.sigma2, commitment.cm] {
+            point.serialize_compressed(&mut transcript).unwrap();
+        }
+        commitment
+            .cm_tilde
+            .serialize_compressed(&mut transcript)
+            .unwrap();
+        let rho = HashUtil::<E::ScalarField, E::G1Affine>::hash_to_field(&transcript);
+
+        let sigma1_inv = signature.sigma1.into_group().neg();
+        E::multi_pairing(
+            [
+                E::G1Prepared::from(signature.sigma2 + commitment.cm * rho),
+                E::G1Prepared::from(sigma1_inv),
+                E::G1Prepared::from(sigma1_inv - pp.g * rho),
+            ],
+            [
+                self.g_tilde.clone(),
+                self.vk_tilde.clone(),
+                E::G2Prepared::from(commitment.cm_tilde),
+            ],
+        )
+        .is_zero()
+    }
+}
+
 // Key generation as a standalone function
 pub fn generate_keys<E: Pairing>(
     pp: &PublicParams<E>,
     rng: &mut impl Rng,
 ) -> (SecretKey<E>, VerificationKey<E>) {
     let x = E::ScalarField::rand(rng);
-    let sk = pp.g.mul(x).into_affine();
-    let vk_tilde = pp.g_tilde.mul(x).into_affine();
-    (SecretKey { sk, x }, VerificationKey { vk_tilde })
+    let sk = SecretKey {
+        sk: pp.g.mul(x).into_affine(),
+        x,
+    };
+    let vk = sk.verification_key(pp);
+    (sk, vk)
 }
-#[derive(Clone)]
+#[serde_as]
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Signature<E: Pairing> {
     // Signature fields based on your scheme
+    #[serde_as(as = "Base64Point")]
     pub sigma1: E::G1Affine,
+    #[serde_as(as = "Base64Point")]
     pub sigma2: E::G1Affine,
 }
 
+/// Issuer's answer to an issuance request, names the key the signature is under
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct IssuerResponse<E: Pairing> {
+    pub key_id: KeyId,
+    pub signature: Signature<E>,
+}
+
 impl<E: Pairing> Signature<E> {
     pub fn randomize(&self, delta_r: &E::ScalarField, delta_u: &E::ScalarField) -> Self {
         let sigma1_prime = self.sigma1.mul(delta_u).into_affine();
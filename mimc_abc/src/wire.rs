@@ -0,0 +1,107 @@
+use crate::credential::{Credential, EscrowedShowCredential, ShowCredential};
+use crate::proof::CommitmentProof;
+use crate::public_params::PublicParams;
+use crate::signature::{IssuerResponse, SecretKey, Signature, VerificationKey};
+use utils::impl_envelope;
+
+impl_envelope!(PublicParams, MimcAbc, PublicParams, |pp| pp.n);
+impl_envelope!(VerificationKey, MimcAbc, PublicKey);
+impl_envelope!(SecretKey, MimcAbc, SecretKey);
+impl_envelope!(CommitmentProof, MimcAbc, IssuanceRequest);
+impl_envelope!(IssuerResponse, MimcAbc, IssuanceResponse);
+impl_envelope!(Signature, MimcAbc, Signature);
+impl_envelope!(Credential, MimcAbc, Credential, |cred| cred
+    .get_messages()
+    .len());
+impl_envelope!(ShowCredential, MimcAbc, Presentation);
+impl_envelope!(EscrowedShowCredential, MimcAbc, EscrowedPresentation);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::MimcAbc;
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ff::UniformRand;
+    use ark_std::test_rng;
+    use schnorr::verifiable_encryption::VerifiableEncryption;
+    use utils::context::PresentationContext;
+    use utils::envelope::{assert_golden, Envelope, EnvelopeError};
+
+    fn golden(name: &str) -> String {
+        format!("{}/golden/{}.hex", env!("CARGO_MANIFEST_DIR"), name)
+    }
+
+    #[test]
+    fn test_mimc_abc_wire_golden() {
+        let mut rng = test_rng();
+        let (protocol, issuer_sk, issuer_vk) = MimcAbc::<Bls12_381>::setup(3, &mut rng);
+        let (_, auditor_pk) = VerifiableEncryption::keygen(&mut rng);
+        let attributes: Vec<Fr> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+        let mut credential =
+            Credential::new(&protocol.ck, &protocol.pp, &attributes, Fr::rand(&mut rng));
+        let request = protocol.obtain(&credential, &mut rng);
+        let response = protocol.issue(&request, &issuer_sk, &mut rng).unwrap();
+        credential.add_issuer_response(response.clone());
+        let context = PresentationContext::new(b"verifier", b"nonce-1", 1_700_000_000);
+        let presentation = protocol.show(&credential, &context, &mut rng).unwrap();
+        let escrowed = protocol
+            .show_with_escrow(&credential, &auditor_pk, 0, b"session", &mut rng)
+            .unwrap();
+
+        assert_golden(&golden("public_params"), &protocol.pp.to_envelope());
+        assert_golden(&golden("verification_key"), &issuer_vk.to_envelope());
+        assert_golden(&golden("issuance_request"), &request.to_envelope());
+        assert_golden(&golden("issuance_response"), &response.to_envelope());
+        assert_golden(&golden("signature"), &response.signature.to_envelope());
+        assert_golden(&golden("credential"), &credential.to_envelope());
+        assert_golden(&golden("presentation"), &presentation.to_envelope());
+        assert_golden(&golden("escrowed_presentation"), &escrowed.to_envelope());
+
+        let pp = PublicParams::<Bls12_381>::from_envelope(&protocol.pp.to_envelope()).unwrap();
+        assert!(pp.get_y_values().is_empty());
+        let verifier = MimcAbc::new(pp);
+        let decoded =
+            ShowCredential::<Bls12_381>::from_envelope(&presentation.to_envelope()).unwrap();
+        assert!(verifier.verify(decoded, &issuer_vk, &context));
+        let decoded =
+            EscrowedShowCredential::<Bls12_381>::from_envelope(&escrowed.to_envelope()).unwrap();
+        assert!(verifier.verify_with_escrow(&decoded, &issuer_vk, &auditor_pk, 0, b"session"));
+        let restored = Credential::<Bls12_381>::from_envelope(&credential.to_envelope()).unwrap();
+        assert!(restored.verify(&verifier.pp, &issuer_vk));
+        assert!(matches!(
+            ShowCredential::<Bls12_381>::from_envelope(&escrowed.to_envelope()),
+            Err(EnvelopeError::KindMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_mimc_abc_json() {
+        let mut rng = test_rng();
+        let (protocol, issuer_sk, issuer_vk) = MimcAbc::<Bls12_381>::setup(3, &mut rng);
+        let attributes: Vec<Fr> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+        let mut credential =
+            Credential::new(&protocol.ck, &protocol.pp, &attributes, Fr::rand(&mut rng));
+        let request = protocol.obtain(&credential, &mut rng);
+        let response = protocol.issue(&request, &issuer_sk, &mut rng).unwrap();
+        credential.add_issuer_response(response.clone());
+        let context = PresentationContext::new(b"verifier", b"nonce-1", 1_700_000_000);
+        let presentation = protocol.show(&credential, &context, &mut rng).unwrap();
+
+        // y_values never leave the setup party
+        let json = serde_json::to_value(&protocol.pp).unwrap();
+        assert!(json.get("y_values").is_none());
+        let pp: PublicParams<Bls12_381> = serde_json::from_value(json).unwrap();
+        let vk: VerificationKey<Bls12_381> =
+            serde_json::from_str(&serde_json::to_string(&issuer_vk).unwrap()).unwrap();
+        let decoded: Signature<Bls12_381> =
+            serde_json::from_str(&serde_json::to_string(&response.signature).unwrap()).unwrap();
+        assert_eq!(decoded.sigma1, response.signature.sigma1);
+        let json = serde_json::to_value(&presentation).unwrap();
+        let decoded: ShowCredential<Bls12_381> = serde_json::from_value(json.clone()).unwrap();
+        assert!(MimcAbc::new(pp).verify(decoded, &vk, &context));
+
+        let mut tampered = json;
+        tampered["r_new"] = serde_json::Value::String("0".into());
+        assert!(serde_json::from_value::<ShowCredential<Bls12_381>>(tampered).is_err());
+    }
+}
@@ -1,29 +1,98 @@
 use crate::commitment::Commitment;
-use crate::keygen::{gen_keys, PublicKey, SecretKey};
-use crate::proofsystem::{CommitmentProof, CommitmentProofs, ProofError, SignatureProofs};
+use crate::keygen::{gen_keys, PreparedVerificationKey, PublicKey, PublicKeyTables, SecretKey};
+use crate::proofsystem::{
+    check_attribute_indices, BatchPresentation, CommitmentProof, CommitmentProofs,
+    PreparedSignatureProof, ProofError, SignatureProofs,
+};
 use crate::publicparams::PublicParams;
 use crate::signature::PSSignature;
 use ark_ec::pairing::Pairing;
+use ark_ec::{CurveGroup, VariableBaseMSM};
 use ark_ff::UniformRand;
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::fmt;
+use ark_std::marker::PhantomData;
 use ark_std::rand::Rng;
+use schnorr::key_proof::KeyProof;
+use schnorr::verifiable_encryption::AuditorPublicKey;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use utils::context::PresentationContext;
+use utils::disclosure::Disclosure;
+use utils::encoding::Base64Bytes;
+use utils::key_ring::{KeyId, KeyRing};
+use utils::schema::Schema;
+use utils::update::{apply_updates, AttributeUpdate};
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
-/// User credential containing attributes and blinding factor
+/// User credential containing attributes and blinding factor, wiped on drop
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize, Zeroize, ZeroizeOnDrop)]
 pub struct UserCred<E: Pairing> {
     pub t: E::ScalarField,
     pub messages: Vec<E::ScalarField>,
 }
 
+impl<E: Pairing> fmt::Debug for UserCred<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UserCred").finish_non_exhaustive()
+    }
+}
+
+/// Blind signature from the issuer, with the id of the key it was made under
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct IssuerResponse<E: Pairing> {
+    pub key_id: KeyId,
+    pub blind_signature: PSSignature<E>,
+}
+
 /// Presentation of a credential with proof
-pub struct ShowCredential {
+#[serde_as]
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct ShowCredential<E: Pairing> {
+    /// Issuer key the credential was issued under, verifiers use it to pick the key
+    pub key_id: KeyId,
+    #[serde_as(as = "Base64Bytes")]
     pub proof: Vec<u8>,
+    /// Messages revealed by `finish_show`, empty for `show`
+    pub disclosed: Disclosure<E::ScalarField>,
+    #[serde(skip)]
+    _pairing: PhantomData<E>,
+}
+
+impl<E: Pairing> ShowCredential<E> {
+    pub fn new(key_id: KeyId, proof: Vec<u8>) -> Self {
+        Self::with_disclosure(key_id, proof, Disclosure::default())
+    }
+
+    pub fn with_disclosure(
+        key_id: KeyId,
+        proof: Vec<u8>,
+        disclosed: Disclosure<E::ScalarField>,
+    ) -> Self {
+        Self {
+            key_id,
+            proof,
+            disclosed,
+            _pairing: PhantomData,
+        }
+    }
+}
+
+/// Randomized signature σ' with its GT commitment, waiting for a verifier's context
+pub struct ShowToken<E: Pairing> {
+    proof: PreparedSignatureProof<E>,
 }
 
 /// Anonymous credential protocol for PS signatures
 pub struct PSAnonCredProtocol<E: Pairing> {
     pub pp: PublicParams<E>,
     pub pk: PublicKey<E>,
-    sk: SecretKey<E>, // Private to prevent unauthorized issuance
+    sk: Option<SecretKey<E>>, // Private to prevent unauthorized issuance, None for holders and verifiers
+    pvk: PreparedVerificationKey<E>, // pk prepared once for the verify calls
+    tables: PublicKeyTables<E>, // generator tables for obtain, issue and show
+    key_id: KeyId,            // hash of pk, named in every presentation
 }
 
 impl<E: Pairing> UserCred<E> {
@@ -45,6 +114,21 @@ impl<E: Pairing> UserCred<E> {
 
         Self::new(&messages, t)
     }
+
+    /// Credential over the merged attribute vector, issuer-set attributes replace the user's entries.
+    /// The indices come from the issuer's response, so they are checked like in `issue_partial`
+    pub fn with_issuer_attributes(
+        &self,
+        issuer_attributes: &[(usize, E::ScalarField)],
+    ) -> Result<Self, ProofError> {
+        let issuer_indices: Vec<usize> = issuer_attributes.iter().map(|(i, _)| *i).collect();
+        check_attribute_indices(self.messages.len(), &issuer_indices)?;
+        let mut messages = self.messages.clone();
+        for (i, a) in issuer_attributes {
+            messages[*i] = *a;
+        }
+        Ok(Self::new(&messages, self.t))
+    }
 }
 
 impl<E: Pairing> PSAnonCredProtocol<E> {
@@ -53,13 +137,94 @@ impl<E: Pairing> PSAnonCredProtocol<E> {
         let context = E::ScalarField::rand(rng);
         let pp = PublicParams::<E>::new(&n, &context, rng);
         let (sk, pk) = gen_keys(&pp, rng);
-        Self { pp, pk, sk }
+        Self {
+            pp,
+            pvk: pk.prepare(),
+            tables: pk.precompute(),
+            key_id: pk.key_id(),
+            pk,
+            sk: Some(sk),
+        }
+    }
+
+    /// Create an issuer instance from previously generated keys
+    pub fn from_keys(pk: PublicKey<E>, sk: SecretKey<E>) -> Self {
+        Self {
+            pp: pk.pp.clone(),
+            pvk: pk.prepare(),
+            tables: pk.precompute(),
+            key_id: pk.key_id(),
+            pk,
+            sk: Some(sk),
+        }
+    }
+
+    /// Create a holder or verifier instance, `issue` and friends fail without the secret key
+    pub fn from_public_key(pk: PublicKey<E>) -> Self {
+        Self {
+            pp: pk.pp.clone(),
+            pvk: pk.prepare(),
+            tables: pk.precompute(),
+            key_id: pk.key_id(),
+            pk,
+            sk: None,
+        }
+    }
+
+    /// Create a holder instance after checking the issuer's key proof, so a malformed key is
+    /// refused before any credential is requested under it
+    pub fn from_verified_public_key(
+        pk: PublicKey<E>,
+        proof: &KeyProof<E>,
+        rng: &mut impl Rng,
+    ) -> Result<Self, ProofError> {
+        pk.verify_key(proof, rng)?;
+        Ok(Self::from_public_key(pk))
+    }
+
+    /// Issuer proves its key is well formed, for holders to check with `from_verified_public_key`
+    pub fn prove_key(&self, rng: &mut impl Rng) -> Result<KeyProof<E>, ProofError> {
+        Ok(self.pk.prove_key(self.secret_key()?, rng))
+    }
+
+    /// Id of the issuer key, see `verify_with_key_ring`
+    pub fn key_id(&self) -> KeyId {
+        self.key_id
+    }
+
+    fn secret_key(&self) -> Result<&SecretKey<E>, ProofError> {
+        self.sk.as_ref().ok_or(ProofError::MissingSecretKey)
+    }
+
+    /// Create a protocol instance for a credential schema, the schema is bound into `pp.context`
+    pub fn with_schema(schema: &Schema, rng: &mut impl Rng) -> Self {
+        let context = schema.context::<E::ScalarField>();
+        let pp = PublicParams::<E>::new(&schema.len(), &context, rng);
+        let (sk, pk) = gen_keys(&pp, rng);
+        Self {
+            pp,
+            pvk: pk.prepare(),
+            tables: pk.precompute(),
+            key_id: pk.key_id(),
+            pk,
+            sk: Some(sk),
+        }
     }
 
     /// User generates proof of knowledge for obtaining a credential
-    pub fn obtain(&self, user_cred: &UserCred<E>) -> Result<Vec<u8>, ProofError> {
-        let commitment = Commitment::new(&self.pp, &self.pk, &user_cred.messages, &user_cred.t);
-        CommitmentProofs::pok_commitment_prove(&commitment)
+    pub fn obtain<R: Rng>(
+        &self,
+        user_cred: &UserCred<E>,
+        rng: &mut R,
+    ) -> Result<Vec<u8>, ProofError> {
+        let commitment = Commitment::new_with_tables(
+            &self.pp,
+            &self.pk,
+            &self.tables,
+            &user_cred.messages,
+            &user_cred.t,
+        );
+        CommitmentProofs::pok_commitment_prove_with_tables(&commitment, &self.tables, rng)
     }
 
     /// Issuer verifies proof and issues credential
@@ -67,9 +232,9 @@ impl<E: Pairing> PSAnonCredProtocol<E> {
         &self,
         serialized_proof: &[u8],
         rng: &mut R,
-    ) -> Result<PSSignature<E>, ProofError> {
+    ) -> Result<IssuerResponse<E>, ProofError> {
         // Verify proof of knowledge
-        if !CommitmentProofs::pok_commitment_verify::<E>(serialized_proof)? {
+        if !CommitmentProofs::pok_commitment_verify::<E>(&self.pk, serialized_proof)? {
             return Err(ProofError::InvalidProof);
         }
 
@@ -78,10 +243,165 @@ impl<E: Pairing> PSAnonCredProtocol<E> {
             CanonicalDeserialize::deserialize_compressed(serialized_proof)?;
 
         // Issue blind signature - using proof.statement as in the original code
-        let blind_signature =
-            PSSignature::blind_sign(&self.pp, &self.pk, &self.sk, &proof.statement, rng);
+        let blind_signature = PSSignature::blind_sign_with_tables(
+            &self.tables,
+            self.secret_key()?,
+            &proof.statement,
+            rng,
+        );
+
+        Ok(self.response(blind_signature))
+    }
 
-        Ok(blind_signature)
+    /// Issuer verifies many proofs in one batch and issues a credential for each. If any proof
+    /// is invalid nothing is issued and the error names the first bad request.
+    pub fn issue_batch<R: Rng>(
+        &self,
+        serialized_proofs: &[Vec<u8>],
+        rng: &mut R,
+    ) -> Result<Vec<IssuerResponse<E>>, ProofError> {
+        let proofs =
+            CommitmentProofs::pok_commitment_batch_verify(&self.pk, serialized_proofs, rng)?;
+        let sk = self.secret_key()?;
+
+        Ok(proofs
+            .iter()
+            .map(|proof| {
+                self.response(PSSignature::blind_sign_with_tables(
+                    &self.tables,
+                    sk,
+                    &proof.statement,
+                    rng,
+                ))
+            })
+            .collect())
+    }
+
+    /// User proves knowledge of a commitment to all attributes except those at `issuer_indices`
+    pub fn obtain_partial(
+        &self,
+        user_cred: &UserCred<E>,
+        issuer_indices: &[usize],
+        rng: &mut impl Rng,
+    ) -> Result<Vec<u8>, ProofError> {
+        CommitmentProofs::pok_partial_commitment_prove(
+            &self.pp,
+            &self.pk,
+            &user_cred.messages,
+            &user_cred.t,
+            issuer_indices,
+            rng,
+        )
+    }
+
+    /// Issuer verifies the partial commitment proof, adds its own attributes and blind signs
+    /// the merged commitment
+    pub fn issue_partial<R: Rng>(
+        &self,
+        serialized_proof: &[u8],
+        issuer_attributes: &[(usize, E::ScalarField)],
+        rng: &mut R,
+    ) -> Result<IssuerResponse<E>, ProofError> {
+        let issuer_indices: Vec<usize> = issuer_attributes.iter().map(|(i, _)| *i).collect();
+        let commitment = CommitmentProofs::pok_partial_commitment_verify(
+            &self.pp,
+            &self.pk,
+            &issuer_indices,
+            serialized_proof,
+        )?
+        .ok_or(ProofError::InvalidProof)?;
+
+        // C' = C · ∏ Y_j^a_j over the issuer-set attributes
+        let bases: Vec<E::G1Affine> = issuer_indices.iter().map(|i| self.pk.y_g1[*i]).collect();
+        let values: Vec<E::ScalarField> = issuer_attributes.iter().map(|(_, a)| *a).collect();
+        let merged = (E::G1::msm_unchecked(&bases, &values) + commitment).into_affine();
+
+        Ok(self.response(PSSignature::blind_sign_with_tables(
+            &self.tables,
+            self.secret_key()?,
+            &merged,
+            rng,
+        )))
+    }
+
+    /// User unblinds a partially blind signature, returns it with the credential over the
+    /// merged attribute vector
+    pub fn complete_partial_signature(
+        blind_signature: &PSSignature<E>,
+        user_cred: &UserCred<E>,
+        issuer_attributes: &[(usize, E::ScalarField)],
+    ) -> Result<(PSSignature<E>, UserCred<E>), ProofError> {
+        Ok((
+            blind_signature.unblind(&user_cred.t),
+            user_cred.with_issuer_attributes(issuer_attributes)?,
+        ))
+    }
+
+    /// User requests a refreshed credential from this issuer. The old credential, signed under
+    /// `old_pp`/`old_pk`, is shown together with a commitment to the attributes after `updates`.
+    /// Returns the request and the user credential the new signature will cover
+    pub fn obtain_reissue<R: Rng>(
+        &self,
+        old_pp: &PublicParams<E>,
+        old_pk: &PublicKey<E>,
+        signature: &PSSignature<E>,
+        user_cred: &UserCred<E>,
+        updates: &[(usize, AttributeUpdate<E::ScalarField>)],
+        context: &PresentationContext,
+        rng: &mut R,
+    ) -> Result<(Vec<u8>, UserCred<E>), ProofError> {
+        let new_t = E::ScalarField::rand(rng);
+        let proof = SignatureProofs::pok_reissuance(
+            old_pp,
+            old_pk,
+            &self.pp,
+            &self.pk,
+            &user_cred.messages,
+            signature,
+            updates,
+            &new_t,
+            context,
+            rng,
+        )?;
+        let new_cred = UserCred::new(&apply_updates(&user_cred.messages, updates), new_t);
+        Ok((proof, new_cred))
+    }
+
+    /// Issuer checks the old credential and the declared updates, then blind signs the new
+    /// commitment. The user unblinds with `complete_signature` and the new credential's t
+    pub fn reissue<R: Rng>(
+        &self,
+        old_pp: &PublicParams<E>,
+        old_pk: &PublicKey<E>,
+        serialized_proof: &[u8],
+        updates: &[(usize, AttributeUpdate<E::ScalarField>)],
+        context: &PresentationContext,
+        rng: &mut R,
+    ) -> Result<IssuerResponse<E>, ProofError> {
+        let commitment = SignatureProofs::verify_reissuance(
+            old_pp,
+            old_pk,
+            &self.pp,
+            &self.pk,
+            updates,
+            context,
+            serialized_proof,
+        )?
+        .ok_or(ProofError::InvalidProof)?;
+
+        Ok(self.response(PSSignature::blind_sign_with_tables(
+            &self.tables,
+            self.secret_key()?,
+            &commitment,
+            rng,
+        )))
+    }
+
+    fn response(&self, blind_signature: PSSignature<E>) -> IssuerResponse<E> {
+        IssuerResponse {
+            key_id: self.key_id,
+            blind_signature,
+        }
     }
 
     /// User completes the blind signature with their blinding factor
@@ -92,61 +412,791 @@ impl<E: Pairing> PSAnonCredProtocol<E> {
         blind_signature.unblind(t)
     }
 
-    /// User shows credential by creating a randomized signature and proof
+    /// User shows credential by creating a randomized signature and proof bound to the verifier's context
     pub fn show<R: Rng>(
         &self,
         signature: &PSSignature<E>,
         user_cred: &UserCred<E>,
-        _rng: &mut R, // Unused parameter but kept for API consistency
-    ) -> Result<ShowCredential, ProofError> {
-        let proof =
-            SignatureProofs::pok_signature(&self.pp, &self.pk, &user_cred.messages, &signature);
+        context: &PresentationContext,
+        rng: &mut R,
+    ) -> Result<ShowCredential<E>, ProofError> {
+        let proof = SignatureProofs::pok_signature_with_tables(
+            &self.tables,
+            &user_cred.messages,
+            signature,
+            context,
+            rng,
+        );
+
+        Ok(ShowCredential::new(self.key_id, proof))
+    }
+
+    /// Offline half of `show`: randomizes the signature and computes the GT commitment
+    pub fn precompute_show<R: Rng>(
+        &self,
+        signature: &PSSignature<E>,
+        user_cred: &UserCred<E>,
+        rng: &mut R,
+    ) -> ShowToken<E> {
+        ShowToken {
+            proof: SignatureProofs::prepare_signature_proof(
+                &self.tables,
+                &user_cred.messages,
+                signature,
+                rng,
+            ),
+        }
+    }
 
-        Ok(ShowCredential { proof })
+    /// Online half of `show`: binds a precomputed token to the verifier's context and reveals
+    /// the messages at `disclosed`
+    pub fn finish_show(
+        &self,
+        token: ShowToken<E>,
+        context: &PresentationContext,
+        disclosed: &[usize],
+    ) -> Result<ShowCredential<E>, ProofError> {
+        let (proof, disclosure) = token.proof.finish(context, disclosed)?;
+        Ok(ShowCredential::with_disclosure(
+            self.key_id,
+            proof,
+            disclosure,
+        ))
     }
 
-    /// Verifier checks credential presentation
-    pub fn verify(&self, show_credential: &ShowCredential) -> Result<bool, ProofError> {
-        Ok(SignatureProofs::verify_knowledge(
+    /// Verifier checks credential presentation against its own context
+    pub fn verify(
+        &self,
+        show_credential: &ShowCredential<E>,
+        context: &PresentationContext,
+    ) -> Result<bool, ProofError> {
+        Ok(SignatureProofs::verify_knowledge_prepared(
             &self.pp,
-            &self.pk,
+            &self.pvk,
             &show_credential.proof,
+            &show_credential.disclosed,
+            context,
         ))
     }
+
+    /// Verifier holding several issuer keys, e.g. during a rotation, checks a presentation under
+    /// the key it names. The key must be valid at the context's timestamp.
+    pub fn verify_with_key_ring(
+        ring: &KeyRing<Self>,
+        show_credential: &ShowCredential<E>,
+        context: &PresentationContext,
+    ) -> Result<bool, ProofError> {
+        ring.get(&show_credential.key_id, context.timestamp)?
+            .verify(show_credential, context)
+    }
+
+    /// Verifier checks many presentations, each against its own context, and returns the indices
+    /// of the ones that fail
+    pub fn verify_batch<R: Rng + Send>(
+        &self,
+        presentations: &[(&ShowCredential<E>, &PresentationContext)],
+        rng: &mut R,
+    ) -> Vec<usize> {
+        let proofs: Vec<BatchPresentation<E::ScalarField>> = presentations
+            .iter()
+            .map(|(show_credential, context)| {
+                (
+                    show_credential.proof.as_slice(),
+                    &show_credential.disclosed,
+                    *context,
+                )
+            })
+            .collect();
+        SignatureProofs::verify_knowledge_batch(&self.pp, &self.pvk, &proofs, rng)
+    }
+
+    /// Show credential with message `index` verifiably encrypted to an auditor, bound to `label`
+    pub fn show_with_escrow<R: Rng>(
+        &self,
+        signature: &PSSignature<E>,
+        user_cred: &UserCred<E>,
+        auditor_pk: &AuditorPublicKey<E::G1Affine>,
+        index: usize,
+        label: &[u8],
+        rng: &mut R,
+    ) -> Result<ShowCredential<E>, ProofError> {
+        let proof = SignatureProofs::pok_signature_with_escrow(
+            &self.pp,
+            &self.pk,
+            &user_cred.messages,
+            signature,
+            auditor_pk,
+            index,
+            label,
+            rng,
+        )?;
+
+        Ok(ShowCredential::new(self.key_id, proof))
+    }
+
+    /// Verifier checks an escrowed presentation for its own session label
+    pub fn verify_with_escrow(
+        &self,
+        show_credential: &ShowCredential<E>,
+        auditor_pk: &AuditorPublicKey<E::G1Affine>,
+        index: usize,
+        label: &[u8],
+    ) -> Result<bool, ProofError> {
+        // the escrow proof does not cover a disclosure
+        if !show_credential.disclosed.attributes.is_empty() {
+            return Ok(false);
+        }
+        SignatureProofs::verify_knowledge_with_escrow_prepared(
+            &self.pp,
+            &self.pvk,
+            auditor_pk,
+            index,
+            label,
+            &show_credential.proof,
+        )
+    }
+
+    /// `show_with_escrow` for the schema attribute `name`
+    pub fn show_with_escrow_by_name<R: Rng>(
+        &self,
+        schema: &Schema,
+        signature: &PSSignature<E>,
+        user_cred: &UserCred<E>,
+        auditor_pk: &AuditorPublicKey<E::G1Affine>,
+        name: &str,
+        label: &[u8],
+        rng: &mut R,
+    ) -> Result<ShowCredential<E>, ProofError> {
+        schema.check_context(&self.pp.context)?;
+        let index = schema.index_of(name)?;
+        self.show_with_escrow(signature, user_cred, auditor_pk, index, label, rng)
+    }
+
+    /// `verify_with_escrow` for the schema attribute `name`
+    pub fn verify_with_escrow_by_name(
+        &self,
+        schema: &Schema,
+        show_credential: &ShowCredential<E>,
+        auditor_pk: &AuditorPublicKey<E::G1Affine>,
+        name: &str,
+        label: &[u8],
+    ) -> Result<bool, ProofError> {
+        schema.check_context(&self.pp.context)?;
+        let index = schema.index_of(name)?;
+        self.verify_with_escrow(show_credential, auditor_pk, index, label)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ark_bls12_381::Bls12_381;
+    use crate::proofsystem::{EscrowedSignatureProof, SignatureProof};
+    use ark_ec::pairing::Pairing;
     use ark_std::test_rng;
+    use ark_std::Zero;
+    use schnorr::batch::BatchError;
+    use schnorr::verifiable_encryption::VerifiableEncryption;
+    use std::ops::Mul;
+    use utils::key_ring::{KeyRingError, ValidityWindow};
+    use utils::schema::{AttributeType, AttributeValue, SchemaError};
 
-    #[test]
-    fn test_ps_anoncred_lifecycle() {
+    fn test_ps_anoncred_lifecycle<E: Pairing>() {
         // Setup phase
         let mut rng = test_rng();
         let message_count = 5;
-        let protocol = PSAnonCredProtocol::<Bls12_381>::new(message_count, &mut rng);
+        let protocol = PSAnonCredProtocol::<E>::new(message_count, &mut rng);
 
         // User phase - generate attributes
-        let user_cred = UserCred::<Bls12_381>::new_random_messages(message_count);
+        let user_cred = UserCred::<E>::new_random_messages(message_count);
 
         // Obtain phase - user creates proof
-        let proof = protocol.obtain(&user_cred).unwrap();
+        let proof = protocol.obtain(&user_cred, &mut rng).unwrap();
 
         // Issue phase - issuer issues credential
-        let blind_signature = protocol.issue(&proof, &mut rng).unwrap();
+        let response = protocol.issue(&proof, &mut rng).unwrap();
 
         // User unblinds the signature
-        let signature = PSAnonCredProtocol::complete_signature(&blind_signature, &user_cred.t);
+        let signature =
+            PSAnonCredProtocol::complete_signature(&response.blind_signature, &user_cred.t);
 
-        // Show phase - user creates presentation
-        let presentation = protocol.show(&signature, &user_cred, &mut rng).unwrap();
+        // Show phase - user creates presentation for the verifier's context
+        let context = PresentationContext::new(b"verifier", b"nonce-1", 1_700_000_000);
+        let presentation = protocol
+            .show(&signature, &user_cred, &context, &mut rng)
+            .unwrap();
 
         // Verify phase
         assert!(
-            protocol.verify(&presentation).unwrap(),
+            protocol.verify(&presentation, &context).unwrap(),
             "Credential verification failed"
         );
+
+        // Replay under a fresh nonce is rejected
+        let replay_context = PresentationContext::new(b"verifier", b"nonce-2", 1_700_000_000);
+        assert!(!protocol.verify(&presentation, &replay_context).unwrap());
+
+        // a second show randomizes the signature afresh, so the two cannot be linked
+        let second = protocol
+            .show(&signature, &user_cred, &replay_context, &mut rng)
+            .unwrap();
+        let sigma1 = |show: &ShowCredential<E>| {
+            SignatureProof::<E>::deserialize_compressed(&show.proof[..])
+                .unwrap()
+                .randomized_signature
+                .sigma1
+        };
+        assert_ne!(sigma1(&presentation), sigma1(&second));
     }
+
+    fn test_ps_anoncred_precompute_show<E: Pairing>() {
+        let mut rng = test_rng();
+        let message_count = 4;
+        let protocol = PSAnonCredProtocol::<E>::new(message_count, &mut rng);
+        let user_cred = UserCred::<E>::new_random_messages(message_count);
+        let proof = protocol.obtain(&user_cred, &mut rng).unwrap();
+        let response = protocol.issue(&proof, &mut rng).unwrap();
+        let signature =
+            PSAnonCredProtocol::complete_signature(&response.blind_signature, &user_cred.t);
+
+        // tokens are made before any verifier context exists
+        let tokens: Vec<ShowToken<E>> = (0..2)
+            .map(|_| protocol.precompute_show(&signature, &user_cred, &mut rng))
+            .collect();
+
+        let contexts = [
+            PresentationContext::new(b"verifier", b"nonce-1", 0),
+            PresentationContext::new(b"verifier", b"nonce-2", 0),
+        ];
+        let presentations: Vec<ShowCredential<E>> = tokens
+            .into_iter()
+            .zip(contexts.iter())
+            .map(|(token, context)| protocol.finish_show(token, context, &[]).unwrap())
+            .collect();
+
+        assert!(protocol.verify(&presentations[0], &contexts[0]).unwrap());
+        assert!(protocol.verify(&presentations[1], &contexts[1]).unwrap());
+        assert!(!protocol.verify(&presentations[0], &contexts[1]).unwrap());
+        // each token randomizes the signature afresh
+        assert_ne!(presentations[0].proof, presentations[1].proof);
+
+        // the messages to reveal are picked when the request arrives, not when the token is made
+        let first = protocol.precompute_show(&signature, &user_cred, &mut rng);
+        let second = protocol.precompute_show(&signature, &user_cred, &mut rng);
+        let one = protocol.finish_show(first, &contexts[0], &[1]).unwrap();
+        let two = protocol.finish_show(second, &contexts[0], &[3, 0]).unwrap();
+        assert_eq!(one.disclosed.attributes, vec![(1, user_cred.messages[1])]);
+        assert_eq!(
+            two.disclosed.attributes,
+            vec![(3, user_cred.messages[3]), (0, user_cred.messages[0])]
+        );
+        assert!(protocol.verify(&one, &contexts[0]).unwrap());
+        assert!(protocol.verify(&two, &contexts[0]).unwrap());
+
+        // a disclosed value the credential does not hold fails, as does dropping the disclosure
+        let mut forged = one.clone();
+        forged.disclosed.attributes[0].1 += E::ScalarField::from(1u64);
+        assert!(!protocol.verify(&forged, &contexts[0]).unwrap());
+        let mut stripped = two.clone();
+        stripped.disclosed = Disclosure::default();
+        assert!(!protocol.verify(&stripped, &contexts[0]).unwrap());
+        assert_eq!(
+            protocol.verify_batch(&[(&one, &contexts[0]), (&forged, &contexts[0])], &mut rng),
+            vec![1]
+        );
+
+        let token = protocol.precompute_show(&signature, &user_cred, &mut rng);
+        assert!(matches!(
+            protocol.finish_show(token, &contexts[0], &[message_count]),
+            Err(ProofError::DisclosureError(_))
+        ));
+    }
+
+    fn test_ps_anoncred_issue_batch<E: Pairing>() {
+        let mut rng = test_rng();
+        let message_count = 3;
+        let protocol = PSAnonCredProtocol::<E>::new(message_count, &mut rng);
+        let other = PSAnonCredProtocol::<E>::new(message_count, &mut rng);
+
+        let users: Vec<UserCred<E>> = (0..4)
+            .map(|_| UserCred {
+                t: E::ScalarField::rand(&mut rng),
+                messages: (0..message_count)
+                    .map(|_| E::ScalarField::rand(&mut rng))
+                    .collect(),
+            })
+            .collect();
+        let mut proofs: Vec<Vec<u8>> = users
+            .iter()
+            .map(|u| protocol.obtain(u, &mut rng).unwrap())
+            .collect();
+
+        let responses = protocol.issue_batch(&proofs, &mut rng).unwrap();
+        let context = PresentationContext::new(b"verifier", b"nonce-1", 1_700_000_000);
+        for (response, user_cred) in responses.iter().zip(users.iter()) {
+            let signature =
+                PSAnonCredProtocol::complete_signature(&response.blind_signature, &user_cred.t);
+            let presentation = protocol
+                .show(&signature, user_cred, &context, &mut rng)
+                .unwrap();
+            assert!(protocol.verify(&presentation, &context).unwrap());
+        }
+
+        // a request made for another issuer is named and nothing is issued
+        proofs[2] = other.obtain(&users[2], &mut rng).unwrap();
+        assert!(matches!(
+            protocol.issue_batch(&proofs, &mut rng),
+            Err(ProofError::BatchError(BatchError::InvalidProof(2)))
+        ));
+    }
+
+    fn test_ps_anoncred_verify_batch<E: Pairing>() {
+        let mut rng = test_rng();
+        let message_count = 3;
+        let protocol = PSAnonCredProtocol::<E>::new(message_count, &mut rng);
+
+        let contexts: Vec<PresentationContext> = (0..6u64)
+            .map(|i| PresentationContext::new(b"verifier", &i.to_le_bytes(), 1_700_000_000))
+            .collect();
+        let mut presentations: Vec<ShowCredential<E>> = contexts
+            .iter()
+            .map(|context| {
+                let user_cred = UserCred::<E> {
+                    t: E::ScalarField::rand(&mut rng),
+                    messages: (0..message_count)
+                        .map(|_| E::ScalarField::rand(&mut rng))
+                        .collect(),
+                };
+                let proof = protocol.obtain(&user_cred, &mut rng).unwrap();
+                let response = protocol.issue(&proof, &mut rng).unwrap();
+                let signature =
+                    PSAnonCredProtocol::complete_signature(&response.blind_signature, &user_cred.t);
+                protocol
+                    .show(&signature, &user_cred, context, &mut rng)
+                    .unwrap()
+            })
+            .collect();
+
+        let batch: Vec<_> = presentations.iter().zip(contexts.iter()).collect();
+        assert!(protocol.verify_batch(&batch, &mut rng).is_empty());
+
+        // presentation 1 is replayed to another context, presentation 4 carries a forged response
+        presentations[1] = presentations[0].clone();
+        let mut proof: SignatureProof<E> =
+            CanonicalDeserialize::deserialize_compressed(presentations[4].proof.as_slice())
+                .unwrap();
+        proof.responses[0] += E::ScalarField::from(1u64);
+        let mut bytes = Vec::new();
+        proof.serialize_compressed(&mut bytes).unwrap();
+        presentations[4] = ShowCredential::new(protocol.key_id(), bytes);
+
+        let batch: Vec<_> = presentations.iter().zip(contexts.iter()).collect();
+        assert_eq!(protocol.verify_batch(&batch, &mut rng), vec![1, 4]);
+        for (i, (presentation, context)) in batch.iter().enumerate() {
+            assert_eq!(
+                protocol.verify(presentation, context).unwrap(),
+                i != 1 && i != 4
+            );
+        }
+        assert!(protocol.verify_batch(&[], &mut rng).is_empty());
+    }
+
+    fn test_ps_anoncred_escrow<E: Pairing>() {
+        let mut rng = test_rng();
+        let message_count = 5;
+        let protocol = PSAnonCredProtocol::<E>::new(message_count, &mut rng);
+        let (auditor_sk, auditor_pk) = VerifiableEncryption::keygen(&mut rng);
+        let user_cred = UserCred::<E>::new_random_messages(message_count);
+
+        let proof = protocol.obtain(&user_cred, &mut rng).unwrap();
+        let response = protocol.issue(&proof, &mut rng).unwrap();
+        let signature =
+            PSAnonCredProtocol::complete_signature(&response.blind_signature, &user_cred.t);
+
+        let index = 1;
+        let label = b"verifier session";
+        let presentation = protocol
+            .show_with_escrow(&signature, &user_cred, &auditor_pk, index, label, &mut rng)
+            .unwrap();
+        assert!(protocol
+            .verify_with_escrow(&presentation, &auditor_pk, index, label)
+            .unwrap());
+        // replaying into another session or claiming another attribute fails
+        assert!(!protocol
+            .verify_with_escrow(&presentation, &auditor_pk, index, b"other session")
+            .unwrap());
+        assert!(!protocol
+            .verify_with_escrow(&presentation, &auditor_pk, 2, label)
+            .unwrap());
+
+        // auditor opens the ciphertext
+        let escrowed: EscrowedSignatureProof<E> =
+            CanonicalDeserialize::deserialize_compressed(&presentation.proof[..]).unwrap();
+        let plaintext =
+            VerifiableEncryption::decrypt(&auditor_sk, &auditor_pk, &escrowed.ciphertext, label)
+                .unwrap();
+        assert_eq!(
+            VerifiableEncryption::resolve(&auditor_pk, &plaintext, &user_cred.messages),
+            Some(index)
+        );
+    }
+
+    fn test_ps_anoncred_issuer_attributes<E: Pairing>() {
+        let mut rng = test_rng();
+        let message_count = 5;
+        let protocol = PSAnonCredProtocol::<E>::new(message_count, &mut rng);
+        let user_cred = UserCred::<E>::new_random_messages(message_count);
+
+        // issuer sets expiry at index 1 and credential type at index 3
+        let issuer_attributes = vec![
+            (1, E::ScalarField::from(1_800_000_000u64)),
+            (3, E::ScalarField::from(7u64)),
+        ];
+        let proof = protocol
+            .obtain_partial(&user_cred, &[1, 3], &mut rng)
+            .unwrap();
+        let response = protocol
+            .issue_partial(&proof, &issuer_attributes, &mut rng)
+            .unwrap();
+        let (signature, merged_cred) = PSAnonCredProtocol::complete_partial_signature(
+            &response.blind_signature,
+            &user_cred,
+            &issuer_attributes,
+        )
+        .unwrap();
+        assert_eq!(merged_cred.messages[3], E::ScalarField::from(7u64));
+        assert_eq!(merged_cred.messages[0], user_cred.messages[0]);
+
+        let context = PresentationContext::new(b"verifier", b"nonce-1", 1_700_000_000);
+        let presentation = protocol
+            .show(&signature, &merged_cred, &context, &mut rng)
+            .unwrap();
+        assert!(protocol.verify(&presentation, &context).unwrap());
+
+        // the user's own values at the issuer indices are not what gets signed
+        let presentation = protocol
+            .show(&signature, &user_cred, &context, &mut rng)
+            .unwrap();
+        assert!(!protocol.verify(&presentation, &context).unwrap());
+
+        // a proof over different indices, or a full commitment proof, is rejected
+        assert!(protocol
+            .issue_partial(&proof, &[(1, E::ScalarField::from(1u64))], &mut rng)
+            .is_err());
+        let full_proof = protocol.obtain(&user_cred, &mut rng).unwrap();
+        assert!(protocol
+            .issue_partial(&full_proof, &issuer_attributes, &mut rng)
+            .is_err());
+        assert!(matches!(
+            protocol.obtain_partial(&user_cred, &[1, 1], &mut rng),
+            Err(ProofError::InvalidAttributeIndex(1))
+        ));
+
+        // a response naming an index twice or past the end is refused, not merged
+        let one = E::ScalarField::from(1u64);
+        for bad in [vec![(1, one), (1, one)], vec![(message_count, one)]] {
+            assert!(PSAnonCredProtocol::complete_partial_signature(
+                &response.blind_signature,
+                &user_cred,
+                &bad
+            )
+            .is_err());
+        }
+    }
+
+    fn test_ps_anoncred_reissue<E: Pairing>() {
+        let mut rng = test_rng();
+        let message_count = 4;
+        let old_issuer = PSAnonCredProtocol::<E>::new(message_count, &mut rng);
+        let new_issuer = PSAnonCredProtocol::<E>::new(message_count, &mut rng);
+        let user_cred = UserCred::<E>::new_random_messages(message_count);
+        let proof = old_issuer.obtain(&user_cred, &mut rng).unwrap();
+        let response = old_issuer.issue(&proof, &mut rng).unwrap();
+        let signature =
+            PSAnonCredProtocol::complete_signature(&response.blind_signature, &user_cred.t);
+
+        // bump the counter at index 0 and set a new expiry at index 3 under the new key
+        let updates = vec![
+            (0, AttributeUpdate::Add(E::ScalarField::from(1u64))),
+            (
+                3,
+                AttributeUpdate::Set(E::ScalarField::from(1_900_000_000u64)),
+            ),
+        ];
+        let context = PresentationContext::new(b"issuer", b"refresh-1", 1_700_000_000);
+        let (request, new_cred) = new_issuer
+            .obtain_reissue(
+                &old_issuer.pp,
+                &old_issuer.pk,
+                &signature,
+                &user_cred,
+                &updates,
+                &context,
+                &mut rng,
+            )
+            .unwrap();
+        assert_eq!(
+            new_cred.messages[0],
+            user_cred.messages[0] + E::ScalarField::from(1u64)
+        );
+        assert_eq!(new_cred.messages[1], user_cred.messages[1]);
+
+        let response = new_issuer
+            .reissue(
+                &old_issuer.pp,
+                &old_issuer.pk,
+                &request,
+                &updates,
+                &context,
+                &mut rng,
+            )
+            .unwrap();
+        let new_signature =
+            PSAnonCredProtocol::complete_signature(&response.blind_signature, &new_cred.t);
+        let show_context = PresentationContext::new(b"verifier", b"nonce-1", 1_700_000_000);
+        let presentation = new_issuer
+            .show(&new_signature, &new_cred, &show_context, &mut rng)
+            .unwrap();
+        assert!(new_issuer.verify(&presentation, &show_context).unwrap());
+
+        // the issuer rejects other declared updates, another context or the wrong old key
+        let other_updates = vec![(0, AttributeUpdate::Add(E::ScalarField::from(2u64)))];
+        assert!(new_issuer
+            .reissue(
+                &old_issuer.pp,
+                &old_issuer.pk,
+                &request,
+                &other_updates,
+                &context,
+                &mut rng
+            )
+            .is_err());
+        let replay_context = PresentationContext::new(b"issuer", b"refresh-2", 1_700_000_000);
+        assert!(new_issuer
+            .reissue(
+                &old_issuer.pp,
+                &old_issuer.pk,
+                &request,
+                &updates,
+                &replay_context,
+                &mut rng
+            )
+            .is_err());
+        assert!(new_issuer
+            .reissue(
+                &new_issuer.pp,
+                &new_issuer.pk,
+                &request,
+                &updates,
+                &context,
+                &mut rng
+            )
+            .is_err());
+    }
+
+    fn test_ps_anoncred_schema<E: Pairing>() {
+        let mut rng = test_rng();
+        let schema = Schema::new(
+            "example.org/member/v1",
+            &[
+                ("member_id", AttributeType::Bytes),
+                ("tier", AttributeType::UInt),
+                ("expiry", AttributeType::Date),
+            ],
+        )
+        .unwrap();
+        let protocol = PSAnonCredProtocol::<E>::with_schema(&schema, &mut rng);
+        let (auditor_sk, auditor_pk) = VerifiableEncryption::keygen(&mut rng);
+
+        // user holds member_id and tier, the issuer sets the expiry by name
+        let messages: Vec<E::ScalarField> = schema
+            .encode(&[
+                ("member_id", AttributeValue::Bytes(b"m-1234".to_vec())),
+                ("tier", AttributeValue::UInt(2)),
+                ("expiry", AttributeValue::Date(0)),
+            ])
+            .unwrap();
+        let user_cred = UserCred::<E>::new(&messages, E::ScalarField::rand(&mut rng));
+        let issuer_attributes = schema
+            .encode_named(&[("expiry", AttributeValue::date(2030, 6, 30).unwrap())])
+            .unwrap();
+        let proof = protocol
+            .obtain_partial(&user_cred, &schema.indices(&["expiry"]).unwrap(), &mut rng)
+            .unwrap();
+        let response = protocol
+            .issue_partial(&proof, &issuer_attributes, &mut rng)
+            .unwrap();
+        let (signature, user_cred) = PSAnonCredProtocol::complete_partial_signature(
+            &response.blind_signature,
+            &user_cred,
+            &issuer_attributes,
+        )
+        .unwrap();
+
+        let label = b"verifier session";
+        let presentation = protocol
+            .show_with_escrow_by_name(
+                &schema,
+                &signature,
+                &user_cred,
+                &auditor_pk,
+                "member_id",
+                label,
+                &mut rng,
+            )
+            .unwrap();
+        assert!(protocol
+            .verify_with_escrow_by_name(&schema, &presentation, &auditor_pk, "member_id", label)
+            .unwrap());
+        assert!(!protocol
+            .verify_with_escrow_by_name(&schema, &presentation, &auditor_pk, "tier", label)
+            .unwrap());
+
+        let escrowed: EscrowedSignatureProof<E> =
+            CanonicalDeserialize::deserialize_compressed(&presentation.proof[..]).unwrap();
+        let plaintext =
+            VerifiableEncryption::decrypt(&auditor_sk, &auditor_pk, &escrowed.ciphertext, label)
+                .unwrap();
+        assert_eq!(
+            plaintext,
+            auditor_pk.g.mul(user_cred.messages[0]).into_affine()
+        );
+        assert!(schema.matches(
+            "member_id",
+            &AttributeValue::Bytes(b"m-1234".to_vec()),
+            &user_cred.messages[0]
+        ));
+
+        // names outside the schema, or a schema the keys were not made for, are rejected
+        assert!(matches!(
+            protocol.verify_with_escrow_by_name(&schema, &presentation, &auditor_pk, "name", label),
+            Err(ProofError::SchemaError(SchemaError::UnknownAttribute(_)))
+        ));
+        let other_schema = Schema::new("other", &[("member_id", AttributeType::Bytes)]).unwrap();
+        assert!(protocol
+            .verify_with_escrow_by_name(
+                &other_schema,
+                &presentation,
+                &auditor_pk,
+                "member_id",
+                label
+            )
+            .is_err());
+    }
+
+    fn test_ps_anoncred_verified_key<E: Pairing>() {
+        let mut rng = test_rng();
+        let issuer = PSAnonCredProtocol::<E>::new(3, &mut rng);
+        let proof = issuer.prove_key(&mut rng).unwrap();
+
+        let holder =
+            PSAnonCredProtocol::from_verified_public_key(issuer.pk.clone(), &proof, &mut rng)
+                .unwrap();
+        let user_cred = UserCred::<E>::new_random_messages(3);
+        let request = holder.obtain(&user_cred, &mut rng).unwrap();
+        assert!(issuer.issue(&request, &mut rng).is_ok());
+
+        // holders cannot prove a key, and a swapped key is refused
+        assert!(matches!(
+            holder.prove_key(&mut rng),
+            Err(ProofError::MissingSecretKey)
+        ));
+        let other = PSAnonCredProtocol::<E>::new(3, &mut rng);
+        assert!(matches!(
+            PSAnonCredProtocol::from_verified_public_key(other.pk, &proof, &mut rng),
+            Err(ProofError::KeyProofError(_))
+        ));
+    }
+
+    fn test_ps_anoncred_key_rotation<E: Pairing>() {
+        let mut rng = test_rng();
+        let old_issuer = PSAnonCredProtocol::<E>::new(3, &mut rng);
+        let new_issuer = PSAnonCredProtocol::<E>::new(3, &mut rng);
+        assert_ne!(old_issuer.key_id(), new_issuer.key_id());
+        assert_eq!(old_issuer.key_id(), old_issuer.pk.key_id());
+
+        let show = |issuer: &PSAnonCredProtocol<E>, context: &PresentationContext| {
+            let mut rng = test_rng();
+            let user_cred = UserCred::<E>::new_random_messages(3);
+            let request = issuer.obtain(&user_cred, &mut rng).unwrap();
+            let response = issuer.issue(&request, &mut rng).unwrap();
+            assert_eq!(response.key_id, issuer.key_id());
+            let signature =
+                PSAnonCredProtocol::complete_signature(&response.blind_signature, &user_cred.t);
+            let show = issuer
+                .show(&signature, &user_cred, context, &mut rng)
+                .unwrap();
+            assert_eq!(show.key_id, response.key_id);
+            show
+        };
+
+        // the new key takes over at 200, the old one is accepted until 300
+        let mut ring = KeyRing::new();
+        ring.insert(
+            old_issuer.key_id(),
+            PSAnonCredProtocol::from_public_key(old_issuer.pk.clone()),
+            ValidityWindow::starting(0),
+        );
+        ring.insert(
+            new_issuer.key_id(),
+            PSAnonCredProtocol::from_public_key(new_issuer.pk.clone()),
+            ValidityWindow::starting(200),
+        );
+        ring.retire(&old_issuer.key_id(), 300).unwrap();
+
+        let during = PresentationContext::new(b"verifier", b"nonce", 250);
+        let old_show = show(&old_issuer, &during);
+        let new_show = show(&new_issuer, &during);
+        assert_eq!(old_show.key_id, old_issuer.key_id());
+        assert!(PSAnonCredProtocol::verify_with_key_ring(&ring, &old_show, &during).unwrap());
+        assert!(PSAnonCredProtocol::verify_with_key_ring(&ring, &new_show, &during).unwrap());
+
+        // naming the other key does not help a presentation
+        let mut relabelled = old_show.clone();
+        relabelled.key_id = new_issuer.key_id();
+        assert!(!PSAnonCredProtocol::verify_with_key_ring(&ring, &relabelled, &during).unwrap());
+
+        let after = PresentationContext::new(b"verifier", b"nonce", 301);
+        let late_show = show(&old_issuer, &after);
+        assert!(matches!(
+            PSAnonCredProtocol::verify_with_key_ring(&ring, &late_show, &after),
+            Err(ProofError::KeyRingError(
+                KeyRingError::OutsideValidity { .. }
+            ))
+        ));
+        relabelled.key_id = KeyId::default();
+        assert!(matches!(
+            PSAnonCredProtocol::verify_with_key_ring(&ring, &relabelled, &during),
+            Err(ProofError::KeyRingError(KeyRingError::UnknownKey(_)))
+        ));
+    }
+
+    fn test_ps_anoncred_secrets_redacted<E: Pairing>() {
+        let mut rng = test_rng();
+        let context = E::ScalarField::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&3, &context, &mut rng);
+        let (mut sk, _) = gen_keys(&pp, &mut rng);
+        let mut user_cred = UserCred::<E>::new_random_messages(3);
+
+        assert_eq!(format!("{:?}", sk), "SecretKey { .. }");
+        assert_eq!(format!("{:?}", user_cred), "UserCred { .. }");
+
+        sk.zeroize();
+        assert!(sk.x.is_zero() && sk.yi.is_empty());
+        user_cred.zeroize();
+        assert!(user_cred.t.is_zero() && user_cred.messages.is_empty());
+    }
+
+    utils::curve_tests!(
+        test_ps_anoncred_lifecycle,
+        test_ps_anoncred_precompute_show,
+        test_ps_anoncred_issue_batch,
+        test_ps_anoncred_verify_batch,
+        test_ps_anoncred_escrow,
+        test_ps_anoncred_issuer_attributes,
+        test_ps_anoncred_reissue,
+        test_ps_anoncred_schema,
+        test_ps_anoncred_verified_key,
+        test_ps_anoncred_key_rotation,
+        test_ps_anoncred_secrets_redacted
+    );
 }
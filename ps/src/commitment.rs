@@ -1,4 +1,4 @@
-use crate::keygen::PublicKey;
+use crate::keygen::{PublicKey, PublicKeyTables};
 use crate::proofsystem::{CommitmentProof, CommitmentProofs, ProofError};
 use crate::publicparams::PublicParams;
 use ark_ec::pairing::Pairing;
@@ -41,6 +41,29 @@ impl<E: Pairing> Commitment<E> {
         }
     }
 
+    /// `new` with the commitment computed from the key's precomputed G1 tables
+    pub fn new_with_tables(
+        pp: &PublicParams<E>,
+        pk: &PublicKey<E>,
+        tables: &PublicKeyTables<E>,
+        messages: &[E::ScalarField],
+        t: &E::ScalarField,
+    ) -> Self {
+        assert_eq!(messages.len(), pp.n, "Invalid message count");
+
+        let mut exponents = messages.to_vec();
+        exponents.push(*t);
+        let commitment = tables.bases_g1.msm(&exponents).into_affine();
+
+        Self {
+            pp: pp.clone(),
+            pk: pk.clone(),
+            messages: messages.to_vec(),
+            t: *t,
+            commitment,
+        }
+    }
+
     /// returns commitment exponents C([m_1,...,m_n],t)
     pub fn get_exponents(&self) -> Vec<E::ScalarField> {
         let mut exponents: Vec<E::ScalarField> = self.messages.clone();
@@ -63,8 +86,8 @@ impl<E: Pairing> Commitment<E> {
     }
 
     /// get pok in g1
-    pub fn prove_opening(&self) -> Result<Vec<u8>, ProofError> {
-        CommitmentProofs::pok_commitment_prove(&self)
+    pub fn prove_opening(&self, rng: &mut impl Rng) -> Result<Vec<u8>, ProofError> {
+        CommitmentProofs::pok_commitment_prove(self, rng)
     }
 }
 
@@ -88,19 +111,19 @@ pub fn compute_commitment_g1<E: Pairing>(
 mod tests {
     use super::*;
     use crate::keygen::gen_keys;
-    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ec::pairing::Pairing;
 
-    #[test]
-    fn test_commitment() {
+    fn test_commitment<E: Pairing>() {
         let n = 4;
         let mut rng = ark_std::test_rng();
-        let context = Fr::rand(&mut rng);
-        let pp = PublicParams::<Bls12_381>::new(&n, &context, &mut rng);
+        let context = E::ScalarField::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&n, &context, &mut rng);
         let (_, pk) = gen_keys(&pp, &mut rng);
 
         // Create random messages and blinding factor
-        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
-        let t = Fr::rand(&mut rng);
+        let messages: Vec<E::ScalarField> =
+            (0..n).map(|_| E::ScalarField::rand(&mut rng)).collect();
+        let t = E::ScalarField::rand(&mut rng);
 
         // Create commitment
         let commitment = Commitment::new(&pp, &pk, &messages, &t);
@@ -110,5 +133,11 @@ mod tests {
             !commitment.commitment.is_zero(),
             "Commitment should not be zero"
         );
+
+        let tables = pk.precompute();
+        let from_tables = Commitment::new_with_tables(&pp, &pk, &tables, &messages, &t);
+        assert_eq!(from_tables.commitment, commitment.commitment);
     }
+
+    utils::curve_tests!(test_commitment);
 }
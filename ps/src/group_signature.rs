@@ -0,0 +1,430 @@
+use crate::keygen::{gen_keys, PublicKey, SecretKey};
+use crate::publicparams::PublicParams;
+use crate::signature::PSSignature;
+use ark_ec::pairing::{Pairing, PairingOutput};
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{UniformRand, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::ops::{Mul, Neg};
+use ark_std::rand::Rng;
+use schnorr::schnorr_pairing::SchnorrProtocolPairing;
+use thiserror::Error;
+use utils::hash::HashUtil;
+
+// Group signatures from Pointcheval-Sanders 2016, section 6. The manager holds a single message PS
+// key and signs a member secret s blindly from tau = Y^s. It also keeps tau_tilde = Y_tilde^s, which
+// lets it recognise the signer of a randomized signature (sigma1, sigma2) through
+// e(sigma2, g2) / e(sigma1, X_tilde) = e(sigma1, tau_tilde). Opening proves knowledge of that
+// tau_tilde for a registered tau, so anyone holding the public registry can check the opening.
+
+#[derive(Error, Debug)]
+pub enum GroupSignatureError {
+    #[error("Invalid join request")]
+    InvalidJoinRequest,
+    #[error("Member key is already registered")]
+    DuplicateMember,
+    #[error("Invalid membership credential")]
+    InvalidCredential,
+    #[error("Signature does not verify")]
+    InvalidSignature,
+    #[error("No registered member produced the signature")]
+    UnknownSigner,
+    #[error("Serialization error: {0}")]
+    SerializationError(#[from] ark_serialize::SerializationError),
+}
+
+/// Member secret s, never leaves the member
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct MemberKey<E: Pairing> {
+    s: E::ScalarField,
+}
+
+/// tau = Y^s and tau_tilde = Y_tilde^s with a proof of knowledge of s for tau
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct JoinRequest<E: Pairing> {
+    pub tau: E::G1Affine,
+    pub tau_tilde: E::G2Affine,
+    pub schnorr_commitment: E::G1Affine,
+    pub response: E::ScalarField,
+}
+
+/// Manager's record of a member, tau is public and tau_tilde is the tracing key
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+struct RegistryEntry<E: Pairing> {
+    tau: E::G1Affine,
+    tau_tilde: E::G2Affine,
+}
+
+pub struct GroupManager<E: Pairing> {
+    pub pk: PublicKey<E>,
+    sk: SecretKey<E>,
+    registry: Vec<RegistryEntry<E>>,
+}
+
+/// A member's PS signature on s, issued by the manager
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct GroupMember<E: Pairing> {
+    pub id: usize,
+    key: MemberKey<E>,
+    credential: PSSignature<E>,
+}
+
+/// Randomized membership credential with a proof of knowledge of s, bound to the message
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct GroupSignature<E: Pairing> {
+    pub randomized_signature: PSSignature<E>,
+    pub schnorr_commitment: PairingOutput<E>,
+    pub response: E::ScalarField,
+}
+
+/// Names the signer's registry entry and proves knowledge of its tracing key
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Opening<E: Pairing> {
+    pub member: usize,
+    pub commitment_g1: PairingOutput<E>,
+    pub commitment_signature: PairingOutput<E>,
+    pub response: E::G2Affine,
+}
+
+impl<E: Pairing> MemberKey<E> {
+    pub fn generate(rng: &mut impl Rng) -> Self {
+        Self {
+            s: E::ScalarField::rand(rng),
+        }
+    }
+
+    pub fn join_request(&self, pk: &PublicKey<E>, rng: &mut impl Rng) -> JoinRequest<E> {
+        let tau = pk.y_g1[0].mul(self.s).into_affine();
+        let tau_tilde = pk.y_g2[0].mul(self.s).into_affine();
+        let k = E::ScalarField::rand(rng);
+        let schnorr_commitment = pk.y_g1[0].mul(k).into_affine();
+        let challenge = join_challenge::<E>(&tau, &tau_tilde, &schnorr_commitment);
+        JoinRequest {
+            tau,
+            tau_tilde,
+            schnorr_commitment,
+            response: k + challenge * self.s,
+        }
+    }
+}
+
+impl<E: Pairing> GroupManager<E> {
+    pub fn new(rng: &mut impl Rng) -> Self {
+        let context = E::ScalarField::rand(rng);
+        let pp = PublicParams::<E>::new(&1, &context, rng);
+        let (sk, pk) = gen_keys(&pp, rng);
+        Self {
+            pk,
+            sk,
+            registry: Vec::new(),
+        }
+    }
+
+    /// Checks the request, records the member and signs s blindly from tau
+    pub fn join(
+        &mut self,
+        request: &JoinRequest<E>,
+        rng: &mut impl Rng,
+    ) -> Result<(usize, PSSignature<E>), GroupSignatureError> {
+        let pp = &self.pk.pp;
+        let y1 = self.pk.y_g1[0];
+        let challenge = join_challenge::<E>(
+            &request.tau,
+            &request.tau_tilde,
+            &request.schnorr_commitment,
+        );
+        let neg_g1 = pp.g1.into_group().neg().into_affine();
+        let valid = !request.tau.is_zero()
+            && y1.mul(request.response) == request.schnorr_commitment + request.tau.mul(challenge)
+            && E::multi_pairing([request.tau, neg_g1], [pp.g2, request.tau_tilde]).is_zero();
+        if !valid {
+            return Err(GroupSignatureError::InvalidJoinRequest);
+        }
+        // a second entry with the same tau would make openings ambiguous
+        if self.registry.iter().any(|e| e.tau == request.tau) {
+            return Err(GroupSignatureError::DuplicateMember);
+        }
+
+        self.registry.push(RegistryEntry {
+            tau: request.tau,
+            tau_tilde: request.tau_tilde,
+        });
+        let credential = PSSignature::blind_sign(pp, &self.pk, &self.sk, &request.tau, rng);
+        Ok((self.registry.len() - 1, credential))
+    }
+
+    /// Public part of the registry, index i is member i
+    pub fn registry(&self) -> Vec<E::G1Affine> {
+        self.registry.iter().map(|e| e.tau).collect()
+    }
+
+    /// Finds the signer of a valid signature and proves the opening
+    pub fn open(
+        &self,
+        message: &[u8],
+        signature: &GroupSignature<E>,
+        rng: &mut impl Rng,
+    ) -> Result<Opening<E>, GroupSignatureError> {
+        if !signature.verify(&self.pk, message) {
+            return Err(GroupSignatureError::InvalidSignature);
+        }
+        let pp = &self.pk.pp;
+        let sigma = &signature.randomized_signature;
+        let statement = sigma.generate_commitment_gt(pp, &self.pk);
+        let (member, entry) = self
+            .registry
+            .iter()
+            .enumerate()
+            .find(|(_, e)| E::pairing(sigma.sigma1, e.tau_tilde) == statement)
+            .ok_or(GroupSignatureError::UnknownSigner)?;
+
+        // proof of knowledge of tau_tilde with e(g1, tau_tilde) = e(tau, g2) and
+        // e(sigma1, tau_tilde) = e(sigma2, g2) / e(sigma1, X_tilde)
+        let blinding = pp.g2.mul(E::ScalarField::rand(rng)).into_affine();
+        let commitment_g1 = E::pairing(pp.g1, blinding);
+        let commitment_signature = E::pairing(sigma.sigma1, blinding);
+        let challenge = open_challenge::<E>(
+            message,
+            signature,
+            member,
+            &commitment_g1,
+            &commitment_signature,
+        )?;
+        Ok(Opening {
+            member,
+            commitment_g1,
+            commitment_signature,
+            response: (blinding + entry.tau_tilde.mul(challenge)).into_affine(),
+        })
+    }
+}
+
+impl<E: Pairing> GroupMember<E> {
+    /// Member checks the credential returned by `GroupManager::join`
+    pub fn new(
+        pk: &PublicKey<E>,
+        id: usize,
+        key: MemberKey<E>,
+        credential: PSSignature<E>,
+    ) -> Result<Self, GroupSignatureError> {
+        if credential.sigma1.is_zero() || !credential.public_verify(&pk.pp, &[key.s], pk) {
+            return Err(GroupSignatureError::InvalidCredential);
+        }
+        Ok(Self {
+            id,
+            key,
+            credential,
+        })
+    }
+
+    pub fn sign(&self, pk: &PublicKey<E>, message: &[u8], rng: &mut impl Rng) -> GroupSignature<E> {
+        let mut r = E::ScalarField::rand(rng);
+        while r.is_zero() {
+            r = E::ScalarField::rand(rng);
+        }
+        let randomized_signature = self.credential.rerandomize(&r, &E::ScalarField::zero());
+
+        let commitment =
+            SchnorrProtocolPairing::commit::<E>(&[randomized_signature.sigma1], &pk.y_g2, rng);
+        let challenge = sign_challenge::<E>(
+            message,
+            &randomized_signature,
+            &commitment.schnorr_commitment,
+        );
+        let response = SchnorrProtocolPairing::prove(&commitment, &[self.key.s], &challenge).0[0];
+        GroupSignature {
+            randomized_signature,
+            schnorr_commitment: commitment.schnorr_commitment,
+            response,
+        }
+    }
+}
+
+impl<E: Pairing> GroupSignature<E> {
+    /// e(sigma2, g2) / e(sigma1, X_tilde) = e(sigma1, Y_tilde)^s for the s the member proves
+    pub fn verify(&self, pk: &PublicKey<E>, message: &[u8]) -> bool {
+        let sigma = &self.randomized_signature;
+        if sigma.sigma1.is_zero() || pk.y_g2.len() != 1 {
+            return false;
+        }
+        let statement = sigma.generate_commitment_gt(&pk.pp, pk);
+        let challenge = sign_challenge::<E>(message, sigma, &self.schnorr_commitment);
+        SchnorrProtocolPairing::verify(
+            &statement,
+            &self.schnorr_commitment,
+            &challenge,
+            &[sigma.sigma1],
+            &pk.y_g2,
+            &[self.response],
+        )
+    }
+}
+
+impl<E: Pairing> Opening<E> {
+    /// Anyone holding the registry checks the manager named the right member
+    pub fn verify(
+        &self,
+        pk: &PublicKey<E>,
+        registry: &[E::G1Affine],
+        message: &[u8],
+        signature: &GroupSignature<E>,
+    ) -> bool {
+        let Some(tau) = registry.get(self.member) else {
+            return false;
+        };
+        if !signature.verify(pk, message) {
+            return false;
+        }
+        let Ok(challenge) = open_challenge::<E>(
+            message,
+            signature,
+            self.member,
+            &self.commitment_g1,
+            &self.commitment_signature,
+        ) else {
+            return false;
+        };
+
+        let pp = &pk.pp;
+        let sigma = &signature.randomized_signature;
+        let statement = sigma.generate_commitment_gt(pp, pk);
+        E::pairing(pp.g1, self.response)
+            == self.commitment_g1 + E::pairing(*tau, pp.g2).mul(challenge)
+            && E::pairing(sigma.sigma1, self.response)
+                == self.commitment_signature + statement.mul(challenge)
+    }
+}
+
+fn join_challenge<E: Pairing>(
+    tau: &E::G1Affine,
+    tau_tilde: &E::G2Affine,
+    schnorr_commitment: &E::G1Affine,
+) -> E::ScalarField {
+    let mut transcript = b"ps-group-join".to_vec();
+    tau.serialize_compressed(&mut transcript).unwrap();
+    tau_tilde.serialize_compressed(&mut transcript).unwrap();
+    schnorr_commitment
+        .serialize_compressed(&mut transcript)
+        .unwrap();
+    HashUtil::<E::ScalarField, E::G1Affine>::hash_to_field(&transcript)
+}
+
+fn sign_challenge<E: Pairing>(
+    message: &[u8],
+    randomized_signature: &PSSignature<E>,
+    schnorr_commitment: &PairingOutput<E>,
+) -> E::ScalarField {
+    let mut transcript = b"ps-group-sign".to_vec();
+    randomized_signature
+        .serialize_compressed(&mut transcript)
+        .unwrap();
+    schnorr_commitment
+        .serialize_compressed(&mut transcript)
+        .unwrap();
+    message.serialize_compressed(&mut transcript).unwrap();
+    HashUtil::<E::ScalarField, E::G1Affine>::hash_to_field(&transcript)
+}
+
+fn open_challenge<E: Pairing>(
+    message: &[u8],
+    signature: &GroupSignature<E>,
+    member: usize,
+    commitment_g1: &PairingOutput<E>,
+    commitment_signature: &PairingOutput<E>,
+) -> Result<E::ScalarField, GroupSignatureError> {
+    let mut transcript = b"ps-group-open".to_vec();
+    message.serialize_compressed(&mut transcript)?;
+    signature.serialize_compressed(&mut transcript)?;
+    (member as u64).serialize_compressed(&mut transcript)?;
+    commitment_g1.serialize_compressed(&mut transcript)?;
+    commitment_signature.serialize_compressed(&mut transcript)?;
+    Ok(HashUtil::<E::ScalarField, E::G1Affine>::hash_to_field(
+        &transcript,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ec::pairing::Pairing;
+
+    fn join<E: Pairing>(manager: &mut GroupManager<E>, rng: &mut impl Rng) -> GroupMember<E> {
+        let key = MemberKey::generate(rng);
+        let request = key.join_request(&manager.pk, rng);
+        let (id, credential) = manager.join(&request, rng).unwrap();
+        GroupMember::new(&manager.pk, id, key, credential).unwrap()
+    }
+
+    fn test_group_sign_and_open<E: Pairing>() {
+        let mut rng = ark_std::test_rng();
+        let mut manager = GroupManager::<E>::new(&mut rng);
+        let alice = join(&mut manager, &mut rng);
+        let bob = join(&mut manager, &mut rng);
+        let registry = manager.registry();
+
+        let message = b"transfer 10 to carol";
+        let signature = bob.sign(&manager.pk, message, &mut rng);
+        assert!(signature.verify(&manager.pk, message));
+        assert!(!signature.verify(&manager.pk, b"transfer 99 to carol"));
+
+        // two signatures by the same member share no element
+        let again = bob.sign(&manager.pk, message, &mut rng);
+        assert_ne!(
+            again.randomized_signature.sigma1,
+            signature.randomized_signature.sigma1
+        );
+
+        let opening = manager.open(message, &signature, &mut rng).unwrap();
+        assert_eq!(opening.member, bob.id);
+        assert!(opening.verify(&manager.pk, &registry, message, &signature));
+
+        // blaming another member or another message fails
+        let mut framed = opening.clone();
+        framed.member = alice.id;
+        assert!(!framed.verify(&manager.pk, &registry, message, &signature));
+        let alice_signature = alice.sign(&manager.pk, message, &mut rng);
+        assert!(!opening.verify(&manager.pk, &registry, message, &alice_signature));
+        assert_eq!(
+            manager
+                .open(message, &alice_signature, &mut rng)
+                .unwrap()
+                .member,
+            alice.id
+        );
+    }
+
+    fn test_group_join_checks<E: Pairing>() {
+        let mut rng = ark_std::test_rng();
+        let mut manager = GroupManager::<E>::new(&mut rng);
+        let key = MemberKey::<E>::generate(&mut rng);
+        let request = key.join_request(&manager.pk, &mut rng);
+
+        // tau_tilde for another secret
+        let mut forged = request.clone();
+        forged.tau_tilde = manager.pk.y_g2[0]
+            .mul(E::ScalarField::rand(&mut rng))
+            .into_affine();
+        assert!(matches!(
+            manager.join(&forged, &mut rng),
+            Err(GroupSignatureError::InvalidJoinRequest)
+        ));
+
+        manager.join(&request, &mut rng).unwrap();
+        assert!(matches!(
+            manager.join(&request, &mut rng),
+            Err(GroupSignatureError::DuplicateMember)
+        ));
+
+        // a signature under another group is not opened
+        let mut other = GroupManager::<E>::new(&mut rng);
+        let outsider = join(&mut other, &mut rng);
+        let signature = outsider.sign(&other.pk, b"message", &mut rng);
+        assert!(!signature.verify(&manager.pk, b"message"));
+        assert!(matches!(
+            manager.open(b"message", &signature, &mut rng),
+            Err(GroupSignatureError::InvalidSignature)
+        ));
+    }
+
+    utils::curve_tests!(test_group_sign_and_open, test_group_join_checks);
+}
@@ -2,22 +2,45 @@ use crate::publicparams::PublicParams;
 use ark_ec::pairing::Pairing;
 use ark_ec::CurveGroup;
 use ark_ff::UniformRand;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::fmt;
 use ark_std::ops::Mul;
 use ark_std::rand::Rng;
+use schnorr::key_proof::{KeyProof, KeyProofError, KeyStatement};
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use utils::encoding::Base64Point;
+use utils::fixed_base::{FixedBaseMsm, FixedBaseTable};
+use utils::key_ring::KeyId;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 
-#[derive(Clone, Debug)]
+const KEY_PROOF_DOMAIN: &[u8] = b"ps-key";
+
+/// Issuer secret key, wiped on drop and left out of `Debug` output
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize, Zeroize, ZeroizeOnDrop)]
 pub struct SecretKey<E: Pairing> {
     pub x: E::ScalarField,
     pub yi: Vec<E::ScalarField>,
     pub x_g1: E::G1Affine,
 }
 
-#[derive(Clone, Debug)]
+impl<E: Pairing> fmt::Debug for SecretKey<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecretKey").finish_non_exhaustive()
+    }
+}
+
+#[serde_as]
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct PublicKey<E: Pairing> {
     pub pp: PublicParams<E>,
+    #[serde_as(as = "Vec<Base64Point>")]
     pub y_g1: Vec<E::G1Affine>, //[Y_1, Y_2, ..., Y_n]
+    #[serde_as(as = "Vec<Base64Point>")]
     pub y_g2: Vec<E::G2Affine>, //[Y_1, Y_2, ..., Y_n]
-    pub x_g2: E::G2Affine,      //X_2 public key
+    #[serde_as(as = "Base64Point")]
+    pub x_g2: E::G2Affine, //X_2 public key
 }
 
 pub fn gen_keys<E: Pairing>(
@@ -53,6 +76,16 @@ pub fn gen_keys<E: Pairing>(
     (sk, pk)
 }
 
+/// `gen_keys` together with the proof holders check before `obtain`
+pub fn gen_keys_with_proof<E: Pairing>(
+    pp: &PublicParams<E>,
+    rng: &mut impl Rng,
+) -> (SecretKey<E>, PublicKey<E>, KeyProof<E>) {
+    let (sk, pk) = gen_keys(pp, rng);
+    let proof = pk.prove_key(&sk, rng);
+    (sk, pk, proof)
+}
+
 impl<E: Pairing> PublicKey<E> {
     /// returns commitment bases g_1, g_2, ..., g
     pub fn get_bases(&self) -> Vec<E::G1Affine> {
@@ -61,32 +94,122 @@ impl<E: Pairing> PublicKey<E> {
         bases
     }
 
+    /// returns commitment bases for the user-held attributes only, skipping `excluded` indices
+    pub fn get_bases_excluding(&self, excluded: &[usize]) -> Vec<E::G1Affine> {
+        let mut bases: Vec<E::G1Affine> = self
+            .y_g1
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !excluded.contains(i))
+            .map(|(_, y)| *y)
+            .collect();
+        bases.push(self.pp.g1.clone());
+        bases
+    }
+
     /// returns commitment bases g_1, g_2, ..., g
     pub fn get_bases_g2(&self) -> Vec<E::G2Affine> {
         let mut bases: Vec<E::G2Affine> = self.y_g2.clone();
         bases.push(self.pp.g2.clone());
         bases
     }
+
+    /// Name of this key in presentations and verifier key rings
+    pub fn key_id(&self) -> KeyId {
+        KeyId::of(self)
+    }
+
+    /// Y_i = g1^{y_i} and Y~_i = g2^{y_i} for each message, then X~ = g2^x
+    pub fn key_statement(&self) -> KeyStatement<E> {
+        let mut images_g2 = self.y_g2.clone();
+        images_g2.push(self.x_g2);
+        KeyStatement {
+            g1: self.pp.g1,
+            images_g1: self.y_g1.clone(),
+            g2: self.pp.g2,
+            images_g2,
+        }
+    }
+
+    /// Proves the key is well formed: the issuer knows x and every y_i, and Y_i and Y~_i share y_i
+    pub fn prove_key(&self, sk: &SecretKey<E>, rng: &mut impl Rng) -> KeyProof<E> {
+        let mut witnesses = Zeroizing::new(sk.yi.clone());
+        witnesses.push(sk.x);
+        KeyProof::prove(KEY_PROOF_DOMAIN, &self.key_statement(), &witnesses, rng)
+    }
+
+    /// Checks a proof from `prove_key`, holders run this before requesting a credential
+    pub fn verify_key(&self, proof: &KeyProof<E>, rng: &mut impl Rng) -> Result<(), KeyProofError> {
+        if self.y_g1.len() != self.pp.n || self.y_g2.len() != self.pp.n {
+            return Err(KeyProofError::LengthMismatch(0));
+        }
+        proof.verify(KEY_PROOF_DOMAIN, &self.key_statement(), rng)
+    }
+}
+
+/// Public key with the line coefficients of its G2 elements and of g2 computed once, for
+/// verifiers that check many signatures or presentations under the same key
+#[derive(Clone, Debug)]
+pub struct PreparedVerificationKey<E: Pairing> {
+    pub pk: PublicKey<E>,
+    pub g2: E::G2Prepared,
+    pub x_g2: E::G2Prepared,
+    pub y_g2: Vec<E::G2Prepared>,
+}
+
+impl<E: Pairing> PublicKey<E> {
+    pub fn prepare(&self) -> PreparedVerificationKey<E> {
+        PreparedVerificationKey {
+            pk: self.clone(),
+            g2: self.pp.g2.into(),
+            x_g2: self.x_g2.into(),
+            y_g2: self.y_g2.iter().map(|y| (*y).into()).collect(),
+        }
+    }
+}
+
+/// Fixed-base tables for the generators issuers and provers multiply on every call, built once
+/// with `PublicKey::precompute` and serializable so a server can load them at startup
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PublicKeyTables<E: Pairing> {
+    /// [Y_1, ..., Y_n, g1], the commitment bases in G1
+    pub bases_g1: FixedBaseMsm<E::G1>,
+    /// [Y~_1, ..., Y~_n, g2], the signature proof bases in G2
+    pub bases_g2: FixedBaseMsm<E::G2>,
+}
+
+impl<E: Pairing> PublicKeyTables<E> {
+    /// Table for g1, which is the last commitment base
+    pub fn g1(&self) -> &FixedBaseTable<E::G1> {
+        self.bases_g1.table(self.bases_g1.len() - 1)
+    }
+}
+
+impl<E: Pairing> PublicKey<E> {
+    pub fn precompute(&self) -> PublicKeyTables<E> {
+        PublicKeyTables {
+            bases_g1: FixedBaseMsm::new(&self.get_bases()),
+            bases_g2: FixedBaseMsm::new(&self.get_bases_g2()),
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
     // use crate::{gen_keys, PublicKey, SecretKey};
-    use ark_bls12_381::{Bls12_381, Fr};
     use ark_ec::pairing::Pairing;
     use ark_std::rand::Rng;
     use ark_std::test_rng;
 
-    #[test]
-    fn test_key_generation_basic() {
+    fn test_key_generation_basic<E: Pairing>() {
         // Initialize test environment
         let mut rng = test_rng();
         let n = 3; // Support for 3 messages
-        let context = Fr::rand(&mut rng);
+        let context = E::ScalarField::rand(&mut rng);
 
         // Generate public parameters
-        let pp = PublicParams::<Bls12_381>::new(&n, &context, &mut rng);
+        let pp = PublicParams::<E>::new(&n, &context, &mut rng);
 
         // Generate a keypair
         let (sk, pk) = gen_keys(&pp, &mut rng);
@@ -102,9 +225,57 @@ mod test {
 
         // Verify secret and public keys are related correctly
         for i in 0..n {
-            let pairing1 = Bls12_381::pairing(pk.y_g1[i], pp.g2);
-            let pairing2 = Bls12_381::pairing(pp.g1, pk.y_g2[i]);
+            let pairing1 = E::pairing(pk.y_g1[i], pp.g2);
+            let pairing2 = E::pairing(pp.g1, pk.y_g2[i]);
             assert_eq!(pairing1, pairing2, "Pairing consistency check failed");
         }
     }
+
+    fn test_public_key_tables<E: Pairing>() {
+        let mut rng = test_rng();
+        let n = 3;
+        let context = E::ScalarField::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&n, &context, &mut rng);
+        let (_, pk) = gen_keys(&pp, &mut rng);
+
+        let tables = pk.precompute();
+        assert_eq!(tables.bases_g1.bases(), pk.get_bases());
+        assert_eq!(tables.bases_g2.bases(), pk.get_bases_g2());
+        assert_eq!(tables.g1().base(), pp.g1);
+
+        // a server loading tables it wrote itself can skip the point checks
+        let mut bytes = Vec::new();
+        tables.serialize_uncompressed(&mut bytes).unwrap();
+        let loaded = PublicKeyTables::<E>::deserialize_uncompressed_unchecked(&bytes[..]).unwrap();
+        let scalar = E::ScalarField::rand(&mut rng);
+        assert_eq!(loaded.g1().mul(&scalar), pp.g1 * scalar);
+        assert_eq!(loaded.bases_g2, tables.bases_g2);
+    }
+
+    fn test_key_proof<E: Pairing>() {
+        let mut rng = test_rng();
+        let context = E::ScalarField::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&3, &context, &mut rng);
+        let (sk, pk, proof) = gen_keys_with_proof(&pp, &mut rng);
+        assert!(pk.verify_key(&proof, &mut rng).is_ok());
+
+        // Y_1 with an exponent other than Y~_1's, e.g. to recognise one user's commitments
+        let mut tagged = pk.clone();
+        tagged.y_g1[1] = (tagged.y_g1[1] + pp.g1).into_affine();
+        let proof = tagged.prove_key(&sk, &mut rng);
+        assert_eq!(
+            tagged.verify_key(&proof, &mut rng),
+            Err(KeyProofError::InvalidProof(0))
+        );
+
+        // a proof for one key does not carry over to another
+        let (_, other_pk) = gen_keys(&pp, &mut rng);
+        assert!(other_pk.verify_key(&proof, &mut rng).is_err());
+    }
+
+    utils::curve_tests!(
+        test_key_generation_basic,
+        test_public_key_tables,
+        test_key_proof
+    );
 }
@@ -1,7 +1,9 @@
 pub mod anon_cred;
 pub mod commitment;
+pub mod group_signature;
 pub mod keygen;
 pub mod proofsystem;
 pub mod publicparams;
 pub mod signature;
 pub mod utils;
+pub mod wire;
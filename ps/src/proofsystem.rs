@@ -1,15 +1,30 @@
-use crate::keygen::PublicKey;
+use crate::keygen::{PreparedVerificationKey, PublicKey, PublicKeyTables};
 use crate::publicparams::PublicParams;
 use crate::utils::PSUtils;
 use crate::{commitment::Commitment, signature::PSSignature};
 use ark_ec::pairing::{Pairing, PairingOutput};
-use ark_ec::{AffineRepr, CurveGroup};
+use ark_ec::{AffineRepr, CurveGroup, VariableBaseMSM};
 use ark_ff::UniformRand;
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::ops::Neg;
+use ark_std::rand::Rng;
+use ark_std::sync::Mutex;
+use schnorr::batch::{BatchError, SchnorrBatch, SchnorrClaim};
+use schnorr::key_proof::KeyProofError;
 use schnorr::schnorr::{SchnorrCommitment, SchnorrProtocol, SchnorrResponses};
-use schnorr::schnorr_pairing::SchnorrProtocolPairing;
+use schnorr::schnorr_pairing::{SchnorrCommitmentPairing, SchnorrProtocolPairing};
+use schnorr::verifiable_encryption::{
+    AuditorPublicKey, Ciphertext, EncryptionProof, VerifiableEncryption,
+};
 use thiserror::Error;
+use utils::context::PresentationContext;
+use utils::disclosure::{Disclosure, DisclosureError};
+use utils::hash::HashUtil;
+use utils::key_ring::KeyRingError;
+use utils::pairing::{batch_randomizer, bisect_failures, PairingCheck};
+use utils::schema::SchemaError;
+use utils::update::{apply_updates, unlinked_indices, updates_to_bytes, AttributeUpdate};
+use zeroize::Zeroizing;
 
 #[derive(Error, Debug)]
 pub enum ProofError {
@@ -21,15 +36,28 @@ pub enum ProofError {
     InvalidEqualityIndex,
     #[error("Mismatched commitment lengths")]
     MismatchedCommitmentLengths,
+    #[error("Invalid attribute index {0}")]
+    InvalidAttributeIndex(usize),
     #[error("Serialization error: {0}")]
     SerializationError(#[from] ark_serialize::SerializationError),
+    #[error("Schema error: {0}")]
+    SchemaError(#[from] SchemaError),
+    #[error("Issuer secret key not available")]
+    MissingSecretKey,
+    #[error("Batch verification failed: {0}")]
+    BatchError(#[from] BatchError),
+    #[error("Issuer key is not well formed: {0}")]
+    KeyProofError(#[from] KeyProofError),
+    #[error("Issuer key not accepted: {0}")]
+    KeyRingError(#[from] KeyRingError),
+    #[error("Disclosure error: {0}")]
+    DisclosureError(#[from] DisclosureError),
 }
 
 #[derive(CanonicalSerialize, CanonicalDeserialize, Debug, Clone)]
 pub struct CommitmentProof<E: Pairing> {
     pub statement: E::G1Affine,
     pub schnorr_commitment: SchnorrCommitment<E::G1Affine>,
-    pub bases: Vec<E::G1Affine>,
     pub challenge: E::ScalarField,
     pub responses: Vec<E::ScalarField>,
 }
@@ -41,22 +69,44 @@ impl CommitmentProofs {
     ///
     /// # Arguments
     /// * `commitment` - The commitment to prove knowledge of
+    /// * `rng` - Source of the Schnorr blindings
     ///
     /// # Returns
     /// A serialized proof
     pub fn pok_commitment_prove<E: Pairing>(
         commitment: &Commitment<E>,
+        rng: &mut impl Rng,
     ) -> Result<Vec<u8>, ProofError> {
-        let mut rng = ark_std::test_rng();
         let bases = commitment.get_bases();
-        let exponents = commitment.get_exponents();
-        let schnorr_commitment = SchnorrProtocol::commit(&bases, &mut rng);
-        let challenge = E::ScalarField::rand(&mut rng);
-        let responses = SchnorrProtocol::prove(&schnorr_commitment, &exponents, &challenge);
+        let schnorr_commitment = SchnorrProtocol::commit(&bases, rng);
+        Self::finish_commitment_proof(commitment, schnorr_commitment)
+    }
+
+    /// `pok_commitment_prove` with the Schnorr commitment taken from the key's G1 tables
+    pub fn pok_commitment_prove_with_tables<E: Pairing>(
+        commitment: &Commitment<E>,
+        tables: &PublicKeyTables<E>,
+        rng: &mut impl Rng,
+    ) -> Result<Vec<u8>, ProofError> {
+        let schnorr_commitment = SchnorrProtocol::commit_with_table(&tables.bases_g1, rng);
+        Self::finish_commitment_proof(commitment, schnorr_commitment)
+    }
+
+    fn finish_commitment_proof<E: Pairing>(
+        commitment: &Commitment<E>,
+        mut schnorr_commitment: SchnorrCommitment<E::G1Affine>,
+    ) -> Result<Vec<u8>, ProofError> {
+        let exponents = Zeroizing::new(commitment.get_exponents());
+        let challenge = commitment_challenge::<E>(
+            &commitment.get_bases(),
+            &commitment.commitment,
+            &schnorr_commitment.commited_blindings,
+        )?;
+        let responses =
+            SchnorrProtocol::prove_and_erase(&mut schnorr_commitment, &exponents, &challenge);
         let proof: CommitmentProof<E> = CommitmentProof {
             statement: commitment.commitment,
             schnorr_commitment,
-            bases,
             challenge,
             responses: responses.0,
         };
@@ -70,27 +120,187 @@ impl CommitmentProofs {
     /// Verify a proof of knowledge of a commitment in G1
     ///
     /// # Arguments
+    /// * `pk` - Issuer's public key, the proof is checked against its commitment bases
     /// * `serialized_proof` - The serialized proof to verify
     ///
     /// # Returns
     /// `true` if the proof is valid, `false` otherwise
-    pub fn pok_commitment_verify<E: Pairing>(serialized_proof: &[u8]) -> Result<bool, ProofError> {
+    pub fn pok_commitment_verify<E: Pairing>(
+        pk: &PublicKey<E>,
+        serialized_proof: &[u8],
+    ) -> Result<bool, ProofError> {
         let proof: CommitmentProof<E> =
             CanonicalDeserialize::deserialize_compressed(serialized_proof)?;
 
-        // Verify using Schnorr protocol
-        let is_valid = SchnorrProtocol::verify(
-            &proof.bases,
+        verify_commitment_proof(&pk.get_bases(), &proof)
+    }
+
+    /// Verify many proofs of knowledge of a commitment in G1 together, for bulk issuance
+    ///
+    /// # Arguments
+    /// * `pk` - Issuer's public key, every proof is checked against its commitment bases
+    /// * `serialized_proofs` - The serialized proofs to verify
+    /// * `rng` - Source of the batch randomizers
+    ///
+    /// # Returns
+    /// The deserialized proofs, or the index of the first invalid one
+    pub fn pok_commitment_batch_verify<E: Pairing, R: Rng>(
+        pk: &PublicKey<E>,
+        serialized_proofs: &[Vec<u8>],
+        rng: &mut R,
+    ) -> Result<Vec<CommitmentProof<E>>, ProofError> {
+        let proofs = serialized_proofs
+            .iter()
+            .map(|bytes| CommitmentProof::<E>::deserialize_compressed(&bytes[..]))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let bases = pk.get_bases();
+        let mut batch = SchnorrBatch::<E>::new();
+        for (i, proof) in proofs.iter().enumerate() {
+            let challenge = commitment_challenge::<E>(
+                &bases,
+                &proof.statement,
+                &proof.schnorr_commitment.commited_blindings,
+            )?;
+            if challenge != proof.challenge {
+                return Err(BatchError::InvalidProof(i).into());
+            }
+            batch.add_g1(SchnorrClaim {
+                bases: bases.clone(),
+                statement: proof.statement,
+                commitment: proof.schnorr_commitment.commited_blindings,
+                challenge: proof.challenge,
+                responses: proof.responses.clone(),
+            });
+        }
+        batch.verify(rng)?;
+
+        Ok(proofs)
+    }
+
+    /// Generate a proof of knowledge of a commitment to the user-held messages only,
+    /// for issuance where the issuer sets the messages at `issuer_indices`
+    ///
+    /// # Arguments
+    /// * `pp` - Public parameters
+    /// * `pk` - Issuer's public key
+    /// * `messages` - Full message vector, entries at `issuer_indices` are ignored
+    /// * `t` - Blinding factor
+    /// * `issuer_indices` - Indices the issuer fills in before signing
+    /// * `rng` - Source of the Schnorr blindings
+    ///
+    /// # Returns
+    /// A serialized proof
+    pub fn pok_partial_commitment_prove<E: Pairing>(
+        pp: &PublicParams<E>,
+        pk: &PublicKey<E>,
+        messages: &[E::ScalarField],
+        t: &E::ScalarField,
+        issuer_indices: &[usize],
+        rng: &mut impl Rng,
+    ) -> Result<Vec<u8>, ProofError> {
+        check_attribute_indices(pp.n, issuer_indices)?;
+        let bases = pk.get_bases_excluding(issuer_indices);
+        let mut exponents: Zeroizing<Vec<E::ScalarField>> = Zeroizing::new(
+            messages
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !issuer_indices.contains(i))
+                .map(|(_, m)| *m)
+                .collect(),
+        );
+        exponents.push(*t);
+
+        let statement = E::G1::msm_unchecked(&bases, &exponents).into_affine();
+        let mut schnorr_commitment = SchnorrProtocol::commit(&bases, rng);
+        let challenge =
+            commitment_challenge::<E>(&bases, &statement, &schnorr_commitment.commited_blindings)?;
+        let responses =
+            SchnorrProtocol::prove_and_erase(&mut schnorr_commitment, &exponents, &challenge);
+        let proof: CommitmentProof<E> = CommitmentProof {
+            statement,
+            schnorr_commitment,
+            challenge,
+            responses: responses.0,
+        };
+
+        let mut serialized_proof = Vec::new();
+        proof.serialize_compressed(&mut serialized_proof)?;
+
+        Ok(serialized_proof)
+    }
+
+    /// Verify a proof from `pok_partial_commitment_prove` over the bases that skip `issuer_indices`
+    ///
+    /// # Returns
+    /// The proven commitment if the proof is valid, `None` otherwise
+    pub fn pok_partial_commitment_verify<E: Pairing>(
+        pp: &PublicParams<E>,
+        pk: &PublicKey<E>,
+        issuer_indices: &[usize],
+        serialized_proof: &[u8],
+    ) -> Result<Option<E::G1Affine>, ProofError> {
+        check_attribute_indices(pp.n, issuer_indices)?;
+        let proof: CommitmentProof<E> =
+            CanonicalDeserialize::deserialize_compressed(serialized_proof)?;
+
+        // a proof over the full bases would let the user fix the issuer's attributes
+        let is_valid = verify_commitment_proof(&pk.get_bases_excluding(issuer_indices), &proof)?;
+
+        Ok(is_valid.then_some(proof.statement))
+    }
+}
+
+/// Fiat-Shamir challenge of a commitment proof, over the issuer's bases, the statement and T
+fn commitment_challenge<E: Pairing>(
+    bases: &[E::G1Affine],
+    statement: &E::G1Affine,
+    schnorr_commitment: &E::G1Affine,
+) -> Result<E::ScalarField, ProofError> {
+    let mut transcript = b"ps-commitment-proof".to_vec();
+    bases.serialize_compressed(&mut transcript)?;
+    statement.serialize_compressed(&mut transcript)?;
+    schnorr_commitment.serialize_compressed(&mut transcript)?;
+    Ok(HashUtil::<E::ScalarField, E::G1Affine>::hash_to_field(
+        &transcript,
+    ))
+}
+
+// the challenge is recomputed, a prover choosing it could answer for any statement. Responses
+// are checked against the issuer's bases, msm_unchecked would silently truncate
+fn verify_commitment_proof<E: Pairing>(
+    bases: &[E::G1Affine],
+    proof: &CommitmentProof<E>,
+) -> Result<bool, ProofError> {
+    let challenge = commitment_challenge::<E>(
+        bases,
+        &proof.statement,
+        &proof.schnorr_commitment.commited_blindings,
+    )?;
+    Ok(challenge == proof.challenge
+        && proof.responses.len() == bases.len()
+        && SchnorrProtocol::verify(
+            bases,
             &proof.statement,
             &proof.schnorr_commitment,
             &SchnorrResponses(proof.responses.clone()),
             &proof.challenge,
-        );
+        ))
+}
 
-        Ok(is_valid)
+/// issuer attribute indices must be in range and distinct
+pub fn check_attribute_indices(n: usize, indices: &[usize]) -> Result<(), ProofError> {
+    for (k, &i) in indices.iter().enumerate() {
+        if i >= n || indices[..k].contains(&i) {
+            return Err(ProofError::InvalidAttributeIndex(i));
+        }
     }
+    Ok(())
 }
 
+/// A serialized signature proof with its disclosure and the context it was made for
+pub type BatchPresentation<'a, F> = (&'a [u8], &'a Disclosure<F>, &'a PresentationContext);
+
 #[derive(CanonicalSerialize, CanonicalDeserialize)]
 pub struct SignatureProof<E: Pairing> {
     pub randomized_signature: PSSignature<E>,
@@ -99,20 +309,71 @@ pub struct SignatureProof<E: Pairing> {
     pub responses: Vec<E::ScalarField>,
 }
 
+/// Signature proof together with an escrow ciphertext of one hidden message
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct EscrowedSignatureProof<E: Pairing> {
+    pub signature_proof: SignatureProof<E>,
+    pub ciphertext: Ciphertext<E::G1Affine>,
+    pub encryption_proof: EncryptionProof<E::G1Affine>,
+}
+
+/// Show proof of an old credential linked to a commitment to the updated attributes under the new key.
+/// Linked attributes reuse the old proof's responses, only the new blinding factor has its own response.
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct ReissuanceProof<E: Pairing> {
+    pub signature_proof: SignatureProof<E>,
+    pub commitment: E::G1Affine,
+    pub commitment_blindings: E::G1Affine,
+    pub t_response: E::ScalarField,
+}
+
+/// σ' and the GT commitment to the blinded exponents. The exponents are the witness, so the
+/// struct is neither `Clone` nor serializable
+pub struct PreparedSignatureProof<E: Pairing> {
+    sigma_prime: PSSignature<E>,
+    schnorr_commitment_pairing: SchnorrCommitmentPairing<E>,
+    exponents: Zeroizing<Vec<E::ScalarField>>,
+}
+
+impl<E: Pairing> PreparedSignatureProof<E> {
+    /// Hashes the context and the messages at `disclosed` into the challenge and computes the
+    /// responses. The disclosure carries the revealed messages and their blindings
+    pub fn finish(
+        self,
+        context: &PresentationContext,
+        disclosed: &[usize],
+    ) -> Result<(Vec<u8>, Disclosure<E::ScalarField>), ProofError> {
+        let n = self.exponents.len() - 1;
+        let disclosure = Disclosure::new(
+            disclosed,
+            &self.exponents[..n],
+            &self.schnorr_commitment_pairing.blindings[..n],
+        )?;
+        let proof = SignatureProofs::finish_signature_proof(
+            self.sigma_prime,
+            self.schnorr_commitment_pairing,
+            &self.exponents,
+            &disclosure,
+            context,
+        );
+        Ok((proof, disclosure))
+    }
+}
+
 pub struct SignatureProofs;
 impl SignatureProofs {
-    ///
-    pub fn pok_signature<E: Pairing>(
+    /// Proof of knowledge of a signature, the challenge is bound to the verifier's presentation context
+    pub fn pok_signature<E: Pairing, R: Rng>(
         pp: &PublicParams<E>,
         pk: &PublicKey<E>,
         messages: &Vec<E::ScalarField>,
         unblind_signature: &PSSignature<E>,
+        context: &PresentationContext,
+        rng: &mut R,
     ) -> Vec<u8> {
-        let mut rng = ark_std::test_rng();
-        let r = E::ScalarField::rand(&mut rng);
-        let t = E::ScalarField::rand(&mut rng);
+        let r = E::ScalarField::rand(rng);
+        let t = E::ScalarField::rand(rng);
         let sigma_prime = unblind_signature.rerandomize(&r, &t);
-        let challenge = E::ScalarField::rand(&mut rng);
 
         let exponents = PSUtils::add_scalar_to_end_of_vector::<E>(messages, &t);
         let base_length = pp.n + 1;
@@ -121,13 +382,76 @@ impl SignatureProofs {
         let bases_g2 = pk.get_bases_g2();
 
         let schnorr_commitment_pairing =
-            SchnorrProtocolPairing::commit::<E>(&bases_g1, &bases_g2, &mut rng);
+            SchnorrProtocolPairing::commit::<E>(&bases_g1, &bases_g2, rng);
+        Self::finish_signature_proof(
+            sigma_prime,
+            schnorr_commitment_pairing,
+            &exponents,
+            &Disclosure::default(),
+            context,
+        )
+    }
+
+    /// `pok_signature` with the pairing commitment taken from the key's G2 tables
+    pub fn pok_signature_with_tables<E: Pairing, R: Rng>(
+        tables: &PublicKeyTables<E>,
+        messages: &[E::ScalarField],
+        unblind_signature: &PSSignature<E>,
+        context: &PresentationContext,
+        rng: &mut R,
+    ) -> Vec<u8> {
+        let prepared = Self::prepare_signature_proof(tables, messages, unblind_signature, rng);
+        Self::finish_signature_proof(
+            prepared.sigma_prime,
+            prepared.schnorr_commitment_pairing,
+            &prepared.exponents,
+            &Disclosure::default(),
+            context,
+        )
+    }
+
+    /// Everything in `pok_signature_with_tables` that does not depend on the verifier: the
+    /// randomized signature and the GT commitment. `PreparedSignatureProof::finish` adds the
+    /// challenge and responses once the context is known
+    pub fn prepare_signature_proof<E: Pairing, R: Rng>(
+        tables: &PublicKeyTables<E>,
+        messages: &[E::ScalarField],
+        unblind_signature: &PSSignature<E>,
+        rng: &mut R,
+    ) -> PreparedSignatureProof<E> {
+        let r = E::ScalarField::rand(rng);
+        let t = E::ScalarField::rand(rng);
+        let sigma_prime = unblind_signature.rerandomize(&r, &t);
+        let exponents = Zeroizing::new(PSUtils::add_scalar_to_end_of_vector::<E>(messages, &t));
+
+        let blindings: Vec<E::ScalarField> = (0..tables.bases_g2.len())
+            .map(|_| E::ScalarField::rand(rng))
+            .collect();
+        // every G1 base is sigma1', so prod e(sigma1', B_i)^rho_i is one pairing with the G2 msm
+        let schnorr_commitment = E::pairing(sigma_prime.sigma1, tables.bases_g2.msm(&blindings));
+        PreparedSignatureProof {
+            sigma_prime,
+            schnorr_commitment_pairing: SchnorrCommitmentPairing {
+                blindings,
+                schnorr_commitment,
+            },
+            exponents,
+        }
+    }
+
+    fn finish_signature_proof<E: Pairing>(
+        sigma_prime: PSSignature<E>,
+        schnorr_commitment_pairing: SchnorrCommitmentPairing<E>,
+        exponents: &[E::ScalarField],
+        disclosure: &Disclosure<E::ScalarField>,
+        context: &PresentationContext,
+    ) -> Vec<u8> {
         let schnorr_commitment_gt = schnorr_commitment_pairing.schnorr_commitment;
+        let challenge =
+            Self::context_challenge::<E>(&sigma_prime, &schnorr_commitment_gt, disclosure, context);
 
         let responses =
-            SchnorrProtocolPairing::prove(&schnorr_commitment_pairing, &exponents, &challenge);
-        // let sigma_prime2 = sigma_prime.clone();
-        // let responses2 = responses.clone();
+            SchnorrProtocolPairing::prove(&schnorr_commitment_pairing, exponents, &challenge);
 
         let proof = SignatureProof {
             randomized_signature: sigma_prime,
@@ -141,16 +465,192 @@ impl SignatureProofs {
         serialized_proof
     }
 
+    /// Verifies a signature proof produced for `context` that reveals `disclosure`, a proof made
+    /// for any other context fails
     pub fn verify_knowledge<E: Pairing>(
         pp: &PublicParams<E>,
         pk: &PublicKey<E>,
         serialized_proof: &[u8],
+        disclosure: &Disclosure<E::ScalarField>,
+        context: &PresentationContext,
+    ) -> bool {
+        Self::verify_knowledge_prepared(pp, &pk.prepare(), serialized_proof, disclosure, context)
+    }
+
+    /// `verify_knowledge` with a prepared key, for verifiers that check many presentations
+    pub fn verify_knowledge_prepared<E: Pairing>(
+        pp: &PublicParams<E>,
+        pvk: &PreparedVerificationKey<E>,
+        serialized_proof: &[u8],
+        disclosure: &Disclosure<E::ScalarField>,
+        context: &PresentationContext,
     ) -> bool {
         let proof: SignatureProof<E> =
-            CanonicalDeserialize::deserialize_compressed(serialized_proof).unwrap();
+            match CanonicalDeserialize::deserialize_compressed(serialized_proof) {
+                Ok(proof) => proof,
+                Err(_) => return false,
+            };
 
-        let computed_signature_commitment = PSUtils::compute_gt::<E>(
-            &[
+        let challenge = Self::context_challenge::<E>(
+            &proof.randomized_signature,
+            &proof.schnorr_commitment,
+            disclosure,
+            context,
+        );
+        if challenge != proof.challenge || !Self::check_disclosure(pp, &proof, disclosure) {
+            return false;
+        }
+
+        Self::verify_signature_proof(pp, pvk, &proof)
+    }
+
+    /// Verifies many signature proofs under one key, each for its own context, and returns the
+    /// indices of the proofs that fail. Valid batches cost one pairing check in total, failures
+    /// are isolated by bisecting the batch.
+    pub fn verify_knowledge_batch<E: Pairing, R: Rng + Send>(
+        pp: &PublicParams<E>,
+        pvk: &PreparedVerificationKey<E>,
+        presentations: &[BatchPresentation<E::ScalarField>],
+        rng: &mut R,
+    ) -> Vec<usize> {
+        // malformed proofs and proofs for another context fail before any group operation
+        let mut failures = Vec::new();
+        let mut proofs: Vec<(usize, SignatureProof<E>)> = Vec::with_capacity(presentations.len());
+        for (i, (serialized_proof, disclosure, context)) in presentations.iter().enumerate() {
+            let proof = match SignatureProof::<E>::deserialize_compressed(*serialized_proof) {
+                Ok(proof) => proof,
+                Err(_) => {
+                    failures.push(i);
+                    continue;
+                }
+            };
+            let challenge = Self::context_challenge::<E>(
+                &proof.randomized_signature,
+                &proof.schnorr_commitment,
+                disclosure,
+                context,
+            );
+            if challenge != proof.challenge || !Self::check_disclosure(pp, &proof, disclosure) {
+                failures.push(i);
+                continue;
+            }
+            proofs.push((i, proof));
+        }
+
+        let positions: Vec<usize> = (0..proofs.len()).collect();
+        let invalid = bisect_failures(&positions, &mut |subset: &[usize]| {
+            let subset: Vec<&SignatureProof<E>> = subset.iter().map(|k| &proofs[*k].1).collect();
+            Self::check_signature_proofs(pp, pvk, &subset, rng)
+        });
+        failures.extend(invalid.iter().map(|k| proofs[*k].0));
+        failures.sort_unstable();
+        failures
+    }
+
+    /// One pairing check for the Schnorr equations of many proofs. The G2 bases are shared by
+    /// every proof, so each contributes to their G1 sides through one randomized MSM per base:
+    /// ∏_j e(Σ_k ρ_k z_kj σ1_k, Y_j) · e(Σ_k ρ_k (z_kt σ1_k - c_k σ2_k), g2) · e(Σ_k ρ_k c_k σ1_k, X)
+    /// = ∏_k T_k^{ρ_k}
+    fn check_signature_proofs<E: Pairing, R: Rng + Send>(
+        pp: &PublicParams<E>,
+        pvk: &PreparedVerificationKey<E>,
+        proofs: &[&SignatureProof<E>],
+        rng: &mut R,
+    ) -> bool {
+        if proofs.is_empty() {
+            return true;
+        }
+
+        let rhos: Vec<E::ScalarField> = proofs.iter().map(|_| batch_randomizer(rng)).collect();
+        let sigma1s: Vec<E::G1Affine> = proofs
+            .iter()
+            .map(|proof| proof.randomized_signature.sigma1)
+            .collect();
+
+        let mut g1_sides: Vec<E::G1> = (0..pp.n)
+            .map(|j| {
+                let scalars: Vec<E::ScalarField> = proofs
+                    .iter()
+                    .zip(rhos.iter())
+                    .map(|(proof, rho)| *rho * proof.responses[j])
+                    .collect();
+                E::G1::msm_unchecked(&sigma1s, &scalars)
+            })
+            .collect();
+
+        let mut g2_bases = sigma1s.clone();
+        let mut g2_scalars: Vec<E::ScalarField> = proofs
+            .iter()
+            .zip(rhos.iter())
+            .map(|(proof, rho)| *rho * proof.responses[pp.n])
+            .collect();
+        for (proof, rho) in proofs.iter().zip(rhos.iter()) {
+            g2_bases.push(proof.randomized_signature.sigma2);
+            g2_scalars.push((*rho * proof.challenge).neg());
+        }
+        g1_sides.push(E::G1::msm_unchecked(&g2_bases, &g2_scalars));
+
+        let x_scalars: Vec<E::ScalarField> = proofs
+            .iter()
+            .zip(rhos.iter())
+            .map(|(proof, rho)| *rho * proof.challenge)
+            .collect();
+        g1_sides.push(E::G1::msm_unchecked(&sigma1s, &x_scalars));
+
+        let g1_sides = E::G1::normalize_batch(&g1_sides);
+        let g2_sides: Vec<&E::G2Prepared> = pvk.y_g2.iter().chain([&pvk.g2, &pvk.x_g2]).collect();
+
+        let commitments: Vec<PairingOutput<E>> = proofs
+            .iter()
+            .map(|proof| proof.schnorr_commitment)
+            .collect();
+        let target = PairingOutput::<E>::msm_unchecked(&commitments, &rhos);
+
+        let pairs: Vec<(&E::G1Affine, &E::G2Prepared)> = g1_sides.iter().zip(g2_sides).collect();
+        PairingCheck::<E>::rand_prepared(&Mutex::new(&mut *rng), &pairs, &target.0).verify()
+    }
+
+    fn context_challenge<E: Pairing>(
+        randomized_signature: &PSSignature<E>,
+        schnorr_commitment: &PairingOutput<E>,
+        disclosure: &Disclosure<E::ScalarField>,
+        context: &PresentationContext,
+    ) -> E::ScalarField {
+        let mut transcript = Vec::new();
+        randomized_signature
+            .serialize_compressed(&mut transcript)
+            .unwrap();
+        schnorr_commitment
+            .serialize_compressed(&mut transcript)
+            .unwrap();
+        transcript.extend(disclosure.to_bytes());
+        context.challenge(b"ps-show", &transcript)
+    }
+
+    // the message responses of disclosed messages must be ρ + c·m, the response for t is never
+    // disclosed
+    fn check_disclosure<E: Pairing>(
+        pp: &PublicParams<E>,
+        proof: &SignatureProof<E>,
+        disclosure: &Disclosure<E::ScalarField>,
+    ) -> bool {
+        proof.responses.len() == pp.n + 1
+            && disclosure.verify(&proof.responses[..pp.n], &proof.challenge)
+    }
+
+    /// checks the Schnorr equations of a signature proof for the challenge it carries
+    fn verify_signature_proof<E: Pairing>(
+        pp: &PublicParams<E>,
+        pvk: &PreparedVerificationKey<E>,
+        proof: &SignatureProof<E>,
+    ) -> bool {
+        let base_length = pp.n + 1;
+        if proof.responses.len() != base_length || pvk.y_g2.len() != pp.n {
+            return false;
+        }
+
+        let computed_signature_commitment = E::multi_pairing(
+            [
                 proof.randomized_signature.sigma2,
                 proof
                     .randomized_signature
@@ -159,33 +659,332 @@ impl SignatureProofs {
                     .neg()
                     .into_affine(),
             ],
-            &[pp.g2, pk.x_g2],
+            [pvk.g2.clone(), pvk.x_g2.clone()],
         );
 
-        // 2. Prepare bases for verification
-        let base_length = pp.n + 1;
+        // Prepare bases for verification
         let bases_g1 =
             PSUtils::copy_point_to_length::<E>(proof.randomized_signature.sigma1, &base_length);
-        let mut bases_g2 = pk.y_g2.clone(); // [Y_{21}, ..., Y_{2n}]
-        bases_g2.push(pp.g2); // Append g2 for t
+        let mut bases_g2 = pvk.y_g2.clone(); // [Y_{21}, ..., Y_{2n}]
+        bases_g2.push(pvk.g2.clone()); // Append g2 for t
 
-        // 3. Verify the Schnorr proof
-        let is_valid = SchnorrProtocolPairing::verify(
+        // Verify the Schnorr proof
+        SchnorrProtocolPairing::verify_prepared(
             &computed_signature_commitment,
             &proof.schnorr_commitment,
             &proof.challenge,
             &bases_g1,
             &bases_g2,
             &proof.responses,
+        )
+    }
+
+    /// Proof of knowledge of a signature where message `index` is also verifiably encrypted to an auditor.
+    /// The challenge is hashed over both proofs and the label, and the blinding of m_index is shared.
+    pub fn pok_signature_with_escrow<E: Pairing, R: Rng>(
+        pp: &PublicParams<E>,
+        pk: &PublicKey<E>,
+        messages: &[E::ScalarField],
+        unblind_signature: &PSSignature<E>,
+        auditor_pk: &AuditorPublicKey<E::G1Affine>,
+        index: usize,
+        label: &[u8],
+        rng: &mut R,
+    ) -> Result<Vec<u8>, ProofError> {
+        if index >= messages.len() {
+            return Err(ProofError::InvalidEqualityIndex);
+        }
+        let r = E::ScalarField::rand(rng);
+        let t = E::ScalarField::rand(rng);
+        let sigma_prime = unblind_signature.rerandomize(&r, &t);
+
+        let exponents = PSUtils::add_scalar_to_end_of_vector::<E>(messages, &t);
+        let base_length = pp.n + 1;
+        let bases_g1 = PSUtils::copy_point_to_length::<E>(sigma_prime.sigma1, &base_length);
+        let bases_g2 = pk.get_bases_g2();
+
+        let schnorr_commitment_pairing =
+            SchnorrProtocolPairing::commit::<E>(&bases_g1, &bases_g2, rng);
+        let (ciphertext, enc_r) =
+            VerifiableEncryption::encrypt(auditor_pk, &messages[index], label, rng);
+        let enc_commitment = VerifiableEncryption::commit(
+            auditor_pk,
+            &schnorr_commitment_pairing.blindings[index],
+            rng,
         );
 
-        assert_eq!(
-            proof.responses.len(),
-            base_length,
-            "responses and base length don't match"
+        let challenge = Self::escrow_challenge::<E>(
+            &sigma_prime,
+            &schnorr_commitment_pairing.schnorr_commitment,
+            &ciphertext,
+            label,
+            &[enc_commitment.a1, enc_commitment.a2, enc_commitment.a3],
+        )?;
+
+        let responses =
+            SchnorrProtocolPairing::prove(&schnorr_commitment_pairing, &exponents, &challenge);
+        let encryption_proof =
+            VerifiableEncryption::prove(&enc_commitment, &enc_r, &messages[index], &challenge);
+
+        let proof = EscrowedSignatureProof {
+            signature_proof: SignatureProof {
+                randomized_signature: sigma_prime,
+                schnorr_commitment: schnorr_commitment_pairing.schnorr_commitment,
+                challenge,
+                responses: responses.0,
+            },
+            ciphertext,
+            encryption_proof,
+        };
+
+        let mut serialized_proof = Vec::new();
+        proof.serialize_compressed(&mut serialized_proof)?;
+        Ok(serialized_proof)
+    }
+
+    pub fn verify_knowledge_with_escrow<E: Pairing>(
+        pp: &PublicParams<E>,
+        pk: &PublicKey<E>,
+        auditor_pk: &AuditorPublicKey<E::G1Affine>,
+        index: usize,
+        label: &[u8],
+        serialized_proof: &[u8],
+    ) -> Result<bool, ProofError> {
+        Self::verify_knowledge_with_escrow_prepared(
+            pp,
+            &pk.prepare(),
+            auditor_pk,
+            index,
+            label,
+            serialized_proof,
+        )
+    }
+
+    /// `verify_knowledge_with_escrow` with a prepared key
+    pub fn verify_knowledge_with_escrow_prepared<E: Pairing>(
+        pp: &PublicParams<E>,
+        pvk: &PreparedVerificationKey<E>,
+        auditor_pk: &AuditorPublicKey<E::G1Affine>,
+        index: usize,
+        label: &[u8],
+        serialized_proof: &[u8],
+    ) -> Result<bool, ProofError> {
+        let proof: EscrowedSignatureProof<E> =
+            CanonicalDeserialize::deserialize_compressed(serialized_proof)?;
+        let signature_proof = &proof.signature_proof;
+        if index >= pp.n || signature_proof.responses.len() != pp.n + 1 {
+            return Err(ProofError::InvalidEqualityIndex);
+        }
+
+        let challenge = Self::escrow_challenge::<E>(
+            &signature_proof.randomized_signature,
+            &signature_proof.schnorr_commitment,
+            &proof.ciphertext,
+            label,
+            &[
+                proof.encryption_proof.a1,
+                proof.encryption_proof.a2,
+                proof.encryption_proof.a3,
+            ],
+        )?;
+        if challenge != signature_proof.challenge {
+            return Ok(false);
+        }
+
+        // the encrypted value and the signed message share a blinding, so their responses match
+        if proof.encryption_proof.z_m != signature_proof.responses[index] {
+            return Ok(false);
+        }
+
+        if !VerifiableEncryption::verify(
+            auditor_pk,
+            &proof.ciphertext,
+            label,
+            &proof.encryption_proof,
+            &challenge,
+        ) {
+            return Ok(false);
+        }
+
+        Ok(Self::verify_signature_proof(pp, pvk, signature_proof))
+    }
+
+    /// Proves knowledge of a signature on `messages` under the old key and that `new_commitment`, a
+    /// commitment under the new key with blinding `new_t`, holds the same messages except for `updates`.
+    /// Set updates are not linked, every other message shares its blinding across both proofs.
+    pub fn pok_reissuance<E: Pairing, R: Rng>(
+        old_pp: &PublicParams<E>,
+        old_pk: &PublicKey<E>,
+        pp: &PublicParams<E>,
+        pk: &PublicKey<E>,
+        messages: &[E::ScalarField],
+        unblind_signature: &PSSignature<E>,
+        updates: &[(usize, AttributeUpdate<E::ScalarField>)],
+        new_t: &E::ScalarField,
+        context: &PresentationContext,
+        rng: &mut R,
+    ) -> Result<Vec<u8>, ProofError> {
+        if old_pp.n != pp.n || messages.len() != pp.n {
+            return Err(ProofError::MismatchedCommitmentLengths);
+        }
+        let indices: Vec<usize> = updates.iter().map(|(i, _)| *i).collect();
+        check_attribute_indices(pp.n, &indices)?;
+
+        let r = E::ScalarField::rand(rng);
+        let t = E::ScalarField::rand(rng);
+        let sigma_prime = unblind_signature.rerandomize(&r, &t);
+        let exponents = PSUtils::add_scalar_to_end_of_vector::<E>(&messages.to_vec(), &t);
+        let base_length = old_pp.n + 1;
+        let bases_g1 = PSUtils::copy_point_to_length::<E>(sigma_prime.sigma1, &base_length);
+        let bases_g2 = old_pk.get_bases_g2();
+        let schnorr_commitment_pairing =
+            SchnorrProtocolPairing::commit::<E>(&bases_g1, &bases_g2, rng);
+
+        // C' = g^t' ∏ Y'_i^m'_i over the updated messages
+        let new_messages = apply_updates(messages, updates);
+        let commitment = Commitment::new(pp, pk, &new_messages, new_t).commitment;
+
+        // blindings of the linked messages are taken from the signature proof
+        let unlinked = unlinked_indices(updates);
+        let t_blinding = E::ScalarField::rand(rng);
+        let mut blindings: Vec<E::ScalarField> = schnorr_commitment_pairing.blindings[..pp.n]
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !unlinked.contains(i))
+            .map(|(_, b)| *b)
+            .collect();
+        blindings.push(t_blinding);
+        let commitment_blindings = SchnorrProtocol::commit_with_prepared_blindings(
+            &pk.get_bases_excluding(&unlinked),
+            &blindings,
+        )
+        .commited_blindings;
+
+        let challenge = Self::reissuance_challenge::<E>(
+            &sigma_prime,
+            &schnorr_commitment_pairing.schnorr_commitment,
+            &commitment,
+            &commitment_blindings,
+            updates,
+            context,
+        )?;
+
+        let responses =
+            SchnorrProtocolPairing::prove(&schnorr_commitment_pairing, &exponents, &challenge);
+
+        let proof = ReissuanceProof {
+            signature_proof: SignatureProof {
+                randomized_signature: sigma_prime,
+                schnorr_commitment: schnorr_commitment_pairing.schnorr_commitment,
+                challenge,
+                responses: responses.0,
+            },
+            commitment,
+            commitment_blindings,
+            t_response: t_blinding + challenge * new_t,
+        };
+
+        let mut serialized_proof = Vec::new();
+        proof.serialize_compressed(&mut serialized_proof)?;
+        Ok(serialized_proof)
+    }
+
+    /// Verifies a re-issuance proof for the declared `updates` and the issuer's `context`
+    ///
+    /// # Returns
+    /// The new commitment to blind sign if the proof is valid, `None` otherwise
+    pub fn verify_reissuance<E: Pairing>(
+        old_pp: &PublicParams<E>,
+        old_pk: &PublicKey<E>,
+        pp: &PublicParams<E>,
+        pk: &PublicKey<E>,
+        updates: &[(usize, AttributeUpdate<E::ScalarField>)],
+        context: &PresentationContext,
+        serialized_proof: &[u8],
+    ) -> Result<Option<E::G1Affine>, ProofError> {
+        if old_pp.n != pp.n {
+            return Err(ProofError::MismatchedCommitmentLengths);
+        }
+        let indices: Vec<usize> = updates.iter().map(|(i, _)| *i).collect();
+        check_attribute_indices(pp.n, &indices)?;
+        let proof: ReissuanceProof<E> =
+            CanonicalDeserialize::deserialize_compressed(serialized_proof)?;
+        let signature_proof = &proof.signature_proof;
+
+        let challenge = Self::reissuance_challenge::<E>(
+            &signature_proof.randomized_signature,
+            &signature_proof.schnorr_commitment,
+            &proof.commitment,
+            &proof.commitment_blindings,
+            updates,
+            context,
+        )?;
+        if challenge != signature_proof.challenge
+            || !Self::verify_signature_proof(old_pp, &old_pk.prepare(), signature_proof)
+        {
+            return Ok(None);
+        }
+
+        // C'' = C' · ∏ Y'_k^-v_k commits to the old linked messages and to zero at Set indices
+        let update_bases: Vec<E::G1Affine> = indices.iter().map(|i| pk.y_g1[*i]).collect();
+        let update_values: Vec<E::ScalarField> =
+            updates.iter().map(|(_, u)| u.public_value()).collect();
+        let statement = (proof.commitment.into_group()
+            - E::G1::msm_unchecked(&update_bases, &update_values))
+        .into_affine();
+
+        let unlinked = unlinked_indices(updates);
+        let mut responses: Vec<E::ScalarField> = signature_proof.responses[..pp.n]
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !unlinked.contains(i))
+            .map(|(_, z)| *z)
+            .collect();
+        responses.push(proof.t_response);
+
+        let is_valid = SchnorrProtocol::verify_schnorr(
+            &pk.get_bases_excluding(&unlinked),
+            &statement,
+            &proof.commitment_blindings,
+            &responses,
+            &challenge,
         );
 
-        is_valid
+        Ok(is_valid.then_some(proof.commitment))
+    }
+
+    fn reissuance_challenge<E: Pairing>(
+        randomized_signature: &PSSignature<E>,
+        schnorr_commitment: &PairingOutput<E>,
+        commitment: &E::G1Affine,
+        commitment_blindings: &E::G1Affine,
+        updates: &[(usize, AttributeUpdate<E::ScalarField>)],
+        context: &PresentationContext,
+    ) -> Result<E::ScalarField, ProofError> {
+        let mut transcript = Vec::new();
+        randomized_signature.serialize_compressed(&mut transcript)?;
+        schnorr_commitment.serialize_compressed(&mut transcript)?;
+        commitment.serialize_compressed(&mut transcript)?;
+        commitment_blindings.serialize_compressed(&mut transcript)?;
+        transcript.extend_from_slice(&updates_to_bytes(updates));
+        Ok(context.challenge(b"ps-reissue", &transcript))
+    }
+
+    fn escrow_challenge<E: Pairing>(
+        randomized_signature: &PSSignature<E>,
+        schnorr_commitment: &PairingOutput<E>,
+        ciphertext: &Ciphertext<E::G1Affine>,
+        label: &[u8],
+        encryption_commitments: &[E::G1Affine; 3],
+    ) -> Result<E::ScalarField, ProofError> {
+        let mut transcript = Vec::new();
+        randomized_signature.serialize_compressed(&mut transcript)?;
+        schnorr_commitment.serialize_compressed(&mut transcript)?;
+        let [a1, a2, a3] = encryption_commitments;
+        VerifiableEncryption::append_to_transcript(&mut transcript, ciphertext, label, a1, a2, a3);
+        Ok(HashUtil::<E::ScalarField, E::G1Affine>::hash_to_field(
+            &transcript,
+        ))
     }
 }
 
@@ -193,19 +992,19 @@ impl SignatureProofs {
 mod tests {
     use super::*;
     use crate::keygen::gen_keys;
-    use ark_bls12_381::{Bls12_381, Fr};
-    #[test]
-    fn test_signature_proof_system() {
+    use ark_ec::pairing::Pairing;
+    fn test_signature_proof_system<E: Pairing>() {
         // Initialize test environment
         let n = 4; // Support 4 messages
         let mut rng = ark_std::test_rng();
-        let context = Fr::rand(&mut rng);
-        let pp = PublicParams::<Bls12_381>::new(&n, &context, &mut rng);
+        let context = E::ScalarField::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&n, &context, &mut rng);
         let (sk, pk) = gen_keys(&pp, &mut rng);
 
         // Create random messages and blinding factor
-        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
-        let t = Fr::rand(&mut rng);
+        let messages: Vec<E::ScalarField> =
+            (0..n).map(|_| E::ScalarField::rand(&mut rng)).collect();
+        let t = E::ScalarField::rand(&mut rng);
 
         // Create commitment
         let commitment = Commitment::new(&pp, &pk, &messages, &t);
@@ -222,40 +1021,264 @@ mod tests {
         assert!(is_signature_valid, "Signature verification failed");
 
         // Generate proof of knowledge of the signature
-        let proof =
-            SignatureProofs::pok_signature(&pp.clone(), &pk.clone(), &messages, &unblind_signature);
+        let context = PresentationContext::new(b"verifier", b"nonce", 0);
+        let proof = SignatureProofs::pok_signature(
+            &pp.clone(),
+            &pk.clone(),
+            &messages,
+            &unblind_signature,
+            &context,
+            &mut rng,
+        );
 
         // Verify the proof
-        let is_proof_valid = SignatureProofs::verify_knowledge(&pp, &pk, &proof);
+        let is_proof_valid =
+            SignatureProofs::verify_knowledge(&pp, &pk, &proof, &Disclosure::default(), &context);
 
         assert!(is_proof_valid, "Signature proof verification failed");
+
+        // A proof captured for one verifier does not verify for another
+        let other_context = PresentationContext::new(b"other verifier", b"nonce", 0);
+        assert!(!SignatureProofs::verify_knowledge(
+            &pp,
+            &pk,
+            &proof,
+            &Disclosure::default(),
+            &other_context
+        ));
+
+        // the prepared key gives the same answers
+        let pvk = pk.prepare();
+        assert!(SignatureProofs::verify_knowledge_prepared(
+            &pp,
+            &pvk,
+            &proof,
+            &Disclosure::default(),
+            &context
+        ));
+        assert!(!SignatureProofs::verify_knowledge_prepared(
+            &pp,
+            &pvk,
+            &proof,
+            &Disclosure::default(),
+            &other_context
+        ));
+
+        // a proof built from the generator tables verifies the same way
+        let tables = pk.precompute();
+        let proof = SignatureProofs::pok_signature_with_tables(
+            &tables,
+            &messages,
+            &unblind_signature,
+            &context,
+            &mut rng,
+        );
+        assert!(SignatureProofs::verify_knowledge(
+            &pp,
+            &pk,
+            &proof,
+            &Disclosure::default(),
+            &context
+        ));
     }
 
-    #[test]
-    fn test_commitment_proof_system_integration() {
+    fn test_commitment_proof_system_integration<E: Pairing>() {
         // Initialize test environment
         let n = 4;
         let mut rng = ark_std::test_rng();
-        let context = Fr::rand(&mut rng);
-        let pp = PublicParams::<Bls12_381>::new(&n, &context, &mut rng);
+        let context = E::ScalarField::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&n, &context, &mut rng);
         let (sk, pk) = gen_keys(&pp, &mut rng);
 
         // Create random messages and blinding factor
-        let messages: Vec<Fr> = (0..n).map(|_| Fr::rand(&mut rng)).collect();
-        let t = Fr::rand(&mut rng);
+        let messages: Vec<E::ScalarField> =
+            (0..n).map(|_| E::ScalarField::rand(&mut rng)).collect();
+        let t = E::ScalarField::rand(&mut rng);
 
         // Create commitment
         let commitment = Commitment::new(&pp, &pk, &messages, &t);
 
         // Generate proof of knowledge
         let proof = commitment
-            .prove_opening()
+            .prove_opening(&mut rng)
             .expect("Proof generation should succeed");
 
         // Verify the proof
-        let is_valid = CommitmentProofs::pok_commitment_verify::<Bls12_381>(&proof)
+        let is_valid = CommitmentProofs::pok_commitment_verify::<E>(&pk, &proof)
             .expect("Proof verification should complete");
 
         assert!(is_valid, "Commitment proof verification should succeed");
+
+        // an opening under another issuer's key does not verify against ours
+        let (_, other_pk) = gen_keys(&pp, &mut rng);
+        assert!(!CommitmentProofs::pok_commitment_verify::<E>(&other_pk, &proof).unwrap());
+    }
+
+    fn test_commitment_proof_erases_blindings<E: Pairing>() {
+        let n = 4;
+        let mut rng = ark_std::test_rng();
+        let context = E::ScalarField::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&n, &context, &mut rng);
+        let (_, pk) = gen_keys(&pp, &mut rng);
+        let messages: Vec<E::ScalarField> =
+            (0..n).map(|_| E::ScalarField::rand(&mut rng)).collect();
+        let t = E::ScalarField::rand(&mut rng);
+        let commitment = Commitment::new(&pp, &pk, &messages, &t);
+
+        let full = commitment.prove_opening(&mut rng).unwrap();
+        let partial =
+            CommitmentProofs::pok_partial_commitment_prove(&pp, &pk, &messages, &t, &[1], &mut rng)
+                .unwrap();
+        for serialized in [&full, &partial] {
+            // with the blindings and a response the witness would be b - c·w
+            let proof = CommitmentProof::<E>::deserialize_compressed(&serialized[..]).unwrap();
+            assert!(proof.schnorr_commitment.random_blindings.is_empty());
+        }
+        assert!(CommitmentProofs::pok_commitment_verify::<E>(&pk, &full).unwrap());
+    }
+
+    fn test_commitment_proof_substituted_bases<E: Pairing>() {
+        let n = 4;
+        let mut rng = ark_std::test_rng();
+        let context = E::ScalarField::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&n, &context, &mut rng);
+        let (_, pk) = gen_keys(&pp, &mut rng);
+
+        // bases with known discrete logs open any statement, here the target itself with exponent one
+        let target = E::G1Affine::rand(&mut rng);
+        let mut bases = pk.get_bases();
+        bases[0] = target;
+        let mut exponents = vec![E::ScalarField::from(0u64); bases.len()];
+        exponents[0] = E::ScalarField::from(1u64);
+
+        let schnorr_commitment = SchnorrProtocol::commit(&bases, &mut rng);
+        let challenge = E::ScalarField::rand(&mut rng);
+        let responses = SchnorrProtocol::prove(&schnorr_commitment, &exponents, &challenge);
+        assert!(SchnorrProtocol::verify(
+            &bases,
+            &target,
+            &schnorr_commitment,
+            &responses,
+            &challenge
+        ));
+
+        let proof: CommitmentProof<E> = CommitmentProof {
+            statement: target,
+            schnorr_commitment,
+            challenge,
+            responses: responses.0,
+        };
+        let mut serialized_proof = Vec::new();
+        proof.serialize_compressed(&mut serialized_proof).unwrap();
+        assert!(!CommitmentProofs::pok_commitment_verify::<E>(&pk, &serialized_proof).unwrap());
+
+        // the same forgery against the partial issuance bases
+        assert!(CommitmentProofs::pok_partial_commitment_verify::<E>(
+            &pp,
+            &pk,
+            &[],
+            &serialized_proof
+        )
+        .unwrap()
+        .is_none());
+    }
+
+    /// A proof simulated for any statement: random responses and challenge, T = Σ b_i·z_i − c·X
+    fn simulate_commitment_proof<E: Pairing>(
+        bases: &[E::G1Affine],
+        statement: E::G1Affine,
+        rng: &mut impl Rng,
+    ) -> Vec<u8> {
+        let responses: Vec<E::ScalarField> = (0..bases.len())
+            .map(|_| E::ScalarField::rand(rng))
+            .collect();
+        let challenge = E::ScalarField::rand(rng);
+        let commited_blindings =
+            (E::G1::msm_unchecked(bases, &responses) - statement * challenge).into_affine();
+        let proof: CommitmentProof<E> = CommitmentProof {
+            statement,
+            schnorr_commitment: SchnorrCommitment {
+                random_blindings: Vec::new(),
+                commited_blindings,
+            },
+            challenge,
+            responses,
+        };
+        // the Schnorr equation holds, only the challenge gives it away
+        assert!(SchnorrProtocol::verify(
+            bases,
+            &proof.statement,
+            &proof.schnorr_commitment,
+            &SchnorrResponses(proof.responses.clone()),
+            &proof.challenge
+        ));
+        let mut serialized_proof = Vec::new();
+        proof.serialize_compressed(&mut serialized_proof).unwrap();
+        serialized_proof
+    }
+
+    fn test_partial_commitment_proof_simulated<E: Pairing>() {
+        let n = 4;
+        let mut rng = ark_std::test_rng();
+        let context = E::ScalarField::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&n, &context, &mut rng);
+        let (_, pk) = gen_keys(&pp, &mut rng);
+        let messages: Vec<E::ScalarField> =
+            (0..n).map(|_| E::ScalarField::rand(&mut rng)).collect();
+        let t = E::ScalarField::rand(&mut rng);
+
+        let honest =
+            CommitmentProofs::pok_partial_commitment_prove(&pp, &pk, &messages, &t, &[1], &mut rng)
+                .unwrap();
+        assert!(
+            CommitmentProofs::pok_partial_commitment_verify::<E>(&pp, &pk, &[1], &honest)
+                .unwrap()
+                .is_some()
+        );
+
+        // a challenge the prover picks lets it answer for a commitment it cannot open
+        let forged = simulate_commitment_proof::<E>(
+            &pk.get_bases_excluding(&[1]),
+            E::G1Affine::rand(&mut rng),
+            &mut rng,
+        );
+        assert!(
+            CommitmentProofs::pok_partial_commitment_verify::<E>(&pp, &pk, &[1], &forged)
+                .unwrap()
+                .is_none()
+        );
     }
+
+    fn test_commitment_proof_simulated<E: Pairing>() {
+        let n = 4;
+        let mut rng = ark_std::test_rng();
+        let context = E::ScalarField::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&n, &context, &mut rng);
+        let (_, pk) = gen_keys(&pp, &mut rng);
+
+        // a forged (T, c) pair for a commitment nobody can open
+        let forged =
+            simulate_commitment_proof::<E>(&pk.get_bases(), E::G1Affine::rand(&mut rng), &mut rng);
+        assert!(!CommitmentProofs::pok_commitment_verify::<E>(&pk, &forged).unwrap());
+
+        let messages: Vec<E::ScalarField> =
+            (0..n).map(|_| E::ScalarField::rand(&mut rng)).collect();
+        let t = E::ScalarField::rand(&mut rng);
+        let honest = Commitment::new(&pp, &pk, &messages, &t)
+            .prove_opening(&mut rng)
+            .unwrap();
+        assert!(matches!(
+            CommitmentProofs::pok_commitment_batch_verify::<E, _>(&pk, &[honest, forged], &mut rng),
+            Err(ProofError::BatchError(BatchError::InvalidProof(1)))
+        ));
+    }
+
+    utils::curve_tests!(
+        test_signature_proof_system,
+        test_commitment_proof_system_integration,
+        test_commitment_proof_erases_blindings,
+        test_commitment_proof_substituted_bases,
+        test_commitment_proof_simulated,
+        test_partial_commitment_proof_simulated
+    );
 }
@@ -1,15 +1,24 @@
 use ark_ec::pairing::Pairing;
 use ark_ec::AffineRepr;
 use ark_ff::UniformRand;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::ops::Mul;
 use ark_std::rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use utils::encoding::{Base64Point, HexScalar};
 
-#[derive(Clone, Debug)]
+#[serde_as]
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct PublicParams<E: Pairing> {
+    #[serde_as(as = "HexScalar")]
     pub context: E::ScalarField, // Domain separation value
-    pub n: usize,                // Number of supported messages
-    pub g1: E::G1Affine,         // Base generator for G1
-    pub g2: E::G2Affine,         // Base generator for G2
+    pub n: usize, // Number of supported messages
+    #[serde_as(as = "Base64Point")]
+    pub g1: E::G1Affine, // Base generator for G1
+    #[serde_as(as = "Base64Point")]
+    pub g2: E::G2Affine, // Base generator for G2
 }
 
 impl<E: Pairing> PublicParams<E> {
@@ -29,18 +38,19 @@ impl<E: Pairing> PublicParams<E> {
 #[cfg(test)]
 mod test {
     use super::*;
-    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ec::pairing::Pairing;
 
-    #[test]
-    fn test_pp_gen() {
+    fn test_pp_gen<E: Pairing>() {
         let n = 4;
         let mut rng = ark_std::test_rng();
-        let context = Fr::rand(&mut rng);
-        let pp = PublicParams::<Bls12_381>::new(&n, &context, &mut rng);
+        let context = E::ScalarField::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&n, &context, &mut rng);
 
         // Verify the public parameters were created
         assert!(!pp.g1.is_zero(), "g1 should not be the identity");
         assert!(!pp.g2.is_zero(), "g2 should not be the identity");
         assert_eq!(pp.n, n, "Message count should match");
     }
+
+    utils::curve_tests!(test_pp_gen);
 }
@@ -1,4 +1,4 @@
-use crate::keygen::{PublicKey, SecretKey};
+use crate::keygen::{PreparedVerificationKey, PublicKey, PublicKeyTables, SecretKey};
 use crate::publicparams::PublicParams;
 use crate::utils::PSUtils;
 use ark_ec::pairing::{Pairing, PairingOutput};
@@ -8,14 +8,21 @@ use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::{
     ops::{Mul, Neg},
     rand::Rng,
-    One,
+    Zero,
 };
 // use utils::helpers::Helpers;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use utils::encoding::Base64Point;
 use utils::pairing::verify_pairing_equation;
 
-#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+#[serde_as]
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct PSSignature<E: Pairing> {
+    #[serde_as(as = "Base64Point")]
     pub sigma1: E::G1Affine,
+    #[serde_as(as = "Base64Point")]
     pub sigma2: E::G1Affine,
 }
 
@@ -40,6 +47,21 @@ impl<E: Pairing> PSSignature<E> {
         Self { sigma1, sigma2 }
     }
 
+    /// `blind_sign` with g1 taken from precomputed tables
+    pub fn blind_sign_with_tables<R: Rng>(
+        tables: &PublicKeyTables<E>,
+        sk: &SecretKey<E>,
+        signature_commitment: &E::G1Affine,
+        rng: &mut R,
+    ) -> Self {
+        let u = E::ScalarField::rand(rng);
+        let g1 = tables.g1();
+        let sigma1 = g1.mul(&u).into_affine();
+        let sigma2 = (g1.mul(&sk.x) + signature_commitment).mul(u).into_affine();
+
+        Self { sigma1, sigma2 }
+    }
+
     /// Unblinds a blind signature using the blinding factor
     pub fn unblind(&self, t: &E::ScalarField) -> Self {
         let sigma2 = self.sigma1.mul(t).neg() + self.sigma2;
@@ -131,6 +153,28 @@ impl<E: Pairing> PSSignature<E> {
             None,
         )
     }
+
+    /// Verifies a signature on public messages with a prepared key:
+    /// e(σ1, X̃ · ∏ Ỹⱼᵐʲ) · e(-σ2, g̃) = 1, where only the first G2 element is prepared here
+    pub fn public_verify_prepared(
+        &self,
+        messages: &[E::ScalarField],
+        pvk: &PreparedVerificationKey<E>,
+    ) -> bool {
+        if self.sigma1.is_zero() || pvk.pk.y_g2.len() != messages.len() {
+            return false;
+        }
+        let yimix = E::G2::msm_unchecked(&pvk.pk.y_g2, messages) + pvk.pk.x_g2;
+        let sigma2_inv = self.sigma2.into_group().neg();
+        E::multi_pairing(
+            [
+                E::G1Prepared::from(self.sigma1),
+                E::G1Prepared::from(sigma2_inv),
+            ],
+            [E::G2Prepared::from(yimix), pvk.g2.clone()],
+        )
+        .is_zero()
+    }
 }
 
 #[cfg(test)]
@@ -139,20 +183,21 @@ mod tests {
     use crate::commitment::compute_commitment_g1;
     use crate::keygen::gen_keys;
     use crate::publicparams::PublicParams;
-    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ec::pairing::Pairing;
     use ark_ff::Zero;
 
-    #[test]
-    fn test_ps_signature_direct() {
+    fn test_ps_signature_direct<E: Pairing>() {
         // Setup with precisely 5 messages
         let message_count = 5;
         let mut rng = ark_std::test_rng();
-        let context = Fr::rand(&mut rng);
-        let pp = PublicParams::<Bls12_381>::new(&message_count, &context, &mut rng);
+        let context = E::ScalarField::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&message_count, &context, &mut rng);
         let (sk, pk) = gen_keys(&pp, &mut rng);
 
         // Generate exactly 5 random messages
-        let messages: Vec<Fr> = (0..message_count).map(|_| Fr::rand(&mut rng)).collect();
+        let messages: Vec<E::ScalarField> = (0..message_count)
+            .map(|_| E::ScalarField::rand(&mut rng))
+            .collect();
 
         // Create signature directly using public_sign
         let signature = PSSignature::public_sign(&messages, &sk, &pp);
@@ -172,7 +217,7 @@ mod tests {
 
         // Optional: Demonstrate that verification is sensitive to message integrity
         let mut modified_messages = messages.clone();
-        modified_messages[2] = Fr::rand(&mut rng); // Modify the third message
+        modified_messages[2] = E::ScalarField::rand(&mut rng); // Modify the third message
 
         let is_invalid = signature.public_verify(&pp, &modified_messages, &pk);
         assert!(
@@ -180,22 +225,28 @@ mod tests {
             "Signature incorrectly verified against modified messages, indicating a \
          fundamental flaw in the verification equation implementation"
         );
+
+        // the prepared key gives the same answers
+        let pvk = pk.prepare();
+        assert!(signature.public_verify_prepared(&messages, &pvk));
+        assert!(!signature.public_verify_prepared(&modified_messages, &pvk));
     }
 
-    #[test]
-    fn test_blind_sign_and_unblind() {
+    fn test_blind_sign_and_unblind<E: Pairing>() {
         // Setup
         let message_count = 4;
         let mut rng = ark_std::test_rng();
-        let context = Fr::rand(&mut rng);
-        let pp = PublicParams::<Bls12_381>::new(&message_count, &context, &mut rng);
+        let context = E::ScalarField::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&message_count, &context, &mut rng);
         let (sk, pk) = gen_keys(&pp, &mut rng);
 
         // Create messages and commitment
-        let messages: Vec<Fr> = (0..message_count).map(|_| Fr::rand(&mut rng)).collect();
-        let t = Fr::rand(&mut rng);
+        let messages: Vec<E::ScalarField> = (0..message_count)
+            .map(|_| E::ScalarField::rand(&mut rng))
+            .collect();
+        let t = E::ScalarField::rand(&mut rng);
 
-        let commitment = compute_commitment_g1::<Bls12_381>(&t, &pp.g1, &messages, &pk.y_g1);
+        let commitment = compute_commitment_g1::<E>(&t, &pp.g1, &messages, &pk.y_g1);
 
         // Blind sign
         let blind_signature = PSSignature::blind_sign(&pp, &pk, &sk, &commitment, &mut rng);
@@ -214,19 +265,27 @@ mod tests {
         // Verify
         let is_valid = signature.public_verify(&pp, &messages, &pk);
         assert!(is_valid, "Unblinded signature verification failed");
+
+        // Same flow with the generator tables
+        let tables = pk.precompute();
+        let blind_signature =
+            PSSignature::blind_sign_with_tables(&tables, &sk, &commitment, &mut rng);
+        let signature = blind_signature.unblind(&t);
+        assert!(signature.public_verify(&pp, &messages, &pk));
     }
 
-    #[test]
-    fn test_signature_rerandomization() {
+    fn test_signature_rerandomization<E: Pairing>() {
         // Setup
         let message_count = 4;
         let mut rng = ark_std::test_rng();
-        let context = Fr::rand(&mut rng);
-        let pp = PublicParams::<Bls12_381>::new(&message_count, &context, &mut rng);
+        let context = E::ScalarField::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&message_count, &context, &mut rng);
         let (sk, pk) = gen_keys(&pp, &mut rng);
 
         // Create messages
-        let messages: Vec<Fr> = (0..message_count).map(|_| Fr::rand(&mut rng)).collect();
+        let messages: Vec<E::ScalarField> = (0..message_count)
+            .map(|_| E::ScalarField::rand(&mut rng))
+            .collect();
 
         // Public sign for testing
         let signature = PSSignature::public_sign(&messages, &sk, &pp);
@@ -236,29 +295,30 @@ mod tests {
         );
 
         // Rerandomize
-        let r = Fr::rand(&mut rng);
-        let t = Fr::rand(&mut rng);
+        let r = E::ScalarField::rand(&mut rng);
+        let t = E::ScalarField::rand(&mut rng);
         let randomized_signature = signature.rerandomize(&r, &t);
     }
 
-    #[test]
-    fn test_randomize_for_pok() {
+    fn test_randomize_for_pok<E: Pairing>() {
         // Setup
         let message_count = 4;
         let mut rng = ark_std::test_rng();
-        let context = Fr::rand(&mut rng);
-        let pp = PublicParams::<Bls12_381>::new(&message_count, &context, &mut rng);
+        let context = E::ScalarField::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&message_count, &context, &mut rng);
         let (sk, pk) = gen_keys(&pp, &mut rng);
 
         // Create messages
-        let messages: Vec<Fr> = (0..message_count).map(|_| Fr::rand(&mut rng)).collect();
+        let messages: Vec<E::ScalarField> = (0..message_count)
+            .map(|_| E::ScalarField::rand(&mut rng))
+            .collect();
 
         // Public sign
         let signature = PSSignature::public_sign(&messages, &sk, &pp);
 
         // // Randomize for POK
-        let r = Fr::rand(&mut rng);
-        let t = Fr::rand(&mut rng);
+        let r = E::ScalarField::rand(&mut rng);
+        let t = E::ScalarField::rand(&mut rng);
         // let randomized = signature.randomize(&r, &t);
 
         // Test the auto-randomization version too
@@ -273,17 +333,18 @@ mod tests {
         );
     }
 
-    #[test]
-    fn test_generate_commitment_gt() {
+    fn test_generate_commitment_gt<E: Pairing>() {
         // Setup
         let message_count = 4;
         let mut rng = ark_std::test_rng();
-        let context = Fr::rand(&mut rng);
-        let pp = PublicParams::<Bls12_381>::new(&message_count, &context, &mut rng);
+        let context = E::ScalarField::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&message_count, &context, &mut rng);
         let (sk, pk) = gen_keys(&pp, &mut rng);
 
         // Create messages
-        let messages: Vec<Fr> = (0..message_count).map(|_| Fr::rand(&mut rng)).collect();
+        let messages: Vec<E::ScalarField> = (0..message_count)
+            .map(|_| E::ScalarField::rand(&mut rng))
+            .collect();
 
         // Public sign
         let signature = PSSignature::public_sign(&messages, &sk, &pp);
@@ -293,17 +354,18 @@ mod tests {
         assert!(!gt_commitment.is_zero(), "GT commitment should not be zero");
     }
 
-    #[test]
-    fn test_pairing_check_verification() {
+    fn test_pairing_check_verification<E: Pairing>() {
         // Setup
         let message_count = 4;
         let mut rng = ark_std::test_rng();
-        let context = Fr::rand(&mut rng);
-        let pp = PublicParams::<Bls12_381>::new(&message_count, &context, &mut rng);
+        let context = E::ScalarField::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&message_count, &context, &mut rng);
         let (sk, pk) = gen_keys(&pp, &mut rng);
 
         // Create messages
-        let messages: Vec<Fr> = (0..message_count).map(|_| Fr::rand(&mut rng)).collect();
+        let messages: Vec<E::ScalarField> = (0..message_count)
+            .map(|_| E::ScalarField::rand(&mut rng))
+            .collect();
 
         // Public sign
         let signature = PSSignature::public_sign(&messages, &sk, &pp);
@@ -312,4 +374,13 @@ mod tests {
         let is_valid_standard = signature.public_verify(&pp, &messages, &pk);
         assert!(is_valid_standard, "Standard verification failed");
     }
+
+    utils::curve_tests!(
+        test_ps_signature_direct,
+        test_blind_sign_and_unblind,
+        test_signature_rerandomization,
+        test_randomize_for_pok,
+        test_generate_commitment_gt,
+        test_pairing_check_verification
+    );
 }
@@ -0,0 +1,125 @@
+use crate::anon_cred::{IssuerResponse, ShowCredential, UserCred};
+use crate::keygen::{PublicKey, SecretKey};
+use crate::proofsystem::{CommitmentProof, ReissuanceProof};
+use crate::publicparams::PublicParams;
+use crate::signature::PSSignature;
+use utils::impl_envelope;
+
+impl_envelope!(PublicParams, Ps, PublicParams, |pp| pp.n);
+impl_envelope!(PublicKey, Ps, PublicKey, |pk| pk.y_g1.len());
+impl_envelope!(SecretKey, Ps, SecretKey, |sk| sk.yi.len());
+impl_envelope!(CommitmentProof, Ps, IssuanceRequest);
+impl_envelope!(ReissuanceProof, Ps, ReissuanceRequest);
+impl_envelope!(IssuerResponse, Ps, IssuanceResponse);
+impl_envelope!(PSSignature, Ps, Signature);
+impl_envelope!(UserCred, Ps, Credential, |cred| cred.messages.len());
+impl_envelope!(ShowCredential, Ps, Presentation);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anon_cred::PSAnonCredProtocol;
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_serialize::CanonicalDeserialize;
+    use ark_std::test_rng;
+    use utils::context::PresentationContext;
+    use utils::envelope::{assert_golden, ArtifactKind, Envelope, EnvelopeError, Header};
+    use utils::update::AttributeUpdate;
+
+    fn golden(name: &str) -> String {
+        format!("{}/golden/{}.hex", env!("CARGO_MANIFEST_DIR"), name)
+    }
+
+    #[test]
+    fn test_ps_wire_golden() {
+        let mut rng = test_rng();
+        let protocol = PSAnonCredProtocol::<Bls12_381>::new(3, &mut rng);
+        let user_cred = UserCred::<Bls12_381>::new_random_messages(3);
+        let request = protocol.obtain(&user_cred, &mut rng).unwrap();
+        let response = protocol.issue(&request, &mut rng).unwrap();
+        let signature =
+            PSAnonCredProtocol::complete_signature(&response.blind_signature, &user_cred.t);
+        let context = PresentationContext::new(b"verifier", b"nonce-1", 1_700_000_000);
+        let presentation = protocol
+            .show(&signature, &user_cred, &context, &mut rng)
+            .unwrap();
+        let new_issuer = PSAnonCredProtocol::<Bls12_381>::new(3, &mut rng);
+        let updates = vec![(0, AttributeUpdate::Add(Fr::from(1u64)))];
+        let (reissue_request, _) = new_issuer
+            .obtain_reissue(
+                &protocol.pp,
+                &protocol.pk,
+                &signature,
+                &user_cred,
+                &updates,
+                &context,
+                &mut rng,
+            )
+            .unwrap();
+        let request = CommitmentProof::<Bls12_381>::deserialize_compressed(&request[..]).unwrap();
+        let reissue_request =
+            ReissuanceProof::<Bls12_381>::deserialize_compressed(&reissue_request[..]).unwrap();
+
+        assert_golden(&golden("public_params"), &protocol.pp.to_envelope());
+        assert_golden(&golden("public_key"), &protocol.pk.to_envelope());
+        assert_golden(&golden("issuance_request"), &request.to_envelope());
+        assert_golden(
+            &golden("reissuance_request"),
+            &reissue_request.to_envelope(),
+        );
+        assert_golden(&golden("issuance_response"), &response.to_envelope());
+        assert_golden(&golden("signature"), &signature.to_envelope());
+        assert_golden(&golden("credential"), &user_cred.to_envelope());
+        assert_golden(&golden("presentation"), &presentation.to_envelope());
+
+        let pk = PublicKey::<Bls12_381>::from_envelope(&protocol.pk.to_envelope()).unwrap();
+        assert_eq!(pk.y_g2, protocol.pk.y_g2);
+        let (header, _) = Header::parse(&user_cred.to_envelope()).unwrap();
+        assert_eq!(header.attribute_count, 3);
+        let decoded =
+            ShowCredential::<Bls12_381>::from_envelope(&presentation.to_envelope()).unwrap();
+        assert!(protocol.verify(&decoded, &context).unwrap());
+        assert!(matches!(
+            PSSignature::<Bls12_381>::from_envelope(&user_cred.to_envelope()),
+            Err(EnvelopeError::KindMismatch {
+                expected: ArtifactKind::Signature,
+                found: ArtifactKind::Credential,
+            })
+        ));
+    }
+
+    #[test]
+    fn test_ps_json() {
+        let mut rng = test_rng();
+        let protocol = PSAnonCredProtocol::<Bls12_381>::new(3, &mut rng);
+        let user_cred = UserCred::<Bls12_381>::new_random_messages(3);
+        let request = protocol.obtain(&user_cred, &mut rng).unwrap();
+        let response = protocol.issue(&request, &mut rng).unwrap();
+        let signature =
+            PSAnonCredProtocol::complete_signature(&response.blind_signature, &user_cred.t);
+        let context = PresentationContext::new(b"verifier", b"nonce-1", 1_700_000_000);
+        let presentation = protocol
+            .show(&signature, &user_cred, &context, &mut rng)
+            .unwrap();
+
+        let pk: PublicKey<Bls12_381> =
+            serde_json::from_str(&serde_json::to_string(&protocol.pk).unwrap()).unwrap();
+        assert_eq!(pk.y_g1, protocol.pk.y_g1);
+        assert_eq!(pk.pp.context, protocol.pp.context);
+        let presentation: ShowCredential<Bls12_381> =
+            serde_json::from_str(&serde_json::to_string(&presentation).unwrap()).unwrap();
+        assert!(protocol.verify(&presentation, &context).unwrap());
+
+        let mut json = serde_json::to_value(&signature).unwrap();
+        assert!(json["sigma1"].is_string());
+        let sig: PSSignature<Bls12_381> = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(sig.sigma2, signature.sigma2);
+        // a G2 encoding in a G1 field is rejected
+        json["sigma1"] = serde_json::to_value(&pk)
+            .unwrap()
+            .get("x_g2")
+            .unwrap()
+            .clone();
+        assert!(serde_json::from_value::<PSSignature<Bls12_381>>(json).is_err());
+    }
+}
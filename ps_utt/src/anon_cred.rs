@@ -1,29 +1,57 @@
 use crate::commitment::Commitment;
-use crate::keygen::{gen_keys, SecretKey, VerificationKey};
-use crate::proofsystem::{CommitmentProof, CommitmentProofError, CommitmentProofs};
-use crate::publicparams::PublicParams;
+use crate::keygen::{gen_keys, gen_keys_with_proof, SecretKey, VerificationKey};
+use crate::proofsystem::{
+    check_attribute_indices, CommitmentProof, CommitmentProofError, CommitmentProofs,
+    PreparedCommitmentProof,
+};
+use crate::publicparams::{PublicParams, PublicParamsTables};
 use crate::signature::PSUTTSignature;
 use ark_ec::pairing::Pairing;
+use ark_ec::CurveGroup;
 use ark_ff::UniformRand;
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::rand::Rng;
+use schnorr::key_proof::KeyProof;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use utils::context::PresentationContext;
+use utils::disclosure::Disclosure;
+use utils::encoding::{Base64Bytes, Base64Point};
 // use std::time::Instant;
 
 /// Presentation of a credential with G1 and G2 elements
+#[serde_as]
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct ShowCredential<E: Pairing> {
     pub randomized_signature: PSUTTSignature<E>,
+    #[serde_as(as = "Base64Point")]
     pub cmg1: E::G1Affine,
+    #[serde_as(as = "Base64Point")]
     pub cmg2: E::G2Affine,
+    #[serde_as(as = "Base64Bytes")]
     pub proof: Vec<u8>,
+    /// Messages revealed by `finish_show`, empty for `show`
+    pub disclosed: Disclosure<E::ScalarField>,
+}
+
+/// Rerandomized signature, the commitment in G1 and G2 and its unanswered opening proof
+pub struct ShowToken<E: Pairing> {
+    randomized_signature: PSUTTSignature<E>,
+    cmg1: E::G1Affine,
+    cmg2: E::G2Affine,
+    proof: PreparedCommitmentProof<E>,
 }
 
 /// User credential containing a secret key and commitment
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
 pub struct UserCred<E: Pairing> {
     pub usk: E::ScalarField,
     pub commitment: Commitment<E>,
 }
 
-impl<E: Pairing> UserCred<E> {    pub fn new(
+impl<E: Pairing> UserCred<E> {
+    pub fn new(
         pp: &PublicParams<E>,
         messages: &Vec<E::ScalarField>,
         usk: E::ScalarField,
@@ -41,6 +69,21 @@ impl<E: Pairing> UserCred<E> {    pub fn new(
         let commitment = Commitment::new(&pp, &messages, &usk);
         Self { usk, commitment }
     }
+
+    /// Credential over the merged attribute vector, issuer-set attributes replace the user's entries
+    pub fn with_issuer_attributes(
+        &self,
+        pp: &PublicParams<E>,
+        issuer_attributes: &[(usize, E::ScalarField)],
+    ) -> Result<UserCred<E>, CommitmentProofError> {
+        let issuer_indices: Vec<usize> = issuer_attributes.iter().map(|(i, _)| *i).collect();
+        check_attribute_indices(self.commitment.messages.len(), &issuer_indices)?;
+        let mut messages = self.commitment.messages.clone();
+        for (i, a) in issuer_attributes {
+            messages[*i] = *a;
+        }
+        Ok(Self::new(pp, &messages, self.usk))
+    }
 }
 
 /// Standard anonymous credential protocol
@@ -48,6 +91,7 @@ pub struct AnonCredProtocol<E: Pairing> {
     pub pp: PublicParams<E>,
     sk: SecretKey<E>,
     vk: VerificationKey<E>,
+    tables: PublicParamsTables<E>,
 }
 
 impl<E: Pairing> AnonCredProtocol<E> {
@@ -56,23 +100,45 @@ impl<E: Pairing> AnonCredProtocol<E> {
         let context = E::ScalarField::rand(rng);
         let pp = PublicParams::<E>::new(&n, &context, rng);
         let (sk, vk) = gen_keys(&pp, rng);
-        Self { pp, sk, vk }
+        let tables = pp.precompute();
+        Self { pp, sk, vk, tables }
+    }
+
+    /// `new` that also returns a proof the issuer key is well formed, published next to `pp`
+    pub fn new_with_key_proof(n: usize, rng: &mut impl Rng) -> (Self, KeyProof<E>) {
+        let context = E::ScalarField::rand(rng);
+        let pp = PublicParams::<E>::new(&n, &context, rng);
+        let (sk, vk, proof) = gen_keys_with_proof(&pp, rng);
+        let tables = pp.precompute();
+        (Self { pp, sk, vk, tables }, proof)
+    }
+
+    /// Holder checks the issuer key proof before `obtain`
+    pub fn verify_key(
+        &self,
+        proof: &KeyProof<E>,
+        rng: &mut impl Rng,
+    ) -> Result<(), CommitmentProofError> {
+        Ok(self.vk.verify_key(&self.pp, proof, rng)?)
     }
 
     /// User generates proof of knowledge for obtaining a credential
-    pub fn obtain(&self, user_cred: &UserCred<E>) -> Result<Vec<u8>, CommitmentProofError> {
-        CommitmentProofs::pok_commitment_prove(&user_cred.commitment)
+    pub fn obtain(
+        &self,
+        user_cred: &UserCred<E>,
+        rng: &mut impl Rng,
+    ) -> Result<Vec<u8>, CommitmentProofError> {
+        CommitmentProofs::pok_commitment_prove(&user_cred.commitment, rng)
     }
 
     /// Issuer verifies proof and issues credential
-    pub fn issue(
+    pub fn issue<R: Rng>(
         &self,
         serialized_proof: &[u8],
+        rng: &mut R,
     ) -> Result<PSUTTSignature<E>, CommitmentProofError> {
-        let mut rng = ark_std::test_rng();
-
         // Verify proof of knowledge
-        if !CommitmentProofs::pok_commitment_verify::<E>(serialized_proof)? {
+        if !CommitmentProofs::pok_commitment_verify::<E>(&self.pp, serialized_proof)? {
             return Err(CommitmentProofError::InvalidProof);
         }
 
@@ -81,11 +147,71 @@ impl<E: Pairing> AnonCredProtocol<E> {
             CanonicalDeserialize::deserialize_compressed(serialized_proof)?;
 
         // Sign the commitment
-        Ok(PSUTTSignature::sign(
-            &self.pp,
+        Ok(PSUTTSignature::sign_with_tables(
+            &self.tables,
             &self.sk,
             &proof.commitment,
-            &mut rng,
+            rng,
+        ))
+    }
+
+    /// Issuer verifies many proofs in one batch and signs each commitment. If any proof is
+    /// invalid nothing is issued and the error names the first bad request.
+    pub fn issue_batch<R: Rng>(
+        &self,
+        serialized_proofs: &[Vec<u8>],
+        rng: &mut R,
+    ) -> Result<Vec<PSUTTSignature<E>>, CommitmentProofError> {
+        let proofs =
+            CommitmentProofs::pok_commitment_batch_verify(&self.pp, serialized_proofs, rng)?;
+
+        Ok(proofs
+            .iter()
+            .map(|proof| {
+                PSUTTSignature::sign_with_tables(&self.tables, &self.sk, &proof.commitment, rng)
+            })
+            .collect())
+    }
+
+    /// User proves knowledge of a commitment to all attributes except those at `issuer_indices`
+    pub fn obtain_partial(
+        &self,
+        user_cred: &UserCred<E>,
+        issuer_indices: &[usize],
+        rng: &mut impl Rng,
+    ) -> Result<Vec<u8>, CommitmentProofError> {
+        CommitmentProofs::pok_partial_commitment_prove(&user_cred.commitment, issuer_indices, rng)
+    }
+
+    /// Issuer verifies the partial commitment proof, adds its own attributes and signs the merged
+    /// commitment. The user holds the credential from `UserCred::with_issuer_attributes`
+    pub fn issue_partial<R: Rng>(
+        &self,
+        serialized_proof: &[u8],
+        issuer_attributes: &[(usize, E::ScalarField)],
+        rng: &mut R,
+    ) -> Result<PSUTTSignature<E>, CommitmentProofError> {
+        let issuer_indices: Vec<usize> = issuer_attributes.iter().map(|(i, _)| *i).collect();
+        let commitment = CommitmentProofs::pok_partial_commitment_verify::<E>(
+            &self.pp,
+            &issuer_indices,
+            serialized_proof,
+        )?
+        .ok_or(CommitmentProofError::InvalidProof)?;
+
+        // cm' = cm · ∏ ck_j^a_j over the issuer-set attributes
+        let merged = issuer_attributes
+            .iter()
+            .fold(E::G1::from(commitment), |acc, (i, a)| {
+                acc + self.tables.bases_g1.table(*i).mul(a)
+            })
+            .into_affine();
+
+        Ok(PSUTTSignature::sign_with_tables(
+            &self.tables,
+            &self.sk,
+            &merged,
+            rng,
         ))
     }
 
@@ -94,38 +220,75 @@ impl<E: Pairing> AnonCredProtocol<E> {
         &self,
         commitment: &Commitment<E>,
         signature: &PSUTTSignature<E>,
+        context: &PresentationContext,
         rng: &mut R,
     ) -> Result<ShowCredential<E>, CommitmentProofError> {
-        // Generate random values for rerandomization
-        // let start_show = Instant::now();
+        let token = self.precompute_show(commitment, signature, rng);
+        self.finish_show(token, context, &[])
+    }
+
+    /// Offline half of `show`: rerandomizes the commitment and signature and computes the
+    /// Schnorr commitment
+    pub fn precompute_show<R: Rng>(
+        &self,
+        commitment: &Commitment<E>,
+        signature: &PSUTTSignature<E>,
+        rng: &mut R,
+    ) -> ShowToken<E> {
         let r_delta = E::ScalarField::rand(rng);
         let u_delta = E::ScalarField::rand(rng);
 
-        // Rerandomize the commitment and signature
-        let randomized_commitment = commitment.create_randomized(&r_delta);
-
+        let randomized_commitment =
+            commitment.create_randomized_with_tables(&self.tables, &r_delta);
         let randomized_signature = signature.rerandomize(&self.pp, &r_delta, &u_delta);
+        let proof = CommitmentProofs::prepare_commitment_proof_with_tables(
+            &randomized_commitment,
+            &self.tables,
+            rng,
+        );
 
-        let serialized_proof = CommitmentProofs::pok_commitment_prove(&randomized_commitment)?;
-        // Create proof of knowledge for the rerandomized commitment
-        let show_cred = ShowCredential {
+        ShowToken {
             randomized_signature,
             cmg1: randomized_commitment.cmg1,
             cmg2: randomized_commitment.cmg2,
-            proof: serialized_proof,
-        };
-        // let duration = start_show.elapsed();
-        // println!("Time to show PS_UTT_G1: {:?}", duration);
+            proof,
+        }
+    }
 
-        Ok(show_cred)
+    /// Online half of `show`: binds a precomputed token to the verifier's context and reveals
+    /// the messages at `disclosed`
+    pub fn finish_show(
+        &self,
+        token: ShowToken<E>,
+        context: &PresentationContext,
+        disclosed: &[usize],
+    ) -> Result<ShowCredential<E>, CommitmentProofError> {
+        let (proof, disclosed) = token.proof.finish(context, disclosed)?;
+        Ok(ShowCredential {
+            randomized_signature: token.randomized_signature,
+            cmg1: token.cmg1,
+            cmg2: token.cmg2,
+            proof,
+            disclosed,
+        })
     }
 
     /// Verifier checks credential presentation
-    pub fn verify(&self, cred_show: &ShowCredential<E>) -> Result<bool, CommitmentProofError> {
-        // Verify proof of knowledge
+    pub fn verify(
+        &self,
+        cred_show: &ShowCredential<E>,
+        context: &PresentationContext,
+    ) -> Result<bool, CommitmentProofError> {
+        // Verify proof of knowledge for this context and the presented commitment
         // let start_verify = Instant::now();
-        if !CommitmentProofs::pok_commitment_verify::<E>(&cred_show.proof)? {
-            return Ok(false);
+        match CommitmentProofs::pok_commitment_verify_with_context::<E>(
+            &self.pp,
+            &cred_show.proof,
+            &cred_show.disclosed,
+            context,
+        )? {
+            Some(commitment) if commitment == cred_show.cmg1 => {}
+            _ => return Ok(false),
         }
 
         // Verify signature
@@ -144,28 +307,32 @@ impl<E: Pairing> AnonCredProtocol<E> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ec::pairing::Pairing;
     use ark_std::test_rng;
+    use schnorr::batch::BatchError;
 
-    #[test]
-    fn test_psutt_credential_lifecycle() {
+    fn test_psutt_credential_lifecycle<E: Pairing>() {
         // Setup phase
         let mut rng = test_rng();
         let message_count = 5;
-        let protocol = AnonCredProtocol::<Bls12_381>::new(message_count, &mut rng);
+        let protocol = AnonCredProtocol::<E>::new(message_count, &mut rng);
 
         // User phase - generate attributes and commitment
-        let user_attributes: Vec<Fr> = (0..message_count).map(|_| Fr::rand(&mut rng)).collect();
-        let user_blinding = Fr::rand(&mut rng);
-        let user_cred = UserCred::<Bls12_381>::new(&protocol.pp, &user_attributes, user_blinding);
+        let user_attributes: Vec<E::ScalarField> = (0..message_count)
+            .map(|_| E::ScalarField::rand(&mut rng))
+            .collect();
+        let user_blinding = E::ScalarField::rand(&mut rng);
+        let user_cred = UserCred::<E>::new(&protocol.pp, &user_attributes, user_blinding);
 
         // Obtain phase - user creates proof of knowledge
         let proof = protocol
-            .obtain(&user_cred)
+            .obtain(&user_cred, &mut rng)
             .expect("Failed to generate proof");
 
         // Issue phase - issuer verifies proof and issues credential
-        let signature = protocol.issue(&proof).expect("Failed to issue credential");
+        let signature = protocol
+            .issue(&proof, &mut rng)
+            .expect("Failed to issue credential");
 
         // Verify original signature
         assert!(
@@ -178,16 +345,215 @@ mod tests {
             "Original signature verification failed"
         );
 
-        // Show phase - user creates presentation
+        // Show phase - user creates presentation for the verifier's context
+        let context = PresentationContext::new(b"verifier", b"nonce-1", 1_700_000_000);
         let presentation = protocol
-            .show(&user_cred.commitment, &signature, &mut rng)
+            .show(&user_cred.commitment, &signature, &context, &mut rng)
             .expect("Failed to generate credential presentation");
 
         // Verify phase
         let is_valid = protocol
-            .verify(&presentation)
+            .verify(&presentation, &context)
             .expect("Verification process failed");
 
         assert!(is_valid, "Credential verification failed");
+
+        // Replay under a fresh nonce is rejected
+        let replay_context = PresentationContext::new(b"verifier", b"nonce-2", 1_700_000_000);
+        assert!(!protocol.verify(&presentation, &replay_context).unwrap());
+    }
+
+    fn test_psutt_precompute_show<E: Pairing>() {
+        let mut rng = test_rng();
+        let message_count = 3;
+        let protocol = AnonCredProtocol::<E>::new(message_count, &mut rng);
+        let user_cred = UserCred::<E>::new_random_messages(&protocol.pp);
+        let proof = protocol.obtain(&user_cred, &mut rng).unwrap();
+        let signature = protocol.issue(&proof, &mut rng).unwrap();
+
+        // tokens are made before any verifier context exists
+        let tokens: Vec<ShowToken<E>> = (0..2)
+            .map(|_| protocol.precompute_show(&user_cred.commitment, &signature, &mut rng))
+            .collect();
+
+        let contexts = [
+            PresentationContext::new(b"verifier", b"nonce-1", 0),
+            PresentationContext::new(b"verifier", b"nonce-2", 0),
+        ];
+        let presentations: Vec<ShowCredential<E>> = tokens
+            .into_iter()
+            .zip(contexts.iter())
+            .map(|(token, context)| protocol.finish_show(token, context, &[]).unwrap())
+            .collect();
+
+        assert!(protocol.verify(&presentations[0], &contexts[0]).unwrap());
+        assert!(protocol.verify(&presentations[1], &contexts[1]).unwrap());
+        assert!(!protocol.verify(&presentations[0], &contexts[1]).unwrap());
+        assert_ne!(presentations[0].cmg1, presentations[1].cmg1);
+
+        // tokens of the same kind reveal whichever messages the verifier asks for
+        let messages = &user_cred.commitment.messages;
+        let first = protocol.precompute_show(&user_cred.commitment, &signature, &mut rng);
+        let second = protocol.precompute_show(&user_cred.commitment, &signature, &mut rng);
+        let one = protocol.finish_show(first, &contexts[0], &[0]).unwrap();
+        let two = protocol.finish_show(second, &contexts[0], &[1, 2]).unwrap();
+        assert_eq!(one.disclosed.attributes, vec![(0, messages[0])]);
+        assert_eq!(
+            two.disclosed.attributes,
+            vec![(1, messages[1]), (2, messages[2])]
+        );
+        assert!(protocol.verify(&one, &contexts[0]).unwrap());
+        assert!(protocol.verify(&two, &contexts[0]).unwrap());
+
+        let mut forged = two.clone();
+        forged.disclosed.attributes[1].1 += E::ScalarField::from(1u64);
+        assert!(!protocol.verify(&forged, &contexts[0]).unwrap());
+
+        let token = protocol.precompute_show(&user_cred.commitment, &signature, &mut rng);
+        assert!(matches!(
+            protocol.finish_show(token, &contexts[0], &[0, 0]),
+            Err(CommitmentProofError::DisclosureError(_))
+        ));
     }
+
+    fn test_psutt_issue_batch<E: Pairing>() {
+        let mut rng = test_rng();
+        let message_count = 3;
+        let protocol = AnonCredProtocol::<E>::new(message_count, &mut rng);
+        let other = AnonCredProtocol::<E>::new(message_count, &mut rng);
+
+        let users: Vec<UserCred<E>> = (0..4)
+            .map(|_| {
+                let messages: Vec<E::ScalarField> = (0..message_count)
+                    .map(|_| E::ScalarField::rand(&mut rng))
+                    .collect();
+                UserCred::new(&protocol.pp, &messages, E::ScalarField::rand(&mut rng))
+            })
+            .collect();
+        let mut proofs: Vec<Vec<u8>> = users
+            .iter()
+            .map(|u| protocol.obtain(u, &mut rng).unwrap())
+            .collect();
+
+        let signatures = protocol.issue_batch(&proofs, &mut rng).unwrap();
+        for (signature, user_cred) in signatures.iter().zip(users.iter()) {
+            assert!(signature.verify_with_pairing_checker(
+                &protocol.pp,
+                &protocol.vk,
+                &user_cred.commitment.cmg1,
+                &user_cred.commitment.cmg2
+            ));
+        }
+
+        // a request made under another issuer's parameters is named and nothing is issued
+        let foreign = UserCred::new(&other.pp, &users[1].commitment.messages, users[1].usk);
+        proofs[1] = other.obtain(&foreign, &mut rng).unwrap();
+        assert!(matches!(
+            protocol.issue_batch(&proofs, &mut rng),
+            Err(CommitmentProofError::BatchError(BatchError::InvalidProof(
+                1
+            )))
+        ));
+    }
+
+    fn test_psutt_issuer_attributes<E: Pairing>() {
+        let mut rng = test_rng();
+        let message_count = 4;
+        let protocol = AnonCredProtocol::<E>::new(message_count, &mut rng);
+        let user_attributes: Vec<E::ScalarField> = (0..message_count)
+            .map(|_| E::ScalarField::rand(&mut rng))
+            .collect();
+        let user_cred = UserCred::<E>::new(
+            &protocol.pp,
+            &user_attributes,
+            E::ScalarField::rand(&mut rng),
+        );
+
+        // issuer sets the issuance epoch at index 2
+        let issuer_attributes = vec![(2, E::ScalarField::from(42u64))];
+        let proof = protocol.obtain_partial(&user_cred, &[2], &mut rng).unwrap();
+        let signature = protocol
+            .issue_partial(&proof, &issuer_attributes, &mut rng)
+            .unwrap();
+        let merged_cred = user_cred
+            .with_issuer_attributes(&protocol.pp, &issuer_attributes)
+            .unwrap();
+        assert!(signature.verify_with_pairing_checker(
+            &protocol.pp,
+            &protocol.vk,
+            &merged_cred.commitment.cmg1,
+            &merged_cred.commitment.cmg2
+        ));
+        assert!(!signature.verify_with_pairing_checker(
+            &protocol.pp,
+            &protocol.vk,
+            &user_cred.commitment.cmg1,
+            &user_cred.commitment.cmg2
+        ));
+
+        let context = PresentationContext::new(b"verifier", b"nonce-1", 1_700_000_000);
+        let presentation = protocol
+            .show(&merged_cred.commitment, &signature, &context, &mut rng)
+            .unwrap();
+        assert!(protocol.verify(&presentation, &context).unwrap());
+
+        // the issuer only accepts a proof that leaves its indices open
+        let full_proof = protocol.obtain(&user_cred, &mut rng).unwrap();
+        assert!(protocol
+            .issue_partial(&full_proof, &issuer_attributes, &mut rng)
+            .is_err());
+        assert!(matches!(
+            protocol.obtain_partial(&user_cred, &[message_count], &mut rng),
+            Err(CommitmentProofError::InvalidAttributeIndex(_))
+        ));
+        let one = E::ScalarField::from(1u64);
+        for bad in [vec![(2, one), (2, one)], vec![(message_count, one)]] {
+            assert!(user_cred
+                .with_issuer_attributes(&protocol.pp, &bad)
+                .is_err());
+        }
+    }
+
+    fn test_psutt_key_proof<E: Pairing>() {
+        let mut rng = test_rng();
+        let (protocol, proof) = AnonCredProtocol::<E>::new_with_key_proof(4, &mut rng);
+        assert!(protocol.verify_key(&proof, &mut rng).is_ok());
+
+        let other = AnonCredProtocol::<E>::new(4, &mut rng);
+        assert!(matches!(
+            other.verify_key(&proof, &mut rng),
+            Err(CommitmentProofError::KeyProofError(_))
+        ));
+    }
+
+    fn test_psutt_issue_randomness<E: Pairing>() {
+        let mut rng = test_rng();
+        let protocol = AnonCredProtocol::<E>::new(3, &mut rng);
+        let user_cred = UserCred::<E>::new_random_messages(&protocol.pp);
+
+        // each issuance draws a fresh u, so σ1 = g1^u never repeats across requests
+        let proof = protocol.obtain(&user_cred, &mut rng).unwrap();
+        let first = protocol.issue(&proof, &mut rng).unwrap();
+        let second = protocol.issue(&proof, &mut rng).unwrap();
+        assert_ne!(first.sigma1, second.sigma1);
+
+        let issuer_attributes = vec![(1, E::ScalarField::from(7u64))];
+        let proof = protocol.obtain_partial(&user_cred, &[1], &mut rng).unwrap();
+        let first = protocol
+            .issue_partial(&proof, &issuer_attributes, &mut rng)
+            .unwrap();
+        let second = protocol
+            .issue_partial(&proof, &issuer_attributes, &mut rng)
+            .unwrap();
+        assert_ne!(first.sigma1, second.sigma1);
+    }
+
+    utils::curve_tests!(
+        test_psutt_credential_lifecycle,
+        test_psutt_precompute_show,
+        test_psutt_issue_batch,
+        test_psutt_issuer_attributes,
+        test_psutt_issue_randomness,
+        test_psutt_key_proof
+    );
 }
@@ -1,26 +1,51 @@
 // use std::time::Instant;
 
 use crate::commitment::Commitment;
-use crate::keygen::{gen_keys_improved, SecretKeyImproved, VerificationKeyImproved};
+use crate::keygen::{
+    gen_keys_improved, gen_keys_improved_with_proof, SecretKeyImproved, VerificationKeyImproved,
+};
 use crate::proofsystem::{
     CommitmentProof, CommitmentProofError, CommitmentProofG2, CommitmentProofs,
+    PreparedCommitmentProof,
 };
-use crate::publicparams::PublicParams;
+use crate::publicparams::{PublicParams, PublicParamsTables};
 use crate::signature::PSUTTSignatureImproved;
 use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup};
 use ark_ff::UniformRand;
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::ops::Neg;
 use ark_std::rand::Rng;
-use utils::pairing::verify_pairing_equation;
-
+use schnorr::batch::{SchnorrBatch, SchnorrClaim};
+use schnorr::key_proof::KeyProof;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use utils::context::PresentationContext;
+use utils::disclosure::Disclosure;
+use utils::encoding::{Base64Bytes, Base64Point};
+use utils::pairing::{bisect_failures, verify_pairing_equation};
+
+#[serde_as]
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct ShowCredentialImproved<E: Pairing> {
     pub randomized_signature: PSUTTSignatureImproved<E>,
+    #[serde_as(as = "Base64Point")]
     pub cmg1: E::G1Affine,
+    #[serde_as(as = "Base64Bytes")]
     pub proof: Vec<u8>,
+    /// Messages revealed by `finish_show`, empty for `show`
+    pub disclosed: Disclosure<E::ScalarField>,
+}
+
+/// Like `ShowToken` without the G2 commitment, which the improved scheme does not send
+pub struct ShowTokenImproved<E: Pairing> {
+    randomized_signature: PSUTTSignatureImproved<E>,
+    cmg1: E::G1Affine,
+    proof: PreparedCommitmentProof<E>,
 }
 
 /// User credential containing a secret key and commitment
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
 pub struct UserCred<E: Pairing> {
     pub usk: E::ScalarField,
     pub commitment: Commitment<E>,
@@ -51,8 +76,9 @@ impl<E: Pairing> UserCred<E> {
 /// Improved anonymous credential protocol with reduced pairing operations
 pub struct AnonCredProtocolImproved<E: Pairing> {
     pub pp: PublicParams<E>,
-    sk: SecretKeyImproved<E>,
+    sk: Option<SecretKeyImproved<E>>, // None for holders and verifiers
     vk: VerificationKeyImproved<E>,
+    tables: PublicParamsTables<E>,
 }
 
 impl<E: Pairing> AnonCredProtocolImproved<E> {
@@ -61,25 +87,84 @@ impl<E: Pairing> AnonCredProtocolImproved<E> {
         let context = E::ScalarField::rand(rng);
         let pp = PublicParams::<E>::new(&n, &context, rng);
         let (sk, vk) = gen_keys_improved(&pp, rng);
-        Self { pp, sk, vk }
+        let tables = pp.precompute();
+        Self {
+            pp,
+            sk: Some(sk),
+            vk,
+            tables,
+        }
+    }
+
+    /// `new` that also returns a proof the issuer key is well formed, published next to `pp`
+    pub fn new_with_key_proof(n: usize, rng: &mut impl Rng) -> (Self, KeyProof<E>) {
+        let context = E::ScalarField::rand(rng);
+        let pp = PublicParams::<E>::new(&n, &context, rng);
+        let (sk, vk, proof) = gen_keys_improved_with_proof(&pp, rng);
+        (Self::from_keys(pp, sk, vk), proof)
+    }
+
+    /// Create an issuer instance from previously generated keys
+    pub fn from_keys(
+        pp: PublicParams<E>,
+        sk: SecretKeyImproved<E>,
+        vk: VerificationKeyImproved<E>,
+    ) -> Self {
+        let tables = pp.precompute();
+        Self {
+            pp,
+            sk: Some(sk),
+            vk,
+            tables,
+        }
+    }
+
+    /// Create a holder or verifier instance, `issue` fails without the secret key
+    pub fn from_verification_key(pp: PublicParams<E>, vk: VerificationKeyImproved<E>) -> Self {
+        let tables = pp.precompute();
+        Self {
+            pp,
+            sk: None,
+            vk,
+            tables,
+        }
+    }
+
+    /// `from_verification_key` for holders, refuses keys whose proof does not verify
+    pub fn from_verified_verification_key(
+        pp: PublicParams<E>,
+        vk: VerificationKeyImproved<E>,
+        proof: &KeyProof<E>,
+        rng: &mut impl Rng,
+    ) -> Result<Self, CommitmentProofError> {
+        vk.verify_key(&pp, proof, rng)?;
+        Ok(Self::from_verification_key(pp, vk))
     }
 
     /// User generates proof of knowledge for obtaining a credential
     // User generates proof of knowledge in G1
-    pub fn obtain(&self, user_cred: &UserCred<E>) -> Result<Vec<u8>, CommitmentProofError> {
-        CommitmentProofs::pok_commitment_prove(&user_cred.commitment)
+    pub fn obtain(
+        &self,
+        user_cred: &UserCred<E>,
+        rng: &mut impl Rng,
+    ) -> Result<Vec<u8>, CommitmentProofError> {
+        CommitmentProofs::pok_commitment_prove(&user_cred.commitment, rng)
     }
 
     /// Issuer verifies proof of knowledge in G1 and verifies consistency of CM in G1 and G2. Issued Signature on G2 commitment.\
-    pub fn issue(
+    pub fn issue<R: Rng>(
         &self,
         cmg2: &E::G2Affine,
         serialized_proof: &[u8],
+        rng: &mut R,
     ) -> Result<PSUTTSignatureImproved<E>, CommitmentProofError> {
-        let mut rng = ark_std::test_rng();
+        let sk = self
+            .sk
+            .as_ref()
+            .ok_or(CommitmentProofError::MissingSecretKey)?;
 
         // Verify proof of knowledge
-        if !CommitmentProofs::pok_commitment_verify::<E>(serialized_proof)? {
+        if !CommitmentProofs::pok_commitment_verify::<E>(&self.pp, serialized_proof)? {
             return Err(CommitmentProofError::InvalidProof);
         }
 
@@ -102,8 +187,11 @@ impl<E: Pairing> AnonCredProtocolImproved<E> {
         }
 
         // Sign the commitment
-        Ok(PSUTTSignatureImproved::sign(
-            &self.pp, &self.sk, &cmg2, &mut rng,
+        Ok(PSUTTSignatureImproved::sign_with_tables(
+            &self.tables,
+            sk,
+            cmg2,
+            rng,
         ))
     }
 
@@ -112,42 +200,73 @@ impl<E: Pairing> AnonCredProtocolImproved<E> {
         &self,
         commitment: &Commitment<E>,
         signature: &PSUTTSignatureImproved<E>,
+        context: &PresentationContext,
         rng: &mut R,
     ) -> Result<ShowCredentialImproved<E>, CommitmentProofError> {
-        // Generate random values for rerandomization
-        // let start_show = Instant::now();
+        let token = self.precompute_show(commitment, signature, rng);
+        self.finish_show(token, context, &[])
+    }
+
+    /// Offline half of `show`: rerandomizes the commitment and signature and computes the
+    /// Schnorr commitment
+    pub fn precompute_show<R: Rng>(
+        &self,
+        commitment: &Commitment<E>,
+        signature: &PSUTTSignatureImproved<E>,
+        rng: &mut R,
+    ) -> ShowTokenImproved<E> {
         let r_delta = E::ScalarField::rand(rng);
         let u_delta = E::ScalarField::rand(rng);
 
-        // Rerandomize the commitment and signature
-        let randomized_commitment = commitment.randomize_commitment_g2(&r_delta);
-
+        let randomized_commitment =
+            commitment.randomize_commitment_g2_with_tables(&self.tables, &r_delta);
         let randomized_signature = signature.rerandomize(&self.pp, &r_delta, &u_delta);
+        let proof = CommitmentProofs::prepare_commitment_proof_with_tables(
+            &randomized_commitment,
+            &self.tables,
+            rng,
+        );
 
-        // Create proof of knowledge for the rerandomized commitment
-
-        let serialized_proof = CommitmentProofs::pok_commitment_prove(&randomized_commitment)?;
-
-        let show_cred = ShowCredentialImproved {
+        ShowTokenImproved {
             randomized_signature,
             cmg1: randomized_commitment.cmg1,
-            proof: serialized_proof,
-        };
-        // let duration = start_show.elapsed();
-        // println!("Time to show PS_UTT_G2: {:?}", duration);
+            proof,
+        }
+    }
 
-        Ok(show_cred)
+    /// Online half of `show`: binds a precomputed token to the verifier's context and reveals
+    /// the messages at `disclosed`
+    pub fn finish_show(
+        &self,
+        token: ShowTokenImproved<E>,
+        context: &PresentationContext,
+        disclosed: &[usize],
+    ) -> Result<ShowCredentialImproved<E>, CommitmentProofError> {
+        let (proof, disclosed) = token.proof.finish(context, disclosed)?;
+        Ok(ShowCredentialImproved {
+            randomized_signature: token.randomized_signature,
+            cmg1: token.cmg1,
+            proof,
+            disclosed,
+        })
     }
 
     /// Verifier checks credential presentation
     pub fn verify(
         &self,
         cred_show: &ShowCredentialImproved<E>,
+        context: &PresentationContext,
     ) -> Result<bool, CommitmentProofError> {
-        // Verify proof of knowledge
+        // Verify proof of knowledge for this context and the presented commitment
         // let start_verify = Instant::now();
-        if !CommitmentProofs::pok_commitment_verify::<E>(&cred_show.proof)? {
-            return Ok(false);
+        match CommitmentProofs::pok_commitment_verify_with_context::<E>(
+            &self.pp,
+            &cred_show.proof,
+            &cred_show.disclosed,
+            context,
+        )? {
+            Some(commitment) if commitment == cred_show.cmg1 => {}
+            _ => return Ok(false),
         }
 
         let is_valid = cred_show
@@ -158,34 +277,94 @@ impl<E: Pairing> AnonCredProtocolImproved<E> {
         // println!("Time to verify PS_UTT_G2: {:?}", duration);
         Ok(is_valid)
     }
+
+    /// Verifier checks many presentations, each against its own context, and returns the indices
+    /// of the ones that fail. The Schnorr proofs share one randomized MSM and the signatures one
+    /// pairing check, a failing batch is bisected.
+    pub fn verify_batch<R: Rng + Send>(
+        &self,
+        presentations: &[(&ShowCredentialImproved<E>, &PresentationContext)],
+        rng: &mut R,
+    ) -> Vec<usize> {
+        let bases = self.pp.get_g1_bases();
+        let mut failures = Vec::new();
+        let mut proofs: Vec<(usize, CommitmentProof<E>)> = Vec::with_capacity(presentations.len());
+        for (i, (cred_show, context)) in presentations.iter().enumerate() {
+            let proof = match CommitmentProof::<E>::deserialize_compressed(&cred_show.proof[..]) {
+                Ok(proof) => proof,
+                Err(_) => {
+                    failures.push(i);
+                    continue;
+                }
+            };
+            let challenge = CommitmentProofs::context_challenge::<E>(
+                &proof.commitment,
+                &proof.schnorr_commitment.commited_blindings,
+                &cred_show.disclosed,
+                context,
+            );
+            if proof.commitment != cred_show.cmg1
+                || !matches!(challenge, Ok(challenge) if challenge == proof.challenge)
+                || !CommitmentProofs::check_disclosure(&self.pp, &proof, &cred_show.disclosed)
+            {
+                failures.push(i);
+                continue;
+            }
+            proofs.push((i, proof));
+        }
+
+        let positions: Vec<usize> = (0..proofs.len()).collect();
+        let invalid = bisect_failures(&positions, &mut |subset: &[usize]| {
+            let mut batch = SchnorrBatch::<E>::new();
+            let mut signatures = Vec::with_capacity(subset.len());
+            for k in subset {
+                let (i, proof) = &proofs[*k];
+                batch.add_g1(SchnorrClaim {
+                    bases: bases.clone(),
+                    statement: proof.commitment,
+                    commitment: proof.schnorr_commitment.commited_blindings,
+                    challenge: proof.challenge,
+                    responses: proof.responses.clone(),
+                });
+                let cred_show = presentations[*i].0;
+                signatures.push((&cred_show.randomized_signature, &cred_show.cmg1));
+            }
+            batch.check(rng)
+                && PSUTTSignatureImproved::verify_batch(&self.pp, &self.vk, &signatures, rng)
+        });
+        failures.extend(invalid.iter().map(|k| proofs[*k].0));
+        failures.sort_unstable();
+        failures
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ec::pairing::Pairing;
     use ark_std::test_rng;
 
-    #[test]
-    fn test_psutt_credential_lifecycle_improved() {
+    fn test_psutt_credential_lifecycle_improved<E: Pairing>() {
         // Setup phase
         let mut rng = test_rng();
         let message_count = 5;
-        let protocol = AnonCredProtocolImproved::<Bls12_381>::new(message_count, &mut rng);
+        let protocol = AnonCredProtocolImproved::<E>::new(message_count, &mut rng);
 
         // User phase - generate attributes and commitment
-        let user_attributes: Vec<Fr> = (0..message_count).map(|_| Fr::rand(&mut rng)).collect();
-        let user_blinding = Fr::rand(&mut rng);
-        let user_cred = UserCred::<Bls12_381>::new(&protocol.pp, &user_attributes, user_blinding);
+        let user_attributes: Vec<E::ScalarField> = (0..message_count)
+            .map(|_| E::ScalarField::rand(&mut rng))
+            .collect();
+        let user_blinding = E::ScalarField::rand(&mut rng);
+        let user_cred = UserCred::<E>::new(&protocol.pp, &user_attributes, user_blinding);
 
         // Obtain phase - user creates proof of knowledge
         let proof = protocol
-            .obtain(&user_cred)
+            .obtain(&user_cred, &mut rng)
             .expect("Failed to generate proof");
 
         // Issue phase - issuer verifies proof and issues credential
         let signature = protocol
-            .issue(&user_cred.commitment.cmg2, &proof)
+            .issue(&user_cred.commitment.cmg2, &proof, &mut rng)
             .expect("Failed to issue credential");
 
         // Verify original signature
@@ -198,16 +377,166 @@ mod tests {
             "Original signature verification failed"
         );
 
-        // Show phase - user creates presentation
+        // Show phase - user creates presentation for the verifier's context
+        let context = PresentationContext::new(b"verifier", b"nonce-1", 1_700_000_000);
         let presentation = protocol
-            .show(&user_cred.commitment, &signature, &mut rng)
+            .show(&user_cred.commitment, &signature, &context, &mut rng)
             .expect("Failed to generate credential presentation");
 
         // Verify phase
         let is_valid = protocol
-            .verify(&presentation)
+            .verify(&presentation, &context)
             .expect("Verification process failed");
 
         assert!(is_valid, "Credential verification failed");
+
+        // A captured presentation does not verify for another verifier
+        let other_context = PresentationContext::new(b"other verifier", b"nonce-1", 1_700_000_000);
+        assert!(!protocol.verify(&presentation, &other_context).unwrap());
     }
+
+    fn test_psutt_precompute_show_improved<E: Pairing>() {
+        let mut rng = test_rng();
+        let message_count = 3;
+        let protocol = AnonCredProtocolImproved::<E>::new(message_count, &mut rng);
+        let user_cred = UserCred::<E>::new_random_messages(&protocol.pp);
+        let proof = protocol.obtain(&user_cred, &mut rng).unwrap();
+        let signature = protocol
+            .issue(&user_cred.commitment.cmg2, &proof, &mut rng)
+            .unwrap();
+
+        // tokens are made before any verifier context exists
+        let tokens: Vec<ShowTokenImproved<E>> = (0..2)
+            .map(|_| protocol.precompute_show(&user_cred.commitment, &signature, &mut rng))
+            .collect();
+
+        let contexts = [
+            PresentationContext::new(b"verifier", b"nonce-1", 0),
+            PresentationContext::new(b"verifier", b"nonce-2", 0),
+        ];
+        let presentations: Vec<ShowCredentialImproved<E>> = tokens
+            .into_iter()
+            .zip(contexts.iter())
+            .map(|(token, context)| protocol.finish_show(token, context, &[]).unwrap())
+            .collect();
+
+        assert!(protocol.verify(&presentations[0], &contexts[0]).unwrap());
+        assert!(protocol.verify(&presentations[1], &contexts[1]).unwrap());
+        assert!(!protocol.verify(&presentations[0], &contexts[1]).unwrap());
+        assert_ne!(presentations[0].cmg1, presentations[1].cmg1);
+
+        // tokens of the same kind reveal whichever messages the verifier asks for
+        let messages = &user_cred.commitment.messages;
+        let first = protocol.precompute_show(&user_cred.commitment, &signature, &mut rng);
+        let second = protocol.precompute_show(&user_cred.commitment, &signature, &mut rng);
+        let one = protocol.finish_show(first, &contexts[0], &[2]).unwrap();
+        let two = protocol.finish_show(second, &contexts[0], &[0, 1]).unwrap();
+        assert_eq!(one.disclosed.attributes, vec![(2, messages[2])]);
+        assert_eq!(
+            two.disclosed.attributes,
+            vec![(0, messages[0]), (1, messages[1])]
+        );
+        assert!(protocol.verify(&one, &contexts[0]).unwrap());
+        assert!(protocol.verify(&two, &contexts[0]).unwrap());
+
+        // a changed value fails alone and in a batch, the challenge covers the disclosure
+        let mut forged = one.clone();
+        forged.disclosed.attributes[0].1 += E::ScalarField::from(1u64);
+        assert!(!protocol.verify(&forged, &contexts[0]).unwrap());
+        assert_eq!(
+            protocol.verify_batch(
+                &[
+                    (&one, &contexts[0]),
+                    (&forged, &contexts[0]),
+                    (&two, &contexts[0])
+                ],
+                &mut rng
+            ),
+            vec![1]
+        );
+
+        let token = protocol.precompute_show(&user_cred.commitment, &signature, &mut rng);
+        assert!(matches!(
+            protocol.finish_show(token, &contexts[0], &[message_count]),
+            Err(CommitmentProofError::DisclosureError(_))
+        ));
+    }
+
+    fn test_psutt_verify_batch_improved<E: Pairing>() {
+        let mut rng = test_rng();
+        let message_count = 3;
+        let protocol = AnonCredProtocolImproved::<E>::new(message_count, &mut rng);
+
+        let contexts: Vec<PresentationContext> = (0..6u64)
+            .map(|i| PresentationContext::new(b"verifier", &i.to_le_bytes(), 1_700_000_000))
+            .collect();
+        let mut presentations: Vec<ShowCredentialImproved<E>> = contexts
+            .iter()
+            .map(|context| {
+                let messages: Vec<E::ScalarField> = (0..message_count)
+                    .map(|_| E::ScalarField::rand(&mut rng))
+                    .collect();
+                let user_cred =
+                    UserCred::<E>::new(&protocol.pp, &messages, E::ScalarField::rand(&mut rng));
+                let proof = protocol.obtain(&user_cred, &mut rng).unwrap();
+                let signature = protocol
+                    .issue(&user_cred.commitment.cmg2, &proof, &mut rng)
+                    .unwrap();
+                protocol
+                    .show(&user_cred.commitment, &signature, context, &mut rng)
+                    .unwrap()
+            })
+            .collect();
+
+        let batch: Vec<_> = presentations.iter().zip(contexts.iter()).collect();
+        assert!(protocol.verify_batch(&batch, &mut rng).is_empty());
+
+        // presentation 2 is replayed to another context, presentation 5 carries another
+        // presentation's signature, which only the pairing check catches
+        presentations[2] = presentations[1].clone();
+        presentations[5].randomized_signature = presentations[0].randomized_signature.clone();
+
+        let batch: Vec<_> = presentations.iter().zip(contexts.iter()).collect();
+        assert_eq!(protocol.verify_batch(&batch, &mut rng), vec![2, 5]);
+        for (i, (presentation, context)) in batch.iter().enumerate() {
+            assert_eq!(
+                protocol.verify(presentation, context).unwrap(),
+                i != 2 && i != 5
+            );
+        }
+    }
+
+    fn test_psutt_verified_key_improved<E: Pairing>() {
+        let mut rng = test_rng();
+        let (issuer, proof) = AnonCredProtocolImproved::<E>::new_with_key_proof(4, &mut rng);
+        let holder = AnonCredProtocolImproved::from_verified_verification_key(
+            issuer.pp.clone(),
+            issuer.vk.clone(),
+            &proof,
+            &mut rng,
+        )
+        .unwrap();
+        assert!(matches!(
+            holder.issue(&E::G2Affine::zero(), &[], &mut rng),
+            Err(CommitmentProofError::MissingSecretKey)
+        ));
+
+        let other = AnonCredProtocolImproved::<E>::new(4, &mut rng);
+        assert!(matches!(
+            AnonCredProtocolImproved::from_verified_verification_key(
+                other.pp.clone(),
+                other.vk.clone(),
+                &proof,
+                &mut rng,
+            ),
+            Err(CommitmentProofError::KeyProofError(_))
+        ));
+    }
+
+    utils::curve_tests!(
+        test_psutt_credential_lifecycle_improved,
+        test_psutt_precompute_show_improved,
+        test_psutt_verify_batch_improved,
+        test_psutt_verified_key_improved
+    );
 }
@@ -1,13 +1,14 @@
 use crate::proofsystem::{CommitmentProof, CommitmentProofError, CommitmentProofs};
-use crate::publicparams::PublicParams;
+use crate::publicparams::{PublicParams, PublicParamsTables};
 use ark_ec::pairing::Pairing;
 use ark_ec::{CurveGroup, VariableBaseMSM};
 use ark_ff::UniformRand;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::ops::{Add, Mul, Neg};
 use ark_std::rand::Rng;
 use schnorr::schnorr::SchnorrProtocol;
 
-#[derive(Clone)]
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
 pub struct Commitment<E: Pairing> {
     pub pp: PublicParams<E>,
     pub messages: Vec<E::ScalarField>,
@@ -31,6 +32,26 @@ impl<E: Pairing> Commitment<E> {
         }
     }
 
+    /// `new` with the fixed-base tables from `PublicParams::precompute`
+    pub fn new_with_tables(
+        pp: &PublicParams<E>,
+        tables: &PublicParamsTables<E>,
+        messages: &[E::ScalarField],
+        r: &E::ScalarField,
+    ) -> Self {
+        assert!(messages.len() <= pp.n, "m.len should be < ck!");
+        // the message bases come first, so msm stops before g1 and g2
+        let cmg1 = (tables.bases_g1.msm(messages) + tables.g1().mul(r)).into_affine();
+        let cmg2 = (tables.bases_g2.msm(messages) + tables.g2().mul(r)).into_affine();
+        Commitment {
+            pp: pp.clone(),
+            messages: messages.to_vec(),
+            r: *r,
+            cmg1,
+            cmg2,
+        }
+    }
+
     pub fn create_randomized(&self, r_delta: &E::ScalarField) -> Self {
         let new_r = self.r + r_delta;
         let cmg1_delta = (self.cmg1 + self.pp.g1.mul(r_delta)).into_affine();
@@ -45,6 +66,23 @@ impl<E: Pairing> Commitment<E> {
         }
     }
 
+    pub fn create_randomized_with_tables(
+        &self,
+        tables: &PublicParamsTables<E>,
+        r_delta: &E::ScalarField,
+    ) -> Self {
+        let cmg1_delta = (self.cmg1 + tables.g1().mul(r_delta)).into_affine();
+        let cmg2_delta = (self.cmg2 + tables.g2().mul(r_delta)).into_affine();
+
+        Self {
+            pp: self.pp.clone(),
+            messages: self.messages.clone(),
+            r: self.r + r_delta,
+            cmg1: cmg1_delta,
+            cmg2: cmg2_delta,
+        }
+    }
+
     pub fn randomize_commitment_g2(&self, r_delta: &E::ScalarField) -> Self {
         let new_r = self.r + r_delta;
         let cmg1_delta = (self.cmg1 + self.pp.g1.mul(r_delta)).into_affine();
@@ -58,6 +96,22 @@ impl<E: Pairing> Commitment<E> {
         }
     }
 
+    pub fn randomize_commitment_g2_with_tables(
+        &self,
+        tables: &PublicParamsTables<E>,
+        r_delta: &E::ScalarField,
+    ) -> Self {
+        let cmg1_delta = (self.cmg1 + tables.g1().mul(r_delta)).into_affine();
+
+        Self {
+            pp: self.pp.clone(),
+            messages: self.messages.clone(),
+            r: self.r + r_delta,
+            cmg1: cmg1_delta,
+            cmg2: self.cmg2,
+        }
+    }
+
     // get all exponents of the commitment, C([m_1,...,m_n],r)
     pub fn get_exponents(&self) -> Vec<E::ScalarField> {
         let mut exponents: Vec<E::ScalarField> = self.messages.clone();
@@ -66,8 +120,8 @@ impl<E: Pairing> Commitment<E> {
     }
 
     // get pok in g1
-    pub fn prove_opening(&self) -> Result<Vec<u8>, CommitmentProofError> {
-        CommitmentProofs::pok_commitment_prove(&self)
+    pub fn prove_opening(&self, rng: &mut impl Rng) -> Result<Vec<u8>, CommitmentProofError> {
+        CommitmentProofs::pok_commitment_prove(self, rng)
     }
 }
 
@@ -100,24 +154,24 @@ pub fn g2_commit<E: Pairing>(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ec::pairing::Pairing;
 
-    #[test]
-    fn test_randomized_commitment() {
+    fn test_randomized_commitment<E: Pairing>() {
         let mut rng = ark_std::test_rng();
-        let r = Fr::rand(&mut rng);
-        let context = Fr::rand(&mut rng);
-        let pp = PublicParams::<Bls12_381>::new(&4, &context, &mut rng);
-        let messages: Vec<Fr> = (0..pp.n).map(|_| Fr::rand(&mut rng)).collect();
+        let r = E::ScalarField::rand(&mut rng);
+        let context = E::ScalarField::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&4, &context, &mut rng);
+        let messages: Vec<E::ScalarField> =
+            (0..pp.n).map(|_| E::ScalarField::rand(&mut rng)).collect();
         let commitment = Commitment::new(&pp, &messages, &r);
 
-        let r_delta = Fr::rand(&mut rng);
+        let r_delta = E::ScalarField::rand(&mut rng);
         let randomized_commitment = commitment.create_randomized(&r_delta);
 
         let cmg1 = commitment.cmg1.add(pp.g1.mul(r_delta));
         let cmg1_rand = randomized_commitment.cmg1;
 
-        let challenge = Fr::rand(&mut rng);
+        let challenge = E::ScalarField::rand(&mut rng);
 
         // Let's test opening proof
         let blinding_commitment = SchnorrProtocol::commit(&pp.get_g1_bases(), &mut rng);
@@ -137,4 +191,35 @@ mod tests {
 
         assert!(is_valid);
     }
+
+    fn test_commitment_with_tables<E: Pairing>() {
+        let mut rng = ark_std::test_rng();
+        let r = E::ScalarField::rand(&mut rng);
+        let context = E::ScalarField::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&4, &context, &mut rng);
+        let tables = pp.precompute();
+        let messages: Vec<E::ScalarField> =
+            (0..pp.n).map(|_| E::ScalarField::rand(&mut rng)).collect();
+
+        let commitment = Commitment::new(&pp, &messages, &r);
+        let with_tables = Commitment::new_with_tables(&pp, &tables, &messages, &r);
+        assert_eq!(with_tables.cmg1, commitment.cmg1);
+        assert_eq!(with_tables.cmg2, commitment.cmg2);
+
+        // fewer messages than keys commit to a prefix, as in g1_commit
+        let short = Commitment::new_with_tables(&pp, &tables, &messages[..2], &r);
+        assert_eq!(short.cmg1, g1_commit(&pp, &messages[..2], &r));
+
+        let r_delta = E::ScalarField::rand(&mut rng);
+        let randomized = commitment.create_randomized(&r_delta);
+        let randomized_with_tables = commitment.create_randomized_with_tables(&tables, &r_delta);
+        assert_eq!(randomized_with_tables.cmg1, randomized.cmg1);
+        assert_eq!(randomized_with_tables.cmg2, randomized.cmg2);
+        assert_eq!(randomized_with_tables.r, randomized.r);
+        let randomized_g2 = commitment.randomize_commitment_g2_with_tables(&tables, &r_delta);
+        assert_eq!(randomized_g2.cmg1, randomized.cmg1);
+        assert_eq!(randomized_g2.cmg2, commitment.cmg2);
+    }
+
+    utils::curve_tests!(test_randomized_commitment, test_commitment_with_tables);
 }
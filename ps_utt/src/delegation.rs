@@ -0,0 +1,738 @@
+use crate::commitment::{g1_commit, Commitment};
+use crate::publicparams::PublicParams;
+use ark_ec::pairing::Pairing;
+use ark_ec::{AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_ff::{Field, UniformRand, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::ops::{Mul, Neg};
+use ark_std::rand::Rng;
+use ark_std::One;
+use schnorr::schnorr::{SchnorrCommitment, SchnorrProtocol};
+use thiserror::Error;
+use utils::context::PresentationContext;
+
+// Delegatable credentials in the style of Crites-Lysyanskaya 2019. Each level of the chain is a
+// mercurial signature by the delegator's key on (g1, cm, pk), where cm is the G1 form of the level's
+// attribute commitment and pk the delegatee's key. PS-UTT signatures only sign committed scalars,
+// while a chain has to sign the next key as group elements, so links use the mercurial signature
+// and keep the PS-UTT commitments for the attributes. A show moves every link to a fresh
+// representative (g1, cm, pk)^mu and converts the next signature to the key pk^mu, so the verifier
+// sees the root key, the chain length and the disclosed attributes, but no intermediate key.
+// Keys lose two elements per level, which caps the chain at `max_depth`.
+
+const SHOW_DOMAIN: &[u8] = b"ps-utt-delegation-show";
+
+/// Possible errors that can occur while delegating or showing a chain
+#[derive(Error, Debug)]
+pub enum DelegationError {
+    #[error("Chain already has the maximum length {0}")]
+    ChainTooLong(usize),
+    #[error("Key length mismatch: expected {expected}, found {found}")]
+    KeyLength { expected: usize, found: usize },
+    #[error("Public key halves are not consistent")]
+    InvalidKey,
+    #[error("Attribute count mismatch: expected {expected}, found {found}")]
+    AttributeCount { expected: usize, found: usize },
+    #[error("Disclosure given for {found} levels, chain has {expected}")]
+    LevelCount { expected: usize, found: usize },
+    #[error("Invalid attribute index {0}")]
+    InvalidAttributeIndex(usize),
+    #[error("Invalid delegation chain")]
+    InvalidChain,
+    #[error("Serialization error: {0}")]
+    SerializationError(#[from] ark_serialize::SerializationError),
+}
+
+/// Commitment parameters shared by every level and the maximum chain length
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct DelegationParams<E: Pairing> {
+    pub pp: PublicParams<E>,
+    pub max_depth: usize,
+}
+
+impl<E: Pairing> DelegationParams<E> {
+    pub fn new(n: usize, max_depth: usize, rng: &mut impl Rng) -> Self {
+        let context = E::ScalarField::rand(rng);
+        Self {
+            pp: PublicParams::new(&n, &context, rng),
+            max_depth,
+        }
+    }
+
+    /// number of key elements at `level`, the root is level 0
+    pub fn key_length(&self, level: usize) -> usize {
+        2 * (self.max_depth - level) + 1
+    }
+}
+
+/// Key in G1 to be signed as a message and the same exponents in G2 to verify the next level
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct DelegationPublicKey<E: Pairing> {
+    pub pk1: Vec<E::G1Affine>,
+    pub pk2: Vec<E::G2Affine>,
+}
+
+impl<E: Pairing> DelegationPublicKey<E> {
+    /// e(pk1_j, g2) = e(g1, pk2_j) for every element
+    fn is_consistent(&self, pp: &PublicParams<E>) -> bool {
+        let neg_g1 = pp.g1.into_group().neg().into_affine();
+        self.pk1.len() == self.pk2.len()
+            && self.pk1.iter().zip(&self.pk2).all(|(k1, k2)| {
+                !k1.is_zero() && E::multi_pairing([*k1, neg_g1], [pp.g2, *k2]).is_zero()
+            })
+    }
+}
+
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct DelegationKey<E: Pairing> {
+    sk: Vec<E::ScalarField>,
+    pub pk: DelegationPublicKey<E>,
+}
+
+impl<E: Pairing> DelegationKey<E> {
+    /// Fresh key for a holder at `level`, the delegatee sends `pk` to its delegator
+    pub fn generate(
+        params: &DelegationParams<E>,
+        level: usize,
+        rng: &mut impl Rng,
+    ) -> Result<Self, DelegationError> {
+        if level > params.max_depth {
+            return Err(DelegationError::ChainTooLong(params.max_depth));
+        }
+        let pp = &params.pp;
+        let sk: Vec<E::ScalarField> = (0..params.key_length(level))
+            .map(|_| E::ScalarField::rand(rng))
+            .collect();
+        let pk1 = sk.iter().map(|x| pp.g1.mul(x)).collect::<Vec<_>>();
+        let pk2 = sk.iter().map(|x| pp.g2.mul(x)).collect::<Vec<_>>();
+        Ok(Self {
+            sk,
+            pk: DelegationPublicKey {
+                pk1: E::G1::normalize_batch(&pk1),
+                pk2: E::G2::normalize_batch(&pk2),
+            },
+        })
+    }
+}
+
+/// Mercurial signature on a G1 vector under a G2 key. The message and the key can both be moved
+/// to another representative of their class
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct ChainSignature<E: Pairing> {
+    pub z: E::G1Affine,
+    pub y: E::G1Affine,
+    pub y_hat: E::G2Affine,
+}
+
+impl<E: Pairing> ChainSignature<E> {
+    fn sign(
+        pp: &PublicParams<E>,
+        sk: &[E::ScalarField],
+        message: &[E::G1Affine],
+        rng: &mut impl Rng,
+    ) -> Self {
+        let y = nonzero_scalar::<E>(rng);
+        let y_inv = y.inverse().unwrap();
+        Self {
+            z: E::G1::msm_unchecked(message, sk).mul(y).into_affine(),
+            y: pp.g1.mul(y_inv).into_affine(),
+            y_hat: pp.g2.mul(y_inv).into_affine(),
+        }
+    }
+
+    /// prod e(M_i, X_i) = e(Z, Y_hat) and e(Y, g2) = e(g1, Y_hat)
+    fn verify(&self, pp: &PublicParams<E>, pk: &[E::G2Affine], message: &[E::G1Affine]) -> bool {
+        if message.len() != pk.len() || self.y_hat.is_zero() || message.iter().any(|m| m.is_zero())
+        {
+            return false;
+        }
+        let mut lefts = message.to_vec();
+        lefts.push(self.z.into_group().neg().into_affine());
+        let mut rights = pk.to_vec();
+        rights.push(self.y_hat);
+        let neg_g1 = pp.g1.into_group().neg().into_affine();
+        E::multi_pairing(lefts, rights).is_zero()
+            && E::multi_pairing([self.y, neg_g1], [pp.g2, self.y_hat]).is_zero()
+    }
+
+    /// signature on the message times `mu` under the key times `rho`
+    fn adapt(&self, mu: &E::ScalarField, rho: &E::ScalarField, rng: &mut impl Rng) -> Self {
+        let psi = nonzero_scalar::<E>(rng);
+        let psi_inv = psi.inverse().unwrap();
+        Self {
+            z: self.z.mul(psi * mu * rho).into_affine(),
+            y: self.y.mul(psi_inv).into_affine(),
+            y_hat: self.y_hat.mul(psi_inv).into_affine(),
+        }
+    }
+}
+
+/// One level of a chain: the delegatee's key, its attribute commitment and the delegator's
+/// signature on both
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct ChainLink<E: Pairing> {
+    pub pk: DelegationPublicKey<E>,
+    pub commitment: Commitment<E>,
+    pub signature: ChainSignature<E>,
+}
+
+/// Holder of the root key, delegates level 1
+pub struct RootAuthority<E: Pairing> {
+    key: DelegationKey<E>,
+}
+
+impl<E: Pairing> RootAuthority<E> {
+    pub fn new(params: &DelegationParams<E>, rng: &mut impl Rng) -> Self {
+        let key = DelegationKey::generate(params, 0, rng).expect("level 0 is always in range");
+        Self { key }
+    }
+
+    pub fn public_key(&self) -> &DelegationPublicKey<E> {
+        &self.key.pk
+    }
+
+    /// Sign the first link of a chain for `delegatee` with the given level attributes
+    pub fn delegate(
+        &self,
+        params: &DelegationParams<E>,
+        delegatee: &DelegationPublicKey<E>,
+        attributes: &[E::ScalarField],
+        rng: &mut impl Rng,
+    ) -> Result<Vec<ChainLink<E>>, DelegationError> {
+        Ok(vec![sign_link(
+            params, &self.key, 0, delegatee, attributes, rng,
+        )?])
+    }
+}
+
+/// A verified chain from the root down to the holder's key
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct DelegatedCredential<E: Pairing> {
+    pub links: Vec<ChainLink<E>>,
+    key: DelegationKey<E>,
+}
+
+impl<E: Pairing> DelegatedCredential<E> {
+    /// Delegatee checks the chain it received ends in its own key
+    pub fn new(
+        params: &DelegationParams<E>,
+        root: &DelegationPublicKey<E>,
+        links: Vec<ChainLink<E>>,
+        key: DelegationKey<E>,
+    ) -> Result<Self, DelegationError> {
+        let ends_in_key = links.last().is_some_and(|link| link.pk.pk1 == key.pk.pk1);
+        if !ends_in_key || !verify_chain(params, root, &links) {
+            return Err(DelegationError::InvalidChain);
+        }
+        Ok(Self { links, key })
+    }
+
+    /// level of this credential, the root's delegatees are level 1
+    pub fn level(&self) -> usize {
+        self.links.len()
+    }
+
+    /// Extend the chain by one level for `delegatee`
+    pub fn delegate(
+        &self,
+        params: &DelegationParams<E>,
+        delegatee: &DelegationPublicKey<E>,
+        attributes: &[E::ScalarField],
+        rng: &mut impl Rng,
+    ) -> Result<Vec<ChainLink<E>>, DelegationError> {
+        let link = sign_link(params, &self.key, self.level(), delegatee, attributes, rng)?;
+        let mut links = self.links.clone();
+        links.push(link);
+        Ok(links)
+    }
+
+    /// Present the chain with the attributes at `disclosed[i]` of level i + 1 revealed, and prove
+    /// possession of the holder's key for the verifier's context
+    pub fn show(
+        &self,
+        params: &DelegationParams<E>,
+        disclosed: &[Vec<usize>],
+        context: &PresentationContext,
+        rng: &mut impl Rng,
+    ) -> Result<ChainPresentation<E>, DelegationError> {
+        let pp = &params.pp;
+        if disclosed.len() != self.links.len() {
+            return Err(DelegationError::LevelCount {
+                expected: self.links.len(),
+                found: disclosed.len(),
+            });
+        }
+        for indices in disclosed {
+            check_indices(pp.n, indices)?;
+        }
+
+        let mus: Vec<E::ScalarField> = (0..self.links.len())
+            .map(|_| nonzero_scalar::<E>(rng))
+            .collect();
+        let mut links = Vec::with_capacity(self.links.len());
+        let mut schnorr_commitments = Vec::with_capacity(self.links.len());
+        let mut witnesses = Vec::with_capacity(self.links.len());
+        for (i, (link, indices)) in self.links.iter().zip(disclosed).enumerate() {
+            let mu = mus[i];
+            let rho = if i == 0 {
+                E::ScalarField::one()
+            } else {
+                mus[i - 1]
+            };
+            let last = i + 1 == self.links.len();
+            let messages = &link.commitment.messages;
+
+            let key1 = link.pk.pk1.iter().map(|k| k.mul(mu)).collect::<Vec<_>>();
+            let key2 = if last {
+                Vec::new()
+            } else {
+                let key2 = link.pk.pk2.iter().map(|k| k.mul(mu)).collect::<Vec<_>>();
+                E::G2::normalize_batch(&key2)
+            };
+            let presentation = LinkPresentation {
+                generator: pp.g1.mul(mu).into_affine(),
+                commitment: link.commitment.cmg1.mul(mu).into_affine(),
+                key1: E::G1::normalize_batch(&key1),
+                key2,
+                signature: link.signature.adapt(&mu, &rho, rng),
+                disclosed: indices.iter().map(|j| (*j, messages[*j])).collect(),
+                schnorr_commitments: Vec::new(),
+                responses: Vec::new(),
+            };
+
+            // witnesses mu, mu m_j for the hidden attributes, mu r
+            let mut level_witnesses = vec![mu];
+            level_witnesses.extend(
+                (0..pp.n)
+                    .filter(|j| !indices.contains(j))
+                    .map(|j| mu * messages[j]),
+            );
+            level_witnesses.push(mu * link.commitment.r);
+            let blindings: Vec<E::ScalarField> = (0..level_witnesses.len())
+                .map(|_| E::ScalarField::rand(rng))
+                .collect();
+            let (generator_bases, commitment_bases) = statement_bases(pp, &presentation);
+            let commitments = vec![
+                SchnorrProtocol::commit_with_prepared_blindings(&generator_bases, &blindings[..1])
+                    .commited_blindings,
+                SchnorrProtocol::commit_with_prepared_blindings(&commitment_bases, &blindings)
+                    .commited_blindings,
+            ];
+
+            links.push(presentation);
+            schnorr_commitments.push(commitments);
+            witnesses.push((level_witnesses, blindings));
+        }
+        for (link, commitments) in links.iter_mut().zip(schnorr_commitments) {
+            link.schnorr_commitments = commitments;
+        }
+
+        // key1[0] of the last level is g1^{mu x_0}
+        let possession_witness = mus[mus.len() - 1] * self.key.sk[0];
+        let possession = SchnorrProtocol::commit(&[pp.g1], rng);
+
+        let challenge = show_challenge(&links, &possession.commited_blindings, context)?;
+        for (link, (level_witnesses, blindings)) in links.iter_mut().zip(witnesses) {
+            let commitment = SchnorrCommitment::<E::G1Affine> {
+                random_blindings: blindings,
+                commited_blindings: E::G1Affine::zero(),
+            };
+            link.responses = SchnorrProtocol::prove(&commitment, &level_witnesses, &challenge).0;
+        }
+        let possession_response =
+            SchnorrProtocol::prove(&possession, &[possession_witness], &challenge).0[0];
+
+        Ok(ChainPresentation {
+            links,
+            possession_commitment: possession.commited_blindings,
+            possession_response,
+        })
+    }
+}
+
+/// One randomized level of a presented chain
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct LinkPresentation<E: Pairing> {
+    /// g1^mu, fixes the representative the level was moved to
+    pub generator: E::G1Affine,
+    pub commitment: E::G1Affine,
+    pub key1: Vec<E::G1Affine>,
+    /// G2 key verifying the next level, empty at the last level
+    pub key2: Vec<E::G2Affine>,
+    pub signature: ChainSignature<E>,
+    pub disclosed: Vec<(usize, E::ScalarField)>,
+    pub schnorr_commitments: Vec<E::G1Affine>,
+    pub responses: Vec<E::ScalarField>,
+}
+
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct ChainPresentation<E: Pairing> {
+    pub links: Vec<LinkPresentation<E>>,
+    pub possession_commitment: E::G1Affine,
+    pub possession_response: E::ScalarField,
+}
+
+impl<E: Pairing> ChainPresentation<E> {
+    /// Verifier checks the chain leads back to `root` and is bound to its context
+    pub fn verify(
+        &self,
+        params: &DelegationParams<E>,
+        root: &DelegationPublicKey<E>,
+        context: &PresentationContext,
+    ) -> Result<bool, DelegationError> {
+        let pp = &params.pp;
+        if self.links.is_empty() || self.links.len() > params.max_depth {
+            return Ok(false);
+        }
+        let challenge = show_challenge(&self.links, &self.possession_commitment, context)?;
+
+        for (i, link) in self.links.iter().enumerate() {
+            let last = i + 1 == self.links.len();
+            let key_length = params.key_length(i + 1);
+            let key2_length = if last { 0 } else { key_length };
+            if link.key1.len() != key_length
+                || link.key2.len() != key2_length
+                || link.generator.is_zero()
+                || link.schnorr_commitments.len() != 2
+                || check_indices(pp.n, &link.disclosed_indices()).is_err()
+            {
+                return Ok(false);
+            }
+            if !last {
+                let key = DelegationPublicKey::<E> {
+                    pk1: link.key1.clone(),
+                    pk2: link.key2.clone(),
+                };
+                if !key.is_consistent(pp) {
+                    return Ok(false);
+                }
+            }
+
+            let signer = if i == 0 {
+                &root.pk2
+            } else {
+                &self.links[i - 1].key2
+            };
+            let message = link_message::<E>(&link.generator, &link.commitment, &link.key1);
+            if !link.signature.verify(pp, signer, &message) {
+                return Ok(false);
+            }
+
+            let (generator_bases, commitment_bases) = statement_bases(pp, link);
+            if link.responses.len() != commitment_bases.len()
+                || !SchnorrProtocol::verify_schnorr(
+                    &generator_bases,
+                    &link.generator,
+                    &link.schnorr_commitments[0],
+                    &link.responses[..1],
+                    &challenge,
+                )
+                || !SchnorrProtocol::verify_schnorr(
+                    &commitment_bases,
+                    &link.commitment,
+                    &link.schnorr_commitments[1],
+                    &link.responses,
+                    &challenge,
+                )
+            {
+                return Ok(false);
+            }
+        }
+
+        let holder_key = &self.links[self.links.len() - 1].key1[0];
+        Ok(SchnorrProtocol::verify_schnorr(
+            &[pp.g1],
+            holder_key,
+            &self.possession_commitment,
+            &[self.possession_response],
+            &challenge,
+        ))
+    }
+}
+
+impl<E: Pairing> LinkPresentation<E> {
+    fn disclosed_indices(&self) -> Vec<usize> {
+        self.disclosed.iter().map(|(j, _)| *j).collect()
+    }
+}
+
+fn sign_link<E: Pairing>(
+    params: &DelegationParams<E>,
+    signer: &DelegationKey<E>,
+    signer_level: usize,
+    delegatee: &DelegationPublicKey<E>,
+    attributes: &[E::ScalarField],
+    rng: &mut impl Rng,
+) -> Result<ChainLink<E>, DelegationError> {
+    let pp = &params.pp;
+    if signer_level >= params.max_depth {
+        return Err(DelegationError::ChainTooLong(params.max_depth));
+    }
+    let expected = params.key_length(signer_level + 1);
+    if delegatee.pk1.len() != expected {
+        return Err(DelegationError::KeyLength {
+            expected,
+            found: delegatee.pk1.len(),
+        });
+    }
+    if !delegatee.is_consistent(pp) {
+        return Err(DelegationError::InvalidKey);
+    }
+    if attributes.len() != pp.n {
+        return Err(DelegationError::AttributeCount {
+            expected: pp.n,
+            found: attributes.len(),
+        });
+    }
+
+    let r = E::ScalarField::rand(rng);
+    let commitment = Commitment::new(pp, &attributes.to_vec(), &r);
+    let message = link_message::<E>(&pp.g1, &commitment.cmg1, &delegatee.pk1);
+    let signature = ChainSignature::sign(pp, &signer.sk, &message, rng);
+    Ok(ChainLink {
+        pk: delegatee.clone(),
+        commitment,
+        signature,
+    })
+}
+
+fn verify_chain<E: Pairing>(
+    params: &DelegationParams<E>,
+    root: &DelegationPublicKey<E>,
+    links: &[ChainLink<E>],
+) -> bool {
+    let pp = &params.pp;
+    links.len() <= params.max_depth
+        && links.iter().enumerate().all(|(i, link)| {
+            let signer = if i == 0 {
+                &root.pk2
+            } else {
+                &links[i - 1].pk.pk2
+            };
+            let opening = &link.commitment;
+            link.pk.pk1.len() == params.key_length(i + 1)
+                && link.pk.is_consistent(pp)
+                && opening.messages.len() == pp.n
+                && g1_commit::<E>(pp, &opening.messages, &opening.r) == opening.cmg1
+                && link.signature.verify(
+                    pp,
+                    signer,
+                    &link_message::<E>(&pp.g1, &opening.cmg1, &link.pk.pk1),
+                )
+        })
+}
+
+/// (g1, cm, pk1), the vector a link signs
+fn link_message<E: Pairing>(
+    generator: &E::G1Affine,
+    commitment: &E::G1Affine,
+    key: &[E::G1Affine],
+) -> Vec<E::G1Affine> {
+    let mut message = vec![*generator, *commitment];
+    message.extend_from_slice(key);
+    message
+}
+
+/// bases of g1^mu and of cm^mu = D^mu prod_hidden ck_j^{mu m_j} g1^{mu r}, with D the product of
+/// the disclosed attributes
+fn statement_bases<E: Pairing>(
+    pp: &PublicParams<E>,
+    link: &LinkPresentation<E>,
+) -> (Vec<E::G1Affine>, Vec<E::G1Affine>) {
+    let indices = link.disclosed_indices();
+    let disclosed_bases: Vec<E::G1Affine> = indices.iter().map(|j| pp.ckg1[*j]).collect();
+    let disclosed_values: Vec<E::ScalarField> = link.disclosed.iter().map(|(_, m)| *m).collect();
+    let d = E::G1::msm_unchecked(&disclosed_bases, &disclosed_values).into_affine();
+
+    let mut commitment_bases = vec![d];
+    commitment_bases.extend(
+        (0..pp.n)
+            .filter(|j| !indices.contains(j))
+            .map(|j| pp.ckg1[j]),
+    );
+    commitment_bases.push(pp.g1);
+    (vec![pp.g1], commitment_bases)
+}
+
+fn show_challenge<E: Pairing>(
+    links: &[LinkPresentation<E>],
+    possession_commitment: &E::G1Affine,
+    context: &PresentationContext,
+) -> Result<E::ScalarField, DelegationError> {
+    let mut transcript = Vec::new();
+    for link in links {
+        link.generator.serialize_compressed(&mut transcript)?;
+        link.commitment.serialize_compressed(&mut transcript)?;
+        link.key1.serialize_compressed(&mut transcript)?;
+        link.key2.serialize_compressed(&mut transcript)?;
+        link.signature.serialize_compressed(&mut transcript)?;
+        link.disclosed.serialize_compressed(&mut transcript)?;
+        link.schnorr_commitments
+            .serialize_compressed(&mut transcript)?;
+    }
+    possession_commitment.serialize_compressed(&mut transcript)?;
+    Ok(context.challenge(SHOW_DOMAIN, &transcript))
+}
+
+/// indices must be in range and distinct
+fn check_indices(n: usize, indices: &[usize]) -> Result<(), DelegationError> {
+    for (k, &i) in indices.iter().enumerate() {
+        if i >= n || indices[..k].contains(&i) {
+            return Err(DelegationError::InvalidAttributeIndex(i));
+        }
+    }
+    Ok(())
+}
+
+fn nonzero_scalar<E: Pairing>(rng: &mut impl Rng) -> E::ScalarField {
+    loop {
+        let x = E::ScalarField::rand(rng);
+        if !x.is_zero() {
+            return x;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ec::pairing::Pairing;
+
+    fn attributes<E: Pairing>(n: usize, rng: &mut impl Rng) -> Vec<E::ScalarField> {
+        (0..n).map(|_| E::ScalarField::rand(rng)).collect()
+    }
+
+    fn test_delegation_chain_show<E: Pairing>() {
+        let mut rng = ark_std::test_rng();
+        let params = DelegationParams::<E>::new(3, 3, &mut rng);
+        let root = RootAuthority::new(&params, &mut rng);
+
+        // root CA -> department -> employee
+        let department_key = DelegationKey::generate(&params, 1, &mut rng).unwrap();
+        let links = root
+            .delegate(
+                &params,
+                &department_key.pk,
+                &attributes::<E>(3, &mut rng),
+                &mut rng,
+            )
+            .unwrap();
+        let department =
+            DelegatedCredential::new(&params, root.public_key(), links, department_key).unwrap();
+
+        let employee_key = DelegationKey::generate(&params, 2, &mut rng).unwrap();
+        let employee_attributes = attributes::<E>(3, &mut rng);
+        let links = department
+            .delegate(&params, &employee_key.pk, &employee_attributes, &mut rng)
+            .unwrap();
+        let employee =
+            DelegatedCredential::new(&params, root.public_key(), links, employee_key).unwrap();
+        assert_eq!(employee.level(), 2);
+
+        let context = PresentationContext::new(b"verifier", b"nonce", 0);
+        let show = employee
+            .show(&params, &[vec![0], vec![1, 2]], &context, &mut rng)
+            .unwrap();
+        assert!(show.verify(&params, root.public_key(), &context).unwrap());
+        assert_eq!(
+            show.links[1].disclosed,
+            vec![(1, employee_attributes[1]), (2, employee_attributes[2])]
+        );
+        // the department's key does not appear in the presentation
+        let department_pk = &department.links[0].pk.pk1;
+        assert!(show.links.iter().all(|l| l.key1 != *department_pk));
+
+        let other_context = PresentationContext::new(b"verifier", b"nonce-2", 0);
+        assert!(!show
+            .verify(&params, root.public_key(), &other_context)
+            .unwrap());
+
+        // a disclosed attribute that was not signed
+        let mut forged = show.clone();
+        forged.links[1].disclosed[0].1 += E::ScalarField::one();
+        assert!(!forged.verify(&params, root.public_key(), &context).unwrap());
+
+        // another root
+        let other_root = RootAuthority::<E>::new(&params, &mut rng);
+        assert!(!show
+            .verify(&params, other_root.public_key(), &context)
+            .unwrap());
+
+        // a dropped level leaves the employee key unsigned by a level 1 key
+        let mut truncated = show.clone();
+        truncated.links.remove(0);
+        assert!(!truncated
+            .verify(&params, root.public_key(), &context)
+            .unwrap());
+    }
+
+    fn test_delegation_limits<E: Pairing>() {
+        let mut rng = ark_std::test_rng();
+        let params = DelegationParams::<E>::new(2, 1, &mut rng);
+        let root = RootAuthority::new(&params, &mut rng);
+
+        // a key for the wrong level
+        let wrong_level = DelegationKey::generate(&params, 0, &mut rng).unwrap();
+        assert!(matches!(
+            root.delegate(
+                &params,
+                &wrong_level.pk,
+                &attributes::<E>(2, &mut rng),
+                &mut rng
+            ),
+            Err(DelegationError::KeyLength {
+                expected: 1,
+                found: 3
+            })
+        ));
+
+        let key = DelegationKey::generate(&params, 1, &mut rng).unwrap();
+        let links = root
+            .delegate(&params, &key.pk, &attributes::<E>(2, &mut rng), &mut rng)
+            .unwrap();
+        let credential = DelegatedCredential::new(&params, root.public_key(), links, key).unwrap();
+
+        // max_depth 1 stops the chain at the root's delegatees
+        assert!(matches!(
+            DelegationKey::<E>::generate(&params, 2, &mut rng),
+            Err(DelegationError::ChainTooLong(1))
+        ));
+        let sibling = DelegationKey::generate(&params, 1, &mut rng).unwrap();
+        assert!(matches!(
+            credential.delegate(
+                &params,
+                &sibling.pk,
+                &attributes::<E>(2, &mut rng),
+                &mut rng
+            ),
+            Err(DelegationError::ChainTooLong(1))
+        ));
+
+        // a chain signed by someone other than the root
+        let other_root = RootAuthority::new(&params, &mut rng);
+        let links = other_root
+            .delegate(
+                &params,
+                &sibling.pk,
+                &attributes::<E>(2, &mut rng),
+                &mut rng,
+            )
+            .unwrap();
+        assert!(matches!(
+            DelegatedCredential::new(&params, root.public_key(), links, sibling),
+            Err(DelegationError::InvalidChain)
+        ));
+
+        let context = PresentationContext::new(b"verifier", b"nonce", 0);
+        let show = credential
+            .show(&params, &[vec![]], &context, &mut rng)
+            .unwrap();
+        assert!(show.verify(&params, root.public_key(), &context).unwrap());
+        assert!(matches!(
+            credential.show(&params, &[vec![2]], &context, &mut rng),
+            Err(DelegationError::InvalidAttributeIndex(2))
+        ));
+    }
+
+    utils::curve_tests!(test_delegation_chain_show, test_delegation_limits);
+}
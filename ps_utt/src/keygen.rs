@@ -2,20 +2,39 @@ use crate::publicparams::PublicParams;
 use ark_ec::pairing::Pairing;
 use ark_ec::CurveGroup;
 use ark_ff::UniformRand;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::ops::Mul;
 use ark_std::rand::Rng;
+use schnorr::key_proof::{KeyProof, KeyProofError, KeyStatement};
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use utils::encoding::Base64Point;
+use zeroize::Zeroizing;
 
+const KEY_PROOF_DOMAIN: &[u8] = b"ps-utt-key";
+const KEY_PROOF_DOMAIN_IMPROVED: &[u8] = b"ps-utt-improved-key";
+
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
 pub struct SecretKey<E: Pairing> {
     pub sk: E::G1Affine,
 }
+#[serde_as]
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct VerificationKey<E: Pairing> {
+    #[serde_as(as = "Base64Point")]
     pub vk: E::G2Affine,
 }
 
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
 pub struct SecretKeyImproved<E: Pairing> {
     pub sk: E::G2Affine,
 }
+#[serde_as]
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct VerificationKeyImproved<E: Pairing> {
+    #[serde_as(as = "Base64Point")]
     pub vk: E::G1Affine,
 }
 
@@ -23,7 +42,26 @@ pub fn gen_keys<E: Pairing>(
     pp: &PublicParams<E>,
     rng: &mut impl Rng,
 ) -> (SecretKey<E>, VerificationKey<E>) {
-    let x = E::ScalarField::rand(rng);
+    let x = Zeroizing::new(E::ScalarField::rand(rng));
+    keys_from_exponent(pp, &x)
+}
+
+/// `gen_keys` together with the proof holders check before `obtain`. The secret key keeps only
+/// g1^x, so the proof can only be made here while x is known.
+pub fn gen_keys_with_proof<E: Pairing>(
+    pp: &PublicParams<E>,
+    rng: &mut impl Rng,
+) -> (SecretKey<E>, VerificationKey<E>, KeyProof<E>) {
+    let x = Zeroizing::new(E::ScalarField::rand(rng));
+    let (sk, vk) = keys_from_exponent(pp, &x);
+    let proof = KeyProof::prove(KEY_PROOF_DOMAIN, &vk.key_statement(pp), &[*x], rng);
+    (sk, vk, proof)
+}
+
+fn keys_from_exponent<E: Pairing>(
+    pp: &PublicParams<E>,
+    x: &E::ScalarField,
+) -> (SecretKey<E>, VerificationKey<E>) {
     let sk = pp.g1.mul(x).into_affine();
     let vk = pp.g2.mul(x).into_affine();
     (SecretKey { sk }, VerificationKey { vk })
@@ -33,41 +71,141 @@ pub fn gen_keys_improved<E: Pairing>(
     pp: &PublicParams<E>,
     rng: &mut impl Rng,
 ) -> (SecretKeyImproved<E>, VerificationKeyImproved<E>) {
-    let x = E::ScalarField::rand(rng);
+    let x = Zeroizing::new(E::ScalarField::rand(rng));
+    keys_from_exponent_improved(pp, &x)
+}
+
+/// `gen_keys_improved` together with the proof holders check before `obtain`
+pub fn gen_keys_improved_with_proof<E: Pairing>(
+    pp: &PublicParams<E>,
+    rng: &mut impl Rng,
+) -> (
+    SecretKeyImproved<E>,
+    VerificationKeyImproved<E>,
+    KeyProof<E>,
+) {
+    let x = Zeroizing::new(E::ScalarField::rand(rng));
+    let (sk, vk) = keys_from_exponent_improved(pp, &x);
+    let proof = KeyProof::prove(KEY_PROOF_DOMAIN_IMPROVED, &vk.key_statement(pp), &[*x], rng);
+    (sk, vk, proof)
+}
+
+fn keys_from_exponent_improved<E: Pairing>(
+    pp: &PublicParams<E>,
+    x: &E::ScalarField,
+) -> (SecretKeyImproved<E>, VerificationKeyImproved<E>) {
     let sk = pp.g2.mul(x).into_affine();
     let vk = pp.g1.mul(x).into_affine();
     (SecretKeyImproved { sk }, VerificationKeyImproved { vk })
 }
 
+impl<E: Pairing> VerificationKey<E> {
+    /// vk = g2^x
+    pub fn key_statement(&self, pp: &PublicParams<E>) -> KeyStatement<E> {
+        KeyStatement {
+            g1: pp.g1,
+            images_g1: Vec::new(),
+            g2: pp.g2,
+            images_g2: vec![self.vk],
+        }
+    }
+
+    /// Checks a proof from `gen_keys_with_proof` and that the commitment key halves share
+    /// their exponents, holders run this before requesting a credential
+    pub fn verify_key(
+        &self,
+        pp: &PublicParams<E>,
+        proof: &KeyProof<E>,
+        rng: &mut impl Rng,
+    ) -> Result<(), KeyProofError> {
+        if !pp.is_consistent(rng) {
+            return Err(KeyProofError::InvalidProof(0));
+        }
+        proof.verify(KEY_PROOF_DOMAIN, &self.key_statement(pp), rng)
+    }
+}
+
+impl<E: Pairing> VerificationKeyImproved<E> {
+    /// vk = g1^x
+    pub fn key_statement(&self, pp: &PublicParams<E>) -> KeyStatement<E> {
+        KeyStatement {
+            g1: pp.g1,
+            images_g1: vec![self.vk],
+            g2: pp.g2,
+            images_g2: Vec::new(),
+        }
+    }
+
+    /// `VerificationKey::verify_key` for the improved scheme
+    pub fn verify_key(
+        &self,
+        pp: &PublicParams<E>,
+        proof: &KeyProof<E>,
+        rng: &mut impl Rng,
+    ) -> Result<(), KeyProofError> {
+        if !pp.is_consistent(rng) {
+            return Err(KeyProofError::InvalidProof(0));
+        }
+        proof.verify(KEY_PROOF_DOMAIN_IMPROVED, &self.key_statement(pp), rng)
+    }
+}
+
 // Add test module
 #[cfg(test)]
 mod test {
     use super::*;
-    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ec::pairing::Pairing;
 
-    #[test]
-    fn test_keygen() {
+    fn test_keygen<E: Pairing>() {
         let n = 4;
         let mut rng = ark_std::test_rng();
-        let context = Fr::rand(&mut rng);
-        let pp = PublicParams::<Bls12_381>::new(&n, &context, &mut rng);
+        let context = E::ScalarField::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&n, &context, &mut rng);
         let (sk, vk) = gen_keys(&pp, &mut rng);
 
-        let p1 = Bls12_381::pairing(pp.g1, vk.vk);
-        let p2 = Bls12_381::pairing(sk.sk, pp.g2);
+        let p1 = E::pairing(pp.g1, vk.vk);
+        let p2 = E::pairing(sk.sk, pp.g2);
         assert_eq!(p1, p2, "p1 and p2 aren't equal!");
     }
 
-    #[test]
-    fn test_keygen_improved() {
+    fn test_keygen_improved<E: Pairing>() {
         let n = 4;
         let mut rng = ark_std::test_rng();
-        let context = Fr::rand(&mut rng);
-        let pp = PublicParams::<Bls12_381>::new(&n, &context, &mut rng);
+        let context = E::ScalarField::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&n, &context, &mut rng);
         let (sk, vk) = gen_keys_improved(&pp, &mut rng);
 
-        let p1 = Bls12_381::pairing(vk.vk, pp.g2);
-        let p2 = Bls12_381::pairing(pp.g1, sk.sk);
+        let p1 = E::pairing(vk.vk, pp.g2);
+        let p2 = E::pairing(pp.g1, sk.sk);
         assert_eq!(p1, p2, "p1 and p2 aren't equal!");
     }
+
+    fn test_key_proof<E: Pairing>() {
+        let mut rng = ark_std::test_rng();
+        let context = E::ScalarField::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&4, &context, &mut rng);
+        let (_, vk, proof) = gen_keys_with_proof(&pp, &mut rng);
+        assert!(vk.verify_key(&pp, &proof, &mut rng).is_ok());
+
+        let (_, vk_improved, proof_improved) = gen_keys_improved_with_proof(&pp, &mut rng);
+        assert!(vk_improved
+            .verify_key(&pp, &proof_improved, &mut rng)
+            .is_ok());
+
+        // proofs are bound to their scheme and key
+        let (_, other_vk) = gen_keys(&pp, &mut rng);
+        assert!(other_vk.verify_key(&pp, &proof, &mut rng).is_err());
+        assert!(vk_improved.verify_key(&pp, &proof, &mut rng).is_err());
+
+        // a commitment key whose G1 and G2 halves disagree is refused
+        let mut tagged = pp.clone();
+        tagged.ckg1[2] = (tagged.ckg1[2] + pp.g1).into_affine();
+        assert!(!tagged.is_consistent(&mut rng));
+        assert_eq!(
+            vk.verify_key(&tagged, &proof, &mut rng),
+            Err(KeyProofError::InvalidProof(0))
+        );
+    }
+
+    utils::curve_tests!(test_keygen, test_keygen_improved, test_key_proof);
 }
@@ -2,8 +2,12 @@ pub mod anon_cred;
 pub mod anon_cred_improved;
 pub mod benchmark_helpers;
 pub mod commitment;
+pub mod delegation;
 pub mod keygen;
+pub mod payment;
 pub mod proofsystem;
 pub mod publicparams;
+pub mod range_proof;
 pub mod signature;
 pub mod test_helpers;
+pub mod wire;
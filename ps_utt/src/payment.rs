@@ -0,0 +1,977 @@
+use crate::commitment::Commitment;
+use crate::keygen::{gen_keys, SecretKey, VerificationKey};
+use crate::proofsystem::{CommitmentProofError, CommitmentProofs};
+use crate::publicparams::PublicParams;
+use crate::range_proof::{RangeParams, RangeProof, RangeProofError};
+use crate::signature::PSUTTSignature;
+use ark_ec::pairing::{Pairing, PairingOutput};
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{Field, UniformRand, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::ops::{Mul, Neg};
+use ark_std::rand::Rng;
+use schnorr::schnorr::SchnorrProtocol;
+use std::collections::HashSet;
+use thiserror::Error;
+use utils::hash::HashUtil;
+
+// Anonymous payments in the style of UTT. A coin is a PS-UTT credential over
+// (owner pid, serial number, value, type, expiry). Accounts hold a registration credential over
+// (pid, s) where s is the owner's secret. Spending a coin reveals the nullifier h^(1/(s + sn)), a
+// Dodis-Yampolskiy VRF output, and proves with one Fiat-Shamir challenge that
+// - the coin and the registration are signed by the bank and share the pid,
+// - the nullifier is computed from the registration's s and the coin's sn,
+// - every output commits to (pid', sn', value', type, expiry) with value' in range,
+// - input and output values add up, by drawing the value blindings so they cancel.
+// Output serial numbers are hashed from the nullifiers, and the bank signs the output commitments
+// after checking the nullifiers against its spent set. The type and expiry of each coin are public
+// when it is spent, every output expires with the earliest input.
+
+/// attribute positions in a coin
+pub const PID: usize = 0;
+pub const SERIAL_NUMBER: usize = 1;
+pub const VALUE: usize = 2;
+pub const COIN_TYPE: usize = 3;
+pub const EXPIRY: usize = 4;
+const COIN_ATTRIBUTES: usize = 5;
+
+/// attribute positions in a registration
+const ACCOUNT_PID: usize = 0;
+const ACCOUNT_SECRET: usize = 1;
+
+#[derive(Error, Debug)]
+pub enum PaymentError {
+    #[error("Transaction has no inputs")]
+    NoInputs,
+    #[error("Coin is not owned by this account")]
+    WrongOwner,
+    #[error("Coins of different types")]
+    MixedCoinTypes,
+    #[error("Coin expired at {0}")]
+    Expired(u64),
+    #[error("Inputs and outputs do not balance")]
+    Unbalanced,
+    #[error("Invalid coin")]
+    InvalidCoin,
+    #[error("Invalid registration")]
+    InvalidRegistration,
+    #[error("Invalid transaction")]
+    InvalidTransaction,
+    #[error("Coin was already spent")]
+    DoubleSpend,
+    #[error("Range proof error: {0}")]
+    RangeProof(#[from] RangeProofError),
+    #[error("Commitment proof error: {0}")]
+    CommitmentProof(#[from] CommitmentProofError),
+    #[error("Serialization error: {0}")]
+    SerializationError(#[from] ark_serialize::SerializationError),
+}
+
+/// Commitment keys for coins and registrations, the nullifier bases and the value range
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PaymentParams<E: Pairing> {
+    pub coin_pp: PublicParams<E>,
+    pub account_pp: PublicParams<E>,
+    pub h: E::G1Affine,
+    pub h_tilde: E::G2Affine,
+    pub w_tilde: E::G2Affine,
+    pub range: RangeParams<E::G1Affine>,
+}
+
+impl<E: Pairing> PaymentParams<E> {
+    pub fn new(value_bits: usize, rng: &mut impl Rng) -> Self {
+        let context = E::ScalarField::rand(rng);
+        Self {
+            coin_pp: PublicParams::new(&COIN_ATTRIBUTES, &context, rng),
+            account_pp: PublicParams::new(&2, &context, rng),
+            h: E::G1Affine::rand(rng),
+            h_tilde: E::G2Affine::rand(rng),
+            w_tilde: E::G2Affine::rand(rng),
+            range: RangeParams::new(value_bits, rng),
+        }
+    }
+}
+
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct BankPublicKey<E: Pairing> {
+    pub coin_vk: VerificationKey<E>,
+    pub account_vk: VerificationKey<E>,
+}
+
+/// Issues registrations and coins, and keeps the local set of spent nullifiers
+pub struct Bank<E: Pairing> {
+    pub params: PaymentParams<E>,
+    pub public_key: BankPublicKey<E>,
+    coin_sk: SecretKey<E>,
+    account_sk: SecretKey<E>,
+    nullifiers: HashSet<E::G1Affine>,
+}
+
+/// Opening of a coin, the sender hands it to the recipient together with the bank's signature
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct CoinOpening<E: Pairing> {
+    pub commitment: Commitment<E>,
+    pub value: u64,
+    pub expiry: u64,
+}
+
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Coin<E: Pairing> {
+    pub opening: CoinOpening<E>,
+    pub signature: PSUTTSignature<E>,
+}
+
+/// The owner's secret s, committed without the pid the bank adds at registration
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct AccountSecret<E: Pairing> {
+    commitment: Commitment<E>,
+}
+
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct RegistrationRequest<E: Pairing> {
+    pub pid: E::ScalarField,
+    pub proof: Vec<u8>,
+}
+
+/// Registered account, spends coins owned by `pid`
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Account<E: Pairing> {
+    pub pid: E::ScalarField,
+    commitment: Commitment<E>,
+    signature: PSUTTSignature<E>,
+}
+
+/// Randomized registration credential, the pid and s responses are shared with the inputs
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct RegistrationShow<E: Pairing> {
+    pub randomized_signature: PSUTTSignature<E>,
+    pub cmg1: E::G1Affine,
+    pub cmg2: E::G2Affine,
+    pub schnorr_commitment: E::G1Affine,
+    /// pid, s, randomness
+    pub responses: Vec<E::ScalarField>,
+}
+
+/// Spent coin with its nullifier and the VRF key vrf_key = h_tilde^(s + sn) w_tilde^t,
+/// vrf_output = e(nullifier, w_tilde)^t
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct InputSpend<E: Pairing> {
+    pub randomized_signature: PSUTTSignature<E>,
+    pub cmg1: E::G1Affine,
+    pub cmg2: E::G2Affine,
+    pub expiry: u64,
+    pub nullifier: E::G1Affine,
+    pub vrf_key: E::G2Affine,
+    pub vrf_output: PairingOutput<E>,
+    pub schnorr_commitment: E::G1Affine,
+    pub vrf_key_commitment: E::G2Affine,
+    pub vrf_output_commitment: PairingOutput<E>,
+    /// sn, value, randomness, t
+    pub responses: Vec<E::ScalarField>,
+}
+
+/// Commitment to a new coin with a range proof on its value
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PaymentOutput<E: Pairing> {
+    pub commitment: E::G1Affine,
+    pub range_proof: RangeProof<E::G1Affine>,
+    pub schnorr_commitment: E::G1Affine,
+    pub range_commitment: E::G1Affine,
+    /// pid, value, randomness, range gamma
+    pub responses: Vec<E::ScalarField>,
+}
+
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Transaction<E: Pairing> {
+    pub coin_type: E::ScalarField,
+    pub output_expiry: u64,
+    pub registration: RegistrationShow<E>,
+    pub inputs: Vec<InputSpend<E>>,
+    pub outputs: Vec<PaymentOutput<E>>,
+}
+
+impl<E: Pairing> Bank<E> {
+    pub fn new(params: PaymentParams<E>, rng: &mut impl Rng) -> Self {
+        let (coin_sk, coin_vk) = gen_keys(&params.coin_pp, rng);
+        let (account_sk, account_vk) = gen_keys(&params.account_pp, rng);
+        Self {
+            params,
+            public_key: BankPublicKey {
+                coin_vk,
+                account_vk,
+            },
+            coin_sk,
+            account_sk,
+            nullifiers: HashSet::new(),
+        }
+    }
+
+    /// Signs the user's commitment to s together with the requested pid
+    pub fn register(
+        &self,
+        request: &RegistrationRequest<E>,
+        rng: &mut impl Rng,
+    ) -> Result<PSUTTSignature<E>, PaymentError> {
+        let pp = &self.params.account_pp;
+        let partial = CommitmentProofs::pok_partial_commitment_verify::<E>(
+            pp,
+            &[ACCOUNT_PID],
+            &request.proof,
+        )?
+        .ok_or(PaymentError::InvalidRegistration)?;
+        let merged = (partial + pp.ckg1[ACCOUNT_PID].mul(request.pid)).into_affine();
+        Ok(PSUTTSignature::sign(pp, &self.account_sk, &merged, rng))
+    }
+
+    /// Issues a fresh coin to `pid`
+    pub fn mint(
+        &self,
+        pid: E::ScalarField,
+        value: u64,
+        coin_type: E::ScalarField,
+        expiry: u64,
+        rng: &mut impl Rng,
+    ) -> Result<Coin<E>, PaymentError> {
+        if value > self.params.range.max_value() {
+            return Err(RangeProofError::ValueTooLarge {
+                value,
+                bits: self.params.range.bits,
+            }
+            .into());
+        }
+        let sn = E::ScalarField::rand(rng);
+        let opening = CoinOpening::new(&self.params, pid, sn, value, coin_type, expiry, rng);
+        let signature = PSUTTSignature::sign(
+            &self.params.coin_pp,
+            &self.coin_sk,
+            &opening.commitment.cmg1,
+            rng,
+        );
+        Ok(Coin { opening, signature })
+    }
+
+    /// Verifies the transaction at time `now`, records its nullifiers and signs the outputs
+    pub fn process(
+        &mut self,
+        transaction: &Transaction<E>,
+        now: u64,
+        rng: &mut impl Rng,
+    ) -> Result<Vec<PSUTTSignature<E>>, PaymentError> {
+        if let Some(input) = transaction.inputs.iter().find(|i| i.expiry < now) {
+            return Err(PaymentError::Expired(input.expiry));
+        }
+        if !transaction.verify(&self.params, &self.public_key)? {
+            return Err(PaymentError::InvalidTransaction);
+        }
+        let nullifiers: HashSet<E::G1Affine> =
+            transaction.inputs.iter().map(|i| i.nullifier).collect();
+        if nullifiers.len() != transaction.inputs.len()
+            || nullifiers.iter().any(|n| self.nullifiers.contains(n))
+        {
+            return Err(PaymentError::DoubleSpend);
+        }
+        self.nullifiers.extend(nullifiers);
+
+        Ok(transaction
+            .outputs
+            .iter()
+            .map(|o| PSUTTSignature::sign(&self.params.coin_pp, &self.coin_sk, &o.commitment, rng))
+            .collect())
+    }
+
+    pub fn is_spent(&self, nullifier: &E::G1Affine) -> bool {
+        self.nullifiers.contains(nullifier)
+    }
+}
+
+impl<E: Pairing> CoinOpening<E> {
+    fn new(
+        params: &PaymentParams<E>,
+        pid: E::ScalarField,
+        sn: E::ScalarField,
+        value: u64,
+        coin_type: E::ScalarField,
+        expiry: u64,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let mut messages = vec![E::ScalarField::zero(); COIN_ATTRIBUTES];
+        messages[PID] = pid;
+        messages[SERIAL_NUMBER] = sn;
+        messages[VALUE] = E::ScalarField::from(value);
+        messages[COIN_TYPE] = coin_type;
+        messages[EXPIRY] = E::ScalarField::from(expiry);
+        let r = E::ScalarField::rand(rng);
+        Self {
+            commitment: Commitment::new(&params.coin_pp, &messages, &r),
+            value,
+            expiry,
+        }
+    }
+
+    pub fn pid(&self) -> E::ScalarField {
+        self.commitment.messages[PID]
+    }
+
+    pub fn coin_type(&self) -> E::ScalarField {
+        self.commitment.messages[COIN_TYPE]
+    }
+}
+
+impl<E: Pairing> AccountSecret<E> {
+    pub fn new(params: &PaymentParams<E>, rng: &mut impl Rng) -> Self {
+        let mut messages = vec![E::ScalarField::zero(); 2];
+        messages[ACCOUNT_SECRET] = E::ScalarField::rand(rng);
+        let r = E::ScalarField::rand(rng);
+        Self {
+            commitment: Commitment::new(&params.account_pp, &messages, &r),
+        }
+    }
+
+    pub fn registration_request(
+        &self,
+        pid: E::ScalarField,
+        rng: &mut impl Rng,
+    ) -> Result<RegistrationRequest<E>, PaymentError> {
+        let proof =
+            CommitmentProofs::pok_partial_commitment_prove(&self.commitment, &[ACCOUNT_PID], rng)?;
+        Ok(RegistrationRequest { pid, proof })
+    }
+}
+
+impl<E: Pairing> Account<E> {
+    /// User checks the bank's registration signature over (pid, s)
+    pub fn new(
+        params: &PaymentParams<E>,
+        public_key: &BankPublicKey<E>,
+        pid: E::ScalarField,
+        secret: AccountSecret<E>,
+        signature: PSUTTSignature<E>,
+    ) -> Result<Self, PaymentError> {
+        let mut messages = secret.commitment.messages.clone();
+        messages[ACCOUNT_PID] = pid;
+        let commitment = Commitment::new(&params.account_pp, &messages, &secret.commitment.r);
+        if !signature_valid(
+            &params.account_pp,
+            &public_key.account_vk,
+            &signature,
+            &commitment.cmg1,
+            &commitment.cmg2,
+        ) {
+            return Err(PaymentError::InvalidRegistration);
+        }
+        Ok(Self {
+            pid,
+            commitment,
+            signature,
+        })
+    }
+
+    /// Recipient checks an output coin of a processed transaction
+    pub fn receive(
+        &self,
+        params: &PaymentParams<E>,
+        public_key: &BankPublicKey<E>,
+        opening: CoinOpening<E>,
+        signature: PSUTTSignature<E>,
+    ) -> Result<Coin<E>, PaymentError> {
+        if opening.pid() != self.pid {
+            return Err(PaymentError::WrongOwner);
+        }
+        let cm = &opening.commitment;
+        if !signature_valid(
+            &params.coin_pp,
+            &public_key.coin_vk,
+            &signature,
+            &cm.cmg1,
+            &cm.cmg2,
+        ) {
+            return Err(PaymentError::InvalidCoin);
+        }
+        Ok(Coin { opening, signature })
+    }
+
+    /// Spends `inputs` into new coins of the given (pid, value). Returns the transaction for the
+    /// bank and the output openings, in order, for the recipients
+    pub fn pay(
+        &self,
+        params: &PaymentParams<E>,
+        inputs: &[Coin<E>],
+        outputs: &[(E::ScalarField, u64)],
+        rng: &mut impl Rng,
+    ) -> Result<(Transaction<E>, Vec<CoinOpening<E>>), PaymentError> {
+        let coin_pp = &params.coin_pp;
+        let account_pp = &params.account_pp;
+        let coin_type = inputs
+            .first()
+            .ok_or(PaymentError::NoInputs)?
+            .opening
+            .coin_type();
+        for coin in inputs {
+            if coin.opening.pid() != self.pid {
+                return Err(PaymentError::WrongOwner);
+            }
+            if coin.opening.coin_type() != coin_type {
+                return Err(PaymentError::MixedCoinTypes);
+            }
+        }
+        let input_total: u128 = inputs.iter().map(|c| c.opening.value as u128).sum();
+        let output_total: u128 = outputs.iter().map(|(_, v)| *v as u128).sum();
+        if input_total != output_total {
+            return Err(PaymentError::Unbalanced);
+        }
+        let output_expiry = inputs.iter().map(|c| c.opening.expiry).min().unwrap();
+        let s = self.commitment.messages[ACCOUNT_SECRET];
+
+        // registration, blindings for pid, s, randomness
+        let r_delta = E::ScalarField::rand(rng);
+        let u_delta = nonzero_scalar::<E>(rng);
+        let account = self.commitment.create_randomized(&r_delta);
+        let account_witnesses = vec![self.pid, s, account.r];
+        let account_blindings = random_scalars::<E>(3, rng);
+        let registration = RegistrationShow {
+            randomized_signature: self.signature.rerandomize(account_pp, &r_delta, &u_delta),
+            cmg1: account.cmg1,
+            cmg2: account.cmg2,
+            schnorr_commitment: SchnorrProtocol::commit_with_prepared_blindings(
+                &account_bases(account_pp),
+                &account_blindings,
+            )
+            .commited_blindings,
+            responses: Vec::new(),
+        };
+        let (k_pid, k_s) = (account_blindings[0], account_blindings[1]);
+
+        // inputs, witnesses sn, value, randomness, t
+        let mut spends = Vec::with_capacity(inputs.len());
+        let mut input_witnesses = Vec::with_capacity(inputs.len());
+        let mut input_blindings = Vec::with_capacity(inputs.len());
+        for coin in inputs {
+            let sn = coin.opening.commitment.messages[SERIAL_NUMBER];
+            let exponent = (s + sn).inverse().ok_or(PaymentError::InvalidCoin)?;
+            let r_delta = E::ScalarField::rand(rng);
+            let u_delta = nonzero_scalar::<E>(rng);
+            let randomized = coin.opening.commitment.create_randomized(&r_delta);
+
+            let t = E::ScalarField::rand(rng);
+            let nullifier = params.h.mul(exponent).into_affine();
+            let vrf_base = E::pairing(nullifier, params.w_tilde);
+            let witnesses = vec![sn, randomized.messages[VALUE], randomized.r, t];
+            let blindings = random_scalars::<E>(4, rng);
+            let (k_sn, k_value, k_r, k_t) =
+                (blindings[0], blindings[1], blindings[2], blindings[3]);
+
+            spends.push(InputSpend {
+                randomized_signature: coin.signature.rerandomize(coin_pp, &r_delta, &u_delta),
+                cmg1: randomized.cmg1,
+                cmg2: randomized.cmg2,
+                expiry: coin.opening.expiry,
+                nullifier,
+                vrf_key: (params.h_tilde.mul(s + sn) + params.w_tilde.mul(t)).into_affine(),
+                vrf_output: vrf_base.mul(t),
+                schnorr_commitment: SchnorrProtocol::commit_with_prepared_blindings(
+                    &input_bases(coin_pp),
+                    &[k_pid, k_sn, k_value, k_r],
+                )
+                .commited_blindings,
+                vrf_key_commitment: SchnorrProtocol::commit_with_prepared_blindings(
+                    &vrf_key_bases(params),
+                    &[k_s, k_sn, k_t],
+                )
+                .commited_blindings,
+                vrf_output_commitment: vrf_base.mul(k_t),
+                responses: Vec::new(),
+            });
+            input_witnesses.push(witnesses);
+            input_blindings.push(blindings);
+        }
+
+        // outputs, witnesses pid, value, randomness, gamma. The last value blinding is fixed so
+        // the value blindings of inputs and outputs cancel
+        let serial_numbers = output_serial_numbers::<E>(&spends, outputs.len())?;
+        let mut value_blinding_sum: E::ScalarField = input_blindings.iter().map(|b| b[1]).sum();
+        let mut payment_outputs = Vec::with_capacity(outputs.len());
+        let mut openings = Vec::with_capacity(outputs.len());
+        let mut output_witnesses = Vec::with_capacity(outputs.len());
+        let mut output_blindings = Vec::with_capacity(outputs.len());
+        for (j, ((pid, value), sn)) in outputs.iter().zip(serial_numbers).enumerate() {
+            let opening = CoinOpening::new(params, *pid, sn, *value, coin_type, output_expiry, rng);
+            let (range_proof, gamma) = RangeProof::prove(&params.range, *value, rng)?;
+            let mut blindings = random_scalars::<E>(4, rng);
+            if j + 1 == outputs.len() {
+                blindings[1] = value_blinding_sum;
+            }
+            value_blinding_sum -= blindings[1];
+
+            payment_outputs.push(PaymentOutput {
+                commitment: opening.commitment.cmg1,
+                range_proof,
+                schnorr_commitment: SchnorrProtocol::commit_with_prepared_blindings(
+                    &output_bases(coin_pp),
+                    &blindings[..3],
+                )
+                .commited_blindings,
+                range_commitment: SchnorrProtocol::commit_with_prepared_blindings(
+                    &[params.range.g, params.range.h],
+                    &[blindings[1], blindings[3]],
+                )
+                .commited_blindings,
+                responses: Vec::new(),
+            });
+            output_witnesses.push(vec![
+                *pid,
+                opening.commitment.messages[VALUE],
+                opening.commitment.r,
+                gamma,
+            ]);
+            output_blindings.push(blindings);
+            openings.push(opening);
+        }
+
+        let mut transaction = Transaction {
+            coin_type,
+            output_expiry,
+            registration,
+            inputs: spends,
+            outputs: payment_outputs,
+        };
+        let challenge = transaction.challenge()?;
+        transaction.registration.responses =
+            responses::<E>(&account_blindings, &account_witnesses, &challenge);
+        for (spend, (b, w)) in transaction
+            .inputs
+            .iter_mut()
+            .zip(input_blindings.iter().zip(&input_witnesses))
+        {
+            spend.responses = responses::<E>(b, w, &challenge);
+        }
+        for (output, (b, w)) in transaction
+            .outputs
+            .iter_mut()
+            .zip(output_blindings.iter().zip(&output_witnesses))
+        {
+            output.responses = responses::<E>(b, w, &challenge);
+        }
+        Ok((transaction, openings))
+    }
+}
+
+impl<E: Pairing> Transaction<E> {
+    /// Checks every proof of the transaction, the bank additionally checks expiry and nullifiers
+    pub fn verify(
+        &self,
+        params: &PaymentParams<E>,
+        public_key: &BankPublicKey<E>,
+    ) -> Result<bool, PaymentError> {
+        let coin_pp = &params.coin_pp;
+        let registration = &self.registration;
+        if self.inputs.is_empty()
+            || registration.responses.len() != 3
+            || self.inputs.iter().any(|i| i.responses.len() != 4)
+            || self.outputs.iter().any(|o| o.responses.len() != 4)
+        {
+            return Ok(false);
+        }
+        let challenge = self.challenge()?;
+
+        if !signature_valid(
+            &params.account_pp,
+            &public_key.account_vk,
+            &registration.randomized_signature,
+            &registration.cmg1,
+            &registration.cmg2,
+        ) || !SchnorrProtocol::verify_schnorr(
+            &account_bases(&params.account_pp),
+            &registration.cmg1,
+            &registration.schnorr_commitment,
+            &registration.responses,
+            &challenge,
+        ) {
+            return Ok(false);
+        }
+        let (z_pid, z_s) = (registration.responses[0], registration.responses[1]);
+
+        let public_part = |expiry: u64| {
+            coin_pp.ckg1[COIN_TYPE].mul(self.coin_type)
+                + coin_pp.ckg1[EXPIRY].mul(E::ScalarField::from(expiry))
+        };
+        let h_pairing = E::pairing(params.h, params.h_tilde);
+        let mut input_value_responses = E::ScalarField::zero();
+        for spend in &self.inputs {
+            let z = &spend.responses;
+            let (z_sn, z_value, z_r, z_t) = (z[0], z[1], z[2], z[3]);
+            let statement = (spend.cmg1.into_group() - public_part(spend.expiry)).into_affine();
+            let vrf_base = E::pairing(spend.nullifier, params.w_tilde);
+            let valid = signature_valid(
+                coin_pp,
+                &public_key.coin_vk,
+                &spend.randomized_signature,
+                &spend.cmg1,
+                &spend.cmg2,
+            ) && SchnorrProtocol::verify_schnorr(
+                &input_bases(coin_pp),
+                &statement,
+                &spend.schnorr_commitment,
+                &[z_pid, z_sn, z_value, z_r],
+                &challenge,
+            ) && SchnorrProtocol::verify_schnorr(
+                &vrf_key_bases(params),
+                &spend.vrf_key,
+                &spend.vrf_key_commitment,
+                &[z_s, z_sn, z_t],
+                &challenge,
+            ) && vrf_base.mul(z_t) == spend.vrf_output_commitment + spend.vrf_output.mul(challenge)
+                // e(nullifier, h_tilde^(s + sn)) = e(h, h_tilde)
+                && !spend.nullifier.is_zero()
+                && E::pairing(spend.nullifier, spend.vrf_key) == h_pairing + spend.vrf_output;
+            if !valid {
+                return Ok(false);
+            }
+            input_value_responses += z_value;
+        }
+
+        let serial_numbers = output_serial_numbers::<E>(&self.inputs, self.outputs.len())?;
+        let mut output_value_responses = E::ScalarField::zero();
+        for (output, sn) in self.outputs.iter().zip(serial_numbers) {
+            let z = &output.responses;
+            let (z_pid, z_value, z_r, z_gamma) = (z[0], z[1], z[2], z[3]);
+            let statement = (output.commitment.into_group()
+                - coin_pp.ckg1[SERIAL_NUMBER].mul(sn)
+                - public_part(self.output_expiry))
+            .into_affine();
+            let valid = output.range_proof.verify(&params.range)
+                && SchnorrProtocol::verify_schnorr(
+                    &output_bases(coin_pp),
+                    &statement,
+                    &output.schnorr_commitment,
+                    &[z_pid, z_value, z_r],
+                    &challenge,
+                )
+                && SchnorrProtocol::verify_schnorr(
+                    &[params.range.g, params.range.h],
+                    &output.range_proof.value_commitment(),
+                    &output.range_commitment,
+                    &[z_value, z_gamma],
+                    &challenge,
+                );
+            if !valid {
+                return Ok(false);
+            }
+            output_value_responses += z_value;
+        }
+
+        Ok(input_value_responses == output_value_responses)
+    }
+
+    pub fn nullifiers(&self) -> Vec<E::G1Affine> {
+        self.inputs.iter().map(|i| i.nullifier).collect()
+    }
+
+    fn challenge(&self) -> Result<E::ScalarField, PaymentError> {
+        let mut transcript = b"ps-utt-payment".to_vec();
+        self.coin_type.serialize_compressed(&mut transcript)?;
+        self.output_expiry.serialize_compressed(&mut transcript)?;
+        let registration = &self.registration;
+        registration
+            .randomized_signature
+            .serialize_compressed(&mut transcript)?;
+        registration.cmg1.serialize_compressed(&mut transcript)?;
+        registration.cmg2.serialize_compressed(&mut transcript)?;
+        registration
+            .schnorr_commitment
+            .serialize_compressed(&mut transcript)?;
+        for spend in &self.inputs {
+            spend
+                .randomized_signature
+                .serialize_compressed(&mut transcript)?;
+            spend.cmg1.serialize_compressed(&mut transcript)?;
+            spend.cmg2.serialize_compressed(&mut transcript)?;
+            spend.expiry.serialize_compressed(&mut transcript)?;
+            spend.nullifier.serialize_compressed(&mut transcript)?;
+            spend.vrf_key.serialize_compressed(&mut transcript)?;
+            spend.vrf_output.serialize_compressed(&mut transcript)?;
+            spend
+                .schnorr_commitment
+                .serialize_compressed(&mut transcript)?;
+            spend
+                .vrf_key_commitment
+                .serialize_compressed(&mut transcript)?;
+            spend
+                .vrf_output_commitment
+                .serialize_compressed(&mut transcript)?;
+        }
+        for output in &self.outputs {
+            output.commitment.serialize_compressed(&mut transcript)?;
+            output.range_proof.serialize_compressed(&mut transcript)?;
+            output
+                .schnorr_commitment
+                .serialize_compressed(&mut transcript)?;
+            output
+                .range_commitment
+                .serialize_compressed(&mut transcript)?;
+        }
+        Ok(HashUtil::<E::ScalarField, E::G1Affine>::hash_to_field(
+            &transcript,
+        ))
+    }
+}
+
+/// sn of output j is hashed from the spent nullifiers, so it is fresh whenever the inputs are
+fn output_serial_numbers<E: Pairing>(
+    inputs: &[InputSpend<E>],
+    count: usize,
+) -> Result<Vec<E::ScalarField>, PaymentError> {
+    let mut transcript = b"ps-utt-payment-sn".to_vec();
+    for input in inputs {
+        input.nullifier.serialize_compressed(&mut transcript)?;
+    }
+    Ok((0..count as u64)
+        .map(|j| {
+            let mut bytes = transcript.clone();
+            bytes.extend_from_slice(&j.to_le_bytes());
+            HashUtil::<E::ScalarField, E::G1Affine>::hash_to_field(&bytes)
+        })
+        .collect())
+}
+
+/// bases of (pid, s, randomness) in a registration
+fn account_bases<E: Pairing>(pp: &PublicParams<E>) -> Vec<E::G1Affine> {
+    vec![pp.ckg1[ACCOUNT_PID], pp.ckg1[ACCOUNT_SECRET], pp.g1]
+}
+
+/// bases of (pid, sn, value, randomness) in a spent coin, type and expiry are public
+fn input_bases<E: Pairing>(pp: &PublicParams<E>) -> Vec<E::G1Affine> {
+    vec![pp.ckg1[PID], pp.ckg1[SERIAL_NUMBER], pp.ckg1[VALUE], pp.g1]
+}
+
+/// bases of (pid, value, randomness) in an output coin, sn, type and expiry are public
+fn output_bases<E: Pairing>(pp: &PublicParams<E>) -> Vec<E::G1Affine> {
+    vec![pp.ckg1[PID], pp.ckg1[VALUE], pp.g1]
+}
+
+/// bases of (s, sn, t) in the VRF key
+fn vrf_key_bases<E: Pairing>(params: &PaymentParams<E>) -> Vec<E::G2Affine> {
+    vec![params.h_tilde, params.h_tilde, params.w_tilde]
+}
+
+/// e(sigma2, g2) = e(sigma1, vk + cmg2) and e(cmg1, g2) = e(g1, cmg2)
+fn signature_valid<E: Pairing>(
+    pp: &PublicParams<E>,
+    vk: &VerificationKey<E>,
+    signature: &PSUTTSignature<E>,
+    cmg1: &E::G1Affine,
+    cmg2: &E::G2Affine,
+) -> bool {
+    let key = (vk.vk + cmg2).into_affine();
+    !signature.sigma1.is_zero()
+        && E::multi_pairing(
+            [
+                signature.sigma2,
+                signature.sigma1.into_group().neg().into_affine(),
+            ],
+            [pp.g2, key],
+        )
+        .is_zero()
+        && E::multi_pairing(
+            [*cmg1, pp.g1.into_group().neg().into_affine()],
+            [pp.g2, *cmg2],
+        )
+        .is_zero()
+}
+
+fn responses<E: Pairing>(
+    blindings: &[E::ScalarField],
+    witnesses: &[E::ScalarField],
+    challenge: &E::ScalarField,
+) -> Vec<E::ScalarField> {
+    blindings
+        .iter()
+        .zip(witnesses)
+        .map(|(b, w)| *b + *w * challenge)
+        .collect()
+}
+
+fn random_scalars<E: Pairing>(n: usize, rng: &mut impl Rng) -> Vec<E::ScalarField> {
+    (0..n).map(|_| E::ScalarField::rand(rng)).collect()
+}
+
+fn nonzero_scalar<E: Pairing>(rng: &mut impl Rng) -> E::ScalarField {
+    loop {
+        let x = E::ScalarField::rand(rng);
+        if !x.is_zero() {
+            return x;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ec::pairing::Pairing;
+
+    fn register<E: Pairing>(bank: &Bank<E>, pid: u64, rng: &mut impl Rng) -> Account<E> {
+        let pid = E::ScalarField::from(pid);
+        let secret = AccountSecret::new(&bank.params, rng);
+        let request = secret.registration_request(pid, rng).unwrap();
+        let signature = bank.register(&request, rng).unwrap();
+        Account::new(&bank.params, &bank.public_key, pid, secret, signature).unwrap()
+    }
+
+    fn test_split_merge_and_double_spend<E: Pairing>() {
+        let mut rng = ark_std::test_rng();
+        let mut bank = Bank::new(PaymentParams::<E>::new(16, &mut rng), &mut rng);
+        let alice = register(&bank, 1, &mut rng);
+        let bob = register(&bank, 2, &mut rng);
+        let coin_type = E::ScalarField::from(7u64);
+
+        let a = bank
+            .mint(alice.pid, 100, coin_type, 1000, &mut rng)
+            .unwrap();
+        let b = bank.mint(alice.pid, 50, coin_type, 900, &mut rng).unwrap();
+        let a = alice
+            .receive(&bank.params, &bank.public_key, a.opening, a.signature)
+            .unwrap();
+
+        // merge two coins and split them between bob and alice's change
+        let (transaction, openings) = alice
+            .pay(
+                &bank.params,
+                &[a, b],
+                &[(bob.pid, 120), (alice.pid, 30)],
+                &mut rng,
+            )
+            .unwrap();
+        assert!(transaction.verify(&bank.params, &bank.public_key).unwrap());
+        let signatures = bank.process(&transaction, 10, &mut rng).unwrap();
+        assert!(transaction.nullifiers().iter().all(|n| bank.is_spent(n)));
+        assert!(matches!(
+            bank.process(&transaction, 10, &mut rng),
+            Err(PaymentError::DoubleSpend)
+        ));
+
+        let mut openings = openings.into_iter();
+        let mut signatures = signatures.into_iter();
+        let bob_coin = bob
+            .receive(
+                &bank.params,
+                &bank.public_key,
+                openings.next().unwrap(),
+                signatures.next().unwrap(),
+            )
+            .unwrap();
+        assert_eq!(bob_coin.opening.value, 120);
+        assert_eq!(bob_coin.opening.expiry, 900);
+        let change = openings.next().unwrap();
+        assert!(matches!(
+            bob.receive(
+                &bank.params,
+                &bank.public_key,
+                change.clone(),
+                signatures.next().unwrap()
+            ),
+            Err(PaymentError::WrongOwner)
+        ));
+
+        // the received coin spends again, and only once
+        let (transaction, _) = bob
+            .pay(
+                &bank.params,
+                std::slice::from_ref(&bob_coin),
+                &[(alice.pid, 120)],
+                &mut rng,
+            )
+            .unwrap();
+        bank.process(&transaction, 20, &mut rng).unwrap();
+        let (again, _) = bob
+            .pay(&bank.params, &[bob_coin], &[(alice.pid, 120)], &mut rng)
+            .unwrap();
+        assert_eq!(again.nullifiers(), transaction.nullifiers());
+        assert!(matches!(
+            bank.process(&again, 20, &mut rng),
+            Err(PaymentError::DoubleSpend)
+        ));
+    }
+
+    fn test_payment_rejections<E: Pairing>() {
+        let mut rng = ark_std::test_rng();
+        let mut bank = Bank::new(PaymentParams::<E>::new(8, &mut rng), &mut rng);
+        let alice = register(&bank, 1, &mut rng);
+        let bob = register(&bank, 2, &mut rng);
+        let coin_type = E::ScalarField::from(1u64);
+        let coin = bank.mint(alice.pid, 200, coin_type, 50, &mut rng).unwrap();
+
+        assert!(matches!(
+            alice.pay(
+                &bank.params,
+                std::slice::from_ref(&coin),
+                &[(bob.pid, 201)],
+                &mut rng
+            ),
+            Err(PaymentError::Unbalanced)
+        ));
+        assert!(matches!(
+            bob.pay(
+                &bank.params,
+                std::slice::from_ref(&coin),
+                &[(bob.pid, 200)],
+                &mut rng
+            ),
+            Err(PaymentError::WrongOwner)
+        ));
+        assert!(matches!(
+            bank.mint(alice.pid, 256, coin_type, 50, &mut rng),
+            Err(PaymentError::RangeProof(_))
+        ));
+        let other_type = bank
+            .mint(alice.pid, 1, E::ScalarField::from(2u64), 50, &mut rng)
+            .unwrap();
+        assert!(matches!(
+            alice.pay(
+                &bank.params,
+                &[coin.clone(), other_type],
+                &[(bob.pid, 201)],
+                &mut rng
+            ),
+            Err(PaymentError::MixedCoinTypes)
+        ));
+
+        let (transaction, _) = alice
+            .pay(
+                &bank.params,
+                &[coin],
+                &[(bob.pid, 150), (alice.pid, 50)],
+                &mut rng,
+            )
+            .unwrap();
+        assert!(matches!(
+            bank.process(&transaction, 51, &mut rng),
+            Err(PaymentError::Expired(50))
+        ));
+
+        // moving value between outputs breaks the proofs
+        let mut tampered = transaction.clone();
+        tampered.outputs.swap(0, 1);
+        assert!(!tampered.verify(&bank.params, &bank.public_key).unwrap());
+        let mut tampered = transaction.clone();
+        tampered.outputs[0].responses[1] += E::ScalarField::from(1u64);
+        tampered.outputs[1].responses[1] -= E::ScalarField::from(1u64);
+        assert!(!tampered.verify(&bank.params, &bank.public_key).unwrap());
+        let mut tampered = transaction.clone();
+        tampered.output_expiry = 500;
+        assert!(!tampered.verify(&bank.params, &bank.public_key).unwrap());
+
+        // a coin of another bank
+        let other = Bank::new(bank.params.clone(), &mut rng);
+        let foreign = other.mint(alice.pid, 10, coin_type, 50, &mut rng).unwrap();
+        let (transaction_foreign, _) = alice
+            .pay(&bank.params, &[foreign], &[(bob.pid, 10)], &mut rng)
+            .unwrap();
+        assert!(matches!(
+            bank.process(&transaction_foreign, 0, &mut rng),
+            Err(PaymentError::InvalidTransaction)
+        ));
+
+        bank.process(&transaction, 50, &mut rng).unwrap();
+    }
+
+    utils::curve_tests!(test_split_merge_and_double_spend, test_payment_rejections);
+}
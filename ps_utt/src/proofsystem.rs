@@ -1,9 +1,18 @@
 use crate::commitment::Commitment;
+use crate::publicparams::{PublicParams, PublicParamsTables};
 use ark_ec::pairing::Pairing;
+use ark_ec::{CurveGroup, VariableBaseMSM};
 use ark_ff::UniformRand;
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::rand::Rng;
+use schnorr::batch::{BatchError, SchnorrBatch, SchnorrClaim};
+use schnorr::key_proof::KeyProofError;
 use schnorr::schnorr::{SchnorrCommitment, SchnorrProtocol, SchnorrResponses};
 use thiserror::Error;
+use utils::context::PresentationContext;
+use utils::disclosure::{Disclosure, DisclosureError};
+use utils::hash::HashUtil;
+use zeroize::Zeroizing;
 
 /// Possible errors that can occur during commitment proof operations
 #[derive(Error, Debug)]
@@ -17,8 +26,18 @@ pub enum CommitmentProofError {
     InvalidEqualityIndex,
     #[error("Mismatched commitment lengths")]
     MismatchedCommitmentLengths,
+    #[error("Invalid attribute index {0}")]
+    InvalidAttributeIndex(usize),
     #[error("Serialization error: {0}")]
     SerializationError(#[from] ark_serialize::SerializationError),
+    #[error("Issuer secret key not available")]
+    MissingSecretKey,
+    #[error("Batch verification failed: {0}")]
+    BatchError(#[from] BatchError),
+    #[error("Issuer key is not well formed: {0}")]
+    KeyProofError(#[from] KeyProofError),
+    #[error("Disclosure error: {0}")]
+    DisclosureError(#[from] DisclosureError),
 }
 
 /// Proof of knowledge of a commitment in the G1 group
@@ -26,7 +45,6 @@ pub enum CommitmentProofError {
 pub struct CommitmentProof<E: Pairing> {
     pub commitment: E::G1Affine,
     pub schnorr_commitment: SchnorrCommitment<E::G1Affine>,
-    pub bases: Vec<E::G1Affine>,
     pub challenge: E::ScalarField,
     pub responses: Vec<E::ScalarField>,
 }
@@ -36,7 +54,6 @@ pub struct CommitmentProof<E: Pairing> {
 pub struct CommitmentProofG2<E: Pairing> {
     pub commitment: E::G2Affine,
     pub schnorr_commitment: SchnorrCommitment<E::G2Affine>,
-    pub bases: Vec<E::G2Affine>,
     pub challenge: E::ScalarField,
     pub responses: Vec<E::ScalarField>,
 }
@@ -46,11 +63,56 @@ pub struct CommitmentProofG2<E: Pairing> {
 pub struct CommitmentEqualityProof<E: Pairing> {
     pub commitments: Vec<E::G1Affine>,
     pub schnorr_commitments: Vec<SchnorrCommitment<E::G1Affine>>,
-    pub bases: Vec<Vec<E::G1Affine>>,
     pub challenge: E::ScalarField,
     pub responses: Vec<Vec<E::ScalarField>>,
 }
 
+/// First move of the commitment proof. The opening is erased by the one `finish` call
+pub struct PreparedCommitmentProof<E: Pairing> {
+    commitment: E::G1Affine,
+    schnorr_commitment: SchnorrCommitment<E::G1Affine>,
+    exponents: Zeroizing<Vec<E::ScalarField>>,
+}
+
+impl<E: Pairing> PreparedCommitmentProof<E> {
+    /// Binds `context` and the opened values at `disclosed` into the challenge
+    pub fn finish(
+        mut self,
+        context: &PresentationContext,
+        disclosed: &[usize],
+    ) -> Result<(Vec<u8>, Disclosure<E::ScalarField>), CommitmentProofError> {
+        let n = self.exponents.len() - 1;
+        let disclosure = Disclosure::new(
+            disclosed,
+            &self.exponents[..n],
+            &self.schnorr_commitment.random_blindings[..n],
+        )?;
+        let challenge = CommitmentProofs::context_challenge::<E>(
+            &self.commitment,
+            &self.schnorr_commitment.commited_blindings,
+            &disclosure,
+            context,
+        )?;
+        let responses = SchnorrProtocol::prove_and_erase(
+            &mut self.schnorr_commitment,
+            &self.exponents,
+            &challenge,
+        );
+
+        let proof: CommitmentProof<E> = CommitmentProof {
+            commitment: self.commitment,
+            schnorr_commitment: self.schnorr_commitment,
+            challenge,
+            responses: responses.0,
+        };
+
+        let mut serialized_proof = Vec::new();
+        proof.serialize_compressed(&mut serialized_proof)?;
+
+        Ok((serialized_proof, disclosure))
+    }
+}
+
 /// Implementation of various commitment proof schemes
 pub struct CommitmentProofs;
 
@@ -59,32 +121,36 @@ impl CommitmentProofs {
     ///
     /// # Arguments
     /// * `commitment` - The commitment to prove knowledge of
+    /// * `rng` - Source of the Schnorr blindings
     ///
     /// # Returns
     /// A serialized proof
     pub fn pok_commitment_prove<E: Pairing>(
         commitment: &Commitment<E>,
+        rng: &mut impl Rng,
     ) -> Result<Vec<u8>, CommitmentProofError> {
-        let mut rng = ark_std::test_rng();
-
         // Get bases and exponents for the proof
         let bases = commitment.pp.get_g1_bases();
         let exponents = commitment.get_exponents();
 
         // Generate Schnorr commitment
-        let schnorr_commitment = SchnorrProtocol::commit(&bases, &mut rng);
+        let mut schnorr_commitment = SchnorrProtocol::commit(&bases, rng);
 
-        // Generate challenge
-        let challenge = E::ScalarField::rand(&mut rng);
+        // Fiat-Shamir challenge over the bases, the commitment and T
+        let challenge = commitment_challenge::<E, _>(
+            &bases,
+            &commitment.cmg1,
+            &schnorr_commitment.commited_blindings,
+        )?;
 
         // Generate responses
-        let responses = SchnorrProtocol::prove(&schnorr_commitment, &exponents, &challenge);
+        let responses =
+            SchnorrProtocol::prove_and_erase(&mut schnorr_commitment, &exponents, &challenge);
 
         // Create and serialize proof with explicit type annotation
         let proof: CommitmentProof<E> = CommitmentProof {
             commitment: commitment.cmg1,
             schnorr_commitment,
-            bases,
             challenge,
             responses: responses.0,
         };
@@ -95,36 +161,228 @@ impl CommitmentProofs {
         Ok(serialized_proof)
     }
 
+    /// Generate a proof of knowledge of a commitment in G1 for a presentation
+    ///
+    /// # Arguments
+    /// * `commitment` - The commitment to prove knowledge of
+    /// * `context` - Verifier context the Fiat-Shamir challenge is bound to
+    ///
+    /// # Returns
+    /// A serialized proof
+    pub fn pok_commitment_prove_with_context<E: Pairing>(
+        commitment: &Commitment<E>,
+        context: &PresentationContext,
+        rng: &mut impl Rng,
+    ) -> Result<Vec<u8>, CommitmentProofError> {
+        let (proof, _) = Self::prepare_commitment_proof(commitment, rng).finish(context, &[])?;
+        Ok(proof)
+    }
+
+    /// `pok_commitment_prove_with_context` with the fixed-base tables from
+    /// `PublicParams::precompute`, the proof is identical
+    pub fn pok_commitment_prove_with_tables<E: Pairing>(
+        commitment: &Commitment<E>,
+        tables: &PublicParamsTables<E>,
+        context: &PresentationContext,
+        rng: &mut impl Rng,
+    ) -> Result<Vec<u8>, CommitmentProofError> {
+        let (proof, _) = Self::prepare_commitment_proof_with_tables(commitment, tables, rng)
+            .finish(context, &[])?;
+        Ok(proof)
+    }
+
+    /// The Schnorr commitment of `pok_commitment_prove_with_context`, which does not depend on
+    /// the verifier. `PreparedCommitmentProof::finish` adds the challenge and responses
+    pub fn prepare_commitment_proof<E: Pairing>(
+        commitment: &Commitment<E>,
+        rng: &mut impl Rng,
+    ) -> PreparedCommitmentProof<E> {
+        let bases = commitment.pp.get_g1_bases();
+        PreparedCommitmentProof {
+            commitment: commitment.cmg1,
+            schnorr_commitment: SchnorrProtocol::commit(&bases, rng),
+            exponents: Zeroizing::new(commitment.get_exponents()),
+        }
+    }
+
+    /// `prepare_commitment_proof` with the fixed-base tables from `PublicParams::precompute`
+    pub fn prepare_commitment_proof_with_tables<E: Pairing>(
+        commitment: &Commitment<E>,
+        tables: &PublicParamsTables<E>,
+        rng: &mut impl Rng,
+    ) -> PreparedCommitmentProof<E> {
+        PreparedCommitmentProof {
+            commitment: commitment.cmg1,
+            schnorr_commitment: SchnorrProtocol::commit_with_table(&tables.bases_g1, rng),
+            exponents: Zeroizing::new(commitment.get_exponents()),
+        }
+    }
+
+    /// Verify a presentation proof of knowledge of a commitment in G1
+    ///
+    /// # Arguments
+    /// * `pp` - The verifier's public parameters, the proof is checked against their bases
+    /// * `serialized_proof` - The serialized proof to verify
+    /// * `disclosure` - The messages the presentation reveals
+    /// * `context` - The verifier's own context, the proof fails under any other
+    ///
+    /// # Returns
+    /// The proven commitment if the proof is valid, `None` otherwise
+    pub fn pok_commitment_verify_with_context<E: Pairing>(
+        pp: &PublicParams<E>,
+        serialized_proof: &[u8],
+        disclosure: &Disclosure<E::ScalarField>,
+        context: &PresentationContext,
+    ) -> Result<Option<E::G1Affine>, CommitmentProofError> {
+        let proof: CommitmentProof<E> =
+            CanonicalDeserialize::deserialize_compressed(serialized_proof)?;
+
+        let challenge = Self::context_challenge::<E>(
+            &proof.commitment,
+            &proof.schnorr_commitment.commited_blindings,
+            disclosure,
+            context,
+        )?;
+        if challenge != proof.challenge || !Self::check_disclosure(pp, &proof, disclosure) {
+            return Ok(None);
+        }
+
+        let is_valid = verify_g1(&pp.get_g1_bases(), &proof);
+
+        Ok(is_valid.then_some(proof.commitment))
+    }
+
+    /// Generate a proof of knowledge of the user-held part of a commitment in G1, for issuance
+    /// where the issuer sets the messages at `issuer_indices`
+    ///
+    /// # Arguments
+    /// * `commitment` - Commitment whose entries at `issuer_indices` are ignored
+    /// * `issuer_indices` - Indices the issuer fills in before signing
+    /// * `rng` - Source of the Schnorr blindings
+    ///
+    /// # Returns
+    /// A serialized proof
+    pub fn pok_partial_commitment_prove<E: Pairing>(
+        commitment: &Commitment<E>,
+        issuer_indices: &[usize],
+        rng: &mut impl Rng,
+    ) -> Result<Vec<u8>, CommitmentProofError> {
+        check_attribute_indices(commitment.pp.n, issuer_indices)?;
+
+        let bases = commitment.pp.get_g1_bases_excluding(issuer_indices);
+        let exponents: Vec<E::ScalarField> = commitment
+            .get_exponents()
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| !issuer_indices.contains(i))
+            .map(|(_, e)| e)
+            .collect();
+        let partial_commitment = E::G1::msm_unchecked(&bases, &exponents).into_affine();
+
+        let mut schnorr_commitment = SchnorrProtocol::commit(&bases, rng);
+        let challenge = commitment_challenge::<E, _>(
+            &bases,
+            &partial_commitment,
+            &schnorr_commitment.commited_blindings,
+        )?;
+        let responses =
+            SchnorrProtocol::prove_and_erase(&mut schnorr_commitment, &exponents, &challenge);
+
+        let proof: CommitmentProof<E> = CommitmentProof {
+            commitment: partial_commitment,
+            schnorr_commitment,
+            challenge,
+            responses: responses.0,
+        };
+
+        let mut serialized_proof = Vec::new();
+        proof.serialize_compressed(&mut serialized_proof)?;
+
+        Ok(serialized_proof)
+    }
+
+    /// Verify a proof from `pok_partial_commitment_prove` over the bases that skip `issuer_indices`
+    ///
+    /// # Returns
+    /// The proven partial commitment if the proof is valid, `None` otherwise
+    pub fn pok_partial_commitment_verify<E: Pairing>(
+        pp: &PublicParams<E>,
+        issuer_indices: &[usize],
+        serialized_proof: &[u8],
+    ) -> Result<Option<E::G1Affine>, CommitmentProofError> {
+        check_attribute_indices(pp.n, issuer_indices)?;
+        let proof: CommitmentProof<E> =
+            CanonicalDeserialize::deserialize_compressed(serialized_proof)?;
+
+        // a proof over the full bases would let the user fix the issuer's attributes
+        let bases = pp.get_g1_bases_excluding(issuer_indices);
+        let challenge = commitment_challenge::<E, _>(
+            &bases,
+            &proof.commitment,
+            &proof.schnorr_commitment.commited_blindings,
+        )?;
+        let is_valid = challenge == proof.challenge && verify_g1(&bases, &proof);
+
+        Ok(is_valid.then_some(proof.commitment))
+    }
+
+    pub(crate) fn context_challenge<E: Pairing>(
+        commitment: &E::G1Affine,
+        schnorr_commitment: &E::G1Affine,
+        disclosure: &Disclosure<E::ScalarField>,
+        context: &PresentationContext,
+    ) -> Result<E::ScalarField, CommitmentProofError> {
+        let mut transcript = Vec::new();
+        commitment.serialize_compressed(&mut transcript)?;
+        schnorr_commitment.serialize_compressed(&mut transcript)?;
+        disclosure.serialize_compressed(&mut transcript)?;
+        Ok(context.challenge(b"ps-utt-show", &transcript))
+    }
+
+    /// the responses of disclosed messages must be ρ + c·m, the response for r is never disclosed
+    pub(crate) fn check_disclosure<E: Pairing>(
+        pp: &PublicParams<E>,
+        proof: &CommitmentProof<E>,
+        disclosure: &Disclosure<E::ScalarField>,
+    ) -> bool {
+        proof.responses.len() == pp.n + 1
+            && disclosure.verify(&proof.responses[..pp.n], &proof.challenge)
+    }
+
     /// Generate a proof of knowledge of a commitment in G2
     ///
     /// # Arguments
     /// * `commitment` - The commitment to prove knowledge of
+    /// * `rng` - Source of the Schnorr blindings
     ///
     /// # Returns
     /// A serialized proof
     pub fn pok_commitment_prove_g2<E: Pairing>(
         commitment: &Commitment<E>,
+        rng: &mut impl Rng,
     ) -> Result<Vec<u8>, CommitmentProofError> {
-        let mut rng = ark_std::test_rng();
-
         // Get bases and exponents for the proof
         let bases = commitment.pp.get_g2_bases();
         let exponents = commitment.get_exponents();
 
         // Generate Schnorr commitment
-        let schnorr_commitment = SchnorrProtocol::commit(&bases, &mut rng);
+        let mut schnorr_commitment = SchnorrProtocol::commit(&bases, rng);
 
-        // Generate challenge
-        let challenge = E::ScalarField::rand(&mut rng);
+        // Fiat-Shamir challenge over the bases, the commitment and T
+        let challenge = commitment_challenge::<E, _>(
+            &bases,
+            &commitment.cmg2,
+            &schnorr_commitment.commited_blindings,
+        )?;
 
         // Generate responses
-        let responses = SchnorrProtocol::prove(&schnorr_commitment, &exponents, &challenge);
+        let responses =
+            SchnorrProtocol::prove_and_erase(&mut schnorr_commitment, &exponents, &challenge);
 
         // Create and serialize proof with explicit type annotation
         let proof: CommitmentProofG2<E> = CommitmentProofG2 {
             commitment: commitment.cmg2,
             schnorr_commitment,
-            bases,
             challenge,
             responses: responses.0,
         };
@@ -138,44 +396,98 @@ impl CommitmentProofs {
     /// Verify a proof of knowledge of a commitment in G1
     ///
     /// # Arguments
+    /// * `pp` - The verifier's public parameters, the proof is checked against their bases
     /// * `serialized_proof` - The serialized proof to verify
     ///
     /// # Returns
     /// `true` if the proof is valid, `false` otherwise
     pub fn pok_commitment_verify<E: Pairing>(
+        pp: &PublicParams<E>,
         serialized_proof: &[u8],
     ) -> Result<bool, CommitmentProofError> {
         let proof: CommitmentProof<E> =
             CanonicalDeserialize::deserialize_compressed(serialized_proof)?;
 
-        // Verify using Schnorr protocol
-        let is_valid = SchnorrProtocol::verify(
-            &proof.bases,
+        let bases = pp.get_g1_bases();
+        let challenge = commitment_challenge::<E, _>(
+            &bases,
             &proof.commitment,
-            &proof.schnorr_commitment,
-            &SchnorrResponses(proof.responses.clone()),
-            &proof.challenge,
-        );
+            &proof.schnorr_commitment.commited_blindings,
+        )?;
+        Ok(challenge == proof.challenge && verify_g1(&bases, &proof))
+    }
 
-        Ok(is_valid)
+    /// Verify many proofs of knowledge of a commitment in G1 together, for bulk issuance
+    ///
+    /// # Arguments
+    /// * `pp` - The verifier's public parameters, every proof is checked against their bases
+    /// * `serialized_proofs` - The serialized proofs to verify
+    /// * `rng` - Source of the batch randomizers
+    ///
+    /// # Returns
+    /// The deserialized proofs, or the index of the first invalid one
+    pub fn pok_commitment_batch_verify<E: Pairing, R: Rng>(
+        pp: &PublicParams<E>,
+        serialized_proofs: &[Vec<u8>],
+        rng: &mut R,
+    ) -> Result<Vec<CommitmentProof<E>>, CommitmentProofError> {
+        let proofs = serialized_proofs
+            .iter()
+            .map(|bytes| CommitmentProof::<E>::deserialize_compressed(&bytes[..]))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let bases = pp.get_g1_bases();
+        let mut batch = SchnorrBatch::<E>::new();
+        for (i, proof) in proofs.iter().enumerate() {
+            let challenge = commitment_challenge::<E, _>(
+                &bases,
+                &proof.commitment,
+                &proof.schnorr_commitment.commited_blindings,
+            )?;
+            if challenge != proof.challenge {
+                return Err(BatchError::InvalidProof(i).into());
+            }
+            batch.add_g1(SchnorrClaim {
+                bases: bases.clone(),
+                statement: proof.commitment,
+                commitment: proof.schnorr_commitment.commited_blindings,
+                challenge: proof.challenge,
+                responses: proof.responses.clone(),
+            });
+        }
+        batch.verify(rng)?;
+
+        Ok(proofs)
     }
 
     /// Verify a proof of knowledge of a commitment in G2
     ///
     /// # Arguments
+    /// * `pp` - The verifier's public parameters, the proof is checked against their bases
     /// * `serialized_proof` - The serialized proof to verify
     ///
     /// # Returns
     /// `true` if the proof is valid, `false` otherwise
     pub fn pok_commitment_verify_g2<E: Pairing>(
+        pp: &PublicParams<E>,
         serialized_proof: &[u8],
     ) -> Result<bool, CommitmentProofError> {
         let proof: CommitmentProofG2<E> =
             CanonicalDeserialize::deserialize_compressed(serialized_proof)?;
 
+        let bases = pp.get_g2_bases();
+        let challenge = commitment_challenge::<E, _>(
+            &bases,
+            &proof.commitment,
+            &proof.schnorr_commitment.commited_blindings,
+        )?;
+        if challenge != proof.challenge || proof.responses.len() != bases.len() {
+            return Ok(false);
+        }
+
         // Verify using Schnorr protocol
         let is_valid = SchnorrProtocol::verify(
-            &proof.bases,
+            &bases,
             &proof.commitment,
             &proof.schnorr_commitment,
             &SchnorrResponses(proof.responses.clone()),
@@ -189,34 +501,36 @@ impl CommitmentProofs {
     ///
     /// # Arguments
     /// * `commitments` - The commitments to prove equality for
+    /// * `rng` - Source of the Schnorr blindings
     ///
     /// # Returns
     /// A serialized proof
     pub fn prove_equality<E: Pairing>(
         commitments: &[Commitment<E>],
+        rng: &mut impl Rng,
     ) -> Result<Vec<u8>, CommitmentProofError> {
-        let mut rng = ark_std::test_rng();
-
         // Generate equal blindness for equality proofs at index 0
-        let equal_blindness = E::ScalarField::rand(&mut rng);
+        let equal_blindness = E::ScalarField::rand(rng);
         let mut schnorr_commitments = Vec::with_capacity(commitments.len());
         let mut responses = Vec::with_capacity(commitments.len());
 
         // Generate Schnorr commitments
-        for commitment in commitments.iter() {
-            let bases = commitment.pp.get_g1_bases();
+        let bases: Vec<Vec<E::G1Affine>> =
+            commitments.iter().map(|c| c.pp.get_g1_bases()).collect();
+        for commitment_bases in &bases {
             let schnorr_commitment =
-                SchnorrProtocol::commit_equality(&bases, &mut rng, &equal_blindness, 0);
+                SchnorrProtocol::commit_equality(commitment_bases, rng, &equal_blindness, 0);
             schnorr_commitments.push(schnorr_commitment);
         }
+        let statements: Vec<E::G1Affine> = commitments.iter().map(|c| c.cmg1).collect();
 
-        // Generate challenge
-        let challenge = E::ScalarField::rand(&mut rng);
+        // Fiat-Shamir challenge over every commitment and its T
+        let challenge = equality_challenge::<E>(&bases, &statements, &schnorr_commitments)?;
 
         // Generate responses
         for (i, commitment) in commitments.iter().enumerate() {
-            let response = SchnorrProtocol::prove(
-                &schnorr_commitments[i],
+            let response = SchnorrProtocol::prove_and_erase(
+                &mut schnorr_commitments[i],
                 &commitment.get_exponents(),
                 &challenge,
             );
@@ -225,9 +539,8 @@ impl CommitmentProofs {
 
         // Create equality proof with explicit type annotation
         let equality_proof: CommitmentEqualityProof<E> = CommitmentEqualityProof {
-            commitments: commitments.iter().map(|c| c.cmg1).collect(),
-            schnorr_commitments: schnorr_commitments.clone(),
-            bases: commitments.iter().map(|c| c.pp.get_g1_bases()).collect(),
+            commitments: statements,
+            schnorr_commitments,
             challenge,
             responses: responses.iter().map(|r| r.0.clone()).collect(),
         };
@@ -242,21 +555,43 @@ impl CommitmentProofs {
     /// Verify a proof that multiple commitments share the same value at index 0
     ///
     /// # Arguments
+    /// * `pps` - The verifier's public parameters for each commitment, in proof order
     /// * `serialized_proof` - The serialized proof to verify
     ///
     /// # Returns
     /// `true` if the proof is valid, `false` otherwise
     pub fn verify_equality<E: Pairing>(
+        pps: &[&PublicParams<E>],
         serialized_proof: &[u8],
     ) -> Result<bool, CommitmentProofError> {
         // Deserialize the proof
         let proof: CommitmentEqualityProof<E> =
             CanonicalDeserialize::deserialize_compressed(serialized_proof)?;
 
+        if proof.commitments.len() != pps.len()
+            || proof.schnorr_commitments.len() != pps.len()
+            || proof.responses.len() != pps.len()
+        {
+            return Err(CommitmentProofError::MismatchedCommitmentLengths);
+        }
+        if pps.is_empty() {
+            return Ok(false);
+        }
+
+        let bases: Vec<Vec<E::G1Affine>> = pps.iter().map(|pp| pp.get_g1_bases()).collect();
+        let challenge =
+            equality_challenge::<E>(&bases, &proof.commitments, &proof.schnorr_commitments)?;
+        if challenge != proof.challenge {
+            return Ok(false);
+        }
+
         // First verify each individual commitment
-        for i in 0..proof.commitments.len() {
+        for (i, bases) in bases.iter().enumerate() {
+            if proof.responses[i].len() != bases.len() {
+                return Ok(false);
+            }
             let is_valid = SchnorrProtocol::verify(
-                &proof.bases[i],
+                bases,
                 &proof.commitments[i],
                 &proof.schnorr_commitments[i],
                 &SchnorrResponses(proof.responses[i].clone()),
@@ -283,30 +618,34 @@ impl CommitmentProofs {
     ///
     /// # Arguments
     /// * `commitment` - The commitment to prove is to zero
+    /// * `rng` - Source of the Schnorr blindings
     ///
     /// # Returns
     /// A serialized proof
     pub fn prove_zero<E: Pairing>(
         commitment: &Commitment<E>,
+        rng: &mut impl Rng,
     ) -> Result<Vec<u8>, CommitmentProofError> {
-        let mut rng = ark_std::test_rng();
-
         // Get bases and exponents for the proof
         let bases = commitment.pp.get_g1_bases();
         let exponents = commitment.get_exponents();
 
         // Generate Schnorr commitment
-        let schnorr_commitment = SchnorrProtocol::commit(&bases, &mut rng);
+        let mut schnorr_commitment = SchnorrProtocol::commit(&bases, rng);
 
-        // Generate challenge and responses
-        let challenge = E::ScalarField::rand(&mut rng);
-        let responses = SchnorrProtocol::prove(&schnorr_commitment, &exponents, &challenge);
+        // Fiat-Shamir challenge over the bases, the commitment and T, then responses
+        let challenge = commitment_challenge::<E, _>(
+            &bases,
+            &commitment.cmg1,
+            &schnorr_commitment.commited_blindings,
+        )?;
+        let responses =
+            SchnorrProtocol::prove_and_erase(&mut schnorr_commitment, &exponents, &challenge);
 
         // Create and serialize proof with explicit type annotation
         let proof: CommitmentProof<E> = CommitmentProof {
             commitment: commitment.cmg1,
             schnorr_commitment,
-            bases,
             challenge,
             responses: responses.0,
         };
@@ -318,104 +657,201 @@ impl CommitmentProofs {
     }
 }
 
+// responses are checked against the verifier's bases, msm_unchecked would silently truncate
+fn verify_g1<E: Pairing>(bases: &[E::G1Affine], proof: &CommitmentProof<E>) -> bool {
+    proof.responses.len() == bases.len()
+        && SchnorrProtocol::verify(
+            bases,
+            &proof.commitment,
+            &proof.schnorr_commitment,
+            &SchnorrResponses(proof.responses.clone()),
+            &proof.challenge,
+        )
+}
+
+/// Fiat-Shamir challenge of a commitment proof in G1 or G2, over the verifier's bases, the
+/// commitment and T
+fn commitment_challenge<E: Pairing, G: CanonicalSerialize>(
+    bases: &[G],
+    commitment: &G,
+    schnorr_commitment: &G,
+) -> Result<E::ScalarField, CommitmentProofError> {
+    let mut transcript = b"ps-utt-commitment-proof".to_vec();
+    bases.serialize_compressed(&mut transcript)?;
+    commitment.serialize_compressed(&mut transcript)?;
+    schnorr_commitment.serialize_compressed(&mut transcript)?;
+    Ok(HashUtil::<E::ScalarField, E::G1Affine>::hash_to_field(
+        &transcript,
+    ))
+}
+
+/// Fiat-Shamir challenge of an equality proof, over each commitment's bases, the commitments
+/// and their T
+fn equality_challenge<E: Pairing>(
+    bases: &[Vec<E::G1Affine>],
+    commitments: &[E::G1Affine],
+    schnorr_commitments: &[SchnorrCommitment<E::G1Affine>],
+) -> Result<E::ScalarField, CommitmentProofError> {
+    let mut transcript = b"ps-utt-equality-proof".to_vec();
+    bases.serialize_compressed(&mut transcript)?;
+    commitments.serialize_compressed(&mut transcript)?;
+    for schnorr_commitment in schnorr_commitments {
+        schnorr_commitment
+            .commited_blindings
+            .serialize_compressed(&mut transcript)?;
+    }
+    Ok(HashUtil::<E::ScalarField, E::G1Affine>::hash_to_field(
+        &transcript,
+    ))
+}
+
+/// issuer attribute indices must be in range and distinct
+pub fn check_attribute_indices(n: usize, indices: &[usize]) -> Result<(), CommitmentProofError> {
+    for (k, &i) in indices.iter().enumerate() {
+        if i >= n || indices[..k].contains(&i) {
+            return Err(CommitmentProofError::InvalidAttributeIndex(i));
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::publicparams::PublicParams;
-    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ec::pairing::Pairing;
+    use ark_ec::AffineRepr;
     use ark_std::{One, Zero};
 
-    #[test]
-    fn test_commitment_knowledge_proof() {
+    fn test_commitment_knowledge_proof<E: Pairing>() {
         let mut rng = ark_std::test_rng();
-        let context = Fr::rand(&mut rng);
+        let context = E::ScalarField::rand(&mut rng);
 
         // Create public parameters and random messages
-        let pp = PublicParams::<Bls12_381>::new(&4, &context, &mut rng);
-        let messages: Vec<_> = (0..pp.n).map(|_| Fr::rand(&mut rng)).collect();
-        let r = Fr::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&4, &context, &mut rng);
+        let messages: Vec<_> = (0..pp.n).map(|_| E::ScalarField::rand(&mut rng)).collect();
+        let r = E::ScalarField::rand(&mut rng);
 
         // Create commitment and prove knowledge
         let commitment = Commitment::new(&pp, &messages, &r);
-        let proof = CommitmentProofs::pok_commitment_prove(&commitment).unwrap();
+        let proof = CommitmentProofs::pok_commitment_prove(&commitment, &mut rng).unwrap();
 
         // Verify proof
-        assert!(CommitmentProofs::pok_commitment_verify::<Bls12_381>(&proof).unwrap());
+        assert!(CommitmentProofs::pok_commitment_verify::<E>(&pp, &proof).unwrap());
+    }
+
+    fn test_commitment_proof_with_tables<E: Pairing>() {
+        let mut rng = ark_std::test_rng();
+        let context = E::ScalarField::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&4, &context, &mut rng);
+        let tables = pp.precompute();
+        let messages: Vec<_> = (0..pp.n).map(|_| E::ScalarField::rand(&mut rng)).collect();
+        let r = E::ScalarField::rand(&mut rng);
+        let commitment = Commitment::new_with_tables(&pp, &tables, &messages, &r);
+
+        let presentation_context = PresentationContext::new(b"verifier", b"nonce", 0);
+        let proof = CommitmentProofs::pok_commitment_prove_with_tables(
+            &commitment,
+            &tables,
+            &presentation_context,
+            &mut rng,
+        )
+        .unwrap();
+        let proven = CommitmentProofs::pok_commitment_verify_with_context::<E>(
+            &pp,
+            &proof,
+            &Disclosure::default(),
+            &presentation_context,
+        )
+        .unwrap();
+        assert_eq!(proven, Some(commitment.cmg1));
     }
 
-    #[test]
-    fn test_commitment_knowledge_proof_g2() {
+    fn test_commitment_knowledge_proof_g2<E: Pairing>() {
         let mut rng = ark_std::test_rng();
-        let context = Fr::rand(&mut rng);
+        let context = E::ScalarField::rand(&mut rng);
 
         // Create public parameters and random messages
-        let pp = PublicParams::<Bls12_381>::new(&4, &context, &mut rng);
-        let messages: Vec<_> = (0..pp.n).map(|_| Fr::rand(&mut rng)).collect();
-        let r = Fr::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&4, &context, &mut rng);
+        let messages: Vec<_> = (0..pp.n).map(|_| E::ScalarField::rand(&mut rng)).collect();
+        let r = E::ScalarField::rand(&mut rng);
 
         // Create commitment and prove knowledge
         let commitment = Commitment::new(&pp, &messages, &r);
-        let proof = CommitmentProofs::pok_commitment_prove_g2(&commitment).unwrap();
+        let proof = CommitmentProofs::pok_commitment_prove_g2(&commitment, &mut rng).unwrap();
 
         // Verify proof
-        assert!(CommitmentProofs::pok_commitment_verify_g2::<Bls12_381>(&proof).unwrap());
+        assert!(CommitmentProofs::pok_commitment_verify_g2::<E>(&pp, &proof).unwrap());
+
+        // a commitment under other parameters does not verify against ours
+        let other_pp = PublicParams::<E>::new(&4, &context, &mut rng);
+        let other = Commitment::new(&other_pp, &messages, &r);
+        let proof = CommitmentProofs::pok_commitment_prove_g2(&other, &mut rng).unwrap();
+        assert!(!CommitmentProofs::pok_commitment_verify_g2::<E>(&pp, &proof).unwrap());
     }
 
-    #[test]
-    fn test_commitment_equality_proofs_2() {
+    fn test_commitment_equality_proofs_2<E: Pairing>() {
         let mut rng = ark_std::test_rng();
-        let context = Fr::rand(&mut rng);
+        let context = E::ScalarField::rand(&mut rng);
 
         // Create two public parameter sets
-        let pp1 = PublicParams::<Bls12_381>::new(&4, &context, &mut rng);
-        let pp2 = PublicParams::<Bls12_381>::new(&4, &context, &mut rng);
+        let pp1 = PublicParams::<E>::new(&4, &context, &mut rng);
+        let pp2 = PublicParams::<E>::new(&4, &context, &mut rng);
 
         // Create two commitments with same message at index 0
-        let shared_message = Fr::rand(&mut rng);
-        let mut messages1: Vec<_> = (0..pp1.n).map(|_| Fr::rand(&mut rng)).collect();
-        let mut messages2: Vec<_> = (0..pp2.n).map(|_| Fr::rand(&mut rng)).collect();
+        let shared_message = E::ScalarField::rand(&mut rng);
+        let mut messages1: Vec<_> = (0..pp1.n).map(|_| E::ScalarField::rand(&mut rng)).collect();
+        let mut messages2: Vec<_> = (0..pp2.n).map(|_| E::ScalarField::rand(&mut rng)).collect();
         messages1[0] = shared_message;
         messages2[0] = shared_message;
 
-        let r1 = Fr::rand(&mut rng);
-        let r2 = Fr::rand(&mut rng);
+        let r1 = E::ScalarField::rand(&mut rng);
+        let r2 = E::ScalarField::rand(&mut rng);
 
         // Create commitments and prove equality
         let commitment1 = Commitment::new(&pp1, &messages1, &r1);
         let commitment2 = Commitment::new(&pp2, &messages2, &r2);
-        let proof = CommitmentProofs::prove_equality(&[commitment1, commitment2]).unwrap();
+        let proof =
+            CommitmentProofs::prove_equality(&[commitment1, commitment2], &mut rng).unwrap();
 
         // Verify equality proof
-        assert!(CommitmentProofs::verify_equality::<Bls12_381>(&proof).unwrap());
+        assert!(CommitmentProofs::verify_equality::<E>(&[&pp1, &pp2], &proof).unwrap());
+
+        // the verifier's parameters are matched to the commitments in order
+        assert!(!CommitmentProofs::verify_equality::<E>(&[&pp2, &pp1], &proof).unwrap());
+        assert!(matches!(
+            CommitmentProofs::verify_equality::<E>(&[&pp1], &proof),
+            Err(CommitmentProofError::MismatchedCommitmentLengths)
+        ));
     }
 
-    #[test]
-    fn test_commitment_equality_proofs_10() {
+    fn test_commitment_equality_proofs_10<E: Pairing>() {
         let mut rng = ark_std::test_rng();
 
         // Create 10 different public parameters
-        let context = Fr::rand(&mut rng);
-        let public_params: Vec<PublicParams<Bls12_381>> = (0..10)
-            .map(|_| PublicParams::<Bls12_381>::new(&4, &context, &mut rng))
+        let context = E::ScalarField::rand(&mut rng);
+        let public_params: Vec<PublicParams<E>> = (0..10)
+            .map(|_| PublicParams::<E>::new(&4, &context, &mut rng))
             .collect();
 
         // Create a shared message for index 0 in all commitments
-        let shared_message = Fr::rand(&mut rng);
+        let shared_message = E::ScalarField::rand(&mut rng);
 
         // Create 10 message vectors, each with the shared message at index 0
-        let messages: Vec<Vec<Fr>> = (0..10)
+        let messages: Vec<Vec<E::ScalarField>> = (0..10)
             .map(|_| {
-                let mut msgs: Vec<_> = (0..4).map(|_| Fr::rand(&mut rng)).collect();
+                let mut msgs: Vec<_> = (0..4).map(|_| E::ScalarField::rand(&mut rng)).collect();
                 msgs[0] = shared_message;
                 msgs
             })
             .collect();
 
         // Generate 10 random blinding factors
-        let blinding_factors: Vec<Fr> = (0..10).map(|_| Fr::rand(&mut rng)).collect();
+        let blinding_factors: Vec<E::ScalarField> =
+            (0..10).map(|_| E::ScalarField::rand(&mut rng)).collect();
 
         // Create 10 commitments
-        let commitments: Vec<Commitment<Bls12_381>> = messages
+        let commitments: Vec<Commitment<E>> = messages
             .iter()
             .zip(public_params.iter())
             .zip(blinding_factors.iter())
@@ -423,12 +859,13 @@ mod tests {
             .collect();
 
         // Create and verify the equality proof
-        let proof = CommitmentProofs::prove_equality(&commitments).unwrap();
-        assert!(CommitmentProofs::verify_equality::<Bls12_381>(&proof).unwrap());
+        let pps: Vec<_> = public_params.iter().collect();
+        let proof = CommitmentProofs::prove_equality(&commitments, &mut rng).unwrap();
+        assert!(CommitmentProofs::verify_equality::<E>(&pps, &proof).unwrap());
 
         // Test that proof fails with different messages
         let mut invalid_messages = messages[0].clone();
-        invalid_messages[0] = Fr::rand(&mut rng); // Change the shared message
+        invalid_messages[0] = E::ScalarField::rand(&mut rng); // Change the shared message
 
         let invalid_commitment =
             Commitment::new(&public_params[0], &invalid_messages, &blinding_factors[0]);
@@ -436,13 +873,148 @@ mod tests {
         let mut invalid_commitments = commitments.clone();
         invalid_commitments[0] = invalid_commitment;
 
-        let invalid_proof = CommitmentProofs::prove_equality(&invalid_commitments);
+        let invalid_proof = CommitmentProofs::prove_equality(&invalid_commitments, &mut rng);
 
         // Either the proof creation fails or the verification fails
         assert!(
             invalid_proof.is_err()
-                || !CommitmentProofs::verify_equality::<Bls12_381>(&invalid_proof.unwrap())
-                    .unwrap()
+                || !CommitmentProofs::verify_equality::<E>(&pps, &invalid_proof.unwrap()).unwrap()
         );
     }
+
+    fn test_commitment_proof_substituted_bases<E: Pairing>() {
+        let mut rng = ark_std::test_rng();
+        let context = E::ScalarField::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&4, &context, &mut rng);
+
+        // a point nobody can open under pp, made the first base so its exponent is known to be one
+        let target = E::G1Affine::rand(&mut rng);
+        let mut bases = pp.get_g1_bases();
+        bases[0] = target;
+        let mut exponents = vec![E::ScalarField::zero(); bases.len()];
+        exponents[0] = E::ScalarField::one();
+
+        let schnorr_commitment = SchnorrProtocol::commit(&bases, &mut rng);
+        let challenge = E::ScalarField::rand(&mut rng);
+        let responses = SchnorrProtocol::prove(&schnorr_commitment, &exponents, &challenge);
+        assert!(SchnorrProtocol::verify(
+            &bases,
+            &target,
+            &schnorr_commitment,
+            &responses,
+            &challenge
+        ));
+
+        let proof: CommitmentProof<E> = CommitmentProof {
+            commitment: target,
+            schnorr_commitment,
+            challenge,
+            responses: responses.0,
+        };
+        let mut serialized_proof = Vec::new();
+        proof.serialize_compressed(&mut serialized_proof).unwrap();
+        assert!(!CommitmentProofs::pok_commitment_verify::<E>(&pp, &serialized_proof).unwrap());
+
+        // fewer responses than bases must not pass as a shorter msm
+        let messages: Vec<_> = (0..pp.n).map(|_| E::ScalarField::rand(&mut rng)).collect();
+        let commitment = Commitment::new(&pp, &messages, &E::ScalarField::rand(&mut rng));
+        let serialized_proof =
+            CommitmentProofs::pok_commitment_prove(&commitment, &mut rng).unwrap();
+        let mut proof =
+            CommitmentProof::<E>::deserialize_compressed(&serialized_proof[..]).unwrap();
+        proof.responses.pop();
+        let mut serialized_proof = Vec::new();
+        proof.serialize_compressed(&mut serialized_proof).unwrap();
+        assert!(!CommitmentProofs::pok_commitment_verify::<E>(&pp, &serialized_proof).unwrap());
+    }
+
+    /// Schnorr commitment T = Σ b_i·z_i − c·X that makes random responses verify for any
+    /// statement under a challenge the prover picked
+    fn simulate<G: AffineRepr>(
+        bases: &[G],
+        statement: G,
+        challenge: G::ScalarField,
+        responses: &[G::ScalarField],
+    ) -> SchnorrCommitment<G> {
+        let commited_blindings =
+            (G::Group::msm_unchecked(bases, responses) - statement * challenge).into_affine();
+        SchnorrCommitment {
+            random_blindings: Vec::new(),
+            commited_blindings,
+        }
+    }
+
+    fn test_commitment_proofs_simulated<E: Pairing>() {
+        let mut rng = ark_std::test_rng();
+        let context = E::ScalarField::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&4, &context, &mut rng);
+        let challenge = E::ScalarField::rand(&mut rng);
+        let responses: Vec<_> = (0..=pp.n).map(|_| E::ScalarField::rand(&mut rng)).collect();
+
+        // a forged (T, c) pair for G1 and G2 commitments nobody can open
+        let bases = pp.get_g1_bases();
+        let target = E::G1Affine::rand(&mut rng);
+        let proof: CommitmentProof<E> = CommitmentProof {
+            commitment: target,
+            schnorr_commitment: simulate(&bases, target, challenge, &responses),
+            challenge,
+            responses: responses.clone(),
+        };
+        assert!(verify_g1(&bases, &proof));
+        let mut forged = Vec::new();
+        proof.serialize_compressed(&mut forged).unwrap();
+        assert!(!CommitmentProofs::pok_commitment_verify::<E>(&pp, &forged).unwrap());
+
+        let messages: Vec<_> = (0..pp.n).map(|_| E::ScalarField::rand(&mut rng)).collect();
+        let commitment = Commitment::new(&pp, &messages, &E::ScalarField::rand(&mut rng));
+        let honest = CommitmentProofs::pok_commitment_prove(&commitment, &mut rng).unwrap();
+        assert!(matches!(
+            CommitmentProofs::pok_commitment_batch_verify::<E, _>(&pp, &[honest, forged], &mut rng),
+            Err(CommitmentProofError::BatchError(BatchError::InvalidProof(
+                1
+            )))
+        ));
+
+        let target = E::G2Affine::rand(&mut rng);
+        let proof: CommitmentProofG2<E> = CommitmentProofG2 {
+            commitment: target,
+            schnorr_commitment: simulate(&pp.get_g2_bases(), target, challenge, &responses),
+            challenge,
+            responses: responses.clone(),
+        };
+        let mut forged = Vec::new();
+        proof.serialize_compressed(&mut forged).unwrap();
+        assert!(!CommitmentProofs::pok_commitment_verify_g2::<E>(&pp, &forged).unwrap());
+
+        // two unrelated commitments answered with the same first response
+        let other_pp = PublicParams::<E>::new(&4, &context, &mut rng);
+        let other_bases = other_pp.get_g1_bases();
+        let targets = [E::G1Affine::rand(&mut rng), E::G1Affine::rand(&mut rng)];
+        let mut other_responses: Vec<_> = (0..=other_pp.n)
+            .map(|_| E::ScalarField::rand(&mut rng))
+            .collect();
+        other_responses[0] = responses[0];
+        let proof: CommitmentEqualityProof<E> = CommitmentEqualityProof {
+            commitments: targets.to_vec(),
+            schnorr_commitments: vec![
+                simulate(&bases, targets[0], challenge, &responses),
+                simulate(&other_bases, targets[1], challenge, &other_responses),
+            ],
+            challenge,
+            responses: vec![responses, other_responses],
+        };
+        let mut forged = Vec::new();
+        proof.serialize_compressed(&mut forged).unwrap();
+        assert!(!CommitmentProofs::verify_equality::<E>(&[&pp, &other_pp], &forged).unwrap());
+    }
+
+    utils::curve_tests!(
+        test_commitment_knowledge_proof,
+        test_commitment_proof_with_tables,
+        test_commitment_knowledge_proof_g2,
+        test_commitment_proof_substituted_bases,
+        test_commitment_proofs_simulated,
+        test_commitment_equality_proofs_2,
+        test_commitment_equality_proofs_10
+    );
 }
@@ -1,17 +1,30 @@
 use ark_ec::pairing::Pairing;
 use ark_ec::AffineRepr;
-use ark_ec::CurveGroup;
+use ark_ec::{CurveGroup, VariableBaseMSM};
 use ark_ff::UniformRand;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::ops::{Mul, MulAssign};
 use ark_std::rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use utils::encoding::{Base64Point, HexScalar};
+use utils::fixed_base::{FixedBaseMsm, FixedBaseTable};
+use utils::pairing::batch_randomizer;
 
-#[derive(Clone)]
+#[serde_as]
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct PublicParams<E: Pairing> {
+    #[serde_as(as = "HexScalar")]
     pub context: E::ScalarField, //e.g. Hash to Field(dmv)
     pub n: usize,
+    #[serde_as(as = "Base64Point")]
     pub g1: E::G1Affine,
+    #[serde_as(as = "Base64Point")]
     pub g2: E::G2Affine,
+    #[serde_as(as = "Vec<Base64Point>")]
     pub ckg1: Vec<E::G1Affine>,
+    #[serde_as(as = "Vec<Base64Point>")]
     pub ckg2: Vec<E::G2Affine>,
 }
 
@@ -38,6 +51,20 @@ impl<E: Pairing> PublicParams<E> {
         }
     }
 
+    /// e(Σ ρ_i g_i, g2) = e(g1, Σ ρ_i g~_i), so g_i and g~_i share y_i for every i
+    pub fn is_consistent(&self, rng: &mut impl Rng) -> bool {
+        if self.ckg1.len() != self.n || self.ckg2.len() != self.n {
+            return false;
+        }
+        if self.ckg1.iter().any(|g| g.is_zero()) {
+            return false;
+        }
+        let rho: Vec<E::ScalarField> = (0..self.n).map(|_| batch_randomizer(rng)).collect();
+        let lhs = E::G1::msm_unchecked(&self.ckg1, &rho).into_affine();
+        let rhs = E::G2::msm_unchecked(&self.ckg2, &rho).into_affine();
+        E::pairing(lhs, self.g2) == E::pairing(self.g1, rhs)
+    }
+
     // gets all g1 bases, g_1,...,g_n,h
     pub fn get_g1_bases(&self) -> Vec<E::G1Affine> {
         // add g1 to end of ckg1
@@ -46,6 +73,19 @@ impl<E: Pairing> PublicParams<E> {
         g1_bases
     }
 
+    // gets g1 bases of the user-held attributes, skipping the excluded indices, then h
+    pub fn get_g1_bases_excluding(&self, excluded: &[usize]) -> Vec<E::G1Affine> {
+        let mut g1_bases: Vec<E::G1Affine> = self
+            .ckg1
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !excluded.contains(i))
+            .map(|(_, ck)| *ck)
+            .collect();
+        g1_bases.push(self.g1.clone());
+        g1_bases
+    }
+
     // gets all g2 bases, g_1,...,g_n,g
     pub fn get_g2_bases(&self) -> Vec<E::G2Affine> {
         // add g2 to end of ckg2
@@ -55,15 +95,64 @@ impl<E: Pairing> PublicParams<E> {
     }
 }
 
+/// Fixed-base tables for the commitment keys and generators, built once with
+/// `PublicParams::precompute` and serializable so a server can load them at startup
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PublicParamsTables<E: Pairing> {
+    /// [g_1, ..., g_n, g1], the G1 commitment bases
+    pub bases_g1: FixedBaseMsm<E::G1>,
+    /// [g~_1, ..., g~_n, g2], the G2 commitment bases
+    pub bases_g2: FixedBaseMsm<E::G2>,
+}
+
+impl<E: Pairing> PublicParamsTables<E> {
+    /// Table for g1, which is the last G1 base
+    pub fn g1(&self) -> &FixedBaseTable<E::G1> {
+        self.bases_g1.table(self.bases_g1.len() - 1)
+    }
+
+    /// Table for g2, which is the last G2 base
+    pub fn g2(&self) -> &FixedBaseTable<E::G2> {
+        self.bases_g2.table(self.bases_g2.len() - 1)
+    }
+}
+
+impl<E: Pairing> PublicParams<E> {
+    pub fn precompute(&self) -> PublicParamsTables<E> {
+        PublicParamsTables {
+            bases_g1: FixedBaseMsm::new(&self.get_g1_bases()),
+            bases_g2: FixedBaseMsm::new(&self.get_g2_bases()),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
-    use ark_bls12_381::{Bls12_381, Fr};
-    #[test]
-    fn test_pp_gen() {
+    use ark_ec::pairing::Pairing;
+    fn test_pp_gen<E: Pairing>() {
         let n = 4;
         let mut rng = ark_std::test_rng();
-        let context = Fr::rand(&mut rng);
-        let pp = PublicParams::<Bls12_381>::new(&n, &context, &mut rng);
+        let context = E::ScalarField::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&n, &context, &mut rng);
     }
+
+    fn test_pp_tables<E: Pairing>() {
+        let mut rng = ark_std::test_rng();
+        let context = E::ScalarField::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&4, &context, &mut rng);
+
+        let tables = pp.precompute();
+        assert_eq!(tables.bases_g1.bases(), pp.get_g1_bases());
+        assert_eq!(tables.bases_g2.bases(), pp.get_g2_bases());
+
+        let mut bytes = Vec::new();
+        tables.serialize_compressed(&mut bytes).unwrap();
+        let loaded = PublicParamsTables::<E>::deserialize_compressed(&bytes[..]).unwrap();
+        let scalar = E::ScalarField::rand(&mut rng);
+        assert_eq!(loaded.g1().mul(&scalar), pp.g1 * scalar);
+        assert_eq!(loaded.g2().mul(&scalar), pp.g2 * scalar);
+    }
+
+    utils::curve_tests!(test_pp_gen, test_pp_tables);
 }
@@ -0,0 +1,227 @@
+use ark_ec::{AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_ff::UniformRand;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::ops::Mul;
+use ark_std::rand::Rng;
+use thiserror::Error;
+use utils::hash::HashUtil;
+
+// Range proof by bit decomposition. The prover commits to every bit as C_j = g^b_j h^gamma_j and
+// proves each C_j opens to 0 or 1 with a CDS OR proof. The value commitment V = prod C_j^(2^j) =
+// g^v h^gamma is then implied, other proofs link v to it by proving knowledge of (v, gamma).
+
+#[derive(Error, Debug)]
+pub enum RangeProofError {
+    #[error("Value {value} does not fit in {bits} bits")]
+    ValueTooLarge { value: u64, bits: usize },
+}
+
+/// Pedersen bases for values of `bits` bits
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct RangeParams<G: AffineRepr> {
+    pub g: G,
+    pub h: G,
+    pub bits: usize,
+}
+
+impl<G: AffineRepr> RangeParams<G> {
+    pub fn new(bits: usize, rng: &mut impl Rng) -> Self {
+        assert!(bits > 0 && bits <= 64, "range must be 1 to 64 bits");
+        Self {
+            g: G::rand(rng),
+            h: G::rand(rng),
+            bits,
+        }
+    }
+
+    /// largest value a proof can cover
+    pub fn max_value(&self) -> u64 {
+        u64::MAX >> (64 - self.bits)
+    }
+}
+
+/// Bit commitments with one OR proof each, branch 0 proves C_j = h^gamma_j and branch 1
+/// proves C_j / g = h^gamma_j. The branch challenges add up to the proof challenge.
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct RangeProof<G: AffineRepr> {
+    pub bit_commitments: Vec<G>,
+    pub commitments_zero: Vec<G>,
+    pub commitments_one: Vec<G>,
+    pub challenges_zero: Vec<G::ScalarField>,
+    pub responses_zero: Vec<G::ScalarField>,
+    pub responses_one: Vec<G::ScalarField>,
+}
+
+impl<G: AffineRepr> RangeProof<G> {
+    /// Proves `value` fits in `params.bits` bits, returns the proof and gamma with
+    /// `value_commitment() = g^value h^gamma`
+    pub fn prove(
+        params: &RangeParams<G>,
+        value: u64,
+        rng: &mut impl Rng,
+    ) -> Result<(Self, G::ScalarField), RangeProofError> {
+        if value > params.max_value() {
+            return Err(RangeProofError::ValueTooLarge {
+                value,
+                bits: params.bits,
+            });
+        }
+        let g_neg = -params.g.into_group();
+
+        let mut bits = Vec::with_capacity(params.bits);
+        let mut gammas = Vec::with_capacity(params.bits);
+        let mut blindings = Vec::with_capacity(params.bits);
+        let mut simulated = Vec::with_capacity(params.bits);
+        let mut bit_commitments = Vec::with_capacity(params.bits);
+        let mut commitments_zero = Vec::with_capacity(params.bits);
+        let mut commitments_one = Vec::with_capacity(params.bits);
+        for j in 0..params.bits {
+            let bit = (value >> j) & 1 == 1;
+            let gamma = G::ScalarField::rand(rng);
+            let mut c = params.h.mul(gamma);
+            if bit {
+                c += params.g;
+            }
+            let k = G::ScalarField::rand(rng);
+            let sim_challenge = G::ScalarField::rand(rng);
+            let sim_response = G::ScalarField::rand(rng);
+
+            // the branch of the other bit value is simulated from its challenge and response
+            let real = params.h.mul(k);
+            if bit {
+                commitments_zero.push(params.h.mul(sim_response) - c.mul(sim_challenge));
+                commitments_one.push(real);
+            } else {
+                commitments_zero.push(real);
+                commitments_one.push(params.h.mul(sim_response) - (c + g_neg).mul(sim_challenge));
+            }
+            bit_commitments.push(c);
+            bits.push(bit);
+            gammas.push(gamma);
+            blindings.push(k);
+            simulated.push((sim_challenge, sim_response));
+        }
+
+        let mut proof = Self {
+            bit_commitments: G::Group::normalize_batch(&bit_commitments),
+            commitments_zero: G::Group::normalize_batch(&commitments_zero),
+            commitments_one: G::Group::normalize_batch(&commitments_one),
+            challenges_zero: Vec::with_capacity(params.bits),
+            responses_zero: Vec::with_capacity(params.bits),
+            responses_one: Vec::with_capacity(params.bits),
+        };
+        let challenge = proof.challenge(params);
+        for j in 0..params.bits {
+            let (sim_challenge, sim_response) = simulated[j];
+            let real_challenge = challenge - sim_challenge;
+            let real_response = blindings[j] + real_challenge * gammas[j];
+            if bits[j] {
+                proof.challenges_zero.push(sim_challenge);
+                proof.responses_zero.push(sim_response);
+                proof.responses_one.push(real_response);
+            } else {
+                proof.challenges_zero.push(real_challenge);
+                proof.responses_zero.push(real_response);
+                proof.responses_one.push(sim_response);
+            }
+        }
+
+        let gamma = gammas
+            .iter()
+            .zip(powers_of_two::<G>(params.bits))
+            .map(|(gamma, p)| *gamma * p)
+            .sum();
+        Ok((proof, gamma))
+    }
+
+    /// V = prod C_j^(2^j) = g^v h^gamma
+    pub fn value_commitment(&self) -> G {
+        G::Group::msm_unchecked(
+            &self.bit_commitments,
+            &powers_of_two::<G>(self.bit_commitments.len()),
+        )
+        .into_affine()
+    }
+
+    pub fn verify(&self, params: &RangeParams<G>) -> bool {
+        let n = params.bits;
+        if self.bit_commitments.len() != n
+            || self.commitments_zero.len() != n
+            || self.commitments_one.len() != n
+            || self.challenges_zero.len() != n
+            || self.responses_zero.len() != n
+            || self.responses_one.len() != n
+        {
+            return false;
+        }
+        let challenge = self.challenge(params);
+        (0..n).all(|j| {
+            let c = self.bit_commitments[j];
+            let challenge_zero = self.challenges_zero[j];
+            let challenge_one = challenge - challenge_zero;
+            params.h.mul(self.responses_zero[j]) == self.commitments_zero[j] + c.mul(challenge_zero)
+                && params.h.mul(self.responses_one[j])
+                    == self.commitments_one[j] + (c.into_group() - params.g).mul(challenge_one)
+        })
+    }
+
+    fn challenge(&self, params: &RangeParams<G>) -> G::ScalarField {
+        let mut transcript = b"ps-utt-range".to_vec();
+        params.serialize_compressed(&mut transcript).unwrap();
+        self.bit_commitments
+            .serialize_compressed(&mut transcript)
+            .unwrap();
+        self.commitments_zero
+            .serialize_compressed(&mut transcript)
+            .unwrap();
+        self.commitments_one
+            .serialize_compressed(&mut transcript)
+            .unwrap();
+        HashUtil::<G::ScalarField, G>::hash_to_field(&transcript)
+    }
+}
+
+fn powers_of_two<G: AffineRepr>(n: usize) -> Vec<G::ScalarField> {
+    (0..n).map(|j| G::ScalarField::from(1u64 << j)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ec::pairing::Pairing;
+
+    fn test_range_proof<E: Pairing>() {
+        let mut rng = ark_std::test_rng();
+        let params = RangeParams::<E::G1Affine>::new(16, &mut rng);
+
+        for value in [0, 1, 12345, params.max_value()] {
+            let (proof, gamma) = RangeProof::prove(&params, value, &mut rng).unwrap();
+            assert!(proof.verify(&params));
+            let expected = params.g.mul(E::ScalarField::from(value)) + params.h.mul(gamma);
+            assert_eq!(proof.value_commitment(), expected.into_affine());
+        }
+
+        assert!(matches!(
+            RangeProof::prove(&params, 1 << 16, &mut rng),
+            Err(RangeProofError::ValueTooLarge {
+                value: 65536,
+                bits: 16
+            })
+        ));
+
+        // a bit commitment to 2 has no valid branch
+        let (mut proof, _) = RangeProof::prove(&params, 1, &mut rng).unwrap();
+        proof.bit_commitments[0] = (proof.bit_commitments[0] + params.g).into_affine();
+        assert!(!proof.verify(&params));
+
+        // proofs are for the bit length of the parameters
+        let wide = RangeParams {
+            bits: 32,
+            ..params.clone()
+        };
+        let (proof, _) = RangeProof::prove(&params, 7, &mut rng).unwrap();
+        assert!(!proof.verify(&wide));
+    }
+
+    utils::curve_tests!(test_range_proof);
+}
@@ -3,21 +3,30 @@ use crate::keygen::{
     gen_keys, gen_keys_improved, SecretKey, SecretKeyImproved, VerificationKey,
     VerificationKeyImproved,
 };
-use crate::publicparams::PublicParams;
+use crate::publicparams::{PublicParams, PublicParamsTables};
 use ark_ec::pairing::Pairing;
 use ark_ec::VariableBaseMSM;
 use ark_ec::{AffineRepr, CurveGroup};
 use ark_ff::UniformRand;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::rand::Rng;
+use ark_std::sync::Mutex;
 use ark_std::{
     ops::{Add, Mul, Neg},
     One,
 };
-use utils::pairing::PairingCheck;
-
-#[derive(Clone, Debug)]
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use utils::encoding::Base64Point;
+use utils::pairing::{batch_randomizer, PairingCheck};
+
+#[serde_as]
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct PSUTTSignature<E: Pairing> {
+    #[serde_as(as = "Base64Point")]
     pub sigma1: E::G1Affine,
+    #[serde_as(as = "Base64Point")]
     pub sigma2: E::G1Affine,
 }
 
@@ -34,6 +43,19 @@ impl<E: Pairing> PSUTTSignature<E> {
         Self { sigma1, sigma2 }
     }
 
+    /// `sign` with the fixed-base table for g1 from `PublicParams::precompute`
+    pub fn sign_with_tables(
+        tables: &PublicParamsTables<E>,
+        sk: &SecretKey<E>,
+        cmg1: &E::G1Affine,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let u = E::ScalarField::rand(rng);
+        let sigma1 = tables.g1().mul(&u).into_affine();
+        let sigma2 = (cmg1.add(sk.sk)).mul(u).into_affine();
+        Self { sigma1, sigma2 }
+    }
+
     pub fn rerandomize(
         &self,
         pp: &PublicParams<E>,
@@ -129,9 +151,13 @@ impl<E: Pairing> PSUTTSignature<E> {
     }
 }
 
-#[derive(Clone, Debug)]
+#[serde_as]
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct PSUTTSignatureImproved<E: Pairing> {
+    #[serde_as(as = "Base64Point")]
     pub sigma1: E::G2Affine,
+    #[serde_as(as = "Base64Point")]
     pub sigma2: E::G2Affine,
 }
 
@@ -148,6 +174,19 @@ impl<E: Pairing> PSUTTSignatureImproved<E> {
         Self { sigma1, sigma2 }
     }
 
+    /// `sign` with the fixed-base table for g2 from `PublicParams::precompute`
+    pub fn sign_with_tables(
+        tables: &PublicParamsTables<E>,
+        sk: &SecretKeyImproved<E>,
+        cmg2: &E::G2Affine,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let u = E::ScalarField::rand(rng);
+        let sigma1 = tables.g2().mul(&u).into_affine();
+        let sigma2 = (cmg2.add(sk.sk)).mul(u).into_affine();
+        Self { sigma1, sigma2 }
+    }
+
     pub fn rerandomize(
         &self,
         pp: &PublicParams<E>,
@@ -212,30 +251,58 @@ impl<E: Pairing> PSUTTSignatureImproved<E> {
 
         check.verify()
     }
+
+    /// Verifies many signatures, each on its own G1 commitment, with one pairing check:
+    /// e(g1, Σ ρ_i sigma2_i) · ∏ e(-ρ_i (vk + cmg1_i), sigma1_i) = 1
+    pub fn verify_batch<R: Rng + Send>(
+        pp: &PublicParams<E>,
+        vk: &VerificationKeyImproved<E>,
+        signatures: &[(&Self, &E::G1Affine)],
+        rng: &mut R,
+    ) -> bool {
+        if signatures.is_empty() {
+            return true;
+        }
+
+        let rhos: Vec<E::ScalarField> = signatures.iter().map(|_| batch_randomizer(rng)).collect();
+        let sigma2s: Vec<E::G2Affine> = signatures.iter().map(|(sig, _)| sig.sigma2).collect();
+        let sigma2_sum = E::G2::msm_unchecked(&sigma2s, &rhos).into_affine();
+        let scaled_keys: Vec<E::G1> = signatures
+            .iter()
+            .zip(rhos.iter())
+            .map(|((_, cmg1), rho)| vk.vk.add(*cmg1).mul(rho.neg()))
+            .collect();
+        let scaled_keys = E::G1::normalize_batch(&scaled_keys);
+
+        let mut pairs = vec![(&pp.g1, &sigma2_sum)];
+        for (scaled_key, (sig, _)) in scaled_keys.iter().zip(signatures.iter()) {
+            pairs.push((scaled_key, &sig.sigma1));
+        }
+        PairingCheck::<E>::rand(&Mutex::new(&mut *rng), &pairs, &E::TargetField::one()).verify()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::commitment::Commitment;
-    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ec::pairing::Pairing;
 
-    #[test]
-    fn test_randomized_signature() {
+    fn test_randomized_signature<E: Pairing>() {
         let mut rng = ark_std::test_rng();
-        let context = Fr::rand(&mut rng);
-        let pp = PublicParams::<Bls12_381>::new(&4, &context, &mut rng);
+        let context = E::ScalarField::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&4, &context, &mut rng);
         let (sk, vk) = gen_keys(&pp, &mut rng);
-        let messages = (0..pp.n).map(|_| Fr::rand(&mut rng)).collect();
-        let r = Fr::rand(&mut rng);
+        let messages = (0..pp.n).map(|_| E::ScalarField::rand(&mut rng)).collect();
+        let r = E::ScalarField::rand(&mut rng);
         let commitment = Commitment::new(&pp, &messages, &r);
 
         let sig = PSUTTSignature::sign(&pp, &sk, &commitment.cmg1, &mut rng);
         let is_valid = sig.verify(&pp, &vk, &commitment.cmg1, &commitment.cmg2);
         assert!(is_valid);
 
-        let u_delta = Fr::rand(&mut rng);
-        let r_delta = Fr::rand(&mut rng);
+        let u_delta = E::ScalarField::rand(&mut rng);
+        let r_delta = E::ScalarField::rand(&mut rng);
         let randomized_commitment = commitment.create_randomized(&r_delta);
         let randomized_sig = sig.rerandomize(&pp, &r_delta, &u_delta);
 
@@ -246,24 +313,27 @@ mod tests {
             &randomized_commitment.cmg2,
         );
         assert!(is_randomized_valid, "randomized sig verification failed");
+
+        let tables = pp.precompute();
+        let sig = PSUTTSignature::sign_with_tables(&tables, &sk, &commitment.cmg1, &mut rng);
+        assert!(sig.verify(&pp, &vk, &commitment.cmg1, &commitment.cmg2));
     }
 
-    #[test]
-    fn test_randomized_signature_pairing_checker() {
+    fn test_randomized_signature_pairing_checker<E: Pairing>() {
         let mut rng = ark_std::test_rng();
-        let context = Fr::rand(&mut rng);
-        let pp = PublicParams::<Bls12_381>::new(&4, &context, &mut rng);
+        let context = E::ScalarField::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&4, &context, &mut rng);
         let (sk, vk) = gen_keys(&pp, &mut rng);
-        let messages = (0..pp.n).map(|_| Fr::rand(&mut rng)).collect();
-        let r = Fr::rand(&mut rng);
+        let messages = (0..pp.n).map(|_| E::ScalarField::rand(&mut rng)).collect();
+        let r = E::ScalarField::rand(&mut rng);
         let commitment = Commitment::new(&pp, &messages, &r);
 
         let sig = PSUTTSignature::sign(&pp, &sk, &commitment.cmg1, &mut rng);
         let is_valid = sig.verify(&pp, &vk, &commitment.cmg1, &commitment.cmg2);
         assert!(is_valid);
 
-        let u_delta = Fr::rand(&mut rng);
-        let r_delta = Fr::rand(&mut rng);
+        let u_delta = E::ScalarField::rand(&mut rng);
+        let r_delta = E::ScalarField::rand(&mut rng);
         let randomized_commitment = commitment.create_randomized(&r_delta);
         let randomized_sig = sig.rerandomize(&pp, &r_delta, &u_delta);
 
@@ -276,22 +346,21 @@ mod tests {
         assert!(is_randomized_valid, "randomized sig verification failed");
     }
 
-    #[test]
-    fn test_randomized_signature_pairing_checker_improved() {
+    fn test_randomized_signature_pairing_checker_improved<E: Pairing>() {
         let mut rng = ark_std::test_rng();
-        let context = Fr::rand(&mut rng);
-        let pp = PublicParams::<Bls12_381>::new(&4, &context, &mut rng);
+        let context = E::ScalarField::rand(&mut rng);
+        let pp = PublicParams::<E>::new(&4, &context, &mut rng);
         let (sk, vk) = gen_keys_improved(&pp, &mut rng);
-        let messages = (0..pp.n).map(|_| Fr::rand(&mut rng)).collect();
-        let r = Fr::rand(&mut rng);
+        let messages = (0..pp.n).map(|_| E::ScalarField::rand(&mut rng)).collect();
+        let r = E::ScalarField::rand(&mut rng);
         let commitment = Commitment::new(&pp, &messages, &r);
 
         let sig = PSUTTSignatureImproved::sign(&pp, &sk, &commitment.cmg2, &mut rng);
         let is_valid = sig.verify(&pp, &vk, &commitment.cmg1);
         assert!(is_valid);
 
-        let u_delta = Fr::rand(&mut rng);
-        let r_delta = Fr::rand(&mut rng);
+        let u_delta = E::ScalarField::rand(&mut rng);
+        let r_delta = E::ScalarField::rand(&mut rng);
         let randomized_commitment = commitment.create_randomized(&r_delta);
         let randomized_sig = sig.rerandomize(&pp, &r_delta, &u_delta);
 
@@ -301,5 +370,16 @@ mod tests {
             &randomized_commitment.cmg1,
         );
         assert!(is_randomized_valid, "randomized sig verification failed");
+
+        let tables = pp.precompute();
+        let sig =
+            PSUTTSignatureImproved::sign_with_tables(&tables, &sk, &commitment.cmg2, &mut rng);
+        assert!(sig.verify(&pp, &vk, &commitment.cmg1));
     }
+
+    utils::curve_tests!(
+        test_randomized_signature,
+        test_randomized_signature_pairing_checker,
+        test_randomized_signature_pairing_checker_improved
+    );
 }
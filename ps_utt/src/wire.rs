@@ -0,0 +1,144 @@
+use crate::anon_cred::{ShowCredential, UserCred};
+use crate::anon_cred_improved::{ShowCredentialImproved, UserCred as UserCredImproved};
+use crate::keygen::{SecretKey, SecretKeyImproved, VerificationKey, VerificationKeyImproved};
+use crate::proofsystem::CommitmentProof;
+use crate::publicparams::PublicParams;
+use crate::signature::{PSUTTSignature, PSUTTSignatureImproved};
+use utils::impl_envelope;
+
+// parameters and the G1 commitment proof are shared by both variants
+impl_envelope!(PublicParams, PsUtt, PublicParams, |pp| pp.n);
+impl_envelope!(CommitmentProof, PsUtt, IssuanceRequest);
+
+impl_envelope!(VerificationKey, PsUtt, PublicKey);
+impl_envelope!(SecretKey, PsUtt, SecretKey);
+impl_envelope!(PSUTTSignature, PsUtt, Signature);
+impl_envelope!(UserCred, PsUtt, Credential, |cred| cred
+    .commitment
+    .messages
+    .len());
+impl_envelope!(ShowCredential, PsUtt, Presentation);
+
+impl_envelope!(VerificationKeyImproved, PsUttImproved, PublicKey);
+impl_envelope!(SecretKeyImproved, PsUttImproved, SecretKey);
+impl_envelope!(PSUTTSignatureImproved, PsUttImproved, Signature);
+impl_envelope!(UserCredImproved, PsUttImproved, Credential, |cred| cred
+    .commitment
+    .messages
+    .len());
+impl_envelope!(ShowCredentialImproved, PsUttImproved, Presentation);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anon_cred::AnonCredProtocol;
+    use crate::anon_cred_improved::AnonCredProtocolImproved;
+    use crate::keygen::{gen_keys, gen_keys_improved};
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ff::UniformRand;
+    use ark_serialize::CanonicalDeserialize;
+    use ark_std::test_rng;
+    use utils::context::PresentationContext;
+    use utils::envelope::{assert_golden, Envelope, EnvelopeError, SchemeId};
+
+    fn golden(name: &str) -> String {
+        format!("{}/golden/{}.hex", env!("CARGO_MANIFEST_DIR"), name)
+    }
+
+    #[test]
+    fn test_psutt_wire_golden() {
+        let mut rng = test_rng();
+        let protocol = AnonCredProtocol::<Bls12_381>::new(3, &mut rng);
+        let (_, vk) = gen_keys(&protocol.pp, &mut rng);
+        let messages: Vec<Fr> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+        let user_cred = UserCred::new(&protocol.pp, &messages, Fr::rand(&mut rng));
+        let request = protocol.obtain(&user_cred, &mut rng).unwrap();
+        let signature = protocol.issue(&request, &mut rng).unwrap();
+        let context = PresentationContext::new(b"verifier", b"nonce-1", 1_700_000_000);
+        let presentation = protocol
+            .show(&user_cred.commitment, &signature, &context, &mut rng)
+            .unwrap();
+        let request = CommitmentProof::<Bls12_381>::deserialize_compressed(&request[..]).unwrap();
+
+        assert_golden(&golden("public_params"), &protocol.pp.to_envelope());
+        assert_golden(&golden("verification_key"), &vk.to_envelope());
+        assert_golden(&golden("issuance_request"), &request.to_envelope());
+        assert_golden(&golden("signature"), &signature.to_envelope());
+        assert_golden(&golden("credential"), &user_cred.to_envelope());
+        assert_golden(&golden("presentation"), &presentation.to_envelope());
+
+        let decoded =
+            ShowCredential::<Bls12_381>::from_envelope(&presentation.to_envelope()).unwrap();
+        assert!(protocol.verify(&decoded, &context).unwrap());
+        let cred = UserCred::<Bls12_381>::from_envelope(&user_cred.to_envelope()).unwrap();
+        assert_eq!(cred.commitment.cmg1, user_cred.commitment.cmg1);
+    }
+
+    #[test]
+    fn test_psutt_improved_wire_golden() {
+        let mut rng = test_rng();
+        let protocol = AnonCredProtocolImproved::<Bls12_381>::new(3, &mut rng);
+        let (_, vk) = gen_keys_improved(&protocol.pp, &mut rng);
+        let messages: Vec<Fr> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+        let user_cred = UserCredImproved::new(&protocol.pp, &messages, Fr::rand(&mut rng));
+        let request = protocol.obtain(&user_cred, &mut rng).unwrap();
+        let signature = protocol
+            .issue(&user_cred.commitment.cmg2, &request, &mut rng)
+            .unwrap();
+        let context = PresentationContext::new(b"verifier", b"nonce-1", 1_700_000_000);
+        let presentation = protocol
+            .show(&user_cred.commitment, &signature, &context, &mut rng)
+            .unwrap();
+
+        assert_golden(&golden("improved_verification_key"), &vk.to_envelope());
+        assert_golden(&golden("improved_signature"), &signature.to_envelope());
+        assert_golden(&golden("improved_credential"), &user_cred.to_envelope());
+        assert_golden(
+            &golden("improved_presentation"),
+            &presentation.to_envelope(),
+        );
+
+        let decoded =
+            ShowCredentialImproved::<Bls12_381>::from_envelope(&presentation.to_envelope())
+                .unwrap();
+        assert!(protocol.verify(&decoded, &context).unwrap());
+        // same body layout, but the header keeps the variants apart
+        assert_eq!(
+            UserCred::<Bls12_381>::from_envelope(&user_cred.to_envelope()).err(),
+            Some(EnvelopeError::SchemeMismatch {
+                expected: SchemeId::PsUtt,
+                found: SchemeId::PsUttImproved,
+            })
+        );
+    }
+
+    #[test]
+    fn test_psutt_json() {
+        let mut rng = test_rng();
+        let protocol = AnonCredProtocol::<Bls12_381>::new(3, &mut rng);
+        let (_, vk) = gen_keys(&protocol.pp, &mut rng);
+        let messages: Vec<Fr> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+        let user_cred = UserCred::new(&protocol.pp, &messages, Fr::rand(&mut rng));
+        let request = protocol.obtain(&user_cred, &mut rng).unwrap();
+        let signature = protocol.issue(&request, &mut rng).unwrap();
+        let context = PresentationContext::new(b"verifier", b"nonce-1", 1_700_000_000);
+        let presentation = protocol
+            .show(&user_cred.commitment, &signature, &context, &mut rng)
+            .unwrap();
+
+        let pp: PublicParams<Bls12_381> =
+            serde_json::from_str(&serde_json::to_string(&protocol.pp).unwrap()).unwrap();
+        assert_eq!(pp.ckg2, protocol.pp.ckg2);
+        let decoded: VerificationKey<Bls12_381> =
+            serde_json::from_str(&serde_json::to_string(&vk).unwrap()).unwrap();
+        assert_eq!(decoded.vk, vk.vk);
+        let json = serde_json::to_value(&presentation).unwrap();
+        assert!(json["randomized_signature"]["sigma1"].is_string());
+        let decoded: ShowCredential<Bls12_381> = serde_json::from_value(json.clone()).unwrap();
+        assert!(protocol.verify(&decoded, &context).unwrap());
+
+        let mut tampered = json;
+        tampered["cmg1"] = tampered["cmg2"].clone();
+        assert!(serde_json::from_value::<ShowCredential<Bls12_381>>(tampered).is_err());
+    }
+}
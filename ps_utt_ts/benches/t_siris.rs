@@ -1,4 +1,3 @@
-use ark_bls12_381::{Bls12_381, Fr, G1Affine, G1Projective};
 use ark_ec::pairing::Pairing;
 use ark_ec::CurveGroup;
 use ark_ff::Field;
@@ -17,9 +16,10 @@ use ps_utt_ts::signature::{PartialSignature, ThresholdSignature};
 use ps_utt_ts::signer::Signer;
 use std::ops::Mul;
 use std::time::Duration;
+use utils::envelope::CurveIdentifier;
 
 /// Benchmark function for threshold PS protocol
-fn benchmark_t_siris(c: &mut Criterion) {
+fn benchmark_t_siris<E: Pairing + CurveIdentifier>(c: &mut Criterion) {
     // Test configurations to match tACT paper's parameters
     let configs = [
         // N=4, t=N/2+1=3, with varying attribute sizes
@@ -47,7 +47,7 @@ fn benchmark_t_siris(c: &mut Criterion) {
 
     // ObtainMaster benchmarks
     {
-        let mut group = c.benchmark_group("t_siris");
+        let mut group = c.benchmark_group(format!("t_siris_{}", E::CURVE_ID.name()));
         group.sample_size(100);
         group.measurement_time(Duration::from_secs(25));
 
@@ -56,10 +56,9 @@ fn benchmark_t_siris(c: &mut Criterion) {
 
             // Setup for this specific configuration
             let mut setup_rng = ark_std::test_rng();
-            let (ck, _, _) =
-                keygen::<Bls12_381>(threshold, n_participants, l_attributes, &mut setup_rng);
+            let (ck, _, _) = keygen::<E>(threshold, n_participants, l_attributes, &mut setup_rng);
 
-            let s1_shared_secret = Fr::rand(&mut setup_rng);
+            let s1_shared_secret = E::ScalarField::rand(&mut setup_rng);
             let shares =
                 generate_shares(&s1_shared_secret, threshold, n_participants, &mut setup_rng);
 
@@ -73,8 +72,8 @@ fn benchmark_t_siris(c: &mut Criterion) {
                     let reconstructed_secret = reconstruct_secret(&shares[0..threshold], threshold);
 
                     // Create attributes specific to this benchmark iteration
-                    let attributes: Vec<Fr> = (0..l_attributes)
-                        .map(|_| Fr::rand(&mut bench_rng))
+                    let attributes: Vec<E::ScalarField> = (0..l_attributes)
+                        .map(|_| E::ScalarField::rand(&mut bench_rng))
                         .collect();
 
                     // Benchmark the complete request_credential operation
@@ -89,7 +88,7 @@ fn benchmark_t_siris(c: &mut Criterion) {
 
     // IssueMaster benchmarks (includes share generation and aggregation)
     {
-        let mut group = c.benchmark_group("t_siris");
+        let mut group = c.benchmark_group(format!("t_siris_{}", E::CURVE_ID.name()));
         group.sample_size(100);
         group.measurement_time(Duration::from_secs(25));
 
@@ -101,7 +100,7 @@ fn benchmark_t_siris(c: &mut Criterion) {
 
             // Setup keys
             let (ck, _, ts_keys) =
-                keygen::<Bls12_381>(threshold, n_participants, l_attributes, &mut setup_rng);
+                keygen::<E>(threshold, n_participants, l_attributes, &mut setup_rng);
 
             // Create signers
             let signers: Vec<_> = ts_keys
@@ -112,8 +111,8 @@ fn benchmark_t_siris(c: &mut Criterion) {
                 .collect();
 
             // Create credential request
-            let attributes: Vec<Fr> = (0..l_attributes)
-                .map(|_| Fr::rand(&mut setup_rng))
+            let attributes: Vec<E::ScalarField> = (0..l_attributes)
+                .map(|_| E::ScalarField::rand(&mut setup_rng))
                 .collect();
             let (mut credential, credential_request) =
                 UserProtocol::request_credential(ck.clone(), Some(&attributes), &mut setup_rng)
@@ -130,12 +129,7 @@ fn benchmark_t_siris(c: &mut Criterion) {
                         .take(threshold)
                         .map(|signer| {
                             let sig = signer
-                                .sign_share(
-                                    &credential_request.commitments,
-                                    &credential_request.proofs,
-                                    &credential_request.h,
-                                    &mut bench_rng,
-                                )
+                                .sign_share(&credential_request, &mut bench_rng)
                                 .expect("Failed to generate signature share");
                             (sig.party_index, sig)
                         })
@@ -148,6 +142,7 @@ fn benchmark_t_siris(c: &mut Criterion) {
                         &credential_request,
                         &signature_shares,
                         threshold,
+                        &mut bench_rng,
                     )
                     .expect("Failed to verify signature shares");
 
@@ -169,7 +164,7 @@ fn benchmark_t_siris(c: &mut Criterion) {
 
     // IssueMaster issue_master_no_zkp benchmarks (includes share generation and aggregation)
     {
-        let mut group = c.benchmark_group("t_siris");
+        let mut group = c.benchmark_group(format!("t_siris_{}", E::CURVE_ID.name()));
         group.sample_size(100);
         group.measurement_time(Duration::from_secs(15));
 
@@ -181,7 +176,7 @@ fn benchmark_t_siris(c: &mut Criterion) {
 
             // Setup keys
             let (ck, _, ts_keys) =
-                keygen::<Bls12_381>(threshold, n_participants, l_attributes, &mut setup_rng);
+                keygen::<E>(threshold, n_participants, l_attributes, &mut setup_rng);
 
             // Create signers
             let signers: Vec<_> = ts_keys
@@ -192,8 +187,8 @@ fn benchmark_t_siris(c: &mut Criterion) {
                 .collect();
 
             // Create credential request
-            let attributes: Vec<Fr> = (0..l_attributes)
-                .map(|_| Fr::rand(&mut setup_rng))
+            let attributes: Vec<E::ScalarField> = (0..l_attributes)
+                .map(|_| E::ScalarField::rand(&mut setup_rng))
                 .collect();
             let (mut credential, credential_request) =
                 UserProtocol::request_credential(ck.clone(), Some(&attributes), &mut setup_rng)
@@ -202,20 +197,13 @@ fn benchmark_t_siris(c: &mut Criterion) {
             // Benchmark the complete issuance process (share generation + verification + aggregation)
             group.bench_function(BenchmarkId::new("issue_master_no_zkp", id_suffix), |b| {
                 b.iter(|| {
-                    let mut bench_rng = ark_std::test_rng();
-
                     // 1. Generate signature shares from threshold signers
                     let verified_shares = signers
                         .iter()
                         .take(threshold)
                         .map(|signer| {
                             let sig = signer
-                                .sign_share_no_zkp_verify(
-                                    &credential_request.commitments,
-                                    &credential_request.proofs,
-                                    &credential_request.h,
-                                    &mut bench_rng,
-                                )
+                                .sign_share_no_zkp_verify(&credential_request)
                                 .expect("Failed to generate signature share");
                             (sig.party_index, sig)
                         })
@@ -249,7 +237,7 @@ fn benchmark_t_siris(c: &mut Criterion) {
 
     // ObtainContext benchmarks (master showing + nullifier + context credential request)
     {
-        let mut group = c.benchmark_group("t_siris");
+        let mut group = c.benchmark_group(format!("t_siris_{}", E::CURVE_ID.name()));
         group.sample_size(100);
         group.measurement_time(Duration::from_secs(25));
 
@@ -259,11 +247,11 @@ fn benchmark_t_siris(c: &mut Criterion) {
             // Setup
             let mut setup_rng = ark_std::test_rng();
             let (ck, vk, ts_keys) =
-                keygen::<Bls12_381>(threshold, n_participants, l_attributes, &mut setup_rng);
+                keygen::<E>(threshold, n_participants, l_attributes, &mut setup_rng);
 
             // Create and issue a complete master credential
-            let attributes: Vec<Fr> = (0..l_attributes)
-                .map(|_| Fr::rand(&mut setup_rng))
+            let attributes: Vec<E::ScalarField> = (0..l_attributes)
+                .map(|_| E::ScalarField::rand(&mut setup_rng))
                 .collect();
             let (mut master_credential, master_request) =
                 UserProtocol::request_credential(ck.clone(), Some(&attributes), &mut setup_rng)
@@ -293,6 +281,7 @@ fn benchmark_t_siris(c: &mut Criterion) {
                 &master_request,
                 &signature_shares,
                 threshold,
+                &mut setup_rng,
             )
             .expect("Failed to verify signature shares");
 
@@ -321,16 +310,16 @@ fn benchmark_t_siris(c: &mut Criterion) {
                             .expect("Failed to show master credential");
 
                     // 2. Generate nullifier (synthetic benchmark)
-                    let sk = Fr::rand(&mut bench_rng);
-                    let ctx = Fr::rand(&mut bench_rng);
+                    let sk = E::ScalarField::rand(&mut bench_rng);
+                    let ctx = E::ScalarField::rand(&mut bench_rng);
                     let combined = sk + ctx;
                     let inv = combined.inverse().unwrap();
-                    let g = G1Projective::rand(&mut bench_rng);
+                    let g = E::G1::rand(&mut bench_rng);
                     let nullifier = g.mul(inv).into_affine();
 
                     // 3. Create context credential request
-                    let context_attrs: Vec<Fr> = (0..l_attributes)
-                        .map(|_| Fr::rand(&mut bench_rng))
+                    let context_attrs: Vec<E::ScalarField> = (0..l_attributes)
+                        .map(|_| E::ScalarField::rand(&mut bench_rng))
                         .collect();
 
                     let context_request = UserProtocol::request_credential(
@@ -350,7 +339,7 @@ fn benchmark_t_siris(c: &mut Criterion) {
 
     // IssueContext benchmarks (master verification + nullifier verification + issuance)
     {
-        let mut group = c.benchmark_group("t_siris");
+        let mut group = c.benchmark_group(format!("t_siris_{}", E::CURVE_ID.name()));
         group.sample_size(100);
         group.measurement_time(Duration::from_secs(25));
 
@@ -360,7 +349,7 @@ fn benchmark_t_siris(c: &mut Criterion) {
             // Setup
             let mut setup_rng = ark_std::test_rng();
             let (ck, vk, ts_keys) =
-                keygen::<Bls12_381>(threshold, n_participants, l_attributes, &mut setup_rng);
+                keygen::<E>(threshold, n_participants, l_attributes, &mut setup_rng);
 
             // Create signers
             let signers: Vec<_> = ts_keys
@@ -371,8 +360,8 @@ fn benchmark_t_siris(c: &mut Criterion) {
                 .collect();
 
             // Create master credential
-            let master_attrs: Vec<Fr> = (0..l_attributes)
-                .map(|_| Fr::rand(&mut setup_rng))
+            let master_attrs: Vec<E::ScalarField> = (0..l_attributes)
+                .map(|_| E::ScalarField::rand(&mut setup_rng))
                 .collect();
             let (mut master_credential, master_request) =
                 UserProtocol::request_credential(ck.clone(), Some(&master_attrs), &mut setup_rng)
@@ -393,6 +382,7 @@ fn benchmark_t_siris(c: &mut Criterion) {
                 &master_request,
                 &signature_shares,
                 threshold,
+                &mut setup_rng,
             )
             .expect("Failed to verify master signature shares");
 
@@ -413,19 +403,19 @@ fn benchmark_t_siris(c: &mut Criterion) {
                     .expect("Failed to show master credential");
 
             // Create context credential request
-            let context_attrs: Vec<Fr> = (0..l_attributes)
-                .map(|_| Fr::rand(&mut setup_rng))
+            let context_attrs: Vec<E::ScalarField> = (0..l_attributes)
+                .map(|_| E::ScalarField::rand(&mut setup_rng))
                 .collect();
             let (mut context_credential, context_request) =
                 UserProtocol::request_credential(ck.clone(), Some(&context_attrs), &mut setup_rng)
                     .expect("Failed to create context credential request");
 
             // Generate synthetic nullifier
-            let sk = Fr::rand(&mut setup_rng);
-            let ctx = Fr::rand(&mut setup_rng);
+            let sk = E::ScalarField::rand(&mut setup_rng);
+            let ctx = E::ScalarField::rand(&mut setup_rng);
             let combined = sk + ctx;
             let inv = combined.inverse().unwrap();
-            let g = G1Projective::rand(&mut setup_rng);
+            let g = E::G1::rand(&mut setup_rng);
             let nullifier = g.mul(inv).into_affine();
 
             // Benchmark IssueContext
@@ -456,12 +446,7 @@ fn benchmark_t_siris(c: &mut Criterion) {
                         .take(threshold)
                         .map(|signer| {
                             let sig = signer
-                                .sign_share(
-                                    &context_request.commitments,
-                                    &context_request.proofs,
-                                    &context_request.h,
-                                    &mut bench_rng,
-                                )
+                                .sign_share(&context_request, &mut bench_rng)
                                 .expect("Failed to generate signature share");
                             (sig.party_index, sig)
                         })
@@ -474,6 +459,7 @@ fn benchmark_t_siris(c: &mut Criterion) {
                         &context_request,
                         &sig_shares,
                         threshold,
+                        &mut bench_rng,
                     )
                     .expect("Failed to verify signature shares");
 
@@ -495,7 +481,7 @@ fn benchmark_t_siris(c: &mut Criterion) {
 
     // IssueContext benchmarks (master verification + nullifier verification + issuance)
     {
-        let mut group = c.benchmark_group("t_siris");
+        let mut group = c.benchmark_group(format!("t_siris_{}", E::CURVE_ID.name()));
         group.sample_size(100);
         group.measurement_time(Duration::from_secs(25));
 
@@ -505,7 +491,7 @@ fn benchmark_t_siris(c: &mut Criterion) {
             // Setup
             let mut setup_rng = ark_std::test_rng();
             let (ck, vk, ts_keys) =
-                keygen::<Bls12_381>(threshold, n_participants, l_attributes, &mut setup_rng);
+                keygen::<E>(threshold, n_participants, l_attributes, &mut setup_rng);
 
             // Create signers
             let signers: Vec<_> = ts_keys
@@ -516,8 +502,8 @@ fn benchmark_t_siris(c: &mut Criterion) {
                 .collect();
 
             // Create master credential
-            let master_attrs: Vec<Fr> = (0..l_attributes)
-                .map(|_| Fr::rand(&mut setup_rng))
+            let master_attrs: Vec<E::ScalarField> = (0..l_attributes)
+                .map(|_| E::ScalarField::rand(&mut setup_rng))
                 .collect();
             let (mut master_credential, master_request) =
                 UserProtocol::request_credential(ck.clone(), Some(&master_attrs), &mut setup_rng)
@@ -549,26 +535,24 @@ fn benchmark_t_siris(c: &mut Criterion) {
                     .expect("Failed to show master credential");
 
             // Create context credential request
-            let context_attrs: Vec<Fr> = (0..l_attributes)
-                .map(|_| Fr::rand(&mut setup_rng))
+            let context_attrs: Vec<E::ScalarField> = (0..l_attributes)
+                .map(|_| E::ScalarField::rand(&mut setup_rng))
                 .collect();
             let (mut context_credential, context_request) =
                 UserProtocol::request_credential(ck.clone(), Some(&context_attrs), &mut setup_rng)
                     .expect("Failed to create context credential request");
 
             // Generate synthetic nullifier
-            let sk = Fr::rand(&mut setup_rng);
-            let ctx = Fr::rand(&mut setup_rng);
+            let sk = E::ScalarField::rand(&mut setup_rng);
+            let ctx = E::ScalarField::rand(&mut setup_rng);
             let combined = sk + ctx;
             let inv = combined.inverse().unwrap();
-            let g = G1Projective::rand(&mut setup_rng);
+            let g = E::G1::rand(&mut setup_rng);
             let nullifier = g.mul(inv).into_affine();
 
             // Benchmark IssueContext
             group.bench_function(BenchmarkId::new("issue_context_no_zkp", id_suffix), |b| {
                 b.iter(|| {
-                    let mut bench_rng = ark_std::test_rng();
-
                     // 1. Verify master credential
                     let master_valid = VerifierProtocol::verify(
                         &ck,
@@ -592,12 +576,7 @@ fn benchmark_t_siris(c: &mut Criterion) {
                         .take(threshold)
                         .map(|signer| {
                             let sig = signer
-                                .sign_share_no_zkp_verify(
-                                    &context_request.commitments,
-                                    &context_request.proofs,
-                                    &context_request.h,
-                                    &mut bench_rng,
-                                )
+                                .sign_share_no_zkp_verify(&context_request)
                                 .expect("Failed to generate signature share");
                             (sig.party_index, sig)
                         })
@@ -631,7 +610,7 @@ fn benchmark_t_siris(c: &mut Criterion) {
 
     // Show benchmark
     {
-        let mut group = c.benchmark_group("t_siris");
+        let mut group = c.benchmark_group(format!("t_siris_{}", E::CURVE_ID.name()));
         group.sample_size(100);
         group.measurement_time(Duration::from_secs(25));
 
@@ -641,11 +620,11 @@ fn benchmark_t_siris(c: &mut Criterion) {
             // Setup - create one complete credential
             let mut setup_rng = ark_std::test_rng();
             let (ck, vk, ts_keys) =
-                keygen::<Bls12_381>(threshold, n_participants, l_attributes, &mut setup_rng);
+                keygen::<E>(threshold, n_participants, l_attributes, &mut setup_rng);
 
             // Create and issue a credential
-            let attributes: Vec<Fr> = (0..l_attributes)
-                .map(|_| Fr::rand(&mut setup_rng))
+            let attributes: Vec<E::ScalarField> = (0..l_attributes)
+                .map(|_| E::ScalarField::rand(&mut setup_rng))
                 .collect();
 
             let (mut credential, credential_request) =
@@ -676,6 +655,7 @@ fn benchmark_t_siris(c: &mut Criterion) {
                 &credential_request,
                 &signature_shares,
                 threshold,
+                &mut setup_rng,
             )
             .expect("Failed to verify signature shares");
 
@@ -705,7 +685,7 @@ fn benchmark_t_siris(c: &mut Criterion) {
 
     // Verify benchmark
     {
-        let mut group = c.benchmark_group("t_siris");
+        let mut group = c.benchmark_group(format!("t_siris_{}", E::CURVE_ID.name()));
         group.sample_size(100);
         group.measurement_time(Duration::from_secs(25));
 
@@ -715,11 +695,11 @@ fn benchmark_t_siris(c: &mut Criterion) {
             // Setup - create one complete credential
             let mut setup_rng = ark_std::test_rng();
             let (ck, vk, ts_keys) =
-                keygen::<Bls12_381>(threshold, n_participants, l_attributes, &mut setup_rng);
+                keygen::<E>(threshold, n_participants, l_attributes, &mut setup_rng);
 
             // Create and issue a credential
-            let attributes: Vec<Fr> = (0..l_attributes)
-                .map(|_| Fr::rand(&mut setup_rng))
+            let attributes: Vec<E::ScalarField> = (0..l_attributes)
+                .map(|_| E::ScalarField::rand(&mut setup_rng))
                 .collect();
 
             let (mut credential, credential_request) =
@@ -750,6 +730,7 @@ fn benchmark_t_siris(c: &mut Criterion) {
                 &credential_request,
                 &signature_shares,
                 threshold,
+                &mut setup_rng,
             )
             .expect("Failed to verify signature shares");
 
@@ -771,7 +752,7 @@ fn benchmark_t_siris(c: &mut Criterion) {
                     .expect("Failed to generate presentation");
 
             // Benchmark just the verification
-            group.bench_function(BenchmarkId::new("verify", id_suffix), |b| {
+            group.bench_function(BenchmarkId::new("verify", &id_suffix), |b| {
                 b.iter(|| {
                     VerifierProtocol::verify(
                         &ck,
@@ -784,15 +765,36 @@ fn benchmark_t_siris(c: &mut Criterion) {
                     .expect("Failed to verify credential")
                 })
             });
+
+            // Same verification with the key prepared once up front
+            let pvk = vk.prepare(&ck);
+            group.bench_function(BenchmarkId::new("verify_prepared", id_suffix), |b| {
+                b.iter(|| {
+                    VerifierProtocol::verify_prepared(
+                        &ck,
+                        &pvk,
+                        &test_cm,
+                        &test_cm_tilde,
+                        &test_sig,
+                        &test_proof,
+                    )
+                    .expect("Failed to verify credential")
+                })
+            });
         }
 
         group.finish();
     }
 }
 
+/// runs the benchmarks once per enabled curve
+fn benchmark_t_siris_curves(c: &mut Criterion) {
+    utils::for_each_curve!(benchmark_t_siris(c));
+}
+
 criterion_group!(
     name = benches;
     config = Criterion::default();
-    targets = benchmark_t_siris
+    targets = benchmark_t_siris_curves
 );
 criterion_main!(benches);
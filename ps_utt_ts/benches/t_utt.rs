@@ -1,4 +1,3 @@
-use ark_bls12_381::{Bls12_381, Fr};
 use ark_ec::pairing::Pairing;
 use ark_ff::UniformRand;
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
@@ -9,9 +8,10 @@ use ps_utt_ts::protocol::{IssuerProtocol, UserProtocol, VerifierProtocol};
 use ps_utt_ts::signature::{PartialSignature, ThresholdSignature};
 use ps_utt_ts::signer::Signer;
 use std::time::Duration;
+use utils::envelope::CurveIdentifier;
 
 /// Benchmark function for threshold PS protocol
-fn benchmark_t_utt(c: &mut Criterion) {
+fn benchmark_t_utt<E: Pairing + CurveIdentifier>(c: &mut Criterion) {
     // Test configurations to match tACT paper's parameters
     let configs = [
         // N=4, t=N/2+1=3, with varying attribute sizes
@@ -39,7 +39,7 @@ fn benchmark_t_utt(c: &mut Criterion) {
 
     // TokenRequest benchmarks
     {
-        let mut group = c.benchmark_group("t_utt");
+        let mut group = c.benchmark_group(format!("t_utt_{}", E::CURVE_ID.name()));
         group
             .sample_size(100)
             .measurement_time(Duration::from_secs(20));
@@ -49,12 +49,11 @@ fn benchmark_t_utt(c: &mut Criterion) {
 
             // Setup for this specific configuration
             let mut setup_rng = ark_std::test_rng();
-            let (ck, _, _) =
-                keygen::<Bls12_381>(threshold, n_participants, l_attributes, &mut setup_rng);
+            let (ck, _, _) = keygen::<E>(threshold, n_participants, l_attributes, &mut setup_rng);
 
             // Create attributes specific to this configuration
-            let attributes: Vec<Fr> = (0..l_attributes)
-                .map(|_| Fr::rand(&mut setup_rng))
+            let attributes: Vec<E::ScalarField> = (0..l_attributes)
+                .map(|_| E::ScalarField::rand(&mut setup_rng))
                 .collect();
 
             // Create credential for this configuration
@@ -81,7 +80,7 @@ fn benchmark_t_utt(c: &mut Criterion) {
 
     // tIssue benchmarks
     {
-        let mut group = c.benchmark_group("t_utt");
+        let mut group = c.benchmark_group(format!("t_utt_{}", E::CURVE_ID.name()));
         group
             .sample_size(100)
             .measurement_time(Duration::from_secs(20));
@@ -94,7 +93,7 @@ fn benchmark_t_utt(c: &mut Criterion) {
 
             // Setup keys
             let (ck, _, ts_keys) =
-                keygen::<Bls12_381>(threshold, n_participants, l_attributes, &mut setup_rng);
+                keygen::<E>(threshold, n_participants, l_attributes, &mut setup_rng);
 
             // Create signers
             let signers: Vec<_> = ts_keys
@@ -105,8 +104,8 @@ fn benchmark_t_utt(c: &mut Criterion) {
                 .collect();
 
             // Create credential request
-            let attributes: Vec<Fr> = (0..l_attributes)
-                .map(|_| Fr::rand(&mut setup_rng))
+            let attributes: Vec<E::ScalarField> = (0..l_attributes)
+                .map(|_| E::ScalarField::rand(&mut setup_rng))
                 .collect();
             let (_, credential_request) =
                 UserProtocol::request_credential(ck.clone(), Some(&attributes), &mut setup_rng)
@@ -122,12 +121,7 @@ fn benchmark_t_utt(c: &mut Criterion) {
                         .take(threshold) // Only use the threshold number of signers
                         .map(|signer| {
                             signer
-                                .sign_share(
-                                    &credential_request.commitments,
-                                    &credential_request.proofs,
-                                    &credential_request.h,
-                                    &mut setup_rng,
-                                )
+                                .sign_share(&credential_request, &mut setup_rng)
                                 .expect("Failed to generate signature share")
                         })
                         .collect::<Vec<_>>();
@@ -141,7 +135,7 @@ fn benchmark_t_utt(c: &mut Criterion) {
     }
 
     {
-        let mut group = c.benchmark_group("t_utt");
+        let mut group = c.benchmark_group(format!("t_utt_{}", E::CURVE_ID.name()));
         group
             .sample_size(100)
             .measurement_time(Duration::from_secs(20));
@@ -154,7 +148,7 @@ fn benchmark_t_utt(c: &mut Criterion) {
 
             // Setup keys
             let (ck, _, ts_keys) =
-                keygen::<Bls12_381>(threshold, n_participants, l_attributes, &mut setup_rng);
+                keygen::<E>(threshold, n_participants, l_attributes, &mut setup_rng);
 
             // Create signers
             let signers: Vec<_> = ts_keys
@@ -165,8 +159,8 @@ fn benchmark_t_utt(c: &mut Criterion) {
                 .collect();
 
             // Create credential request
-            let attributes: Vec<Fr> = (0..l_attributes)
-                .map(|_| Fr::rand(&mut setup_rng))
+            let attributes: Vec<E::ScalarField> = (0..l_attributes)
+                .map(|_| E::ScalarField::rand(&mut setup_rng))
                 .collect();
             let (_, credential_request) =
                 UserProtocol::request_credential(ck.clone(), Some(&attributes), &mut setup_rng)
@@ -182,12 +176,7 @@ fn benchmark_t_utt(c: &mut Criterion) {
                         .take(threshold) // Only use the threshold number of signers
                         .map(|signer| {
                             signer
-                                .sign_share_no_zkp_verify(
-                                    &credential_request.commitments,
-                                    &credential_request.proofs,
-                                    &credential_request.h,
-                                    &mut setup_rng,
-                                )
+                                .sign_share_no_zkp_verify(&credential_request)
                                 .expect("Failed to generate signature share")
                         })
                         .collect::<Vec<_>>();
@@ -202,7 +191,7 @@ fn benchmark_t_utt(c: &mut Criterion) {
 
     // aggregate_verify benchmarks
     {
-        let mut group = c.benchmark_group("t_utt");
+        let mut group = c.benchmark_group(format!("t_utt_{}", E::CURVE_ID.name()));
         group
             .sample_size(100)
             .measurement_time(Duration::from_secs(20));
@@ -215,7 +204,7 @@ fn benchmark_t_utt(c: &mut Criterion) {
 
             // Setup keys
             let (ck, _, ts_keys) =
-                keygen::<Bls12_381>(threshold, n_participants, l_attributes, &mut setup_rng);
+                keygen::<E>(threshold, n_participants, l_attributes, &mut setup_rng);
 
             // Create signers
             let signers: Vec<_> = ts_keys
@@ -226,25 +215,20 @@ fn benchmark_t_utt(c: &mut Criterion) {
                 .collect();
 
             // Create credential and request
-            let attributes: Vec<Fr> = (0..l_attributes)
-                .map(|_| Fr::rand(&mut setup_rng))
+            let attributes: Vec<E::ScalarField> = (0..l_attributes)
+                .map(|_| E::ScalarField::rand(&mut setup_rng))
                 .collect();
             let (mut credential, credential_request) =
                 UserProtocol::request_credential(ck.clone(), Some(&attributes), &mut setup_rng)
                     .expect("Failed to create credential request");
 
             // Generate signature shares
-            let signature_shares: Vec<(usize, PartialSignature<Bls12_381>)> = signers
+            let signature_shares: Vec<(usize, PartialSignature<E>)> = signers
                 .iter()
                 .take(threshold)
                 .map(|signer| {
                     let sig = signer
-                        .sign_share(
-                            &credential_request.commitments,
-                            &credential_request.proofs,
-                            &credential_request.h,
-                            &mut setup_rng,
-                        )
+                        .sign_share(&credential_request, &mut setup_rng)
                         .expect("Failed to generate signature share");
                     (sig.party_index, sig)
                 })
@@ -260,6 +244,7 @@ fn benchmark_t_utt(c: &mut Criterion) {
                         &credential_request,
                         &signature_shares,
                         threshold,
+                        &mut setup_rng,
                     )
                     .expect("Failed to verify signature shares");
 
@@ -281,7 +266,7 @@ fn benchmark_t_utt(c: &mut Criterion) {
 
     // aggregate_no_verify benchmarks
     {
-        let mut group = c.benchmark_group("t_utt");
+        let mut group = c.benchmark_group(format!("t_utt_{}", E::CURVE_ID.name()));
         group
             .sample_size(100)
             .measurement_time(Duration::from_secs(20));
@@ -294,7 +279,7 @@ fn benchmark_t_utt(c: &mut Criterion) {
 
             // Setup keys
             let (ck, _, ts_keys) =
-                keygen::<Bls12_381>(threshold, n_participants, l_attributes, &mut setup_rng);
+                keygen::<E>(threshold, n_participants, l_attributes, &mut setup_rng);
 
             // Create signers
             let signers: Vec<_> = ts_keys
@@ -305,25 +290,20 @@ fn benchmark_t_utt(c: &mut Criterion) {
                 .collect();
 
             // Create credential and request
-            let attributes: Vec<Fr> = (0..l_attributes)
-                .map(|_| Fr::rand(&mut setup_rng))
+            let attributes: Vec<E::ScalarField> = (0..l_attributes)
+                .map(|_| E::ScalarField::rand(&mut setup_rng))
                 .collect();
             let (mut credential, credential_request) =
                 UserProtocol::request_credential(ck.clone(), Some(&attributes), &mut setup_rng)
                     .expect("Failed to create credential request");
 
             // Generate signature shares
-            let signature_shares: Vec<(usize, PartialSignature<Bls12_381>)> = signers
+            let signature_shares: Vec<(usize, PartialSignature<E>)> = signers
                 .iter()
                 .take(threshold)
                 .map(|signer| {
                     let sig = signer
-                        .sign_share(
-                            &credential_request.commitments,
-                            &credential_request.proofs,
-                            &credential_request.h,
-                            &mut setup_rng,
-                        )
+                        .sign_share(&credential_request, &mut setup_rng)
                         .expect("Failed to generate signature share");
                     (sig.party_index, sig)
                 })
@@ -336,6 +316,7 @@ fn benchmark_t_utt(c: &mut Criterion) {
                 &credential_request,
                 &signature_shares,
                 threshold,
+                &mut setup_rng,
             )
             .expect("Failed to verify signature shares");
 
@@ -359,7 +340,7 @@ fn benchmark_t_utt(c: &mut Criterion) {
     }
 
     {
-        let mut group = c.benchmark_group("t_utt");
+        let mut group = c.benchmark_group(format!("t_utt_{}", E::CURVE_ID.name()));
         group
             .sample_size(100)
             .measurement_time(Duration::from_secs(20));
@@ -372,7 +353,7 @@ fn benchmark_t_utt(c: &mut Criterion) {
 
             // Setup keys
             let (ck, vk, ts_keys) =
-                keygen::<Bls12_381>(threshold, n_participants, l_attributes, &mut setup_rng);
+                keygen::<E>(threshold, n_participants, l_attributes, &mut setup_rng);
 
             // Create signers
             let signers: Vec<_> = ts_keys
@@ -383,25 +364,20 @@ fn benchmark_t_utt(c: &mut Criterion) {
                 .collect();
 
             // Create credential and request
-            let attributes: Vec<Fr> = (0..l_attributes)
-                .map(|_| Fr::rand(&mut setup_rng))
+            let attributes: Vec<E::ScalarField> = (0..l_attributes)
+                .map(|_| E::ScalarField::rand(&mut setup_rng))
                 .collect();
             let (mut credential, credential_request) =
                 UserProtocol::request_credential(ck.clone(), Some(&attributes), &mut setup_rng)
                     .expect("Failed to create credential request");
 
             // Generate signature shares
-            let signature_shares: Vec<(usize, PartialSignature<Bls12_381>)> = signers
+            let signature_shares: Vec<(usize, PartialSignature<E>)> = signers
                 .iter()
                 .take(threshold)
                 .map(|signer| {
                     let sig = signer
-                        .sign_share(
-                            &credential_request.commitments,
-                            &credential_request.proofs,
-                            &credential_request.h,
-                            &mut setup_rng,
-                        )
+                        .sign_share(&credential_request, &mut setup_rng)
                         .expect("Failed to generate signature share");
                     (sig.party_index, sig)
                 })
@@ -414,6 +390,7 @@ fn benchmark_t_utt(c: &mut Criterion) {
                 &credential_request,
                 &signature_shares,
                 threshold,
+                &mut setup_rng,
             )
             .expect("Failed to verify signature shares");
 
@@ -446,7 +423,7 @@ fn benchmark_t_utt(c: &mut Criterion) {
 
     // Verify benchmarks
     {
-        let mut group = c.benchmark_group("t_utt");
+        let mut group = c.benchmark_group(format!("t_utt_{}", E::CURVE_ID.name()));
         group
             .sample_size(100)
             .measurement_time(Duration::from_secs(20));
@@ -459,7 +436,7 @@ fn benchmark_t_utt(c: &mut Criterion) {
 
             // Setup keys and parameters
             let (ck, vk, ts_keys) =
-                keygen::<Bls12_381>(threshold, n_participants, l_attributes, &mut setup_rng);
+                keygen::<E>(threshold, n_participants, l_attributes, &mut setup_rng);
 
             // Create signers
             let signers: Vec<_> = ts_keys
@@ -470,8 +447,8 @@ fn benchmark_t_utt(c: &mut Criterion) {
                 .collect();
 
             // Create credential and request
-            let attributes: Vec<Fr> = (0..l_attributes)
-                .map(|_| Fr::rand(&mut setup_rng))
+            let attributes: Vec<E::ScalarField> = (0..l_attributes)
+                .map(|_| E::ScalarField::rand(&mut setup_rng))
                 .collect();
             let (mut credential, credential_request) =
                 UserProtocol::request_credential(ck.clone(), Some(&attributes), &mut setup_rng)
@@ -493,6 +470,7 @@ fn benchmark_t_utt(c: &mut Criterion) {
                 &credential_request,
                 &signature_shares,
                 threshold,
+                &mut setup_rng,
             )
             .expect("Failed to verify signature shares");
 
@@ -559,9 +537,14 @@ fn benchmark_t_utt(c: &mut Criterion) {
     }
 }
 
+/// runs the benchmarks once per enabled curve
+fn benchmark_t_utt_curves(c: &mut Criterion) {
+    utils::for_each_curve!(benchmark_t_utt(c));
+}
+
 criterion_group!(
     name = benches;
     config = Criterion::default();
-    targets = benchmark_t_utt
+    targets = benchmark_t_utt_curves
 );
 criterion_main!(benches);
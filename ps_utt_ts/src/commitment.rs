@@ -1,13 +1,17 @@
 // use crate::proofsystem::{CommitmentProof, CommitmentProofError, CommitmentProofs};
 use crate::errors::CommitmentError;
-use crate::shamir::generate_shares;
+use crate::symmetric_commitment::SymmetricCommitmentKey;
 use ark_ec::pairing::Pairing;
-use ark_ec::{CurveGroup, VariableBaseMSM};
+use ark_ec::CurveGroup;
 use ark_ff::UniformRand;
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
-use ark_std::ops::{Add, Mul, Neg};
+use ark_std::ops::Mul;
 use ark_std::rand::Rng;
-use schnorr::schnorr::{SchnorrCommitment, SchnorrProtocol, SchnorrResponses};
+use schnorr::batch::{BatchError, SchnorrBatch, SchnorrClaim};
+use schnorr::schnorr::SchnorrProtocol;
+use utils::hash::HashUtil;
+
+const COMMITMENT_DOMAIN: &[u8] = b"ps-utt-ts-commitment-proof";
 
 #[derive(Debug, Clone, CanonicalDeserialize, CanonicalSerialize)]
 pub struct Commitment<E: Pairing> {
@@ -20,7 +24,6 @@ pub struct Commitment<E: Pairing> {
 pub struct CommitmentProof<E: Pairing> {
     pub commitment: E::G1Affine,
     pub schnorr_commitment: E::G1Affine,
-    pub bases: Vec<E::G1Affine>,
     pub challenge: E::ScalarField,
     pub responses: Vec<E::ScalarField>,
 }
@@ -51,10 +54,14 @@ impl<E: Pairing> Commitment<E> {
 
     pub fn prove(self, rng: &mut impl Rng) -> Result<Vec<u8>, CommitmentError> {
         let schnorr_commitment = SchnorrProtocol::commit(&self.bases, rng);
-        let challenge = E::ScalarField::rand(rng);
+        let challenge = opening_challenge::<E>(
+            COMMITMENT_DOMAIN,
+            &self.bases,
+            &self.cm,
+            &schnorr_commitment.commited_blindings,
+        )?;
         let responses = SchnorrProtocol::prove(&schnorr_commitment, &self.exponents, &challenge);
         let proof: CommitmentProof<E> = CommitmentProof {
-            bases: self.bases.clone(),
             commitment: self.cm,
             schnorr_commitment: schnorr_commitment.commited_blindings,
             challenge,
@@ -67,13 +74,24 @@ impl<E: Pairing> Commitment<E> {
         Ok(serialized_proof)
     }
 
-    pub fn verify(serialized_proof: &[u8]) -> Result<bool, CommitmentError> {
+    /// Verify a proof from `prove` for a commitment h^m g^r, with g taken from the
+    /// signers' commitment key
+    pub fn verify(
+        ck: &SymmetricCommitmentKey<E>,
+        h: &E::G1Affine,
+        serialized_proof: &[u8],
+    ) -> Result<bool, CommitmentError> {
         let proof: CommitmentProof<E> =
             CanonicalDeserialize::deserialize_compressed(serialized_proof)?;
 
+        let bases = [*h, ck.g];
+        if proof.responses.len() != bases.len() || !has_opening_challenge(h, ck, &proof)? {
+            return Ok(false);
+        }
+
         // Verify using Schnorr protocol
         let is_valid = SchnorrProtocol::verify_schnorr(
-            &proof.bases,
+            &bases,
             &proof.commitment,
             &proof.schnorr_commitment,
             &proof.responses,
@@ -84,14 +102,14 @@ impl<E: Pairing> Commitment<E> {
     }
 }
 
+/// Batch verify serialized proofs for per-message commitments h^m g^r, the bases come
+/// from `h` and the signers' commitment key rather than from the proofs
 pub fn batch_verify<E: Pairing>(
+    ck: &SymmetricCommitmentKey<E>,
+    h: &E::G1Affine,
     serialized_proofs: &[Vec<u8>],
     rng: &mut impl Rng,
 ) -> Result<bool, CommitmentError> {
-    if serialized_proofs.is_empty() {
-        return Ok(true); // No proofs to verify
-    }
-
     // Step 1: Deserialize all proofs
     let mut deserialized_proofs = Vec::with_capacity(serialized_proofs.len());
 
@@ -102,86 +120,174 @@ pub fn batch_verify<E: Pairing>(
         }
     }
 
-    // Step 2: Perform batch verification using random linear combination
-    // Generate a random scalar for each proof
-    let random_scalars: Vec<E::ScalarField> = (0..deserialized_proofs.len())
-        .map(|_| E::ScalarField::rand(rng))
-        .collect();
-
-    // For each proof, compute LHS = g^(r + e*m) and RHS = T * C^e
-    let mut all_bases = Vec::new();
-    let mut all_scalars = Vec::new();
-
-    // Calculate combined LHS
-    for (i, proof) in deserialized_proofs.iter().enumerate() {
-        // Add this proof's bases and responses to the combined MSM operation
-        // We scale by the random scalar for this proof
-        for (base_idx, base) in proof.bases.iter().enumerate() {
-            all_bases.push(*base);
-            all_scalars.push(proof.responses[base_idx] * random_scalars[i]);
+    for proof in &deserialized_proofs {
+        if !has_opening_challenge(h, ck, proof)? {
+            return Ok(false);
         }
     }
 
-    // Calculate LHS using a single multi-scalar multiplication
-    let lhs = E::G1::msm_unchecked(&all_bases, &all_scalars).into_affine();
+    Ok(batch_verify_proofs(ck, h, &deserialized_proofs, rng).is_ok())
+}
 
-    // Optimize RHS calculation with a single MSM operation
-    let mut rhs_bases = Vec::with_capacity(deserialized_proofs.len() * 2);
-    let mut rhs_scalars = Vec::with_capacity(deserialized_proofs.len() * 2);
+/// the proof answers the challenge `Commitment::prove` derives, not one the prover picked
+fn has_opening_challenge<E: Pairing>(
+    h: &E::G1Affine,
+    ck: &SymmetricCommitmentKey<E>,
+    proof: &CommitmentProof<E>,
+) -> Result<bool, CommitmentError> {
+    let challenge = opening_challenge::<E>(
+        COMMITMENT_DOMAIN,
+        &[*h, ck.g],
+        &proof.commitment,
+        &proof.schnorr_commitment,
+    )?;
+    Ok(challenge == proof.challenge)
+}
 
-    for (i, proof) in deserialized_proofs.iter().enumerate() {
-        // Add T (schnorr_commitment) with random scalar
-        rhs_bases.push(proof.schnorr_commitment);
-        rhs_scalars.push(random_scalars[i]);
+/// Fiat-Shamir challenge of a standalone opening proof, over the bases, the commitment and T.
+/// Proofs in an `IssuanceRequest` answer the request challenge instead
+pub(crate) fn opening_challenge<E: Pairing>(
+    domain: &[u8],
+    bases: &[E::G1Affine],
+    commitment: &E::G1Affine,
+    schnorr_commitment: &E::G1Affine,
+) -> Result<E::ScalarField, CommitmentError> {
+    let mut transcript = domain.to_vec();
+    bases.serialize_compressed(&mut transcript)?;
+    commitment.serialize_compressed(&mut transcript)?;
+    schnorr_commitment.serialize_compressed(&mut transcript)?;
+    Ok(HashUtil::<E::ScalarField, E::G1Affine>::hash_to_field(
+        &transcript,
+    ))
+}
 
-        // Add C^e (commitment * challenge) with random scalar
-        rhs_bases.push(proof.commitment);
-        rhs_scalars.push(random_scalars[i] * proof.challenge);
+/// Batch verify proofs for per-message commitments h^m g^r, a failure names the first bad proof
+pub fn batch_verify_proofs<E: Pairing>(
+    ck: &SymmetricCommitmentKey<E>,
+    h: &E::G1Affine,
+    proofs: &[CommitmentProof<E>],
+    rng: &mut impl Rng,
+) -> Result<(), BatchError> {
+    let mut batch = SchnorrBatch::<E>::new();
+    for proof in proofs {
+        batch.add_g1(SchnorrClaim {
+            bases: vec![*h, ck.g],
+            statement: proof.commitment,
+            commitment: proof.schnorr_commitment,
+            challenge: proof.challenge,
+            responses: proof.responses.clone(),
+        });
     }
-
-    // Calculate RHS using a single efficient MSM operation
-    let rhs = E::G1::msm_unchecked(&rhs_bases, &rhs_scalars).into_affine();
-
-    // Check if LHS == RHS
-    Ok(lhs == rhs)
+    batch.verify(rng)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ark_bls12_381::{Bls12_381, Fr, G1Affine};
+    use ark_ec::pairing::Pairing;
+    use ark_ec::VariableBaseMSM;
     use ark_std::rand::{rngs::StdRng, SeedableRng};
+    use ark_std::{One, Zero};
 
-    #[test]
-    fn test_basic_commitment_and_proof() {
+    fn test_basic_commitment_and_proof<E: Pairing>() {
         let mut rng = StdRng::seed_from_u64(12345);
 
-        // Generate random base points
-        let h = G1Affine::rand(&mut rng);
-        let g = G1Affine::rand(&mut rng);
+        // Generate the signers' commitment key and the credential's h
+        let y_values: Vec<E::ScalarField> =
+            (0..2).map(|_| E::ScalarField::rand(&mut rng)).collect();
+        let ck = SymmetricCommitmentKey::<E>::new(&y_values, &mut rng);
+        let h = E::G1Affine::rand(&mut rng);
 
         // Generate a random message
-        let m = Fr::rand(&mut rng);
+        let m = E::ScalarField::rand(&mut rng);
 
         // Create a commitment
-        let commitment = Commitment::<Bls12_381>::new(&h, &g, &m, None, &mut rng);
+        let commitment = Commitment::<E>::new(&h, &ck.g, &m, None, &mut rng);
 
         // Generate a proof
         let serialized_proof = commitment.prove(&mut rng).unwrap();
 
-        // Verify the proof by deserializing and checking
-        let proof: CommitmentProof<Bls12_381> =
-            CanonicalDeserialize::deserialize_compressed(&serialized_proof[..]).unwrap();
+        // Verify the proof against the verifier's bases
+        assert!(Commitment::<E>::verify(&ck, &h, &serialized_proof).unwrap());
+        assert!(batch_verify::<E>(&ck, &h, &[serialized_proof.clone()], &mut rng).unwrap());
 
-        // Verify the proof using Schnorr protocol
-        let is_valid = SchnorrProtocol::verify_schnorr(
-            &proof.bases,
-            &proof.commitment,
-            &proof.schnorr_commitment,
-            &proof.responses,
-            &proof.challenge,
-        );
+        // under a different h the same proof is for other bases
+        let other_h = E::G1Affine::rand(&mut rng);
+        assert!(!Commitment::<E>::verify(&ck, &other_h, &serialized_proof).unwrap());
+    }
 
-        assert!(is_valid, "Proof verification failed");
+    fn test_substituted_bases<E: Pairing>() {
+        let mut rng = StdRng::seed_from_u64(12345);
+        let y_values: Vec<E::ScalarField> =
+            (0..2).map(|_| E::ScalarField::rand(&mut rng)).collect();
+        let ck = SymmetricCommitmentKey::<E>::new(&y_values, &mut rng);
+        let h = E::G1Affine::rand(&mut rng);
+
+        // g replaced by a base with a known discrete log, g' = h^k
+        let k = E::ScalarField::rand(&mut rng);
+        let fake_g = h.mul(k).into_affine();
+        let m = E::ScalarField::rand(&mut rng);
+        let forged = Commitment::<E>::new(&h, &fake_g, &m, None, &mut rng);
+        let forged_proof = forged.prove(&mut rng).unwrap();
+        assert!(!Commitment::<E>::verify(&ck, &h, &forged_proof).unwrap());
+
+        // one forged proof fails the whole batch
+        let honest = Commitment::<E>::new(&h, &ck.g, &m, None, &mut rng)
+            .prove(&mut rng)
+            .unwrap();
+        assert!(batch_verify::<E>(&ck, &h, &[honest.clone()], &mut rng).unwrap());
+        assert!(!batch_verify::<E>(&ck, &h, &[honest, forged_proof], &mut rng).unwrap());
+
+        // a statement the prover cannot open, used as its own base with exponent one
+        let target = E::G1Affine::rand(&mut rng);
+        let forged = Commitment::<E> {
+            bases: vec![target, ck.g],
+            exponents: vec![E::ScalarField::one(), E::ScalarField::zero()],
+            cm: target,
+        };
+        let forged_proof = forged.prove(&mut rng).unwrap();
+        assert!(!Commitment::<E>::verify(&ck, &h, &forged_proof).unwrap());
+        assert!(!batch_verify::<E>(&ck, &h, &[forged_proof], &mut rng).unwrap());
     }
+
+    fn test_simulated_proof<E: Pairing>() {
+        let mut rng = StdRng::seed_from_u64(12345);
+        let y_values: Vec<E::ScalarField> =
+            (0..2).map(|_| E::ScalarField::rand(&mut rng)).collect();
+        let ck = SymmetricCommitmentKey::<E>::new(&y_values, &mut rng);
+        let h = E::G1Affine::rand(&mut rng);
+
+        // a forged (T, c) pair with T = h^z1 g^z2 − c·X for a commitment nobody can open
+        let target = E::G1Affine::rand(&mut rng);
+        let challenge = E::ScalarField::rand(&mut rng);
+        let responses = vec![
+            E::ScalarField::rand(&mut rng),
+            E::ScalarField::rand(&mut rng),
+        ];
+        let schnorr_commitment =
+            (E::G1::msm_unchecked(&[h, ck.g], &responses) - target * challenge).into_affine();
+        assert!(SchnorrProtocol::verify_schnorr(
+            &[h, ck.g],
+            &target,
+            &schnorr_commitment,
+            &responses,
+            &challenge
+        ));
+        let proof: CommitmentProof<E> = CommitmentProof {
+            commitment: target,
+            schnorr_commitment,
+            challenge,
+            responses,
+        };
+        let mut forged = Vec::new();
+        proof.serialize_compressed(&mut forged).unwrap();
+        assert!(!Commitment::<E>::verify(&ck, &h, &forged).unwrap());
+        assert!(!batch_verify::<E>(&ck, &h, &[forged], &mut rng).unwrap());
+    }
+
+    utils::curve_tests!(
+        test_basic_commitment_and_proof,
+        test_substituted_bases,
+        test_simulated_proof
+    );
 }
@@ -1,18 +1,28 @@
-use crate::commitment::{Commitment, CommitmentProof};
+use crate::commitment::{batch_verify_proofs, Commitment, CommitmentProof};
 use crate::errors::{CommitmentError, CredentialError, SignatureError};
 use crate::signature::{PartialSignature, ThresholdSignature};
 use crate::signer::Signer;
-use crate::symmetric_commitment::{SymmetricCommitment, SymmetricCommitmentKey};
+use crate::symmetric_commitment::{
+    SymmetricCommitment, SymmetricCommitmentKey, SymmetricCommitmentKeyTables,
+};
 use ark_ec::pairing::Pairing;
 use ark_ec::AffineRepr;
-use ark_ec::CurveGroup;
+use ark_ec::{CurveGroup, VariableBaseMSM};
 use ark_ff::UniformRand;
-use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, SerializationError, Valid, Validate,
+};
+use ark_std::io::{Read, Write};
 use ark_std::ops::Mul;
 use ark_std::rand::Rng;
 use ark_std::Zero;
+use schnorr::schnorr::{SchnorrCommitment, SchnorrProtocol};
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
 use std::iter;
 use thiserror::Error;
+use utils::encoding::{Base64Bytes, Base64Point};
+use utils::hash::HashUtil;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum CredentialState {
@@ -21,19 +31,181 @@ pub enum CredentialState {
     Signed,      // Has valid signature
     Randomized,  // Has been shown/randomized
 }
-/// Commitment to a single message with its proof
-pub struct CredentialCommitments<E: Pairing> {
+
+impl CredentialState {
+    fn tag(&self) -> u8 {
+        match self {
+            CredentialState::Initialized => 0,
+            CredentialState::Committed => 1,
+            CredentialState::Signed => 2,
+            CredentialState::Randomized => 3,
+        }
+    }
+}
+
+impl CanonicalSerialize for CredentialState {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.tag().serialize_with_mode(writer, compress)
+    }
+
+    fn serialized_size(&self, _compress: Compress) -> usize {
+        1
+    }
+}
+
+impl Valid for CredentialState {
+    fn check(&self) -> Result<(), SerializationError> {
+        Ok(())
+    }
+}
+
+impl CanonicalDeserialize for CredentialState {
+    fn deserialize_with_mode<R: Read>(
+        reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        match u8::deserialize_with_mode(reader, compress, validate)? {
+            0 => Ok(CredentialState::Initialized),
+            1 => Ok(CredentialState::Committed),
+            2 => Ok(CredentialState::Signed),
+            3 => Ok(CredentialState::Randomized),
+            _ => Err(SerializationError::InvalidData),
+        }
+    }
+}
+/// Per-message commitments h^m_k g^r_k with their opening proofs. All proofs answer one
+/// challenge over h, the credential context and every commitment, signers check them as a unit.
+/// h is hashed from the context and cm_h = g^ρ ∏ ck_k^m_k, whose opening shares the message
+/// responses of `proofs`, so a user can neither pick h nor reuse it for other messages.
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct IssuanceRequest<E: Pairing> {
     pub h: E::G1Affine,
+    pub context: E::ScalarField,
+    pub cm_h: E::G1Affine,
     pub commitments: Vec<E::G1Affine>,
-    pub proofs: Vec<Vec<u8>>,
+    pub proofs: Vec<CommitmentProof<E>>,
+    /// Schnorr commitment g^ρ' ∏ ck_k^m'_k and response for ρ of the cm_h opening
+    pub cm_h_schnorr: E::G1Affine,
+    pub cm_h_response: E::ScalarField,
 }
 
+impl<E: Pairing> IssuanceRequest<E> {
+    /// The signature base for a credential, h = H(context ‖ cm_h)
+    pub fn derive_h(context: &E::ScalarField, cm_h: &E::G1Affine) -> E::G1Affine {
+        let mut transcript = b"ps-utt-ts-h".to_vec();
+        context.serialize_compressed(&mut transcript).unwrap();
+        cm_h.serialize_compressed(&mut transcript).unwrap();
+        HashUtil::<E::ScalarField, E::G1Affine>::hash_to_curve(&transcript)
+    }
+
+    /// Fiat-Shamir challenge shared by all proofs of a request
+    pub fn challenge(
+        h: &E::G1Affine,
+        context: &E::ScalarField,
+        cm_h: &E::G1Affine,
+        cm_h_schnorr: &E::G1Affine,
+        commitments: &[E::G1Affine],
+        schnorr_commitments: &[E::G1Affine],
+    ) -> E::ScalarField {
+        let mut transcript = b"ps-utt-ts-issuance".to_vec();
+        h.serialize_compressed(&mut transcript).unwrap();
+        context.serialize_compressed(&mut transcript).unwrap();
+        cm_h.serialize_compressed(&mut transcript).unwrap();
+        cm_h_schnorr.serialize_compressed(&mut transcript).unwrap();
+        commitments.serialize_compressed(&mut transcript).unwrap();
+        schnorr_commitments
+            .serialize_compressed(&mut transcript)
+            .unwrap();
+        HashUtil::<E::ScalarField, E::G1Affine>::hash_to_field(&transcript)
+    }
+
+    /// Checks the request against the signers' commitment key: h hashed from the request,
+    /// one proof per commitment, each for the commitment at its position, all answering the
+    /// request challenge, and cm_h opening to the committed messages
+    pub fn verify(
+        &self,
+        ck: &SymmetricCommitmentKey<E>,
+        rng: &mut impl Rng,
+    ) -> Result<(), SignatureError> {
+        if self.commitments.len() != self.proofs.len() || self.commitments.is_empty() {
+            return Err(SignatureError::RequestLengthMismatch {
+                commitments: self.commitments.len(),
+                proofs: self.proofs.len(),
+            });
+        }
+        if self.commitments.len() > ck.ck.len() {
+            return Err(SignatureError::TooManyCommitments {
+                max: ck.ck.len(),
+                got: self.commitments.len(),
+            });
+        }
+        if let Some(k) = self
+            .commitments
+            .iter()
+            .zip(self.proofs.iter())
+            .position(|(cm, proof)| proof.commitment != *cm)
+        {
+            return Err(SignatureError::ProofCommitmentMismatch(k));
+        }
+        if self.h != Self::derive_h(&self.context, &self.cm_h) {
+            return Err(SignatureError::RequestBaseMismatch);
+        }
+
+        let schnorr_commitments: Vec<E::G1Affine> = self
+            .proofs
+            .iter()
+            .map(|proof| proof.schnorr_commitment)
+            .collect();
+        let challenge = Self::challenge(
+            &self.h,
+            &self.context,
+            &self.cm_h,
+            &self.cm_h_schnorr,
+            &self.commitments,
+            &schnorr_commitments,
+        );
+        if self.proofs.iter().any(|proof| proof.challenge != challenge) {
+            return Err(SignatureError::RequestChallengeMismatch);
+        }
+
+        // cm_h opens to the same m_k as the per-message commitments
+        let mut bases = ck.ck[..self.commitments.len()].to_vec();
+        bases.push(ck.g);
+        let mut responses = self
+            .proofs
+            .iter()
+            .map(|proof| proof.responses.first().copied())
+            .collect::<Option<Vec<_>>>()
+            .ok_or(CommitmentError::InvalidProof)?;
+        responses.push(self.cm_h_response);
+        if !SchnorrProtocol::verify_schnorr(
+            &bases,
+            &self.cm_h,
+            &self.cm_h_schnorr,
+            &responses,
+            &challenge,
+        ) {
+            return Err(CommitmentError::ProofVerificationFailed.into());
+        }
+
+        batch_verify_proofs(ck, &self.h, &self.proofs, rng).map_err(CommitmentError::from)?;
+        Ok(())
+    }
+}
+
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
 pub struct Credential<E: Pairing> {
     pub ck: SymmetricCommitmentKey<E>,
     pub cm: SymmetricCommitment<E>,
     messages: Vec<E::ScalarField>,
     pub blindings: Vec<E::ScalarField>, //public for testing
-    h: E::G1Affine,
+    pub(crate) h: E::G1Affine,
+    cm_h_blinding: E::ScalarField,
     sig: Option<ThresholdSignature<E>>,
     pub context: E::ScalarField, // context for the credential like an id
     pub state: CredentialState,
@@ -54,26 +226,48 @@ impl<E: Pairing> Credential<E> {
                 .take(num_messages)
                 .collect(),
         };
-        // gen h
-        let h = E::G1Affine::rand(rng);
         // gen cm
         let cm = SymmetricCommitment::<E>::new(&ck, &messages, &E::ScalarField::zero());
 
-        Self {
+        let mut credential = Self {
             ck,
             cm,
             messages,
             blindings: Vec::new(),
-            h,
+            h: E::G1Affine::zero(),
+            cm_h_blinding: E::ScalarField::rand(rng),
             sig: None,
             context: E::ScalarField::rand(rng),
             state: CredentialState::Initialized,
             metadata: None,
-        }
+        };
+        credential.derive_h();
+        credential
     }
 
     pub fn set_attributes(&mut self, messages: Vec<E::ScalarField>) {
         self.messages = messages;
+        self.derive_h();
+    }
+
+    // cm_h = g^ρ ∏ ck_k^m_k, signers recompute h from it
+    fn cm_h(&self) -> E::G1Affine {
+        let mut bases: Vec<E::G1Affine> = self
+            .ck
+            .ck
+            .iter()
+            .take(self.messages.len())
+            .copied()
+            .collect();
+        bases.push(self.ck.g);
+        let mut exponents = self.messages.clone();
+        exponents.push(self.cm_h_blinding);
+        E::G1::msm_unchecked(&bases, &exponents).into_affine()
+    }
+
+    // h = H(context ‖ cm_h), a base no one knows the discrete log of
+    fn derive_h(&mut self) {
+        self.h = IssuanceRequest::<E>::derive_h(&self.context, &self.cm_h());
     }
 
     // set the symmetric commitment, at the start it will be CM.Com([m_1, ..., m_L], 0)
@@ -96,7 +290,7 @@ impl<E: Pairing> Credential<E> {
     pub fn compute_commitments_per_m(
         &mut self,
         rng: &mut impl Rng,
-    ) -> Result<CredentialCommitments<E>, CommitmentError> {
+    ) -> Result<IssuanceRequest<E>, CommitmentError> {
         if self.messages.is_empty() {
             return Err(CommitmentError::InvalidComputeCommitment);
         }
@@ -104,8 +298,6 @@ impl<E: Pairing> Credential<E> {
         let num_messages = self.messages.len();
 
         // Pre-allocate vectors with capacity
-        let mut commitments = Vec::with_capacity(num_messages);
-        let mut commitment_proofs = Vec::with_capacity(num_messages);
         let mut blindings = Vec::with_capacity(num_messages);
 
         // Generate all randomness at once for better entropy management
@@ -136,57 +328,29 @@ impl<E: Pairing> Credential<E> {
 
         // Batch normalize all commitments at once (converting from projective to affine coordinates)
         // This is much more efficient than converting one by one
-        commitments = E::G1::normalize_batch(&projective_commitments);
+        let commitments = E::G1::normalize_batch(&projective_commitments);
 
-        // Generate proofs for each commitment (can be parallelized with Rayon)
+        // Schnorr commitments for each opening (can be parallelized with Rayon)
+        let bases = [self.h, self.ck.g];
         #[cfg(feature = "parallel")]
-        {
+        let schnorr_commitments: Vec<SchnorrCommitment<E::G1Affine>> = {
             use rand::thread_rng;
             use rayon::prelude::*;
 
-            let proof_results: Vec<Result<Vec<u8>, CommitmentError>> = (0..num_messages)
+            // Use a thread-local RNG instead of sharing the mutable reference
+            (0..num_messages)
                 .into_par_iter()
-                .map(|i| {
-                    let current_cm = Commitment::<E> {
-                        bases: vec![self.h, self.ck.g],
-                        exponents: vec![self.messages[i], blindings[i]],
-                        cm: commitments[i],
-                    };
-                    // Use a thread-local RNG instead of sharing the mutable reference
-                    current_cm.prove(&mut thread_rng())
-                })
-                .collect();
-
-            for result in proof_results {
-                match result {
-                    Ok(proof) => commitment_proofs.push(proof),
-                    Err(err) => return Err(err),
-                }
-            }
-        }
+                .map(|_| SchnorrProtocol::commit(&bases, &mut thread_rng()))
+                .collect()
+        };
 
         // Sequential fallback if parallel feature is not enabled
         #[cfg(not(feature = "parallel"))]
-        {
-            for i in 0..num_messages {
-                let current_cm = Commitment {
-                    bases: vec![self.h, self.ck.g],
-                    exponents: vec![self.messages[i], blindings[i]],
-                    cm: commitments[i],
-                };
-
-                match current_cm.prove(rng) {
-                    Ok(proof) => commitment_proofs.push(proof),
-                    Err(err) => return Err(err),
-                }
-            }
-        }
+        let schnorr_commitments: Vec<SchnorrCommitment<E::G1Affine>> = (0..num_messages)
+            .map(|_| SchnorrProtocol::commit(&bases, rng))
+            .collect();
 
-        Ok(CredentialCommitments {
-            h: self.h,
-            commitments,
-            proofs: commitment_proofs,
-        })
+        Ok(self.issuance_request(commitments, &blindings, schnorr_commitments, rng))
     }
 
     // commit to each message attribute individually for threshold sig
@@ -196,40 +360,100 @@ impl<E: Pairing> Credential<E> {
     pub fn compute_commitments_per_m_old(
         &mut self,
         rng: &mut impl Rng,
-    ) -> Result<CredentialCommitments<E>, CommitmentError> {
+    ) -> Result<IssuanceRequest<E>, CommitmentError> {
         if self.messages.is_empty() {
             return Err(CommitmentError::InvalidComputeCommitment);
         }
 
-        // loop through         // Initialize vectors to store commitments and proofs
+        // loop through         // Initialize vectors to store commitments and Schnorr commitments
         let mut commitments: Vec<E::G1Affine> = Vec::with_capacity(self.messages.len());
-        let mut commitment_proofs: Vec<Vec<u8>> = Vec::with_capacity(self.messages.len());
+        let mut blindings = Vec::with_capacity(self.messages.len());
+        let mut schnorr_commitments = Vec::with_capacity(self.messages.len());
 
-        // Generate commitment and proof for each message
+        // Generate commitment and Schnorr commitment for each message
         for i in 0..self.messages.len() {
             let current_cm =
                 Commitment::<E>::new(&self.h, &self.ck.g, &self.messages[i], None, rng);
 
             // store the randomness
             self.blindings.push(current_cm.exponents[1]);
+            blindings.push(current_cm.exponents[1]);
             // Store the commitment
             commitments.push(current_cm.cm);
 
             self.state = CredentialState::Committed;
 
-            // Generate and store the proof
-            match current_cm.prove(rng) {
-                Ok(proof) => commitment_proofs.push(proof),
-                Err(err) => return Err(err),
-            }
+            schnorr_commitments.push(SchnorrProtocol::commit(&current_cm.bases, rng));
         }
 
-        // Return the commitments and proofs in a CredentialCommitments struct
-        Ok(CredentialCommitments {
+        Ok(self.issuance_request(commitments, &blindings, schnorr_commitments, rng))
+    }
+
+    // answers the request challenge with every opening (m_k, r_k), the challenge covers all
+    // commitments so proofs cannot be moved between positions or requests. The cm_h opening
+    // reuses the m_k blindings, its message responses are those of the per-message proofs
+    fn issuance_request(
+        &self,
+        commitments: Vec<E::G1Affine>,
+        blindings: &[E::ScalarField],
+        schnorr_commitments: Vec<SchnorrCommitment<E::G1Affine>>,
+        rng: &mut impl Rng,
+    ) -> IssuanceRequest<E> {
+        let committed_blindings: Vec<E::G1Affine> = schnorr_commitments
+            .iter()
+            .map(|t| t.commited_blindings)
+            .collect();
+        let cm_h = self.cm_h();
+        let rho_blinding = E::ScalarField::rand(rng);
+        let mut bases: Vec<E::G1Affine> = self
+            .ck
+            .ck
+            .iter()
+            .take(self.messages.len())
+            .copied()
+            .collect();
+        bases.push(self.ck.g);
+        let mut cm_h_blindings: Vec<E::ScalarField> = schnorr_commitments
+            .iter()
+            .map(|t| t.random_blindings[0])
+            .collect();
+        cm_h_blindings.push(rho_blinding);
+        let cm_h_schnorr = E::G1::msm_unchecked(&bases, &cm_h_blindings).into_affine();
+        let challenge = IssuanceRequest::<E>::challenge(
+            &self.h,
+            &self.context,
+            &cm_h,
+            &cm_h_schnorr,
+            &commitments,
+            &committed_blindings,
+        );
+        let proofs = schnorr_commitments
+            .iter()
+            .enumerate()
+            .map(|(k, schnorr_commitment)| {
+                let responses = SchnorrProtocol::prove(
+                    schnorr_commitment,
+                    &[self.messages[k], blindings[k]],
+                    &challenge,
+                );
+                CommitmentProof {
+                    commitment: commitments[k],
+                    schnorr_commitment: schnorr_commitment.commited_blindings,
+                    challenge,
+                    responses: responses.0,
+                }
+            })
+            .collect();
+
+        IssuanceRequest {
             h: self.h,
+            context: self.context,
+            cm_h,
             commitments,
-            proofs: commitment_proofs,
-        })
+            proofs,
+            cm_h_schnorr,
+            cm_h_response: rho_blinding + challenge * self.cm_h_blinding,
+        }
     }
 
     pub fn attach_signature(&mut self, sig: ThresholdSignature<E>) {
@@ -265,9 +489,65 @@ impl<E: Pairing> Credential<E> {
         Ok((randomized_sig, rand_sym_cm.cm, rand_sym_cm.cm_tilde, proof))
     }
 
+    /// `show` with the commitment randomized and proven from the key's tables
+    pub fn show_with_tables(
+        &self,
+        tables: &SymmetricCommitmentKeyTables<E>,
+        rng: &mut impl Rng,
+    ) -> Result<(ThresholdSignature<E>, E::G1Affine, E::G2Affine, Vec<u8>), CredentialError> {
+        if self.state != CredentialState::Signed {
+            return Err(CredentialError::InvalidState(
+                "Credential must be signed before showing".to_string(),
+            ));
+        }
+
+        let sig = self.sig.as_ref().unwrap();
+        let (randomized_sig, r_delta) = sig.randomize(rng);
+        let rand_sym_cm = self.cm.randomize_with_tables(tables, &r_delta);
+        let proof = rand_sym_cm
+            .clone()
+            .prove_with_tables(tables, rng)
+            .map_err(CredentialError::ProofGenerationFailed)?;
+        Ok((randomized_sig, rand_sym_cm.cm, rand_sym_cm.cm_tilde, proof))
+    }
+
     // Helper methods for multi-credential management
     pub fn with_metadata(mut self, metadata: String) -> Self {
         self.metadata = Some(metadata);
         self
     }
 }
+
+/// Presentation produced by `Credential::show`
+#[serde_as]
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct ShowCredential<E: Pairing> {
+    pub randomized_signature: ThresholdSignature<E>,
+    #[serde_as(as = "Base64Point")]
+    pub cm: E::G1Affine,
+    #[serde_as(as = "Base64Point")]
+    pub cm_tilde: E::G2Affine,
+    #[serde_as(as = "Base64Bytes")]
+    pub proof: Vec<u8>,
+}
+
+impl<E: Pairing> From<(ThresholdSignature<E>, E::G1Affine, E::G2Affine, Vec<u8>)>
+    for ShowCredential<E>
+{
+    fn from(
+        (randomized_signature, cm, cm_tilde, proof): (
+            ThresholdSignature<E>,
+            E::G1Affine,
+            E::G2Affine,
+            Vec<u8>,
+        ),
+    ) -> Self {
+        Self {
+            randomized_signature,
+            cm,
+            cm_tilde,
+            proof,
+        }
+    }
+}
@@ -1,5 +1,8 @@
 use ark_serialize::SerializationError;
+use schnorr::batch::BatchError;
+use schnorr::key_proof::KeyProofError;
 use thiserror::Error;
+use utils::key_ring::KeyRingError;
 
 /// Errors that can occur during commitment operations
 #[derive(Error, Debug)]
@@ -21,6 +24,9 @@ pub enum CommitmentError {
 
     #[error("Batch Proof verification failed")]
     BatchVerifyError,
+
+    #[error("Batch proof verification failed: {0}")]
+    BatchError(#[from] BatchError),
 }
 
 /// Errors that can occur during signature operations
@@ -58,6 +64,27 @@ pub enum SignatureError {
 
     #[error("Invalid credential state: {0}")]
     InvalidState(String),
+
+    #[error("Issuance request has {commitments} commitments but {proofs} proofs")]
+    RequestLengthMismatch { commitments: usize, proofs: usize },
+
+    #[error("Issuance request has {got} commitments, the commitment key allows {max}")]
+    TooManyCommitments { max: usize, got: usize },
+
+    #[error("Proof {0} is not for the commitment at the same position")]
+    ProofCommitmentMismatch(usize),
+
+    #[error("Issuance request proofs do not answer the request challenge")]
+    RequestChallengeMismatch,
+
+    #[error("Issuance request h is not hashed from its context and cm_h")]
+    RequestBaseMismatch,
+
+    #[error("Signature share from party {0} is under another key")]
+    KeyIdMismatch(usize),
+
+    #[error("Issuer key not accepted: {0}")]
+    KeyRingError(#[from] KeyRingError),
 }
 
 /// Errors that can occur during protocol operations
@@ -76,6 +103,12 @@ pub enum ProtocolError {
 
     #[error("User error: {0}")]
     UserError(String),
+
+    #[error("Key share proof failed: {0}")]
+    KeyProofError(#[from] KeyProofError),
+
+    #[error("Key shares are not a sharing of the verification key")]
+    InconsistentKeyShares,
 }
 
 #[derive(Error, Debug)]
@@ -89,3 +122,22 @@ pub enum CredentialError {
     #[error("Invalid credential state: {0}")]
     InvalidState(String),
 }
+
+/// Errors that can occur during threshold auditor decryption
+#[derive(Error, Debug)]
+pub enum DecryptionError {
+    #[error("Invalid decryption share from auditor {0}")]
+    InvalidShare(usize),
+
+    #[error("Duplicate decryption share from auditor {0}")]
+    DuplicateShare(usize),
+
+    #[error("No verification share for auditor {0}")]
+    UnknownAuditor(usize),
+
+    #[error("Insufficient decryption shares, needed {needed}, got {got}")]
+    InsufficientShares { needed: usize, got: usize },
+
+    #[error("Ciphertext is not bound to this label")]
+    LabelMismatch,
+}
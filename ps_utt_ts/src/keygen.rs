@@ -1,17 +1,41 @@
+use crate::errors::ProtocolError;
 use crate::shamir::{generate_shares, reconstruct_secret};
 use crate::symmetric_commitment::SymmetricCommitmentKey;
 use ark_ec::pairing::Pairing;
-use ark_ec::CurveGroup;
-use ark_ff::UniformRand;
+use ark_ec::{CurveGroup, VariableBaseMSM};
+use ark_ff::{Field, UniformRand, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::fmt;
 use ark_std::ops::Mul;
 use ark_std::rand::Rng;
+use schnorr::key_proof::{KeyProof, KeyProofError, KeyStatement};
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use utils::encoding::Base64Point;
+use utils::key_ring::KeyId;
+use utils::pairing::batch_randomizer;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 
-#[derive(Clone)]
+const KEY_SHARE_PROOF_DOMAIN: &[u8] = b"ps-utt-ts-key-share";
+const COMMITMENT_KEY_PROOF_DOMAIN: &[u8] = b"ps-utt-ts-commitment-key";
+
+/// A signer's share of the key, wiped on drop. `Debug` shows only the index
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize, Zeroize, ZeroizeOnDrop)]
 pub struct SecretKeyShare<E: Pairing> {
+    #[zeroize(skip)]
     pub index: usize,
     pub x_share: E::ScalarField,
     pub y_shares: Vec<E::ScalarField>,
 }
+
+impl<E: Pairing> fmt::Debug for SecretKeyShare<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecretKeyShare")
+            .field("index", &self.index)
+            .finish_non_exhaustive()
+    }
+}
+
 #[derive(Clone)]
 pub struct ThresholdKeys<E: Pairing> {
     pub t: usize,
@@ -21,18 +45,178 @@ pub struct ThresholdKeys<E: Pairing> {
     pub vk_shares: Vec<VerificationKeyShare<E>>,
 }
 
-#[derive(Clone)]
+#[serde_as]
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct VerificationKey<E: Pairing> {
+    #[serde_as(as = "Base64Point")]
     pub g_tilde_x: E::G2Affine,
 }
 
+/// Verification key with g_tilde_x and g_tilde prepared once for repeated verification
 #[derive(Clone)]
+pub struct PreparedVerificationKey<E: Pairing> {
+    pub vk: VerificationKey<E>,
+    pub g_tilde_x: E::G2Prepared,
+    pub g_tilde: E::G2Prepared,
+}
+
+impl<E: Pairing> VerificationKey<E> {
+    /// Name of this key in issuance responses, presentations and verifier key rings
+    pub fn key_id(&self) -> KeyId {
+        KeyId::of(self)
+    }
+
+    pub fn prepare(&self, ck: &SymmetricCommitmentKey<E>) -> PreparedVerificationKey<E> {
+        PreparedVerificationKey {
+            vk: self.clone(),
+            g_tilde_x: self.g_tilde_x.into(),
+            g_tilde: ck.g_tilde.into(),
+        }
+    }
+}
+
+#[serde_as]
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct VerificationKeyShare<E: Pairing> {
     pub index: usize,
+    #[serde_as(as = "Base64Point")]
     pub g_tilde_x_share: E::G2Affine,
+    #[serde_as(as = "Vec<Base64Point>")]
     pub g_tilde_y_shares: Vec<E::G2Affine>,
 }
 
+impl<E: Pairing> VerificationKeyShare<E> {
+    /// g~^{x_i} followed by g~^{y_k,i}
+    pub fn key_statement(&self, ck: &SymmetricCommitmentKey<E>) -> KeyStatement<E> {
+        let mut images_g2 = Vec::with_capacity(1 + self.g_tilde_y_shares.len());
+        images_g2.push(self.g_tilde_x_share);
+        images_g2.extend_from_slice(&self.g_tilde_y_shares);
+        KeyStatement {
+            g1: ck.g,
+            images_g1: Vec::new(),
+            g2: ck.g_tilde,
+            images_g2,
+        }
+    }
+}
+
+/// Proofs the dealer publishes with the key shares: one per signer share and one that `ck`
+/// and `ck_tilde` use the same y_k
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct KeyShareProofs<E: Pairing> {
+    pub share_proofs: Vec<KeyProof<E>>,
+    pub ck_proof: KeyProof<E>,
+}
+
+fn commitment_key_statement<E: Pairing>(ck: &SymmetricCommitmentKey<E>) -> KeyStatement<E> {
+    KeyStatement {
+        g1: ck.g,
+        images_g1: ck.ck.clone(),
+        g2: ck.g_tilde,
+        images_g2: ck.ck_tilde.clone(),
+    }
+}
+
+/// Checks the dealer's output before use. The proofs are verified in one Schnorr batch, then one
+/// MSM checks that every share lies on the degree t polynomial through the first t+1 and that
+/// the polynomial opens to vk and ck_tilde at 0. A failing share proof is reported by its
+/// position in `vk_shares`, a failing commitment key proof as `vk_shares.len()`.
+pub fn verify_key_shares<E: Pairing>(
+    ck: &SymmetricCommitmentKey<E>,
+    vk: &VerificationKey<E>,
+    vk_shares: &[VerificationKeyShare<E>],
+    proofs: &KeyShareProofs<E>,
+    t: usize,
+    rng: &mut impl Rng,
+) -> Result<(), ProtocolError> {
+    let l = ck.ck_tilde.len();
+    if vk_shares.len() <= t
+        || proofs.share_proofs.len() != vk_shares.len()
+        || vk_shares.iter().any(|s| s.g_tilde_y_shares.len() != l)
+    {
+        return Err(ProtocolError::InconsistentKeyShares);
+    }
+    let mut indices: Vec<usize> = vk_shares.iter().map(|s| s.index).collect();
+    indices.sort_unstable();
+    indices.dedup();
+    if indices.len() != vk_shares.len() || indices[0] == 0 {
+        return Err(ProtocolError::InconsistentKeyShares);
+    }
+
+    let statements: Vec<KeyStatement<E>> = vk_shares.iter().map(|s| s.key_statement(ck)).collect();
+    let claims: Vec<(&KeyStatement<E>, &KeyProof<E>)> =
+        statements.iter().zip(proofs.share_proofs.iter()).collect();
+    KeyProof::verify_batch(KEY_SHARE_PROOF_DOMAIN, &claims, rng)?;
+    // the domains differ, so the commitment key proof is checked on its own
+    proofs
+        .ck_proof
+        .verify(
+            COMMITMENT_KEY_PROOF_DOMAIN,
+            &commitment_key_statement(ck),
+            rng,
+        )
+        .map_err(|e| match e {
+            KeyProofError::LengthMismatch(_) => KeyProofError::LengthMismatch(vk_shares.len()),
+            KeyProofError::IdentityElement(_) => KeyProofError::IdentityElement(vk_shares.len()),
+            KeyProofError::InvalidProof(_) => KeyProofError::InvalidProof(vk_shares.len()),
+        })?;
+
+    // W_i = g~^{x_i} * prod_k (g~^{y_k,i})^{rho_k}, interpolated through the first t+1 shares.
+    // sigma_0 (T - P(0)) + sum_j sigma_j (W_j - P(j)) must vanish, T = vk * prod_k ck~_k^{rho_k}
+    let rho: Vec<E::ScalarField> = (0..l).map(|_| batch_randomizer(rng)).collect();
+    let base: Vec<usize> = vk_shares[..=t].iter().map(|s| s.index).collect();
+    let mut weights = vec![E::ScalarField::zero(); vk_shares.len()];
+    let sigma_0: E::ScalarField = batch_randomizer(rng);
+    let mut points = vec![(E::ScalarField::zero(), sigma_0)];
+    for j in t + 1..vk_shares.len() {
+        let sigma_j: E::ScalarField = batch_randomizer(rng);
+        weights[j] = sigma_j;
+        points.push((E::ScalarField::from(vk_shares[j].index as u64), sigma_j));
+    }
+    for (point, sigma) in points {
+        for (k, &i) in base.iter().enumerate() {
+            weights[k] -= sigma * lagrange_at(&base, i, point);
+        }
+    }
+
+    let mut bases = Vec::with_capacity((vk_shares.len() + 1) * (l + 1));
+    let mut scalars = Vec::with_capacity(bases.capacity());
+    bases.push(vk.g_tilde_x);
+    scalars.push(sigma_0);
+    for (c, r) in ck.ck_tilde.iter().zip(rho.iter()) {
+        bases.push(*c);
+        scalars.push(sigma_0 * r);
+    }
+    for (share, w) in vk_shares.iter().zip(weights.iter()) {
+        bases.push(share.g_tilde_x_share);
+        scalars.push(*w);
+        for (y, r) in share.g_tilde_y_shares.iter().zip(rho.iter()) {
+            bases.push(*y);
+            scalars.push(*w * r);
+        }
+    }
+    if !E::G2::msm_unchecked(&bases, &scalars).is_zero() {
+        return Err(ProtocolError::InconsistentKeyShares);
+    }
+    Ok(())
+}
+
+/// Lagrange basis polynomial of `i` over `indices`, evaluated at `x`
+fn lagrange_at<F: Field>(indices: &[usize], i: usize, x: F) -> F {
+    let i_field = F::from(i as u64);
+    let mut result = F::one();
+    for &m in indices {
+        if m == i {
+            continue;
+        }
+        let m_field = F::from(m as u64);
+        result *= (x - m_field) * (i_field - m_field).inverse().expect("indices are distinct");
+    }
+    result
+}
+
 pub fn keygen<E: Pairing>(
     t: usize,
     n: usize,
@@ -42,13 +226,71 @@ pub fn keygen<E: Pairing>(
     SymmetricCommitmentKey<E>,
     VerificationKey<E>,
     ThresholdKeys<E>,
+) {
+    let (ck, vk, ts_keys, _) = deal(t, n, l, rng);
+    (ck, vk, ts_keys)
+}
+
+/// `keygen` together with the proofs holders check with `verify_key_shares`
+pub fn keygen_with_proofs<E: Pairing>(
+    t: usize,
+    n: usize,
+    l: usize,
+    rng: &mut impl Rng,
+) -> (
+    SymmetricCommitmentKey<E>,
+    VerificationKey<E>,
+    ThresholdKeys<E>,
+    KeyShareProofs<E>,
+) {
+    let (ck, vk, ts_keys, y_values) = deal(t, n, l, rng);
+    let share_proofs = ts_keys
+        .sk_shares
+        .iter()
+        .zip(ts_keys.vk_shares.iter())
+        .map(|(sk, vk_share)| {
+            let mut witnesses = Zeroizing::new(Vec::with_capacity(1 + l));
+            witnesses.push(sk.x_share);
+            witnesses.extend_from_slice(&sk.y_shares);
+            KeyProof::prove(
+                KEY_SHARE_PROOF_DOMAIN,
+                &vk_share.key_statement(&ck),
+                &witnesses,
+                rng,
+            )
+        })
+        .collect();
+    let ck_proof = KeyProof::prove(
+        COMMITMENT_KEY_PROOF_DOMAIN,
+        &commitment_key_statement(&ck),
+        &y_values,
+        rng,
+    );
+    let proofs = KeyShareProofs {
+        share_proofs,
+        ck_proof,
+    };
+    (ck, vk, ts_keys, proofs)
+}
+
+#[allow(clippy::type_complexity)]
+fn deal<E: Pairing>(
+    t: usize,
+    n: usize,
+    l: usize,
+    rng: &mut impl Rng,
+) -> (
+    SymmetricCommitmentKey<E>,
+    VerificationKey<E>,
+    ThresholdKeys<E>,
+    Zeroizing<Vec<E::ScalarField>>,
 ) {
     // 1. generate x and xshares
-    let x = E::ScalarField::rand(rng);
-    let x_shares = generate_shares(&x, t, n, rng);
+    let x = Zeroizing::new(E::ScalarField::rand(rng));
+    let x_shares = generate_shares(&*x, t, n, rng);
 
     // generate y values [y1,..,yL]
-    let mut y_values = Vec::with_capacity(l);
+    let mut y_values = Zeroizing::new(Vec::with_capacity(l));
     // [[y1_1,...,y1_L]_1,...,[yL_1,...,yL_L]_k]
     let mut y_shares_by_k = Vec::with_capacity(l);
 
@@ -61,7 +303,7 @@ pub fn keygen<E: Pairing>(
 
     let ck: SymmetricCommitmentKey<E> = SymmetricCommitmentKey::new(&y_values, rng);
 
-    let g_tilde_x = ck.g_tilde.mul(x).into_affine();
+    let g_tilde_x = ck.g_tilde.mul(*x).into_affine();
     let vk: VerificationKey<E> = VerificationKey { g_tilde_x };
 
     // exponentiate the shares for g1,g2 values of shares
@@ -108,19 +350,18 @@ pub fn keygen<E: Pairing>(
         vk_shares,
     };
 
-    (ck, vk, ts_keys)
+    (ck, vk, ts_keys, y_values)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ark_bls12_381::{Bls12_381, Fr};
     use ark_ec::pairing::Pairing;
     use ark_ec::CurveGroup;
     use ark_std::test_rng;
+    use ark_std::Zero;
 
-    #[test]
-    fn test_dist_keygen() {
+    fn test_dist_keygen<E: Pairing>() {
         let mut rng = test_rng();
         let threshold = 2; // t=2, need t+1=3 participants to reconstruct
         let n_participants = 5;
@@ -129,9 +370,9 @@ mod tests {
         // Generate threshold keys
         // Destructuring with type annotation
         let (ck, vk, ts_keys): (
-            SymmetricCommitmentKey<Bls12_381>,
-            VerificationKey<Bls12_381>,
-            ThresholdKeys<Bls12_381>,
+            SymmetricCommitmentKey<E>,
+            VerificationKey<E>,
+            ThresholdKeys<E>,
         ) = keygen(threshold, n_participants, l_attributes, &mut rng);
 
         // Verify number of participants
@@ -148,13 +389,13 @@ mod tests {
         let subset_indices = (0..threshold + 1).collect::<Vec<_>>();
 
         // Collect x shares from these participants
-        let x_shares_subset: Vec<(usize, Fr)> = subset_indices
+        let x_shares_subset: Vec<(usize, E::ScalarField)> = subset_indices
             .iter()
             .map(|&i| (ts_keys.sk_shares[i].index, ts_keys.sk_shares[i].x_share))
             .collect();
 
         // Reconstruct x
-        let reconstructed_x: <Bls12_381 as Pairing>::ScalarField =
+        let reconstructed_x: <E as Pairing>::ScalarField =
             reconstruct_secret(&x_shares_subset, threshold + 1);
 
         let computed_g_tilde_x = ck.g_tilde.mul(reconstructed_x).into_affine();
@@ -165,12 +406,13 @@ mod tests {
 
         // Test reconstruction of each y_k
         for k in 0..l_attributes {
-            let y_k_shares_subset: Vec<(usize, Fr)> = subset_indices
+            let y_k_shares_subset: Vec<(usize, E::ScalarField)> = subset_indices
                 .iter()
                 .map(|&i| (ts_keys.sk_shares[i].index, ts_keys.sk_shares[i].y_shares[k]))
                 .collect();
 
-            let reconstructed_y_k: Fr = reconstruct_secret(&y_k_shares_subset, threshold + 1);
+            let reconstructed_y_k: E::ScalarField =
+                reconstruct_secret(&y_k_shares_subset, threshold + 1);
             let computed_g_tilde_y_k = ck.g_tilde.mul(reconstructed_y_k).into_affine();
             assert_eq!(
                 computed_g_tilde_y_k, ck.ck_tilde[k],
@@ -179,4 +421,77 @@ mod tests {
             );
         }
     }
+
+    fn test_secret_key_share_redacted<E: Pairing>() {
+        let mut rng = test_rng();
+        let (_, _, ts_keys): (
+            SymmetricCommitmentKey<E>,
+            VerificationKey<E>,
+            ThresholdKeys<E>,
+        ) = keygen(1, 3, 2, &mut rng);
+        let mut share = ts_keys.sk_shares[1].clone();
+
+        // only the public index is printed
+        assert_eq!(
+            format!("{:?}", share),
+            format!("SecretKeyShare {{ index: {}, .. }}", share.index)
+        );
+
+        let index = share.index;
+        share.zeroize();
+        assert_eq!(share.index, index);
+        assert!(share.x_share.is_zero() && share.y_shares.is_empty());
+    }
+
+    fn test_verify_key_shares<E: Pairing>() {
+        let mut rng = test_rng();
+        let (t, n) = (2, 5);
+        let (ck, vk, ts_keys, proofs) = keygen_with_proofs::<E>(t, n, 3, &mut rng);
+        assert!(verify_key_shares(&ck, &vk, &ts_keys.vk_shares, &proofs, t, &mut rng).is_ok());
+
+        // a share off the polynomial is caught even with a valid proof for it
+        let mut shares = ts_keys.vk_shares.clone();
+        let mut share_proofs = proofs.clone();
+        let other = keygen::<E>(t, n, 3, &mut rng).2;
+        let sk = &other.sk_shares[4];
+        shares[4].g_tilde_x_share = ck.g_tilde.mul(sk.x_share).into_affine();
+        shares[4].g_tilde_y_shares = sk
+            .y_shares
+            .iter()
+            .map(|y| ck.g_tilde.mul(y).into_affine())
+            .collect();
+        let mut witnesses = vec![sk.x_share];
+        witnesses.extend_from_slice(&sk.y_shares);
+        share_proofs.share_proofs[4] = KeyProof::prove(
+            KEY_SHARE_PROOF_DOMAIN,
+            &shares[4].key_statement(&ck),
+            &witnesses,
+            &mut rng,
+        );
+        assert!(matches!(
+            verify_key_shares(&ck, &vk, &shares, &share_proofs, t, &mut rng),
+            Err(ProtocolError::InconsistentKeyShares)
+        ));
+
+        // a share whose proof does not verify is reported by position
+        let mut bad = proofs.clone();
+        bad.share_proofs.swap(1, 3);
+        assert!(matches!(
+            verify_key_shares(&ck, &vk, &ts_keys.vk_shares, &bad, t, &mut rng),
+            Err(ProtocolError::KeyProofError(KeyProofError::InvalidProof(1)))
+        ));
+
+        // shares of another key do not open to this vk
+        let (_, other_vk, _) = keygen::<E>(t, n, 3, &mut rng);
+        assert!(matches!(
+            verify_key_shares(&ck, &other_vk, &ts_keys.vk_shares, &proofs, t, &mut rng),
+            Err(ProtocolError::InconsistentKeyShares)
+        ));
+    }
+
+    utils::curve_tests!(
+        test_dist_keygen,
+        test_secret_key_share_redacted,
+        test_verify_key_shares
+    );
 }
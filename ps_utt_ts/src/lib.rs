@@ -9,5 +9,8 @@ pub mod shamir;
 pub mod signature;
 pub mod signer;
 pub mod symmetric_commitment;
-pub mod tests;
+#[cfg(test)]
+mod tests;
+pub mod threshold_decryption;
 pub mod user;
+pub mod wire;
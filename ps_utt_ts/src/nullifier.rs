@@ -27,7 +27,9 @@ use ark_std::{
     rand::Rng,
     One, UniformRand, Zero,
 };
+use core::fmt;
 use core::marker::PhantomData;
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 /// Input to the Private Pairing-Free VRF
 #[derive(Clone, Debug)]
@@ -52,13 +54,19 @@ pub struct DYPFPrivPublicKey<G: AffineRepr> {
     pub cm_x: G,  // Commitment to input: g2^x * g^r_x
 }
 
-/// Secret key for the Private Pairing-Free VRF
-#[derive(Clone, Debug)]
-pub struct DYPFPrivSecretKey<F> {
+/// Secret key for the Private Pairing-Free VRF, wiped on drop and left out of `Debug` output
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct DYPFPrivSecretKey<F: Zeroize> {
     pub sk: F,   // Secret key
     pub r_sk: F, // Randomness used in commitment
 }
 
+impl<F: Zeroize> fmt::Debug for DYPFPrivSecretKey<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DYPFPrivSecretKey").finish_non_exhaustive()
+    }
+}
+
 /// Output of the Private Pairing-Free VRF
 #[derive(Clone, Debug)]
 pub struct DYPFPrivVRFOutput<G: AffineRepr> {
@@ -301,22 +309,21 @@ impl<G: AffineRepr> DYPFPrivVRF<G> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ark_bls12_381::{Fr, G1Affine};
+    use ark_ec::pairing::Pairing;
     use ark_std::test_rng;
 
-    #[test]
-    fn test_pdy_priv_vrf_complete_protocol() {
+    fn test_pdy_priv_vrf_complete_protocol<E: Pairing>() {
         let mut rng = test_rng();
 
         // Initialize VRF
-        let vrf = DYPFPrivVRF::<G1Affine>::new(&mut rng);
+        let vrf = DYPFPrivVRF::<E::G1Affine>::new(&mut rng);
 
         // Generate keys with commitment to secret key
         let (sk, mut pk) = vrf.generate_keys(&mut rng);
 
         // Create input and commitment to input
-        let x = Fr::rand(&mut rng);
-        let r_x = Fr::rand(&mut rng);
+        let x = E::ScalarField::rand(&mut rng);
+        let r_x = E::ScalarField::rand(&mut rng);
 
         // Compute commitment to x: cm_x = g2^x * g^r_x
         let cm_x = (vrf.pp.g2.mul(x) + vrf.pp.g.mul(r_x)).into_affine();
@@ -334,11 +341,13 @@ mod tests {
         let output = vrf.evaluate(&witness).expect("Failed to evaluate VRF");
 
         // Generate proof
-        let challenge = Fr::rand(&mut rng);
+        let challenge = E::ScalarField::rand(&mut rng);
         let proof = vrf.prove_with_challenge(&witness, &output, &challenge, &mut rng);
 
         // Verify
         let is_valid = vrf.verify(&pk, &output, &proof, &challenge);
         assert!(is_valid, "P-DY-Priv VRF verification failed");
     }
+
+    utils::curve_tests!(test_pdy_priv_vrf_complete_protocol);
 }
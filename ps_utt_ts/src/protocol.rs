@@ -1,14 +1,18 @@
-use crate::credential::{Credential, CredentialCommitments};
-use crate::errors::{CredentialError, SignatureError};
+use crate::credential::{Credential, IssuanceRequest, ShowCredential};
+use crate::errors::{CredentialError, ProtocolError, SignatureError};
 use crate::keygen::VerificationKeyShare;
-use crate::keygen::{keygen, ThresholdKeys, VerificationKey};
+use crate::keygen::{
+    keygen, keygen_with_proofs, verify_key_shares, KeyShareProofs, PreparedVerificationKey,
+    ThresholdKeys, VerificationKey,
+};
 use crate::signature::{PartialSignature, ThresholdSignature};
 use crate::signer::Signer;
-use crate::symmetric_commitment::SymmetricCommitmentKey;
+use crate::symmetric_commitment::{SymmetricCommitmentKey, SymmetricCommitmentKeyTables};
 use crate::user::User;
 use ark_ec::pairing::Pairing;
 use ark_std::{rand::Rng, UniformRand};
 use rayon::prelude::*;
+use utils::key_ring::KeyRing;
 
 pub struct IssuerProtocol;
 pub struct UserProtocol;
@@ -29,25 +33,50 @@ impl IssuerProtocol {
         keygen(threshold, num_signers, num_attributes, rng)
     }
 
+    /// `setup` together with the proofs holders check with `UserProtocol::verify_issuer_keys`
+    pub fn setup_with_proofs<E: Pairing>(
+        threshold: usize,
+        num_signers: usize,
+        num_attributes: usize,
+        rng: &mut impl Rng,
+    ) -> (
+        SymmetricCommitmentKey<E>,
+        VerificationKey<E>,
+        ThresholdKeys<E>,
+        KeyShareProofs<E>,
+    ) {
+        keygen_with_proofs(threshold, num_signers, num_attributes, rng)
+    }
+
     /// Issuer signs a credential request
     pub fn issue_share<E: Pairing>(
         signer: &Signer<E>,
-        commitments: &[E::G1Affine],
-        commitment_proofs: &[Vec<u8>],
-        h: &E::G1Affine,
+        request: &IssuanceRequest<E>,
         rng: &mut impl Rng,
     ) -> Result<PartialSignature<E>, SignatureError> {
-        signer.sign_share(commitments, commitment_proofs, h, rng)
+        signer.sign_share(request, rng)
     }
 }
 
 impl UserProtocol {
+    /// User checks the published key shares before requesting a credential
+    pub fn verify_issuer_keys<E: Pairing>(
+        commitment_key: &SymmetricCommitmentKey<E>,
+        vk: &VerificationKey<E>,
+        vk_shares: &[VerificationKeyShare<E>],
+        proofs: &KeyShareProofs<E>,
+        threshold: usize,
+        rng: &mut impl Rng,
+    ) -> Result<(), ProtocolError> {
+        verify_key_shares(commitment_key, vk, vk_shares, proofs, threshold, rng)
+    }
+
     /// User creates a credential request
     pub fn request_credential<E: Pairing>(
         commitment_key: SymmetricCommitmentKey<E>,
         attributes: Option<&[E::ScalarField]>,
         rng: &mut impl Rng,
-    ) -> Result<(Credential<E>, CredentialCommitments<E>), CredentialError> {
+    ) -> Result<(Credential<E>, IssuanceRequest<E>), CredentialError> {
         let mut credential = Credential::new(commitment_key, attributes, rng);
         let commitments = credential.compute_commitments_per_m(rng)?;
         Ok((credential, commitments))
@@ -56,7 +85,7 @@ impl UserProtocol {
     // /// User collects signatures from multiple issuers
     // pub fn collect_signature_shares<E: Pairing>(
     //     signers: &[Signer<E>],
-    //     credential_request: &CredentialCommitments<E>,
+    //     credential_request: &IssuanceRequest<E>,
     //     threshold: usize,
     //     rng: &mut impl Rng,
     // ) -> Result<Vec<(usize, PartialSignature<E>)>, SignatureError> {
@@ -91,14 +120,10 @@ impl UserProtocol {
 
     pub fn collect_signature_shares<E: Pairing>(
         signers: &[Signer<E>],
-        credential_request: &CredentialCommitments<E>,
+        credential_request: &IssuanceRequest<E>,
         threshold: usize,
         rng: &mut impl Rng,
     ) -> Result<Vec<(usize, PartialSignature<E>)>, SignatureError> {
-        let commitments = &credential_request.commitments;
-        let proofs = &credential_request.proofs;
-        let h = &credential_request.h;
-
         let shares: Vec<_> = signers
             .par_iter()
             .take(threshold)
@@ -106,7 +131,7 @@ impl UserProtocol {
                 // Each thread gets its own RNG
                 let mut thread_rng = rand::thread_rng();
                 signer
-                    .sign_share(commitments, proofs, h, &mut thread_rng)
+                    .sign_share(credential_request, &mut thread_rng)
                     .map(|sig_share| (sig_share.party_index, sig_share))
             })
             .collect::<Result<Vec<_>, _>>()?;
@@ -125,17 +150,18 @@ impl UserProtocol {
     pub fn verify_signature_shares<E: Pairing>(
         commitment_key: &SymmetricCommitmentKey<E>,
         vk_shares: &[VerificationKeyShare<E>],
-        credential_request: &CredentialCommitments<E>,
+        credential_request: &IssuanceRequest<E>,
         signature_shares: &[(usize, PartialSignature<E>)],
         threshold: usize,
+        rng: &mut impl Rng,
     ) -> Result<Vec<(usize, PartialSignature<E>)>, SignatureError> {
         User::process_signature_shares(
             commitment_key,
             vk_shares,
-            &credential_request.commitments,
-            &credential_request.proofs,
+            credential_request,
             signature_shares,
             threshold,
+            rng,
         )
     }
 
@@ -163,6 +189,15 @@ impl UserProtocol {
     ) -> Result<(ThresholdSignature<E>, E::G1Affine, E::G2Affine, Vec<u8>), CredentialError> {
         credential.show(rng)
     }
+
+    /// User shows a credential using precomputed commitment key tables
+    pub fn show_with_tables<E: Pairing>(
+        credential: &Credential<E>,
+        tables: &SymmetricCommitmentKeyTables<E>,
+        rng: &mut impl Rng,
+    ) -> Result<(ThresholdSignature<E>, E::G1Affine, E::G2Affine, Vec<u8>), CredentialError> {
+        credential.show_with_tables(tables, rng)
+    }
 }
 
 impl VerifierProtocol {
@@ -184,4 +219,43 @@ impl VerifierProtocol {
             proof,
         )
     }
+
+    /// Verify a presentation under the key its signature names. Ring entries keep the commitment
+    /// key dealt with each verification key. The key must be valid at `timestamp`, so credentials
+    /// under a retired key stop verifying once its window closes
+    pub fn verify_with_key_ring<E: Pairing>(
+        ring: &KeyRing<(SymmetricCommitmentKey<E>, PreparedVerificationKey<E>)>,
+        presentation: &ShowCredential<E>,
+        timestamp: u64,
+    ) -> Result<bool, SignatureError> {
+        let (commitment_key, verification_key) =
+            ring.get(&presentation.randomized_signature.key_id, timestamp)?;
+        Self::verify_prepared(
+            commitment_key,
+            verification_key,
+            &presentation.cm,
+            &presentation.cm_tilde,
+            &presentation.randomized_signature,
+            &presentation.proof,
+        )
+    }
+
+    /// Verify a credential presentation with a key prepared via `VerificationKey::prepare`
+    pub fn verify_prepared<E: Pairing>(
+        commitment_key: &SymmetricCommitmentKey<E>,
+        verification_key: &PreparedVerificationKey<E>,
+        commitment: &E::G1Affine,
+        commitment_tilde: &E::G2Affine,
+        signature: &ThresholdSignature<E>,
+        proof: &[u8],
+    ) -> Result<bool, SignatureError> {
+        ThresholdSignature::<E>::verify_prepared(
+            commitment_key,
+            verification_key,
+            commitment,
+            commitment_tilde,
+            signature,
+            proof,
+        )
+    }
 }
@@ -79,15 +79,14 @@ pub fn reconstruct_secret<F: Field>(shares: &[(usize, F)], threshold: usize) ->
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ark_bls12_381::Fr;
+    use ark_ec::pairing::Pairing;
     use ark_ff::One;
     use ark_std::test_rng;
 
-    #[test]
-    fn test_shamir_secret_sharing_basic() {
+    fn test_shamir_secret_sharing_basic<E: Pairing>() {
         let mut rng = test_rng();
 
-        let secret = Fr::rand(&mut rng);
+        let secret = E::ScalarField::rand(&mut rng);
         let threshold = 3;
         let num_shares = 5;
 
@@ -98,4 +97,6 @@ mod tests {
         let reconstructed_secret = reconstruct_secret(&shares[0..threshold], threshold);
         assert_eq!(reconstructed_secret, secret);
     }
+
+    utils::curve_tests!(test_shamir_secret_sharing_basic);
 }
@@ -1,28 +1,44 @@
-use crate::commitment::Commitment;
 use crate::errors::SignatureError;
-use crate::keygen::{keygen, SecretKeyShare, ThresholdKeys, VerificationKey, VerificationKeyShare};
+use crate::keygen::{PreparedVerificationKey, VerificationKey, VerificationKeyShare};
 use crate::symmetric_commitment::{SymmetricCommitment, SymmetricCommitmentKey};
 use ark_ec::pairing::Pairing;
 use ark_ec::{AffineRepr, CurveGroup, VariableBaseMSM};
 use ark_ff::{Field, UniformRand};
-use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::rand::Rng;
 use ark_std::{
-    ops::{Add, Mul, Neg},
-    One, Zero,
+    ops::{Mul, Neg},
+    Zero,
 };
-use utils::pairing::{verify_pairing_equation, PairingCheck};
-
-#[derive(Clone, Debug)]
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use utils::encoding::Base64Point;
+use utils::hash::HashUtil;
+use utils::key_ring::KeyId;
+use utils::pairing::verify_pairing_equation;
+
+#[serde_as]
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct PartialSignature<E: Pairing> {
+    /// Threshold key the share is under, see `Signer::with_key_id`
+    pub key_id: KeyId,
     pub party_index: usize,
+    #[serde_as(as = "Base64Point")]
     pub h: E::G1Affine,
+    #[serde_as(as = "Base64Point")]
     pub sigma: E::G1Affine,
 }
 
-#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+#[serde_as]
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct ThresholdSignature<E: Pairing> {
+    /// Key id shared by the aggregated shares, kept through randomization so presentations name it
+    pub key_id: KeyId,
+    #[serde_as(as = "Base64Point")]
     pub h: E::G1Affine,
+    #[serde_as(as = "Base64Point")]
     pub sigma: E::G1Affine,
 }
 
@@ -81,7 +97,14 @@ impl<E: Pairing> ThresholdSignature<E> {
         let mut indices = Vec::with_capacity(signature_shares.len());
         let mut sigma_2_components = Vec::with_capacity(signature_shares.len());
 
+        // shares under different keys do not combine, e.g. during a rotation
+        let key_id = signature_shares
+            .first()
+            .map_or(KeyId::default(), |(_, share)| share.key_id);
         for (_, share) in signature_shares {
+            if share.key_id != key_id {
+                return Err(SignatureError::KeyIdMismatch(share.party_index));
+            }
             indices.push(share.party_index);
             sigma_2_components.push((share.party_index, share.sigma));
         }
@@ -103,6 +126,7 @@ impl<E: Pairing> ThresholdSignature<E> {
 
         // Construct the final signature
         Ok(ThresholdSignature {
+            key_id,
             h: *h,
             sigma: final_sigma,
         })
@@ -129,6 +153,7 @@ impl<E: Pairing> ThresholdSignature<E> {
         let sigma_prime = (temp + self.sigma).mul(u_delta).into_affine();
 
         ThresholdSignature {
+            key_id: self.key_id,
             h: h_prime,
             sigma: sigma_prime,
         }
@@ -144,33 +169,48 @@ impl<E: Pairing> ThresholdSignature<E> {
         sig: &ThresholdSignature<E>,
         serialized_proof: &[u8],
     ) -> Result<bool, SignatureError> {
-        let mut rng = ark_std::test_rng();
-        let mr = std::sync::Mutex::new(rng);
-        // Optimized check: e(sigma2, g2) * e(sigma1, vk + cmg2)^-1 = 1
-        let vk_plus_cm_tilde = vk.g_tilde_x.add(cm_tilde).into_affine();
-        let check1 = PairingCheck::<E>::rand(
-            &mr,
-            &[
-                (&sig.sigma, &ck.g_tilde),
-                (&sig.h.into_group().neg().into_affine(), &vk_plus_cm_tilde),
+        Self::verify_prepared(ck, &vk.prepare(ck), cm, cm_tilde, sig, serialized_proof)
+    }
+
+    /// Verify a threshold signature with a prepared verification key.
+    /// e(sigma, g2) = e(h, vk + cmg2) and e(cmg1, g2) = e(g1, cmg2) are folded into
+    /// e(sigma + rho*cmg1, g2) * e(-h, vk) * e(-h - rho*g1, cmg2) = 1, with rho hashed
+    /// from the presentation so only cmg2 needs preparing per call
+    pub fn verify_prepared(
+        ck: &SymmetricCommitmentKey<E>,
+        pvk: &PreparedVerificationKey<E>,
+        cm: &E::G1Affine,
+        cm_tilde: &E::G2Affine,
+        sig: &ThresholdSignature<E>,
+        serialized_proof: &[u8],
+    ) -> Result<bool, SignatureError> {
+        // the opening proof is over the verifier's commitment bases and for the presented cm
+        match SymmetricCommitment::<E>::verify(ck, serialized_proof)? {
+            Some(commitment) if commitment == *cm => {}
+            _ => return Err(SignatureError::SignatureVerificationFailed),
+        }
+
+        let mut transcript = Vec::new();
+        for point in [sig.h, sig.sigma, *cm] {
+            point.serialize_compressed(&mut transcript)?;
+        }
+        cm_tilde.serialize_compressed(&mut transcript)?;
+        let rho = HashUtil::<E::ScalarField, E::G1Affine>::hash_to_field(&transcript);
+
+        let h_neg = sig.h.into_group().neg();
+        let is_valid = E::multi_pairing(
+            [
+                E::G1Prepared::from(sig.sigma + cm.mul(rho)),
+                E::G1Prepared::from(h_neg),
+                E::G1Prepared::from(h_neg - ck.g.mul(rho)),
             ],
-            &E::TargetField::one(),
-        );
-
-        // Optimized check: e(cmg1, g2) * e(g1, cmg2)^-1 = 1
-        let check2 = PairingCheck::<E>::rand(
-            &mr,
-            &[
-                (cm, &ck.g_tilde),
-                (&ck.g.into_group().neg().into_affine(), cm_tilde),
+            [
+                pvk.g_tilde.clone(),
+                pvk.g_tilde_x.clone(),
+                E::G2Prepared::from(*cm_tilde),
             ],
-            &E::TargetField::one(),
-        );
-
-        let mut final_check = PairingCheck::<E>::new();
-        final_check.merge(&check1);
-        final_check.merge(&check2);
-        let is_valid = final_check.verify();
+        )
+        .is_zero();
         if !is_valid {
             return Err(SignatureError::SignatureVerificationFailed);
         }
@@ -1,6 +1,5 @@
-use crate::commitment::batch_verify;
-use crate::commitment::Commitment;
-use crate::errors::{CommitmentError, SignatureError};
+use crate::credential::IssuanceRequest;
+use crate::errors::SignatureError;
 use crate::keygen::{SecretKeyShare, VerificationKeyShare};
 use crate::signature::PartialSignature;
 use crate::symmetric_commitment::SymmetricCommitmentKey;
@@ -8,12 +7,15 @@ use ark_ec::pairing::Pairing;
 use ark_ec::CurveGroup;
 use ark_std::ops::Mul;
 use ark_std::rand::Rng;
+use utils::key_ring::KeyId;
 
 /// A signer in the threshold signature scheme with lifetime parameters
 pub struct Signer<'a, E: Pairing> {
     pub ck: &'a SymmetricCommitmentKey<E>,
     pub sk_share: &'a SecretKeyShare<E>,
     pub vk_share: &'a VerificationKeyShare<E>,
+    /// Id of the threshold verification key, copied into every share
+    pub key_id: KeyId,
 }
 
 impl<'a, E: Pairing> Signer<'a, E> {
@@ -27,31 +29,27 @@ impl<'a, E: Pairing> Signer<'a, E> {
             ck,
             sk_share,
             vk_share,
+            key_id: KeyId::default(),
         }
     }
 
+    /// Names the threshold key in the shares this signer issues, `VerificationKey::key_id`
+    pub fn with_key_id(mut self, key_id: KeyId) -> Self {
+        self.key_id = key_id;
+        self
+    }
+
     /// sign a share of the threshold signature
     pub fn sign_share(
         &self,
-        commitments: &[E::G1Affine],
-        commitment_proofs: &[Vec<u8>],
-        h: &E::G1Affine,
+        request: &IssuanceRequest<E>,
         rng: &mut impl Rng,
     ) -> Result<PartialSignature<E>, SignatureError> {
-        // Verify all commitment proofs
-
+        // Verify all commitment proofs as one request, batched
         // from 45% to 50% improvement in schnorr verification time
-        let valid = batch_verify::<E>(commitment_proofs, rng)?;
-        if !valid {
-            return Err(CommitmentError::BatchVerifyError.into());
-        }
-
-        // for (_, proof) in commitments.iter().zip(commitment_proofs.iter()) {
-        //     let valid = Commitment::<E>::verify(proof)?;
-        //     if !valid {
-        //         return Err(SignatureError::InvalidShare(self.sk_share.index).into());
-        //     }
-        // }
+        request.verify(self.ck, rng)?;
+        let commitments = &request.commitments;
+        let h = &request.h;
 
         // Extract the index and secret key shares
         let i = self.sk_share.index;
@@ -68,6 +66,7 @@ impl<'a, E: Pairing> Signer<'a, E> {
         }
 
         Ok(PartialSignature {
+            key_id: self.key_id,
             party_index: i,
             h: h.clone(),
             sigma: sigma.into_affine(),
@@ -78,25 +77,14 @@ impl<'a, E: Pairing> Signer<'a, E> {
     // for testing and comparison purposes - no zkp verify.
     pub fn sign_share_no_zkp_verify(
         &self,
-        commitments: &[E::G1Affine],
-        commitment_proofs: &[Vec<u8>],
-        h: &E::G1Affine,
-        rng: &mut impl Rng,
+        request: &IssuanceRequest<E>,
     ) -> Result<PartialSignature<E>, SignatureError> {
         // Verify all commitment proofs
 
         // from 45% to 50% improvement in schnorr verification time
-        // let valid = batch_verify::<E>(commitment_proofs, rng)?;
-        // if !valid {
-        //     return Err(CommitmentError::BatchVerifyError.into());
-        // }
-
-        // for (_, proof) in commitments.iter().zip(commitment_proofs.iter()) {
-        //     let valid = Commitment::<E>::verify(proof)?;
-        //     if !valid {
-        //         return Err(SignatureError::InvalidShare(self.sk_share.index).into());
-        //     }
-        // }
+        // request.verify(self.ck, rng)?;
+        let commitments = &request.commitments;
+        let h = &request.h;
 
         // Extract the index and secret key shares
         let i = self.sk_share.index;
@@ -113,6 +101,7 @@ impl<'a, E: Pairing> Signer<'a, E> {
         }
 
         Ok(PartialSignature {
+            key_id: self.key_id,
             party_index: i,
             h: h.clone(),
             sigma: sigma.into_affine(),
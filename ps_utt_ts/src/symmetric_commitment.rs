@@ -1,15 +1,18 @@
-use crate::commitment::CommitmentProof;
+use crate::commitment::{opening_challenge, CommitmentProof};
 use crate::errors::CommitmentError;
-use crate::shamir::generate_shares;
 use ark_ec::pairing::Pairing;
 use ark_ec::{CurveGroup, VariableBaseMSM};
 use ark_ff::UniformRand;
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
-use ark_std::ops::{Add, Mul, Neg};
+use ark_std::ops::{Add, Mul};
 use ark_std::rand::Rng;
-use schnorr::schnorr::{SchnorrCommitment, SchnorrProtocol, SchnorrResponses};
-use serde::Serialize;
-use thiserror::Error;
+use schnorr::schnorr::{SchnorrCommitment, SchnorrProtocol};
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use utils::encoding::Base64Point;
+use utils::fixed_base::{FixedBaseMsm, FixedBaseTable};
+
+const OPENING_DOMAIN: &[u8] = b"ps-utt-ts-opening-proof";
 
 #[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
 pub struct SymmetricCommitment<E: Pairing> {
@@ -20,11 +23,17 @@ pub struct SymmetricCommitment<E: Pairing> {
     pub cm_tilde: E::G2Affine,
 }
 
-#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+#[serde_as]
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct SymmetricCommitmentKey<E: Pairing> {
+    #[serde_as(as = "Base64Point")]
     pub g: E::G1Affine,
+    #[serde_as(as = "Vec<Base64Point>")]
     pub ck: Vec<E::G1Affine>,
+    #[serde_as(as = "Base64Point")]
     pub g_tilde: E::G2Affine,
+    #[serde_as(as = "Vec<Base64Point>")]
     pub ck_tilde: Vec<E::G2Affine>,
 }
 
@@ -67,6 +76,36 @@ impl<E: Pairing> SymmetricCommitmentKey<E> {
     }
 }
 
+/// Fixed-base tables for the commitment key, built once with `SymmetricCommitmentKey::precompute`
+/// and serializable so a holder or server can load them at startup
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct SymmetricCommitmentKeyTables<E: Pairing> {
+    /// [ck_1, ..., ck_L, g] as returned by `get_bases`
+    pub bases: FixedBaseMsm<E::G1>,
+    /// [ck~_1, ..., ck~_L, g~]
+    pub bases_tilde: FixedBaseMsm<E::G2>,
+}
+
+impl<E: Pairing> SymmetricCommitmentKeyTables<E> {
+    pub fn g(&self) -> &FixedBaseTable<E::G1> {
+        self.bases.table(self.bases.len() - 1)
+    }
+
+    pub fn g_tilde(&self) -> &FixedBaseTable<E::G2> {
+        self.bases_tilde.table(self.bases_tilde.len() - 1)
+    }
+}
+
+impl<E: Pairing> SymmetricCommitmentKey<E> {
+    pub fn precompute(&self) -> SymmetricCommitmentKeyTables<E> {
+        let (bases, bases_tilde) = self.get_bases();
+        SymmetricCommitmentKeyTables {
+            bases: FixedBaseMsm::new(&bases),
+            bases_tilde: FixedBaseMsm::new(&bases_tilde),
+        }
+    }
+}
+
 // takes in pp, messages, r. creates cm, cm_tilde by 1. exponentiate each pp.ckg1 with mi and pp.g1 with r, msm together
 impl<E: Pairing> SymmetricCommitment<E> {
     pub fn new(
@@ -89,6 +128,27 @@ impl<E: Pairing> SymmetricCommitment<E> {
         }
     }
 
+    /// `new` with cm and cm_tilde computed from the key's tables
+    pub fn new_with_tables(
+        ck: &SymmetricCommitmentKey<E>,
+        tables: &SymmetricCommitmentKeyTables<E>,
+        messages: &[E::ScalarField],
+        r: &E::ScalarField,
+    ) -> Self {
+        assert!(messages.len() < tables.bases.len(), "message.len > ck.len");
+        // the msm stops at the last message, so g and g~ are added separately for short inputs
+        let cm = (tables.bases.msm(messages) + tables.g().mul(r)).into_affine();
+        let cm_tilde = (tables.bases_tilde.msm(messages) + tables.g_tilde().mul(r)).into_affine();
+
+        Self {
+            ck: ck.clone(),
+            messages: messages.to_vec(),
+            r: *r,
+            cm,
+            cm_tilde,
+        }
+    }
+
     pub fn randomize(&self, r_delta: &E::ScalarField) -> Self {
         let new_r = self.r + r_delta;
         let cm_delta = (self.cm + self.ck.g.mul(r_delta)).into_affine();
@@ -103,6 +163,21 @@ impl<E: Pairing> SymmetricCommitment<E> {
         }
     }
 
+    /// `randomize` with g^r_delta and g~^r_delta taken from the key's tables
+    pub fn randomize_with_tables(
+        &self,
+        tables: &SymmetricCommitmentKeyTables<E>,
+        r_delta: &E::ScalarField,
+    ) -> Self {
+        Self {
+            ck: self.ck.clone(),
+            messages: self.messages.clone(),
+            r: self.r + r_delta,
+            cm: (self.cm + tables.g().mul(r_delta)).into_affine(),
+            cm_tilde: (self.cm_tilde + tables.g_tilde().mul(r_delta)).into_affine(),
+        }
+    }
+
     pub fn randomize_just_g1(&self, r_delta: &E::ScalarField) -> Self {
         let new_r = self.r + r_delta;
         let cm_delta = (self.cm + self.ck.g.mul(r_delta)).into_affine();
@@ -126,13 +201,34 @@ impl<E: Pairing> SymmetricCommitment<E> {
     pub fn prove(self, rng: &mut impl Rng) -> Result<Vec<u8>, CommitmentError> {
         let bases = self.ck.get_bases().0;
         let schnorr_commitment = SchnorrProtocol::commit(&bases, rng);
-        let challenge = E::ScalarField::rand(rng);
+        self.finish_proof(schnorr_commitment)
+    }
+
+    /// `prove` with the Schnorr commitment taken from the key's tables
+    pub fn prove_with_tables(
+        self,
+        tables: &SymmetricCommitmentKeyTables<E>,
+        rng: &mut impl Rng,
+    ) -> Result<Vec<u8>, CommitmentError> {
+        let schnorr_commitment = SchnorrProtocol::commit_with_table(&tables.bases, rng);
+        self.finish_proof(schnorr_commitment)
+    }
+
+    fn finish_proof(
+        self,
+        schnorr_commitment: SchnorrCommitment<E::G1Affine>,
+    ) -> Result<Vec<u8>, CommitmentError> {
+        let challenge = opening_challenge::<E>(
+            OPENING_DOMAIN,
+            &self.ck.get_bases().0,
+            &self.cm,
+            &schnorr_commitment.commited_blindings,
+        )?;
         let responses =
             SchnorrProtocol::prove(&schnorr_commitment, &self.get_exponents(), &challenge);
         let proof: CommitmentProof<E> = CommitmentProof {
             commitment: self.cm,
             schnorr_commitment: schnorr_commitment.commited_blindings,
-            bases: bases,
             challenge,
             responses: responses.0,
         };
@@ -143,21 +239,35 @@ impl<E: Pairing> SymmetricCommitment<E> {
         Ok(serialized_proof)
     }
 
-    // Verify PoK
-    pub fn verify(serialized_proof: &[u8]) -> Result<bool, CommitmentError> {
+    // Verify PoK against the verifier's commitment key
+    pub fn verify(
+        ck: &SymmetricCommitmentKey<E>,
+        serialized_proof: &[u8],
+    ) -> Result<Option<E::G1Affine>, CommitmentError> {
         let proof: CommitmentProof<E> =
             CanonicalDeserialize::deserialize_compressed(serialized_proof)?;
 
+        let (bases, _) = ck.get_bases();
+        let challenge = opening_challenge::<E>(
+            OPENING_DOMAIN,
+            &bases,
+            &proof.commitment,
+            &proof.schnorr_commitment,
+        )?;
+        if challenge != proof.challenge || proof.responses.len() != bases.len() {
+            return Ok(None);
+        }
+
         // Verify using Schnorr protocol
         let is_valid = SchnorrProtocol::verify_schnorr(
-            &proof.bases,
+            &bases,
             &proof.commitment,
             &proof.schnorr_commitment,
             &proof.responses,
             &proof.challenge,
         );
 
-        Ok(is_valid)
+        Ok(is_valid.then_some(proof.commitment))
     }
 
     // pub fun verify
@@ -195,13 +305,12 @@ pub fn g2_commit<E: Pairing>(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ark_bls12_381::{Bls12_381, Fr, G1Affine};
-    use ark_std::rand::{rngs::StdRng, SeedableRng};
+    use crate::shamir::generate_shares;
+    use ark_ec::pairing::Pairing;
 
-    #[test]
-    fn test_randomized_commitment() {
+    fn test_randomized_commitment<E: Pairing>() {
         let mut rng = ark_std::test_rng();
-        let x = Fr::rand(&mut rng);
+        let x = E::ScalarField::rand(&mut rng);
         let t = 3;
         let n = 5;
         let l = 4;
@@ -214,20 +323,20 @@ mod tests {
 
         // gen l x t degree poly's
         for _ in 0..l {
-            let y_k = Fr::rand(&mut rng);
+            let y_k = E::ScalarField::rand(&mut rng);
             y_values.push(y_k);
             y_shares_by_k.push(generate_shares(&y_k, t, n, &mut rng));
         }
 
-        let ck: SymmetricCommitmentKey<Bls12_381> =
-            SymmetricCommitmentKey::new(&y_values, &mut rng);
+        let ck: SymmetricCommitmentKey<E> = SymmetricCommitmentKey::new(&y_values, &mut rng);
 
         // create commitment with messages
-        let messages: Vec<Fr> = (0..l).map(|_| Fr::rand(&mut rng)).collect();
-        let r = Fr::rand(&mut rng);
+        let messages: Vec<E::ScalarField> =
+            (0..l).map(|_| E::ScalarField::rand(&mut rng)).collect();
+        let r = E::ScalarField::rand(&mut rng);
         let commitment = SymmetricCommitment::new(&ck, &messages, &r);
 
-        let challenge = Fr::rand(&mut rng);
+        let challenge = E::ScalarField::rand(&mut rng);
 
         // Let's test opening proof
         let (bases, _) = ck.get_bases();
@@ -245,4 +354,94 @@ mod tests {
 
         assert!(is_valid);
     }
+
+    fn test_opening_proof_substituted_key<E: Pairing>() {
+        let mut rng = ark_std::test_rng();
+        let l = 3;
+        let y_values: Vec<E::ScalarField> =
+            (0..l).map(|_| E::ScalarField::rand(&mut rng)).collect();
+        let ck = SymmetricCommitmentKey::<E>::new(&y_values, &mut rng);
+        let messages: Vec<E::ScalarField> =
+            (0..l).map(|_| E::ScalarField::rand(&mut rng)).collect();
+        let r = E::ScalarField::rand(&mut rng);
+
+        let commitment = SymmetricCommitment::new(&ck, &messages, &r);
+        let proof = commitment.clone().prove(&mut rng).unwrap();
+        assert_eq!(
+            SymmetricCommitment::<E>::verify(&ck, &proof).unwrap(),
+            Some(commitment.cm)
+        );
+
+        // a key whose exponents the prover knows, the proof is only checked against ours
+        let fake_y: Vec<E::ScalarField> = (0..l).map(|_| E::ScalarField::rand(&mut rng)).collect();
+        let fake_ck = SymmetricCommitmentKey::<E>::new(&fake_y, &mut rng);
+        let forged = SymmetricCommitment::new(&fake_ck, &messages, &r);
+        let proof = forged.prove(&mut rng).unwrap();
+        assert!(SymmetricCommitment::<E>::verify(&fake_ck, &proof)
+            .unwrap()
+            .is_some());
+        assert!(SymmetricCommitment::<E>::verify(&ck, &proof)
+            .unwrap()
+            .is_none());
+
+        // a forged (T, c) pair for a commitment nobody can open
+        let (bases, _) = ck.get_bases();
+        let target = E::G1Affine::rand(&mut rng);
+        let challenge = E::ScalarField::rand(&mut rng);
+        let responses: Vec<E::ScalarField> = (0..bases.len())
+            .map(|_| E::ScalarField::rand(&mut rng))
+            .collect();
+        let proof: CommitmentProof<E> = CommitmentProof {
+            commitment: target,
+            schnorr_commitment: (E::G1::msm_unchecked(&bases, &responses) - target * challenge)
+                .into_affine(),
+            challenge,
+            responses,
+        };
+        let mut forged = Vec::new();
+        proof.serialize_compressed(&mut forged).unwrap();
+        assert!(SymmetricCommitment::<E>::verify(&ck, &forged)
+            .unwrap()
+            .is_none());
+    }
+
+    fn test_commitment_with_tables<E: Pairing>() {
+        let mut rng = ark_std::test_rng();
+        let l = 3;
+        let y_values: Vec<E::ScalarField> =
+            (0..l).map(|_| E::ScalarField::rand(&mut rng)).collect();
+        let ck = SymmetricCommitmentKey::<E>::new(&y_values, &mut rng);
+        let tables = ck.precompute();
+        let messages: Vec<E::ScalarField> =
+            (0..l).map(|_| E::ScalarField::rand(&mut rng)).collect();
+        let r = E::ScalarField::rand(&mut rng);
+
+        let commitment = SymmetricCommitment::new(&ck, &messages, &r);
+        let from_tables = SymmetricCommitment::new_with_tables(&ck, &tables, &messages, &r);
+        assert_eq!(from_tables.cm, commitment.cm);
+        assert_eq!(from_tables.cm_tilde, commitment.cm_tilde);
+
+        // fewer messages than bases commit to the leading bases only
+        let short = SymmetricCommitment::new(&ck, &messages[..1].to_vec(), &r);
+        let short_tables = SymmetricCommitment::new_with_tables(&ck, &tables, &messages[..1], &r);
+        assert_eq!(short_tables.cm, short.cm);
+
+        let r_delta = E::ScalarField::rand(&mut rng);
+        let randomized = from_tables.randomize_with_tables(&tables, &r_delta);
+        assert_eq!(randomized.cm, commitment.randomize(&r_delta).cm);
+        let proof = randomized
+            .clone()
+            .prove_with_tables(&tables, &mut rng)
+            .unwrap();
+        assert_eq!(
+            SymmetricCommitment::<E>::verify(&ck, &proof).unwrap(),
+            Some(randomized.cm)
+        );
+    }
+
+    utils::curve_tests!(
+        test_commitment_with_tables,
+        test_randomized_commitment,
+        test_opening_proof_substituted_key
+    );
 }
@@ -1,23 +1,15 @@
 use crate::{
-    commitment::Commitment,
     credential::Credential,
-    credential::CredentialCommitments,
+    credential::ShowCredential,
     errors::SignatureError,
     keygen::keygen,
-    keygen::{SecretKeyShare, ThresholdKeys, VerificationKey, VerificationKeyShare},
-    protocol::{IssuerProtocol, UserProtocol, VerifierProtocol},
-    shamir::reconstruct_secret,
-    signature::{PartialSignature, ThresholdSignature},
+    protocol::{UserProtocol, VerifierProtocol},
     signer::Signer,
-    symmetric_commitment::SymmetricCommitmentKey,
+    user::User,
 };
-use ark_bls12_381::{Bls12_381, Fr};
 use ark_ec::pairing::Pairing;
-use ark_ec::CurveGroup;
 use ark_ff::UniformRand;
-use ark_std::rand::Rng;
 use ark_std::test_rng;
-use std::ops::{Add, Mul, Neg};
 
 // Constants for tests
 const THRESHOLD: usize = 2;
@@ -26,17 +18,14 @@ const L_ATTRIBUTES: usize = 3;
 
 #[cfg(test)]
 mod tests {
-    use crate::symmetric_commitment;
-
     use super::*;
+    use utils::key_ring::{KeyRing, KeyRingError, ValidityWindow};
 
-    #[test]
-    fn test_complete_credential_flow() {
+    fn test_complete_credential_flow<E: Pairing>() {
         let mut rng = test_rng();
 
         // 1. SETUP: Generate system parameters and keys
-        let (ck, vk, ts_keys) =
-            keygen::<Bls12_381>(THRESHOLD, N_PARTICIPANTS, L_ATTRIBUTES, &mut rng);
+        let (ck, vk, ts_keys) = keygen::<E>(THRESHOLD, N_PARTICIPANTS, L_ATTRIBUTES, &mut rng);
 
         // Create signers from key shares
         // Create signers
@@ -48,10 +37,12 @@ mod tests {
             .collect();
 
         // 2. USER: Create credential with random attributes
-        let attributes: Vec<Fr> = (0..L_ATTRIBUTES).map(|_| Fr::rand(&mut rng)).collect();
+        let attributes: Vec<E::ScalarField> = (0..L_ATTRIBUTES)
+            .map(|_| E::ScalarField::rand(&mut rng))
+            .collect();
         let mut credential = Credential::new(ck.clone(), Some(&attributes), &mut rng);
 
-        // Generate commitments for each attribute 
+        // Generate commitments for each attribute
         let (mut credential, credential_request) =
             UserProtocol::request_credential(ck.clone(), Some(&attributes), &mut rng)
                 .expect("Failed to create credential request");
@@ -72,6 +63,7 @@ mod tests {
             &credential_request,
             &signature_shares,
             THRESHOLD,
+            &mut rng,
         )
         .expect("Failed to verify signature shares");
 
@@ -106,6 +98,44 @@ mod tests {
         .expect("Verification failed");
 
         assert!(is_valid, "Credential verification should succeed");
+
+        // 9. VERIFIER: Same check with a prepared key, and a forged signature is rejected
+        let pvk = vk.prepare(&ck);
+        assert!(VerifierProtocol::verify_prepared(
+            &ck,
+            &pvk,
+            &commitment,
+            &commitment_tilde,
+            &randomized_sig,
+            &proof,
+        )
+        .expect("Prepared verification failed"));
+        let mut forged_sig = randomized_sig.clone();
+        forged_sig.sigma = forged_sig.h;
+        assert!(VerifierProtocol::verify_prepared(
+            &ck,
+            &pvk,
+            &commitment,
+            &commitment_tilde,
+            &forged_sig,
+            &proof,
+        )
+        .is_err());
+
+        // 10. USER: Presentations from the commitment key tables verify the same way
+        let tables = ck.precompute();
+        let (randomized_sig, commitment, commitment_tilde, proof) =
+            UserProtocol::show_with_tables(&credential, &tables, &mut rng)
+                .expect("Failed to generate credential presentation");
+        assert!(VerifierProtocol::verify_prepared(
+            &ck,
+            &pvk,
+            &commitment,
+            &commitment_tilde,
+            &randomized_sig,
+            &proof,
+        )
+        .expect("Verification failed"));
     }
 
     // #[test]
@@ -114,7 +144,7 @@ mod tests {
 
     //     // Generate keys
     //     let (ck, vk, ts_keys) =
-    //         keygen::<Bls12_381>(THRESHOLD, N_PARTICIPANTS, L_ATTRIBUTES, &mut rng);
+    //         keygen::<E>(THRESHOLD, N_PARTICIPANTS, L_ATTRIBUTES, &mut rng);
 
     //     // Verify correct number of shares
     //     assert_eq!(ts_keys.sk_shares.len(), N_PARTICIPANTS);
@@ -130,7 +160,7 @@ mod tests {
     //     let subset_indices = (0..THRESHOLD + 1).collect::<Vec<_>>();
 
     //     // Collect x shares from these participants
-    //     let x_shares_subset: Vec<(usize, Fr)> = subset_indices
+    //     let x_shares_subset: Vec<(usize, E::ScalarField)> = subset_indices
     //         .iter()
     //         .map(|&i| (ts_keys.sk_shares[i].index, ts_keys.sk_shares[i].x_share))
     //         .collect();
@@ -152,9 +182,9 @@ mod tests {
 
     //     // Generate keys
     //     let (ck, vk, ts_keys) =
-    //         keygen::<Bls12_381>(THRESHOLD, N_PARTICIPANTS, L_ATTRIBUTES, &mut rng);
+    //         keygen::<E>(THRESHOLD, N_PARTICIPANTS, L_ATTRIBUTES, &mut rng);
     //     // Create a credential with random attributes
-    //     let messages: Vec<Fr> = (0..L_ATTRIBUTES).map(|_| Fr::rand(&mut rng)).collect();
+    //     let messages: Vec<E::ScalarField> = (0..L_ATTRIBUTES).map(|_| E::ScalarField::rand(&mut rng)).collect();
     //     let credential = Credential::new(ck, Some(&messages), &mut rng);
 
     //     // Verify the credential has the correct messages
@@ -172,7 +202,7 @@ mod tests {
 
     //     // Generate keys
     //     let (ck, vk, ts_keys) =
-    //         keygen::<Bls12_381>(THRESHOLD, N_PARTICIPANTS, L_ATTRIBUTES, &mut rng);
+    //         keygen::<E>(THRESHOLD, N_PARTICIPANTS, L_ATTRIBUTES, &mut rng);
     //     // Create signers
     //     let signers: Vec<_> = ts_keys
     //         .sk_shares
@@ -182,7 +212,7 @@ mod tests {
     //         .collect();
 
     //     // Create a credential with random attributes
-    //     let messages: Vec<Fr> = (0..L_ATTRIBUTES).map(|_| Fr::rand(&mut rng)).collect();
+    //     let messages: Vec<E::ScalarField> = (0..L_ATTRIBUTES).map(|_| E::ScalarField::rand(&mut rng)).collect();
     //     let mut credential = Credential::new(ck.clone(), Some(&messages), &mut rng);
 
     //     // Generate commitments
@@ -214,7 +244,7 @@ mod tests {
 
     //     // Verify each signature share
     //     for (i, (_, share)) in signature_shares.iter().enumerate() {
-    //         let valid = ThresholdSignature::<Bls12_381>::verify_share(
+    //         let valid = ThresholdSignature::<E>::verify_share(
     //             &ck,
     //             &ts_keys.vk_shares[i],
     //             &commitments.commitments,
@@ -230,7 +260,7 @@ mod tests {
     //     let mut rng = test_rng();
 
     //     let (ck, vk, ts_keys) =
-    //         keygen::<Bls12_381>(THRESHOLD, N_PARTICIPANTS, L_ATTRIBUTES, &mut rng);
+    //         keygen::<E>(THRESHOLD, N_PARTICIPANTS, L_ATTRIBUTES, &mut rng);
 
     //     // Create signers
     //     let signers: Vec<_> = ts_keys
@@ -241,7 +271,7 @@ mod tests {
     //         .collect();
 
     //     // Create a credential with random attributes
-    //     let messages: Vec<Fr> = (0..L_ATTRIBUTES).map(|_| Fr::rand(&mut rng)).collect();
+    //     let messages: Vec<E::ScalarField> = (0..L_ATTRIBUTES).map(|_| E::ScalarField::rand(&mut rng)).collect();
     //     let mut credential = Credential::new(ck.clone(), Some(&messages), &mut rng);
 
     //     // Generate commitments
@@ -275,7 +305,7 @@ mod tests {
     //         .collect::<Vec<_>>();
 
     //     // aggregate_shares the signature shares
-    //     let threshold_signature = ThresholdSignature::<Bls12_381>::aggregate_signature_shares(
+    //     let threshold_signature = ThresholdSignature::<E>::aggregate_signature_shares(
     //         &ck,
     //         &sufficient_shares,
     //         &blindings,
@@ -286,7 +316,7 @@ mod tests {
 
     //     // Verify the aggregate_sharesd signature
     //     let valid =
-    //         Verifier::<Bls12_381>::verify_signature(&ck, &vk, &messages, &threshold_signature);
+    //         Verifier::<E>::verify_signature(&ck, &vk, &messages, &threshold_signature);
 
     //     assert!(valid, "aggregate_sharesd signature verification failed");
     // }
@@ -296,7 +326,7 @@ mod tests {
     //     let mut rng = test_rng();
 
     //     let (ck, vk, ts_keys) =
-    //         keygen::<Bls12_381>(THRESHOLD, N_PARTICIPANTS, L_ATTRIBUTES, &mut rng);
+    //         keygen::<E>(THRESHOLD, N_PARTICIPANTS, L_ATTRIBUTES, &mut rng);
 
     //     // Create signers
     //     let signers: Vec<_> = ts_keys
@@ -307,7 +337,7 @@ mod tests {
     //         .collect();
 
     //     // Create a credential with random attributes
-    //     let messages: Vec<Fr> = (0..L_ATTRIBUTES).map(|_| Fr::rand(&mut rng)).collect();
+    //     let messages: Vec<E::ScalarField> = (0..L_ATTRIBUTES).map(|_| E::ScalarField::rand(&mut rng)).collect();
     //     let mut credential = Credential::new(ck.clone(), Some(&messages), &mut rng);
 
     //     // Generate commitments
@@ -331,7 +361,7 @@ mod tests {
 
     //     // aggregate_shares signatures
     //     let blindings = credential.get_blinding_factors();
-    //     let threshold_signature = ThresholdSignature::<Bls12_381>::aggregate_signature_shares(
+    //     let threshold_signature = ThresholdSignature::<E>::aggregate_signature_shares(
     //         &ck,
     //         &signature_shares,
     //         &blindings,
@@ -345,7 +375,7 @@ mod tests {
 
     //     // Verify original signature
     //     let valid_original =
-    //         Verifier::<Bls12_381>::verify_signature(&ck, &vk, &messages, &threshold_signature);
+    //         Verifier::<E>::verify_signature(&ck, &vk, &messages, &threshold_signature);
     //     assert!(valid_original, "Original signature verification failed");
 
     //     // Rerandomize signature
@@ -367,4 +397,184 @@ mod tests {
     //         }
     //     }
     // }
+
+    fn test_key_rotation<E: Pairing>() {
+        let mut rng = test_rng();
+        let mut issue = || {
+            let (ck, vk, ts_keys) = keygen::<E>(THRESHOLD, N_PARTICIPANTS, L_ATTRIBUTES, &mut rng);
+            let signers: Vec<_> = ts_keys
+                .sk_shares
+                .iter()
+                .zip(ts_keys.vk_shares.iter())
+                .map(|(sk_share, vk_share)| {
+                    Signer::new(&ck, sk_share, vk_share).with_key_id(vk.key_id())
+                })
+                .collect();
+            let (mut credential, request) =
+                UserProtocol::request_credential(ck.clone(), None, &mut rng).unwrap();
+            let shares: Vec<_> = signers[..=THRESHOLD]
+                .iter()
+                .map(|signer| {
+                    let share = signer.sign_share(&request, &mut rng).unwrap();
+                    (share.party_index, share)
+                })
+                .collect();
+            assert!(shares.iter().all(|(_, s)| s.key_id == vk.key_id()));
+            let signature = UserProtocol::aggregate_shares(
+                &ck,
+                &shares,
+                credential.get_blinding_factors(),
+                THRESHOLD + 1,
+                &request.h,
+            )
+            .unwrap();
+            credential.attach_signature(signature);
+            let presentation: ShowCredential<E> =
+                UserProtocol::show(&credential, &mut rng).unwrap().into();
+            (ck, vk, shares, presentation)
+        };
+        let (old_ck, old_vk, old_shares, old_show) = issue();
+        let (new_ck, new_vk, new_shares, new_show) = issue();
+        assert_eq!(old_show.randomized_signature.key_id, old_vk.key_id());
+
+        let mut ring = KeyRing::new();
+        ring.insert(
+            old_vk.key_id(),
+            (old_ck.clone(), old_vk.prepare(&old_ck)),
+            ValidityWindow::starting(0),
+        );
+        ring.insert(
+            new_vk.key_id(),
+            (new_ck.clone(), new_vk.prepare(&new_ck)),
+            ValidityWindow::starting(200),
+        );
+        ring.retire(&old_vk.key_id(), 300).unwrap();
+
+        assert!(VerifierProtocol::verify_with_key_ring(&ring, &old_show, 250).unwrap());
+        assert!(VerifierProtocol::verify_with_key_ring(&ring, &new_show, 250).unwrap());
+        assert!(matches!(
+            VerifierProtocol::verify_with_key_ring(&ring, &old_show, 301),
+            Err(SignatureError::KeyRingError(
+                KeyRingError::OutsideValidity { .. }
+            ))
+        ));
+
+        // shares under the old and the new key are not aggregated together
+        let mixed = vec![old_shares[0].clone(), new_shares[1].clone()];
+        assert!(matches!(
+            UserProtocol::aggregate_shares(&new_ck, &mixed, &[], 2, &mixed[0].1.h),
+            Err(SignatureError::KeyIdMismatch(_))
+        ));
+    }
+
+    fn test_issuance_request_binding<E: Pairing>() {
+        let mut rng = test_rng();
+        let (ck, _, ts_keys) = keygen::<E>(THRESHOLD, N_PARTICIPANTS, L_ATTRIBUTES, &mut rng);
+        let signer = Signer::new(&ck, &ts_keys.sk_shares[0], &ts_keys.vk_shares[0]);
+
+        let (_, request) = UserProtocol::request_credential::<E>(ck.clone(), None, &mut rng)
+            .expect("Failed to create credential request");
+        let (_, other_request) = UserProtocol::request_credential::<E>(ck.clone(), None, &mut rng)
+            .expect("Failed to create credential request");
+        assert!(signer.sign_share(&request, &mut rng).is_ok());
+
+        // proofs swapped between positions no longer match their commitments
+        let mut swapped = request.clone();
+        swapped.proofs.swap(0, 1);
+        assert!(matches!(
+            signer.sign_share(&swapped, &mut rng),
+            Err(SignatureError::ProofCommitmentMismatch(0))
+        ));
+
+        // swapping commitments along with their proofs changes the transcript
+        swapped.commitments.swap(0, 1);
+        assert!(matches!(
+            signer.sign_share(&swapped, &mut rng),
+            Err(SignatureError::RequestChallengeMismatch)
+        ));
+
+        // a valid proof replayed from another request, with its commitment
+        let mut replayed = request.clone();
+        replayed.commitments[0] = other_request.commitments[0];
+        replayed.proofs[0] = other_request.proofs[0].clone();
+        assert!(matches!(
+            signer.sign_share(&replayed, &mut rng),
+            Err(SignatureError::RequestChallengeMismatch)
+        ));
+
+        // the whole request replayed under another context, h or cm_h
+        let mut replayed = request.clone();
+        replayed.context = other_request.context;
+        assert!(matches!(
+            signer.sign_share(&replayed, &mut rng),
+            Err(SignatureError::RequestBaseMismatch)
+        ));
+        let mut replayed = request.clone();
+        replayed.h = other_request.h;
+        assert!(matches!(
+            signer.sign_share(&replayed, &mut rng),
+            Err(SignatureError::RequestBaseMismatch)
+        ));
+        let mut replayed = request.clone();
+        replayed.cm_h = other_request.cm_h;
+        assert!(matches!(
+            signer.sign_share(&replayed, &mut rng),
+            Err(SignatureError::RequestBaseMismatch)
+        ));
+
+        // one proof per commitment
+        let mut short = request.clone();
+        short.proofs.pop();
+        assert!(matches!(
+            signer.sign_share(&short, &mut rng),
+            Err(SignatureError::RequestLengthMismatch {
+                commitments: L_ATTRIBUTES,
+                proofs: 2
+            })
+        ));
+
+        // a share for one request is not accepted for another
+        let share = signer.sign_share(&request, &mut rng).unwrap();
+        assert!(!User::verify_signature_share(
+            &ck,
+            &ts_keys.vk_shares[0],
+            &other_request,
+            &share,
+            &mut rng
+        )
+        .unwrap());
+    }
+
+    fn test_issuance_request_reused_h<E: Pairing>() {
+        let mut rng = test_rng();
+        let (ck, _, ts_keys) = keygen::<E>(THRESHOLD, N_PARTICIPANTS, L_ATTRIBUTES, &mut rng);
+        let signer = Signer::new(&ck, &ts_keys.sk_shares[0], &ts_keys.vk_shares[0]);
+        let (_, request) = UserProtocol::request_credential::<E>(ck.clone(), None, &mut rng)
+            .expect("Failed to create credential request");
+
+        // a second credential on the first one's h, its openings are proven correctly but h is
+        // not the hash of its own context and cm_h
+        let mut credential = Credential::<E>::new(ck.clone(), None, &mut rng);
+        credential.h = request.h;
+        let reused = credential.compute_commitments_per_m_old(&mut rng).unwrap();
+        assert!(matches!(
+            signer.sign_share(&reused, &mut rng),
+            Err(SignatureError::RequestBaseMismatch)
+        ));
+
+        // copying the context as well still leaves h bound to the first credential's messages
+        credential.context = request.context;
+        let reused = credential.compute_commitments_per_m_old(&mut rng).unwrap();
+        assert!(matches!(
+            signer.sign_share(&reused, &mut rng),
+            Err(SignatureError::RequestBaseMismatch)
+        ));
+    }
+
+    utils::curve_tests!(
+        test_complete_credential_flow,
+        test_issuance_request_binding,
+        test_issuance_request_reused_h,
+        test_key_rotation
+    );
 }
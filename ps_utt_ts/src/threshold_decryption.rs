@@ -0,0 +1,311 @@
+// t-of-n auditor decryption of escrow ciphertexts from schnorr::verifiable_encryption.
+// Both auditor secrets x (decryption) and y (label tag) are Shamir shared by a dealer. Each auditor
+// returns u^{x_i}, u^{y_i} with a Chaum-Pedersen proof against its public shares g^{x_i}, g^{y_i},
+// so the combiner can name the auditor that sent a bad share.
+use crate::errors::DecryptionError;
+use crate::shamir::generate_shares;
+use crate::signature::compute_lagrange_coefficient;
+use ark_ec::{AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_ff::UniformRand;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::rand::Rng;
+use schnorr::verifiable_encryption::{AuditorPublicKey, Ciphertext, VerifiableEncryption};
+use std::collections::HashSet;
+use utils::hash::HashUtil;
+
+/// Secret share held by auditor `index`
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct AuditorKeyShare<G: AffineRepr> {
+    pub index: usize,
+    pub x_share: G::ScalarField,
+    pub y_share: G::ScalarField,
+}
+
+/// Public share of auditor `index`, h_share = g^{x_i} and k_share = g^{y_i}
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct AuditorVerificationShare<G: AffineRepr> {
+    pub index: usize,
+    pub h_share: G,
+    pub k_share: G,
+}
+
+/// Chaum-Pedersen proof that log_g(h_share) = log_u(d) and log_g(k_share) = log_u(w)
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct ChaumPedersenProof<G: AffineRepr> {
+    pub t_g_x: G,
+    pub t_u_x: G,
+    pub t_g_y: G,
+    pub t_u_y: G,
+    pub z_x: G::ScalarField,
+    pub z_y: G::ScalarField,
+}
+
+/// Decryption share d = u^{x_i}, tag share w = u^{y_i}
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct DecryptionShare<G: AffineRepr> {
+    pub index: usize,
+    pub d: G,
+    pub w: G,
+    pub proof: ChaumPedersenProof<G>,
+}
+
+pub struct ThresholdAuditorKeys<G: AffineRepr> {
+    pub t: usize,
+    pub n: usize,
+    pub pk: AuditorPublicKey<G>,
+    pub key_shares: Vec<AuditorKeyShare<G>>,
+    pub verification_shares: Vec<AuditorVerificationShare<G>>,
+}
+
+/// dealer generates an auditor key whose secrets are shared t-of-n
+pub fn auditor_keygen<G: AffineRepr>(
+    t: usize,
+    n: usize,
+    rng: &mut impl Rng,
+) -> ThresholdAuditorKeys<G> {
+    let g = G::Group::rand(rng).into_affine();
+    let x = G::ScalarField::rand(rng);
+    let y = G::ScalarField::rand(rng);
+    let x_shares = generate_shares(&x, t, n, rng);
+    let y_shares = generate_shares(&y, t, n, rng);
+
+    let pk = AuditorPublicKey {
+        g,
+        h: g.mul(x).into_affine(),
+        k: g.mul(y).into_affine(),
+    };
+
+    let mut key_shares = Vec::with_capacity(n);
+    let mut verification_shares = Vec::with_capacity(n);
+    for ((index, x_share), (_, y_share)) in x_shares.into_iter().zip(y_shares) {
+        verification_shares.push(AuditorVerificationShare {
+            index,
+            h_share: g.mul(x_share).into_affine(),
+            k_share: g.mul(y_share).into_affine(),
+        });
+        key_shares.push(AuditorKeyShare {
+            index,
+            x_share,
+            y_share,
+        });
+    }
+
+    ThresholdAuditorKeys {
+        t,
+        n,
+        pk,
+        key_shares,
+        verification_shares,
+    }
+}
+
+impl<G: AffineRepr> AuditorKeyShare<G> {
+    /// auditor computes its decryption and tag shares for a ciphertext
+    pub fn decryption_share(
+        &self,
+        pk: &AuditorPublicKey<G>,
+        ciphertext: &Ciphertext<G>,
+        rng: &mut impl Rng,
+    ) -> DecryptionShare<G> {
+        let u = ciphertext.u;
+        let d = u.mul(self.x_share).into_affine();
+        let w = u.mul(self.y_share).into_affine();
+
+        let a = G::ScalarField::rand(rng);
+        let b = G::ScalarField::rand(rng);
+        let t_g_x = pk.g.mul(a).into_affine();
+        let t_u_x = u.mul(a).into_affine();
+        let t_g_y = pk.g.mul(b).into_affine();
+        let t_u_y = u.mul(b).into_affine();
+
+        let h_share = pk.g.mul(self.x_share).into_affine();
+        let k_share = pk.g.mul(self.y_share).into_affine();
+        let challenge = share_challenge(
+            self.index,
+            &[pk.g, u, h_share, k_share, d, w, t_g_x, t_u_x, t_g_y, t_u_y],
+        );
+
+        DecryptionShare {
+            index: self.index,
+            d,
+            w,
+            proof: ChaumPedersenProof {
+                t_g_x,
+                t_u_x,
+                t_g_y,
+                t_u_y,
+                z_x: a + challenge * self.x_share,
+                z_y: b + challenge * self.y_share,
+            },
+        }
+    }
+}
+
+impl<G: AffineRepr> DecryptionShare<G> {
+    /// checks the Chaum-Pedersen proof against the auditor's public share
+    pub fn verify(
+        &self,
+        pk: &AuditorPublicKey<G>,
+        vk_share: &AuditorVerificationShare<G>,
+        ciphertext: &Ciphertext<G>,
+    ) -> bool {
+        if vk_share.index != self.index {
+            return false;
+        }
+        let u = ciphertext.u;
+        let p = &self.proof;
+        let challenge = share_challenge(
+            self.index,
+            &[
+                pk.g,
+                u,
+                vk_share.h_share,
+                vk_share.k_share,
+                self.d,
+                self.w,
+                p.t_g_x,
+                p.t_u_x,
+                p.t_g_y,
+                p.t_u_y,
+            ],
+        );
+
+        pk.g.mul(p.z_x) == p.t_g_x + vk_share.h_share.mul(challenge)
+            && u.mul(p.z_x) == p.t_u_x + self.d.mul(challenge)
+            && pk.g.mul(p.z_y) == p.t_g_y + vk_share.k_share.mul(challenge)
+            && u.mul(p.z_y) == p.t_u_y + self.w.mul(challenge)
+    }
+}
+
+fn share_challenge<G: AffineRepr>(index: usize, points: &[G]) -> G::ScalarField {
+    let mut transcript = (index as u64).to_le_bytes().to_vec();
+    for p in points {
+        p.serialize_compressed(&mut transcript).unwrap();
+    }
+    HashUtil::<G::ScalarField, G>::hash_to_field(&transcript)
+}
+
+/// Verifies every share, then recovers g^m from the first t valid ones.
+/// Fails with the index of the first auditor whose share does not verify.
+pub fn combine_decryption_shares<G: AffineRepr>(
+    pk: &AuditorPublicKey<G>,
+    verification_shares: &[AuditorVerificationShare<G>],
+    ciphertext: &Ciphertext<G>,
+    label: &[u8],
+    shares: &[DecryptionShare<G>],
+    threshold: usize,
+) -> Result<G, DecryptionError> {
+    if shares.len() < threshold {
+        return Err(DecryptionError::InsufficientShares {
+            needed: threshold,
+            got: shares.len(),
+        });
+    }
+
+    let mut seen = HashSet::new();
+    for share in shares {
+        if !seen.insert(share.index) {
+            return Err(DecryptionError::DuplicateShare(share.index));
+        }
+        let vk_share = verification_shares
+            .iter()
+            .find(|vk| vk.index == share.index)
+            .ok_or(DecryptionError::UnknownAuditor(share.index))?;
+        if !share.verify(pk, vk_share, ciphertext) {
+            return Err(DecryptionError::InvalidShare(share.index));
+        }
+    }
+
+    let shares = &shares[..threshold];
+    let indices: Vec<usize> = shares.iter().map(|s| s.index).collect();
+    let lagrange: Vec<G::ScalarField> = indices
+        .iter()
+        .map(|&i| compute_lagrange_coefficient(&indices, i))
+        .collect();
+    let ds: Vec<G> = shares.iter().map(|s| s.d).collect();
+    let ws: Vec<G> = shares.iter().map(|s| s.w).collect();
+    let u_x = G::Group::msm_unchecked(&ds, &lagrange);
+    let u_y = G::Group::msm_unchecked(&ws, &lagrange);
+
+    // v = u^y g^t only if the ciphertext was produced under this label
+    let t = VerifiableEncryption::tag(&ciphertext.u, &ciphertext.e, label);
+    if (u_y + pk.g.mul(t)).into_affine() != ciphertext.v {
+        return Err(DecryptionError::LabelMismatch);
+    }
+
+    Ok((ciphertext.e.into_group() - u_x).into_affine())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ec::pairing::Pairing;
+    use ark_std::test_rng;
+    use std::ops::Mul;
+
+    fn test_threshold_decryption<E: Pairing>() {
+        let mut rng = test_rng();
+        let (t, n) = (3, 5);
+        let keys = auditor_keygen::<E::G1Affine>(t, n, &mut rng);
+        let m = E::ScalarField::rand(&mut rng);
+        let label = b"verifier session";
+        let (ct, _) = VerifiableEncryption::encrypt(&keys.pk, &m, label, &mut rng);
+
+        let shares: Vec<DecryptionShare<E::G1Affine>> = keys.key_shares[1..1 + t]
+            .iter()
+            .map(|ks| ks.decryption_share(&keys.pk, &ct, &mut rng))
+            .collect();
+        let plaintext =
+            combine_decryption_shares(&keys.pk, &keys.verification_shares, &ct, label, &shares, t)
+                .unwrap();
+        assert_eq!(plaintext, keys.pk.g.mul(m).into_affine());
+
+        assert!(matches!(
+            combine_decryption_shares(
+                &keys.pk,
+                &keys.verification_shares,
+                &ct,
+                b"other session",
+                &shares,
+                t
+            ),
+            Err(DecryptionError::LabelMismatch)
+        ));
+        assert!(matches!(
+            combine_decryption_shares(
+                &keys.pk,
+                &keys.verification_shares,
+                &ct,
+                label,
+                &shares[..t - 1],
+                t
+            ),
+            Err(DecryptionError::InsufficientShares { .. })
+        ));
+    }
+
+    fn test_threshold_decryption_identifies_bad_share<E: Pairing>() {
+        let mut rng = test_rng();
+        let (t, n) = (2, 3);
+        let keys = auditor_keygen::<E::G1Affine>(t, n, &mut rng);
+        let m = E::ScalarField::rand(&mut rng);
+        let (ct, _) = VerifiableEncryption::encrypt(&keys.pk, &m, b"", &mut rng);
+
+        let mut shares: Vec<DecryptionShare<E::G1Affine>> = keys
+            .key_shares
+            .iter()
+            .map(|ks| ks.decryption_share(&keys.pk, &ct, &mut rng))
+            .collect();
+        // auditor 2 sends a share computed with a wrong key
+        shares[1].d = (shares[1].d + keys.pk.g).into_affine();
+
+        let result =
+            combine_decryption_shares(&keys.pk, &keys.verification_shares, &ct, b"", &shares, t);
+        assert!(matches!(result, Err(DecryptionError::InvalidShare(2))));
+    }
+
+    utils::curve_tests!(
+        test_threshold_decryption,
+        test_threshold_decryption_identifies_bad_share
+    );
+}
@@ -1,5 +1,5 @@
-use crate::commitment::batch_verify;
-use crate::errors::{CommitmentError, SignatureError};
+use crate::credential::IssuanceRequest;
+use crate::errors::SignatureError;
 use crate::keygen::VerificationKeyShare;
 use crate::signature::PartialSignature;
 use crate::symmetric_commitment::SymmetricCommitmentKey;
@@ -17,30 +17,32 @@ impl User {
     pub fn verify_signature_share<E: Pairing>(
         commitment_key: &SymmetricCommitmentKey<E>,
         vk_share: &VerificationKeyShare<E>,
-        commitments: &[E::G1Affine],
-        commitment_proofs: &[Vec<u8>],
+        request: &IssuanceRequest<E>,
         sig_share: &PartialSignature<E>,
         rng: &mut impl Rng,
     ) -> Result<bool, SignatureError> {
-        // 1. First verify the ZKPs for each commitment
-        // We can use the optimised version of batch_verify
-        // let timer_start = std::time::Instant::now();
-        let valid = batch_verify::<E>(commitment_proofs, rng)?;
-        if !valid {
-            return Err(CommitmentError::BatchVerifyError.into());
-        }
-        // let elapsed = timer_start.elapsed();
-
-        // println!("Batch verify time: {:?}", elapsed);
-
-        // for (_, proof) in commitments.iter().zip(commitment_proofs.iter()) {
-        //     let is_valid =
-        //         Commitment::<E>::verify(proof).map_err(|e| SignatureError::CommitmentError(e))?;
+        // 1. First verify the ZKPs for the request as a unit
+        request.verify(commitment_key, rng)?;
+
+        Ok(Self::verify_share_equation(
+            commitment_key,
+            vk_share,
+            request,
+            sig_share,
+        ))
+    }
 
-        //     if !is_valid {
-        //         return Ok(false);
-        //     }
-        // }
+    fn verify_share_equation<E: Pairing>(
+        commitment_key: &SymmetricCommitmentKey<E>,
+        vk_share: &VerificationKeyShare<E>,
+        request: &IssuanceRequest<E>,
+        sig_share: &PartialSignature<E>,
+    ) -> bool {
+        // a share over another h is not for this request
+        if sig_share.h != request.h {
+            return false;
+        }
+        let commitments = &request.commitments;
 
         // 2. Verify the signature share using the pairing equation
         // e([σ*]_i,2, g̃) = e(h, g̃^[x]_i) · ∏_{k∈[ℓ]} e(cm_k, g̃^[y_k]_i)
@@ -62,9 +64,7 @@ impl User {
         }
 
         // Verify the pairing equation
-        let is_valid_signature = verify_pairing_equation::<E>(&pairs, None);
-
-        Ok(is_valid_signature)
+        verify_pairing_equation::<E>(&pairs, None)
     }
 
     /// Process signature shares - verify and collect valid ones
@@ -72,11 +72,13 @@ impl User {
     pub fn process_signature_shares<E: Pairing>(
         commitment_key: &SymmetricCommitmentKey<E>,
         vk_shares: &[VerificationKeyShare<E>],
-        commitments: &[E::G1Affine],
-        commitment_proofs: &[Vec<u8>],
+        request: &IssuanceRequest<E>,
         signature_shares: &[(usize, PartialSignature<E>)],
         threshold: usize,
+        rng: &mut impl Rng,
     ) -> Result<Vec<(usize, PartialSignature<E>)>, SignatureError> {
+        // the request is the same for every share, its proofs are checked once
+        request.verify(commitment_key, rng)?;
         let mut valid_shares = Vec::new();
 
         for (i, sig_share) in signature_shares {
@@ -91,14 +93,8 @@ impl User {
                     )))?;
 
             // Verify this signature share
-            let is_valid = Self::verify_signature_share(
-                commitment_key,
-                vk_share,
-                commitments,
-                commitment_proofs,
-                sig_share,
-                &mut ark_std::test_rng(),
-            )?;
+            let is_valid =
+                Self::verify_share_equation(commitment_key, vk_share, request, sig_share);
 
             if is_valid {
                 valid_shares.push((*i, sig_share.clone()));
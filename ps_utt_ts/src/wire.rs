@@ -0,0 +1,138 @@
+use crate::credential::{Credential, IssuanceRequest, ShowCredential};
+use crate::keygen::{SecretKeyShare, VerificationKey, VerificationKeyShare};
+use crate::signature::{PartialSignature, ThresholdSignature};
+use crate::symmetric_commitment::SymmetricCommitmentKey;
+use utils::impl_envelope;
+
+impl_envelope!(SymmetricCommitmentKey, PsUttTs, PublicParams, |ck| ck
+    .ck
+    .len());
+impl_envelope!(VerificationKey, PsUttTs, PublicKey);
+impl_envelope!(VerificationKeyShare, PsUttTs, PublicKeyShare, |vk| vk
+    .g_tilde_y_shares
+    .len());
+impl_envelope!(IssuanceRequest, PsUttTs, IssuanceRequest, |req| req
+    .commitments
+    .len());
+impl_envelope!(SecretKeyShare, PsUttTs, SecretKeyShare, |sk| sk
+    .y_shares
+    .len());
+impl_envelope!(PartialSignature, PsUttTs, IssuanceResponse);
+impl_envelope!(ThresholdSignature, PsUttTs, Signature);
+impl_envelope!(Credential, PsUttTs, Credential, |cred| cred
+    .get_messages()
+    .len());
+impl_envelope!(ShowCredential, PsUttTs, Presentation);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keygen::keygen;
+    use crate::protocol::{UserProtocol, VerifierProtocol};
+    use crate::signer::Signer;
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ff::UniformRand;
+    use ark_std::test_rng;
+    use utils::envelope::{assert_golden, Envelope, Header};
+
+    fn golden(name: &str) -> String {
+        format!("{}/golden/{}.hex", env!("CARGO_MANIFEST_DIR"), name)
+    }
+
+    #[test]
+    fn test_ps_utt_ts_wire_golden() {
+        let mut rng = test_rng();
+        let (ck, vk, ts_keys) = keygen::<Bls12_381>(2, 3, 3, &mut rng);
+        let signers: Vec<_> = ts_keys
+            .sk_shares
+            .iter()
+            .zip(ts_keys.vk_shares.iter())
+            .map(|(sk_share, vk_share)| Signer::new(&ck, sk_share, vk_share))
+            .collect();
+        let attributes: Vec<Fr> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+        // the parallel request and signing paths draw from thread_rng, golden bytes need the sequential ones
+        let mut credential = Credential::new(ck.clone(), Some(&attributes), &mut rng);
+        let request = credential.compute_commitments_per_m_old(&mut rng).unwrap();
+        let shares: Vec<_> = signers[..2]
+            .iter()
+            .map(|signer| {
+                let share = signer.sign_share(&request, &mut rng).unwrap();
+                (share.party_index, share)
+            })
+            .collect();
+        let signature = UserProtocol::aggregate_shares(
+            &ck,
+            &shares,
+            credential.get_blinding_factors(),
+            2,
+            &request.h,
+        )
+        .unwrap();
+        credential.attach_signature(signature.clone());
+        let presentation: ShowCredential<Bls12_381> =
+            UserProtocol::show(&credential, &mut rng).unwrap().into();
+
+        assert_golden(&golden("commitment_key"), &ck.to_envelope());
+        assert_golden(&golden("verification_key"), &vk.to_envelope());
+        assert_golden(
+            &golden("verification_key_share"),
+            &ts_keys.vk_shares[0].to_envelope(),
+        );
+        assert_golden(&golden("issuance_request"), &request.to_envelope());
+        assert_golden(&golden("partial_signature"), &shares[0].1.to_envelope());
+        assert_golden(&golden("signature"), &signature.to_envelope());
+        assert_golden(&golden("credential"), &credential.to_envelope());
+        assert_golden(&golden("presentation"), &presentation.to_envelope());
+
+        let decoded =
+            ShowCredential::<Bls12_381>::from_envelope(&presentation.to_envelope()).unwrap();
+        assert!(VerifierProtocol::verify(
+            &ck,
+            &vk,
+            &decoded.cm,
+            &decoded.cm_tilde,
+            &decoded.randomized_signature,
+            &decoded.proof,
+        )
+        .unwrap());
+        // a stored credential can be shown again after decoding
+        let restored = Credential::<Bls12_381>::from_envelope(&credential.to_envelope()).unwrap();
+        assert_eq!(restored.get_messages(), &attributes);
+        assert!(UserProtocol::show(&restored, &mut rng).is_ok());
+        let (header, _) = Header::parse(&request.to_envelope()).unwrap();
+        assert_eq!(header.attribute_count, 3);
+    }
+
+    #[test]
+    fn test_ps_utt_ts_json() {
+        let mut rng = test_rng();
+        let (ck, vk, ts_keys) = keygen::<Bls12_381>(2, 3, 3, &mut rng);
+        let signer = Signer::new(&ck, &ts_keys.sk_shares[0], &ts_keys.vk_shares[0]);
+        let attributes: Vec<Fr> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+        let mut credential = Credential::new(ck.clone(), Some(&attributes), &mut rng);
+        let request = credential.compute_commitments_per_m_old(&mut rng).unwrap();
+        let share = signer.sign_share(&request, &mut rng).unwrap();
+
+        let vk_share: VerificationKeyShare<Bls12_381> =
+            serde_json::from_str(&serde_json::to_string(&ts_keys.vk_shares[0]).unwrap()).unwrap();
+        assert_eq!(vk_share.index, ts_keys.vk_shares[0].index);
+        assert_eq!(
+            vk_share.g_tilde_y_shares,
+            ts_keys.vk_shares[0].g_tilde_y_shares
+        );
+        let decoded: VerificationKey<Bls12_381> =
+            serde_json::from_str(&serde_json::to_string(&vk).unwrap()).unwrap();
+        assert_eq!(decoded.g_tilde_x, vk.g_tilde_x);
+        let decoded: SymmetricCommitmentKey<Bls12_381> =
+            serde_json::from_str(&serde_json::to_string(&ck).unwrap()).unwrap();
+        assert_eq!(decoded.ck_tilde, ck.ck_tilde);
+
+        let json = serde_json::to_value(&share).unwrap();
+        let decoded: PartialSignature<Bls12_381> = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(decoded.party_index, share.party_index);
+        assert_eq!(decoded.sigma, share.sigma);
+        let mut tampered = json;
+        tampered["sigma"] = serde_json::Value::String("AAAA".into());
+        assert!(serde_json::from_value::<PartialSignature<Bls12_381>>(tampered).is_err());
+    }
+}
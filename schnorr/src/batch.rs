@@ -0,0 +1,339 @@
+// Batch verification of Schnorr proofs with different bases and statement sizes in G1, G2 and GT.
+// Every claim is scaled by a small random exponent and the claims of one group are folded into a
+// single check, a batch that fails is re-checked one proof at a time to name the bad one.
+use crate::schnorr::SchnorrProtocol;
+use crate::schnorr_pairing::SchnorrProtocolPairing;
+use ark_ec::pairing::{Pairing, PairingOutput};
+use ark_ec::{AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_ff::Zero;
+use ark_std::ops::{Mul, Neg};
+use ark_std::rand::Rng;
+use thiserror::Error;
+use utils::pairing::batch_randomizer;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum BatchError {
+    #[error("Proof {0} has a different number of bases and responses")]
+    LengthMismatch(usize),
+    #[error("Proof {0} failed verification")]
+    InvalidProof(usize),
+}
+
+/// Claim ∏ bases_i^{responses_i} = commitment · statement^challenge in G1 or G2
+#[derive(Clone, Debug)]
+pub struct SchnorrClaim<G: AffineRepr> {
+    pub bases: Vec<G>,
+    pub statement: G,
+    pub commitment: G,
+    pub challenge: G::ScalarField,
+    pub responses: Vec<G::ScalarField>,
+}
+
+/// Claim ∏ e(bases_g1_i, bases_g2_i)^{responses_i} = commitment · statement^challenge in GT
+#[derive(Clone, Debug)]
+pub struct PairingClaim<E: Pairing> {
+    pub bases_g1: Vec<E::G1Affine>,
+    pub bases_g2: Vec<E::G2Affine>,
+    pub statement: PairingOutput<E>,
+    pub commitment: PairingOutput<E>,
+    pub challenge: E::ScalarField,
+    pub responses: Vec<E::ScalarField>,
+}
+
+impl<G: AffineRepr> SchnorrClaim<G> {
+    fn is_well_formed(&self) -> bool {
+        self.bases.len() == self.responses.len()
+    }
+
+    fn verify(&self) -> bool {
+        SchnorrProtocol::verify_schnorr(
+            &self.bases,
+            &self.statement,
+            &self.commitment,
+            &self.responses,
+            &self.challenge,
+        )
+    }
+}
+
+impl<E: Pairing> PairingClaim<E> {
+    fn is_well_formed(&self) -> bool {
+        self.bases_g1.len() == self.responses.len() && self.bases_g2.len() == self.responses.len()
+    }
+
+    fn verify(&self) -> bool {
+        SchnorrProtocolPairing::verify(
+            &self.statement,
+            &self.commitment,
+            &self.challenge,
+            &self.bases_g1,
+            &self.bases_g2,
+            &self.responses,
+        )
+    }
+}
+
+/// Collects Schnorr claims over the groups of one pairing and verifies them together. Each
+/// claim gets the index it was added at, which is the index errors report.
+pub struct SchnorrBatch<E: Pairing> {
+    g1: Vec<(usize, SchnorrClaim<E::G1Affine>)>,
+    g2: Vec<(usize, SchnorrClaim<E::G2Affine>)>,
+    gt: Vec<(usize, PairingClaim<E>)>,
+}
+
+impl<E: Pairing> Default for SchnorrBatch<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E: Pairing> SchnorrBatch<E> {
+    pub fn new() -> Self {
+        Self {
+            g1: Vec::new(),
+            g2: Vec::new(),
+            gt: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.g1.len() + self.g2.len() + self.gt.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Adds a claim in G1 and returns its index in the batch
+    pub fn add_g1(&mut self, claim: SchnorrClaim<E::G1Affine>) -> usize {
+        let index = self.len();
+        self.g1.push((index, claim));
+        index
+    }
+
+    /// Adds a claim in G2 and returns its index in the batch
+    pub fn add_g2(&mut self, claim: SchnorrClaim<E::G2Affine>) -> usize {
+        let index = self.len();
+        self.g2.push((index, claim));
+        index
+    }
+
+    /// Adds a claim in GT and returns its index in the batch
+    pub fn add_gt(&mut self, claim: PairingClaim<E>) -> usize {
+        let index = self.len();
+        self.gt.push((index, claim));
+        index
+    }
+
+    /// Verifies every claim with one MSM per source group and one multi-pairing for GT.
+    /// On failure the lowest index of a malformed or invalid claim is returned.
+    pub fn verify<R: Rng>(&self, rng: &mut R) -> Result<(), BatchError> {
+        // msm_unchecked truncates to the shorter input, shapes are checked before combining
+        let malformed = self
+            .g1
+            .iter()
+            .filter(|(_, c)| !c.is_well_formed())
+            .map(|(i, _)| *i)
+            .chain(
+                self.g2
+                    .iter()
+                    .filter(|(_, c)| !c.is_well_formed())
+                    .map(|(i, _)| *i),
+            )
+            .chain(
+                self.gt
+                    .iter()
+                    .filter(|(_, c)| !c.is_well_formed())
+                    .map(|(i, _)| *i),
+            )
+            .min();
+        if let Some(index) = malformed {
+            return Err(BatchError::LengthMismatch(index));
+        }
+
+        if self.check(rng) {
+            return Ok(());
+        }
+
+        // the batch equation is a combination of the individual ones, some claim must fail
+        let invalid = self
+            .g1
+            .iter()
+            .filter(|(_, c)| !c.verify())
+            .map(|(i, _)| *i)
+            .chain(self.g2.iter().filter(|(_, c)| !c.verify()).map(|(i, _)| *i))
+            .chain(self.gt.iter().filter(|(_, c)| !c.verify()).map(|(i, _)| *i))
+            .min();
+        match invalid {
+            Some(index) => Err(BatchError::InvalidProof(index)),
+            None => Ok(()),
+        }
+    }
+
+    /// The combined check alone, for callers that isolate failures themselves. Malformed
+    /// claims fail it.
+    pub fn check<R: Rng>(&self, rng: &mut R) -> bool {
+        self.g1.iter().all(|(_, c)| c.is_well_formed())
+            && self.g2.iter().all(|(_, c)| c.is_well_formed())
+            && self.gt.iter().all(|(_, c)| c.is_well_formed())
+            && combine_group(&self.g1, rng)
+            && combine_group(&self.g2, rng)
+            && self.combine_gt(rng)
+    }
+
+    /// ∏_k ∏_i e(bases_g1_ki^{ρ_k z_ki}, bases_g2_ki) = ∏_k (T_k · X_k^{c_k})^{ρ_k}
+    fn combine_gt<R: Rng>(&self, rng: &mut R) -> bool {
+        if self.gt.is_empty() {
+            return true;
+        }
+
+        let mut scaled_g1 = Vec::new();
+        let mut bases_g2 = Vec::new();
+        let mut rhs_bases = Vec::with_capacity(2 * self.gt.len());
+        let mut rhs_scalars = Vec::with_capacity(2 * self.gt.len());
+        for (_, claim) in &self.gt {
+            let rho: E::ScalarField = batch_randomizer(rng);
+            for ((g1, g2), z) in claim
+                .bases_g1
+                .iter()
+                .zip(claim.bases_g2.iter())
+                .zip(claim.responses.iter())
+            {
+                scaled_g1.push(g1.mul(rho * z));
+                bases_g2.push(*g2);
+            }
+            rhs_bases.push(claim.commitment);
+            rhs_scalars.push(rho);
+            rhs_bases.push(claim.statement);
+            rhs_scalars.push(rho * claim.challenge);
+        }
+
+        let scaled_g1 = E::G1::normalize_batch(&scaled_g1);
+        let lhs = E::multi_pairing(scaled_g1, bases_g2);
+        let rhs = PairingOutput::<E>::msm_unchecked(&rhs_bases, &rhs_scalars);
+        lhs == rhs
+    }
+}
+
+/// Σ_k ρ_k (Σ_i z_ki·B_ki − T_k − c_k·X_k) = 0 as a single MSM
+fn combine_group<G: AffineRepr, R: Rng>(claims: &[(usize, SchnorrClaim<G>)], rng: &mut R) -> bool {
+    if claims.is_empty() {
+        return true;
+    }
+
+    let size: usize = claims.iter().map(|(_, c)| c.bases.len() + 2).sum();
+    let mut bases = Vec::with_capacity(size);
+    let mut scalars = Vec::with_capacity(size);
+    for (_, claim) in claims {
+        let rho: G::ScalarField = batch_randomizer(rng);
+        for (base, z) in claim.bases.iter().zip(claim.responses.iter()) {
+            bases.push(*base);
+            scalars.push(rho * z);
+        }
+        bases.push(claim.commitment);
+        scalars.push(rho.neg());
+        bases.push(claim.statement);
+        scalars.push((rho * claim.challenge).neg());
+    }
+
+    G::Group::msm_unchecked(&bases, &scalars).is_zero()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schnorr_pairing::compute_gt_from_g1_g2_scalars;
+    use ark_ff::UniformRand;
+    use ark_std::test_rng;
+
+    fn group_claim<G: AffineRepr, R: Rng>(n: usize, rng: &mut R) -> SchnorrClaim<G> {
+        let bases: Vec<G> = (0..n).map(|_| G::Group::rand(rng).into_affine()).collect();
+        let witnesses: Vec<G::ScalarField> = (0..n).map(|_| G::ScalarField::rand(rng)).collect();
+        let statement = G::Group::msm_unchecked(&bases, &witnesses).into_affine();
+        let commitment = SchnorrProtocol::commit(&bases, rng);
+        let challenge = G::ScalarField::rand(rng);
+        let responses = SchnorrProtocol::prove(&commitment, &witnesses, &challenge);
+        SchnorrClaim {
+            bases,
+            statement,
+            commitment: commitment.commited_blindings,
+            challenge,
+            responses: responses.0,
+        }
+    }
+
+    fn gt_claim<E: Pairing, R: Rng>(n: usize, rng: &mut R) -> PairingClaim<E> {
+        let bases_g1: Vec<E::G1Affine> = (0..n).map(|_| E::G1::rand(rng).into_affine()).collect();
+        let bases_g2: Vec<E::G2Affine> = (0..n).map(|_| E::G2::rand(rng).into_affine()).collect();
+        let witnesses: Vec<E::ScalarField> = (0..n).map(|_| E::ScalarField::rand(rng)).collect();
+        let statement = compute_gt_from_g1_g2_scalars(&bases_g1, &bases_g2, &witnesses);
+        let commitment = SchnorrProtocolPairing::commit::<E>(&bases_g1, &bases_g2, rng);
+        let challenge = E::ScalarField::rand(rng);
+        let responses = SchnorrProtocolPairing::prove(&commitment, &witnesses, &challenge);
+        PairingClaim {
+            bases_g1,
+            bases_g2,
+            statement,
+            commitment: commitment.schnorr_commitment,
+            challenge,
+            responses: responses.0,
+        }
+    }
+
+    fn mixed_batch<E: Pairing, R: Rng>(rng: &mut R) -> SchnorrBatch<E> {
+        let mut batch = SchnorrBatch::<E>::new();
+        assert_eq!(batch.add_g1(group_claim(1, rng)), 0);
+        assert_eq!(batch.add_g2(group_claim(3, rng)), 1);
+        assert_eq!(batch.add_gt(gt_claim(2, rng)), 2);
+        assert_eq!(batch.add_g1(group_claim(5, rng)), 3);
+        assert_eq!(batch.add_gt(gt_claim(1, rng)), 4);
+        assert_eq!(batch.add_g2(group_claim(2, rng)), 5);
+        batch
+    }
+
+    fn test_batch_mixed_groups<E: Pairing>() {
+        let mut rng = test_rng();
+        assert_eq!(SchnorrBatch::<E>::new().verify(&mut rng), Ok(()));
+
+        let batch = mixed_batch::<E, _>(&mut rng);
+        assert_eq!(batch.len(), 6);
+        assert_eq!(batch.verify(&mut rng), Ok(()));
+    }
+
+    fn test_batch_reports_invalid_proof<E: Pairing>() {
+        let mut rng = test_rng();
+
+        let mut batch = mixed_batch::<E, _>(&mut rng);
+        batch.g2[1].1.responses[0] += E::ScalarField::from(1u64);
+        assert_eq!(batch.verify(&mut rng), Err(BatchError::InvalidProof(5)));
+
+        // the lowest failing index is reported across groups
+        batch.gt[0].1.challenge += E::ScalarField::from(1u64);
+        assert_eq!(batch.verify(&mut rng), Err(BatchError::InvalidProof(2)));
+
+        // a statement that only cancels against another claim's error is still caught
+        let mut batch = mixed_batch::<E, _>(&mut rng);
+        let shift = E::G1::rand(&mut rng);
+        batch.g1[0].1.commitment = (batch.g1[0].1.commitment + shift).into_affine();
+        batch.g1[1].1.commitment = (batch.g1[1].1.commitment.into_group() - shift).into_affine();
+        assert_eq!(batch.verify(&mut rng), Err(BatchError::InvalidProof(0)));
+    }
+
+    fn test_batch_length_mismatch<E: Pairing>() {
+        let mut rng = test_rng();
+
+        let mut batch = mixed_batch::<E, _>(&mut rng);
+        batch.g1[1].1.responses.pop();
+        assert_eq!(batch.verify(&mut rng), Err(BatchError::LengthMismatch(3)));
+
+        let mut batch = mixed_batch::<E, _>(&mut rng);
+        batch.gt[1].1.bases_g2.push(E::G2Affine::generator());
+        assert_eq!(batch.verify(&mut rng), Err(BatchError::LengthMismatch(4)));
+    }
+
+    utils::curve_tests!(
+        test_batch_mixed_groups,
+        test_batch_reports_invalid_proof,
+        test_batch_length_mismatch
+    );
+}
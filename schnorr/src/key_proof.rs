@@ -0,0 +1,304 @@
+// Proofs that an issuer key is well formed. The issuer shows it knows every exponent w_i behind
+// its key and, where an exponent appears in both source groups, that g1^{w_i} and g2^{w_i} use
+// the same one: each pair of Schnorr claims shares its response. A key whose G1 and G2 halves
+// disagree could sign commitments only for chosen users, so holders check the proof before they
+// request a credential.
+use crate::batch::{BatchError, SchnorrBatch, SchnorrClaim};
+use ark_ec::pairing::Pairing;
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::ops::{Mul, Range};
+use ark_std::rand::Rng;
+use ark_std::UniformRand;
+use thiserror::Error;
+use utils::hash::G1HashUtil;
+use zeroize::Zeroizing;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum KeyProofError {
+    #[error("Key proof {0} does not match the shape of its key")]
+    LengthMismatch(usize),
+    #[error("Key {0} contains the identity")]
+    IdentityElement(usize),
+    #[error("Key proof {0} failed verification")]
+    InvalidProof(usize),
+}
+
+/// Public part of a key: g1^{w_i} for the first `images_g1.len()` exponents and g2^{w_i} for
+/// the first `images_g2.len()`, every exponent has an image in at least one group
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct KeyStatement<E: Pairing> {
+    pub g1: E::G1Affine,
+    pub images_g1: Vec<E::G1Affine>,
+    pub g2: E::G2Affine,
+    pub images_g2: Vec<E::G2Affine>,
+}
+
+/// Fiat-Shamir proof of knowledge of the exponents of a `KeyStatement`, the verifier recomputes
+/// the challenge from the statement and the commitments
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct KeyProof<E: Pairing> {
+    pub commitments_g1: Vec<E::G1Affine>,
+    pub commitments_g2: Vec<E::G2Affine>,
+    pub responses: Vec<E::ScalarField>,
+}
+
+impl<E: Pairing> KeyStatement<E> {
+    /// Number of exponents behind the key
+    pub fn len(&self) -> usize {
+        self.images_g1.len().max(self.images_g2.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn has_identity(&self) -> bool {
+        self.images_g1.iter().any(|p| p.is_zero()) || self.images_g2.iter().any(|q| q.is_zero())
+    }
+}
+
+impl<E: Pairing> KeyProof<E> {
+    /// Proves knowledge of `witnesses`, the exponents of `statement` in order. `domain` names
+    /// the scheme so a proof for one kind of key is not accepted for another.
+    pub fn prove<R: Rng>(
+        domain: &[u8],
+        statement: &KeyStatement<E>,
+        witnesses: &[E::ScalarField],
+        rng: &mut R,
+    ) -> Self {
+        assert_eq!(
+            witnesses.len(),
+            statement.len(),
+            "one witness per key exponent"
+        );
+        let blindings: Zeroizing<Vec<E::ScalarField>> = Zeroizing::new(
+            (0..witnesses.len())
+                .map(|_| E::ScalarField::rand(rng))
+                .collect(),
+        );
+
+        let commitments_g1 = E::G1::normalize_batch(
+            &blindings[..statement.images_g1.len()]
+                .iter()
+                .map(|r| statement.g1.mul(r))
+                .collect::<Vec<_>>(),
+        );
+        let commitments_g2 = E::G2::normalize_batch(
+            &blindings[..statement.images_g2.len()]
+                .iter()
+                .map(|r| statement.g2.mul(r))
+                .collect::<Vec<_>>(),
+        );
+
+        let challenge = Self::challenge(domain, statement, &commitments_g1, &commitments_g2);
+        let responses = blindings
+            .iter()
+            .zip(witnesses.iter())
+            .map(|(r, w)| *r + challenge * w)
+            .collect();
+
+        Self {
+            commitments_g1,
+            commitments_g2,
+            responses,
+        }
+    }
+
+    /// Verifies the proof against `statement`, both groups are checked in one batch
+    pub fn verify<R: Rng>(
+        &self,
+        domain: &[u8],
+        statement: &KeyStatement<E>,
+        rng: &mut R,
+    ) -> Result<(), KeyProofError> {
+        Self::verify_batch(domain, &[(statement, self)], rng)
+    }
+
+    /// Verifies many key proofs with one MSM per source group. On failure the error carries
+    /// the position of the first bad key in `proofs`.
+    pub fn verify_batch<R: Rng>(
+        domain: &[u8],
+        proofs: &[(&KeyStatement<E>, &KeyProof<E>)],
+        rng: &mut R,
+    ) -> Result<(), KeyProofError> {
+        let mut batch = SchnorrBatch::<E>::new();
+        let mut ranges = Vec::with_capacity(proofs.len());
+        for (k, (statement, proof)) in proofs.iter().enumerate() {
+            if statement.has_identity() {
+                return Err(KeyProofError::IdentityElement(k));
+            }
+            ranges.push(
+                proof
+                    .add_to_batch(domain, statement, &mut batch)
+                    .ok_or(KeyProofError::LengthMismatch(k))?,
+            );
+        }
+
+        batch.verify(rng).map_err(|e| {
+            let claim = match e {
+                BatchError::LengthMismatch(i) | BatchError::InvalidProof(i) => i,
+            };
+            let k = ranges.iter().position(|r| r.contains(&claim)).unwrap_or(0);
+            KeyProofError::InvalidProof(k)
+        })
+    }
+
+    /// Adds one claim per key element to `batch`, for callers that verify key proofs together
+    /// with other Schnorr proofs. Returns the batch indices of the claims, or `None` if the
+    /// proof does not have the shape of the statement.
+    pub fn add_to_batch(
+        &self,
+        domain: &[u8],
+        statement: &KeyStatement<E>,
+        batch: &mut SchnorrBatch<E>,
+    ) -> Option<Range<usize>> {
+        if self.commitments_g1.len() != statement.images_g1.len()
+            || self.commitments_g2.len() != statement.images_g2.len()
+            || self.responses.len() != statement.len()
+        {
+            return None;
+        }
+
+        // the challenge is recomputed, a proof made for another key or domain fails here
+        let challenge = Self::challenge(
+            domain,
+            statement,
+            &self.commitments_g1,
+            &self.commitments_g2,
+        );
+        let start = batch.len();
+        for ((image, commitment), z) in statement
+            .images_g1
+            .iter()
+            .zip(self.commitments_g1.iter())
+            .zip(self.responses.iter())
+        {
+            batch.add_g1(SchnorrClaim {
+                bases: vec![statement.g1],
+                statement: *image,
+                commitment: *commitment,
+                challenge,
+                responses: vec![*z],
+            });
+        }
+        for ((image, commitment), z) in statement
+            .images_g2
+            .iter()
+            .zip(self.commitments_g2.iter())
+            .zip(self.responses.iter())
+        {
+            batch.add_g2(SchnorrClaim {
+                bases: vec![statement.g2],
+                statement: *image,
+                commitment: *commitment,
+                challenge,
+                responses: vec![*z],
+            });
+        }
+        Some(start..batch.len())
+    }
+
+    fn challenge(
+        domain: &[u8],
+        statement: &KeyStatement<E>,
+        commitments_g1: &[E::G1Affine],
+        commitments_g2: &[E::G2Affine],
+    ) -> E::ScalarField {
+        let mut transcript = Vec::new();
+        transcript.extend_from_slice(&(domain.len() as u64).to_le_bytes());
+        transcript.extend_from_slice(domain);
+        statement.serialize_compressed(&mut transcript).unwrap();
+        commitments_g1
+            .serialize_compressed(&mut transcript)
+            .unwrap();
+        commitments_g2
+            .serialize_compressed(&mut transcript)
+            .unwrap();
+        G1HashUtil::<E>::hash_to_field(&transcript)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_std::test_rng;
+
+    fn key<E: Pairing, R: Rng>(
+        paired: usize,
+        n: usize,
+        rng: &mut R,
+    ) -> (KeyStatement<E>, Vec<E::ScalarField>) {
+        let g1 = E::G1::rand(rng).into_affine();
+        let g2 = E::G2::rand(rng).into_affine();
+        let witnesses: Vec<E::ScalarField> = (0..n).map(|_| E::ScalarField::rand(rng)).collect();
+        let statement = KeyStatement {
+            g1,
+            images_g1: witnesses[..paired]
+                .iter()
+                .map(|w| g1.mul(w).into_affine())
+                .collect(),
+            g2,
+            images_g2: witnesses.iter().map(|w| g2.mul(w).into_affine()).collect(),
+        };
+        (statement, witnesses)
+    }
+
+    fn test_key_proof<E: Pairing>() {
+        let mut rng = test_rng();
+        let (statement, witnesses) = key::<E, _>(3, 4, &mut rng);
+        let proof = KeyProof::prove(b"test-key", &statement, &witnesses, &mut rng);
+        assert!(proof.verify(b"test-key", &statement, &mut rng).is_ok());
+
+        // bound to the domain
+        assert_eq!(
+            proof.verify(b"other-key", &statement, &mut rng),
+            Err(KeyProofError::InvalidProof(0))
+        );
+
+        // a G1 element with an exponent other than its G2 partner is caught
+        let mut malformed = statement.clone();
+        malformed.images_g1[1] = (malformed.images_g1[1] + statement.g1).into_affine();
+        let proof = KeyProof::prove(b"test-key", &malformed, &witnesses, &mut rng);
+        assert_eq!(
+            proof.verify(b"test-key", &malformed, &mut rng),
+            Err(KeyProofError::InvalidProof(0))
+        );
+
+        let mut identity = statement.clone();
+        identity.images_g2[3] = E::G2Affine::zero();
+        assert_eq!(
+            proof.verify(b"test-key", &identity, &mut rng),
+            Err(KeyProofError::IdentityElement(0))
+        );
+    }
+
+    fn test_key_proof_batch<E: Pairing>() {
+        let mut rng = test_rng();
+        let keys: Vec<(KeyStatement<E>, Vec<E::ScalarField>)> =
+            (0..4).map(|k| key(k, 3, &mut rng)).collect();
+        let mut proofs: Vec<KeyProof<E>> = keys
+            .iter()
+            .map(|(s, w)| KeyProof::prove(b"test-key", s, w, &mut rng))
+            .collect();
+
+        let refs: Vec<_> = keys.iter().map(|(s, _)| s).zip(proofs.iter()).collect();
+        assert!(KeyProof::verify_batch(b"test-key", &refs, &mut rng).is_ok());
+
+        proofs[2].responses[1] += E::ScalarField::from(1u64);
+        let refs: Vec<_> = keys.iter().map(|(s, _)| s).zip(proofs.iter()).collect();
+        assert_eq!(
+            KeyProof::verify_batch(b"test-key", &refs, &mut rng),
+            Err(KeyProofError::InvalidProof(2))
+        );
+
+        proofs[1].responses.pop();
+        let refs: Vec<_> = keys.iter().map(|(s, _)| s).zip(proofs.iter()).collect();
+        assert_eq!(
+            KeyProof::verify_batch(b"test-key", &refs, &mut rng),
+            Err(KeyProofError::LengthMismatch(1))
+        );
+    }
+
+    utils::curve_tests!(test_key_proof, test_key_proof_batch);
+}
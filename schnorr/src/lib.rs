@@ -1,2 +1,5 @@
+pub mod batch;
+pub mod key_proof;
 pub mod schnorr;
 pub mod schnorr_pairing;
+pub mod verifiable_encryption;
@@ -2,14 +2,27 @@
 // TODO let proofs = SchnorrProtocol::new(ck, messages, commitment) this is what it should be!
 use ark_ec::{AffineRepr, CurveGroup, VariableBaseMSM};
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
-use ark_std::{rand::Rng, vec::Vec, UniformRand};
+use ark_std::{fmt, rand::Rng, vec::Vec, UniformRand};
+use utils::fixed_base::FixedBaseMsm;
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
-#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+/// Commitment T to the blindings of a Schnorr proof. The blindings are wiped when the commitment
+/// is dropped, `prove_and_erase` wipes them as soon as the responses exist
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize, Zeroize, ZeroizeOnDrop)]
 pub struct SchnorrCommitment<G: AffineRepr> {
     pub random_blindings: Vec<G::ScalarField>,
+    #[zeroize(skip)]
     pub commited_blindings: G,
 }
 
+impl<G: AffineRepr> fmt::Debug for SchnorrCommitment<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SchnorrCommitment")
+            .field("commited_blindings", &self.commited_blindings)
+            .finish_non_exhaustive()
+    }
+}
+
 #[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
 pub struct SchnorrResponses<G: AffineRepr>(pub Vec<G::ScalarField>);
 
@@ -35,6 +48,35 @@ impl SchnorrProtocol {
         }
     }
 
+    /// `commit` over bases with precomputed fixed-base tables, for provers that commit to the
+    /// same generators on every show
+    pub fn commit_with_table<G: AffineRepr, R: Rng>(
+        table: &FixedBaseMsm<G::Group>,
+        rng: &mut R,
+    ) -> SchnorrCommitment<G> {
+        Self::commit_with_table_prefix(&[], table, rng)
+    }
+
+    /// `commit` over `prefix` followed by the table's bases, for proofs that mix per-show
+    /// points (a randomized signature) with fixed generators
+    pub fn commit_with_table_prefix<G: AffineRepr, R: Rng>(
+        prefix: &[G],
+        table: &FixedBaseMsm<G::Group>,
+        rng: &mut R,
+    ) -> SchnorrCommitment<G> {
+        let random_blindings: Vec<G::ScalarField> = (0..prefix.len() + table.len())
+            .map(|_| G::ScalarField::rand(rng))
+            .collect();
+        let (prefix_blindings, table_blindings) = random_blindings.split_at(prefix.len());
+        let commited_blindings: G = (G::Group::msm_unchecked(prefix, prefix_blindings)
+            + table.msm(table_blindings))
+        .into_affine();
+        SchnorrCommitment {
+            random_blindings,
+            commited_blindings,
+        }
+    }
+
     /// returns a commitment to random blindings, the commitment T = g_1^{\rho_1},...,g_L^{\rho_L} from random blindings and bases
     pub fn commit_with_prepared_blindings<G: AffineRepr>(
         public_generators: &[G],
@@ -88,6 +130,18 @@ impl SchnorrProtocol {
         SchnorrResponses(schnorr_responsess)
     }
 
+    /// `prove` for a commitment that is sent along with the responses. The blindings are wiped
+    /// afterwards, so the serialized commitment carries only T and cannot answer a second challenge
+    pub fn prove_and_erase<G: AffineRepr>(
+        commitment: &mut SchnorrCommitment<G>,
+        witnesses: &[G::ScalarField],
+        challenge: &G::ScalarField,
+    ) -> SchnorrResponses<G> {
+        let responses = Self::prove(commitment, witnesses, challenge);
+        commitment.random_blindings.zeroize();
+        responses
+    }
+
     // this shouldn't be used, taking in SchnorrResponses is silly because it has randomness
     pub fn verify<G: AffineRepr>(
         public_generators: &[G],
@@ -156,6 +210,46 @@ mod tests {
         check::<G2Affine>(&mut rng);
     }
 
+    #[test]
+    fn test_schnorr_prove_and_erase() {
+        let mut rng = test_rng();
+
+        fn check<G: AffineRepr>(rng: &mut impl Rng) {
+            let base = G::Group::rand(rng).into_affine();
+            let witness = G::ScalarField::rand(rng);
+            let statement = base.mul(witness).into_affine();
+
+            let mut schnorr_commitment = SchnorrProtocol::commit(&[base], rng);
+            let challenge = G::ScalarField::rand(rng);
+            let schnorr_responses =
+                SchnorrProtocol::prove_and_erase(&mut schnorr_commitment, &[witness], &challenge);
+
+            // only T is left, and it still verifies
+            assert!(schnorr_commitment.random_blindings.is_empty());
+            assert!(!format!("{:?}", schnorr_commitment).contains("random_blindings"));
+            assert!(SchnorrProtocol::verify(
+                &[base],
+                &statement,
+                &schnorr_commitment,
+                &schnorr_responses,
+                &challenge
+            ));
+
+            // a serialized commitment no longer carries the blindings
+            let mut bytes = Vec::new();
+            schnorr_commitment.serialize_compressed(&mut bytes).unwrap();
+            let loaded = SchnorrCommitment::<G>::deserialize_compressed(&bytes[..]).unwrap();
+            assert!(loaded.random_blindings.is_empty());
+            assert_eq!(
+                loaded.commited_blindings,
+                schnorr_commitment.commited_blindings
+            );
+        }
+
+        check::<G1Affine>(&mut rng);
+        check::<G2Affine>(&mut rng);
+    }
+
     #[test]
     fn test_schnorr_double() {
         let mut rng = test_rng();
@@ -216,5 +310,30 @@ mod tests {
         );
 
         assert!(is_valid, "Schnorr proof verification failed");
+
+        // Same proof with the commitment taken from fixed-base tables
+        let table = FixedBaseMsm::<G1Projective>::new(&public_generators);
+        let commitment = SchnorrProtocol::commit_with_table(&table, &mut rng);
+        let schnorr_responses = SchnorrProtocol::prove(&commitment, &witnesses, &challenge);
+        assert!(SchnorrProtocol::verify(
+            &public_generators,
+            &public_statement,
+            &commitment,
+            &schnorr_responses,
+            &challenge,
+        ));
+
+        // and with the first base kept out of the table
+        let table = FixedBaseMsm::<G1Projective>::new(&public_generators[1..]);
+        let commitment =
+            SchnorrProtocol::commit_with_table_prefix(&public_generators[..1], &table, &mut rng);
+        let schnorr_responses = SchnorrProtocol::prove(&commitment, &witnesses, &challenge);
+        assert!(SchnorrProtocol::verify(
+            &public_generators,
+            &public_statement,
+            &commitment,
+            &schnorr_responses,
+            &challenge,
+        ));
     }
 }
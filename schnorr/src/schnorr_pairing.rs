@@ -2,15 +2,26 @@ use ark_ec::pairing::{Pairing, PairingOutput};
 use ark_ec::AffineRepr;
 use ark_ec::Group;
 use ark_ff::{PrimeField, UniformRand, Zero};
-use ark_std::rand::Rng;
+use ark_std::{fmt, rand::Rng};
 use std::ops::Mul;
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
-#[derive(Clone, Debug)]
+/// GT commitment to the blindings of a pairing Schnorr proof, the blindings are wiped on drop
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
 pub struct SchnorrCommitmentPairing<E: Pairing> {
     pub blindings: Vec<E::ScalarField>,
+    #[zeroize(skip)]
     pub schnorr_commitment: PairingOutput<E>,
 }
 
+impl<E: Pairing> fmt::Debug for SchnorrCommitmentPairing<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SchnorrCommitmentPairing")
+            .field("schnorr_commitment", &self.schnorr_commitment)
+            .finish_non_exhaustive()
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct SchnorrResponsesPairing<E: Pairing>(pub Vec<E::ScalarField>);
 
@@ -98,6 +109,29 @@ impl SchnorrProtocolPairing {
 
         lhs == rhs
     }
+
+    /// Same as `verify` for G2 bases that are already prepared
+    pub fn verify_prepared<E: Pairing>(
+        statement: &PairingOutput<E>,
+        schnorr_commitment: &PairingOutput<E>,
+        challenge: &E::ScalarField,
+        bases_g1: &[E::G1Affine],
+        bases_g2: &[E::G2Prepared],
+        responses: &[E::ScalarField],
+    ) -> bool {
+        assert!(
+            bases_g1.len() == bases_g2.len() && bases_g1.len() == responses.len(),
+            "bases in g1, g2, and scalars in responses must match length, found {}, {}, {}",
+            bases_g1.len(),
+            bases_g2.len(),
+            responses.len()
+        );
+        let lhs = compute_gt_prepared(bases_g1, bases_g2, responses);
+
+        let rhs = statement.mul_bigint(challenge.into_bigint()) + schnorr_commitment;
+
+        lhs == rhs
+    }
 }
 
 pub fn compute_gt_from_g1_g2_scalars<E: Pairing>(
@@ -130,6 +164,26 @@ pub fn compute_gt_from_g1_g2_scalars<E: Pairing>(
     E::multi_pairing(prepared_g1, prepared_g2)
 }
 
+/// Computes ∏ e(g1_i^{scalar_i}, g2_i) for prepared G2 points
+pub fn compute_gt_prepared<E: Pairing>(
+    g1_points: &[E::G1Affine],
+    g2_points: &[E::G2Prepared],
+    scalars: &[E::ScalarField],
+) -> PairingOutput<E> {
+    assert!(
+        g1_points.len() == g2_points.len() && g2_points.len() == scalars.len(),
+        "Mismatched number of G1, G2, and scalars"
+    );
+
+    let prepared_g1: Vec<_> = g1_points
+        .iter()
+        .zip(scalars.iter())
+        .map(|(g1, s)| E::G1Prepared::from(g1.into_group().mul(s)))
+        .collect();
+
+    E::multi_pairing(prepared_g1, g2_points.iter().cloned())
+}
+
 #[cfg(test)]
 
 mod test {
@@ -174,5 +228,16 @@ mod test {
 
         // Assert that the verification passes
         assert!(is_valid, "Schnorr proof verification failed");
+
+        let prepared_g2: Vec<<Bls12_381 as Pairing>::G2Prepared> =
+            bases_g2.iter().map(|b| (*b).into()).collect();
+        assert!(SchnorrProtocolPairing::verify_prepared(
+            &statement,
+            &commitment.schnorr_commitment,
+            &challenge,
+            &bases_g1,
+            &prepared_g2,
+            &responses.0,
+        ));
     }
 }
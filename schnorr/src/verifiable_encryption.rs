@@ -0,0 +1,240 @@
+// Verifiable encryption of a committed attribute to an auditor, Camenisch-Shoup style ElGamal
+// generalised from crypto_benchmarks::encryption_camenisch_shoup.
+//
+// The attribute m is encrypted "in the exponent" as g^m, so the auditor recovers g^m and resolves it
+// against the set of known identities. The proof of well-formedness uses the same challenge and the
+// same blinding for m as the credential show proof, so equal responses link the ciphertext to the
+// hidden attribute of the presentation.
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{rand::Rng, vec::Vec, UniformRand};
+use utils::hash::HashUtil;
+
+/// Auditor public key, h = g^x and k = g^y
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct AuditorPublicKey<G: AffineRepr> {
+    pub g: G,
+    pub h: G,
+    pub k: G,
+}
+
+/// Auditor secret key, x decrypts and y checks the label tag
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct AuditorSecretKey<G: AffineRepr> {
+    pub x: G::ScalarField,
+    pub y: G::ScalarField,
+}
+
+/// Ciphertext (u, e, v) = (g^r, h^r g^m, k^r g^t) with t = H(u, e, label)
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Ciphertext<G: AffineRepr> {
+    pub u: G,
+    pub e: G,
+    pub v: G,
+}
+
+/// Prover state between commit and prove, s_m must be the blinding used for m in the show proof
+#[derive(Clone, Debug)]
+pub struct EncryptionCommitment<G: AffineRepr> {
+    pub s_r: G::ScalarField,
+    pub s_m: G::ScalarField,
+    pub a1: G,
+    pub a2: G,
+    pub a3: G,
+}
+
+/// Proof that a ciphertext is well formed, z_m equals the show proof response for the attribute
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct EncryptionProof<G: AffineRepr> {
+    pub a1: G,
+    pub a2: G,
+    pub a3: G,
+    pub z_r: G::ScalarField,
+    pub z_m: G::ScalarField,
+}
+
+pub struct VerifiableEncryption;
+
+impl VerifiableEncryption {
+    /// generate an auditor key pair over random generator g
+    pub fn keygen<G: AffineRepr, R: Rng>(
+        rng: &mut R,
+    ) -> (AuditorSecretKey<G>, AuditorPublicKey<G>) {
+        let g = G::Group::rand(rng).into_affine();
+        let x = G::ScalarField::rand(rng);
+        let y = G::ScalarField::rand(rng);
+        let pk = AuditorPublicKey {
+            g,
+            h: g.mul(x).into_affine(),
+            k: g.mul(y).into_affine(),
+        };
+        (AuditorSecretKey { x, y }, pk)
+    }
+
+    /// t = H(u, e, label), binds the ciphertext to a verifier session
+    pub fn tag<G: AffineRepr>(u: &G, e: &G, label: &[u8]) -> G::ScalarField {
+        let mut bytes = Vec::new();
+        u.serialize_compressed(&mut bytes).unwrap();
+        e.serialize_compressed(&mut bytes).unwrap();
+        bytes.extend_from_slice(label);
+        HashUtil::<G::ScalarField, G>::hash_to_field(&bytes)
+    }
+
+    /// encrypt attribute m under label, returns the ciphertext and the encryption randomness r
+    pub fn encrypt<G: AffineRepr, R: Rng>(
+        pk: &AuditorPublicKey<G>,
+        m: &G::ScalarField,
+        label: &[u8],
+        rng: &mut R,
+    ) -> (Ciphertext<G>, G::ScalarField) {
+        let r = G::ScalarField::rand(rng);
+        let u = pk.g.mul(r).into_affine();
+        let e = (pk.h.mul(r) + pk.g.mul(*m)).into_affine();
+        let t = Self::tag(&u, &e, label);
+        let v = (pk.k.mul(r) + pk.g.mul(t)).into_affine();
+        (Ciphertext { u, e, v }, r)
+    }
+
+    /// first move of the well-formedness proof, m_blinding is shared with the credential show proof
+    pub fn commit<G: AffineRepr, R: Rng>(
+        pk: &AuditorPublicKey<G>,
+        m_blinding: &G::ScalarField,
+        rng: &mut R,
+    ) -> EncryptionCommitment<G> {
+        let s_r = G::ScalarField::rand(rng);
+        let s_m = *m_blinding;
+        EncryptionCommitment {
+            s_r,
+            s_m,
+            a1: pk.g.mul(s_r).into_affine(),
+            a2: (pk.h.mul(s_r) + pk.g.mul(s_m)).into_affine(),
+            a3: pk.k.mul(s_r).into_affine(),
+        }
+    }
+
+    pub fn prove<G: AffineRepr>(
+        commitment: &EncryptionCommitment<G>,
+        r: &G::ScalarField,
+        m: &G::ScalarField,
+        challenge: &G::ScalarField,
+    ) -> EncryptionProof<G> {
+        EncryptionProof {
+            a1: commitment.a1,
+            a2: commitment.a2,
+            a3: commitment.a3,
+            z_r: commitment.s_r + *challenge * r,
+            z_m: commitment.s_m + *challenge * m,
+        }
+    }
+
+    /// append ciphertext, label and proof commitments to a transcript the challenge is hashed from
+    pub fn append_to_transcript<G: AffineRepr>(
+        transcript: &mut Vec<u8>,
+        ciphertext: &Ciphertext<G>,
+        label: &[u8],
+        a1: &G,
+        a2: &G,
+        a3: &G,
+    ) {
+        ciphertext.serialize_compressed(&mut *transcript).unwrap();
+        transcript.extend_from_slice(label);
+        a1.serialize_compressed(&mut *transcript).unwrap();
+        a2.serialize_compressed(&mut *transcript).unwrap();
+        a3.serialize_compressed(&mut *transcript).unwrap();
+    }
+
+    /// checks the three Schnorr equations, the caller must check z_m against the show proof response
+    pub fn verify<G: AffineRepr>(
+        pk: &AuditorPublicKey<G>,
+        ciphertext: &Ciphertext<G>,
+        label: &[u8],
+        proof: &EncryptionProof<G>,
+        challenge: &G::ScalarField,
+    ) -> bool {
+        // g^z_r = a1 u^c
+        let lhs1 = pk.g.mul(proof.z_r);
+        let rhs1 = proof.a1 + ciphertext.u.mul(*challenge);
+        if lhs1 != rhs1 {
+            return false;
+        }
+        // h^z_r g^z_m = a2 e^c
+        let lhs2 = pk.h.mul(proof.z_r) + pk.g.mul(proof.z_m);
+        let rhs2 = proof.a2 + ciphertext.e.mul(*challenge);
+        if lhs2 != rhs2 {
+            return false;
+        }
+        // k^z_r = a3 (v g^-t)^c
+        let t = Self::tag(&ciphertext.u, &ciphertext.e, label);
+        let v_minus_tg = ciphertext.v.into_group() - pk.g.mul(t);
+        let lhs3 = pk.k.mul(proof.z_r);
+        let rhs3 = proof.a3 + v_minus_tg.into_affine().mul(*challenge);
+        lhs3 == rhs3
+    }
+
+    /// auditor decryption, returns g^m or None if the ciphertext is not bound to label
+    pub fn decrypt<G: AffineRepr>(
+        sk: &AuditorSecretKey<G>,
+        pk: &AuditorPublicKey<G>,
+        ciphertext: &Ciphertext<G>,
+        label: &[u8],
+    ) -> Option<G> {
+        let t = Self::tag(&ciphertext.u, &ciphertext.e, label);
+        let v_check = ciphertext.u.mul(sk.y) + pk.g.mul(t);
+        if v_check.into_affine() != ciphertext.v {
+            return None;
+        }
+        Some((ciphertext.e.into_group() - ciphertext.u.mul(sk.x)).into_affine())
+    }
+
+    /// resolve a decrypted g^m against candidate attributes, returns the matching index
+    pub fn resolve<G: AffineRepr>(
+        pk: &AuditorPublicKey<G>,
+        plaintext: &G,
+        candidates: &[G::ScalarField],
+    ) -> Option<usize> {
+        candidates
+            .iter()
+            .position(|m| pk.g.mul(*m).into_affine() == *plaintext)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::{Fr, G1Affine};
+    use ark_std::test_rng;
+
+    #[test]
+    fn test_verifiable_encryption() {
+        let mut rng = test_rng();
+        let (sk, pk) = VerifiableEncryption::keygen::<G1Affine, _>(&mut rng);
+        let m = Fr::rand(&mut rng);
+        let label = b"verifier session 1";
+
+        let (ct, r) = VerifiableEncryption::encrypt(&pk, &m, label, &mut rng);
+        let s_m = Fr::rand(&mut rng);
+        let commitment = VerifiableEncryption::commit(&pk, &s_m, &mut rng);
+        let challenge = Fr::rand(&mut rng);
+        let proof = VerifiableEncryption::prove(&commitment, &r, &m, &challenge);
+
+        assert!(VerifiableEncryption::verify(
+            &pk, &ct, label, &proof, &challenge
+        ));
+        assert_eq!(proof.z_m, s_m + challenge * m);
+        assert!(!VerifiableEncryption::verify(
+            &pk,
+            &ct,
+            b"other session",
+            &proof,
+            &challenge
+        ));
+
+        let plaintext = VerifiableEncryption::decrypt(&sk, &pk, &ct, label).unwrap();
+        let candidates = vec![Fr::rand(&mut rng), m, Fr::rand(&mut rng)];
+        assert_eq!(
+            VerifiableEncryption::resolve(&pk, &plaintext, &candidates),
+            Some(1)
+        );
+        assert!(VerifiableEncryption::decrypt(&sk, &pk, &ct, b"other session").is_none());
+    }
+}
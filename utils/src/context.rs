@@ -0,0 +1,67 @@
+use ark_ff::PrimeField;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use blake2::Blake2s256;
+use digest::Digest;
+
+/// Verifier supplied context a presentation is bound to. It is absorbed into the show proof challenge,
+/// so a captured presentation does not verify under another verifier id, nonce or timestamp.
+#[derive(Clone, Debug, Default, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PresentationContext {
+    pub verifier_id: Vec<u8>,
+    pub nonce: Vec<u8>,
+    pub timestamp: u64,
+    pub data: Vec<u8>,
+}
+
+impl PresentationContext {
+    pub fn new(verifier_id: &[u8], nonce: &[u8], timestamp: u64) -> Self {
+        Self {
+            verifier_id: verifier_id.to_vec(),
+            nonce: nonce.to_vec(),
+            timestamp,
+            data: Vec::new(),
+        }
+    }
+
+    /// attach arbitrary application bytes, e.g. a transaction hash
+    pub fn with_data(mut self, data: &[u8]) -> Self {
+        self.data = data.to_vec();
+        self
+    }
+
+    /// length prefixed encoding so no two contexts share bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.serialize_compressed(&mut bytes).unwrap();
+        bytes
+    }
+
+    /// Fiat-Shamir challenge over a scheme domain tag, this context and the proof transcript
+    pub fn challenge<F: PrimeField>(&self, domain: &[u8], transcript: &[u8]) -> F {
+        let mut hasher = Blake2s256::new();
+        hasher.update((domain.len() as u64).to_le_bytes());
+        hasher.update(domain);
+        hasher.update(self.to_bytes());
+        hasher.update(transcript);
+        F::from_le_bytes_mod_order(&hasher.finalize())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Fr;
+
+    #[test]
+    fn test_context_challenge() {
+        let ctx = PresentationContext::new(b"verifier", b"nonce-1", 1_700_000_000);
+        let c: Fr = ctx.challenge(b"test", b"transcript");
+        assert_eq!(c, ctx.clone().challenge(b"test", b"transcript"));
+
+        let other_nonce = PresentationContext::new(b"verifier", b"nonce-2", 1_700_000_000);
+        assert_ne!(c, other_nonce.challenge::<Fr>(b"test", b"transcript"));
+        let with_data = ctx.clone().with_data(b"tx");
+        assert_ne!(c, with_data.challenge::<Fr>(b"test", b"transcript"));
+        assert_ne!(c, ctx.challenge::<Fr>(b"other", b"transcript"));
+    }
+}
@@ -0,0 +1,50 @@
+//! Pairings the schemes are tested and benchmarked on. BLS12-381 is always available, BN254
+//! and BLS12-377 are behind the `bn254` and `bls12-377` features.
+
+pub use ark_bls12_381::Bls12_381;
+
+#[cfg(feature = "bls12-377")]
+pub use ark_bls12_377::Bls12_377;
+#[cfg(feature = "bn254")]
+pub use ark_bn254::Bn254;
+
+/// Instantiates the generic tests `fn name<E>()` of the enclosing module as `#[test]`s, in one
+/// submodule per enabled curve. The curve features are checked in the calling crate, which
+/// forwards them to `utils`.
+#[macro_export]
+macro_rules! curve_tests {
+    ($($test:ident),+ $(,)?) => {
+        $crate::curve_tests!(@curve bls12_381, $crate::curves::Bls12_381, $($test),+);
+        $crate::curve_tests!(
+            @curve #[cfg(feature = "bn254")] bn254, $crate::curves::Bn254, $($test),+
+        );
+        $crate::curve_tests!(
+            @curve #[cfg(feature = "bls12-377")] bls12_377, $crate::curves::Bls12_377, $($test),+
+        );
+    };
+    (@curve $(#[$cfg:meta])* $module:ident, $curve:ty, $($test:ident),+) => {
+        $(#[$cfg])*
+        mod $module {
+            $(
+                #[test]
+                fn $test() {
+                    super::$test::<$curve>()
+                }
+            )+
+        }
+    };
+}
+
+/// Calls the generic function `f::<E>(args)` once per enabled curve, with the curve features
+/// checked in the calling crate like `curve_tests!`. The benches use it to run every group on
+/// each curve.
+#[macro_export]
+macro_rules! for_each_curve {
+    ($f:ident($($arg:expr),* $(,)?)) => {
+        $f::<$crate::curves::Bls12_381>($($arg),*);
+        #[cfg(feature = "bn254")]
+        $f::<$crate::curves::Bn254>($($arg),*);
+        #[cfg(feature = "bls12-377")]
+        $f::<$crate::curves::Bls12_377>($($arg),*);
+    };
+}
@@ -0,0 +1,141 @@
+use crate::encoding::HexScalar;
+use ark_ff::PrimeField;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum DisclosureError {
+    #[error("Invalid disclosed attribute index {0}")]
+    InvalidIndex(usize),
+}
+
+/// Attributes revealed by a presentation whose Schnorr commitment was computed before the
+/// verifier chose them. Each value travels with its blinding ρ and the verifier checks that the
+/// response is ρ + c·m. Both are hashed into the challenge, so ρ is fixed before c is known.
+#[serde_as]
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    CanonicalSerialize,
+    CanonicalDeserialize,
+    Serialize,
+    Deserialize,
+)]
+#[serde(bound = "")]
+pub struct Disclosure<F: PrimeField> {
+    #[serde_as(as = "Vec<(_, HexScalar)>")]
+    pub attributes: Vec<(usize, F)>,
+    #[serde_as(as = "Vec<HexScalar>")]
+    pub blindings: Vec<F>,
+}
+
+impl<F: PrimeField> Disclosure<F> {
+    /// reveals messages[i] for each i in `indices`, blindings[i] is the Schnorr blinding of messages[i]
+    pub fn new(
+        indices: &[usize],
+        messages: &[F],
+        blindings: &[F],
+    ) -> Result<Self, DisclosureError> {
+        check_indices(messages.len().min(blindings.len()), indices)?;
+        Ok(Self {
+            attributes: indices.iter().map(|i| (*i, messages[*i])).collect(),
+            blindings: indices.iter().map(|i| blindings[*i]).collect(),
+        })
+    }
+
+    pub fn indices(&self) -> Vec<usize> {
+        self.attributes.iter().map(|(i, _)| *i).collect()
+    }
+
+    /// the same disclosure over the exponents -m_i, for proofs that carry negated attributes
+    pub fn negated(&self) -> Self {
+        Self {
+            attributes: self.attributes.iter().map(|(i, m)| (*i, -*m)).collect(),
+            blindings: self.blindings.clone(),
+        }
+    }
+
+    /// checks responses[i] = ρ_i + c·m_i for every disclosed attribute, `responses` holds one
+    /// response per attribute
+    pub fn verify(&self, responses: &[F], challenge: &F) -> bool {
+        self.attributes.len() == self.blindings.len()
+            && check_indices(responses.len(), &self.indices()).is_ok()
+            && self
+                .attributes
+                .iter()
+                .zip(&self.blindings)
+                .all(|((i, m), rho)| responses[*i] == *rho + *challenge * m)
+    }
+
+    /// encoding for a Fiat-Shamir transcript
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.serialize_compressed(&mut bytes).unwrap();
+        bytes
+    }
+}
+
+// indices must be in range and distinct
+fn check_indices(n: usize, indices: &[usize]) -> Result<(), DisclosureError> {
+    for (k, &i) in indices.iter().enumerate() {
+        if i >= n || indices[..k].contains(&i) {
+            return Err(DisclosureError::InvalidIndex(i));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Fr;
+
+    #[test]
+    fn test_disclosure() {
+        let messages = vec![Fr::from(3u64), Fr::from(5u64), Fr::from(7u64)];
+        let blindings = vec![Fr::from(11u64), Fr::from(13u64), Fr::from(17u64)];
+        let c = Fr::from(2u64);
+        let responses: Vec<Fr> = messages
+            .iter()
+            .zip(&blindings)
+            .map(|(m, rho)| *rho + c * m)
+            .collect();
+
+        let disclosure = Disclosure::new(&[2, 0], &messages, &blindings).unwrap();
+        assert_eq!(
+            disclosure.attributes,
+            vec![(2, messages[2]), (0, messages[0])]
+        );
+        assert!(disclosure.verify(&responses, &c));
+        assert!(!disclosure.verify(&responses, &(c + Fr::from(1u64))));
+        assert!(disclosure.negated().verify(
+            &messages
+                .iter()
+                .zip(&blindings)
+                .map(|(m, rho)| *rho - c * m)
+                .collect::<Vec<_>>(),
+            &c
+        ));
+
+        let mut forged = disclosure.clone();
+        forged.attributes[0].1 += Fr::from(1u64);
+        assert!(!forged.verify(&responses, &c));
+        forged.attributes[0] = (3, messages[2]);
+        assert!(!forged.verify(&responses, &c));
+
+        assert!(Disclosure::<Fr>::default().verify(&responses, &c));
+        assert_eq!(
+            Disclosure::new(&[1, 1], &messages, &blindings),
+            Err(DisclosureError::InvalidIndex(1))
+        );
+        assert_eq!(
+            Disclosure::new(&[3], &messages, &blindings),
+            Err(DisclosureError::InvalidIndex(3))
+        );
+    }
+}
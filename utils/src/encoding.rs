@@ -0,0 +1,212 @@
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serializer};
+use serde_with::{DeserializeAs, SerializeAs};
+
+const BASE64URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// unpadded base64url
+pub fn to_base64url(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity((bytes.len() * 4).div_ceil(3));
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..chunk.len() + 1 {
+            out.push(BASE64URL[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+    out
+}
+
+/// decodes unpadded base64url, None on padding, foreign characters or non-zero trailing bits
+pub fn from_base64url(s: &str) -> Option<Vec<u8>> {
+    if s.len() % 4 == 1 {
+        return None;
+    }
+    let mut out = Vec::with_capacity(s.len() * 3 / 4);
+    for chunk in s.as_bytes().chunks(4) {
+        let mut n = 0u32;
+        for (i, c) in chunk.iter().enumerate() {
+            let v = BASE64URL.iter().position(|a| a == c)? as u32;
+            n |= v << (18 - 6 * i);
+        }
+        let bytes = [(n >> 16) as u8, (n >> 8) as u8, n as u8];
+        let len = chunk.len() - 1;
+        if bytes[len..].iter().any(|b| *b != 0) {
+            return None;
+        }
+        out.extend_from_slice(&bytes[..len]);
+    }
+    Some(out)
+}
+
+/// lowercase hex
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// decodes lowercase hex
+pub fn from_hex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    let digit = |c: u8| match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        _ => None,
+    };
+    s.as_bytes()
+        .chunks(2)
+        .map(|pair| Some(digit(pair[0])? << 4 | digit(pair[1])?))
+        .collect()
+}
+
+/// Decodes a compressed canonical encoding. Validation rejects points off the curve or outside the
+/// prime order subgroup, and re-encoding rejects anything that is not the unique canonical form.
+fn decode_canonical<T: CanonicalSerialize + CanonicalDeserialize>(bytes: &[u8]) -> Option<T> {
+    let value = T::deserialize_compressed(bytes).ok()?;
+    let mut canonical = Vec::with_capacity(bytes.len());
+    value.serialize_compressed(&mut canonical).ok()?;
+    (canonical == bytes).then_some(value)
+}
+
+/// serde_with adapter encoding group elements as base64url of their compressed bytes
+pub struct Base64Point;
+
+impl<T: CanonicalSerialize> SerializeAs<T> for Base64Point {
+    fn serialize_as<S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut bytes = Vec::new();
+        value
+            .serialize_compressed(&mut bytes)
+            .map_err(serde::ser::Error::custom)?;
+        serializer.serialize_str(&to_base64url(&bytes))
+    }
+}
+
+impl<'de, T: CanonicalSerialize + CanonicalDeserialize> DeserializeAs<'de, T> for Base64Point {
+    fn deserialize_as<D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        let bytes = from_base64url(&s).ok_or_else(|| D::Error::custom("invalid base64url"))?;
+        decode_canonical(&bytes).ok_or_else(|| D::Error::custom("invalid or non-canonical point"))
+    }
+}
+
+/// serde_with adapter encoding field elements as hex of their canonical little-endian bytes
+pub struct HexScalar;
+
+impl<T: CanonicalSerialize> SerializeAs<T> for HexScalar {
+    fn serialize_as<S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut bytes = Vec::new();
+        value
+            .serialize_compressed(&mut bytes)
+            .map_err(serde::ser::Error::custom)?;
+        serializer.serialize_str(&to_hex(&bytes))
+    }
+}
+
+impl<'de, T: CanonicalSerialize + CanonicalDeserialize> DeserializeAs<'de, T> for HexScalar {
+    fn deserialize_as<D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        let bytes = from_hex(&s).ok_or_else(|| D::Error::custom("invalid hex"))?;
+        decode_canonical(&bytes).ok_or_else(|| D::Error::custom("invalid or non-canonical scalar"))
+    }
+}
+
+/// serde_with adapter for opaque proof bytes as base64url
+pub struct Base64Bytes;
+
+impl SerializeAs<Vec<u8>> for Base64Bytes {
+    fn serialize_as<S: Serializer>(value: &Vec<u8>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&to_base64url(value))
+    }
+}
+
+impl<'de> DeserializeAs<'de, Vec<u8>> for Base64Bytes {
+    fn deserialize_as<D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        from_base64url(&s).ok_or_else(|| D::Error::custom("invalid base64url"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::{Fr, G1Affine};
+    use ark_ec::AffineRepr;
+    use ark_ff::{Field, UniformRand};
+    use serde::Serialize;
+    use serde_with::serde_as;
+
+    #[serde_as]
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Example {
+        #[serde_as(as = "Base64Point")]
+        point: G1Affine,
+        #[serde_as(as = "Vec<HexScalar>")]
+        scalars: Vec<Fr>,
+    }
+
+    #[test]
+    fn test_encodings_roundtrip() {
+        for len in 0..8 {
+            let bytes: Vec<u8> = (0..len as u8).map(|b| b.wrapping_mul(97)).collect();
+            assert_eq!(from_base64url(&to_base64url(&bytes)), Some(bytes.clone()));
+            assert_eq!(from_hex(&to_hex(&bytes)), Some(bytes));
+        }
+        assert_eq!(to_base64url(b"\xfb\xff"), "-_8");
+        // "-_9" sets trailing bits past the last byte
+        assert_eq!(from_base64url("-_9"), None);
+        assert_eq!(from_base64url("-_8="), None);
+        assert_eq!(from_hex("0G"), None);
+
+        let mut rng = ark_std::test_rng();
+        let example = Example {
+            point: G1Affine::rand(&mut rng),
+            scalars: vec![Fr::rand(&mut rng), Fr::from(7u64)],
+        };
+        let json = serde_json::to_string(&example).unwrap();
+        assert_eq!(serde_json::from_str::<Example>(&json).unwrap(), example);
+    }
+
+    #[test]
+    fn test_rejects_invalid_points_and_scalars() {
+        let generator = to_base64url(&{
+            let mut bytes = Vec::new();
+            G1Affine::generator()
+                .serialize_compressed(&mut bytes)
+                .unwrap();
+            bytes
+        });
+        let json = |point: &str, scalar: &str| {
+            format!("{{\"point\":\"{}\",\"scalars\":[\"{}\"]}}", point, scalar)
+        };
+        let one = to_hex(&{
+            let mut bytes = Vec::new();
+            Fr::ONE.serialize_compressed(&mut bytes).unwrap();
+            bytes
+        });
+        assert!(serde_json::from_str::<Example>(&json(&generator, &one)).is_ok());
+
+        // the field modulus is not a canonical scalar
+        let modulus = "01000000fffffffffe5bfeff02a4bd5305d8a10908d83933487d9d2953a7ed73";
+        assert!(serde_json::from_str::<Example>(&json(&generator, modulus)).is_err());
+
+        // a point on the curve outside the prime order subgroup
+        let mut x = ark_bls12_381::Fq::ONE;
+        let off_subgroup = loop {
+            if let Some(p) = G1Affine::get_point_from_x_unchecked(x, false) {
+                if !p.is_in_correct_subgroup_assuming_on_curve() {
+                    break p;
+                }
+            }
+            x += ark_bls12_381::Fq::ONE;
+        };
+        let mut bytes = Vec::new();
+        off_subgroup.serialize_compressed(&mut bytes).unwrap();
+        assert!(serde_json::from_str::<Example>(&json(&to_base64url(&bytes), &one)).is_err());
+    }
+}
@@ -0,0 +1,379 @@
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use thiserror::Error;
+
+/// First bytes of every envelope
+pub const MAGIC: [u8; 4] = *b"ACRD";
+/// Wire format version written by `to_envelope`
+pub const VERSION: u8 = 1;
+/// magic, version, scheme, curve, kind, attribute count, body length
+pub const HEADER_LEN: usize = 4 + 1 + 1 + 1 + 1 + 4 + 4;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum EnvelopeError {
+    #[error("Not an envelope, bad magic")]
+    BadMagic,
+    #[error("Unsupported envelope version {0}")]
+    UnsupportedVersion(u8),
+    #[error("Unknown scheme id {0}")]
+    UnknownScheme(u8),
+    #[error("Unknown curve id {0}")]
+    UnknownCurve(u8),
+    #[error("Unknown artifact kind {0}")]
+    UnknownKind(u8),
+    #[error("Expected scheme {expected:?}, found {found:?}")]
+    SchemeMismatch { expected: SchemeId, found: SchemeId },
+    #[error("Expected curve {expected:?}, found {found:?}")]
+    CurveMismatch { expected: CurveId, found: CurveId },
+    #[error("Expected artifact {expected:?}, found {found:?}")]
+    KindMismatch {
+        expected: ArtifactKind,
+        found: ArtifactKind,
+    },
+    #[error("Header declares {header} attributes, body has {body}")]
+    AttributeCountMismatch { header: u32, body: u32 },
+    #[error("Envelope is truncated")]
+    Truncated,
+    #[error("Envelope has trailing bytes")]
+    TrailingBytes,
+    #[error("Invalid body: {0}")]
+    InvalidBody(String),
+}
+
+impl From<SerializationError> for EnvelopeError {
+    fn from(e: SerializationError) -> Self {
+        EnvelopeError::InvalidBody(e.to_string())
+    }
+}
+
+/// Credential scheme an artifact belongs to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SchemeId {
+    Ps = 1,
+    PsUtt = 2,
+    PsUttImproved = 3,
+    PsUttTs = 4,
+    BbsPlus16 = 5,
+    BbsPlusOg = 6,
+    MimcAbc = 7,
+}
+
+/// Pairing the group elements in the body live on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CurveId {
+    Bls12_381 = 1,
+    Bn254 = 2,
+    Bls12_377 = 3,
+}
+
+impl CurveId {
+    /// lowercase name, used to label tests and benchmark groups
+    pub fn name(&self) -> &'static str {
+        match self {
+            CurveId::Bls12_381 => "bls12_381",
+            CurveId::Bn254 => "bn254",
+            CurveId::Bls12_377 => "bls12_377",
+        }
+    }
+}
+
+/// Role of an artifact in the issue/show flow
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArtifactKind {
+    PublicParams = 1,
+    PublicKey = 2,
+    PublicKeyShare = 3,
+    IssuanceRequest = 4,
+    ReissuanceRequest = 5,
+    IssuanceResponse = 6,
+    Signature = 7,
+    Credential = 8,
+    Presentation = 9,
+    EscrowedPresentation = 10,
+    SecretKey = 11,
+    SecretKeyShare = 12,
+}
+
+impl TryFrom<u8> for SchemeId {
+    type Error = EnvelopeError;
+
+    fn try_from(id: u8) -> Result<Self, Self::Error> {
+        Ok(match id {
+            1 => SchemeId::Ps,
+            2 => SchemeId::PsUtt,
+            3 => SchemeId::PsUttImproved,
+            4 => SchemeId::PsUttTs,
+            5 => SchemeId::BbsPlus16,
+            6 => SchemeId::BbsPlusOg,
+            7 => SchemeId::MimcAbc,
+            _ => return Err(EnvelopeError::UnknownScheme(id)),
+        })
+    }
+}
+
+impl TryFrom<u8> for CurveId {
+    type Error = EnvelopeError;
+
+    fn try_from(id: u8) -> Result<Self, Self::Error> {
+        match id {
+            1 => Ok(CurveId::Bls12_381),
+            2 => Ok(CurveId::Bn254),
+            3 => Ok(CurveId::Bls12_377),
+            _ => Err(EnvelopeError::UnknownCurve(id)),
+        }
+    }
+}
+
+impl TryFrom<u8> for ArtifactKind {
+    type Error = EnvelopeError;
+
+    fn try_from(id: u8) -> Result<Self, Self::Error> {
+        Ok(match id {
+            1 => ArtifactKind::PublicParams,
+            2 => ArtifactKind::PublicKey,
+            3 => ArtifactKind::PublicKeyShare,
+            4 => ArtifactKind::IssuanceRequest,
+            5 => ArtifactKind::ReissuanceRequest,
+            6 => ArtifactKind::IssuanceResponse,
+            7 => ArtifactKind::Signature,
+            8 => ArtifactKind::Credential,
+            9 => ArtifactKind::Presentation,
+            10 => ArtifactKind::EscrowedPresentation,
+            11 => ArtifactKind::SecretKey,
+            12 => ArtifactKind::SecretKeyShare,
+            _ => return Err(EnvelopeError::UnknownKind(id)),
+        })
+    }
+}
+
+/// Pairings with an assigned wire id
+pub trait CurveIdentifier {
+    const CURVE_ID: CurveId;
+}
+
+impl CurveIdentifier for ark_bls12_381::Bls12_381 {
+    const CURVE_ID: CurveId = CurveId::Bls12_381;
+}
+
+#[cfg(feature = "bn254")]
+impl CurveIdentifier for ark_bn254::Bn254 {
+    const CURVE_ID: CurveId = CurveId::Bn254;
+}
+
+#[cfg(feature = "bls12-377")]
+impl CurveIdentifier for ark_bls12_377::Bls12_377 {
+    const CURVE_ID: CurveId = CurveId::Bls12_377;
+}
+
+/// Decoded envelope header
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Header {
+    pub version: u8,
+    pub scheme: SchemeId,
+    pub curve: CurveId,
+    pub kind: ArtifactKind,
+    /// 0 when the artifact has no fixed attribute count
+    pub attribute_count: u32,
+}
+
+impl Header {
+    /// reads the header of an envelope and returns it with the body it frames
+    pub fn parse(bytes: &[u8]) -> Result<(Self, &[u8]), EnvelopeError> {
+        if bytes.len() < HEADER_LEN {
+            return Err(EnvelopeError::Truncated);
+        }
+        if bytes[..4] != MAGIC {
+            return Err(EnvelopeError::BadMagic);
+        }
+        if bytes[4] != VERSION {
+            return Err(EnvelopeError::UnsupportedVersion(bytes[4]));
+        }
+        let header = Header {
+            version: bytes[4],
+            scheme: SchemeId::try_from(bytes[5])?,
+            curve: CurveId::try_from(bytes[6])?,
+            kind: ArtifactKind::try_from(bytes[7])?,
+            attribute_count: u32::from_le_bytes(bytes[8..12].try_into().unwrap()),
+        };
+        let body_len = u32::from_le_bytes(bytes[12..16].try_into().unwrap()) as usize;
+        let body = &bytes[HEADER_LEN..];
+        if body.len() < body_len {
+            return Err(EnvelopeError::Truncated);
+        }
+        if body.len() > body_len {
+            return Err(EnvelopeError::TrailingBytes);
+        }
+        Ok((header, body))
+    }
+}
+
+/// Versioned, self-describing encoding of a credential artifact. The body is the compressed
+/// canonical serialization of the artifact, framed by a header naming scheme, curve and kind.
+pub trait Envelope: CanonicalSerialize + CanonicalDeserialize {
+    const SCHEME: SchemeId;
+    const KIND: ArtifactKind;
+
+    fn curve() -> CurveId;
+
+    /// number of attributes the artifact is for, 0 when it does not fix one
+    fn attribute_count(&self) -> u32 {
+        0
+    }
+
+    fn to_envelope(&self) -> Vec<u8> {
+        let body_len = self.compressed_size();
+        let mut bytes = Vec::with_capacity(HEADER_LEN + body_len);
+        bytes.extend_from_slice(&MAGIC);
+        bytes.push(VERSION);
+        bytes.push(Self::SCHEME as u8);
+        bytes.push(Self::curve() as u8);
+        bytes.push(Self::KIND as u8);
+        bytes.extend_from_slice(&self.attribute_count().to_le_bytes());
+        bytes.extend_from_slice(&(body_len as u32).to_le_bytes());
+        self.serialize_compressed(&mut bytes).unwrap();
+        bytes
+    }
+
+    /// decodes an envelope, rejecting other schemes, curves or kinds. Group elements are checked.
+    fn from_envelope(bytes: &[u8]) -> Result<Self, EnvelopeError> {
+        let (header, body) = Header::parse(bytes)?;
+        if header.scheme != Self::SCHEME {
+            return Err(EnvelopeError::SchemeMismatch {
+                expected: Self::SCHEME,
+                found: header.scheme,
+            });
+        }
+        if header.curve != Self::curve() {
+            return Err(EnvelopeError::CurveMismatch {
+                expected: Self::curve(),
+                found: header.curve,
+            });
+        }
+        if header.kind != Self::KIND {
+            return Err(EnvelopeError::KindMismatch {
+                expected: Self::KIND,
+                found: header.kind,
+            });
+        }
+        let mut reader = body;
+        let artifact = Self::deserialize_compressed(&mut reader)?;
+        if !reader.is_empty() {
+            return Err(EnvelopeError::TrailingBytes);
+        }
+        if artifact.attribute_count() != header.attribute_count {
+            return Err(EnvelopeError::AttributeCountMismatch {
+                header: header.attribute_count,
+                body: artifact.attribute_count(),
+            });
+        }
+        Ok(artifact)
+    }
+}
+
+/// Implements `Envelope` for artifacts generic over a pairing `E`
+#[macro_export]
+macro_rules! impl_envelope {
+    ($ty:ident, $scheme:ident, $kind:ident) => {
+        $crate::impl_envelope!($ty, $scheme, $kind, |_a| 0);
+    };
+    ($ty:ident, $scheme:ident, $kind:ident, |$a:ident| $count:expr) => {
+        impl<E> $crate::envelope::Envelope for $ty<E>
+        where
+            E: ark_ec::pairing::Pairing + $crate::envelope::CurveIdentifier,
+        {
+            const SCHEME: $crate::envelope::SchemeId = $crate::envelope::SchemeId::$scheme;
+            const KIND: $crate::envelope::ArtifactKind = $crate::envelope::ArtifactKind::$kind;
+
+            fn curve() -> $crate::envelope::CurveId {
+                E::CURVE_ID
+            }
+
+            fn attribute_count(&self) -> u32 {
+                let $a = self;
+                ($count) as u32
+            }
+        }
+    };
+}
+
+/// Compares `bytes` with the hex golden file at `path`, or rewrites the file when
+/// `UPDATE_GOLDEN` is set. Used by the golden-file tests of the credential crates.
+#[doc(hidden)]
+pub fn assert_golden(path: &str, bytes: &[u8]) {
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        if let Some(dir) = std::path::Path::new(path).parent() {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+        std::fs::write(path, hex + "\n").unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(path)
+        .unwrap_or_else(|_| panic!("missing golden file {}, run with UPDATE_GOLDEN=1", path));
+    assert_eq!(expected.trim(), hex, "golden file {} changed", path);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use ark_ec::pairing::Pairing;
+
+    #[derive(CanonicalSerialize, CanonicalDeserialize, Debug, PartialEq)]
+    struct Messages<E: Pairing> {
+        messages: Vec<E::ScalarField>,
+    }
+
+    impl_envelope!(Messages, Ps, Credential, |m| m.messages.len());
+
+    fn test_envelope_roundtrip_and_header<E: Pairing + CurveIdentifier>() {
+        let artifact = Messages::<E> {
+            messages: vec![1u64.into(), 2u64.into(), 3u64.into()],
+        };
+        let bytes = artifact.to_envelope();
+        assert_eq!(&bytes[..4], b"ACRD");
+        let (header, body) = Header::parse(&bytes).unwrap();
+        assert_eq!(header.scheme, SchemeId::Ps);
+        assert_eq!(header.curve, E::CURVE_ID);
+        assert_eq!(header.kind, ArtifactKind::Credential);
+        assert_eq!(header.attribute_count, 3);
+        assert_eq!(body.len(), bytes.len() - HEADER_LEN);
+        assert_eq!(Messages::<E>::from_envelope(&bytes).unwrap(), artifact);
+
+        let mut wrong_kind = bytes.clone();
+        wrong_kind[7] = ArtifactKind::Presentation as u8;
+        assert!(matches!(
+            Messages::<E>::from_envelope(&wrong_kind),
+            Err(EnvelopeError::KindMismatch { .. })
+        ));
+        let mut wrong_version = bytes.clone();
+        wrong_version[4] = 2;
+        assert_eq!(
+            Messages::<E>::from_envelope(&wrong_version),
+            Err(EnvelopeError::UnsupportedVersion(2))
+        );
+        let mut wrong_count = bytes.clone();
+        wrong_count[8] = 4;
+        assert!(matches!(
+            Messages::<E>::from_envelope(&wrong_count),
+            Err(EnvelopeError::AttributeCountMismatch { .. })
+        ));
+        assert_eq!(
+            Messages::<E>::from_envelope(&bytes[..bytes.len() - 1]),
+            Err(EnvelopeError::Truncated)
+        );
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(
+            Messages::<E>::from_envelope(&trailing),
+            Err(EnvelopeError::TrailingBytes)
+        );
+        if E::CURVE_ID != CurveId::Bls12_381 {
+            assert!(matches!(
+                Messages::<Bls12_381>::from_envelope(&bytes),
+                Err(EnvelopeError::CurveMismatch { .. })
+            ));
+        }
+    }
+
+    crate::curve_tests!(test_envelope_roundtrip_and_header);
+}
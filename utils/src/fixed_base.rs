@@ -0,0 +1,151 @@
+use ark_ec::CurveGroup;
+use ark_ff::{BigInteger, PrimeField};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use rayon::prelude::*;
+
+/// Window width used by `FixedBaseTable::new`, 2^4 points per window keeps a G1 table near
+/// 100KB while a multiplication costs one addition per window
+pub const DEFAULT_WINDOW: usize = 4;
+
+/// Windowed table for one fixed base B. Row i holds j * 2^(w*i) * B for j in 0..2^w, so a
+/// scalar multiplication is one table lookup and addition per w-bit digit and no doublings.
+/// Issuers and provers build it once per generator and can serialize it to load at startup.
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct FixedBaseTable<G: CurveGroup> {
+    window: usize,
+    table: Vec<Vec<G::Affine>>,
+}
+
+impl<G: CurveGroup> FixedBaseTable<G> {
+    pub fn new(base: &G::Affine) -> Self {
+        Self::with_window(base, DEFAULT_WINDOW)
+    }
+
+    pub fn with_window(base: &G::Affine, window: usize) -> Self {
+        assert!(
+            (1..=16).contains(&window),
+            "window must be between 1 and 16 bits"
+        );
+        let rows = (G::ScalarField::MODULUS_BIT_SIZE as usize).div_ceil(window);
+        let mut row_base = G::from(*base);
+        let mut table = Vec::with_capacity(rows);
+        for _ in 0..rows {
+            let mut row = Vec::with_capacity(1 << window);
+            let mut acc = G::zero();
+            for _ in 0..(1 << window) {
+                row.push(acc);
+                acc += row_base;
+            }
+            table.push(G::normalize_batch(&row));
+            // acc is now 2^w times the row base
+            row_base = acc;
+        }
+        Self { window, table }
+    }
+
+    /// The base point the table was built for
+    pub fn base(&self) -> G::Affine {
+        self.table[0][1]
+    }
+
+    pub fn mul(&self, scalar: &G::ScalarField) -> G {
+        let bits = scalar.into_bigint().to_bits_le();
+        let mut result = G::zero();
+        for (row, digit_bits) in self.table.iter().zip(bits.chunks(self.window)) {
+            let digit = digit_bits
+                .iter()
+                .rev()
+                .fold(0usize, |acc, bit| (acc << 1) | *bit as usize);
+            if digit != 0 {
+                result += row[digit];
+            }
+        }
+        result
+    }
+}
+
+/// Fixed-base tables for a list of bases, replacing `msm_unchecked` when the bases never change
+/// (commitment keys, Schnorr commitments over issuer generators)
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct FixedBaseMsm<G: CurveGroup> {
+    tables: Vec<FixedBaseTable<G>>,
+}
+
+impl<G: CurveGroup> FixedBaseMsm<G> {
+    pub fn new(bases: &[G::Affine]) -> Self {
+        Self {
+            tables: bases.par_iter().map(FixedBaseTable::new).collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tables.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty()
+    }
+
+    pub fn bases(&self) -> Vec<G::Affine> {
+        self.tables.iter().map(FixedBaseTable::base).collect()
+    }
+
+    pub fn table(&self, index: usize) -> &FixedBaseTable<G> {
+        &self.tables[index]
+    }
+
+    /// Sum of scalars[i] * bases[i], like `msm_unchecked` extra bases or scalars are ignored
+    pub fn msm(&self, scalars: &[G::ScalarField]) -> G {
+        self.tables
+            .iter()
+            .zip(scalars)
+            .map(|(table, scalar)| table.mul(scalar))
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ec::pairing::Pairing;
+    use ark_ec::VariableBaseMSM;
+    use ark_std::{test_rng, UniformRand};
+
+    fn test_fixed_base_mul<E: Pairing>() {
+        let mut rng = test_rng();
+        let base = E::G1::rand(&mut rng).into_affine();
+        let table = FixedBaseTable::<E::G1>::new(&base);
+        assert_eq!(table.base(), base);
+        for _ in 0..10 {
+            let scalar = E::ScalarField::rand(&mut rng);
+            assert_eq!(table.mul(&scalar), base * scalar);
+        }
+        assert_eq!(table.mul(&E::ScalarField::from(0u64)), E::G1::default());
+        assert_eq!(table.mul(&-E::ScalarField::from(1u64)), -E::G1::from(base));
+
+        let base_g2 = E::G2::rand(&mut rng).into_affine();
+        let table_g2 = FixedBaseTable::<E::G2>::with_window(&base_g2, 7);
+        let scalar = E::ScalarField::rand(&mut rng);
+        assert_eq!(table_g2.mul(&scalar), base_g2 * scalar);
+    }
+
+    fn test_fixed_base_msm<E: Pairing>() {
+        let mut rng = test_rng();
+        let bases: Vec<E::G1Affine> = (0..5)
+            .map(|_| E::G1::rand(&mut rng).into_affine())
+            .collect();
+        let scalars: Vec<E::ScalarField> = (0..5).map(|_| E::ScalarField::rand(&mut rng)).collect();
+        let tables = FixedBaseMsm::<E::G1>::new(&bases);
+        assert_eq!(tables.bases(), bases);
+        assert_eq!(tables.msm(&scalars), E::G1::msm_unchecked(&bases, &scalars));
+
+        // tables survive a round trip through their serialized form
+        let mut bytes = Vec::new();
+        tables.serialize_compressed(&mut bytes).unwrap();
+        let loaded = FixedBaseMsm::<E::G1>::deserialize_compressed(&bytes[..]).unwrap();
+        assert_eq!(loaded, tables);
+        assert_eq!(loaded.msm(&scalars), tables.msm(&scalars));
+    }
+
+    crate::curve_tests!(test_fixed_base_mul, test_fixed_base_msm);
+}
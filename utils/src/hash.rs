@@ -1,8 +1,6 @@
-use ark_ec::{AffineRepr, CurveGroup};
-use ark_ff::{Field, PrimeField};
-use ark_serialize::CanonicalSerialize;
-use ark_std::Zero;
-use blake2::Blake2s256;
+use ark_ec::{pairing::Pairing, AffineRepr};
+use ark_ff::PrimeField;
+use blake2::{Blake2b512, Blake2s256};
 use digest::Digest;
 use std::marker::PhantomData;
 
@@ -17,9 +15,18 @@ impl<F: PrimeField, G: AffineRepr<ScalarField = F>> HashUtil<F, G> {
         F::from_le_bytes_mod_order(&hash)
     }
 
+    // Hash message to a point by try-and-increment, unlike g^H(m) its discrete log is unknown
     pub fn hash_to_curve(message: &[u8]) -> G {
-        let field_element = Self::hash_to_field(message);
-        G::generator().mul(field_element).into_affine()
+        (0u32..)
+            .find_map(|counter| {
+                let mut hasher = Blake2b512::new();
+                hasher.update(message);
+                hasher.update(counter.to_le_bytes());
+                G::from_random_bytes(&hasher.finalize())
+                    .map(|point| point.clear_cofactor())
+                    .filter(|point| !point.is_zero())
+            })
+            .unwrap()
     }
 
     pub fn hash_fields(elements: &[F]) -> F {
@@ -45,36 +52,35 @@ impl<F: PrimeField, G: AffineRepr<ScalarField = F>> HashUtil<F, G> {
     }
 }
 
-// Testing
-use ark_bls12_381::{Fr, G1Affine};
-pub type BLS12_381HashUtil = HashUtil<Fr, G1Affine>;
+/// `HashUtil` over the scalar field and G1 of a pairing
+pub type G1HashUtil<E> = HashUtil<<E as Pairing>::ScalarField, <E as Pairing>::G1Affine>;
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ark_std::rand::{RngCore, SeedableRng};
     use ark_std::test_rng;
     use ark_std::UniformRand;
+    use ark_std::Zero;
 
-    #[test]
-    fn test_hash_to_field() {
+    fn test_hash_to_field<E: Pairing>() {
         let message = b"Hello, world!";
-        let field_element = BLS12_381HashUtil::hash_to_field(message);
-        assert_ne!(field_element, Fr::zero());
+        let field_element = G1HashUtil::<E>::hash_to_field(message);
+        assert_ne!(field_element, E::ScalarField::zero());
     }
 
-    #[test]
-    fn test_hash_to_curve() {
+    fn test_hash_to_curve<E: Pairing>() {
         let message = b"Hello, world!";
-        let curve_point = BLS12_381HashUtil::hash_to_curve(message);
-        assert_ne!(curve_point, G1Affine::identity());
+        let curve_point = G1HashUtil::<E>::hash_to_curve(message);
+        assert_ne!(curve_point, E::G1Affine::zero());
     }
 
-    #[test]
-    fn test_hash_fields() {
+    fn test_hash_fields<E: Pairing>() {
         let mut rng = test_rng();
-        let elements: Vec<Fr> = (0..5).map(|_| Fr::rand(&mut rng)).collect();
-        let hashed = BLS12_381HashUtil::hash_fields(&elements);
-        assert_ne!(hashed, Fr::zero());
+        let elements: Vec<E::ScalarField> =
+            (0..5).map(|_| E::ScalarField::rand(&mut rng)).collect();
+        let hashed = G1HashUtil::<E>::hash_fields(&elements);
+        assert_ne!(hashed, E::ScalarField::zero());
     }
+
+    crate::curve_tests!(test_hash_to_field, test_hash_to_curve, test_hash_fields);
 }
@@ -0,0 +1,225 @@
+// Issuer key rotation. Every public key is named by a hash of its compressed encoding, issuance
+// responses and presentations carry that name, and a verifier keeps the keys it accepts in a
+// ring where each key has a validity window. During a rotation the old key stays in the ring
+// with a closing time, so credentials issued under it keep verifying until then.
+use crate::encoding::{from_hex, to_hex};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::fmt;
+use blake2::Blake2s256;
+use digest::Digest;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+
+const KEY_ID_DOMAIN: &[u8] = b"anoncred-key-id";
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum KeyRingError {
+    #[error("No key with id {0} in the key ring")]
+    UnknownKey(KeyId),
+    #[error("Key {key_id} is not valid at {timestamp}")]
+    OutsideValidity { key_id: KeyId, timestamp: u64 },
+}
+
+/// Hash of an issuer public key, all zero for artifacts made before the key was known
+#[derive(
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    CanonicalSerialize,
+    CanonicalDeserialize,
+)]
+pub struct KeyId(pub [u8; 32]);
+
+impl KeyId {
+    /// Id of `key`, from its compressed encoding
+    pub fn of<K: CanonicalSerialize>(key: &K) -> Self {
+        let mut bytes = Vec::new();
+        key.serialize_compressed(&mut bytes).unwrap();
+        let mut hasher = Blake2s256::new();
+        hasher.update((KEY_ID_DOMAIN.len() as u64).to_le_bytes());
+        hasher.update(KEY_ID_DOMAIN);
+        hasher.update(&bytes);
+        Self(hasher.finalize().into())
+    }
+}
+
+impl fmt::Display for KeyId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&to_hex(&self.0))
+    }
+}
+
+impl fmt::Debug for KeyId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "KeyId({})", self)
+    }
+}
+
+impl Serialize for KeyId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&to_hex(&self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        from_hex(&s)
+            .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+            .map(KeyId)
+            .ok_or_else(|| serde::de::Error::custom("invalid key id"))
+    }
+}
+
+/// Times, in the units of `PresentationContext::timestamp`, between which a key is accepted.
+/// `not_after` is `None` for the current key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ValidityWindow {
+    pub not_before: u64,
+    pub not_after: Option<u64>,
+}
+
+impl ValidityWindow {
+    /// Open ended window starting at `not_before`
+    pub fn starting(not_before: u64) -> Self {
+        Self {
+            not_before,
+            not_after: None,
+        }
+    }
+
+    pub fn contains(&self, timestamp: u64) -> bool {
+        timestamp >= self.not_before && self.not_after.is_none_or(|end| timestamp <= end)
+    }
+}
+
+/// Issuer keys a verifier accepts, looked up by the id a presentation names. `K` is whatever
+/// the scheme verifies with, usually a prepared key.
+#[derive(Clone)]
+pub struct KeyRing<K> {
+    entries: Vec<(KeyId, ValidityWindow, K)>,
+}
+
+impl<K> Default for KeyRing<K> {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl<K> KeyRing<K> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `key` under `key_id`, replacing any key already under that id
+    pub fn insert(&mut self, key_id: KeyId, key: K, window: ValidityWindow) {
+        self.entries.retain(|(id, _, _)| *id != key_id);
+        self.entries.push((key_id, window, key));
+    }
+
+    /// Closes the window of a key being rotated out, it is refused after `not_after`
+    pub fn retire(&mut self, key_id: &KeyId, not_after: u64) -> Result<(), KeyRingError> {
+        let (_, window, _) = self
+            .entries
+            .iter_mut()
+            .find(|(id, _, _)| id == key_id)
+            .ok_or(KeyRingError::UnknownKey(*key_id))?;
+        window.not_after = Some(not_after);
+        Ok(())
+    }
+
+    /// Drops keys whose window closed before `timestamp`
+    pub fn prune(&mut self, timestamp: u64) {
+        self.entries
+            .retain(|(_, window, _)| window.not_after.is_none_or(|end| end >= timestamp));
+    }
+
+    /// Key named `key_id`, if it is valid at `timestamp`
+    pub fn get(&self, key_id: &KeyId, timestamp: u64) -> Result<&K, KeyRingError> {
+        let (_, window, key) = self
+            .entries
+            .iter()
+            .find(|(id, _, _)| id == key_id)
+            .ok_or(KeyRingError::UnknownKey(*key_id))?;
+        if !window.contains(timestamp) {
+            return Err(KeyRingError::OutsideValidity {
+                key_id: *key_id,
+                timestamp,
+            });
+        }
+        Ok(key)
+    }
+
+    /// Keys valid at `timestamp`, e.g. to build an issuer-hiding policy
+    pub fn valid_at(&self, timestamp: u64) -> impl Iterator<Item = (&KeyId, &K)> {
+        self.entries
+            .iter()
+            .filter(move |(_, window, _)| window.contains(timestamp))
+            .map(|(id, _, key)| (id, key))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_id() {
+        let a = KeyId::of(&vec![1u8, 2, 3]);
+        assert_eq!(a, KeyId::of(&vec![1u8, 2, 3]));
+        assert_ne!(a, KeyId::of(&vec![1u8, 2, 4]));
+        assert_ne!(a, KeyId::default());
+
+        let json = serde_json::to_string(&a).unwrap();
+        assert_eq!(json, format!("\"{}\"", a));
+        assert_eq!(serde_json::from_str::<KeyId>(&json).unwrap(), a);
+        assert!(serde_json::from_str::<KeyId>("\"00ff\"").is_err());
+    }
+
+    #[test]
+    fn test_key_ring_rotation() {
+        let old = KeyId::of(&1u64);
+        let new = KeyId::of(&2u64);
+        let mut ring = KeyRing::new();
+        ring.insert(old, "old", ValidityWindow::starting(100));
+        assert_eq!(
+            ring.get(&old, 50),
+            Err(KeyRingError::OutsideValidity {
+                key_id: old,
+                timestamp: 50
+            })
+        );
+        assert_eq!(ring.get(&new, 150), Err(KeyRingError::UnknownKey(new)));
+
+        // rotation: the new key starts at 200, the old one is accepted until 300
+        ring.insert(new, "new", ValidityWindow::starting(200));
+        ring.retire(&old, 300).unwrap();
+        assert_eq!(ring.get(&old, 250), Ok(&"old"));
+        assert_eq!(ring.get(&new, 250), Ok(&"new"));
+        assert_eq!(ring.valid_at(250).count(), 2);
+        assert!(ring.get(&old, 301).is_err());
+        assert_eq!(
+            ring.valid_at(301).map(|(id, _)| *id).collect::<Vec<_>>(),
+            vec![new]
+        );
+
+        ring.prune(301);
+        assert_eq!(ring.len(), 1);
+        assert_eq!(ring.get(&old, 250), Err(KeyRingError::UnknownKey(old)));
+    }
+}
@@ -1,2 +1,12 @@
+pub mod context;
+pub mod curves;
+pub mod disclosure;
+pub mod encoding;
+pub mod envelope;
+pub mod fixed_base;
+pub mod hash;
 pub mod helpers;
+pub mod key_ring;
 pub mod pairing;
+pub mod schema;
+pub mod update;
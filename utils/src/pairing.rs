@@ -103,15 +103,30 @@ where
         rng: &Mutex<R>,
         it: &[(&'a E::G1Affine, &'a E::G2Affine)],
         out: &'a <E as Pairing>::TargetField,
+    ) -> PairingCheck<E> {
+        let prepared: Vec<E::G2Prepared> = it
+            .par_iter()
+            .map(|(_, b)| E::G2Prepared::from(**b))
+            .collect();
+        let pairs: Vec<(&E::G1Affine, &E::G2Prepared)> =
+            it.iter().map(|(a, _)| *a).zip(prepared.iter()).collect();
+        Self::rand_prepared(rng, &pairs, out)
+    }
+
+    /// Same as `rand` for G2 elements that are already prepared, e.g. the fixed elements of a
+    /// verification key whose line coefficients are computed once and reused across checks.
+    pub fn rand_prepared<'a, R: Rng + Send>(
+        rng: &Mutex<R>,
+        it: &[(&'a E::G1Affine, &'a E::G2Prepared)],
+        out: &'a <E as Pairing>::TargetField,
     ) -> PairingCheck<E> {
         let coeff = rand_fr::<E, R>(&rng);
         let miller_out = it
             .into_par_iter()
             .map(|(a, b)| {
                 let na = a.mul(coeff).into_affine();
-                (E::G1Prepared::from(na), E::G2Prepared::from(**b))
+                E::miller_loop(E::G1Prepared::from(na), (*b).clone())
             })
-            .map(|(a, b)| E::miller_loop(a, b))
             .map(|res| res.0)
             .product();
         let mut outt = out.clone();
@@ -210,45 +225,101 @@ pub fn create_check<E: Pairing>(
     PairingCheck::<E>::rand(&Mutex::new(rng), pairs, &target_value)
 }
 
+/// Nonzero 128-bit exponent for small-exponent batch verification. A batch that contains an
+/// invalid equation passes with probability at most 2^-128.
+pub fn batch_randomizer<F: PrimeField, R: Rng>(rng: &mut R) -> F {
+    loop {
+        let r: u128 = rng.gen();
+        if r != 0 {
+            return F::from(r);
+        }
+    }
+}
+
+/// Finds the entries of `indices` that fail, given `check` which verifies a subset as one batch.
+/// A passing batch clears all its entries at once, a failing one is split in halves until the
+/// failures are single entries.
+pub fn bisect_failures<F: FnMut(&[usize]) -> bool>(indices: &[usize], check: &mut F) -> Vec<usize> {
+    if indices.is_empty() || check(indices) {
+        return Vec::new();
+    }
+    if indices.len() == 1 {
+        return indices.to_vec();
+    }
+    let (left, right) = indices.split_at(indices.len() / 2);
+    let mut failures = bisect_failures(left, check);
+    failures.extend(bisect_failures(right, check));
+    failures
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
-    use ark_bls12_381::{Bls12_381 as Bls12, G1Projective, G2Projective};
     use ark_std::test_rng;
     use ark_std::{rand::Rng, UniformRand};
 
-    fn gen_pairing_check<R: Rng + Send>(r: &mut R) -> PairingCheck<Bls12> {
-        let g1r = G1Projective::rand(r);
-        let g2r = G2Projective::rand(r);
+    fn gen_pairing_check<E: Pairing, R: Rng + Send>(r: &mut R) -> PairingCheck<E> {
+        let g1r = E::G1::rand(r);
+        let g2r = E::G2::rand(r);
 
         // expected output from g1r and g2r
-        let exp = Bls12::pairing(g1r, g2r);
+        let exp = E::pairing(g1r, g2r);
 
         // Wrap the random number generator in a Mutex for safe data parallelism
         let mr = Mutex::new(r);
 
         // the pairing lhs should equal the expected output
         let tuple =
-            PairingCheck::<Bls12>::rand(&mr, &[(&g1r.into_affine(), &g2r.into_affine())], &exp.0);
+            PairingCheck::<E>::rand(&mr, &[(&g1r.into_affine(), &g2r.into_affine())], &exp.0);
 
         assert!(tuple.verify());
         tuple
     }
-    #[test]
-    fn test_pairing_randomize() {
+
+    fn test_pairing_randomize<E: Pairing>() {
         let mut rng = test_rng();
 
         let tuples = (0..3)
-            .map(|_| gen_pairing_check(&mut rng))
+            .map(|_| gen_pairing_check::<E, _>(&mut rng))
             .collect::<Vec<_>>();
 
         //
-        let final_tuple = tuples
-            .iter()
-            .fold(PairingCheck::<Bls12>::new(), |mut acc, tu| {
-                acc.merge(tu);
-                acc
-            });
+        let final_tuple = tuples.iter().fold(PairingCheck::<E>::new(), |mut acc, tu| {
+            acc.merge(tu);
+            acc
+        });
         assert!(final_tuple.verify());
     }
+
+    fn test_pairing_rand_prepared<E: Pairing>() {
+        let mut rng = test_rng();
+        let a = E::G1Affine::rand(&mut rng);
+        let b = E::G2Affine::rand(&mut rng);
+        let exp = E::pairing(a, b);
+        let prepared = E::G2Prepared::from(b);
+
+        let mr = Mutex::new(&mut rng);
+        assert!(PairingCheck::<E>::rand_prepared(&mr, &[(&a, &prepared)], &exp.0).verify());
+        let wrong = exp + exp;
+        assert!(!PairingCheck::<E>::rand_prepared(&mr, &[(&a, &prepared)], &wrong.0).verify());
+    }
+
+    #[test]
+    fn test_bisect_failures() {
+        let indices: Vec<usize> = (0..37).collect();
+        let bad = [3, 4, 20, 36];
+        let mut calls = 0;
+        let failures = bisect_failures(&indices, &mut |subset: &[usize]| {
+            calls += 1;
+            subset.iter().all(|i| !bad.contains(i))
+        });
+        assert_eq!(failures, bad);
+        // far fewer checks than one per entry
+        assert!(calls < indices.len());
+
+        assert!(bisect_failures(&indices, &mut |_: &[usize]| true).is_empty());
+        assert!(bisect_failures(&[], &mut |_: &[usize]| false).is_empty());
+    }
+
+    crate::curve_tests!(test_pairing_randomize, test_pairing_rand_prepared);
 }
@@ -0,0 +1,347 @@
+use ark_ff::PrimeField;
+use blake2::Blake2s256;
+use digest::Digest;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum SchemaError {
+    #[error("Unknown attribute {0}")]
+    UnknownAttribute(String),
+    #[error("Duplicate attribute {0}")]
+    DuplicateAttribute(String),
+    #[error("Missing attribute {0}")]
+    MissingAttribute(String),
+    #[error("Attribute {name} expects a {expected:?} value")]
+    TypeMismatch {
+        name: String,
+        expected: AttributeType,
+    },
+    #[error("Attribute {0} is hashed and cannot be decoded")]
+    NotDecodable(String),
+    #[error("Value of attribute {0} is out of range")]
+    OutOfRange(String),
+    #[error("Parameters were not generated for schema {0}")]
+    ContextMismatch(String),
+}
+
+/// How an attribute value is mapped to a scalar
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AttributeType {
+    /// hashed to the field
+    String,
+    /// embedded directly, so range proofs apply
+    UInt,
+    /// days since 1970-01-01, embedded directly
+    Date,
+    /// 0 or 1
+    Bool,
+    /// hashed to the field
+    Bytes,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AttributeValue {
+    String(String),
+    UInt(u64),
+    Date(u32),
+    Bool(bool),
+    Bytes(Vec<u8>),
+}
+
+impl AttributeType {
+    fn tag(&self) -> u8 {
+        match self {
+            AttributeType::String => 0,
+            AttributeType::UInt => 1,
+            AttributeType::Date => 2,
+            AttributeType::Bool => 3,
+            AttributeType::Bytes => 4,
+        }
+    }
+}
+
+impl AttributeValue {
+    /// date value from a proleptic Gregorian calendar day, None before 1970-01-01
+    pub fn date(year: i32, month: u32, day: u32) -> Option<Self> {
+        // days_from_civil, H. Hinnant
+        let y = if month <= 2 { year - 1 } else { year } as i64;
+        let era = if y >= 0 { y } else { y - 399 } / 400;
+        let yoe = y - era * 400;
+        let mp = (month as i64 + 9) % 12;
+        let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        let days = era * 146097 + doe - 719468;
+        u32::try_from(days).ok().map(AttributeValue::Date)
+    }
+
+    pub fn ty(&self) -> AttributeType {
+        match self {
+            AttributeValue::String(_) => AttributeType::String,
+            AttributeValue::UInt(_) => AttributeType::UInt,
+            AttributeValue::Date(_) => AttributeType::Date,
+            AttributeValue::Bool(_) => AttributeType::Bool,
+            AttributeValue::Bytes(_) => AttributeType::Bytes,
+        }
+    }
+
+    pub fn encode<F: PrimeField>(&self) -> F {
+        match self {
+            AttributeValue::String(s) => hash_to_scalar(b"schema-string", s.as_bytes()),
+            AttributeValue::UInt(v) => F::from(*v),
+            AttributeValue::Date(days) => F::from(*days as u64),
+            AttributeValue::Bool(b) => F::from(*b as u64),
+            AttributeValue::Bytes(bytes) => hash_to_scalar(b"schema-bytes", bytes),
+        }
+    }
+}
+
+fn hash_to_scalar<F: PrimeField>(domain: &[u8], bytes: &[u8]) -> F {
+    let mut hasher = Blake2s256::new();
+    hasher.update((domain.len() as u64).to_le_bytes());
+    hasher.update(domain);
+    hasher.update(bytes);
+    F::from_le_bytes_mod_order(&hasher.finalize())
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AttributeDef {
+    pub name: String,
+    pub ty: AttributeType,
+}
+
+/// Named, typed attribute layout of a credential. Attribute i of the schema is message i.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Schema {
+    pub id: String,
+    pub attributes: Vec<AttributeDef>,
+}
+
+impl Schema {
+    pub fn new(id: &str, attributes: &[(&str, AttributeType)]) -> Result<Self, SchemaError> {
+        let mut defs: Vec<AttributeDef> = Vec::with_capacity(attributes.len());
+        for (name, ty) in attributes {
+            if defs.iter().any(|d| d.name == *name) {
+                return Err(SchemaError::DuplicateAttribute(name.to_string()));
+            }
+            defs.push(AttributeDef {
+                name: name.to_string(),
+                ty: *ty,
+            });
+        }
+        Ok(Self {
+            id: id.to_string(),
+            attributes: defs,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.attributes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty()
+    }
+
+    /// scalar for `PublicParams::context`, binds the schema id and the attribute layout
+    pub fn context<F: PrimeField>(&self) -> F {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&(self.id.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(self.id.as_bytes());
+        for def in &self.attributes {
+            bytes.extend_from_slice(&(def.name.len() as u64).to_le_bytes());
+            bytes.extend_from_slice(def.name.as_bytes());
+            bytes.push(def.ty.tag());
+        }
+        hash_to_scalar(b"schema", &bytes)
+    }
+
+    /// fails unless `context` was derived from this schema
+    pub fn check_context<F: PrimeField>(&self, context: &F) -> Result<(), SchemaError> {
+        if self.context::<F>() != *context {
+            return Err(SchemaError::ContextMismatch(self.id.clone()));
+        }
+        Ok(())
+    }
+
+    pub fn index_of(&self, name: &str) -> Result<usize, SchemaError> {
+        self.attributes
+            .iter()
+            .position(|d| d.name == name)
+            .ok_or_else(|| SchemaError::UnknownAttribute(name.to_string()))
+    }
+
+    pub fn indices(&self, names: &[&str]) -> Result<Vec<usize>, SchemaError> {
+        names.iter().map(|name| self.index_of(name)).collect()
+    }
+
+    fn encode_value<F: PrimeField>(
+        &self,
+        index: usize,
+        value: &AttributeValue,
+    ) -> Result<F, SchemaError> {
+        let def = &self.attributes[index];
+        if value.ty() != def.ty {
+            return Err(SchemaError::TypeMismatch {
+                name: def.name.clone(),
+                expected: def.ty,
+            });
+        }
+        Ok(value.encode())
+    }
+
+    /// encodes a value for every attribute into the message vector, in schema order
+    pub fn encode<F: PrimeField>(
+        &self,
+        values: &[(&str, AttributeValue)],
+    ) -> Result<Vec<F>, SchemaError> {
+        let named = self.encode_named::<F>(values)?;
+        let mut messages = vec![None; self.len()];
+        for (i, m) in named {
+            messages[i] = Some(m);
+        }
+        messages
+            .into_iter()
+            .zip(&self.attributes)
+            .map(|(m, def)| m.ok_or_else(|| SchemaError::MissingAttribute(def.name.clone())))
+            .collect()
+    }
+
+    /// encodes some attributes as (index, scalar) pairs, e.g. issuer-set attributes or updates
+    pub fn encode_named<F: PrimeField>(
+        &self,
+        values: &[(&str, AttributeValue)],
+    ) -> Result<Vec<(usize, F)>, SchemaError> {
+        let mut encoded: Vec<(usize, F)> = Vec::with_capacity(values.len());
+        for (name, value) in values {
+            let index = self.index_of(name)?;
+            if encoded.iter().any(|(i, _)| *i == index) {
+                return Err(SchemaError::DuplicateAttribute(name.to_string()));
+            }
+            encoded.push((index, self.encode_value(index, value)?));
+        }
+        Ok(encoded)
+    }
+
+    /// recovers a directly embedded value, hashed attributes can only be compared with `matches`
+    pub fn decode<F: PrimeField>(
+        &self,
+        name: &str,
+        message: &F,
+    ) -> Result<AttributeValue, SchemaError> {
+        let def = &self.attributes[self.index_of(name)?];
+        let out_of_range = || SchemaError::OutOfRange(name.to_string());
+        let bigint = message.into_bigint();
+        let limbs = bigint.as_ref();
+        if limbs[1..].iter().any(|l| *l != 0) {
+            return match def.ty {
+                AttributeType::String | AttributeType::Bytes => {
+                    Err(SchemaError::NotDecodable(name.to_string()))
+                }
+                _ => Err(out_of_range()),
+            };
+        }
+        let v = limbs[0];
+        match def.ty {
+            AttributeType::UInt => Ok(AttributeValue::UInt(v)),
+            AttributeType::Date => u32::try_from(v)
+                .map(AttributeValue::Date)
+                .map_err(|_| out_of_range()),
+            AttributeType::Bool if v <= 1 => Ok(AttributeValue::Bool(v == 1)),
+            AttributeType::Bool => Err(out_of_range()),
+            AttributeType::String | AttributeType::Bytes => {
+                Err(SchemaError::NotDecodable(name.to_string()))
+            }
+        }
+    }
+
+    /// whether `message` is the encoding of `value` for attribute `name`
+    pub fn matches<F: PrimeField>(&self, name: &str, value: &AttributeValue, message: &F) -> bool {
+        self.index_of(name)
+            .and_then(|i| self.encode_value::<F>(i, value))
+            .is_ok_and(|m| m == *message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Fr;
+
+    fn schema() -> Schema {
+        Schema::new(
+            "example.org/license/v1",
+            &[
+                ("name", AttributeType::String),
+                ("age", AttributeType::UInt),
+                ("expiry", AttributeType::Date),
+                ("organ_donor", AttributeType::Bool),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_schema_encode_decode() {
+        let schema = schema();
+        let expiry = AttributeValue::date(2030, 1, 1).unwrap();
+        assert_eq!(expiry, AttributeValue::Date(21915));
+        assert_eq!(AttributeValue::date(1969, 12, 31), None);
+
+        let values = vec![
+            ("organ_donor", AttributeValue::Bool(true)),
+            ("name", AttributeValue::String("Alice".into())),
+            ("age", AttributeValue::UInt(42)),
+            ("expiry", expiry.clone()),
+        ];
+        let messages: Vec<Fr> = schema.encode(&values).unwrap();
+        assert_eq!(messages[1], Fr::from(42u64));
+        assert_eq!(
+            schema.decode("age", &messages[1]),
+            Ok(AttributeValue::UInt(42))
+        );
+        assert_eq!(schema.decode("expiry", &messages[2]), Ok(expiry));
+        assert_eq!(
+            schema.decode("organ_donor", &messages[3]),
+            Ok(AttributeValue::Bool(true))
+        );
+        assert_eq!(
+            schema.decode("name", &messages[0]),
+            Err(SchemaError::NotDecodable("name".into()))
+        );
+        assert!(schema.matches(
+            "name",
+            &AttributeValue::String("Alice".into()),
+            &messages[0]
+        ));
+        assert!(!schema.matches("name", &AttributeValue::String("Bob".into()), &messages[0]));
+
+        assert!(matches!(
+            schema.encode::<Fr>(&values[..3]),
+            Err(SchemaError::MissingAttribute(_))
+        ));
+        assert!(matches!(
+            schema.encode_named::<Fr>(&[("age", AttributeValue::Bool(true))]),
+            Err(SchemaError::TypeMismatch { .. })
+        ));
+        assert_eq!(
+            schema.encode_named::<Fr>(&[("height", AttributeValue::UInt(1))]),
+            Err(SchemaError::UnknownAttribute("height".into()))
+        );
+    }
+
+    #[test]
+    fn test_schema_context() {
+        let schema = schema();
+        let context: Fr = schema.context();
+        assert!(schema.check_context(&context).is_ok());
+
+        let mut renamed = schema.clone();
+        renamed.attributes[1].name = "years".into();
+        assert!(renamed.check_context(&context).is_err());
+        assert!(Schema::new(
+            "dup",
+            &[("a", AttributeType::UInt), ("a", AttributeType::Bool)]
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,92 @@
+use ark_ff::PrimeField;
+
+/// Declared change to one hidden attribute at re-issuance. The issuer learns the kind of change and its
+/// public value, never the attribute itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AttributeUpdate<F: PrimeField> {
+    /// m' = m + delta, e.g. bump a counter
+    Add(F),
+    /// m' = value, e.g. a new expiry, the old value is not linked
+    Set(F),
+}
+
+impl<F: PrimeField> AttributeUpdate<F> {
+    pub fn apply(&self, m: &F) -> F {
+        match self {
+            AttributeUpdate::Add(delta) => *m + delta,
+            AttributeUpdate::Set(value) => *value,
+        }
+    }
+
+    /// the public part the issuer strips from the new commitment before linking it to the old attributes
+    pub fn public_value(&self) -> F {
+        match self {
+            AttributeUpdate::Add(delta) => *delta,
+            AttributeUpdate::Set(value) => *value,
+        }
+    }
+
+    /// whether the updated attribute is still proven equal to the old one
+    pub fn is_linked(&self) -> bool {
+        matches!(self, AttributeUpdate::Add(_))
+    }
+}
+
+/// applies (index, update) pairs to a copy of the attribute vector
+pub fn apply_updates<F: PrimeField>(
+    messages: &[F],
+    updates: &[(usize, AttributeUpdate<F>)],
+) -> Vec<F> {
+    let mut updated = messages.to_vec();
+    for (i, update) in updates {
+        updated[*i] = update.apply(&messages[*i]);
+    }
+    updated
+}
+
+/// indices of attributes whose new value is not linked to the old credential
+pub fn unlinked_indices<F: PrimeField>(updates: &[(usize, AttributeUpdate<F>)]) -> Vec<usize> {
+    updates
+        .iter()
+        .filter(|(_, update)| !update.is_linked())
+        .map(|(i, _)| *i)
+        .collect()
+}
+
+/// encoding of the declared updates for a Fiat-Shamir transcript
+pub fn updates_to_bytes<F: PrimeField>(updates: &[(usize, AttributeUpdate<F>)]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for (i, update) in updates {
+        bytes.extend_from_slice(&(*i as u64).to_le_bytes());
+        bytes.push(update.is_linked() as u8);
+        update
+            .public_value()
+            .serialize_compressed(&mut bytes)
+            .unwrap();
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Fr;
+
+    #[test]
+    fn test_apply_updates() {
+        let messages = vec![Fr::from(1u64), Fr::from(5u64), Fr::from(9u64)];
+        let updates = vec![
+            (1, AttributeUpdate::Add(Fr::from(1u64))),
+            (2, AttributeUpdate::Set(Fr::from(20u64))),
+        ];
+        assert_eq!(
+            apply_updates(&messages, &updates),
+            vec![Fr::from(1u64), Fr::from(6u64), Fr::from(20u64)]
+        );
+        assert_eq!(unlinked_indices(&updates), vec![2]);
+        assert_ne!(
+            updates_to_bytes(&updates),
+            updates_to_bytes(&[(1, AttributeUpdate::Set(Fr::from(1u64)))])
+        );
+    }
+}
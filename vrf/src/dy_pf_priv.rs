@@ -27,7 +27,9 @@ use ark_std::{
     rand::Rng,
     One, UniformRand, Zero,
 };
+use core::fmt;
 use core::marker::PhantomData;
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 /// Input to the Private Pairing-Free VRF
 #[derive(Clone, Debug)]
@@ -52,13 +54,19 @@ pub struct DYPFPrivPublicKey<G: AffineRepr> {
     pub cm_x: G,  // Commitment to input: g2^x * g^r_x
 }
 
-/// Secret key for the Private Pairing-Free VRF
-#[derive(Clone, Debug)]
-pub struct DYPFPrivSecretKey<F> {
+/// Secret key for the Private Pairing-Free VRF, wiped on drop and left out of `Debug` output
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct DYPFPrivSecretKey<F: Zeroize> {
     pub sk: F,   // Secret key
     pub r_sk: F, // Randomness used in commitment
 }
 
+impl<F: Zeroize> fmt::Debug for DYPFPrivSecretKey<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DYPFPrivSecretKey").finish_non_exhaustive()
+    }
+}
+
 /// Output of the Private Pairing-Free VRF
 #[derive(Clone, Debug)]
 pub struct DYPFPrivVRFOutput<G: AffineRepr> {